@@ -22,6 +22,7 @@ use project::{
     search::SearchQuery, DiagnosticSummary, FormatTrigger, HoverBlockKind, Project, ProjectPath,
 };
 use rand::prelude::*;
+use rpc::proto::update_shared_register::Kind;
 use serde_json::json;
 use settings::SettingsStore;
 use std::{
@@ -5920,3 +5921,72 @@ async fn test_cmd_k_left(cx: &mut TestAppContext) {
         assert!(workspace.items(cx).collect::<Vec<_>>().len() == 2);
     });
 }
+
+#[gpui::test]
+async fn test_shared_register(
+    executor: BackgroundExecutor,
+    cx_a: &mut TestAppContext,
+    cx_b: &mut TestAppContext,
+) {
+    let mut server = TestServer::start(executor.clone()).await;
+    let client_a = server.create_client(cx_a, "user_a").await;
+    let client_b = server.create_client(cx_b, "user_b").await;
+    server
+        .create_room(&mut [(&client_a, cx_a), (&client_b, cx_b)])
+        .await;
+    let active_call_a = cx_a.read(ActiveCall::global);
+
+    client_a
+        .fs()
+        .insert_tree("/dir", json!({ "a.txt": "one" }))
+        .await;
+    let (project_a, _) = client_a.build_local_project("/dir", cx_a).await;
+    let project_id = active_call_a
+        .update(cx_a, |call, cx| call.share_project(project_a.clone(), cx))
+        .await
+        .unwrap();
+    let project_b = client_b.build_remote_project(project_id, cx_b).await;
+
+    let shared_registers = |project: &Model<Project>, cx: &mut TestAppContext| {
+        let registers = Rc::new(RefCell::new(Vec::new()));
+        cx.update(|cx| {
+            let registers = registers.clone();
+            cx.subscribe(project, move |_, event, _| {
+                if let project::Event::SharedRegisterUpdated { name, text, kind } = event {
+                    registers
+                        .borrow_mut()
+                        .push((name.clone(), text.clone(), *kind));
+                }
+            })
+            .detach();
+        });
+        registers
+    };
+    let registers_a = shared_registers(&project_a, cx_a);
+    let registers_b = shared_registers(&project_b, cx_b);
+
+    // the host's register reaches the guest
+    project_a
+        .update(cx_a, |project, _| {
+            project.share_register("s".to_string(), "one\n".to_string(), Kind::Linewise)
+        })
+        .unwrap();
+    executor.run_until_parked();
+    assert_eq!(
+        *registers_b.borrow(),
+        vec![("s".to_string(), "one\n".to_string(), Kind::Linewise)]
+    );
+
+    // and a guest's reaches the host, without coming back to the guest
+    project_b
+        .update(cx_b, |project, _| {
+            project.share_register("s".to_string(), "two".to_string(), Kind::Charwise)
+        })
+        .unwrap();
+    executor.run_until_parked();
+    assert_eq!(
+        *registers_a.borrow(),
+        vec![("s".to_string(), "two".to_string(), Kind::Charwise)]
+    );
+    assert_eq!(registers_b.borrow().len(), 1);
+}
@@ -7,7 +7,8 @@ use std::{
 use client::{parse_zed_link, telemetry::Telemetry};
 use collections::HashMap;
 use command_palette_hooks::{
-    CommandInterceptResult, CommandPaletteFilter, CommandPaletteInterceptor,
+    CommandInterceptResult, CommandPaletteActionObserver, CommandPaletteFilter,
+    CommandPaletteInterceptor, ToggleWithQuery,
 };
 use fuzzy::{StringMatch, StringMatchCandidate};
 use gpui::{
@@ -57,19 +58,27 @@ fn trim_consecutive_whitespaces(input: &str) -> String {
 
 impl CommandPalette {
     fn register(workspace: &mut Workspace, _: &mut ViewContext<Workspace>) {
-        workspace.register_action(|workspace, _: &Toggle, cx| {
-            let Some(previous_focus_handle) = cx.focused() else {
-                return;
-            };
-            let telemetry = workspace.client().telemetry().clone();
-            workspace.toggle_modal(cx, move |cx| {
-                CommandPalette::new(previous_focus_handle, telemetry, cx)
-            });
+        workspace.register_action(|workspace, _: &Toggle, cx| Self::toggle(workspace, "", cx));
+        workspace.register_action(|workspace, action: &ToggleWithQuery, cx| {
+            Self::toggle(workspace, &action.query, cx)
+        });
+    }
+
+    /// Opens the palette with the query already typed in, or closes it if it's open.
+    fn toggle(workspace: &mut Workspace, query: &str, cx: &mut ViewContext<Workspace>) {
+        let Some(previous_focus_handle) = cx.focused() else {
+            return;
+        };
+        let telemetry = workspace.client().telemetry().clone();
+        let query = query.to_string();
+        workspace.toggle_modal(cx, move |cx| {
+            CommandPalette::new(previous_focus_handle, &query, telemetry, cx)
         });
     }
 
     fn new(
         previous_focus_handle: FocusHandle,
+        query: &str,
         telemetry: Arc<Telemetry>,
         cx: &mut ViewContext<Self>,
     ) -> Self {
@@ -102,7 +111,13 @@ impl CommandPalette {
             previous_focus_handle,
         );
 
-        let picker = cx.new_view(|cx| Picker::uniform_list(delegate, cx));
+        let picker = cx.new_view(|cx| {
+            let picker = Picker::uniform_list(delegate, cx);
+            if !query.is_empty() {
+                picker.set_query(query, cx);
+            }
+            picker
+        });
         Self { picker }
     }
 }
@@ -117,7 +132,10 @@ impl FocusableView for CommandPalette {
 
 impl Render for CommandPalette {
     fn render(&mut self, _cx: &mut ViewContext<Self>) -> impl IntoElement {
-        v_flex().w(rems(34.)).child(self.picker.clone())
+        v_flex()
+            .key_context("CommandPalette")
+            .w(rems(34.))
+            .child(self.picker.clone())
     }
 }
 
@@ -377,6 +395,10 @@ impl PickerDelegate for CommandPaletteDelegate {
         let action = command.action;
         cx.focus(&self.previous_focus_handle);
         self.dismissed(cx);
+        if let Some(observer) = cx.try_global::<CommandPaletteActionObserver>() {
+            let observer = observer.0.clone();
+            observer(&*action, cx);
+        }
         cx.dispatch_action(action);
     }
 
@@ -1,7 +1,17 @@
-use std::any::TypeId;
+use std::{any::TypeId, rc::Rc};
 
 use collections::HashSet;
-use gpui::{Action, AppContext, Global};
+use gpui::{impl_actions, Action, AppContext, Global, WindowContext};
+use serde::Deserialize;
+
+/// Opens the command palette with the query already typed in, or closes it if it's
+/// open, for crates that can't depend on the command palette itself.
+#[derive(Clone, Debug, Default, PartialEq, Deserialize)]
+pub struct ToggleWithQuery {
+    pub query: String,
+}
+
+impl_actions!(command_palette, [ToggleWithQuery]);
 
 #[derive(Default)]
 pub struct CommandPaletteFilter {
@@ -17,6 +27,13 @@ pub struct CommandPaletteInterceptor(
 
 impl Global for CommandPaletteInterceptor {}
 
+/// Called with each action the command palette runs, just before it's dispatched.
+/// Keystroke observers don't see these actions, so this is how they're recorded (as
+/// in a macro).
+pub struct CommandPaletteActionObserver(pub Rc<dyn Fn(&dyn Action, &mut WindowContext)>);
+
+impl Global for CommandPaletteActionObserver {}
+
 pub struct CommandInterceptResult {
     pub action: Box<dyn Action>,
     pub string: String,
@@ -129,6 +129,7 @@ gpui::actions!(
         AcceptPartialCopilotSuggestion,
         AddSelectionAbove,
         AddSelectionBelow,
+        AutoIndent,
         Backspace,
         Cancel,
         ConfirmRename,
@@ -88,6 +88,7 @@ pub use multi_buffer::{
 };
 use ordered_float::OrderedFloat;
 use parking_lot::{Mutex, RwLock};
+use persistence::DB;
 use project::project_settings::{GitGutterSetting, ProjectSettings};
 use project::Item;
 use project::{FormatTrigger, Location, Project, ProjectPath, ProjectTransaction};
@@ -107,6 +108,7 @@ use std::{
     num::NonZeroU32,
     ops::{ControlFlow, Deref, DerefMut, Range, RangeInclusive},
     path::Path,
+    rc::Rc,
     sync::Arc,
     time::{Duration, Instant},
 };
@@ -123,7 +125,9 @@ use ui::{
 };
 use util::{maybe, post_inc, RangeExt, ResultExt, TryFutureExt};
 use workspace::Toast;
-use workspace::{searchable::SearchEvent, ItemNavHistory, SplitDirection, ViewId, Workspace};
+use workspace::{
+    searchable::SearchEvent, ItemId, ItemNavHistory, SplitDirection, ViewId, Workspace, WorkspaceId,
+};
 
 use crate::hover_links::find_url;
 
@@ -221,6 +225,7 @@ impl InlayId {
 enum DocumentHighlightRead {}
 enum DocumentHighlightWrite {}
 enum InputComposition {}
+enum SearchScope {}
 
 #[derive(Copy, Clone, PartialEq, Eq)]
 pub enum Direction {
@@ -378,6 +383,11 @@ pub struct Editor {
     soft_wrap_mode_override: Option<language_settings::SoftWrap>,
     project: Option<Model<Project>>,
     completion_provider: Option<Box<dyn CompletionProvider>>,
+    /// Replaces the completion provider until the completions menu is closed.
+    temporary_completion_provider: Option<Rc<dyn CompletionProvider>>,
+    show_completions_on_input_override: Option<bool>,
+    extend_comment_on_newline_override: Option<bool>,
+    discard_untitled_changes: bool,
     collaboration_hub: Option<Box<dyn CollaborationHub>>,
     blink_manager: Model<BlinkManager>,
     show_cursor_names: bool,
@@ -389,6 +399,7 @@ pub struct Editor {
     show_wrap_guides: Option<bool>,
     placeholder_text: Option<Arc<str>>,
     highlighted_rows: Option<Range<u32>>,
+    gutter_signs: BTreeMap<TypeId, Vec<GutterSign>>,
     background_highlights: BTreeMap<TypeId, BackgroundHighlight>,
     inlay_background_highlights: TreeMap<Option<TypeId>, InlayBackgroundHighlight>,
     nav_history: Option<ItemNavHistory>,
@@ -408,6 +419,8 @@ pub struct Editor {
     workspace: Option<(WeakView<Workspace>, i64)>,
     keymap_context_layers: BTreeMap<TypeId, KeyContext>,
     input_enabled: bool,
+    ime_composition_enabled: bool,
+    search_scope: Option<Vec<Range<Anchor>>>,
     use_modal_editing: bool,
     read_only: bool,
     leader_peer_id: Option<PeerId>,
@@ -462,6 +475,24 @@ impl Default for GutterDimensions {
     }
 }
 
+/// A short label in the gutter beside a line, like the name of a vim mark.
+#[derive(Clone, Debug)]
+pub struct GutterSign {
+    pub anchor: Anchor,
+    pub text: SharedString,
+    pub color: fn(&ThemeColors) -> Hsla,
+    /// When signs set by different callers are on the same line, only the one
+    /// with the highest priority is shown.
+    pub priority: u8,
+}
+
+// fn pointers can't be compared reliably, so signs are equal whatever their color
+impl PartialEq for GutterSign {
+    fn eq(&self, other: &Self) -> bool {
+        self.anchor == other.anchor && self.text == other.text && self.priority == other.priority
+    }
+}
+
 #[derive(Debug)]
 pub struct RemoteSelection {
     pub replica_id: ReplicaId,
@@ -776,7 +807,7 @@ impl CompletionsMenu {
             return Task::ready(());
         }
 
-        let Some(provider) = editor.completion_provider.as_ref() else {
+        let Some(provider) = editor.active_completion_provider() else {
             return Task::ready(());
         };
 
@@ -1517,6 +1548,10 @@ impl Editor {
             active_diagnostics: None,
             soft_wrap_mode_override,
             completion_provider: project.clone().map(|project| Box::new(project) as _),
+            temporary_completion_provider: None,
+            show_completions_on_input_override: None,
+            extend_comment_on_newline_override: None,
+            discard_untitled_changes: false,
             collaboration_hub: project.clone().map(|project| Box::new(project) as _),
             project,
             blink_manager: blink_manager.clone(),
@@ -1527,6 +1562,7 @@ impl Editor {
             show_wrap_guides: None,
             placeholder_text: None,
             highlighted_rows: None,
+            gutter_signs: Default::default(),
             background_highlights: Default::default(),
             inlay_background_highlights: Default::default(),
             nav_history: None,
@@ -1547,6 +1583,8 @@ impl Editor {
             workspace: None,
             keymap_context_layers: Default::default(),
             input_enabled: true,
+            ime_composition_enabled: true,
+            search_scope: None,
             use_modal_editing: mode == EditorMode::Full,
             read_only: false,
             use_autoclose: true,
@@ -1698,6 +1736,10 @@ impl Editor {
         &self.buffer
     }
 
+    pub fn project(&self) -> Option<&Model<Project>> {
+        self.project.as_ref()
+    }
+
     pub fn workspace(&self) -> Option<View<Workspace>> {
         self.workspace.as_ref()?.0.upgrade()
     }
@@ -1763,6 +1805,12 @@ impl Editor {
         self.completion_provider = Some(hub);
     }
 
+    fn active_completion_provider(&self) -> Option<&dyn CompletionProvider> {
+        self.temporary_completion_provider
+            .as_deref()
+            .or(self.completion_provider.as_deref())
+    }
+
     pub fn placeholder_text(&self, _cx: &mut WindowContext) -> Option<&str> {
         self.placeholder_text.as_deref()
     }
@@ -1802,25 +1850,69 @@ impl Editor {
         }
     }
 
+    /// Adds to the editor's key context. Setting the layer it already has does
+    /// nothing, so it can be set whenever it might have changed.
     pub fn set_keymap_context_layer<Tag: 'static>(
         &mut self,
         context: KeyContext,
         cx: &mut ViewContext<Self>,
     ) {
-        self.keymap_context_layers
-            .insert(TypeId::of::<Tag>(), context);
+        let tag = TypeId::of::<Tag>();
+        if self.keymap_context_layers.get(&tag) == Some(&context) {
+            return;
+        }
+        self.keymap_context_layers.insert(tag, context);
         cx.notify();
     }
 
     pub fn remove_keymap_context_layer<Tag: 'static>(&mut self, cx: &mut ViewContext<Self>) {
-        self.keymap_context_layers.remove(&TypeId::of::<Tag>());
-        cx.notify();
+        if self
+            .keymap_context_layers
+            .remove(&TypeId::of::<Tag>())
+            .is_some()
+        {
+            cx.notify();
+        }
     }
 
     pub fn set_input_enabled(&mut self, input_enabled: bool) {
         self.input_enabled = input_enabled;
     }
 
+    /// Whether an input method can compose text (as for Chinese or Japanese). When
+    /// it can't, the text it's composing isn't shown, and only the text it commits is
+    /// typed.
+    pub fn set_ime_composition_enabled(&mut self, enabled: bool, cx: &mut ViewContext<Self>) {
+        if self.ime_composition_enabled && !enabled {
+            self.unmark_text(cx);
+        }
+        self.ime_composition_enabled = enabled;
+    }
+
+    /// Limits searching the editor (as the search bar does) to matches inside the
+    /// ranges, which are highlighted. `None` searches all of it again.
+    pub fn set_search_scope(
+        &mut self,
+        scope: Option<Vec<Range<Anchor>>>,
+        cx: &mut ViewContext<Self>,
+    ) {
+        match &scope {
+            Some(ranges) => self.highlight_background::<SearchScope>(
+                ranges.clone(),
+                |theme| theme.editor_highlighted_line_background,
+                cx,
+            ),
+            None => {
+                self.clear_background_highlights::<SearchScope>(cx);
+            }
+        }
+        self.search_scope = scope;
+    }
+
+    pub fn search_scope(&self) -> Option<&[Range<Anchor>]> {
+        self.search_scope.as_deref()
+    }
+
     pub fn set_autoindent(&mut self, autoindent: bool) {
         if autoindent {
             self.autoindent_mode = Some(AutoindentMode::EachLine);
@@ -1849,6 +1941,24 @@ impl Editor {
         self.show_copilot_suggestions = show_copilot_suggestions;
     }
 
+    /// Overrides the `show_completions_on_input` setting for this editor, or stops
+    /// overriding it with `None`.
+    pub fn set_show_completions_on_input(&mut self, show_completions_on_input: Option<bool>) {
+        self.show_completions_on_input_override = show_completions_on_input;
+    }
+
+    /// Overrides the `extend_comment_on_newline` setting for this editor, or stops
+    /// overriding it with `None`.
+    pub fn set_extend_comment_on_newline(&mut self, extend_comment_on_newline: Option<bool>) {
+        self.extend_comment_on_newline_override = extend_comment_on_newline;
+    }
+
+    /// Whether the changes to a buffer that has no file yet can be discarded, so that
+    /// it isn't counted as dirty and closes without asking to save it.
+    pub fn set_discard_untitled_changes(&mut self, discard_untitled_changes: bool) {
+        self.discard_untitled_changes = discard_untitled_changes;
+    }
+
     pub fn set_use_modal_editing(&mut self, to: bool) {
         self.use_modal_editing = to;
     }
@@ -1939,7 +2049,7 @@ impl Editor {
                     })
                     .detach();
 
-                    self.show_completions(&ShowCompletions, cx);
+                    self.request_completions(cx);
                 } else {
                     drop(context_menu);
                     self.hide_context_menu(cx);
@@ -2358,7 +2468,7 @@ impl Editor {
     }
 
     pub fn cancel(&mut self, _: &Cancel, cx: &mut ViewContext<Self>) {
-        if self.dismiss_menus_and_popups(cx) {
+        if self.dismiss_menus_and_popups(false, cx) {
             return;
         }
 
@@ -2371,7 +2481,11 @@ impl Editor {
         cx.propagate();
     }
 
-    pub fn dismiss_menus_and_popups(&mut self, cx: &mut ViewContext<Self>) -> bool {
+    pub fn dismiss_menus_and_popups(
+        &mut self,
+        keep_snippet: bool,
+        cx: &mut ViewContext<Self>,
+    ) -> bool {
         if self.take_rename(false, cx).is_some() {
             return true;
         }
@@ -2388,7 +2502,7 @@ impl Editor {
             return true;
         }
 
-        if self.snippet_stack.pop().is_some() {
+        if !keep_snippet && self.snippet_stack.pop().is_some() {
             return true;
         }
 
@@ -2722,7 +2836,9 @@ impl Editor {
                             // Comment extension on newline is allowed only for cursor selections
                             let comment_delimiter = language.line_comment_prefixes().filter(|_| {
                                 let is_comment_extension_enabled =
-                                    multi_buffer.settings_at(0, cx).extend_comment_on_newline;
+                                    this.extend_comment_on_newline_override.unwrap_or_else(|| {
+                                        multi_buffer.settings_at(0, cx).extend_comment_on_newline
+                                    });
                                 is_cursor && is_comment_extension_enabled
                             });
                             let get_comment_delimiter = |delimiters: &[Arc<str>]| {
@@ -2977,7 +3093,10 @@ impl Editor {
     }
 
     fn trigger_completion_on_input(&mut self, text: &str, cx: &mut ViewContext<Self>) {
-        if !EditorSettings::get_global(cx).show_completions_on_input {
+        if !self
+            .show_completions_on_input_override
+            .unwrap_or(EditorSettings::get_global(cx).show_completions_on_input)
+        {
             return;
         }
 
@@ -2987,7 +3106,14 @@ impl Editor {
             .read(cx)
             .is_completion_trigger(selection.head(), text, cx)
         {
-            self.show_completions(&ShowCompletions, cx);
+            // a temporary provider only lasts as long as its menu.
+            if !matches!(
+                self.context_menu.read().as_ref(),
+                Some(ContextMenu::Completions(_))
+            ) {
+                self.temporary_completion_provider = None;
+            }
+            self.request_completions(cx);
         } else {
             self.hide_context_menu(cx);
         }
@@ -2995,7 +3121,7 @@ impl Editor {
 
     /// If any empty selections is touching the start of its innermost containing autoclose
     /// region, expand it to select the brackets.
-    fn select_autoclose_pair(&mut self, cx: &mut ViewContext<Self>) {
+    pub fn select_autoclose_pair(&mut self, cx: &mut ViewContext<Self>) {
         let selections = self.selections.all::<usize>(cx);
         let buffer = self.buffer.read(cx).read(cx);
         let mut new_selections = Vec::new();
@@ -3322,11 +3448,28 @@ impl Editor {
     }
 
     fn show_completions(&mut self, _: &ShowCompletions, cx: &mut ViewContext<Self>) {
+        self.temporary_completion_provider = None;
+        self.request_completions(cx);
+    }
+
+    /// Shows completions from the given provider instead of the editor's own,
+    /// until the completions menu is closed.
+    pub fn show_completions_from(
+        &mut self,
+        provider: Rc<dyn CompletionProvider>,
+        cx: &mut ViewContext<Self>,
+    ) {
+        self.hide_context_menu(cx);
+        self.temporary_completion_provider = Some(provider);
+        self.request_completions(cx);
+    }
+
+    fn request_completions(&mut self, cx: &mut ViewContext<Self>) {
         if self.pending_rename.is_some() {
             return;
         }
 
-        let Some(provider) = self.completion_provider.as_ref() else {
+        let Some(provider) = self.active_completion_provider() else {
             return;
         };
 
@@ -3449,6 +3592,7 @@ impl Editor {
     ) -> Option<Task<Result<()>>> {
         use language::ToOffset as _;
 
+        let temporary_provider = self.temporary_completion_provider.clone();
         let completions_menu = if let ContextMenu::Completions(menu) = self.hide_context_menu(cx)? {
             menu
         } else {
@@ -3558,7 +3702,9 @@ impl Editor {
             this.refresh_copilot_suggestions(true, cx);
         });
 
-        let provider = self.completion_provider.as_ref()?;
+        let provider = temporary_provider
+            .as_deref()
+            .or(self.completion_provider.as_deref())?;
         let apply_edits = provider.apply_additional_edits_for_completion(
             buffer_handle,
             completion.clone(),
@@ -4273,6 +4419,7 @@ impl Editor {
     fn hide_context_menu(&mut self, cx: &mut ViewContext<Self>) -> Option<ContextMenu> {
         cx.notify();
         self.completion_tasks.clear();
+        self.temporary_completion_provider = None;
         let context_menu = self.context_menu.write().take();
         if context_menu.is_some() {
             self.update_visible_copilot_suggestion(cx);
@@ -4375,6 +4522,10 @@ impl Editor {
         Ok(())
     }
 
+    pub fn has_active_snippet(&self) -> bool {
+        !self.snippet_stack.is_empty()
+    }
+
     pub fn move_to_next_snippet_tabstop(&mut self, cx: &mut ViewContext<Self>) -> bool {
         self.move_to_snippet_tabstop(Bias::Right, cx)
     }
@@ -4742,6 +4893,43 @@ impl Editor {
         });
     }
 
+    /// Reindents the selected lines to the indentation their language suggests (using the
+    /// language of each line within injections), leaving blank lines as they are.
+    pub fn autoindent(&mut self, _: &AutoIndent, cx: &mut ViewContext<Self>) {
+        if self.read_only(cx) {
+            return;
+        }
+        let display_map = self.display_map.update(cx, |map, cx| map.snapshot(cx));
+        let selections = self.selections.all::<Point>(cx);
+        let snapshot = self.buffer.read(cx).snapshot(cx);
+        let mut rows = selections
+            .iter()
+            .flat_map(|selection| selection.spanned_rows(false, &display_map))
+            .collect::<Vec<_>>();
+        rows.sort_unstable();
+        rows.dedup();
+
+        let edits = snapshot
+            .suggested_indents(rows, cx)
+            .into_iter()
+            .filter(|(row, _)| !snapshot.is_line_blank(*row))
+            .filter_map(|(row, suggested_indent)| {
+                Buffer::edit_for_indent_size_adjustment(
+                    row,
+                    snapshot.indent_size_for_line(row),
+                    suggested_indent,
+                )
+            })
+            .collect::<Vec<_>>();
+
+        self.transact(cx, |this, cx| {
+            this.buffer
+                .update(cx, |buffer, cx| buffer.edit(edits, None, cx));
+            let selections = this.selections.all::<usize>(cx);
+            this.change_selections(Some(Autoscroll::fit()), cx, |s| s.select(selections));
+        });
+    }
+
     pub fn delete_line(&mut self, _: &DeleteLine, cx: &mut ViewContext<Self>) {
         let display_map = self.display_map.update(cx, |map, cx| map.snapshot(cx));
         let selections = self.selections.all::<Point>(cx);
@@ -5026,7 +5214,8 @@ impl Editor {
         self.manipulate_lines(cx, |lines| lines.shuffle(&mut thread_rng()))
     }
 
-    fn manipulate_lines<Fn>(&mut self, cx: &mut ViewContext<Self>, mut callback: Fn)
+    /// Replaces the lines of each selection with the ones the callback leaves.
+    pub fn manipulate_lines<Fn>(&mut self, cx: &mut ViewContext<Self>, mut callback: Fn)
     where
         Fn: FnMut(&mut Vec<&str>),
     {
@@ -5704,6 +5893,19 @@ impl Editor {
             .update(cx, |buffer, cx| buffer.finalize_last_transaction(cx));
     }
 
+    /// Makes undoing the transaction restore these selections, and redoing it the
+    /// current ones, as for a transaction that later ones were merged into.
+    pub fn set_transaction_selections(
+        &mut self,
+        transaction_id: TransactionId,
+        selections: Arc<[Selection<Anchor>]>,
+    ) {
+        let selections_after = Some(self.selections.disjoint_anchors());
+        self.selection_history
+            .selections_by_transaction
+            .insert(transaction_id, (selections, selections_after));
+    }
+
     pub fn move_left(&mut self, _: &MoveLeft, cx: &mut ViewContext<Self>) {
         self.change_selections(Some(Autoscroll::fit()), cx, |s| {
             let line_mode = s.line_mode;
@@ -6410,7 +6612,7 @@ impl Editor {
         self.nav_history.as_ref()
     }
 
-    fn push_to_nav_history(
+    pub fn push_to_nav_history(
         &mut self,
         cursor_anchor: Anchor,
         new_position: Option<Point>,
@@ -6421,13 +6623,21 @@ impl Editor {
             let cursor_position = cursor_anchor.to_point(&buffer);
             let scroll_state = self.scroll_manager.anchor();
             let scroll_top_row = scroll_state.top_row(&buffer);
+            // moving to another excerpt of a multibuffer is a jump however short it is
+            let is_short_move = new_position.map_or(false, |new_position| {
+                let row_delta = (new_position.row as i64 - cursor_position.row as i64).abs();
+                let same_excerpt = self.buffer.read(cx).is_singleton()
+                    || buffer
+                        .excerpt_containing(
+                            cursor_position.min(new_position)..cursor_position.max(new_position),
+                        )
+                        .is_some();
+                row_delta < MIN_NAVIGATION_HISTORY_ROW_DELTA && same_excerpt
+            });
             drop(buffer);
 
-            if let Some(new_position) = new_position {
-                let row_delta = (new_position.row as i64 - cursor_position.row as i64).abs();
-                if row_delta < MIN_NAVIGATION_HISTORY_ROW_DELTA {
-                    return;
-                }
+            if is_short_move {
+                return;
             }
 
             nav_history.push(
@@ -8276,7 +8486,7 @@ impl Editor {
         self.pending_rename.as_ref()
     }
 
-    fn format(&mut self, _: &Format, cx: &mut ViewContext<Self>) -> Option<Task<Result<()>>> {
+    pub fn format(&mut self, _: &Format, cx: &mut ViewContext<Self>) -> Option<Task<Result<()>>> {
         let project = match &self.project {
             Some(project) => project.clone(),
             None => return None,
@@ -8465,7 +8675,10 @@ impl Editor {
         self.end_transaction_at(Instant::now(), cx)
     }
 
-    fn start_transaction_at(&mut self, now: Instant, cx: &mut ViewContext<Self>) {
+    /// Starts a transaction that lasts until `end_transaction_at`, for edits that
+    /// can't be made in one `transact`. Undoing it restores the selections it
+    /// started with.
+    pub fn start_transaction_at(&mut self, now: Instant, cx: &mut ViewContext<Self>) {
         self.end_selection(cx);
         if let Some(tx_id) = self
             .buffer
@@ -8476,7 +8689,7 @@ impl Editor {
         }
     }
 
-    fn end_transaction_at(
+    pub fn end_transaction_at(
         &mut self,
         now: Instant,
         cx: &mut ViewContext<Self>,
@@ -8607,6 +8820,7 @@ impl Editor {
                 self.request_autoscroll(Autoscroll::fit(), cx);
             }
 
+            self.serialize_folds(cx);
             cx.notify();
         }
     }
@@ -8626,10 +8840,52 @@ impl Editor {
                 self.request_autoscroll(Autoscroll::fit(), cx);
             }
 
+            self.serialize_folds(cx);
             cx.notify();
         }
     }
 
+    /// Saves the folds of a singleton buffer, so that they are restored with the workspace.
+    pub(crate) fn serialize_folds(&mut self, cx: &mut ViewContext<Self>) {
+        let Some(workspace_id) = self.workspace.as_ref().map(|workspace| workspace.1) else {
+            return;
+        };
+        if self.buffer.read(cx).as_singleton().is_none() {
+            return;
+        }
+        let display_map = self.display_map.update(cx, |map, cx| map.snapshot(cx));
+        let buffer = &display_map.buffer_snapshot;
+        let folds = display_map
+            .folds_in_range(0..buffer.len())
+            .map(|fold| {
+                (
+                    fold.range.start.to_offset(buffer),
+                    fold.range.end.to_offset(buffer),
+                )
+            })
+            .collect::<Vec<_>>();
+        let item_id = cx.view().item_id().as_u64() as ItemId;
+        cx.background_executor()
+            .spawn(async move { DB.save_folds(item_id, workspace_id, folds).await.log_err() })
+            .detach();
+    }
+
+    pub fn read_folds_from_db(
+        &mut self,
+        item_id: ItemId,
+        workspace_id: WorkspaceId,
+        cx: &mut ViewContext<Self>,
+    ) {
+        if let Ok(folds) = DB.get_folds(item_id, workspace_id) {
+            let len = self.buffer.read(cx).len(cx);
+            let ranges = folds
+                .into_iter()
+                .filter(|(start, end)| start < end && *end <= len)
+                .map(|(start, end)| start..end);
+            self.fold_ranges(ranges, false, cx);
+        }
+    }
+
     pub fn set_gutter_hovered(&mut self, hovered: bool, cx: &mut ViewContext<Self>) {
         if hovered != self.gutter_hovered {
             self.gutter_hovered = hovered;
@@ -8933,6 +9189,64 @@ impl Editor {
         self.highlighted_rows.clone()
     }
 
+    /// Shows short labels (like the names of vim's marks) in the gutter, beside the
+    /// lines of their anchors. Each `T` replaces only its own signs.
+    pub fn set_gutter_signs<T: 'static>(
+        &mut self,
+        signs: Vec<GutterSign>,
+        cx: &mut ViewContext<Self>,
+    ) {
+        if signs.is_empty() {
+            self.clear_gutter_signs::<T>(cx);
+        } else if self.gutter_signs.get(&TypeId::of::<T>()) != Some(&signs) {
+            self.gutter_signs.insert(TypeId::of::<T>(), signs);
+            cx.notify();
+        }
+    }
+
+    pub fn clear_gutter_signs<T: 'static>(&mut self, cx: &mut ViewContext<Self>) {
+        if self.gutter_signs.remove(&TypeId::of::<T>()).is_some() {
+            cx.notify();
+        }
+    }
+
+    /// The sign to show on each display row in the range, with its color. A row's
+    /// signs from the same caller are joined together, and otherwise the one with
+    /// the highest priority wins.
+    pub fn gutter_signs_in_range(
+        &self,
+        rows: Range<u32>,
+        snapshot: &DisplaySnapshot,
+        theme: &ThemeColors,
+    ) -> BTreeMap<u32, (String, Hsla)> {
+        let mut signs_by_row = BTreeMap::<u32, (u8, String, Hsla)>::default();
+        for signs in self.gutter_signs.values() {
+            let mut rows_of_signs = BTreeMap::<u32, (u8, String, Hsla)>::default();
+            for sign in signs {
+                let row = sign.anchor.to_display_point(snapshot).row();
+                if rows.contains(&row) {
+                    let (priority, text, _) = rows_of_signs
+                        .entry(row)
+                        .or_insert_with(|| (sign.priority, String::new(), (sign.color)(theme)));
+                    *priority = (*priority).max(sign.priority);
+                    text.push_str(&sign.text);
+                }
+            }
+            for (row, sign) in rows_of_signs {
+                match signs_by_row.get(&row) {
+                    Some((priority, _, _)) if *priority >= sign.0 => {}
+                    _ => {
+                        signs_by_row.insert(row, sign);
+                    }
+                }
+            }
+        }
+        signs_by_row
+            .into_iter()
+            .map(|(row, (_, text, color))| (row, (text, color)))
+            .collect()
+    }
+
     pub fn highlight_background<T: 'static>(
         &mut self,
         ranges: Vec<Range<Anchor>>,
@@ -9372,6 +9686,13 @@ impl Editor {
             }
         }
 
+        // The pane's history is disabled while the excerpts are opened, so record where
+        // we were explicitly, letting `GoBack` return to this excerpt.
+        if !split {
+            let cursor_anchor = self.selections.newest_anchor().head();
+            self.push_to_nav_history(cursor_anchor, None, cx);
+        }
+
         // We defer the pane interaction because we ourselves are a workspace item
         // and activating a new item causes the pane to call a method on us reentrantly,
         // which panics if we're on the stack.
@@ -10207,6 +10528,9 @@ impl ViewInputHandler for Editor {
         new_selected_range_utf16: Option<Range<usize>>,
         cx: &mut ViewContext<Self>,
     ) {
+        if !self.ime_composition_enabled {
+            return;
+        }
         if !self.input_enabled {
             cx.emit(EditorEvent::InputIgnored { text: text.into() });
             return;
@@ -328,6 +328,56 @@ fn test_ime_composition(cx: &mut TestAppContext) {
     });
 }
 
+#[gpui::test]
+fn test_keymap_context_layers(cx: &mut TestAppContext) {
+    init_test(cx, |_| {});
+    struct Layer;
+
+    let editor = cx.add_window(|cx| {
+        let buffer = MultiBuffer::build_simple("abc", cx);
+        build_editor(buffer, cx)
+    });
+    let notifications = Rc::new(RefCell::new(0));
+    let _subscription = cx.update(|cx| {
+        let notifications = notifications.clone();
+        cx.observe(&editor.root_view(cx).unwrap(), move |_, _| {
+            *notifications.borrow_mut() += 1
+        })
+    });
+    let context = |mode: &str| {
+        let mut context = KeyContext::default();
+        context.set("mode", mode.to_string());
+        context
+    };
+
+    _ = editor.update(cx, |editor, cx| {
+        editor.set_keymap_context_layer::<Layer>(context("normal"), cx)
+    });
+    assert_eq!(*notifications.borrow(), 1);
+
+    // setting the layer it already has doesn't notify
+    _ = editor.update(cx, |editor, cx| {
+        editor.set_keymap_context_layer::<Layer>(context("normal"), cx)
+    });
+    assert_eq!(*notifications.borrow(), 1);
+
+    _ = editor.update(cx, |editor, cx| {
+        editor.set_keymap_context_layer::<Layer>(context("insert"), cx)
+    });
+    assert_eq!(*notifications.borrow(), 2);
+
+    // and neither does removing a layer it doesn't have
+    _ = editor.update(cx, |editor, cx| {
+        editor.remove_keymap_context_layer::<Layer>(cx);
+        editor.remove_keymap_context_layer::<Layer>(cx)
+    });
+    assert_eq!(*notifications.borrow(), 3);
+    _ = editor.update(cx, |editor, cx| {
+        editor.remove_keymap_context_layer::<Layer>(cx)
+    });
+    assert_eq!(*notifications.borrow(), 3);
+}
+
 #[gpui::test]
 fn test_selection_with_mouse(cx: &mut TestAppContext) {
     init_test(cx, |_| {});
@@ -662,6 +712,75 @@ async fn test_navigation_history(cx: &mut TestAppContext) {
     });
 }
 
+#[gpui::test]
+async fn test_navigation_history_between_excerpts(cx: &mut TestAppContext) {
+    init_test(cx, |_| {});
+
+    let fs = FakeFs::new(cx.executor());
+    let project = Project::test(fs, [], cx).await;
+    let workspace = cx.add_window(|cx| Workspace::test_new(project, cx));
+    let pane = workspace
+        .update(cx, |workspace, _| workspace.active_pane().clone())
+        .unwrap();
+    let buffer = cx.new_model(|cx| {
+        Buffer::new(
+            0,
+            BufferId::new(cx.entity_id().as_u64()).unwrap(),
+            sample_text(30, 4, 'a'),
+        )
+    });
+    let multibuffer = cx.new_model(|cx| {
+        let mut multibuffer = MultiBuffer::new(0, ReadWrite);
+        multibuffer.push_excerpts(
+            buffer.clone(),
+            [
+                ExcerptRange {
+                    context: Point::new(0, 0)..Point::new(2, 4),
+                    primary: None,
+                },
+                ExcerptRange {
+                    context: Point::new(20, 0)..Point::new(22, 4),
+                    primary: None,
+                },
+            ],
+            cx,
+        );
+        multibuffer
+    });
+
+    _ = workspace.update(cx, |_v, cx| {
+        cx.new_view(|cx| {
+            let mut editor = build_editor(multibuffer, cx);
+            let handle = cx.view();
+            editor.set_nav_history(Some(pane.read(cx).nav_history_for_item(&handle)));
+
+            fn pop_history(editor: &mut Editor, cx: &mut WindowContext) -> Option<NavigationEntry> {
+                editor.nav_history.as_mut().unwrap().pop_backward(cx)
+            }
+
+            // Moving within an excerpt is too small a distance to be remembered.
+            editor.change_selections(None, cx, |s| {
+                s.select_ranges([Point::new(1, 0)..Point::new(1, 0)])
+            });
+            assert!(pop_history(&mut editor, cx).is_none());
+
+            // Moving to the next excerpt is, though it's only a couple of rows away.
+            editor.change_selections(None, cx, |s| {
+                s.select_ranges([Point::new(3, 0)..Point::new(3, 0)])
+            });
+            let nav_entry = pop_history(&mut editor, cx).unwrap();
+            editor.navigate(nav_entry.data.unwrap(), cx);
+            assert_eq!(
+                editor.selections.ranges::<Point>(cx),
+                &[Point::new(1, 0)..Point::new(1, 0)]
+            );
+            assert!(pop_history(&mut editor, cx).is_none());
+
+            editor
+        })
+    });
+}
+
 #[gpui::test]
 fn test_cancel(cx: &mut TestAppContext) {
     init_test(cx, |_| {});
@@ -157,6 +157,7 @@ impl EditorElement {
         register_action(view, cx, Editor::tab_prev);
         register_action(view, cx, Editor::indent);
         register_action(view, cx, Editor::outdent);
+        register_action(view, cx, Editor::autoindent);
         register_action(view, cx, Editor::delete_line);
         register_action(view, cx, Editor::join_lines);
         register_action(view, cx, Editor::sort_lines_case_sensitive);
@@ -748,6 +749,32 @@ impl EditorElement {
             }
         }
 
+        // signs sit at the right of the left padding, next to the line numbers, but
+        // clear of the git diff markers, and the code actions indicator takes their
+        // place on its row.
+        let start_row = layout.visible_display_row_range.start;
+        let min_sign_x = if show_git_gutter {
+            0.3 * line_height
+        } else {
+            Pixels::ZERO
+        };
+        let code_actions_row = layout
+            .code_actions_indicator
+            .as_ref()
+            .map(|indicator| indicator.row);
+        for (row, sign) in &layout.gutter_signs {
+            if Some(*row) == code_actions_row {
+                continue;
+            }
+            let sign_origin = bounds.origin
+                + point(
+                    (layout.gutter_dimensions.left_padding - sign.width).max(min_sign_x),
+                    (row - start_row) as f32 * line_height - (scroll_top % line_height),
+                );
+
+            sign.paint(sign_origin, line_height, cx).log_err();
+        }
+
         cx.with_z_index(1, |cx| {
             for (ix, fold_indicator) in layout.fold_indicators.drain(..).enumerate() {
                 if let Some(fold_indicator) = fold_indicator {
@@ -1834,6 +1861,32 @@ impl EditorElement {
         relative_rows
     }
 
+    fn shape_gutter_signs(
+        &self,
+        signs: BTreeMap<u32, (String, Hsla)>,
+        cx: &ViewContext<Editor>,
+    ) -> Vec<(u32, ShapedLine)> {
+        let font_size = self.style.text.font_size.to_pixels(cx.rem_size());
+        signs
+            .into_iter()
+            .map(|(row, (sign, color))| {
+                let run = TextRun {
+                    len: sign.len(),
+                    font: self.style.text.font(),
+                    color,
+                    background_color: None,
+                    underline: None,
+                    strikethrough: None,
+                };
+                let shaped_line = cx
+                    .text_system()
+                    .shape_line(sign.into(), font_size, &[run])
+                    .unwrap();
+                (row, shaped_line)
+            })
+            .collect()
+    }
+
     fn shape_line_numbers(
         &self,
         rows: Range<u32>,
@@ -2227,6 +2280,14 @@ impl EditorElement {
             );
 
             let display_hunks = self.layout_git_gutters(start_row..end_row, &snapshot);
+            let gutter_signs = self.shape_gutter_signs(
+                editor.gutter_signs_in_range(
+                    start_row..end_row,
+                    &snapshot.display_snapshot,
+                    cx.theme().colors(),
+                ),
+                cx,
+            );
 
             let scrollbar_row_range = scroll_position.y..(scroll_position.y + height_in_lines);
 
@@ -2424,6 +2485,7 @@ impl EditorElement {
                 highlighted_ranges,
                 redacted_ranges,
                 line_numbers,
+                gutter_signs,
                 display_hunks,
                 blocks,
                 selections,
@@ -3200,6 +3262,7 @@ pub struct LayoutState {
     active_rows: BTreeMap<u32, bool>,
     highlighted_rows: Option<Range<u32>>,
     line_numbers: Vec<Option<ShapedLine>>,
+    gutter_signs: Vec<(u32, ShapedLine)>,
     display_hunks: Vec<DisplayDiffHunk>,
     blocks: Vec<BlockLayout>,
     highlighted_ranges: Vec<(Range<DisplayPoint>, Hsla)>,
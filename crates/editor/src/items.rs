@@ -682,6 +682,15 @@ impl Item for Editor {
     }
 
     fn is_dirty(&self, cx: &AppContext) -> bool {
+        if self.discard_untitled_changes
+            && self
+                .buffer()
+                .read(cx)
+                .as_singleton()
+                .is_some_and(|buffer| buffer.read(cx).file().is_none())
+        {
+            return false;
+        }
         self.buffer().read(cx).read(cx).is_dirty()
     }
 
@@ -869,6 +878,7 @@ impl Item for Editor {
 
         if let Some(buffer) = self.buffer().read(cx).as_singleton() {
             serialize(buffer.clone(), workspace_id, item_id, cx);
+            self.serialize_folds(cx);
 
             cx.subscribe(&buffer, |this, buffer, event, cx| {
                 if let Some((_, workspace_id)) = this.workspace.as_ref() {
@@ -960,6 +970,7 @@ impl Item for Editor {
                             let mut editor = Editor::for_buffer(buffer, Some(project), cx);
 
                             editor.read_scroll_position_from_db(item_id, workspace_id, cx);
+                            editor.read_folds_from_db(item_id, workspace_id, cx);
                             editor
                         })
                     })
@@ -1123,6 +1134,7 @@ impl SearchableItem for Editor {
         cx: &mut ViewContext<Self>,
     ) -> Task<Vec<Range<Anchor>>> {
         let buffer = self.buffer().read(cx).snapshot(cx);
+        let scope = self.search_scope.clone();
         cx.background_executor().spawn(async move {
             let mut ranges = Vec::new();
             if let Some((_, _, excerpt_buffer)) = buffer.as_singleton() {
@@ -1156,6 +1168,14 @@ impl SearchableItem for Editor {
                     );
                 }
             }
+            if let Some(scope) = scope {
+                ranges.retain(|range| {
+                    scope.iter().any(|scope| {
+                        scope.start.cmp(&range.start, &buffer).is_le()
+                            && range.end.cmp(&scope.end, &buffer).is_le()
+                    })
+                });
+            }
             ranges
         })
     }
@@ -1,7 +1,6 @@
 use std::path::PathBuf;
 
-use db::sqlez_macros::sql;
-use db::{define_connection, query};
+use db::{define_connection, query, sqlez_macros::sql};
 
 use workspace::{ItemId, WorkspaceDb, WorkspaceId};
 
@@ -15,6 +14,13 @@ define_connection!(
     //   scroll_vertical_offset: f32,
     //   scroll_horizontal_offset: f32,
     // )
+    //
+    // editor_folds(
+    //   item_id: usize,
+    //   workspace_id: usize,
+    //   start_offset: usize,
+    //   end_offset: usize,
+    // )
     pub static ref DB: EditorDb<WorkspaceDb> =
         &[sql! (
             CREATE TABLE editors(
@@ -31,6 +37,17 @@ define_connection!(
             ALTER TABLE editors ADD COLUMN scroll_top_row INTEGER NOT NULL DEFAULT 0;
             ALTER TABLE editors ADD COLUMN scroll_horizontal_offset REAL NOT NULL DEFAULT 0;
             ALTER TABLE editors ADD COLUMN scroll_vertical_offset REAL NOT NULL DEFAULT 0;
+        ),
+        sql! (
+            CREATE TABLE editor_folds(
+                item_id INTEGER NOT NULL,
+                workspace_id INTEGER NOT NULL,
+                start_offset INTEGER NOT NULL,
+                end_offset INTEGER NOT NULL,
+                FOREIGN KEY(workspace_id) REFERENCES workspaces(workspace_id)
+                ON DELETE CASCADE
+                ON UPDATE CASCADE
+            ) STRICT;
         )];
 );
 
@@ -80,4 +97,37 @@ impl EditorDb {
             WHERE item_id = ?1 AND workspace_id = ?2
         }
     }
+
+    // Returns the folded ranges, as buffer offsets
+    query! {
+        pub fn get_folds(item_id: ItemId, workspace_id: WorkspaceId) -> Result<Vec<(usize, usize)>> {
+            SELECT start_offset, end_offset
+            FROM editor_folds
+            WHERE item_id = ? AND workspace_id = ?
+            ORDER BY start_offset
+        }
+    }
+
+    pub async fn save_folds(
+        &self,
+        item_id: ItemId,
+        workspace_id: WorkspaceId,
+        folds: Vec<(usize, usize)>,
+    ) -> anyhow::Result<()> {
+        self.write(move |conn| {
+            conn.with_savepoint("update_folds", || {
+                conn.exec_bound(sql!(
+                    DELETE FROM editor_folds WHERE item_id = ? AND workspace_id = ?
+                ))?((item_id, workspace_id))?;
+                for (start, end) in folds {
+                    conn.exec_bound(sql!(
+                        INSERT INTO editor_folds(item_id, workspace_id, start_offset, end_offset)
+                        VALUES (?1, ?2, ?3, ?4)
+                    ))?((item_id, workspace_id, start, end))?;
+                }
+                Ok(())
+            })
+        })
+        .await
+    }
 }
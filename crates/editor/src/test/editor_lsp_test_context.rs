@@ -149,6 +149,10 @@ impl EditorLspTestContext {
                 ("<" @open ">" @close)
                 ("\"" @open "\"" @close)
                 (closure_parameters "|" @open "|" @close)"#})),
+            outline: Some(Cow::from(indoc! {r#"
+                (function_item
+                    "fn" @context
+                    name: (_) @name) @item"#})),
             ..Default::default()
         })
         .expect("Could not parse queries");
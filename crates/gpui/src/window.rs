@@ -280,6 +280,7 @@ pub struct Window {
     pub(crate) refreshing: bool,
     pub(crate) drawing: bool,
     activation_observers: SubscriberSet<(), AnyObserver>,
+    pending_input_observers: SubscriberSet<(), AnyObserver>,
     pub(crate) focus: Option<FocusId>,
     focus_enabled: bool,
     pending_input: Option<PendingInput>,
@@ -474,6 +475,7 @@ impl Window {
             refreshing: false,
             drawing: false,
             activation_observers: SubscriberSet::new(),
+            pending_input_observers: SubscriberSet::new(),
             focus: None,
             focus_enabled: true,
             pending_input: None,
@@ -1353,15 +1355,18 @@ impl<'a> WindowContext<'a> {
                         let Some(currently_pending) = cx.window.pending_input.take() else {
                             return;
                         };
+                        cx.pending_input_changed();
                         cx.replay_pending_input(currently_pending)
                     })
                     .log_err();
                 }));
                 self.window.pending_input = Some(currently_pending);
+                self.pending_input_changed();
 
                 self.propagate_event = false;
                 return;
             } else if let Some(currently_pending) = self.window.pending_input.take() {
+                self.pending_input_changed();
                 if bindings
                     .iter()
                     .all(|binding| !currently_pending.used_by_binding(binding))
@@ -1426,6 +1431,22 @@ impl<'a> WindowContext<'a> {
         }
     }
 
+    /// The keystrokes typed so far of a multi-stroke key binding that's in progress on
+    /// this window, if there is one.
+    pub fn pending_input_keystrokes(&self) -> Option<&[Keystroke]> {
+        self.window
+            .pending_input
+            .as_ref()
+            .map(|pending| pending.keystrokes.as_slice())
+    }
+
+    fn pending_input_changed(&mut self) {
+        self.window
+            .pending_input_observers
+            .clone()
+            .retain(&(), |callback| callback(self));
+    }
+
     /// Determine whether a potential multi-stroke key binding is in progress on this window.
     pub fn has_pending_keystrokes(&self) -> bool {
         self.window
@@ -2177,6 +2198,22 @@ impl<'a, V: 'static> ViewContext<'a, V> {
         subscription
     }
 
+    /// Register a callback to be invoked when a multi-stroke key binding starts or
+    /// stops being in progress on the window, or gets another keystroke (see
+    /// `pending_input_keystrokes`), including when it's abandoned after a timeout.
+    pub fn observe_pending_input(
+        &mut self,
+        mut callback: impl FnMut(&mut V, &mut ViewContext<V>) + 'static,
+    ) -> Subscription {
+        let view = self.view.downgrade();
+        let (subscription, activate) = self.window.pending_input_observers.insert(
+            (),
+            Box::new(move |cx| view.update(cx, |view, cx| callback(view, cx)).is_ok()),
+        );
+        activate();
+        subscription
+    }
+
     /// Registers a callback to be invoked when the window appearance changes.
     pub fn observe_window_appearance(
         &mut self,
@@ -47,7 +47,7 @@ pub use text::{
     Anchor, Bias, Buffer as TextBuffer, BufferId, BufferSnapshot as TextBufferSnapshot, Edit,
     OffsetRangeExt, OffsetUtf16, Patch, Point, PointUtf16, Rope, RopeFingerprint, Selection,
     SelectionGoal, Subscription, TextDimension, TextSummary, ToOffset, ToOffsetUtf16, ToPoint,
-    ToPointUtf16, Transaction, TransactionId, Unclipped,
+    ToPointUtf16, Transaction, TransactionId, Unclipped, UndoTreeNode,
 };
 use theme::SyntaxTheme;
 #[cfg(any(test, feature = "test-support"))]
@@ -921,6 +921,15 @@ impl Buffer {
         }
     }
 
+    /// Detaches the buffer from its [File], leaving it unnamed like a new buffer.
+    pub fn clear_file(&mut self, cx: &mut ModelContext<Self>) {
+        if self.file.take().is_some() {
+            self.file_update_count += 1;
+            cx.emit(Event::FileHandleChanged);
+            cx.notify();
+        }
+    }
+
     /// Returns the current diff base, see [Buffer::set_diff_base].
     pub fn diff_base(&self) -> Option<&str> {
         self.diff_base.as_deref()
@@ -2095,6 +2104,27 @@ impl Buffer {
         redone
     }
 
+    /// Restores the state the buffer was in just after a transaction (or before the
+    /// first one with `None`), which can be on another branch of the undo tree.
+    pub fn restore_undo_state(
+        &mut self,
+        transaction_id: Option<TransactionId>,
+        cx: &mut ModelContext<Self>,
+    ) -> bool {
+        let was_dirty = self.is_dirty();
+        let old_version = self.version.clone();
+
+        let operations = self.text.restore_undo_state(transaction_id);
+        let changed = !operations.is_empty();
+        for operation in operations {
+            self.send_operation(Operation::Buffer(operation), cx);
+        }
+        if changed {
+            self.did_edit(&old_version, was_dirty, cx)
+        }
+        changed
+    }
+
     /// Override current completion triggers with the user-provided completion triggers.
     pub fn set_completion_triggers(&mut self, triggers: Vec<String>, cx: &mut ModelContext<Self>) {
         self.completion_triggers = triggers.clone();
@@ -2205,11 +2235,12 @@ impl BufferSnapshot {
     }
 
     /// Retrieve the suggested indent size for all of the given rows. The unit of indentation
-    /// is passed in as `single_indent_size`.
+    /// for each row is given by `single_indent_size`, so that rows of injected languages can
+    /// be indented with their own.
     pub fn suggested_indents(
         &self,
         rows: impl Iterator<Item = u32>,
-        single_indent_size: IndentSize,
+        single_indent_size: impl Fn(u32) -> IndentSize,
     ) -> BTreeMap<u32, IndentSize> {
         let mut result = BTreeMap::new();
 
@@ -2225,7 +2256,7 @@ impl BufferSnapshot {
                         .get(&suggestion.basis_row)
                         .copied()
                         .unwrap_or_else(|| self.indent_size_for_line(suggestion.basis_row))
-                        .with_delta(suggestion.delta, single_indent_size)
+                        .with_delta(suggestion.delta, single_indent_size(row))
                 } else {
                     self.indent_size_for_line(row)
                 };
@@ -2337,6 +2368,11 @@ impl BufferSnapshot {
             Point::new(prev_non_blank_row.unwrap_or(row_range.start), 0)
                 ..Point::new(row_range.end, 0),
             |row, line| {
+                // Lines of injected languages are matched against their own patterns.
+                let indent_len = (line.len() - line.trim_start().len()) as u32;
+                let config = self
+                    .language_at(Point::new(row, indent_len))
+                    .map_or(config, |language| &language.config);
                 if config
                     .decrease_indent_pattern
                     .as_ref()
@@ -1624,6 +1624,63 @@ fn test_autoindent_with_injected_languages(cx: &mut AppContext) {
     });
 }
 
+#[gpui::test]
+fn test_suggested_indents_with_injected_languages(cx: &mut AppContext) {
+    init_settings(cx, |settings| {
+        settings.languages.extend([
+            (
+                "HTML".into(),
+                LanguageSettingsContent {
+                    tab_size: Some(2.try_into().unwrap()),
+                    ..Default::default()
+                },
+            ),
+            (
+                "JavaScript".into(),
+                LanguageSettingsContent {
+                    tab_size: Some(8.try_into().unwrap()),
+                    ..Default::default()
+                },
+            ),
+        ])
+    });
+
+    let html_language = Arc::new(html_lang());
+    let javascript_language = Arc::new(javascript_lang());
+    let language_registry = Arc::new(LanguageRegistry::test());
+    language_registry.add(html_language.clone());
+    language_registry.add(javascript_language.clone());
+
+    cx.new_model(|cx| {
+        let text = "
+            <div>
+            a
+            </div>
+            <script>
+                init({
+            b
+                })
+            </script>
+        "
+        .unindent();
+        let mut buffer = Buffer::new(0, BufferId::new(cx.entity_id().as_u64()).unwrap(), text);
+        buffer.set_language_registry(language_registry);
+        buffer.set_language(Some(html_language), cx);
+
+        // each row is indented with the unit of its own language
+        let snapshot = buffer.snapshot();
+        let indents = snapshot.suggested_indents([1, 5].into_iter(), |row| {
+            let indent = snapshot.indent_size_for_line(row);
+            snapshot.language_indent_size_at(Point::new(row, indent.len), cx)
+        });
+        assert_eq!(
+            indents,
+            BTreeMap::from_iter([(1, IndentSize::spaces(2)), (5, IndentSize::spaces(12))])
+        );
+        buffer
+    });
+}
+
 #[gpui::test]
 fn test_autoindent_query_with_outdent_captures(cx: &mut AppContext) {
     init_settings(cx, |settings| {
@@ -1255,6 +1255,10 @@ impl Language {
 }
 
 impl LanguageScope {
+    pub fn language_name(&self) -> Arc<str> {
+        self.language.config.name.clone()
+    }
+
     pub fn collapsed_placeholder(&self) -> &str {
         self.language.config.collapsed_placeholder.as_ref()
     }
@@ -1384,6 +1388,11 @@ impl Grammar {
         })
     }
 
+    /// Whether the grammar has an indents query, to suggest how lines are indented.
+    pub fn has_indents_query(&self) -> bool {
+        self.indents_config.is_some()
+    }
+
     pub fn highlight_map(&self) -> HighlightMap {
         self.highlight_map.lock().clone()
     }
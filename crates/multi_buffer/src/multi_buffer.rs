@@ -719,6 +719,16 @@ impl MultiBuffer {
         }
     }
 
+    /// The ids of the transactions that can be undone, oldest first. It's empty for a
+    /// singleton, whose transactions are in its buffer's history.
+    pub fn undo_stack_transaction_ids(&self) -> Vec<TransactionId> {
+        self.history
+            .undo_stack
+            .iter()
+            .map(|transaction| transaction.id)
+            .collect()
+    }
+
     pub fn finalize_last_transaction(&mut self, cx: &mut ModelContext<Self>) {
         self.history.finalize_last_transaction();
         for BufferState { buffer, .. } in self.buffers.borrow().values() {
@@ -2385,8 +2395,6 @@ impl MultiBufferSnapshot {
         let mut rows_for_excerpt = Vec::new();
         let mut cursor = self.excerpts.cursor::<Point>();
         let mut rows = rows.into_iter().peekable();
-        let mut prev_row = u32::MAX;
-        let mut prev_language_indent_size = IndentSize::default();
 
         while let Some(row) = rows.next() {
             cursor.seek(&Point::new(row, 0), Bias::Right, &());
@@ -2395,16 +2403,14 @@ impl MultiBufferSnapshot {
                 _ => continue,
             };
 
-            // Retrieve the language and indent size once for each disjoint region being indented.
-            let single_indent_size = if row.saturating_sub(1) == prev_row {
-                prev_language_indent_size
-            } else {
+            // Each row is indented with the unit of the language at its first non-blank, which
+            // differs from the buffer's language within an injection (like a script in HTML).
+            let single_indent_size = |row| {
+                let indent = excerpt.buffer.indent_size_for_line(row);
                 excerpt
                     .buffer
-                    .language_indent_size_at(Point::new(row, 0), cx)
+                    .language_indent_size_at(Point::new(row, indent.len), cx)
             };
-            prev_language_indent_size = single_indent_size;
-            prev_row = row;
 
             let start_buffer_row = excerpt.range.context.start.to_point(&excerpt.buffer).row;
             let start_multibuffer_row = cursor.start().row;
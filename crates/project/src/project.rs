@@ -84,7 +84,7 @@ use std::{
         atomic::{AtomicUsize, Ordering::SeqCst},
         Arc,
     },
-    time::{Duration, Instant},
+    time::{Duration, Instant, SystemTime},
 };
 use task::static_source::StaticSource;
 use terminals::Terminals;
@@ -296,6 +296,12 @@ pub enum Event {
     CollaboratorLeft(proto::PeerId),
     RefreshInlayHints,
     RevealInProjectPanel(ProjectEntryId),
+    /// A collaborator shared the text of a register (like vim's), to be put here.
+    SharedRegisterUpdated {
+        name: String,
+        text: String,
+        kind: proto::update_shared_register::Kind,
+    },
 }
 
 pub enum LanguageServerState {
@@ -544,6 +550,7 @@ impl Project {
         client.add_model_request_handler(Self::handle_open_buffer_by_path);
         client.add_model_request_handler(Self::handle_save_buffer);
         client.add_model_message_handler(Self::handle_update_diff_base);
+        client.add_model_message_handler(Self::handle_update_shared_register);
         client.add_model_request_handler(Self::handle_lsp_command::<lsp_ext_command::ExpandMacro>);
     }
 
@@ -1950,12 +1957,39 @@ impl Project {
         };
         let worktree = file.worktree.clone();
         let path = file.path.clone();
+        // a buffer given a new path by `rename_buffer` gets its entry once it's written
+        let has_changed_file = file.entry_id.is_none();
+        self.notify_will_save(&buffer, cx);
         worktree.update(cx, |worktree, cx| match worktree {
-            Worktree::Local(worktree) => worktree.save_buffer(buffer, path, false, cx),
+            Worktree::Local(worktree) => worktree.save_buffer(buffer, path, has_changed_file, cx),
             Worktree::Remote(worktree) => worktree.save_buffer(buffer, cx),
         })
     }
 
+    /// Tells the language servers that asked for `willSave` notifications that the
+    /// buffer is about to be written.
+    fn notify_will_save(&self, buffer: &Model<Buffer>, cx: &AppContext) {
+        let buffer = buffer.read(cx);
+        let Some(file) = File::from_dyn(buffer.file()).and_then(|file| file.as_local()) else {
+            return;
+        };
+        let text_document = lsp::TextDocumentIdentifier {
+            uri: lsp::Url::from_file_path(file.abs_path(cx)).unwrap(),
+        };
+        for (_, server) in self.language_servers_for_buffer(buffer, cx) {
+            if will_save(server) {
+                server
+                    .notify::<lsp::notification::WillSaveTextDocument>(
+                        lsp::WillSaveTextDocumentParams {
+                            text_document: text_document.clone(),
+                            reason: lsp::TextDocumentSaveReason::MANUAL,
+                        },
+                    )
+                    .log_err();
+            }
+        }
+    }
+
     pub fn save_buffer_as(
         &mut self,
         buffer: Model<Buffer>,
@@ -1990,6 +2024,70 @@ impl Project {
         })
     }
 
+    /// Gives a local buffer a new path without writing it, so that the next save
+    /// writes there and the file it was read from is left as it is. With `None`, the
+    /// buffer is left without a file, like a new one.
+    pub fn rename_buffer(
+        &mut self,
+        buffer: &Model<Buffer>,
+        new_path: Option<ProjectPath>,
+        cx: &mut ModelContext<Self>,
+    ) -> Result<()> {
+        if self.is_remote() {
+            bail!("cannot rename buffers in a remote project");
+        }
+        let old_file = File::from_dyn(buffer.read(cx).file()).cloned();
+        let new_file = match new_path {
+            Some(new_path) => {
+                let worktree = self
+                    .worktree_for_id(new_path.worktree_id, cx)
+                    .context("no such worktree")?;
+                Some(File {
+                    worktree,
+                    path: new_path.path,
+                    mtime: old_file
+                        .as_ref()
+                        .map_or(SystemTime::UNIX_EPOCH, |file| file.mtime),
+                    // nothing is there until the buffer is saved
+                    entry_id: None,
+                    is_local: true,
+                    is_deleted: true,
+                    is_private: false,
+                })
+            }
+            None => None,
+        };
+
+        let buffer_id = buffer.read(cx).remote_id();
+        if let Some(old_file) = &old_file {
+            self.unregister_buffer_from_language_servers(buffer, old_file, cx);
+            self.local_buffer_ids_by_path.remove(&ProjectPath {
+                worktree_id: old_file.worktree_id(cx),
+                path: old_file.path.clone(),
+            });
+            if let Some(entry_id) = old_file.entry_id {
+                self.local_buffer_ids_by_entry_id.remove(&entry_id);
+            }
+        }
+        if let Some(project_id) = self.remote_id() {
+            self.client
+                .send(proto::UpdateBufferFile {
+                    project_id,
+                    buffer_id: buffer_id.into(),
+                    file: new_file.as_ref().map(|file| file.to_proto()),
+                })
+                .log_err();
+        }
+
+        buffer.update(cx, |buffer, cx| match new_file {
+            Some(new_file) => buffer.file_updated(Arc::new(new_file), cx),
+            None => buffer.clear_file(cx),
+        });
+        self.detect_language_for_buffer(buffer, cx);
+        self.register_buffer_with_language_servers(buffer, cx);
+        Ok(())
+    }
+
     pub fn get_open_buffer(
         &mut self,
         path: &ProjectPath,
@@ -6661,6 +6759,25 @@ impl Project {
         None
     }
 
+    /// Sends the text of a register (like vim's) to the collaborators in the project,
+    /// which get it in a [`Event::SharedRegisterUpdated`].
+    pub fn share_register(
+        &self,
+        name: String,
+        text: String,
+        kind: proto::update_shared_register::Kind,
+    ) -> Result<()> {
+        if let Some(project_id) = self.remote_id() {
+            self.client.send(proto::UpdateSharedRegister {
+                project_id,
+                name,
+                text,
+                kind: kind as i32,
+            })?;
+        }
+        Ok(())
+    }
+
     pub fn is_shared(&self) -> bool {
         match &self.client_state {
             ProjectClientState::Shared { .. } => true,
@@ -7876,6 +7993,22 @@ impl Project {
         })?
     }
 
+    async fn handle_update_shared_register(
+        this: Model<Self>,
+        envelope: TypedEnvelope<proto::UpdateSharedRegister>,
+        _: Arc<Client>,
+        mut cx: AsyncAppContext,
+    ) -> Result<()> {
+        this.update(&mut cx, |_, cx| {
+            let payload = envelope.payload;
+            cx.emit(Event::SharedRegisterUpdated {
+                kind: payload.kind(),
+                name: payload.name,
+                text: payload.text,
+            });
+        })
+    }
+
     async fn handle_update_diff_base(
         this: Model<Self>,
         envelope: TypedEnvelope<proto::UpdateDiffBase>,
@@ -9554,6 +9687,18 @@ async fn wait_for_loading_buffer(
     }
 }
 
+fn will_save(server: &lsp::LanguageServer) -> bool {
+    server
+        .capabilities()
+        .text_document_sync
+        .as_ref()
+        .and_then(|sync| match sync {
+            lsp::TextDocumentSyncCapability::Kind(_) => None,
+            lsp::TextDocumentSyncCapability::Options(options) => options.will_save,
+        })
+        .unwrap_or(false)
+}
+
 fn include_text(server: &lsp::LanguageServer) -> bool {
     server
         .capabilities()
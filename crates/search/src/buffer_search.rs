@@ -551,6 +551,11 @@ impl BufferSearchBar {
         self.dismissed
     }
 
+    /// The query last searched for, if it was valid.
+    pub fn active_search(&self) -> Option<Arc<SearchQuery>> {
+        self.active_search.clone()
+    }
+
     pub fn dismiss(&mut self, _: &Dismiss, cx: &mut ViewContext<Self>) {
         self.dismissed = true;
         for searchable_item in self.searchable_items_with_matches.keys() {
@@ -763,6 +768,10 @@ impl BufferSearchBar {
         }
     }
 
+    pub fn active_match_index(&self) -> Option<usize> {
+        self.active_match_index
+    }
+
     pub fn select_match(&mut self, direction: Direction, count: usize, cx: &mut ViewContext<Self>) {
         if let Some(index) = self.active_match_index {
             if let Some(searchable_item) = self.active_searchable_item.as_ref() {
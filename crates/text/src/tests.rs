@@ -591,6 +591,53 @@ fn test_history() {
     assert_eq!(buffer.text(), "X12cde6");
 }
 
+#[test]
+fn test_undo_tree() {
+    let mut buffer = Buffer::new(0, BufferId::new(1).unwrap(), "abc".into());
+    buffer.set_group_interval(Duration::from_secs(0));
+
+    buffer.edit([(3..3, "1")]);
+    buffer.edit([(4..4, "2")]);
+    assert_eq!(buffer.text(), "abc12");
+    // editing after an undo starts a branch, keeping "2" in the tree
+    buffer.undo();
+    buffer.edit([(4..4, "3")]);
+    assert_eq!(buffer.text(), "abc13");
+
+    let tree = buffer.undo_tree();
+    assert_eq!(tree.len(), 3);
+    assert_eq!(tree[0].parent, None);
+    assert_eq!(tree[1].parent, Some(tree[0].transaction_id));
+    assert_eq!(tree[2].parent, Some(tree[0].transaction_id));
+
+    buffer.restore_undo_state(Some(tree[1].transaction_id));
+    assert_eq!(buffer.text(), "abc12");
+    buffer.restore_undo_state(None);
+    assert_eq!(buffer.text(), "abc");
+    // redo follows the branch that was restored last
+    buffer.restore_undo_state(Some(tree[2].transaction_id));
+    buffer.undo();
+    buffer.undo();
+    buffer.redo();
+    buffer.redo();
+    assert_eq!(buffer.text(), "abc13");
+    assert_eq!(buffer.undo_tree().len(), 3);
+
+    // the oldest branches are dropped once there are too many
+    for _ in 0..MAX_ABANDONED_TRANSACTIONS + 10 {
+        buffer.undo();
+        buffer.edit([(4..4, "4")]);
+    }
+    assert_eq!(buffer.text(), "abc14");
+    let tree = buffer.undo_tree();
+    assert_eq!(tree.len(), MAX_ABANDONED_TRANSACTIONS + 2);
+    assert!(tree
+        .iter()
+        .all(|node| node.parent.map_or(true, |parent| tree
+            .iter()
+            .any(|node| node.transaction_id == parent))));
+}
+
 #[test]
 fn test_finalize_last_transaction() {
     let now = Instant::now();
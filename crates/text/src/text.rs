@@ -50,6 +50,10 @@ lazy_static! {
 
 pub type TransactionId = clock::Lamport;
 
+/// How many transactions the branches of the undo tree keep, like vim's default
+/// `undolevels`.
+const MAX_ABANDONED_TRANSACTIONS: usize = 1000;
+
 pub struct Buffer {
     snapshot: BufferSnapshot,
     history: History,
@@ -125,12 +129,26 @@ impl HistoryEntry {
     }
 }
 
+/// A transaction in the undo history, including those on branches that were left
+/// when an edit was made after undoing.
+#[derive(Clone, Debug)]
+pub struct UndoTreeNode {
+    pub transaction_id: TransactionId,
+    /// The transaction it was made on top of, or `None` for the first.
+    pub parent: Option<TransactionId>,
+    pub edit_count: usize,
+    pub last_edit_at: Instant,
+}
+
 struct History {
     base_text: Rope,
     operations: TreeMap<clock::Lamport, Operation>,
     insertion_slices: HashMap<clock::Lamport, Vec<InsertionSlice>>,
     undo_stack: Vec<HistoryEntry>,
     redo_stack: Vec<HistoryEntry>,
+    /// Transactions that were on the redo stack when an edit cleared it, with the
+    /// transactions they were made on top of.
+    abandoned: Vec<(Option<TransactionId>, HistoryEntry)>,
     transaction_depth: usize,
     group_interval: Duration,
 }
@@ -149,6 +167,7 @@ impl History {
             insertion_slices: Default::default(),
             undo_stack: Vec::new(),
             redo_stack: Vec::new(),
+            abandoned: Vec::new(),
             transaction_depth: 0,
             // Don't group transactions in tests unless we opt in, because it's a footgun.
             #[cfg(any(test, feature = "test-support"))]
@@ -202,7 +221,8 @@ impl History {
                 self.undo_stack.pop();
                 None
             } else {
-                self.redo_stack.clear();
+                let parent_ix = self.undo_stack.len().checked_sub(2);
+                self.abandon_redo_stack(parent_ix.map(|ix| self.undo_stack[ix].transaction.id));
                 let entry = self.undo_stack.last_mut().unwrap();
                 entry.last_edit_at = now;
                 Some(entry)
@@ -248,6 +268,15 @@ impl History {
         let new_len = self.undo_stack.len() - n;
         let (entries_to_keep, entries_to_merge) = self.undo_stack.split_at_mut(new_len);
         if let Some(last_entry) = entries_to_keep.last_mut() {
+            // branches left from a merged transaction now start from the one it's merged into
+            for (parent, _) in &mut self.abandoned {
+                if entries_to_merge
+                    .iter()
+                    .any(|entry| Some(entry.transaction.id) == *parent)
+                {
+                    *parent = Some(last_entry.transaction.id);
+                }
+            }
             for entry in &*entries_to_merge {
                 for edit_id in &entry.transaction.edit_ids {
                     last_entry.transaction.edit_ids.push(*edit_id);
@@ -272,13 +301,123 @@ impl History {
 
     fn push_transaction(&mut self, transaction: Transaction, now: Instant) {
         assert_eq!(self.transaction_depth, 0);
+        self.abandon_redo_stack(self.undo_stack.last().map(|entry| entry.transaction.id));
         self.undo_stack.push(HistoryEntry {
             transaction,
             first_edit_at: now,
             last_edit_at: now,
             suppress_grouping: false,
         });
-        self.redo_stack.clear();
+    }
+
+    /// Keeps the redo stack as a branch of the undo tree, rather than losing it, when
+    /// an edit is made on top of `parent`. Past `MAX_ABANDONED_TRANSACTIONS`, the
+    /// oldest branches are dropped.
+    fn abandon_redo_stack(&mut self, mut parent: Option<TransactionId>) {
+        while let Some(entry) = self.redo_stack.pop() {
+            let id = entry.transaction.id;
+            self.abandoned.push((parent, entry));
+            parent = Some(id);
+        }
+        while self.abandoned.len() > MAX_ABANDONED_TRANSACTIONS {
+            self.drop_oldest_branch();
+        }
+    }
+
+    /// Drops the oldest transaction on a branch, with those made on top of it, which
+    /// can't be redone without it.
+    fn drop_oldest_branch(&mut self) {
+        let Some(oldest) = self
+            .abandoned
+            .iter()
+            .map(|(_, entry)| entry.transaction.id)
+            .min()
+        else {
+            return;
+        };
+        let mut dropped = vec![oldest];
+        while let Some(id) = dropped.pop() {
+            self.abandoned.retain(|(parent, entry)| {
+                if entry.transaction.id == id {
+                    false
+                } else if *parent == Some(id) {
+                    dropped.push(entry.transaction.id);
+                    false
+                } else {
+                    true
+                }
+            });
+        }
+    }
+
+    /// The transaction each transaction in the history was made on top of.
+    fn undo_tree(&self) -> impl Iterator<Item = (Option<TransactionId>, &HistoryEntry)> {
+        let undo = self.undo_stack.iter().enumerate().map(|(ix, entry)| {
+            let parent = ix
+                .checked_sub(1)
+                .map(|ix| self.undo_stack[ix].transaction.id);
+            (parent, entry)
+        });
+        let redo = self.redo_stack.iter().enumerate().map(|(ix, entry)| {
+            let parent = match self.redo_stack.get(ix + 1) {
+                Some(parent) => Some(parent.transaction.id),
+                None => self.undo_stack.last().map(|entry| entry.transaction.id),
+            };
+            (parent, entry)
+        });
+        let abandoned = self
+            .abandoned
+            .iter()
+            .map(|(parent, entry)| (*parent, entry));
+        undo.chain(redo).chain(abandoned)
+    }
+
+    /// Moves the transactions around so that the undo stack holds just the ones that
+    /// lead to `target`, returning those to be undone and then those to be redone.
+    fn switch_to_state(
+        &mut self,
+        target: Option<TransactionId>,
+    ) -> Option<(Vec<Transaction>, Vec<Transaction>)> {
+        assert_eq!(self.transaction_depth, 0);
+        let parents = self
+            .undo_tree()
+            .map(|(parent, entry)| (entry.transaction.id, parent))
+            .collect::<HashMap<_, _>>();
+        let mut path = Vec::new();
+        let mut next = target;
+        while let Some(id) = next {
+            path.push(id);
+            next = *parents.get(&id)?;
+        }
+        path.reverse();
+
+        let common = self
+            .undo_stack
+            .iter()
+            .zip(&path)
+            .take_while(|(entry, id)| entry.transaction.id == **id)
+            .count();
+        let mut undone = Vec::new();
+        while self.undo_stack.len() > common {
+            let entry = self.pop_undo()?;
+            undone.push(entry.transaction.clone());
+        }
+
+        // the rest of the path becomes the redo stack, and the rest of the tree a branch
+        self.abandon_redo_stack(self.undo_stack.last().map(|entry| entry.transaction.id));
+        for id in path[common..].iter().rev() {
+            let ix = self
+                .abandoned
+                .iter()
+                .position(|(_, entry)| entry.transaction.id == *id)?;
+            let (_, entry) = self.abandoned.remove(ix);
+            self.redo_stack.push(entry);
+        }
+        let mut redone = Vec::new();
+        while let Some(entry) = self.pop_redo() {
+            redone.push(entry.transaction.clone());
+        }
+        Some((undone, redone))
     }
 
     fn push_undo(&mut self, op_id: clock::Lamport) {
@@ -360,6 +499,12 @@ impl History {
 
     fn merge_transactions(&mut self, transaction: TransactionId, destination: TransactionId) {
         if let Some(transaction) = self.forget(transaction) {
+            // branches left from the merged transaction now start from the destination
+            for (parent, _) in &mut self.abandoned {
+                if *parent == Some(transaction.id) {
+                    *parent = Some(destination);
+                }
+            }
             if let Some(destination) = self.transaction_mut(destination) {
                 destination.edit_ids.extend(transaction.edit_ids);
             }
@@ -1162,6 +1307,23 @@ impl Buffer {
         self.history.redo_stack.last()
     }
 
+    /// Every transaction in the undo history, including those on branches left by
+    /// undoing and then editing, in the order they were made.
+    pub fn undo_tree(&self) -> Vec<UndoTreeNode> {
+        let mut nodes = self
+            .history
+            .undo_tree()
+            .map(|(parent, entry)| UndoTreeNode {
+                transaction_id: entry.transaction.id,
+                parent,
+                edit_count: entry.transaction.edit_ids.len(),
+                last_edit_at: entry.last_edit_at,
+            })
+            .collect::<Vec<_>>();
+        nodes.sort_by_key(|node| node.transaction_id);
+        nodes
+    }
+
     pub fn start_transaction(&mut self) -> Option<TransactionId> {
         self.start_transaction_at(Instant::now())
     }
@@ -1268,6 +1430,19 @@ impl Buffer {
             .collect()
     }
 
+    /// Puts the buffer back in the state it was in just after the given transaction
+    /// (or before the first one), which can be on another branch of the undo tree.
+    pub fn restore_undo_state(&mut self, transaction_id: Option<TransactionId>) -> Vec<Operation> {
+        let Some((undone, redone)) = self.history.switch_to_state(transaction_id) else {
+            return Vec::new();
+        };
+        undone
+            .into_iter()
+            .chain(redone)
+            .map(|transaction| self.undo_or_redo(transaction).unwrap())
+            .collect()
+    }
+
     fn undo_or_redo(&mut self, transaction: Transaction) -> Result<Operation> {
         let mut counts = HashMap::default();
         for edit_id in transaction.edit_ids {
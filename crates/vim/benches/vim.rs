@@ -0,0 +1,189 @@
+//! Benchmarks for vim mode's motions, searches and operators on large buffers,
+//! including lines long enough to be pathological. They type the keys into an
+//! editor, as the tests do, so they cover the rope and display map work each
+//! command does as well as vim mode's own. Run them with `cargo bench -p vim`.
+
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use criterion::Criterion;
+use editor::test::editor_lsp_test_context::EditorLspTestContext;
+use gpui::{BackgroundExecutor, TestAppContext, VisualContext};
+use search::BufferSearchBar;
+use settings::SettingsStore;
+use vim::VimModeSetting;
+
+const LINES: usize = 10_000;
+
+fn main() {
+    gpui::run_test(
+        1,
+        0,
+        &mut |dispatcher, _| {
+            let executor = BackgroundExecutor::new(Arc::new(dispatcher.clone()));
+            let mut cx = TestAppContext::new(dispatcher, None);
+            let mut cx = executor.block_test(vim_context(&mut cx));
+            let mut criterion = Criterion::default().configure_from_args();
+
+            word_motions(&mut criterion, &mut cx);
+            search(&mut criterion, &mut cx);
+            operators(&mut criterion, &mut cx);
+
+            criterion.final_summary();
+        },
+        None,
+    );
+}
+
+fn word_motions(criterion: &mut Criterion, cx: &mut EditorLspTestContext) {
+    let code = code();
+    bench(
+        criterion,
+        cx,
+        "1000w",
+        &format!("ˇ{code}"),
+        &["1", "0", "0", "0", "w"],
+    );
+    bench(
+        criterion,
+        cx,
+        "1000b",
+        &format!("{code}ˇ"),
+        &["1", "0", "0", "0", "b"],
+    );
+    bench(
+        criterion,
+        cx,
+        "1000e",
+        &format!("ˇ{code}"),
+        &["1", "0", "0", "0", "e"],
+    );
+
+    // one long line, on which every other character starts a word
+    let punctuation = "a.".repeat(100_000);
+    bench(
+        criterion,
+        cx,
+        "1000w in a long line",
+        &format!("ˇ{punctuation}"),
+        &["1", "0", "0", "0", "w"],
+    );
+
+    // one long word, which w has to go through to the end
+    let word = "a".repeat(1 << 20);
+    bench(
+        criterion,
+        cx,
+        "w over a long word",
+        &format!("ˇ{word}\nb"),
+        &["w"],
+    );
+    bench(
+        criterion,
+        cx,
+        "$ in a long line",
+        &format!("ˇ{word}"),
+        &["$"],
+    );
+}
+
+fn search(criterion: &mut Criterion, cx: &mut EditorLspTestContext) {
+    let code = code();
+    bench(
+        criterion,
+        cx,
+        "/ to the end",
+        &format!("ˇ{code}needle"),
+        &["/", "n", "e", "e", "d", "l", "e", "enter"],
+    );
+    bench(criterion, cx, "*", &format!("ˇ{code}"), &["*"]);
+    bench(criterion, cx, "* then n", &format!("ˇ{code}"), &["*", "n"]);
+}
+
+fn operators(criterion: &mut Criterion, cx: &mut EditorLspTestContext) {
+    let code = code();
+    bench(criterion, cx, "dG", &format!("ˇ{code}"), &["d", "shift-g"]);
+    bench(criterion, cx, "yG", &format!("ˇ{code}"), &["y", "shift-g"]);
+    bench(criterion, cx, ">G", &format!("ˇ{code}"), &[">", "shift-g"]);
+    bench(criterion, cx, "=G", &format!("ˇ{code}"), &["=", "shift-g"]);
+    bench(
+        criterion,
+        cx,
+        "g~G",
+        &format!("ˇ{code}"),
+        &["g", "~", "shift-g"],
+    );
+    bench(
+        criterion,
+        cx,
+        "1000dd then .",
+        &format!("ˇ{code}"),
+        &["1", "0", "0", "0", "d", "d", "."],
+    );
+}
+
+/// Times typing the keys into an editor holding the text, with the cursor where
+/// it's marked.
+fn bench(
+    criterion: &mut Criterion,
+    cx: &mut EditorLspTestContext,
+    name: &str,
+    text: &str,
+    keystrokes: &[&str],
+) {
+    criterion.bench_function(name, |b| {
+        b.iter_custom(|iterations| {
+            let mut elapsed = Duration::ZERO;
+            for _ in 0..iterations {
+                cx.set_state(text);
+                cx.simulate_keystroke("escape");
+                cx.run_until_parked();
+
+                let start = Instant::now();
+                for keystroke in keystrokes {
+                    cx.simulate_keystroke(keystroke);
+                }
+                cx.run_until_parked();
+                elapsed += start.elapsed();
+            }
+            elapsed
+        })
+    });
+}
+
+fn code() -> String {
+    (0..LINES)
+        .map(|i| format!("    let value_{i} = compute(first.field, second[{i}], \"text\");\n"))
+        .collect()
+}
+
+async fn vim_context(cx: &mut TestAppContext) -> EditorLspTestContext {
+    cx.update(|cx| {
+        search::init(cx);
+        let settings = SettingsStore::test(cx);
+        cx.set_global(settings);
+        release_channel::init("0.0.0", cx);
+        command_palette::init(cx);
+        vim::init(cx);
+    });
+
+    let mut cx = EditorLspTestContext::new_rust(Default::default(), cx).await;
+    cx.update(|cx| {
+        cx.update_global(|store: &mut SettingsStore, cx| {
+            store.update_user_settings::<VimModeSetting>(cx, |s| *s = Some(true));
+        });
+        settings::KeymapFile::load_asset("keymaps/default-macos.json", cx).unwrap();
+        settings::KeymapFile::load_asset("keymaps/vim.json", cx).unwrap();
+    });
+    cx.update_workspace(|workspace, cx| {
+        workspace.active_pane().update(cx, |pane, cx| {
+            pane.toolbar().update(cx, |toolbar, cx| {
+                let buffer_search_bar = cx.new_view(BufferSearchBar::new);
+                toolbar.add_item(buffer_search_bar, cx);
+            })
+        });
+    });
+    cx
+}
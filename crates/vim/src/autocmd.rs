@@ -0,0 +1,135 @@
+use anyhow::Result;
+use editor::actions::Format;
+use gpui::{Task, WindowContext};
+use settings::Settings;
+
+use crate::{AutocmdEvent, Vim, VimSettings};
+
+/// Runs the actions the `autocmds` setting gives for the event, like vim's
+/// `:autocmd`. They're dispatched as key bindings dispatch them, to whichever
+/// view is focused once the event is over. They're not run while a macro, `.` or
+/// `:g` is replayed.
+pub(crate) fn run_autocmds(vim: &mut Vim, event: AutocmdEvent, cx: &mut WindowContext) {
+    if let Some(format) = start_autocmds(vim, event, cx) {
+        format.detach_and_log_err(cx);
+    }
+}
+
+/// Runs the autocmds for the event like `run_autocmds`, but formatting the active
+/// editor (`editor::Format`) directly, so that a save can wait for its task.
+pub(crate) fn start_autocmds(
+    vim: &mut Vim,
+    event: AutocmdEvent,
+    cx: &mut WindowContext,
+) -> Option<Task<Result<()>>> {
+    if vim.is_replaying() {
+        return None;
+    }
+    let names = VimSettings::get_global(cx).autocmds.get(&event).cloned()?;
+    let mut format = None;
+    for name in names {
+        if name == "editor::Format" {
+            format = vim
+                .update_active_editor(cx, |_, editor, cx| editor.format(&Format, cx))
+                .flatten();
+            if format.is_some() {
+                continue;
+            }
+        }
+        match cx.build_action(&name, None) {
+            Ok(action) => cx.dispatch_action(action),
+            Err(_) => vim.show_message(format!("E492: Not an editor command: {name}")),
+        }
+    }
+    format
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::Path;
+
+    use collections::HashMap;
+    use editor::test::editor_lsp_test_context::EditorLspTestContext;
+    use language::language_settings::{AllLanguageSettings, FormatOnSave};
+    use settings::SettingsStore;
+
+    use crate::{state::Mode, test::VimTestContext, AutocmdEvent, VimSettings};
+
+    #[gpui::test]
+    async fn test_autocmds(cx: &mut gpui::TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+        cx.update_global(|store: &mut SettingsStore, cx| {
+            store.update_user_settings::<VimSettings>(cx, |settings| {
+                settings.autocmds = Some(HashMap::from_iter([
+                    (
+                        AutocmdEvent::InsertLeave,
+                        vec!["editor::MoveToBeginning".to_string()],
+                    ),
+                    (
+                        AutocmdEvent::InsertEnter,
+                        vec!["editor::MoveToEnd".to_string(), "no::Action".to_string()],
+                    ),
+                ]))
+            });
+        });
+
+        cx.set_state("one\ntwˇo", Mode::Normal);
+        cx.simulate_keystrokes(["i"]);
+        cx.assert_state("one\ntwoˇ", Mode::Insert);
+        assert_eq!(
+            cx.message().as_deref(),
+            Some("E492: Not an editor command: no::Action")
+        );
+        cx.simulate_keystrokes(["escape"]);
+        cx.assert_state("ˇone\ntwo", Mode::Normal);
+
+        // they're not run again as a macro is replayed
+        cx.simulate_keystrokes(["q", "a", "i", "escape", "q"]);
+        cx.assert_state("ˇone\ntwo", Mode::Normal);
+        cx.simulate_keystrokes(["j", "@", "a"]);
+        cx.run_until_parked();
+        cx.assert_state("one\nˇtwo", Mode::Normal);
+    }
+
+    #[gpui::test]
+    async fn test_format_before_write(cx: &mut gpui::TestAppContext) {
+        VimTestContext::init(cx);
+        let lsp = EditorLspTestContext::new_rust(
+            lsp::ServerCapabilities {
+                document_formatting_provider: Some(lsp::OneOf::Left(true)),
+                ..Default::default()
+            },
+            cx,
+        )
+        .await;
+        let mut cx = VimTestContext::new_with_lsp(lsp, true);
+        cx.update_global(|store: &mut SettingsStore, cx| {
+            store.update_user_settings::<AllLanguageSettings>(cx, |settings| {
+                settings.defaults.format_on_save = Some(FormatOnSave::Off);
+            });
+            store.update_user_settings::<VimSettings>(cx, |settings| {
+                settings.autocmds = Some(HashMap::from_iter([(
+                    AutocmdEvent::BufWrite,
+                    vec!["editor::Format".to_string()],
+                )]))
+            });
+        });
+        cx.lsp
+            .handle_request::<lsp::request::Formatting, _, _>(|_, _| async move {
+                Ok(Some(vec![lsp::TextEdit::new(
+                    lsp::Range::new(lsp::Position::new(0, 2), lsp::Position::new(0, 4)),
+                    " ".to_string(),
+                )]))
+            });
+        let fs = cx.workspace(|workspace, cx| workspace.project().read(cx).fs().clone());
+
+        // the file is saved once the language server has formatted it
+        cx.set_state("fn  ˇmain() {}", Mode::Normal);
+        cx.simulate_keystrokes([":", "w", "enter"]);
+        cx.run_until_parked();
+        assert_eq!(
+            fs.load(Path::new("/root/dir/file.rs")).await.unwrap(),
+            "fn main() {}"
+        );
+    }
+}
@@ -0,0 +1,497 @@
+use collections::HashSet;
+use editor::Editor;
+use gpui::{impl_actions, Action, ViewContext};
+use serde_derive::Deserialize;
+use settings::Settings;
+use workspace::{pane, SaveIntent, Workspace};
+
+use crate::{only::buffer_id, utils::command_abs_path, Vim, VimSettings};
+
+/// The buffer `:b` goes to. Buffers are the tabs of the active pane, numbered from 1.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+pub enum BufferTarget {
+    /// `:b {N}`, or `:{N}b`
+    Number(usize),
+    /// `:b#`, the buffer that was active before the current one
+    Alternate,
+    /// `:bfirst`
+    First,
+    /// `:blast`
+    Last,
+    /// `:bnext`, or with a count (`:bnext 2` or `:2bnext`) that many along, going
+    /// around from the last buffer to the first
+    Next(usize),
+    /// `:bprevious` (or `:bNext`), going around from the first buffer to the last
+    Previous(usize),
+}
+
+/// Goes to another buffer. With `!` (as in `:bnext!`), a buffer with changes can be
+/// left even when the `hidden` setting is off.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct GoToBuffer {
+    pub target: BufferTarget,
+    #[serde(default)]
+    pub force: bool,
+}
+
+/// `:e[dit] {file}` opens the file in the active pane, which is relative to the
+/// project's root. `:e! {file}` discards the changes to the current buffer first.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct EditFile {
+    pub path: String,
+    #[serde(default)]
+    pub force: bool,
+}
+
+/// Closes buffers (`:bd`): the current one, or the ones numbered, as a list
+/// (`:bd 2 4`) or a range (`:2,4bd`, or `:%bd` for all of them). With `!` their
+/// changes are discarded.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct DeleteBuffers {
+    pub numbers: Vec<usize>,
+    pub all: bool,
+    pub force: bool,
+}
+
+impl_actions!(vim, [GoToBuffer, DeleteBuffers, EditFile]);
+
+pub(crate) fn register(workspace: &mut Workspace, _: &mut ViewContext<Workspace>) {
+    workspace.register_action(go_to_buffer);
+    workspace.register_action(delete_buffers);
+    workspace.register_action(edit_file);
+}
+
+/// Whether the active buffer can be left for another one. Like vim's, the `hidden`
+/// setting lets a buffer with changes be left, keeping its tab open with them. When
+/// it's off, that needs a `!`, or another pane that shows the buffer too, and E37 is
+/// shown otherwise.
+fn can_leave_buffer(workspace: &Workspace, force: bool, cx: &mut ViewContext<Workspace>) -> bool {
+    if force || VimSettings::get_global(cx).hidden {
+        return true;
+    }
+    let Some(item) = workspace.active_item(cx) else {
+        return true;
+    };
+    if !item.is_dirty(cx) {
+        return true;
+    }
+    let buffer = buffer_id(item.as_ref(), cx);
+    let shown_elsewhere = workspace.panes().iter().any(|pane| {
+        pane != workspace.active_pane()
+            && pane
+                .read(cx)
+                .active_item()
+                .is_some_and(|item| buffer_id(item.as_ref(), cx) == buffer)
+    });
+    if !shown_elsewhere {
+        Vim::update(cx, |vim, _| {
+            vim.show_message("E37: No write since last change (add ! to override)")
+        });
+    }
+    shown_elsewhere
+}
+
+fn go_to_buffer(workspace: &mut Workspace, action: &GoToBuffer, cx: &mut ViewContext<Workspace>) {
+    let pane = workspace.active_pane().clone();
+    let index = pane.update(cx, |pane, _| {
+        let count = pane.items_len();
+        let active = pane.active_item_index();
+        match action.target {
+            BufferTarget::Number(number) if (1..=count).contains(&number) => Ok(number - 1),
+            BufferTarget::Number(number) => Err(format!("E86: Buffer {number} does not exist")),
+            BufferTarget::Alternate => pane
+                .alternate_item_index()
+                .ok_or_else(|| "E23: No alternate file".to_string()),
+            BufferTarget::First if count > 0 => Ok(0),
+            BufferTarget::Last if count > 0 => Ok(count - 1),
+            BufferTarget::Next(n) if count > 0 => Ok((active + n) % count),
+            BufferTarget::Previous(n) if count > 0 => Ok((active + count - n % count) % count),
+            BufferTarget::First
+            | BufferTarget::Last
+            | BufferTarget::Next(_)
+            | BufferTarget::Previous(_) => Err("E85: There is no listed buffer".into()),
+        }
+    });
+    let index = match index {
+        Ok(index) => index,
+        Err(message) => return Vim::update(cx, |vim, _| vim.show_message(message)),
+    };
+    if index != pane.read(cx).active_item_index() && can_leave_buffer(workspace, action.force, cx) {
+        pane.update(cx, |pane, cx| pane.activate_item(index, true, true, cx));
+    }
+}
+
+fn edit_file(workspace: &mut Workspace, action: &EditFile, cx: &mut ViewContext<Workspace>) {
+    Vim::update(cx, |vim, cx| vim.clear_operator(cx));
+    let Some(abs_path) = command_abs_path(workspace, &action.path, cx) else {
+        Vim::update(cx, |vim, _| {
+            vim.show_message("E32: No project to open the file in")
+        });
+        return;
+    };
+    if !can_leave_buffer(workspace, action.force, cx) {
+        return;
+    }
+
+    // the changes are discarded by reloading the file, which leaves its tab open
+    let buffer = workspace
+        .active_item_as::<Editor>(cx)
+        .and_then(|editor| editor.read(cx).buffer().read(cx).as_singleton())
+        .filter(|buffer| action.force && buffer.read(cx).is_dirty());
+    let reload = buffer.map(|buffer| {
+        workspace.project().update(cx, |project, cx| {
+            project.reload_buffers(HashSet::from_iter([buffer]), false, cx)
+        })
+    });
+    cx.spawn(|workspace, mut cx| async move {
+        if let Some(reload) = reload {
+            reload.await?;
+        }
+        workspace
+            .update(&mut cx, |workspace, cx| {
+                workspace.open_abs_path(abs_path, true, cx)
+            })?
+            .await?;
+        anyhow::Ok(())
+    })
+    .detach_and_log_err(cx);
+}
+
+fn delete_buffers(
+    workspace: &mut Workspace,
+    action: &DeleteBuffers,
+    cx: &mut ViewContext<Workspace>,
+) {
+    let save_intent = if action.force {
+        SaveIntent::Skip
+    } else {
+        SaveIntent::Close
+    };
+    if action.numbers.is_empty() && !action.all {
+        cx.dispatch_action(
+            pane::CloseActiveItem {
+                save_intent: Some(save_intent),
+            }
+            .boxed_clone(),
+        );
+        return;
+    }
+
+    let pane = workspace.active_pane().clone();
+    pane.update(cx, |pane, cx| {
+        let item_ids = pane
+            .items()
+            .enumerate()
+            .filter(|(ix, _)| action.all || action.numbers.contains(&(ix + 1)))
+            .map(|(_, item)| item.item_id())
+            .collect::<Vec<_>>();
+        if item_ids.is_empty() {
+            Vim::update(cx, |vim, _| {
+                vim.show_message("E516: No buffers were deleted")
+            });
+            return;
+        }
+        pane.close_items(cx, save_intent, move |item_id| item_ids.contains(&item_id))
+            .detach_and_log_err(cx);
+    });
+}
+
+/// Parses the buffer commands: `:b`, `:bfirst`, `:blast`, `:bnext`, `:bprevious` and
+/// `:bd`, with the buffer numbers or range they take.
+pub(crate) fn parse_buffer_command(query: &str) -> Option<(&'static str, Box<dyn Action>)> {
+    let (range, rest) = split_buffer_range(query);
+    let name_len = rest
+        .find(|c: char| !c.is_ascii_alphabetic())
+        .unwrap_or(rest.len());
+    let (name, args) = rest.split_at(name_len);
+    let (force, args) = match args.strip_prefix('!') {
+        Some(args) => (true, args),
+        None => (false, args),
+    };
+    let args = args.trim();
+
+    match name {
+        "b" | "bu" | "buf" | "buff" | "buffe" | "buffer" => {
+            let target = match (range, args) {
+                (Some(BufferRange::Numbers(start, end)), "") if start == end => {
+                    BufferTarget::Number(end)
+                }
+                (None, "#") => BufferTarget::Alternate,
+                (None, number) => BufferTarget::Number(number.parse().ok()?),
+                _ => return None,
+            };
+            Some(("buffer", GoToBuffer { target, force }.boxed_clone()))
+        }
+        "bf" | "bfi" | "bfir" | "bfirs" | "bfirst" | "br" | "bre" | "brew" | "brewi" | "brewin"
+        | "brewind"
+            if range.is_none() && args.is_empty() =>
+        {
+            let target = BufferTarget::First;
+            Some(("bfirst", GoToBuffer { target, force }.boxed_clone()))
+        }
+        "bl" | "bla" | "blas" | "blast" if range.is_none() && args.is_empty() => {
+            let target = BufferTarget::Last;
+            Some(("blast", GoToBuffer { target, force }.boxed_clone()))
+        }
+        "bn" | "bne" | "bnex" | "bnext" => {
+            let target = BufferTarget::Next(buffer_count(range, args)?);
+            Some(("bnext", GoToBuffer { target, force }.boxed_clone()))
+        }
+        "bp" | "bpr" | "bpre" | "bprev" | "bprevi" | "bprevio" | "bpreviou" | "bprevious"
+        | "bN" | "bNe" | "bNex" | "bNext" => {
+            let target = BufferTarget::Previous(buffer_count(range, args)?);
+            Some(("bprevious", GoToBuffer { target, force }.boxed_clone()))
+        }
+        "bd" | "bde" | "bdel" | "bdele" | "bdelet" | "bdelete" => {
+            let mut numbers = args
+                .split_whitespace()
+                .map(|number| number.parse().ok())
+                .collect::<Option<Vec<usize>>>()?;
+            let all = match range {
+                Some(BufferRange::All) => true,
+                Some(BufferRange::Numbers(start, end)) => {
+                    numbers.extend(start.min(end)..=start.max(end));
+                    false
+                }
+                None => false,
+            };
+            Some((
+                "bdelete",
+                DeleteBuffers {
+                    numbers,
+                    all,
+                    force,
+                }
+                .boxed_clone(),
+            ))
+        }
+        _ => None,
+    }
+}
+
+/// The count of `:bnext 2` or `:2bnext`, or 1.
+fn buffer_count(range: Option<BufferRange>, args: &str) -> Option<usize> {
+    match (range, args) {
+        (None, "") => Some(1),
+        (None, count) => count.parse().ok().filter(|count| *count > 0),
+        (Some(BufferRange::Numbers(start, end)), "") if start == end && end > 0 => Some(end),
+        _ => None,
+    }
+}
+
+/// `:e[dit] {file}` or `:e[dit]! {file}`.
+pub(crate) fn parse_edit_file(query: &str) -> Option<EditFile> {
+    let name_len = query
+        .find(|c: char| !c.is_ascii_alphabetic())
+        .unwrap_or(query.len());
+    let (name, args) = query.split_at(name_len);
+    if !matches!(name, "e" | "ed" | "edi" | "edit") {
+        return None;
+    }
+    let (force, args) = match args.strip_prefix('!') {
+        Some(args) => (true, args),
+        None => (false, args),
+    };
+    let path = args.trim();
+    if path.is_empty() || !args.starts_with(' ') {
+        return None;
+    }
+    Some(EditFile {
+        path: path.to_string(),
+        force,
+    })
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum BufferRange {
+    All,
+    Numbers(usize, usize),
+}
+
+/// Splits the buffer numbers off the front of a buffer command, like the `2,4` of
+/// `:2,4bd`, or the `%` of `:%bd`.
+fn split_buffer_range(query: &str) -> (Option<BufferRange>, &str) {
+    if let Some(rest) = query.strip_prefix('%') {
+        return (Some(BufferRange::All), rest);
+    }
+    let number = |text: &str| -> Option<(usize, usize)> {
+        let len = text
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(text.len());
+        Some((text[..len].parse().ok()?, len))
+    };
+    let Some((start, len)) = number(query) else {
+        return (None, query);
+    };
+    let rest = &query[len..];
+    match rest
+        .strip_prefix(',')
+        .and_then(|end| Some((number(end)?, end)))
+    {
+        Some(((end, len), end_text)) => (Some(BufferRange::Numbers(start, end)), &end_text[len..]),
+        None => (Some(BufferRange::Numbers(start, start)), rest),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::Path;
+
+    use editor::Editor;
+    use settings::SettingsStore;
+
+    use super::{parse_buffer_command, parse_edit_file, BufferTarget, DeleteBuffers, GoToBuffer};
+    use crate::{state::Mode, test::VimTestContext, VimSettings};
+
+    fn go_to(query: &str) -> Option<BufferTarget> {
+        let (_, action) = parse_buffer_command(query)?;
+        action
+            .as_any()
+            .downcast_ref::<GoToBuffer>()
+            .map(|go_to| go_to.target)
+    }
+
+    fn delete(query: &str) -> Option<DeleteBuffers> {
+        let (_, action) = parse_buffer_command(query)?;
+        action.as_any().downcast_ref::<DeleteBuffers>().cloned()
+    }
+
+    #[test]
+    fn test_parse_buffer_command() {
+        assert_eq!(go_to("b 2"), Some(BufferTarget::Number(2)));
+        assert_eq!(go_to("b2"), Some(BufferTarget::Number(2)));
+        assert_eq!(go_to("2b"), Some(BufferTarget::Number(2)));
+        assert_eq!(go_to("b#"), Some(BufferTarget::Alternate));
+        assert_eq!(go_to("bfirst"), Some(BufferTarget::First));
+        assert_eq!(go_to("brewind"), Some(BufferTarget::First));
+        assert_eq!(go_to("bl"), Some(BufferTarget::Last));
+        assert_eq!(go_to("bn"), Some(BufferTarget::Next(1)));
+        assert_eq!(go_to("bnext! 2"), Some(BufferTarget::Next(2)));
+        assert_eq!(go_to("3bN"), Some(BufferTarget::Previous(3)));
+        assert_eq!(go_to("bp x"), None);
+        assert_eq!(go_to("b"), None);
+        assert_eq!(go_to("bar"), None);
+
+        let delete_buffers = |numbers: Vec<usize>, all, force| {
+            Some(DeleteBuffers {
+                numbers,
+                all,
+                force,
+            })
+        };
+        assert_eq!(delete("bd"), delete_buffers(vec![], false, false));
+        assert_eq!(delete("bd! 2 4"), delete_buffers(vec![2, 4], false, true));
+        assert_eq!(delete("2,4bd"), delete_buffers(vec![2, 3, 4], false, false));
+        assert_eq!(delete("%bdelete"), delete_buffers(vec![], true, false));
+        assert_eq!(delete("bd x"), None);
+
+        let edit = parse_edit_file("e! src/a b.rs").unwrap();
+        assert_eq!(edit.path, "src/a b.rs");
+        assert!(edit.force);
+        assert!(!parse_edit_file("edit a.rs").unwrap().force);
+        assert!(parse_edit_file("e").is_none());
+        assert!(parse_edit_file("ex a.rs").is_none());
+    }
+
+    #[gpui::test]
+    async fn test_buffer_commands(cx: &mut gpui::TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+        let run = |cx: &mut VimTestContext, command: &str| {
+            cx.simulate_keystrokes([":"]);
+            cx.simulate_input(command);
+            cx.simulate_keystrokes(["enter"]);
+            cx.run_until_parked();
+        };
+        let active = |cx: &mut VimTestContext| {
+            cx.workspace(|workspace, cx| {
+                let pane = workspace.active_pane().read(cx);
+                (pane.active_item_index() + 1, pane.items_len())
+            })
+        };
+
+        cx.set_state("ˇone", Mode::Normal);
+        run(&mut cx, "tabnew");
+        run(&mut cx, "tabnew");
+        assert_eq!(active(&mut cx), (3, 3));
+
+        run(&mut cx, "b1");
+        assert_eq!(active(&mut cx), (1, 3));
+        run(&mut cx, "b#");
+        assert_eq!(active(&mut cx), (3, 3));
+        run(&mut cx, "2b");
+        assert_eq!(active(&mut cx), (2, 3));
+        run(&mut cx, "bfirst");
+        assert_eq!(active(&mut cx), (1, 3));
+        run(&mut cx, "blast");
+        assert_eq!(active(&mut cx), (3, 3));
+        run(&mut cx, "b 4");
+        assert_eq!(
+            cx.message().as_deref(),
+            Some("E86: Buffer 4 does not exist")
+        );
+
+        run(&mut cx, "2,3bd");
+        assert_eq!(active(&mut cx), (1, 1));
+        run(&mut cx, "bd 5");
+        assert_eq!(
+            cx.message().as_deref(),
+            Some("E516: No buffers were deleted")
+        );
+    }
+
+    #[gpui::test]
+    async fn test_nohidden(cx: &mut gpui::TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+        let run = |cx: &mut VimTestContext, command: &str| {
+            cx.simulate_keystrokes([":"]);
+            cx.simulate_input(command);
+            cx.simulate_keystrokes(["enter"]);
+            cx.run_until_parked();
+        };
+        let active = |cx: &mut VimTestContext| {
+            cx.workspace(|workspace, cx| {
+                let pane = workspace.active_pane().read(cx);
+                (pane.active_item_index() + 1, pane.items_len())
+            })
+        };
+        cx.update_global(|store: &mut SettingsStore, cx| {
+            store.update_user_settings::<VimSettings>(cx, |s| s.hidden = Some(false));
+        });
+
+        cx.set_state("ˇchanged", Mode::Normal);
+        run(&mut cx, "tabnew");
+        run(&mut cx, "bn");
+        assert_eq!(active(&mut cx), (1, 2));
+
+        // the changed buffer can't be left without a !
+        run(&mut cx, "bn");
+        assert_eq!(active(&mut cx), (1, 2));
+        assert_eq!(
+            cx.message().as_deref(),
+            Some("E37: No write since last change (add ! to override)")
+        );
+        run(&mut cx, "bprevious!");
+        assert_eq!(active(&mut cx), (2, 2));
+        run(&mut cx, "b1");
+        assert_eq!(active(&mut cx), (1, 2));
+
+        // and :e! drops its changes
+        let fs = cx.workspace(|workspace, cx| workspace.project().read(cx).fs().clone());
+        fs.as_fake()
+            .insert_file("/root/dir/other.rs", "other".into())
+            .await;
+        run(&mut cx, "e other.rs");
+        assert_eq!(active(&mut cx), (1, 2));
+        run(&mut cx, "e! other.rs");
+        assert_eq!(active(&mut cx), (3, 3));
+        cx.workspace(|workspace, cx| {
+            let editor = workspace.active_item_as::<Editor>(cx).unwrap();
+            assert_eq!(editor.read(cx).text(cx), "other");
+            let pane = workspace.active_pane().read(cx);
+            let first = pane.item_for_index(0).unwrap();
+            assert!(!first.is_dirty(cx));
+            let path = first.project_path(cx).unwrap().path;
+            assert_eq!(path.as_ref(), Path::new("file.rs"));
+        });
+    }
+}
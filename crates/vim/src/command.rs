@@ -1,18 +1,58 @@
+use std::{
+    ops::{Range, RangeInclusive},
+    rc::Rc,
+};
+
 use command_palette_hooks::CommandInterceptResult;
-use editor::actions::{SortLinesCaseInsensitive, SortLinesCaseSensitive};
-use gpui::{impl_actions, Action, AppContext, ViewContext};
+use editor::{
+    display_map::DisplaySnapshot, scroll::Autoscroll, Anchor, DisplayPoint, Editor, ToOffset,
+    ToPoint,
+};
+use gpui::{actions, impl_actions, Action, AppContext, ViewContext, WeakView, WindowContext};
+use language::{Point, SelectionGoal};
+use regex::Regex;
 use serde_derive::Deserialize;
+use settings::Settings;
 use workspace::{SaveIntent, Workspace};
 
 use crate::{
+    autocmd::start_autocmds,
+    buffers::{parse_buffer_command, parse_edit_file},
+    cquit::parse_quit_with_error,
+    file_name::parse_file_name,
+    filter::{parse_filter, parse_read, ReadCommand},
+    help::parse_help,
+    hlsearch::NoHighlightSearch,
+    insert::NormalBefore,
+    key_notation::keystroke_for,
+    location_list::{parse_location_tag, NextLocation, OpenLocationList, PreviousLocation},
+    messages::ShowMessages,
+    modifiable::{parse_set_modifiable, parse_view_file},
     motion::{EndOfDocument, Motion, StartOfDocument},
     normal::{
+        character_info::ShowCharacterInfo,
+        counts::ShowFileInfo,
+        delete::delete_motion,
+        mark::parse_delete_marks,
         move_cursor,
-        search::{FindCommand, ReplaceCommand},
+        paste::{parse_put, PutLines},
+        registers::parse_show_registers,
+        repeat::{replay, ReplayStep},
+        search::{is_substitute, FindCommand, ReplaceCommand},
         JoinLines,
     },
-    state::Mode,
-    Vim,
+    only::OnlyPane,
+    preview::ClosePreview,
+    quickfix::{parse_make, parse_quickfix_do, NextQuickfix, PreviousQuickfix},
+    rename::parse_rename,
+    state::{Mode, ReplayableAction},
+    tags::{parse_tag, parse_tag_select, parse_workspace_symbols, PopTag},
+    tutor::OpenTutor,
+    undo_tree::ShowUndoList,
+    utils::{end_undo_group, start_undo_group, UndoGroup},
+    visual::{self, VisualDelete},
+    write::{parse_write_as, parse_write_command},
+    AutocmdEvent, SuspendBehavior, Vim, VimSettings,
 };
 
 #[derive(Debug, Clone, PartialEq, Deserialize)]
@@ -20,7 +60,98 @@ pub struct GoToLine {
     pub line: u32,
 }
 
-impl_actions!(vim, [GoToLine]);
+/// ExCommand wraps the command typed into the command palette, so that vim
+/// can record it in the command history before running it.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct ExCommand {
+    pub command: String,
+}
+
+/// Runs the keys as normal mode commands (`:normal {keys}`).
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct Normal {
+    pub keys: String,
+}
+
+/// Runs an ex command on every line that matches the pattern (`:g/pattern/command`),
+/// or that doesn't match it (`:v/pattern/command`).
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct OnMatchingLines {
+    pub pattern: String,
+    pub invert: bool,
+    pub command: String,
+}
+
+/// Sorts the selected lines, or all of them outside of visual mode (`:sort`). With a
+/// pattern, lines are sorted by what follows its match (or by the match itself for
+/// `:sort r`), and the lines it doesn't match come first. The sort is stable, so
+/// lines with equal keys stay in the order they were in. With `:sort u` only the
+/// first of the lines with equal keys is kept. Bound to a key, the fields default to
+/// false, as in `["vim::Sort", { "unique": true }]`.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct Sort {
+    pub pattern: Option<String>,
+    pub on_match: bool,
+    pub ignore_case: bool,
+    pub reverse: bool,
+    pub unique: bool,
+}
+
+/// One end of the range of lines before an ex command.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+pub enum Address {
+    /// `.`
+    CurrentLine,
+    /// `$`
+    LastLine,
+    /// A line number, counting from 1
+    Line(u32),
+    /// `'a`, or `'<` and `'>` for the lines of the visual selection
+    Mark(char),
+    /// `w0`, the first line visible in the editor, as in vim's `line('w0')`
+    WindowTop,
+    /// `w$`, the last line visible in the editor
+    WindowBottom,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+pub struct LineAddress {
+    pub address: Address,
+    /// The sum of the `+n` and `-n` after the address
+    pub offset: i32,
+}
+
+/// The lines an ex command acts on, like `%` (every line), `1,10`, `.,$`, `'a,'b`,
+/// `.+2` or `w0,w$` (the lines visible in the editor).
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+pub struct CommandRange {
+    pub start: LineAddress,
+    pub end: LineAddress,
+}
+
+/// Runs an ex command on a range of lines (`:{range}{command}`), by selecting the
+/// lines and running the command as it runs on a linewise visual selection. Without
+/// a command, it goes to the last line of the range.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct WithRange {
+    pub range: CommandRange,
+    pub command: String,
+}
+
+impl_actions!(
+    vim,
+    [
+        GoToLine,
+        ExCommand,
+        Normal,
+        OnMatchingLines,
+        Sort,
+        WithRange
+    ]
+);
+
+actions!(vim, [DeleteLines, Suspend, ReverseLines]);
 
 pub fn register(workspace: &mut Workspace, _: &mut ViewContext<Workspace>) {
     workspace.register_action(|_: &mut Workspace, action: &GoToLine, cx| {
@@ -29,18 +160,536 @@ pub fn register(workspace: &mut Workspace, _: &mut ViewContext<Workspace>) {
             move_cursor(vim, Motion::StartOfDocument, Some(action.line as usize), cx);
         });
     });
+
+    workspace.register_action(|_: &mut Workspace, action: &ExCommand, cx| {
+        let Some((name, command)) = parse_command(&action.command, cx) else {
+            Vim::update(cx, |vim, _| {
+                vim.show_message(format!(
+                    "E492: Not an editor command: {}",
+                    action.command.trim_start_matches(':')
+                ))
+            });
+            return;
+        };
+        let format = Vim::update(cx, |vim, cx| {
+            let format = if matches!(name, "write" | "write!" | "update" | "wall" | "wall!") {
+                start_autocmds(vim, AutocmdEvent::BufWrite, cx)
+            } else {
+                None
+            };
+            let command = action.command.trim_start_matches(':').to_string();
+            // the `":` register holds the last command line, for `@:`
+            vim.workspace_state
+                .registers
+                .insert(":".to_string(), command.clone());
+            vim.workspace_state.record_command(command);
+            format
+        });
+        // the file is saved once it's been formatted
+        if let Some(format) = format {
+            cx.spawn(|workspace, mut cx| async move {
+                if let Err(err) = format.await {
+                    log::error!("failed to format before saving: {err}");
+                }
+                workspace.update(&mut cx, |_, cx| cx.dispatch_action(command))
+            })
+            .detach_and_log_err(cx);
+            return;
+        }
+        // the command's edits are undone together, including those of the actions
+        // it dispatches in turn (like a command on a range), so the group ends once
+        // they've all run
+        let undo_group = Vim::update(cx, |vim, cx| start_undo_group(vim, cx));
+        cx.dispatch_action(command);
+        if let Some(undo_group) = undo_group {
+            cx.spawn(|_, mut cx| async move { cx.update(|cx| end_undo_group(&undo_group, cx)) })
+                .detach();
+        }
+    });
+
+    workspace.register_action(|_: &mut Workspace, action: &Normal, cx| {
+        // if a key fails, the unfinished command is still abandoned.
+        replay(normal_steps(&action.keys), cx).on_finish(finish_normal);
+    });
+
+    workspace.register_action(|_: &mut Workspace, action: &OnMatchingLines, cx| {
+        on_matching_lines(action, cx)
+    });
+
+    workspace.register_action(|_: &mut Workspace, action: &Sort, cx| sort(action, cx));
+    workspace.register_action(|_: &mut Workspace, _: &ReverseLines, cx| {
+        Vim::update(cx, |vim, cx| {
+            manipulate_selected_lines(vim, cx, |lines| lines.reverse())
+        })
+    });
+
+    workspace.register_action(|_: &mut Workspace, action: &WithRange, cx| with_range(action, cx));
+
+    // A GUI editor can't be suspended like terminal vim, so `ctrl-z` and `:stop` minimize
+    // (or hide) the window instead.
+    workspace.register_action(|_: &mut Workspace, _: &Suspend, cx| {
+        Vim::update(cx, |vim, cx| vim.clear_operator(cx));
+        match VimSettings::get_global(cx).suspend {
+            SuspendBehavior::Minimize => cx.minimize_window(),
+            SuspendBehavior::Hide => cx.hide(),
+        }
+    });
+
+    // `:d` deletes like `dd` (or `Vd` for a selection), so the lines go in the registers.
+    workspace.register_action(|workspace: &mut Workspace, _: &DeleteLines, cx| {
+        if Vim::read(cx).state().mode.is_visual() {
+            Vim::update(cx, |vim, cx| vim.switch_mode(Mode::VisualLine, false, cx));
+            visual::delete(workspace, &VisualDelete, cx);
+        } else {
+            Vim::update(cx, |vim, cx| {
+                delete_motion(
+                    vim,
+                    Motion::CurrentLine {
+                        display_lines: false,
+                    },
+                    None,
+                    cx,
+                );
+                vim.clear_operator(cx);
+            });
+        }
+    });
+}
+
+/// The keys of `:normal` are typed one at a time, and then insert mode
+/// or a pending operator is abandoned, as if escape was pressed.
+fn normal_steps(keys: &str) -> Vec<ReplayStep> {
+    let mut steps = keys
+        .chars()
+        .map(|c| ReplayStep::Keystroke(keystroke_for(c)))
+        .collect::<Vec<_>>();
+    steps.push(ReplayStep::Run(Box::new(finish_normal)));
+    steps
+}
+
+pub(crate) fn finish_normal(cx: &mut WindowContext) {
+    Vim::update(cx, |vim, cx| {
+        if vim.state().mode == Mode::Insert {
+            cx.dispatch_action(NormalBefore.boxed_clone())
+        } else {
+            vim.switch_mode(Mode::Normal, false, cx)
+        }
+    })
+}
+
+fn with_range(action: &WithRange, cx: &mut WindowContext) {
+    if let Some(read) = parse_read(&action.command) {
+        return at_range_end(action.range, cx, |line| {
+            ReadCommand {
+                line: Some(line),
+                ..read
+            }
+            .boxed_clone()
+        });
+    }
+    if let Some(put) = parse_put(&action.command) {
+        return at_range_end(action.range, cx, |line| {
+            PutLines {
+                line: Some(line),
+                ..put
+            }
+            .boxed_clone()
+        });
+    }
+    let command = if action.command.is_empty() {
+        None
+    } else {
+        match parse_command(&action.command, cx) {
+            Some((name, command)) if accepts_range(name, &action.command) => Some(command),
+            Some(_) => {
+                Vim::update(cx, |vim, _| vim.show_message("E481: No range allowed"));
+                return;
+            }
+            None => {
+                Vim::update(cx, |vim, _| {
+                    vim.show_message(format!("E492: Not an editor command: {}", action.command))
+                });
+                return;
+            }
+        }
+    };
+
+    Vim::update(cx, |vim, cx| {
+        let rows =
+            vim.update_active_editor(cx, |vim, editor, cx| action.range.rows(vim, editor, cx));
+        let rows = match rows {
+            Some(Ok(rows)) => rows,
+            Some(Err(message)) => return vim.show_message(message),
+            None => return,
+        };
+        let Some(command) = command else {
+            vim.switch_mode(Mode::Normal, false, cx);
+            move_cursor(
+                vim,
+                Motion::StartOfDocument,
+                Some(*rows.end() as usize + 1),
+                cx,
+            );
+            return;
+        };
+        vim.switch_mode(Mode::VisualLine, false, cx);
+        vim.update_active_editor(cx, |_, editor, cx| {
+            let end = Point::new(
+                *rows.end(),
+                editor.buffer().read(cx).snapshot(cx).line_len(*rows.end()),
+            );
+            editor.change_selections(Some(Autoscroll::fit()), cx, |s| {
+                s.select_ranges([Point::new(*rows.start(), 0)..end])
+            });
+        });
+        cx.dispatch_action(command);
+    });
+}
+
+/// Whether the command can follow a range: the commands that act on the lines of a
+/// visual selection.
+fn accepts_range(name: &str, command: &str) -> bool {
+    matches!(
+        name,
+        "join" | "delete" | "sort" | "reverse" | "!" | "write !"
+    ) || is_substitute(command)
+}
+
+/// Runs a command that takes the last line of the range, like `:{range}r !{cmd}` and
+/// `:{range}put`, which put text below it. Line 0 (as in `:0r`) is above the first line.
+fn at_range_end(
+    range: CommandRange,
+    cx: &mut WindowContext,
+    command: impl FnOnce(u32) -> Box<dyn Action>,
+) {
+    let top = LineAddress {
+        address: Address::Line(0),
+        offset: 0,
+    };
+    Vim::update(cx, |vim, cx| {
+        let line = if range.end == top {
+            Ok(0)
+        } else {
+            vim.update_active_editor(cx, |vim, editor, cx| range.rows(vim, editor, cx))
+                .unwrap_or(Err("E16: Invalid range"))
+                .map(|rows| *rows.end() + 1)
+        };
+        match line {
+            Ok(line) => cx.dispatch_action(command(line)),
+            Err(message) => vim.show_message(message),
+        }
+    })
+}
+
+impl CommandRange {
+    /// The rows the range covers (in order, as vim swaps a backwards range), or the
+    /// error vim shows for it. The addresses and their offsets count buffer lines,
+    /// however the text is folded or soft wrapped, and then a closed fold at either
+    /// end is taken in whole, as vim's ex commands act on all the lines of one.
+    fn rows(
+        &self,
+        vim: &Vim,
+        editor: &mut Editor,
+        cx: &mut ViewContext<Editor>,
+    ) -> Result<RangeInclusive<u32>, &'static str> {
+        let map = editor.snapshot(cx).display_snapshot;
+        let start = self.start.row(vim, editor, &map, cx)?;
+        let end = self.end.row(vim, editor, &map, cx)?;
+        let (start, end) = (start.min(end), start.max(end));
+        let start = map.prev_line_boundary(Point::new(start, 0)).0.row;
+        let end = map.next_line_boundary(Point::new(end, 0)).0.row;
+        Ok(start..=end)
+    }
+}
+
+impl LineAddress {
+    fn row(
+        &self,
+        vim: &Vim,
+        editor: &Editor,
+        map: &DisplaySnapshot,
+        cx: &AppContext,
+    ) -> Result<u32, &'static str> {
+        let snapshot = &map.buffer_snapshot;
+        let selection = editor.selections.newest::<Point>(cx);
+        let row = match self.address {
+            Address::CurrentLine => selection.head().row as i64,
+            Address::LastLine => snapshot.max_point().row as i64,
+            Address::Line(line) => line.saturating_sub(1) as i64,
+            Address::Mark('<') if vim.state().mode.is_visual() => selection.start.row as i64,
+            Address::Mark('>') if vim.state().mode.is_visual() => {
+                let mut row = selection.end.row;
+                if row > selection.start.row && selection.end.column == 0 {
+                    row -= 1;
+                }
+                row as i64
+            }
+            Address::Mark(name) => {
+                let Some(mark) = vim.state().marks.get(&name) else {
+                    return Err("E20: Mark not set");
+                };
+                mark.to_point(snapshot).row as i64
+            }
+            Address::WindowTop | Address::WindowBottom => {
+                let Some(visible_rows) = editor.visible_line_count() else {
+                    return Err("E16: Invalid range");
+                };
+                let top = editor.scroll_manager.anchor().scroll_position(map).y as u32;
+                let row = if self.address == Address::WindowTop {
+                    top
+                } else {
+                    (top + (visible_rows as u32).max(1) - 1).min(map.max_point().row())
+                };
+                DisplayPoint::new(row, 0).to_point(map).row as i64
+            }
+        };
+        let row = row + self.offset as i64;
+        if row < 0 || row > snapshot.max_point().row as i64 {
+            return Err("E16: Invalid range");
+        }
+        Ok(row as u32)
+    }
+}
+
+/// The steps that run the command given to another one, like the command after
+/// `:g/pattern/`: the keys of a `:normal` command, or an ex command.
+pub(crate) fn command_steps(
+    command: &str,
+    cx: &AppContext,
+) -> Option<Rc<dyn Fn() -> Vec<ReplayStep>>> {
+    if let Some(normal) = parse_normal(command) {
+        Some(Rc::new(move || normal_steps(&normal.keys)))
+    } else {
+        let (_, command) = parse_command(command, cx)?;
+        Some(Rc::new(move || {
+            vec![ReplayStep::Replay(ReplayableAction::Action(
+                command.boxed_clone(),
+            ))]
+        }))
+    }
+}
+
+/// `:g` marks the matching lines before running the command on any of them,
+/// so that lines inserted or deleted by the command don't throw off which lines
+/// it runs on. Like vim, a marked line that was deleted is skipped, the whole
+/// command is undone with one `u`, and it stops at the first line that fails.
+fn on_matching_lines(action: &OnMatchingLines, cx: &mut WindowContext) {
+    let pattern = if action.pattern.is_empty() {
+        // an empty pattern reuses the last search
+        let Some(last_search) = Vim::read(cx).workspace_state.search_history.last() else {
+            log::error!("E35: No previous regular expression");
+            return;
+        };
+        last_search.clone()
+    } else {
+        action.pattern.clone()
+    };
+    let regex = match Regex::new(&pattern) {
+        Ok(regex) => regex,
+        Err(err) => {
+            log::error!("invalid pattern {pattern:?}: {err}");
+            return;
+        }
+    };
+
+    let Some(command) = command_steps(&action.command, cx) else {
+        log::error!("E492: Not an editor command: {}", action.command);
+        return;
+    };
+
+    let Some((marked_lines, editor, undo_group)) = Vim::update(cx, |vim, cx| {
+        vim.switch_mode(Mode::Normal, false, cx);
+        vim.update_active_editor(cx, |_, editor, cx| {
+            let snapshot = editor.buffer().read(cx).snapshot(cx);
+            let max_row = snapshot.max_point().row;
+            let mut marked_lines = Vec::new();
+            for row in 0..=max_row {
+                let line = snapshot
+                    .text_for_range(Point::new(row, 0)..Point::new(row, snapshot.line_len(row)))
+                    .collect::<String>();
+                if regex.is_match(&line) == action.invert {
+                    continue;
+                }
+                // the line including its newline, which becomes empty if the line is deleted.
+                let end = if row < max_row {
+                    snapshot.anchor_before(Point::new(row + 1, 0))
+                } else {
+                    snapshot.anchor_after(snapshot.max_point())
+                };
+                let range = snapshot.anchor_before(Point::new(row, 0))..end;
+                let was_empty = range.start.to_offset(&snapshot) == range.end.to_offset(&snapshot);
+                marked_lines.push((range, was_empty));
+            }
+
+            (
+                marked_lines,
+                cx.view().downgrade(),
+                UndoGroup::new(editor, cx),
+            )
+        })
+    }) else {
+        return;
+    };
+
+    let steps = marked_lines
+        .into_iter()
+        .map(|(range, was_empty)| {
+            let editor = editor.clone();
+            let command = command.clone();
+            ReplayStep::Run(Box::new(move |cx: &mut WindowContext| {
+                if move_to_marked_line(&editor, range, was_empty, cx) {
+                    replay(command(), cx);
+                }
+            }))
+        })
+        .collect();
+
+    replay(steps, cx).on_finish(move |cx| {
+        finish_normal(cx);
+        end_undo_group(&undo_group, cx);
+    });
+}
+
+fn sort(action: &Sort, cx: &mut WindowContext) {
+    Vim::update(cx, |vim, cx| {
+        let regex = match action.pattern.as_deref() {
+            None => None,
+            Some(pattern) => {
+                // an empty pattern reuses the last search
+                let pattern = if pattern.is_empty() {
+                    match vim.workspace_state.search_history.last() {
+                        Some(last_search) => last_search.clone(),
+                        None => return vim.show_message("E35: No previous regular expression"),
+                    }
+                } else {
+                    pattern.to_string()
+                };
+                match Regex::new(&pattern) {
+                    Ok(regex) => Some(regex),
+                    Err(_) => {
+                        return vim.show_message(format!("E383: Invalid search string: {pattern}"))
+                    }
+                }
+            }
+        };
+
+        manipulate_selected_lines(vim, cx, |lines| sort_lines(lines, action, regex.as_ref()));
+    })
+}
+
+/// Rearranges the selected lines (or all of them outside of visual mode) in one undo
+/// step, like `:sort` and `:reverse`, leaving the cursor on the first of them.
+fn manipulate_selected_lines(
+    vim: &mut Vim,
+    cx: &mut WindowContext,
+    mut callback: impl FnMut(&mut Vec<&str>),
+) {
+    let whole_buffer = !vim.state().mode.is_visual();
+    vim.update_active_editor(cx, |_, editor, cx| {
+        editor.transact(cx, |editor, cx| {
+            if whole_buffer {
+                editor.select_all(&editor::actions::SelectAll, cx);
+            }
+            editor.manipulate_lines(cx, |lines| callback(lines));
+            editor.change_selections(None, cx, |s| {
+                s.move_with(|_, selection| {
+                    selection.collapse_to(selection.start, SelectionGoal::None)
+                })
+            });
+        })
+    });
+    vim.switch_mode(Mode::Normal, true, cx);
 }
 
-pub fn command_interceptor(mut query: &str, cx: &AppContext) -> Option<CommandInterceptResult> {
+fn sort_lines(lines: &mut Vec<&str>, action: &Sort, regex: Option<&Regex>) {
+    let keys = lines
+        .iter()
+        .map(|line| {
+            let key = match regex {
+                None => Some(*line),
+                Some(regex) => regex.find(line).map(|found| {
+                    if action.on_match {
+                        found.as_str()
+                    } else {
+                        &line[found.end()..]
+                    }
+                }),
+            };
+            key.map(|key| {
+                if action.ignore_case {
+                    key.to_lowercase()
+                } else {
+                    key.to_string()
+                }
+            })
+        })
+        .collect::<Vec<_>>();
+
+    // sort_by is stable, so lines with the same key keep their order even when reversed.
+    let mut order = (0..lines.len()).collect::<Vec<_>>();
+    order.sort_by(|a, b| {
+        let ordering = keys[*a].cmp(&keys[*b]);
+        if action.reverse {
+            ordering.reverse()
+        } else {
+            ordering
+        }
+    });
+    if action.unique {
+        order.dedup_by(|a, b| keys[*a] == keys[*b]);
+    }
+    *lines = order.into_iter().map(|ix| lines[ix]).collect();
+}
+
+fn move_to_marked_line(
+    editor: &WeakView<Editor>,
+    range: Range<Anchor>,
+    was_empty: bool,
+    cx: &mut WindowContext,
+) -> bool {
+    editor
+        .update(cx, |editor, cx| {
+            let snapshot = editor.buffer().read(cx).snapshot(cx);
+            if !was_empty && range.start.to_offset(&snapshot) == range.end.to_offset(&snapshot) {
+                return false;
+            }
+            let row = range.start.to_point(&snapshot).row;
+            editor.change_selections(Some(Autoscroll::fit()), cx, |s| {
+                s.select_ranges([Point::new(row, 0)..Point::new(row, 0)])
+            });
+            true
+        })
+        .unwrap_or(false)
+}
+
+pub fn command_interceptor(query: &str, cx: &AppContext) -> Option<CommandInterceptResult> {
+    let (name, _) = parse_command(query, cx)?;
+
+    let query = query.trim_start_matches(':');
+    let string = ":".to_owned() + name;
+    let positions = generate_positions(&string, query);
+
+    Some(CommandInterceptResult {
+        action: ExCommand {
+            command: query.to_string(),
+        }
+        .boxed_clone(),
+        string,
+        positions,
+    })
+}
+
+pub(crate) fn parse_command<'a>(
+    mut query: &'a str,
+    cx: &AppContext,
+) -> Option<(&'a str, Box<dyn Action>)> {
     // Note: this is a very poor simulation of vim's command palette.
-    // In the future we should adjust it to handle parsing range syntax,
-    // and then calling the appropriate commands with/without ranges.
+    // A range in front of a command is split off by parse_range, and
+    // only the commands that act on a visual selection accept one
+    // (see accepts_range).
     //
     // We also need to support passing arguments to commands like :w
     // (ideally with filename autocompletion).
-    //
-    // For now, you can only do a replace on the % range, and you can
-    // only use a specific line number range to "go to line"
     while query.starts_with(':') {
         query = &query[1..];
     }
@@ -166,8 +815,9 @@ pub fn command_interceptor(mut query: &str, cx: &AppContext) -> Option<CommandIn
             }
             .boxed_clone(),
         ),
-        "cq" | "cqu" | "cqui" | "cquit" | "cq!" | "cqu!" | "cqui!" | "cquit!" => {
-            ("cquit!", zed_actions::Quit.boxed_clone())
+        "st" | "sto" | "stop" | "st!" | "sto!" | "stop!" | "sus" | "susp" | "suspe" | "suspen"
+        | "suspend" | "sus!" | "susp!" | "suspe!" | "suspen!" | "suspend!" => {
+            ("stop", Suspend.boxed_clone())
         }
 
         // pane management
@@ -183,6 +833,8 @@ pub fn command_interceptor(mut query: &str, cx: &AppContext) -> Option<CommandIn
             "vnew",
             workspace::NewFileInDirection(workspace::SplitDirection::Left).boxed_clone(),
         ),
+        "pc" | "pcl" | "pclo" | "pclos" | "pclose" => ("pclose", ClosePreview.boxed_clone()),
+        "ene" | "enew" => ("enew", workspace::NewFile.boxed_clone()),
         "tabe" | "tabed" | "tabedi" | "tabedit" => ("tabedit", workspace::NewFile.boxed_clone()),
         "tabnew" => ("tabnew", workspace::NewFile.boxed_clone()),
 
@@ -215,105 +867,97 @@ pub fn command_interceptor(mut query: &str, cx: &AppContext) -> Option<CommandIn
             }
             .boxed_clone(),
         ),
-        "on" | "onl" | "only" => (
-            "only",
-            workspace::CloseInactiveTabsAndPanes {
-                save_intent: Some(SaveIntent::Close),
-            }
-            .boxed_clone(),
-        ),
-        "on!" | "onl!" | "only!" => (
-            "only!",
-            workspace::CloseInactiveTabsAndPanes {
-                save_intent: Some(SaveIntent::Skip),
-            }
-            .boxed_clone(),
-        ),
+        "on" | "onl" | "only" => ("only", OnlyPane { force: false }.boxed_clone()),
+        "on!" | "onl!" | "only!" => ("only!", OnlyPane { force: true }.boxed_clone()),
 
         // quickfix / loclist (merged together for now)
-        "cl" | "cli" | "clis" | "clist" => (
-            "clist",
-            cx.build_action("diagnostics::Deploy", None).unwrap(),
-        ),
+        "cl" | "cli" | "clis" | "clist" => {
+            ("clist", cx.build_action("diagnostics::Deploy", None).ok()?)
+        }
         "cc" => ("cc", editor::actions::Hover.boxed_clone()),
         "ll" => ("ll", editor::actions::Hover.boxed_clone()),
-        "cn" | "cne" | "cnex" | "cnext" => ("cnext", editor::actions::GoToDiagnostic.boxed_clone()),
-        "lne" | "lnex" | "lnext" => ("cnext", editor::actions::GoToDiagnostic.boxed_clone()),
+        "cn" | "cne" | "cnex" | "cnext" => ("cnext", NextQuickfix.boxed_clone()),
+        "ln" | "lne" | "lnex" | "lnext" => ("lnext", NextLocation.boxed_clone()),
+        "lop" | "lope" | "lopen" => ("lopen", OpenLocationList.boxed_clone()),
 
-        "cpr" | "cpre" | "cprev" | "cprevi" | "cprevio" | "cpreviou" | "cprevious" => (
-            "cprevious",
-            editor::actions::GoToPrevDiagnostic.boxed_clone(),
-        ),
-        "cN" | "cNe" | "cNex" | "cNext" => {
-            ("cNext", editor::actions::GoToPrevDiagnostic.boxed_clone())
+        "cpr" | "cpre" | "cprev" | "cprevi" | "cprevio" | "cpreviou" | "cprevious" => {
+            ("cprevious", PreviousQuickfix.boxed_clone())
         }
-        "lp" | "lpr" | "lpre" | "lprev" | "lprevi" | "lprevio" | "lpreviou" | "lprevious" => (
-            "lprevious",
-            editor::actions::GoToPrevDiagnostic.boxed_clone(),
-        ),
-        "lN" | "lNe" | "lNex" | "lNext" => {
-            ("lNext", editor::actions::GoToPrevDiagnostic.boxed_clone())
+        "cN" | "cNe" | "cNex" | "cNext" => ("cNext", PreviousQuickfix.boxed_clone()),
+        "lp" | "lpr" | "lpre" | "lprev" | "lprevi" | "lprevio" | "lpreviou" | "lprevious" => {
+            ("lprevious", PreviousLocation.boxed_clone())
         }
+        "lN" | "lNe" | "lNex" | "lNext" => ("lNext", PreviousLocation.boxed_clone()),
+
+        // tags
+        "po" | "pop" => ("pop", PopTag.boxed_clone()),
 
         // modify the buffer (should accept [range])
         "j" | "jo" | "joi" | "join" => ("join", JoinLines.boxed_clone()),
         "d" | "de" | "del" | "dele" | "delet" | "delete" | "dl" | "dell" | "delel" | "deletl"
         | "deletel" | "dp" | "dep" | "delp" | "delep" | "deletp" | "deletep" => {
-            ("delete", editor::actions::DeleteLine.boxed_clone())
+            ("delete", DeleteLines.boxed_clone())
+        }
+        "rev" | "reve" | "rever" | "revers" | "reverse" => ("reverse", ReverseLines.boxed_clone()),
+
+        // search
+        "noh" | "nohl" | "nohls" | "nohlse" | "nohlsea" | "nohlsear" | "nohlsearc"
+        | "nohlsearch" => ("nohlsearch", NoHighlightSearch.boxed_clone()),
+
+        // information
+        "as" | "asc" | "asci" | "ascii" => ("ascii", ShowCharacterInfo.boxed_clone()),
+        "undol" | "undoli" | "undolis" | "undolist" => ("undolist", ShowUndoList.boxed_clone()),
+        "f" | "fi" | "fil" | "file" => ("file", ShowFileInfo.boxed_clone()),
+        "Tut" | "Tuto" | "Tutor" => ("Tutor", OpenTutor.boxed_clone()),
+        "mes" | "mess" | "messa" | "messag" | "message" | "messages" => {
+            ("messages", ShowMessages.boxed_clone())
         }
-        "sor" | "sor " | "sort" | "sort " => ("sort", SortLinesCaseSensitive.boxed_clone()),
-        "sor i" | "sort i" => ("sort i", SortLinesCaseInsensitive.boxed_clone()),
 
         // Explore, etc.
         "E" | "Ex" | "Exp" | "Expl" | "Explo" | "Explor" | "Explore" => (
             "Explore",
-            cx.build_action("project_panel::ToggleFocus", None).unwrap(),
+            cx.build_action("project_panel::ToggleFocus", None).ok()?,
         ),
         "H" | "He" | "Hex" | "Hexp" | "Hexpl" | "Hexplo" | "Hexplor" | "Hexplore" => (
             "Hexplore",
-            cx.build_action("project_panel::ToggleFocus", None).unwrap(),
+            cx.build_action("project_panel::ToggleFocus", None).ok()?,
         ),
         "L" | "Le" | "Lex" | "Lexp" | "Lexpl" | "Lexplo" | "Lexplor" | "Lexplore" => (
             "Lexplore",
-            cx.build_action("project_panel::ToggleFocus", None).unwrap(),
+            cx.build_action("project_panel::ToggleFocus", None).ok()?,
         ),
         "S" | "Se" | "Sex" | "Sexp" | "Sexpl" | "Sexplo" | "Sexplor" | "Sexplore" => (
             "Sexplore",
-            cx.build_action("project_panel::ToggleFocus", None).unwrap(),
+            cx.build_action("project_panel::ToggleFocus", None).ok()?,
         ),
         "Ve" | "Vex" | "Vexp" | "Vexpl" | "Vexplo" | "Vexplor" | "Vexplore" => (
             "Vexplore",
-            cx.build_action("project_panel::ToggleFocus", None).unwrap(),
+            cx.build_action("project_panel::ToggleFocus", None).ok()?,
         ),
         "te" | "ter" | "term" => (
             "term",
-            cx.build_action("terminal_panel::ToggleFocus", None)
-                .unwrap(),
+            cx.build_action("terminal_panel::ToggleFocus", None).ok()?,
         ),
         // Zed panes
         "T" | "Te" | "Ter" | "Term" => (
             "Term",
-            cx.build_action("terminal_panel::ToggleFocus", None)
-                .unwrap(),
+            cx.build_action("terminal_panel::ToggleFocus", None).ok()?,
         ),
         "C" | "Co" | "Col" | "Coll" | "Colla" | "Collab" => (
             "Collab",
-            cx.build_action("collab_panel::ToggleFocus", None).unwrap(),
+            cx.build_action("collab_panel::ToggleFocus", None).ok()?,
         ),
         "Ch" | "Cha" | "Chat" => (
             "Chat",
-            cx.build_action("chat_panel::ToggleFocus", None).unwrap(),
+            cx.build_action("chat_panel::ToggleFocus", None).ok()?,
         ),
         "No" | "Not" | "Noti" | "Notif" | "Notifi" | "Notific" | "Notifica" | "Notificat"
         | "Notificati" | "Notificatio" | "Notification" => (
             "Notifications",
             cx.build_action("notification_panel::ToggleFocus", None)
-                .unwrap(),
-        ),
-        "A" | "AI" | "Ai" => (
-            "AI",
-            cx.build_action("assistant::ToggleFocus", None).unwrap(),
+                .ok()?,
         ),
+        "A" | "AI" | "Ai" => ("AI", cx.build_action("assistant::ToggleFocus", None).ok()?),
 
         // goto (other ranges handled under _ => )
         "$" => ("$", EndOfDocument.boxed_clone()),
@@ -330,7 +974,55 @@ pub fn command_interceptor(mut query: &str, cx: &AppContext) -> Option<CommandIn
                     }
                     .boxed_clone(),
                 )
-            } else if query.starts_with('%') {
+            } else if let Some(quit_with_error) = parse_quit_with_error(query) {
+                ("cquit", quit_with_error.boxed_clone())
+            } else if let Some(edit_file) = parse_edit_file(query) {
+                ("edit", edit_file.boxed_clone())
+            } else if let Some((name, action)) = parse_buffer_command(query) {
+                (name, action)
+            } else if let Some(normal) = parse_normal(query) {
+                ("normal", normal.boxed_clone())
+            } else if let Some(delete_marks) = parse_delete_marks(query) {
+                ("delmarks", delete_marks.boxed_clone())
+            } else if let Some(show_registers) = parse_show_registers(query) {
+                ("registers", show_registers.boxed_clone())
+            } else if let Some(help) = parse_help(query) {
+                ("help", help.boxed_clone())
+            } else if let Some(make) = parse_make(query) {
+                ("make", make.boxed_clone())
+            } else if let Some(quickfix_do) = parse_quickfix_do(query) {
+                (quickfix_do.name(), quickfix_do.boxed_clone())
+            } else if let Some(write_command) = parse_write_command(query) {
+                ("write !", write_command.boxed_clone())
+            } else if let Some(write_as) = parse_write_as(query) {
+                ("write", write_as.boxed_clone())
+            } else if let Some(file_name) = parse_file_name(query) {
+                ("file", file_name.boxed_clone())
+            } else if let Some(view_file) = parse_view_file(query) {
+                ("view", view_file.boxed_clone())
+            } else if let Some(set_modifiable) = parse_set_modifiable(query) {
+                ("set", set_modifiable.boxed_clone())
+            } else if let Some(rename) = parse_rename(query) {
+                ("Rename", rename.boxed_clone())
+            } else if let Some(put) = parse_put(query) {
+                ("put", put.boxed_clone())
+            } else if let Some(read) = parse_read(query) {
+                ("read", read.boxed_clone())
+            } else if let Some(filter) = parse_filter(query) {
+                ("!", filter.boxed_clone())
+            } else if let Some(location_tag) = parse_location_tag(query) {
+                ("ltag", location_tag.boxed_clone())
+            } else if let Some(tag) = parse_tag(query) {
+                ("tag", tag.boxed_clone())
+            } else if let Some(tag) = parse_tag_select(query) {
+                ("tselect", tag.boxed_clone())
+            } else if let Some(workspace_symbols) = parse_workspace_symbols(query) {
+                ("wsymbol", workspace_symbols.boxed_clone())
+            } else if let Some(sort) = parse_sort(query) {
+                ("sort", sort.boxed_clone())
+            } else if let Some(on_matching_lines) = parse_on_matching_lines(query) {
+                (query, on_matching_lines.boxed_clone())
+            } else if query.strip_prefix('%').is_some_and(is_substitute) || is_substitute(query) {
                 (
                     query,
                     ReplaceCommand {
@@ -340,22 +1032,203 @@ pub fn command_interceptor(mut query: &str, cx: &AppContext) -> Option<CommandIn
                 )
             } else if let Ok(line) = query.parse::<u32>() {
                 (query, GoToLine { line }.boxed_clone())
+            } else if let Some((range, command)) = parse_range(query) {
+                (
+                    query,
+                    WithRange {
+                        range,
+                        command: command.trim_start().to_string(),
+                    }
+                    .boxed_clone(),
+                )
             } else {
                 return None;
             }
         }
     };
 
-    let string = ":".to_owned() + name;
-    let positions = generate_positions(&string, query);
+    Some((name, action))
+}
 
-    Some(CommandInterceptResult {
-        action,
-        string,
-        positions,
+/// Splits the range off the front of an ex command.
+fn parse_range(query: &str) -> Option<(CommandRange, &str)> {
+    if let Some(rest) = query.strip_prefix('%') {
+        let range = CommandRange {
+            start: LineAddress {
+                address: Address::Line(1),
+                offset: 0,
+            },
+            end: LineAddress {
+                address: Address::LastLine,
+                offset: 0,
+            },
+        };
+        return Some((range, rest));
+    }
+    let (start, rest) = parse_address(query)?;
+    let Some(rest) = rest.strip_prefix(',').or_else(|| rest.strip_prefix(';')) else {
+        return Some((CommandRange { start, end: start }, rest));
+    };
+    let (end, rest) = parse_address(rest)?;
+    Some((CommandRange { start, end }, rest))
+}
+
+/// Splits a line address off the front of a range, like `.`, `$`, `12`, `'a`, `w0`
+/// or `.+2`. On its own, an offset like `-3` is relative to the current line.
+fn parse_address(query: &str) -> Option<(LineAddress, &str)> {
+    let digits = |text: &str| {
+        text.find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(text.len())
+    };
+    let (address, mut rest) = match query.chars().next()? {
+        '.' => (Some(Address::CurrentLine), &query[1..]),
+        '$' => (Some(Address::LastLine), &query[1..]),
+        'w' if query[1..].starts_with('0') => (Some(Address::WindowTop), &query[2..]),
+        'w' if query[1..].starts_with('$') => (Some(Address::WindowBottom), &query[2..]),
+        '\'' => {
+            let name = query[1..].chars().next()?;
+            (Some(Address::Mark(name)), &query[1 + name.len_utf8()..])
+        }
+        c if c.is_ascii_digit() => {
+            let len = digits(query);
+            (
+                Some(Address::Line(query[..len].parse().ok()?)),
+                &query[len..],
+            )
+        }
+        _ => (None, query),
+    };
+
+    let mut offset = None;
+    while let Some(sign) = rest.chars().next().filter(|c| matches!(c, '+' | '-')) {
+        rest = &rest[1..];
+        let len = digits(rest);
+        let amount = if len == 0 {
+            1
+        } else {
+            rest[..len].parse::<i32>().ok()?
+        };
+        rest = &rest[len..];
+        let amount = if sign == '+' { amount } else { -amount };
+        offset = Some(offset.unwrap_or(0) + amount);
+    }
+    if address.is_none() && offset.is_none() {
+        return None;
+    }
+    let address = LineAddress {
+        address: address.unwrap_or(Address::CurrentLine),
+        offset: offset.unwrap_or(0),
+    };
+    Some((address, rest))
+}
+
+fn parse_normal(query: &str) -> Option<Normal> {
+    let (name, keys) = query.split_once(' ')?;
+    match name {
+        "norm" | "norma" | "normal" | "norm!" | "norma!" | "normal!" => Some(Normal {
+            keys: keys.trim_start().to_string(),
+        }),
+        _ => None,
+    }
+}
+
+// :g/pattern/command, :g!/pattern/command and :v/pattern/command
+// as with :s, \( and \) are flipped to ( and ) (and vice-versa) in the pattern.
+fn parse_on_matching_lines(query: &str) -> Option<OnMatchingLines> {
+    let name_len = query.find(|c: char| !c.is_ascii_alphabetic())?;
+    let (name, mut rest) = query.split_at(name_len);
+    let mut invert = match name {
+        "g" | "gl" | "glo" | "glob" | "globa" | "global" => false,
+        "v" | "vg" | "vgl" | "vglo" | "vglob" | "vgloba" | "vglobal" => true,
+        _ => return None,
+    };
+    if let Some(bang) = rest.strip_prefix('!') {
+        invert = true;
+        rest = bang;
+    }
+
+    let (pattern, command) = split_pattern(rest)?;
+    let command = command.trim_start();
+    if command.is_empty() {
+        return None;
+    }
+    Some(OnMatchingLines {
+        pattern,
+        invert,
+        command: command.to_string(),
     })
 }
 
+// :sor[t][!] [i] [r] [u] [/pattern/]
+fn parse_sort(query: &str) -> Option<Sort> {
+    let name_len = query
+        .find(|c: char| !c.is_ascii_alphabetic())
+        .unwrap_or(query.len());
+    let (name, mut rest) = query.split_at(name_len);
+    if name != "sor" && name != "sort" {
+        return None;
+    }
+    let reverse = if let Some(bang) = rest.strip_prefix('!') {
+        rest = bang;
+        true
+    } else {
+        false
+    };
+
+    let mut sort = Sort {
+        reverse,
+        ..Default::default()
+    };
+    while let Some(c) = rest.chars().next() {
+        match c {
+            'i' => sort.ignore_case = true,
+            'r' => sort.on_match = true,
+            'u' => sort.unique = true,
+            c if c.is_whitespace() => {}
+            _ if sort.pattern.is_none() => {
+                let (pattern, after) = split_pattern(rest)?;
+                sort.pattern = Some(pattern);
+                rest = after;
+                continue;
+            }
+            _ => return None,
+        }
+        rest = &rest[c.len_utf8()..];
+    }
+    Some(sort)
+}
+
+/// Splits `/pattern/rest` at the closing delimiter, which can be any punctuation.
+fn split_pattern(text: &str) -> Option<(String, &str)> {
+    let mut chars = text.char_indices();
+    let (_, delimiter) = chars.next()?;
+    if delimiter.is_alphanumeric() || delimiter.is_whitespace() || "\\\"|".contains(delimiter) {
+        return None;
+    }
+
+    let mut pattern = String::new();
+    let mut escaped = false;
+    for (ix, c) in chars {
+        if escaped {
+            escaped = false;
+            if c != '(' && c != ')' && c != delimiter {
+                pattern.push('\\')
+            }
+            pattern.push(c)
+        } else if c == '\\' {
+            escaped = true;
+        } else if c == delimiter {
+            return Some((pattern, &text[ix + c.len_utf8()..]));
+        } else {
+            if c == '(' || c == ')' {
+                pattern.push('\\')
+            }
+            pattern.push(c)
+        }
+    }
+    None
+}
+
 fn generate_positions(string: &str, query: &str) -> Vec<usize> {
     let mut positions = Vec::new();
     let mut chars = query.chars();
@@ -382,9 +1255,15 @@ fn generate_positions(string: &str, query: &str) -> Vec<usize> {
 mod test {
     use std::path::Path;
 
-    use crate::test::{NeovimBackedTestContext, VimTestContext};
+    use super::{parse_range, Address, CommandRange, LineAddress};
+    use crate::{
+        state::Mode,
+        test::{NeovimBackedTestContext, VimTestContext},
+        ScratchBuffers, VimSettings,
+    };
     use gpui::TestAppContext;
     use indoc::indoc;
+    use settings::SettingsStore;
 
     #[gpui::test]
     async fn test_command_basics(cx: &mut TestAppContext) {
@@ -451,6 +1330,383 @@ mod test {
             .await;
     }
 
+    #[gpui::test]
+    async fn test_command_replace_specials(cx: &mut TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+
+        cx.set_state("ˇhello world\nFOO Bar", Mode::Normal);
+        cx.simulate_keystrokes([":"]);
+        cx.simulate_input("%s/\\(\\w+\\) \\(\\w+\\)/\\u\\2 \\U\\1\\E!/");
+        cx.simulate_keystrokes(["enter"]);
+        cx.assert_state("World HELLO!\nˇBar FOO!", Mode::Normal);
+
+        cx.simulate_keystrokes([":"]);
+        cx.simulate_input("%s/\\w+/\\L&/");
+        cx.simulate_keystrokes(["enter"]);
+        cx.assert_state("world hello!\nˇbar foo!", Mode::Normal);
+
+        // ~ is the previous replacement
+        cx.simulate_keystrokes([":"]);
+        cx.simulate_input("%s/o/0/");
+        cx.simulate_keystrokes(["enter"]);
+        cx.simulate_keystrokes([":"]);
+        cx.simulate_input("%s/!/~~/");
+        cx.simulate_keystrokes(["enter"]);
+        cx.assert_state("w0rld hell000\nˇbar f0000", Mode::Normal);
+    }
+
+    #[gpui::test]
+    async fn test_command_replace_range(cx: &mut TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+
+        // without a range, only the current line is changed
+        cx.set_state("ˇaa aa\naa aa\naa aa", Mode::Normal);
+        cx.simulate_keystrokes([":"]);
+        cx.simulate_input("s/a/b/");
+        cx.simulate_keystrokes(["enter"]);
+        cx.assert_state("ˇbb bb\naa aa\naa aa", Mode::Normal);
+
+        // in visual mode, the selected lines are
+        cx.set_state("ˇaa aa\naa aa\naa aa", Mode::Normal);
+        cx.simulate_keystrokes(["j", "shift-v", "j", ":"]);
+        cx.simulate_input("'<,'>s/a/b/");
+        cx.simulate_keystrokes(["enter"]);
+        cx.assert_state("aa aa\nbb bb\nˇbb bb", Mode::Normal);
+
+        // with multiple cursors, the lines of each of them are
+        cx.set_state("ˇaa aa\naa aa\naa ˇaa ˇaa", Mode::Normal);
+        cx.simulate_keystrokes([":"]);
+        cx.simulate_input("s/a/b/");
+        cx.simulate_keystrokes(["enter"]);
+        cx.assert_state("bb bb\naa aa\nˇbb bb bb", Mode::Normal);
+
+        // and in visual block mode, only the matches inside the block
+        cx.set_state("ˇaa aa\naa aa\naa aa", Mode::Normal);
+        cx.simulate_keystrokes(["l", "ctrl-v", "j", "l", "l", ":"]);
+        cx.simulate_input("s/a/b/");
+        cx.simulate_keystrokes(["enter"]);
+        cx.assert_state("ab ba\nˇab ba\naa aa", Mode::Normal);
+        cx.simulate_keystrokes(["ctrl-v", "j", "$", ":"]);
+        cx.simulate_input("s/a+$/c/");
+        cx.simulate_keystrokes(["enter"]);
+        cx.assert_state("ab ba\nab bc\nˇaa c", Mode::Normal);
+    }
+
+    #[gpui::test]
+    async fn test_command_replace_expression(cx: &mut TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+
+        cx.set_state("ˇone\ntwo\nthree", Mode::Normal);
+        cx.simulate_keystrokes([":"]);
+        cx.simulate_input("%s/^\\(t\\)\\(.*\\)/\\=line('.') . '. ' . submatch(2).submatch(1)/");
+        cx.simulate_keystrokes(["enter"]);
+        cx.assert_state("one\n2. wot\nˇ3. hreet", Mode::Normal);
+
+        // invalid expressions don't change the buffer
+        cx.simulate_keystrokes([":"]);
+        cx.simulate_input("%s/one/\\=col('.')/");
+        cx.simulate_keystrokes(["enter"]);
+        cx.assert_state("one\n2. wot\nˇ3. hreet", Mode::Normal);
+    }
+
+    #[test]
+    fn test_parse_range() {
+        let address = |address, offset| LineAddress { address, offset };
+        assert_eq!(
+            parse_range("1,10d"),
+            Some((
+                CommandRange {
+                    start: address(Address::Line(1), 0),
+                    end: address(Address::Line(10), 0),
+                },
+                "d"
+            ))
+        );
+        assert_eq!(
+            parse_range(".,$-1 sort"),
+            Some((
+                CommandRange {
+                    start: address(Address::CurrentLine, 0),
+                    end: address(Address::LastLine, -1),
+                },
+                " sort"
+            ))
+        );
+        assert_eq!(
+            parse_range("'a,+2j"),
+            Some((
+                CommandRange {
+                    start: address(Address::Mark('a'), 0),
+                    end: address(Address::CurrentLine, 2),
+                },
+                "j"
+            ))
+        );
+        assert_eq!(
+            parse_range("%s/a/b/").map(|(range, rest)| (range.end, rest)),
+            Some((address(Address::LastLine, 0), "s/a/b/"))
+        );
+        assert_eq!(
+            parse_range("w0,w$-1d"),
+            Some((
+                CommandRange {
+                    start: address(Address::WindowTop, 0),
+                    end: address(Address::WindowBottom, -1),
+                },
+                "d"
+            ))
+        );
+        assert_eq!(parse_range("sort"), None);
+    }
+
+    #[gpui::test]
+    async fn test_command_ranges(cx: &mut TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+
+        cx.set_state("ˇa\nb\nc\nd\ne", Mode::Normal);
+        cx.simulate_keystrokes([":"]);
+        cx.simulate_input("2,3d");
+        cx.simulate_keystrokes(["enter"]);
+        cx.assert_state("a\nˇd\ne", Mode::Normal);
+
+        cx.simulate_keystrokes([":"]);
+        cx.simulate_input("%j");
+        cx.simulate_keystrokes(["enter"]);
+        cx.assert_state("a dˇ e", Mode::Normal);
+
+        cx.set_state("aa\nˇaa\naa\naa", Mode::Normal);
+        cx.simulate_keystrokes([":"]);
+        cx.simulate_input(".,+1s/a/b/");
+        cx.simulate_keystrokes(["enter"]);
+        cx.assert_state("aa\nba\nˇba\naa", Mode::Normal);
+
+        // a range on its own goes to its last line
+        cx.simulate_keystrokes([":"]);
+        cx.simulate_input("$-2");
+        cx.simulate_keystrokes(["enter"]);
+        cx.assert_state("aa\nˇba\nba\naa", Mode::Normal);
+
+        cx.simulate_keystrokes([":"]);
+        cx.simulate_input("1,2frob");
+        cx.simulate_keystrokes(["enter"]);
+        assert_eq!(
+            cx.message().as_deref(),
+            Some("E492: Not an editor command: frob")
+        );
+        cx.simulate_keystrokes([":"]);
+        cx.simulate_input("1,2w");
+        cx.simulate_keystrokes(["enter"]);
+        assert_eq!(cx.message().as_deref(), Some("E481: No range allowed"));
+        cx.simulate_keystrokes([":"]);
+        cx.simulate_input("1,9d");
+        cx.simulate_keystrokes(["enter"]);
+        assert_eq!(cx.message().as_deref(), Some("E16: Invalid range"));
+        cx.assert_state("aa\nˇba\nba\naa", Mode::Normal);
+    }
+
+    #[gpui::test]
+    async fn test_command_ranges_with_folds(cx: &mut TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+
+        // an address in a closed fold takes in the whole fold
+        cx.set_state("a\nˇb\nc\nd\ne", Mode::Normal);
+        cx.simulate_keystrokes(["z", "f", "j", ":", ".", "d", "enter"]);
+        cx.assert_state("a\nˇd\ne", Mode::Normal);
+
+        // but offsets count the lines of the buffer, not the lines that are shown
+        cx.set_state("ˇa\nb\nc\nd\ne", Mode::Normal);
+        cx.simulate_keystrokes(["j", "z", "f", "j", "g", "g", ":"]);
+        cx.simulate_input(".+2d");
+        cx.simulate_keystrokes(["enter"]);
+        cx.assert_state("a\nˇd\ne", Mode::Normal);
+
+        // w$ and w0 are the last and first lines in view
+        cx.set_state("ˇa\nb\nc\nd\ne", Mode::Normal);
+        cx.simulate_keystrokes([":"]);
+        cx.simulate_input("w$-1,w$d");
+        cx.simulate_keystrokes(["enter"]);
+        cx.assert_state("a\nb\nˇc", Mode::Normal);
+        cx.simulate_keystrokes(["ctrl-e", ":"]);
+        cx.simulate_input("w0d");
+        cx.simulate_keystrokes(["enter"]);
+        cx.assert_state("a\nˇc", Mode::Normal);
+    }
+
+    #[gpui::test]
+    async fn test_command_delete(cx: &mut TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+
+        cx.set_state("ˇa\nb\nc", Mode::Normal);
+        cx.simulate_keystrokes([":", "d", "enter"]);
+        cx.assert_state("ˇb\nc", Mode::Normal);
+        assert_eq!(cx.read_register('1').as_deref(), Some("a\n"));
+
+        cx.simulate_keystrokes(["v", ":", "d", "enter"]);
+        cx.assert_state("ˇc", Mode::Normal);
+        assert_eq!(cx.read_register('1').as_deref(), Some("b\n"));
+        assert_eq!(cx.read_register('2').as_deref(), Some("a\n"));
+    }
+
+    #[gpui::test]
+    async fn test_command_normal(cx: &mut TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+
+        cx.set_state("ˇone two\nthree", Mode::Normal);
+        cx.simulate_keystrokes([":"]);
+        cx.simulate_input("normal Ahi");
+        cx.simulate_keystrokes(["enter"]);
+        cx.assert_state("one twohˇi\nthree", Mode::Normal);
+    }
+
+    #[gpui::test]
+    async fn test_command_global(cx: &mut TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+
+        // the macro adds a line below, which shifts the later matches down
+        cx.set_state("foo 1\nˇbar\nfoo 2\nfoo 3", Mode::Normal);
+        cx.simulate_keystrokes(["q", "q", "o", "x", "escape", "q"]);
+        cx.assert_state("foo 1\nbar\nˇx\nfoo 2\nfoo 3", Mode::Normal);
+
+        cx.simulate_keystrokes([":"]);
+        cx.simulate_input("g/foo/normal @q");
+        cx.simulate_keystrokes(["enter"]);
+        cx.assert_state("foo 1\nx\nbar\nx\nfoo 2\nx\nfoo 3\nˇx", Mode::Normal);
+
+        // it's undone in one step
+        cx.simulate_keystrokes(["u"]);
+        assert_eq!(cx.buffer_text(), "foo 1\nbar\nx\nfoo 2\nfoo 3");
+    }
+
+    #[gpui::test]
+    async fn test_command_global_deleted_lines(cx: &mut TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+
+        // the second foo is deleted before its turn, so it's skipped
+        cx.set_state("ˇfoo\nfoo\nbar\nfoo\nbaz", Mode::Normal);
+        cx.simulate_keystrokes([":"]);
+        cx.simulate_input("g/foo/normal jdd");
+        cx.simulate_keystrokes(["enter"]);
+        assert_eq!(cx.buffer_text(), "foo\nbar\nfoo");
+
+        cx.set_state("ˇfoo\nbar\nfoo\nbaz", Mode::Normal);
+        cx.simulate_keystrokes([":"]);
+        cx.simulate_input("v/foo/d");
+        cx.simulate_keystrokes(["enter"]);
+        assert_eq!(cx.buffer_text(), "foo\nfoo");
+    }
+
+    #[gpui::test]
+    async fn test_command_global_aborts(cx: &mut TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+
+        // f fails on the second line, so the third line is left alone
+        cx.set_state("ˇa,b\nno comma\nc,d", Mode::Normal);
+        cx.simulate_keystrokes([":"]);
+        cx.simulate_input("g/./normal f,r;");
+        cx.simulate_keystrokes(["enter"]);
+        assert_eq!(cx.buffer_text(), "a;b\nno comma\nc,d");
+
+        cx.simulate_keystrokes(["u"]);
+        assert_eq!(cx.buffer_text(), "a,b\nno comma\nc,d");
+    }
+
+    #[gpui::test]
+    async fn test_command_undo(cx: &mut TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+
+        // a command is undone in one step, which puts the cursor back
+        cx.set_state("ˇa\nb\na\nb", Mode::Normal);
+        cx.simulate_keystrokes([":"]);
+        cx.simulate_input("%s/a/c/");
+        cx.simulate_keystrokes(["enter"]);
+        cx.assert_state("c\nb\nˇc\nb", Mode::Normal);
+        cx.simulate_keystrokes(["u"]);
+        cx.assert_state("ˇa\nb\na\nb", Mode::Normal);
+
+        // even when it's run on a range, by selecting the lines first
+        cx.set_state("ˇd\nc\nb\na", Mode::Normal);
+        cx.simulate_keystrokes([":"]);
+        cx.simulate_input("2,3sort");
+        cx.simulate_keystrokes(["enter"]);
+        assert_eq!(cx.buffer_text(), "d\nb\nc\na");
+        cx.simulate_keystrokes(["u"]);
+        cx.assert_state("ˇd\nc\nb\na", Mode::Normal);
+    }
+
+    #[gpui::test]
+    async fn test_command_sort(cx: &mut TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+
+        cx.set_state("c\nB\nˇa", Mode::Normal);
+        cx.simulate_keystrokes([":"]);
+        cx.simulate_input("sort");
+        cx.simulate_keystrokes(["enter"]);
+        cx.assert_state("ˇB\na\nc", Mode::Normal);
+
+        cx.simulate_keystrokes([":"]);
+        cx.simulate_input("sort! i");
+        cx.simulate_keystrokes(["enter"]);
+        cx.assert_state("ˇc\nB\na", Mode::Normal);
+
+        // only the selected lines are sorted in visual mode
+        cx.set_state("z\nˇc\nb\na\ny", Mode::Normal);
+        cx.simulate_keystrokes(["shift-v", "j", "j", ":"]);
+        cx.simulate_input("sort");
+        cx.simulate_keystrokes(["enter"]);
+        cx.assert_state("z\nˇa\nb\nc\ny", Mode::Normal);
+
+        // u keeps the first of the lines that sort the same
+        cx.set_state("ˇb\nA\na\nb", Mode::Normal);
+        cx.simulate_keystrokes([":"]);
+        cx.simulate_input("sort ui");
+        cx.simulate_keystrokes(["enter"]);
+        cx.assert_state("ˇA\nb", Mode::Normal);
+    }
+
+    #[gpui::test]
+    async fn test_command_reverse(cx: &mut TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+
+        cx.set_state("a\nˇb\nc", Mode::Normal);
+        cx.simulate_keystrokes([":"]);
+        cx.simulate_input("reverse");
+        cx.simulate_keystrokes(["enter"]);
+        cx.assert_state("ˇc\nb\na", Mode::Normal);
+
+        // from visual mode, it only reverses the selected lines
+        cx.set_state("z\nˇa\nb\nc\ny", Mode::Normal);
+        cx.simulate_keystrokes(["shift-v", "j", "j", ":"]);
+        cx.simulate_input("rev");
+        cx.simulate_keystrokes(["enter"]);
+        cx.assert_state("z\nˇc\nb\na\ny", Mode::Normal);
+        cx.simulate_keystrokes(["u"]);
+        assert_eq!(cx.buffer_text(), "z\na\nb\nc\ny");
+    }
+
+    #[gpui::test]
+    async fn test_command_sort_pattern(cx: &mut TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+
+        // by what follows the match, keeping lines with the same key in order, and
+        // the lines without a match first
+        cx.set_state("ˇb,2\na,1\nheader\nc,1\nd,2", Mode::Normal);
+        cx.simulate_keystrokes([":"]);
+        cx.simulate_input("sort /[^,]*,/");
+        cx.simulate_keystrokes(["enter"]);
+        assert_eq!(cx.buffer_text(), "header\na,1\nc,1\nb,2\nd,2");
+
+        // by the match itself
+        cx.set_state("ˇuse b::y;\nuse a::z;\nuse c::x;", Mode::Normal);
+        cx.simulate_keystrokes([":"]);
+        cx.simulate_input("sort /[a-z];/ r");
+        cx.simulate_keystrokes(["enter"]);
+        assert_eq!(cx.buffer_text(), "use c::x;\nuse b::y;\nuse a::z;");
+
+        // it's undone in one step
+        cx.simulate_keystrokes(["u"]);
+        assert_eq!(cx.buffer_text(), "use b::y;\nuse a::z;\nuse c::x;");
+    }
+
     #[gpui::test]
     async fn test_command_search(cx: &mut TestAppContext) {
         let mut cx = NeovimBackedTestContext::new(cx).await;
@@ -506,6 +1762,36 @@ mod test {
         assert_eq!(fs.load(&path).await.unwrap(), "@@\n");
     }
 
+    // :x and ZZ save like zed does, trimming trailing whitespace and adding a final
+    // newline, before closing the file
+    #[gpui::test]
+    async fn test_command_exit_formats(cx: &mut TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+        let path = Path::new("/root/dir/file.rs");
+        let fs = cx.workspace(|workspace, cx| workspace.project().read(cx).fs().clone());
+
+        cx.set_state("ˇone  \ntwo\t", Mode::Normal);
+        cx.simulate_keystrokes([":", "x", "enter"]);
+        cx.run_until_parked();
+        assert!(!cx.has_pending_prompt());
+        assert_eq!(fs.load(&path).await.unwrap(), "one\ntwo\n");
+        cx.workspace(|workspace, cx| assert_eq!(workspace.items(cx).count(), 0));
+    }
+
+    #[gpui::test]
+    async fn test_zz_formats(cx: &mut TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+        let path = Path::new("/root/dir/file.rs");
+        let fs = cx.workspace(|workspace, cx| workspace.project().read(cx).fs().clone());
+
+        cx.set_state("ˇone\t\ntwo  ", Mode::Normal);
+        cx.simulate_keystrokes(["shift-z", "shift-z"]);
+        cx.run_until_parked();
+        assert!(!cx.has_pending_prompt());
+        assert_eq!(fs.load(&path).await.unwrap(), "one\ntwo\n");
+        cx.workspace(|workspace, cx| assert_eq!(workspace.items(cx).count(), 0));
+    }
+
     #[gpui::test]
     async fn test_command_quit(cx: &mut TestAppContext) {
         let mut cx = VimTestContext::new(cx, true).await;
@@ -519,4 +1805,30 @@ mod test {
         cx.simulate_keystrokes([":", "q", "a", "enter"]);
         cx.workspace(|workspace, cx| assert_eq!(workspace.items(cx).count(), 0));
     }
+
+    #[gpui::test]
+    async fn test_scratch_buffers(cx: &mut TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+
+        // by default a new buffer with changes asks to be saved
+        cx.simulate_keystrokes([":", "e", "n", "e", "w", "enter", "i", "a", "escape"]);
+        cx.workspace(|workspace, cx| {
+            assert_eq!(workspace.items(cx).count(), 2);
+            assert!(workspace.active_item(cx).unwrap().is_dirty(cx));
+        });
+
+        cx.update_global(|store: &mut SettingsStore, cx| {
+            store.update_user_settings::<VimSettings>(cx, |s| {
+                s.scratch_buffers = Some(ScratchBuffers::Discard)
+            });
+        });
+        cx.simulate_keystrokes([":", "v", "n", "e", "w", "enter", "i", "b", "escape"]);
+        cx.workspace(|workspace, cx| {
+            assert!(!workspace.active_item(cx).unwrap().is_dirty(cx));
+        });
+        cx.simulate_keystrokes([":", "q", "enter"]);
+        cx.run_until_parked();
+        assert!(!cx.has_pending_prompt());
+        cx.workspace(|workspace, cx| assert_eq!(workspace.items(cx).count(), 2));
+    }
 }
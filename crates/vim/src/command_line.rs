@@ -0,0 +1,144 @@
+use editor::{Editor, EditorMode};
+use gpui::{impl_actions, AppContext, View, ViewContext, WindowContext};
+use language::CharKind;
+use serde_derive::Deserialize;
+
+use crate::{
+    utils::{char_kind, coerce_punctuation, KeywordChars},
+    Vim,
+};
+
+/// Inserts the word under the cursor in the active editor into the `:` or `/`
+/// prompt (`ctrl-r ctrl-w`, or `ctrl-r ctrl-a` to ignore punctuation).
+#[derive(Clone, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct InsertWordUnderCursor {
+    #[serde(default)]
+    ignore_punctuation: bool,
+}
+
+impl_actions!(vim, [InsertWordUnderCursor]);
+
+pub(crate) fn init(cx: &mut AppContext) {
+    cx.observe_new_views(|editor: &mut Editor, cx: &mut ViewContext<Editor>| {
+        if editor.mode() != EditorMode::SingleLine {
+            return;
+        }
+        let handle = cx.view().downgrade();
+        editor.register_action(move |action: &InsertWordUnderCursor, cx| {
+            if let Some(editor) = handle.upgrade() {
+                insert_word_under_cursor(editor, action, cx)
+            }
+        });
+    })
+    .detach();
+}
+
+fn insert_word_under_cursor(
+    prompt: View<Editor>,
+    action: &InsertWordUnderCursor,
+    cx: &mut WindowContext,
+) {
+    let word = Vim::update(cx, |vim, cx| {
+        vim.update_active_editor(cx, |_, editor, cx| {
+            word_under_cursor(editor, action.ignore_punctuation, cx)
+        })
+    })
+    .flatten();
+
+    if let Some(word) = word {
+        prompt.update(cx, |prompt, cx| prompt.insert(&word, cx));
+    }
+}
+
+/// Like vim, this is the word under the cursor, or the next word on the line
+/// if the cursor is on whitespace.
+pub(crate) fn word_under_cursor(
+    editor: &Editor,
+    ignore_punctuation: bool,
+    cx: &ViewContext<Editor>,
+) -> Option<String> {
+    let snapshot = editor.buffer().read(cx).snapshot(cx);
+    let offset = editor.selections.newest::<usize>(cx).head();
+    let scope = snapshot.language_scope_at(offset);
+    let keyword_chars = KeywordChars::new(cx);
+    let kind = |c| coerce_punctuation(char_kind(&scope, c, &keyword_chars), ignore_punctuation);
+
+    let mut start = offset;
+    for c in snapshot.chars_at(offset) {
+        if c == '\n' {
+            return None;
+        }
+        if kind(c) != CharKind::Whitespace {
+            break;
+        }
+        start += c.len_utf8();
+    }
+    let word_kind = kind(snapshot.chars_at(start).next()?);
+    if word_kind == CharKind::Whitespace {
+        return None;
+    }
+
+    for c in snapshot.reversed_chars_at(start) {
+        if kind(c) != word_kind {
+            break;
+        }
+        start -= c.len_utf8();
+    }
+    let mut end = start;
+    for c in snapshot.chars_at(start) {
+        if kind(c) != word_kind {
+            break;
+        }
+        end += c.len_utf8();
+    }
+
+    Some(snapshot.text_for_range(start..end).collect())
+}
+
+#[cfg(test)]
+mod test {
+    use search::BufferSearchBar;
+
+    use crate::{state::Mode, test::VimTestContext};
+
+    #[gpui::test]
+    async fn test_insert_word_under_cursor(cx: &mut gpui::TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+
+        cx.set_state("hello woˇrld-wide", Mode::Normal);
+        cx.simulate_keystrokes(["/", "ctrl-r", "ctrl-w"]);
+
+        let search_bar = cx.workspace(|workspace, cx| {
+            workspace
+                .active_pane()
+                .read(cx)
+                .toolbar()
+                .read(cx)
+                .item_of_type::<BufferSearchBar>()
+                .expect("Buffer search bar should be deployed")
+        });
+        cx.update_view(search_bar.clone(), |bar, cx| {
+            assert_eq!(bar.query(cx), "world");
+        });
+
+        cx.simulate_keystrokes(["ctrl-u", "ctrl-r", "ctrl-a"]);
+        cx.update_view(search_bar.clone(), |bar, cx| {
+            assert_eq!(bar.query(cx), "world-wide");
+        });
+
+        cx.simulate_keystrokes(["ctrl-w"]);
+        cx.update_view(search_bar, |bar, cx| {
+            assert_eq!(bar.query(cx), "world-");
+        });
+    }
+
+    #[gpui::test]
+    async fn test_insert_word_after_cursor(cx: &mut gpui::TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+
+        cx.set_state("helloˇ  world\nagain", Mode::Normal);
+        cx.simulate_keystrokes(["/", "ctrl-r", "ctrl-w", "enter"]);
+        cx.assert_state("hello  ˇworld\nagain", Mode::Normal);
+    }
+}
@@ -0,0 +1,192 @@
+use editor::Editor;
+use gpui::{actions, impl_actions, Action, ViewContext, VisualContext};
+use language::Point;
+use serde_derive::Deserialize;
+use workspace::{searchable::Direction, SaveIntent, SplitDirection, Workspace};
+
+use crate::{
+    command::command_interceptor, normal::search::FindCommand, state::CommandLineKind, Vim,
+};
+
+/// Opens the command-line window (`q:`, or `q/` and `q?` for searches),
+/// an editable buffer of the command or search history. Without `backwards`, a
+/// search goes the way of the one being typed, as with `ctrl-f` in the search bar.
+#[derive(Clone, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct OpenCommandLineWindow {
+    #[serde(default)]
+    search: bool,
+    #[serde(default)]
+    backwards: Option<bool>,
+}
+
+impl_actions!(vim, [OpenCommandLineWindow]);
+
+actions!(vim, [ExecuteCommandLine]);
+
+pub(crate) fn register(workspace: &mut Workspace, _: &mut ViewContext<Workspace>) {
+    workspace.register_action(open_command_line_window);
+    workspace.register_action(execute_command_line);
+}
+
+fn open_command_line_window(
+    workspace: &mut Workspace,
+    action: &OpenCommandLineWindow,
+    cx: &mut ViewContext<Workspace>,
+) {
+    let kind = if action.search {
+        CommandLineKind::Search {
+            backwards: action.backwards.unwrap_or_else(|| {
+                Vim::read(cx).workspace_state.search.direction == Direction::Prev
+            }),
+        }
+    } else {
+        CommandLineKind::Command
+    };
+    // when opened from the command palette, replace it.
+    workspace.hide_modal(cx);
+
+    let history = Vim::update(cx, |vim, cx| {
+        vim.clear_operator(cx);
+        match kind {
+            CommandLineKind::Command => vim.workspace_state.command_history.clone(),
+            CommandLineKind::Search { .. } => vim.workspace_state.search_history.clone(),
+        }
+    });
+
+    // like vim, the history is followed by an empty line for a new command.
+    let mut text = history.join("\n");
+    if !text.is_empty() {
+        text.push('\n');
+    }
+
+    let project = workspace.project().clone();
+    let buffer = match project.update(cx, |project, cx| project.create_buffer(&text, None, cx)) {
+        Ok(buffer) => buffer,
+        Err(err) => {
+            log::error!("failed to open the command-line window: {err}");
+            return;
+        }
+    };
+    let editor = cx.new_view(|cx| {
+        let mut editor = Editor::for_buffer(buffer, Some(project), cx);
+        editor.change_selections(None, cx, |s| s.select_ranges([text.len()..text.len()]));
+        editor
+    });
+    Vim::update(cx, |vim, _| {
+        vim.editor_states
+            .entry(editor.entity_id())
+            .or_default()
+            .command_line_window = Some(kind);
+    });
+    workspace.split_item(SplitDirection::Down, Box::new(editor), cx);
+}
+
+fn execute_command_line(
+    workspace: &mut Workspace,
+    _: &ExecuteCommandLine,
+    cx: &mut ViewContext<Workspace>,
+) {
+    let Some(editor) = workspace.active_item_as::<Editor>(cx) else {
+        return;
+    };
+    let Some(kind) = Vim::read(cx)
+        .editor_states
+        .get(&editor.entity_id())
+        .and_then(|state| state.command_line_window)
+    else {
+        return;
+    };
+
+    let line = editor.update(cx, |editor, cx| {
+        let snapshot = editor.buffer().read(cx).snapshot(cx);
+        let row = editor.selections.newest::<Point>(cx).head().row;
+        snapshot
+            .text_for_range(Point::new(row, 0)..Point::new(row, snapshot.line_len(row)))
+            .collect::<String>()
+    });
+    let action: Option<Box<dyn Action>> = match kind {
+        _ if line.trim().is_empty() => None,
+        CommandLineKind::Command => command_interceptor(&line, cx).map(|result| result.action),
+        CommandLineKind::Search { backwards } => Some(
+            FindCommand {
+                query: line,
+                backwards,
+            }
+            .boxed_clone(),
+        ),
+    };
+
+    let close = workspace.active_pane().update(cx, |pane, cx| {
+        pane.close_item_by_id(editor.entity_id(), SaveIntent::Skip, cx)
+    });
+    cx.spawn(|workspace, mut cx| async move {
+        close.await?;
+        if let Some(action) = action {
+            workspace.update(&mut cx, |_, cx| cx.dispatch_action(action))?;
+        }
+        anyhow::Ok(())
+    })
+    .detach_and_log_err(cx);
+}
+
+#[cfg(test)]
+mod test {
+    use editor::Editor;
+    use indoc::indoc;
+
+    use crate::{state::Mode, test::VimTestContext};
+
+    fn command_line_window_text(cx: &mut VimTestContext) -> String {
+        cx.workspace(|workspace, cx| {
+            workspace
+                .active_item_as::<Editor>(cx)
+                .expect("command-line window should be open")
+                .read(cx)
+                .text(cx)
+        })
+    }
+
+    #[gpui::test]
+    async fn test_command_line_window(cx: &mut gpui::TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+
+        cx.set_state(indoc! {"ˇa\nb\nc\nd"}, Mode::Normal);
+        cx.simulate_keystrokes([":", "j", "enter"]);
+        cx.simulate_keystrokes([":", "3", "enter"]);
+        cx.assert_state(indoc! {"a b\nc\nˇd"}, Mode::Normal);
+
+        cx.simulate_keystrokes(["q", ":"]);
+        assert_eq!(command_line_window_text(&mut cx), "j\n3\n");
+
+        // edit the history entry, and run it
+        cx.simulate_keystrokes(["k", "k", "c", "c", "1", "escape", "enter"]);
+        cx.run_until_parked();
+        cx.workspace(|workspace, _| assert_eq!(workspace.panes().len(), 1));
+        cx.assert_state(indoc! {"ˇa b\nc\nd"}, Mode::Normal);
+
+        cx.simulate_keystrokes(["q", ":"]);
+        assert_eq!(command_line_window_text(&mut cx), "j\n3\n1\n");
+    }
+
+    #[gpui::test]
+    async fn test_search_line_window(cx: &mut gpui::TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+
+        cx.set_state(indoc! {"ˇone\ntwo\none\ntwo"}, Mode::Normal);
+        cx.simulate_keystrokes(["/", "t", "w", "o", "enter"]);
+        cx.assert_state(indoc! {"one\nˇtwo\none\ntwo"}, Mode::Normal);
+
+        cx.simulate_keystrokes(["q", "/"]);
+        assert_eq!(command_line_window_text(&mut cx), "two\n");
+        cx.simulate_keystrokes(["k", "enter"]);
+        cx.run_until_parked();
+        cx.assert_state(indoc! {"one\ntwo\none\nˇtwo"}, Mode::Normal);
+
+        // ctrl-f in the search bar keeps the direction of a ? search
+        cx.simulate_keystrokes(["?", "ctrl-f"]);
+        cx.simulate_keystrokes(["k", "enter"]);
+        cx.run_until_parked();
+        cx.assert_state(indoc! {"one\nˇtwo\none\ntwo"}, Mode::Normal);
+    }
+}
@@ -0,0 +1,496 @@
+use std::{
+    ops::Range,
+    path::{Path, PathBuf},
+    rc::Rc,
+    sync::Arc,
+};
+
+use anyhow::Result;
+use collections::HashSet;
+use editor::{CompletionProvider, Editor};
+use futures::StreamExt;
+use gpui::{actions, impl_actions, AppContext, Model, Task, ViewContext, WindowContext};
+use language::{
+    Anchor, Buffer, CodeLabel, Completion, Language, LanguageServerId, Point, ToOffset, ToPoint,
+};
+use parking_lot::RwLock;
+use project::{Fs, Project};
+use serde_derive::Deserialize;
+use settings::Settings;
+use workspace::Workspace;
+
+use crate::{utils::worktree_root, Vim, VimSettings};
+
+/// Completes the word before the cursor from the words in open buffers
+/// (`ctrl-n`, or `ctrl-p` to prefer the words before the cursor).
+#[derive(Clone, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+struct CompleteKeyword {
+    #[serde(default)]
+    backwards: bool,
+}
+
+impl_actions!(vim, [CompleteKeyword]);
+
+actions!(vim, [CompleteFilePath, CompleteLine]);
+
+pub(crate) fn register(workspace: &mut Workspace, _: &mut ViewContext<Workspace>) {
+    workspace.register_action(|workspace: &mut Workspace, _: &CompleteFilePath, cx| {
+        let project = workspace.project().clone();
+        show_completions_from(Rc::new(FilePathCompletions { project }), cx)
+    });
+    workspace.register_action(|workspace: &mut Workspace, _: &CompleteLine, cx| {
+        let project = workspace.project().clone();
+        show_completions_from(Rc::new(LineCompletions { project }), cx)
+    });
+    workspace.register_action(|workspace: &mut Workspace, action: &CompleteKeyword, cx| {
+        let project = workspace.project().clone();
+        let backwards = action.backwards;
+        show_completions_from(Rc::new(KeywordCompletions { project, backwards }), cx)
+    });
+}
+
+fn show_completions_from(provider: Rc<dyn CompletionProvider>, cx: &mut WindowContext) {
+    Vim::update(cx, |vim, cx| {
+        vim.update_active_editor(cx, |_, editor, cx| {
+            editor.show_completions_from(provider, cx)
+        });
+    });
+}
+
+/// Completions are listed in the order they're found in, rather than the order
+/// a language server would give them.
+fn completion(old_range: Range<Anchor>, text: String, rank: usize) -> Completion {
+    Completion {
+        old_range,
+        label: CodeLabel::plain(text.clone(), None),
+        new_text: text,
+        documentation: None,
+        server_id: LanguageServerId(0),
+        lsp_completion: lsp::CompletionItem {
+            sort_text: Some(format!("{rank:08}")),
+            ..Default::default()
+        },
+    }
+}
+
+/// `ctrl-x ctrl-f` completes the file name before the cursor. Like vim resolves
+/// relative paths from the current directory, they're resolved from the root of
+/// the file's worktree.
+struct FilePathCompletions {
+    project: Model<Project>,
+}
+
+pub(crate) fn is_path_char(c: char) -> bool {
+    c.is_alphanumeric() || "/.-_+~#$%=@".contains(c)
+}
+
+impl CompletionProvider for FilePathCompletions {
+    fn completions(
+        &self,
+        buffer: &Model<Buffer>,
+        buffer_position: Anchor,
+        cx: &mut ViewContext<Editor>,
+    ) -> Task<Result<Vec<Completion>>> {
+        let snapshot = buffer.read(cx).snapshot();
+        let end = buffer_position.to_offset(&snapshot);
+        let mut start = end;
+        for c in snapshot.reversed_chars_at(end) {
+            if !is_path_char(c) {
+                break;
+            }
+            start -= c.len_utf8();
+        }
+        let typed = snapshot.text_for_range(start..end).collect::<String>();
+        let (directory, prefix) = match typed.rfind('/') {
+            Some(ix) => typed.split_at(ix + 1),
+            None => ("", typed.as_str()),
+        };
+        let directory = if Path::new(directory).is_absolute() {
+            PathBuf::from(directory)
+        } else {
+            match worktree_root(buffer, &self.project, cx) {
+                Some(root) => root.join(directory),
+                None => return Task::ready(Ok(Vec::new())),
+            }
+        };
+        let prefix = prefix.to_string();
+        let old_range = snapshot.anchor_before(end - prefix.len())..buffer_position;
+        let fs = self.project.read(cx).fs().clone();
+
+        cx.background_executor().spawn(async move {
+            let mut entries = file_names(fs, &directory).await?;
+            entries.retain(|name| {
+                name.starts_with(&prefix) && (prefix.starts_with('.') || !name.starts_with('.'))
+            });
+            entries.sort();
+            Ok(entries
+                .into_iter()
+                .enumerate()
+                .map(|(rank, name)| completion(old_range.clone(), name, rank))
+                .collect())
+        })
+    }
+
+    fn resolve_completions(
+        &self,
+        _: Vec<usize>,
+        _: Arc<RwLock<Box<[Completion]>>>,
+        _: &mut ViewContext<Editor>,
+    ) -> Task<Result<bool>> {
+        Task::ready(Ok(false))
+    }
+
+    fn apply_additional_edits_for_completion(
+        &self,
+        _: Model<Buffer>,
+        _: Completion,
+        _: bool,
+        _: &mut ViewContext<Editor>,
+    ) -> Task<Result<Option<language::Transaction>>> {
+        Task::ready(Ok(None))
+    }
+}
+
+/// The names in a directory, with a trailing `/` on the directories.
+async fn file_names(fs: Arc<dyn Fs>, directory: &Path) -> Result<Vec<String>> {
+    let mut names = Vec::new();
+    let mut entries = fs.read_dir(directory).await?;
+    while let Some(path) = entries.next().await {
+        let path = path?;
+        let Some(name) = path.file_name() else {
+            continue;
+        };
+        let mut name = name.to_string_lossy().to_string();
+        if fs.metadata(&path).await?.is_some_and(|meta| meta.is_dir) {
+            name.push('/');
+        }
+        names.push(name);
+    }
+    Ok(names)
+}
+
+/// `ctrl-x ctrl-l` completes the whole line, from the lines in open buffers that
+/// start with what's been typed on it.
+struct LineCompletions {
+    project: Model<Project>,
+}
+
+impl CompletionProvider for LineCompletions {
+    fn completions(
+        &self,
+        buffer: &Model<Buffer>,
+        buffer_position: Anchor,
+        cx: &mut ViewContext<Editor>,
+    ) -> Task<Result<Vec<Completion>>> {
+        let snapshot = buffer.read(cx).snapshot();
+        let cursor = buffer_position.to_point(&snapshot);
+        let indent = snapshot
+            .indent_size_for_line(cursor.row)
+            .len
+            .min(cursor.column);
+        let start = Point::new(cursor.row, indent);
+        let typed = snapshot.text_for_range(start..cursor).collect::<String>();
+        let old_range = snapshot.anchor_before(start)..buffer_position;
+
+        // the current buffer is searched first, like vim
+        let mut snapshots = vec![(snapshot, Some(cursor.row))];
+        for other in self.project.read(cx).opened_buffers() {
+            if &other != buffer {
+                snapshots.push((other.read(cx).snapshot(), None));
+            }
+        }
+
+        cx.background_executor().spawn(async move {
+            let mut seen = HashSet::default();
+            let mut completions = Vec::new();
+            for (snapshot, current_row) in snapshots {
+                for (row, line) in snapshot.text().lines().enumerate() {
+                    let line = line.trim();
+                    if line.is_empty()
+                        || !line.starts_with(&typed)
+                        || current_row == Some(row as u32)
+                        || !seen.insert(line.to_string())
+                    {
+                        continue;
+                    }
+                    let rank = completions.len();
+                    completions.push(completion(old_range.clone(), line.to_string(), rank));
+                }
+            }
+            Ok(completions)
+        })
+    }
+
+    fn resolve_completions(
+        &self,
+        _: Vec<usize>,
+        _: Arc<RwLock<Box<[Completion]>>>,
+        _: &mut ViewContext<Editor>,
+    ) -> Task<Result<bool>> {
+        Task::ready(Ok(false))
+    }
+
+    fn apply_additional_edits_for_completion(
+        &self,
+        _: Model<Buffer>,
+        _: Completion,
+        _: bool,
+        _: &mut ViewContext<Editor>,
+    ) -> Task<Result<Option<language::Transaction>>> {
+        Task::ready(Ok(None))
+    }
+}
+
+/// Vim's default `iskeyword`, with the characters a language adds to it.
+pub(crate) fn is_keyword_char(c: char, extra: &[char]) -> bool {
+    c.is_alphanumeric() || c == '_' || extra.contains(&c)
+}
+
+/// The characters the `iskeyword` of the language's entry in the `languages` setting
+/// adds to the keywords.
+pub(crate) fn keyword_chars(language: Option<&Arc<Language>>, cx: &AppContext) -> Vec<char> {
+    VimSettings::get_global(cx)
+        .language_options(language)
+        .and_then(|options| options.iskeyword.as_deref())
+        .map_or_else(Vec::new, |chars| chars.chars().collect())
+}
+
+/// The keyword under the cursor, or the first one after it on the line, for `*` and
+/// `#`, with the characters its language's `iskeyword` adds. `None` when it has none
+/// of them, for the search bar's own suggestion.
+pub(crate) fn keyword_at_cursor(editor: &Editor, cx: &AppContext) -> Option<(String, Vec<char>)> {
+    let snapshot = editor.buffer().read(cx).snapshot(cx);
+    let head = editor.selections.newest::<Point>(cx).head();
+    let extra = keyword_chars(snapshot.language_at(head), cx);
+    let line = snapshot
+        .text_for_range(Point::new(head.row, 0)..Point::new(head.row, snapshot.line_len(head.row)))
+        .collect::<String>();
+    let (_, keyword) = keywords(&line, &extra)
+        .into_iter()
+        .find(|(offset, keyword)| offset + keyword.len() > head.column as usize)?;
+    keyword
+        .contains(|c| extra.contains(&c))
+        .then(|| (keyword.to_string(), extra))
+}
+
+/// The keywords in the text, with their offsets.
+pub(crate) fn keywords<'a>(text: &'a str, extra: &[char]) -> Vec<(usize, &'a str)> {
+    let mut keywords = Vec::new();
+    let mut start = None;
+    for (offset, c) in text.char_indices().chain([(text.len(), ' ')]) {
+        match (start, is_keyword_char(c, extra)) {
+            (None, true) => start = Some(offset),
+            (Some(word_start), false) => {
+                keywords.push((word_start, &text[word_start..offset]));
+                start = None;
+            }
+            _ => {}
+        }
+    }
+    keywords
+}
+
+/// `ctrl-n` and `ctrl-p` complete keywords from open buffers, like vim's keyword
+/// completion. The current buffer comes first, searching from the cursor (forwards
+/// for `ctrl-n`, backwards for `ctrl-p`), so the nearest words are listed first.
+struct KeywordCompletions {
+    project: Model<Project>,
+    backwards: bool,
+}
+
+impl CompletionProvider for KeywordCompletions {
+    fn completions(
+        &self,
+        buffer: &Model<Buffer>,
+        buffer_position: Anchor,
+        cx: &mut ViewContext<Editor>,
+    ) -> Task<Result<Vec<Completion>>> {
+        let snapshot = buffer.read(cx).snapshot();
+        let extra = keyword_chars(snapshot.language_at(buffer_position), cx);
+        let end = buffer_position.to_offset(&snapshot);
+        let mut start = end;
+        for c in snapshot.reversed_chars_at(end) {
+            if !is_keyword_char(c, &extra) {
+                break;
+            }
+            start -= c.len_utf8();
+        }
+        let prefix = snapshot.text_for_range(start..end).collect::<String>();
+        let old_range = snapshot.anchor_before(start)..buffer_position;
+        let backwards = self.backwards;
+
+        let mut others = Vec::new();
+        for other in self.project.read(cx).opened_buffers() {
+            if &other != buffer {
+                others.push(other.read(cx).snapshot());
+            }
+        }
+
+        cx.background_executor().spawn(async move {
+            let text = snapshot.text();
+            let other_texts = others.iter().map(|other| other.text()).collect::<Vec<_>>();
+            let current = keywords(&text, &extra);
+            // skip the word being completed
+            let (before, after): (Vec<_>, Vec<_>) = current
+                .into_iter()
+                .filter(|(offset, _)| *offset != start)
+                .partition(|(offset, _)| *offset < start);
+            let mut candidates = if backwards {
+                before
+                    .into_iter()
+                    .rev()
+                    .chain(after.into_iter().rev())
+                    .collect()
+            } else {
+                after.into_iter().chain(before).collect::<Vec<_>>()
+            };
+            for text in &other_texts {
+                candidates.extend(keywords(text, &extra));
+            }
+
+            let mut seen = HashSet::default();
+            let mut completions = Vec::new();
+            for (_, word) in candidates {
+                if word.len() > prefix.len()
+                    && word.starts_with(&prefix)
+                    && seen.insert(word.to_string())
+                {
+                    let rank = completions.len();
+                    completions.push(completion(old_range.clone(), word.to_string(), rank));
+                }
+            }
+            Ok(completions)
+        })
+    }
+
+    fn resolve_completions(
+        &self,
+        _: Vec<usize>,
+        _: Arc<RwLock<Box<[Completion]>>>,
+        _: &mut ViewContext<Editor>,
+    ) -> Task<Result<bool>> {
+        Task::ready(Ok(false))
+    }
+
+    fn apply_additional_edits_for_completion(
+        &self,
+        _: Model<Buffer>,
+        _: Completion,
+        _: bool,
+        _: &mut ViewContext<Editor>,
+    ) -> Task<Result<Option<language::Transaction>>> {
+        Task::ready(Ok(None))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use settings::SettingsStore;
+
+    use crate::{state::Mode, test::VimTestContext, LanguageOptions, VimSettings};
+
+    #[gpui::test]
+    async fn test_complete_file_path(cx: &mut gpui::TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+
+        cx.set_state("ˇ", Mode::Normal);
+        cx.simulate_keystrokes(["i", "d", "i", "ctrl-x", "ctrl-f"]);
+        cx.run_until_parked();
+        cx.simulate_keystrokes(["enter"]);
+        cx.assert_state("dir/ˇ", Mode::Insert);
+
+        cx.simulate_keystrokes(["ctrl-x", "ctrl-f"]);
+        cx.run_until_parked();
+        cx.simulate_keystrokes(["enter"]);
+        cx.assert_state("dir/file.rsˇ", Mode::Insert);
+    }
+
+    #[gpui::test]
+    async fn test_complete_line(cx: &mut gpui::TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+
+        cx.set_state("    let one = 1;\n    ˇ\n}", Mode::Normal);
+        cx.simulate_keystrokes(["a", "l", "e", "ctrl-x", "ctrl-l"]);
+        cx.run_until_parked();
+        cx.simulate_keystrokes(["enter"]);
+        cx.assert_state("    let one = 1;\n    let one = 1;ˇ\n}", Mode::Insert);
+    }
+
+    #[gpui::test]
+    async fn test_complete_keyword(cx: &mut gpui::TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+
+        // the nearest word comes first
+        cx.set_state("only once\nˇ\nonward", Mode::Normal);
+        cx.simulate_keystrokes(["i", "o", "n", "ctrl-p"]);
+        cx.run_until_parked();
+        cx.simulate_keystrokes(["enter"]);
+        cx.assert_state("only once\nonceˇ\nonward", Mode::Insert);
+
+        cx.simulate_keystrokes(["escape", "c", "c", "o", "n", "ctrl-n"]);
+        cx.run_until_parked();
+        cx.simulate_keystrokes(["enter"]);
+        cx.assert_state("only once\nonwardˇ\nonward", Mode::Insert);
+
+        // the language's iskeyword adds to the characters of keywords
+        cx.update_global(|store: &mut SettingsStore, cx| {
+            store.update_user_settings::<VimSettings>(cx, |s| {
+                s.languages = Some(
+                    [(
+                        "Rust".to_string(),
+                        LanguageOptions {
+                            iskeyword: Some("-".to_string()),
+                            ..Default::default()
+                        },
+                    )]
+                    .into(),
+                );
+            });
+        });
+        cx.set_state("one-two\nˇ", Mode::Normal);
+        cx.simulate_keystrokes(["i", "o", "n", "ctrl-n"]);
+        cx.run_until_parked();
+        cx.simulate_keystrokes(["enter"]);
+        cx.assert_state("one-two\none-twoˇ", Mode::Insert);
+    }
+
+    #[gpui::test]
+    async fn test_iskeyword_motions(cx: &mut gpui::TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+        cx.update_global(|store: &mut SettingsStore, cx| {
+            store.update_user_settings::<VimSettings>(cx, |s| {
+                s.languages = Some(
+                    [(
+                        "Rust".to_string(),
+                        LanguageOptions {
+                            iskeyword: Some("-".to_string()),
+                            ..Default::default()
+                        },
+                    )]
+                    .into(),
+                );
+            });
+        });
+
+        // the language's iskeyword makes words of w, b and e longer
+        cx.set_state("ˇone-two three", Mode::Normal);
+        cx.simulate_keystrokes(["w"]);
+        cx.assert_state("one-two ˇthree", Mode::Normal);
+        cx.simulate_keystrokes(["b"]);
+        cx.assert_state("ˇone-two three", Mode::Normal);
+        cx.simulate_keystrokes(["e"]);
+        cx.assert_state("one-twˇo three", Mode::Normal);
+
+        // * searches for the whole keyword
+        cx.set_state("ˇone-two one one-two", Mode::Normal);
+        cx.simulate_keystrokes(["*"]);
+        cx.run_until_parked();
+        cx.assert_state("one-two one ˇone-two", Mode::Normal);
+
+        // and ctrl-w in insert mode deletes it
+        cx.set_state("one one-twoˇ", Mode::Insert);
+        cx.simulate_keystrokes(["ctrl-w"]);
+        cx.assert_state("one ˇ", Mode::Insert);
+    }
+}
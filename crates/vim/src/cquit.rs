@@ -0,0 +1,121 @@
+use gpui::{impl_actions, ViewContext};
+use serde_derive::Deserialize;
+use workspace::{item::ItemHandle, CloseAllItemsAndPanes, SaveIntent, Workspace};
+
+use crate::Vim;
+
+/// `:cq[uit]` quits Zed. When a `zed --wait` is waiting for a tab of the window (as
+/// `git commit` or `crontab -e` runs it when Zed is their editor), it closes every
+/// tab of the window without saving instead, like `:qa!`, and abandons them: the
+/// CLI exits with an error, so the tool cancels what it was doing. `:cq {N}` exits
+/// with the status N rather than 1.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct QuitWithError {
+    #[serde(default = "default_status")]
+    status: i32,
+}
+
+fn default_status() -> i32 {
+    1
+}
+
+impl_actions!(vim, [QuitWithError]);
+
+pub(crate) fn register(workspace: &mut Workspace, _: &mut ViewContext<Workspace>) {
+    workspace.register_action(quit_with_error);
+}
+
+fn quit_with_error(
+    workspace: &mut Workspace,
+    action: &QuitWithError,
+    cx: &mut ViewContext<Workspace>,
+) {
+    Vim::update(cx, |vim, cx| vim.clear_operator(cx));
+    let item_ids = workspace
+        .items(cx)
+        .map(|item| item.item_id())
+        .collect::<Vec<_>>();
+    if !workspace::abandon_items(item_ids, action.status, cx) {
+        cx.dispatch_action(Box::new(zed_actions::Quit));
+        return;
+    }
+    workspace.close_all_items_and_panes(
+        &CloseAllItemsAndPanes {
+            save_intent: Some(SaveIntent::Skip),
+        },
+        cx,
+    );
+}
+
+/// `:cq[uit][!] [N]`
+pub(crate) fn parse_quit_with_error(query: &str) -> Option<QuitWithError> {
+    let name_len = query
+        .find(|c: char| !c.is_ascii_alphabetic())
+        .unwrap_or(query.len());
+    let (name, args) = query.split_at(name_len);
+    if !matches!(name, "cq" | "cqu" | "cqui" | "cquit") {
+        return None;
+    }
+    let args = args.strip_prefix('!').unwrap_or(args).trim();
+    let status = if args.is_empty() {
+        default_status()
+    } else {
+        args.parse().ok()?
+    };
+    Some(QuitWithError { status })
+}
+
+#[cfg(test)]
+mod test {
+    use std::{cell::Cell, rc::Rc};
+
+    use crate::{state::Mode, test::VimTestContext};
+
+    #[gpui::test]
+    async fn test_quit_with_error(cx: &mut gpui::TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+
+        cx.set_state("ˇchanged", Mode::Normal);
+        let item_id = cx.workspace(|workspace, cx| workspace.active_item(cx).unwrap().item_id());
+        cx.update(|cx| workspace::await_items([item_id], cx));
+        cx.simulate_keystrokes([":"]);
+        cx.simulate_input("cq 3");
+        cx.simulate_keystrokes(["enter"]);
+        cx.run_until_parked();
+
+        // the changes are discarded without asking, and the item is abandoned
+        assert!(!cx.has_pending_prompt());
+        cx.workspace(|workspace, cx| assert_eq!(workspace.items(cx).count(), 0));
+        let status = cx.update(|cx| workspace::take_abandoned_status(item_id, cx));
+        assert_eq!(status, Some(3));
+        let status = cx.update(|cx| workspace::take_abandoned_status(item_id, cx));
+        assert_eq!(status, None);
+    }
+
+    #[gpui::test]
+    async fn test_quit_with_error_without_wait(cx: &mut gpui::TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+        let quit = Rc::new(Cell::new(false));
+        cx.update(|cx| {
+            let quit = quit.clone();
+            cx.on_action(move |_: &zed_actions::Quit, _| quit.set(true));
+        });
+
+        // with no zed --wait waiting, it quits Zed, as :qa! does
+        cx.set_state("ˇchanged", Mode::Normal);
+        cx.simulate_keystrokes([":"]);
+        cx.simulate_input("cq");
+        cx.simulate_keystrokes(["enter"]);
+        cx.run_until_parked();
+        assert!(quit.get());
+    }
+
+    #[test]
+    fn test_parse_quit_with_error() {
+        assert_eq!(super::parse_quit_with_error("cq").unwrap().status, 1);
+        assert_eq!(super::parse_quit_with_error("cquit! 2").unwrap().status, 2);
+        assert!(super::parse_quit_with_error("cq x").is_none());
+        assert!(super::parse_quit_with_error("cquitx").is_none());
+    }
+}
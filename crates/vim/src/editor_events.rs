@@ -1,10 +1,16 @@
-use crate::{insert::NormalBefore, Vim, VimModeSetting};
-use editor::{Editor, EditorEvent};
+use crate::{insert::NormalBefore, normal::fold::set_fold_level, Vim, VimModeSetting, VimSettings};
+use editor::{Editor, EditorEvent, EditorMode};
 use gpui::{Action, AppContext, Entity, EntityId, View, ViewContext, WindowContext};
 use settings::{Settings, SettingsStore};
 
 pub fn init(cx: &mut AppContext) {
-    cx.observe_new_views(|_, cx: &mut ViewContext<Editor>| {
+    cx.observe_new_views(|editor: &mut Editor, cx: &mut ViewContext<Editor>| {
+        if VimModeSetting::get_global(cx).0 {
+            if let Some(level) = VimSettings::get_global(cx).fold_level {
+                apply_fold_level(editor, level, cx);
+            }
+        }
+
         let editor = cx.view().clone();
         cx.subscribe(&editor, |_, editor, event: &EditorEvent, cx| match event {
             EditorEvent::Focused => cx.window_context().defer(|cx| focused(editor, cx)),
@@ -30,6 +36,28 @@ pub fn init(cx: &mut AppContext) {
     .detach();
 }
 
+/// Files are opened with the `fold_level` setting, unless their folds were restored
+/// with the workspace.
+fn apply_fold_level(editor: &mut Editor, level: u32, cx: &mut ViewContext<Editor>) {
+    if editor.mode() != EditorMode::Full || editor.buffer().read(cx).as_singleton().is_none() {
+        return;
+    }
+    if editor
+        .snapshot(cx)
+        .display_snapshot
+        .fold_snapshot
+        .fold_count()
+        > 0
+    {
+        return;
+    }
+    let level = set_fold_level(editor, |_| level, cx);
+    let id = cx.view().entity_id();
+    cx.update_global(|vim: &mut Vim, _| {
+        vim.editor_states.entry(id).or_default().fold_level = Some(level);
+    });
+}
+
 fn focused(editor: View<Editor>, cx: &mut WindowContext) {
     Vim::update(cx, |vim, cx| {
         if !vim.enabled {
@@ -51,6 +79,13 @@ fn blurred(editor: View<Editor>, cx: &mut WindowContext) {
                 vim.clear_operator(cx);
             }
         }
+        // The editor may no longer be the active one if focus has already moved on
+        // (to another pane, or a modal), but what was typed in it must not fire
+        // when it's focused again.
+        if let Some(state) = vim.editor_states.get_mut(&editor.entity_id()) {
+            state.clear_pending();
+            vim.workspace_state.selected_register = None;
+        }
     });
 }
 
@@ -63,7 +98,12 @@ fn released(entity_id: EntityId, cx: &mut AppContext) {
         {
             vim.active_editor = None;
             vim.editor_subscription = None;
+            vim.buffer_subscription = None;
         }
+        // a pane's location list goes with the editor it was made for
+        vim.workspace_state
+            .location_lists
+            .retain(|_, list| !list.is_for(entity_id));
         vim.editor_states.remove(&entity_id)
     });
 }
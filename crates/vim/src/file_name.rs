@@ -0,0 +1,225 @@
+use std::path::Path;
+
+use editor::Editor;
+use gpui::{impl_actions, Model, View, ViewContext, WindowContext};
+use language::Buffer;
+use project::{File, Project, ProjectPath};
+use serde_derive::Deserialize;
+use workspace::Workspace;
+
+use crate::{normal::counts::file_info, utils::worktree_root, Vim};
+
+/// `:file {name}` gives the current buffer a new name, relative to the project's root,
+/// without writing it: the file it was read from is left as it is, and the next `:w`
+/// writes the new one. `:file! {name}` moves the file on disk as well, as renaming it
+/// in the project panel would, and `:0file` leaves the buffer without a name.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct FileName {
+    path: Option<String>,
+    #[serde(default)]
+    move_file: bool,
+}
+
+impl_actions!(vim, [FileName]);
+
+pub(crate) fn register(workspace: &mut Workspace, _: &mut ViewContext<Workspace>) {
+    workspace.register_action(file_name);
+}
+
+fn file_name(workspace: &mut Workspace, action: &FileName, cx: &mut ViewContext<Workspace>) {
+    Vim::update(cx, |vim, cx| vim.clear_operator(cx));
+    let Some(editor) = workspace.active_item_as::<Editor>(cx) else {
+        return;
+    };
+    let Some(buffer) = editor.read(cx).buffer().read(cx).as_singleton() else {
+        return;
+    };
+    let project = workspace.project().clone();
+    let Some(path) = &action.path else {
+        rename_buffer(&project, &buffer, None, &editor, cx);
+        return;
+    };
+
+    let path = Path::new(path);
+    let abs_path = if path.is_absolute() {
+        path.to_path_buf()
+    } else if let Some(root) = worktree_root(&buffer, &project, cx) {
+        root.join(path)
+    } else {
+        Vim::update(cx, |vim, _| {
+            vim.show_message("E32: No project to name the file in")
+        });
+        return;
+    };
+    let Some((worktree, relative_path)) = project.read(cx).find_local_worktree(&abs_path, cx)
+    else {
+        Vim::update(cx, |vim, _| {
+            vim.show_message(format!(
+                "E32: \"{}\" is outside the project",
+                path.display()
+            ))
+        });
+        return;
+    };
+    let new_path = ProjectPath {
+        worktree_id: worktree.read(cx).id(),
+        path: relative_path.into(),
+    };
+
+    // a buffer that hasn't been written yet has nothing on disk to move
+    let old_entry = File::from_dyn(buffer.read(cx).file())
+        .filter(|_| action.move_file)
+        .and_then(|file| Some((file.worktree.clone(), file.entry_id?)));
+    let Some((old_worktree, entry_id)) = old_entry else {
+        rename_buffer(&project, &buffer, Some(new_path), &editor, cx);
+        return;
+    };
+    if old_worktree != worktree {
+        Vim::update(cx, |vim, _| {
+            vim.show_message("E212: Can't move the file to another folder of the project")
+        });
+        return;
+    }
+    if project.read(cx).entry_for_path(&new_path, cx).is_some() {
+        Vim::update(cx, |vim, _| vim.show_message("E13: File exists"));
+        return;
+    }
+
+    let display_path = new_path.path.to_string_lossy().to_string();
+    let rename = project.update(cx, |project, cx| {
+        project.rename_entry(entry_id, new_path.path, cx)
+    });
+    cx.spawn(|workspace, mut cx| async move {
+        let renamed = rename.await;
+        workspace.update(&mut cx, |_, cx| {
+            Vim::update(cx, |vim, _| match renamed {
+                Ok(_) => vim.show_message(format!("\"{display_path}\" moved")),
+                Err(error) => vim.show_message(format!("E212: Can't move the file: {error}")),
+            })
+        })
+    })
+    .detach_and_log_err(cx);
+}
+
+/// Renames the buffer in the project, then shows its new name as `ctrl-g` would.
+fn rename_buffer(
+    project: &Model<Project>,
+    buffer: &Model<Buffer>,
+    new_path: Option<ProjectPath>,
+    editor: &View<Editor>,
+    cx: &mut WindowContext,
+) {
+    let renamed = project.update(cx, |project, cx| {
+        project.rename_buffer(buffer, new_path, cx)
+    });
+    let message = match renamed {
+        Ok(()) => file_info(editor.read(cx), false, cx),
+        Err(error) => format!("E212: Can't rename the buffer: {error}"),
+    };
+    Vim::update(cx, |vim, _| vim.show_message(message));
+}
+
+/// `:f[ile] {name}`, `:f[ile]! {name}` or `:0f[ile]`. On its own, `:file` is `ctrl-g`.
+pub(crate) fn parse_file_name(query: &str) -> Option<FileName> {
+    let (clear, query) = match query.strip_prefix('0') {
+        Some(query) => (true, query),
+        None => (false, query),
+    };
+    let name_len = query
+        .find(|c: char| !c.is_ascii_alphabetic())
+        .unwrap_or(query.len());
+    let (name, args) = query.split_at(name_len);
+    if !matches!(name, "f" | "fi" | "fil" | "file") {
+        return None;
+    }
+    let (move_file, args) = match args.strip_prefix('!') {
+        Some(args) => (true, args),
+        None => (false, args),
+    };
+    let path = args.trim();
+    if clear {
+        return path.is_empty().then_some(FileName {
+            path: None,
+            move_file: false,
+        });
+    }
+    if path.is_empty() || !args.starts_with(' ') {
+        return None;
+    }
+    Some(FileName {
+        path: Some(path.to_string()),
+        move_file,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::Path;
+
+    use crate::{state::Mode, test::VimTestContext};
+
+    fn buffer_path(cx: &mut VimTestContext) -> Option<String> {
+        cx.update_editor(|editor, cx| {
+            let buffer = editor.buffer().read(cx).as_singleton()?;
+            let file = buffer.read(cx).file()?;
+            Some(file.path().to_string_lossy().to_string())
+        })
+    }
+
+    #[gpui::test]
+    async fn test_file_name(cx: &mut gpui::TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+        let fs = cx.workspace(|workspace, cx| workspace.project().read(cx).fs().clone());
+        let old_path = buffer_path(&mut cx).unwrap();
+
+        // the buffer is renamed, and the old file is left alone until it's written
+        cx.set_state("ˇrenamed", Mode::Normal);
+        cx.simulate_keystrokes([":"]);
+        cx.simulate_input("file new.rs");
+        cx.simulate_keystrokes(["enter"]);
+        cx.run_until_parked();
+        let new_path = buffer_path(&mut cx).unwrap();
+        assert_ne!(new_path, old_path);
+        assert!(new_path.ends_with("new.rs"));
+        assert!(cx.message().unwrap().contains("new.rs\" [Modified]"));
+        assert!(!fs.is_file(Path::new("/root/dir/new.rs")).await);
+        cx.simulate_keystrokes([":", "w", "enter"]);
+        cx.run_until_parked();
+        assert_eq!(
+            fs.load(Path::new("/root/dir/new.rs")).await.unwrap(),
+            "renamed"
+        );
+
+        // with a bang, the file is moved on disk, and the buffer follows it
+        cx.simulate_keystrokes([":"]);
+        cx.simulate_input("file! moved.rs");
+        cx.simulate_keystrokes(["enter"]);
+        cx.run_until_parked();
+        assert!(buffer_path(&mut cx).unwrap().ends_with("moved.rs"));
+        assert!(!fs.is_file(Path::new("/root/dir/new.rs")).await);
+        assert_eq!(
+            fs.load(Path::new("/root/dir/moved.rs")).await.unwrap(),
+            "renamed"
+        );
+
+        // and :0file leaves it without a name
+        cx.simulate_keystrokes([":"]);
+        cx.simulate_input("0file");
+        cx.simulate_keystrokes(["enter"]);
+        assert_eq!(buffer_path(&mut cx), None);
+        assert!(cx.message().unwrap().starts_with("\"[No Name]\""));
+    }
+
+    #[test]
+    fn test_parse_file_name() {
+        let file_name = super::parse_file_name("f! a b.rs").unwrap();
+        assert_eq!(file_name.path.as_deref(), Some("a b.rs"));
+        assert!(file_name.move_file);
+        assert!(!super::parse_file_name("file x").unwrap().move_file);
+        assert_eq!(super::parse_file_name("0file").unwrap().path, None);
+        assert!(super::parse_file_name("0f x").is_none());
+        assert!(super::parse_file_name("file").is_none());
+        assert!(super::parse_file_name("filex y").is_none());
+    }
+}
@@ -0,0 +1,495 @@
+use std::ops::Range;
+
+use command_palette_hooks::ToggleWithQuery;
+use editor::Editor;
+use gpui::{actions, impl_actions, ViewContext, WindowContext};
+use language::Point;
+use serde_derive::Deserialize;
+use workspace::Workspace;
+
+use crate::{
+    messages::show_output,
+    motion::Motion,
+    normal::rewrap::{line_rows, move_to_lines},
+    object::Object,
+    state::Mode,
+    utils::{file_root, run_filter, worktree_root, KeywordChars},
+    Vim,
+};
+
+/// `:r[ead] !{cmd}` puts the output of a shell command on new lines below the cursor's
+/// line, or below the line given before it (`:0r !cmd` puts them above the first line).
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub(crate) struct ReadCommand {
+    pub command: String,
+    /// The line the output goes below, counting from 1, or the cursor's line
+    pub line: Option<u32>,
+}
+
+/// `:{range}!{cmd}` pipes the lines through a shell command, and replaces them with
+/// its output (as in `:%!jq .`). Without a range, `:!{cmd}` shows the command's output.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub(crate) struct FilterLines {
+    pub command: String,
+}
+
+impl_actions!(vim, [ReadCommand, FilterLines]);
+
+actions!(vim, [VisualFilter]);
+
+pub(crate) fn register(workspace: &mut Workspace, _: &mut ViewContext<Workspace>) {
+    workspace.register_action(|_: &mut Workspace, action: &ReadCommand, cx| {
+        Vim::update(cx, |vim, cx| {
+            vim.switch_mode(Mode::Normal, false, cx);
+            vim.update_active_editor(cx, |vim, editor, cx| {
+                let line = action
+                    .line
+                    .unwrap_or_else(|| editor.selections.newest::<Point>(cx).head().row + 1);
+                read_command(vim, editor, line, action.command.clone(), cx)
+            });
+        })
+    });
+    workspace.register_action(filter_lines);
+    // `!` in visual mode starts `:'<,'>!`, for the command to pipe the lines through.
+    workspace.register_action(|_: &mut Workspace, _: &VisualFilter, cx| {
+        Vim::update(cx, |vim, cx| vim.clear_operator(cx));
+        cx.dispatch_action(Box::new(ToggleWithQuery {
+            query: "'<,'>!".to_string(),
+        }));
+    });
+}
+
+fn filter_lines(workspace: &mut Workspace, action: &FilterLines, cx: &mut ViewContext<Workspace>) {
+    let Some(editor) = workspace.active_item_as::<Editor>(cx) else {
+        return;
+    };
+    // a range is run as a linewise selection
+    if Vim::read(cx).state().mode.is_visual() {
+        Vim::update(cx, |vim, cx| {
+            editor.update(cx, |editor, cx| {
+                let rows = editor
+                    .selections
+                    .all::<Point>(cx)
+                    .into_iter()
+                    .map(|selection| line_rows(selection.start..selection.end))
+                    .collect();
+                filter_rows(vim, editor, rows, action.command.clone(), false, cx);
+            });
+            vim.switch_mode(Mode::Normal, true, cx);
+        });
+        return;
+    }
+
+    let project = workspace.project().clone();
+    let root = editor
+        .read(cx)
+        .buffer()
+        .read(cx)
+        .as_singleton()
+        .and_then(|buffer| worktree_root(&buffer, &project, cx));
+    if Vim::update(cx, |vim, _| vim.refuse_pending_operation()) {
+        return;
+    }
+    let command = action.command.clone();
+    let task = cx.spawn(|workspace, mut cx| async move {
+        let output = run_filter(&command, "", root.as_deref()).await;
+        workspace
+            .update(&mut cx, |workspace, cx| {
+                Vim::update(cx, |vim, _| vim.finish_pending_operation());
+                match output {
+                    Ok(output) if output.is_empty() => {}
+                    Ok(output) => show_output(workspace, &output, cx),
+                    Err(error) => Vim::update(cx, |vim, _| vim.show_message(error.to_string())),
+                }
+            })
+            .ok();
+    });
+    Vim::update(cx, |vim, _| vim.pending_operation = Some(task));
+}
+
+/// `!{motion}` starts `:.,.+{n}!` for the lines the motion covers, for the command
+/// to pipe them through.
+pub fn filter_motion(vim: &mut Vim, motion: Motion, times: Option<usize>, cx: &mut WindowContext) {
+    vim.stop_recording();
+    let rows = vim.update_active_editor(cx, |_, editor, cx| {
+        let text_layout_details = editor.text_layout_details(cx);
+        let keyword_chars = KeywordChars::new(cx);
+        let mut rows = None;
+        editor.change_selections(None, cx, |s| {
+            s.move_with(|map, selection| {
+                motion.expand_selection(
+                    map,
+                    selection,
+                    times,
+                    true,
+                    &text_layout_details,
+                    &keyword_chars,
+                );
+                rows.get_or_insert(line_rows(
+                    selection.start.to_point(map)..selection.end.to_point(map),
+                ));
+            });
+        });
+        rows
+    });
+    open_filter_prompt(vim, rows.flatten(), cx);
+}
+
+/// `!ip` and friends
+pub fn filter_object(vim: &mut Vim, object: Object, around: bool, cx: &mut WindowContext) {
+    vim.stop_recording();
+    let rows = vim.update_active_editor(cx, |_, editor, cx| {
+        let keyword_chars = KeywordChars::new(cx);
+        let mut rows = None;
+        editor.change_selections(None, cx, |s| {
+            s.move_with(|map, selection| {
+                object.expand_selection(map, selection, around, &keyword_chars);
+                rows.get_or_insert(line_rows(
+                    selection.start.to_point(map)..selection.end.to_point(map),
+                ));
+            });
+        });
+        rows
+    });
+    open_filter_prompt(vim, rows.flatten(), cx);
+}
+
+/// Puts the cursor on the first of the rows, and opens the `:` prompt with the range
+/// of lines from there, as vim does.
+fn open_filter_prompt(vim: &mut Vim, rows: Option<Range<u32>>, cx: &mut WindowContext) {
+    let Some(rows) = rows else {
+        return;
+    };
+    if vim
+        .update_active_editor(cx, |_, editor, cx| {
+            let start = Point::new(rows.start, 0);
+            editor.change_selections(None, cx, |s| s.select_ranges([start..start]));
+        })
+        .is_none()
+    {
+        return;
+    }
+    vim.switch_mode(Mode::Normal, false, cx);
+    let query = match rows.len() {
+        0 | 1 => ".!".to_string(),
+        len => format!(".,.+{}!", len - 1),
+    };
+    cx.dispatch_action(Box::new(ToggleWithQuery { query }));
+}
+
+/// Runs the lines through the command in the background, and replaces them with its
+/// output in a single edit (undone by one `u`) once it's done. The cursor goes to the
+/// first non-blank of the last line of the output, as after `gq`, or of the first
+/// line. When the command fails, or the lines have been edited in the meantime,
+/// they're left as they are. Interrupting it (`ctrl-c`) kills the command.
+pub(crate) fn filter_rows(
+    vim: &mut Vim,
+    editor: &mut Editor,
+    rows: Vec<Range<u32>>,
+    command: String,
+    cursor_at_end: bool,
+    cx: &mut ViewContext<Editor>,
+) {
+    if vim.refuse_pending_operation() {
+        return;
+    }
+    let buffer = editor.buffer().read(cx).snapshot(cx);
+    let root = editor
+        .buffer()
+        .read(cx)
+        .as_singleton()
+        .and_then(|buffer| file_root(buffer.read(cx), cx));
+    let inputs = rows
+        .into_iter()
+        .map(|rows| {
+            let start = Point::new(rows.start, 0);
+            let end = Point::new(rows.end - 1, buffer.line_len(rows.end - 1));
+            let text = buffer.text_for_range(start..end).collect::<String>();
+            (buffer.anchor_before(start)..buffer.anchor_after(end), text)
+        })
+        .collect::<Vec<_>>();
+
+    let task = cx.spawn(|editor, mut cx| async move {
+        let outputs = async {
+            let mut outputs = Vec::new();
+            for (range, text) in inputs {
+                // the command is given whole lines, each ending with a newline.
+                let input = text.clone() + "\n";
+                let output = run_filter(&command, &input, root.as_deref()).await?;
+                let output = output.strip_suffix('\n').unwrap_or(&output).to_string();
+                outputs.push((range, text, output));
+            }
+            anyhow::Ok(outputs)
+        }
+        .await;
+        editor
+            .update(&mut cx, |editor, cx| {
+                Vim::update(cx, |vim, _| vim.finish_pending_operation());
+                let outputs = match outputs {
+                    Ok(outputs) => outputs,
+                    Err(error) => {
+                        Vim::update(cx, |vim, _| vim.show_message(error.to_string()));
+                        return;
+                    }
+                };
+                let buffer = editor.buffer().read(cx).snapshot(cx);
+                if outputs.iter().any(|(range, text, _)| {
+                    buffer.text_for_range(range.clone()).collect::<String>() != *text
+                }) {
+                    Vim::update(cx, |vim, _| {
+                        vim.show_message("The lines changed while the command ran")
+                    });
+                    return;
+                }
+                let cursors = outputs
+                    .iter()
+                    .map(|(range, _, _)| {
+                        if cursor_at_end {
+                            range.end
+                        } else {
+                            range.start
+                        }
+                    })
+                    .collect::<Vec<_>>();
+                let edits = outputs
+                    .into_iter()
+                    .filter(|(_, text, output)| output != text)
+                    .map(|(range, _, output)| (range, output));
+                editor.transact(cx, |editor, cx| {
+                    editor.edit(edits, cx);
+                    move_to_lines(editor, cursors, cx);
+                });
+            })
+            .ok();
+    });
+    vim.pending_operation = Some(task);
+}
+
+/// Runs the command in the background, and puts its output on new lines below the
+/// line (or above the first line for line 0), with the cursor on the first of them.
+fn read_command(
+    vim: &mut Vim,
+    editor: &mut Editor,
+    line: u32,
+    command: String,
+    cx: &mut ViewContext<Editor>,
+) {
+    if vim.refuse_pending_operation() {
+        return;
+    }
+    let buffer = editor.buffer().read(cx).snapshot(cx);
+    let root = editor
+        .buffer()
+        .read(cx)
+        .as_singleton()
+        .and_then(|buffer| file_root(buffer.read(cx), cx));
+    // before the new lines, so that it stays on the line they go below.
+    let position = if line == 0 {
+        buffer.anchor_before(Point::zero())
+    } else {
+        let row = (line - 1).min(buffer.max_point().row);
+        buffer.anchor_before(Point::new(row, buffer.line_len(row)))
+    };
+
+    let task = cx.spawn(|editor, mut cx| async move {
+        let output = run_filter(&command, "", root.as_deref()).await;
+        editor
+            .update(&mut cx, |editor, cx| {
+                Vim::update(cx, |vim, _| vim.finish_pending_operation());
+                let output = match output {
+                    Ok(output) => output,
+                    Err(error) => {
+                        Vim::update(cx, |vim, _| vim.show_message(error.to_string()));
+                        return;
+                    }
+                };
+                let output = output.strip_suffix('\n').unwrap_or(&output);
+                let text = if line == 0 {
+                    format!("{output}\n")
+                } else {
+                    format!("\n{output}")
+                };
+                editor.transact(cx, |editor, cx| {
+                    editor.edit([(position..position, text)], cx);
+                    let buffer = editor.buffer().read(cx).snapshot(cx);
+                    let mut row = position.to_point(&buffer).row;
+                    if line > 0 {
+                        row += 1;
+                    }
+                    move_to_lines(editor, vec![buffer.anchor_before(Point::new(row, 0))], cx);
+                });
+            })
+            .ok();
+    });
+    vim.pending_operation = Some(task);
+}
+
+/// `:r !{cmd}`, and `:r! {cmd}`.
+pub(crate) fn parse_read(query: &str) -> Option<ReadCommand> {
+    let name_len = query
+        .find(|c: char| !c.is_ascii_alphabetic())
+        .unwrap_or(query.len());
+    let (name, args) = query.split_at(name_len);
+    if !matches!(name, "r" | "re" | "rea" | "read") {
+        return None;
+    }
+    let command = args.trim_start().strip_prefix('!')?.trim();
+    if command.is_empty() {
+        return None;
+    }
+    Some(ReadCommand {
+        command: command.to_string(),
+        line: None,
+    })
+}
+
+/// `:!{cmd}`
+pub(crate) fn parse_filter(query: &str) -> Option<FilterLines> {
+    let command = query.strip_prefix('!')?.trim();
+    if command.is_empty() {
+        return None;
+    }
+    Some(FilterLines {
+        command: command.to_string(),
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use indoc::indoc;
+
+    use crate::{state::Mode, test::VimTestContext};
+
+    #[gpui::test]
+    async fn test_filter_lines(cx: &mut gpui::TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+
+        cx.set_state(
+            indoc! {"
+                c
+                ˇb
+                a"},
+            Mode::Normal,
+        );
+        cx.simulate_keystrokes([":"]);
+        cx.simulate_input("%!sort");
+        cx.simulate_keystrokes(["enter"]);
+        cx.run_until_operation_finishes();
+        cx.assert_state(
+            indoc! {"
+                ˇa
+                b
+                c"},
+            Mode::Normal,
+        );
+        cx.simulate_keystrokes(["u"]);
+        cx.assert_state(
+            indoc! {"
+                ˇc
+                b
+                a"},
+            Mode::Normal,
+        );
+
+        // the `!` operator starts the command with the lines of the motion
+        cx.set_state(
+            indoc! {"
+                one
+                ˇtwo
+                three
+
+                four"},
+            Mode::Normal,
+        );
+        cx.simulate_keystrokes(["!", "i", "p"]);
+        cx.simulate_input("tr a-z A-Z");
+        cx.simulate_keystrokes(["enter"]);
+        cx.run_until_operation_finishes();
+        cx.assert_state(
+            indoc! {"
+                ˇONE
+                TWO
+                THREE
+
+                four"},
+            Mode::Normal,
+        );
+
+        cx.set_state("ˇone\ntwo", Mode::Normal);
+        cx.simulate_keystrokes(["j", "!", "!"]);
+        cx.simulate_input("rev");
+        cx.simulate_keystrokes(["enter"]);
+        cx.run_until_operation_finishes();
+        cx.assert_state("one\nˇowt", Mode::Normal);
+    }
+
+    #[gpui::test]
+    async fn test_read_command(cx: &mut gpui::TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+
+        cx.set_state("ˇone\ntwo", Mode::Normal);
+        cx.simulate_keystrokes([":"]);
+        cx.simulate_input("r !printf 'a\\nb'");
+        cx.simulate_keystrokes(["enter"]);
+        cx.run_until_operation_finishes();
+        cx.assert_state("one\nˇa\nb\ntwo", Mode::Normal);
+
+        cx.simulate_keystrokes([":"]);
+        cx.simulate_input("0r !echo top");
+        cx.simulate_keystrokes(["enter"]);
+        cx.run_until_operation_finishes();
+        cx.assert_state("ˇtop\none\na\nb\ntwo", Mode::Normal);
+
+        cx.simulate_keystrokes([":"]);
+        cx.simulate_input("$r !echo bottom");
+        cx.simulate_keystrokes(["enter"]);
+        cx.run_until_operation_finishes();
+        cx.assert_state("top\none\na\nb\ntwo\nˇbottom", Mode::Normal);
+    }
+
+    #[gpui::test]
+    async fn test_interrupt_command(cx: &mut gpui::TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+
+        cx.set_state("ˇb\na", Mode::Normal);
+        cx.simulate_keystrokes([":"]);
+        cx.simulate_input("%!sleep 10; sort");
+        cx.simulate_keystrokes(["enter"]);
+        cx.run_until_parked();
+
+        // another command waits for it to be over, or to be interrupted
+        cx.simulate_keystrokes([":"]);
+        cx.simulate_input("r !echo c");
+        cx.simulate_keystrokes(["enter"]);
+        cx.run_until_parked();
+        assert_eq!(
+            cx.message().as_deref(),
+            Some("Another operation is still running (ctrl-c interrupts it)")
+        );
+
+        cx.simulate_keystrokes(["ctrl-c"]);
+        cx.run_until_operation_finishes();
+        assert_eq!(cx.message().as_deref(), Some("Interrupted"));
+        cx.assert_state("ˇb\na", Mode::Normal);
+
+        cx.simulate_keystrokes([":"]);
+        cx.simulate_input("r !echo c");
+        cx.simulate_keystrokes(["enter"]);
+        cx.run_until_operation_finishes();
+        cx.assert_state("b\nˇc\na", Mode::Normal);
+    }
+
+    #[test]
+    fn test_parse_read() {
+        assert_eq!(
+            super::parse_read("r !date").map(|read| read.command),
+            Some("date".to_string())
+        );
+        assert_eq!(
+            super::parse_read("read!ls -a").map(|read| read.command),
+            Some("ls -a".to_string())
+        );
+        assert!(super::parse_read("r file.txt").is_none());
+        assert!(super::parse_read("redo").is_none());
+    }
+}
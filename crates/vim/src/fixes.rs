@@ -0,0 +1,423 @@
+use std::sync::Arc;
+
+use anyhow::Result;
+use editor::Editor;
+use fuzzy::{StringMatch, StringMatchCandidate};
+use gpui::{
+    actions, impl_actions, AppContext, DismissEvent, EventEmitter, FocusHandle, FocusableView,
+    Model, ParentElement, Render, Styled, Task, View, ViewContext, VisualContext, WeakView,
+    WindowContext,
+};
+use language::{Buffer, CodeAction, DiagnosticEntry};
+use picker::{Picker, PickerDelegate};
+use serde_derive::Deserialize;
+use ui::{prelude::*, HighlightedLabel, ListItem, ListItemSpacing};
+use workspace::{ModalView, Workspace};
+
+use crate::{
+    list_picker::{self, ModalDelegate, StringListDelegate},
+    state::{RecordedSelection, ReplayableAction},
+    Vim,
+};
+
+actions!(vim, [PickFix]);
+
+/// Applies the fix with the same title to the diagnostic under the cursor, which is
+/// how `.` repeats a fix picked with `z q`.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub(crate) struct RepeatFix {
+    title: String,
+}
+
+impl_actions!(vim, [RepeatFix]);
+
+pub(crate) fn register(workspace: &mut Workspace, _: &mut ViewContext<Workspace>) {
+    workspace.register_action(pick_fix);
+    workspace.register_action(repeat_fix);
+}
+
+/// `z q` lists the fixes the language server offers for the diagnostic under the
+/// cursor (its quick fixes, leaving out the other code actions), like `z=` lists the
+/// spelling suggestions, and applies the one that's picked. With a count, the
+/// count-th fix is applied straight away.
+fn pick_fix(workspace: &mut Workspace, _: &PickFix, cx: &mut ViewContext<Workspace>) {
+    let count = Vim::update(cx, |vim, cx| {
+        let count = vim.take_count(cx);
+        vim.clear_operator(cx);
+        count
+    });
+    let Some(editor) = workspace.active_item_as::<Editor>(cx) else {
+        return;
+    };
+    let Some((buffer, fixes)) = fixes_under_cursor(&editor, cx) else {
+        Vim::update(cx, |vim, _| {
+            vim.show_message("No diagnostic under the cursor")
+        });
+        return;
+    };
+    cx.spawn(|workspace, mut cx| async move {
+        let fixes = fixes.await?;
+        workspace.update(&mut cx, |workspace, cx| {
+            if fixes.is_empty() {
+                Vim::update(cx, |vim, _| {
+                    vim.show_message("No fixes for the diagnostic under the cursor")
+                });
+                return;
+            }
+            if let Some(count) = count {
+                if let Some(fix) = fixes.get(count - 1) {
+                    apply_fix(&editor, buffer, fix.clone(), cx);
+                }
+                return;
+            }
+            workspace.toggle_modal(cx, |cx| FixList::new(editor, buffer, fixes, cx));
+        })
+    })
+    .detach_and_log_err(cx);
+}
+
+fn repeat_fix(workspace: &mut Workspace, action: &RepeatFix, cx: &mut ViewContext<Workspace>) {
+    let Some(editor) = workspace.active_item_as::<Editor>(cx) else {
+        return;
+    };
+    let Some((buffer, fixes)) = fixes_under_cursor(&editor, cx) else {
+        Vim::update(cx, |vim, _| {
+            vim.show_message("No diagnostic under the cursor")
+        });
+        return;
+    };
+    let title = action.title.clone();
+    cx.spawn(|workspace, mut cx| async move {
+        let fixes = fixes.await?;
+        workspace.update(&mut cx, |_, cx| {
+            match fixes.into_iter().find(|fix| fix.lsp_action.title == title) {
+                Some(fix) => apply_fix(&editor, buffer, fix, cx),
+                None => Vim::update(cx, |vim, _| {
+                    vim.show_message(format!("No fix \"{title}\" for the diagnostic here"))
+                }),
+            }
+        })
+    })
+    .detach_and_log_err(cx);
+}
+
+/// The diagnostic under the cursor: the most severe, and then the narrowest, of those
+/// that cover it.
+fn diagnostic_under_cursor(
+    editor: &View<Editor>,
+    cx: &AppContext,
+) -> Option<(Model<Buffer>, DiagnosticEntry<usize>)> {
+    let editor = editor.read(cx);
+    let buffer = editor.buffer().read(cx).as_singleton()?;
+    let cursor = editor.selections.newest::<usize>(cx).head();
+    let diagnostic = buffer
+        .read(cx)
+        .snapshot()
+        .diagnostics_in_range::<_, usize>(cursor..cursor, false)
+        .min_by_key(|entry| (entry.diagnostic.severity, entry.range.len()))?;
+    Some((buffer, diagnostic))
+}
+
+/// Asks for the code actions at the diagnostic under the cursor, keeping those that
+/// fix it.
+fn fixes_under_cursor(
+    editor: &View<Editor>,
+    cx: &mut WindowContext,
+) -> Option<(Model<Buffer>, Task<Result<Vec<CodeAction>>>)> {
+    let (buffer, diagnostic) = diagnostic_under_cursor(editor, cx)?;
+    let project = editor.read(cx).project()?.clone();
+    let actions = project.update(cx, |project, cx| {
+        project.code_actions(&buffer, diagnostic.range, cx)
+    });
+    let message = diagnostic.diagnostic.message;
+    let fixes = cx.spawn(|_| async move {
+        let actions = actions.await?;
+        Ok(actions
+            .into_iter()
+            .filter(|action| fixes_diagnostic(action, &message))
+            .collect())
+    });
+    Some((buffer, fixes))
+}
+
+/// Whether the code action fixes the diagnostic: those that say which diagnostics they
+/// fix are matched by its message, and otherwise it has to be a quick fix.
+fn fixes_diagnostic(action: &CodeAction, message: &str) -> bool {
+    match &action.lsp_action.diagnostics {
+        Some(diagnostics) if !diagnostics.is_empty() => diagnostics
+            .iter()
+            .any(|diagnostic| diagnostic.message == message),
+        _ => action.lsp_action.kind.as_ref() == Some(&lsp::CodeActionKind::QUICKFIX),
+    }
+}
+
+/// Applies the fix, and records it for `.` if all it does is change this buffer. A fix
+/// that runs a command on the server, or changes other files, isn't repeated.
+fn apply_fix(
+    editor: &View<Editor>,
+    buffer: Model<Buffer>,
+    fix: CodeAction,
+    cx: &mut WindowContext,
+) {
+    let Some(project) = editor.read(cx).project().cloned() else {
+        return;
+    };
+    let title = fix.lsp_action.title.clone();
+    let runs_command = fix.lsp_action.command.is_some();
+    let apply = project.update(cx, |project, cx| {
+        project.apply_code_action(buffer.clone(), fix, true, cx)
+    });
+    cx.spawn(|mut cx| async move {
+        let transaction = apply.await?;
+        cx.update(|cx| {
+            let only_here = transaction.0.keys().all(|edited| *edited == buffer);
+            if runs_command || !only_here {
+                return;
+            }
+            Vim::update(cx, |vim, _| {
+                let state = &mut vim.workspace_state;
+                state.recorded_actions =
+                    vec![ReplayableAction::Action(Box::new(RepeatFix { title }))];
+                state.recorded_count = None;
+                state.recorded_selection = RecordedSelection::None;
+            })
+        })
+    })
+    .detach_and_log_err(cx);
+}
+
+pub struct FixList {
+    picker: View<Picker<FixListDelegate>>,
+}
+
+impl FixList {
+    fn new(
+        editor: View<Editor>,
+        buffer: Model<Buffer>,
+        fixes: Vec<CodeAction>,
+        cx: &mut ViewContext<Self>,
+    ) -> Self {
+        let candidates = fixes
+            .iter()
+            .enumerate()
+            .map(|(id, fix)| StringMatchCandidate::new(id, fix.lsp_action.title.clone()))
+            .collect();
+        let delegate = FixListDelegate {
+            fix_list: cx.view().downgrade(),
+            editor,
+            buffer,
+            fixes,
+            candidates,
+            matches: Vec::new(),
+            selected_index: 0,
+        };
+        let picker = cx.new_view(|cx| Picker::uniform_list(delegate, cx));
+        Self { picker }
+    }
+}
+
+impl Render for FixList {
+    fn render(&mut self, _cx: &mut ViewContext<Self>) -> impl IntoElement {
+        v_flex().w(rems(34.)).child(self.picker.clone())
+    }
+}
+
+impl FocusableView for FixList {
+    fn focus_handle(&self, cx: &AppContext) -> FocusHandle {
+        self.picker.focus_handle(cx)
+    }
+}
+
+impl EventEmitter<DismissEvent> for FixList {}
+impl ModalView for FixList {}
+
+pub struct FixListDelegate {
+    fix_list: WeakView<FixList>,
+    editor: View<Editor>,
+    /// The buffer the diagnostic is in
+    buffer: Model<Buffer>,
+    fixes: Vec<CodeAction>,
+    candidates: Vec<StringMatchCandidate>,
+    matches: Vec<StringMatch>,
+    selected_index: usize,
+}
+
+impl ModalDelegate for FixListDelegate {
+    type Modal = FixList;
+
+    fn modal(&self) -> &WeakView<FixList> {
+        &self.fix_list
+    }
+}
+
+impl StringListDelegate for FixListDelegate {
+    fn candidates(&self) -> &[StringMatchCandidate] {
+        &self.candidates
+    }
+
+    fn set_matches(&mut self, matches: Vec<StringMatch>) {
+        self.matches = matches;
+    }
+}
+
+impl PickerDelegate for FixListDelegate {
+    type ListItem = ListItem;
+
+    fn placeholder_text(&self, _cx: &mut WindowContext) -> Arc<str> {
+        "Fix with...".into()
+    }
+
+    fn match_count(&self) -> usize {
+        self.matches.len()
+    }
+
+    fn selected_index(&self) -> usize {
+        self.selected_index
+    }
+
+    fn set_selected_index(&mut self, ix: usize, _: &mut ViewContext<Picker<Self>>) {
+        self.selected_index = ix;
+    }
+
+    fn update_matches(&mut self, query: String, cx: &mut ViewContext<Picker<Self>>) -> Task<()> {
+        list_picker::update_matches(self, query, cx)
+    }
+
+    fn confirm(&mut self, _: bool, cx: &mut ViewContext<Picker<Self>>) {
+        if let Some(mat) = self.matches.get(self.selected_index) {
+            let fix = self.fixes[mat.candidate_id].clone();
+            apply_fix(&self.editor, self.buffer.clone(), fix, cx);
+            self.editor.update(cx, |editor, cx| editor.focus(cx));
+        }
+        self.dismissed(cx);
+    }
+
+    fn dismissed(&mut self, cx: &mut ViewContext<Picker<Self>>) {
+        list_picker::dismiss(self, cx)
+    }
+
+    fn render_match(
+        &self,
+        ix: usize,
+        selected: bool,
+        _: &mut ViewContext<Picker<Self>>,
+    ) -> Option<Self::ListItem> {
+        let mat = &self.matches[ix];
+        Some(
+            ListItem::new(ix)
+                .inset(true)
+                .spacing(ListItemSpacing::Sparse)
+                .selected(selected)
+                .child(HighlightedLabel::new(
+                    mat.string.clone(),
+                    mat.positions.clone(),
+                )),
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use editor::test::editor_lsp_test_context::EditorLspTestContext;
+
+    use super::FixList;
+    use crate::{state::Mode, test::VimTestContext};
+
+    #[gpui::test]
+    async fn test_pick_fix(cx: &mut gpui::TestAppContext) {
+        VimTestContext::init(cx);
+        let cx = EditorLspTestContext::new_rust(
+            lsp::ServerCapabilities {
+                code_action_provider: Some(lsp::CodeActionProviderCapability::Simple(true)),
+                ..Default::default()
+            },
+            cx,
+        )
+        .await;
+        let mut cx = VimTestContext::new_with_lsp(cx, true);
+
+        cx.set_state("let ˇa = 1;\nlet b = 2;", Mode::Normal);
+        cx.simulate_keystrokes(["z", "q"]);
+        assert_eq!(
+            cx.message().as_deref(),
+            Some("No diagnostic under the cursor")
+        );
+
+        let unused = |row| lsp::Diagnostic {
+            range: lsp::Range::new(lsp::Position::new(row, 4), lsp::Position::new(row, 5)),
+            severity: Some(lsp::DiagnosticSeverity::WARNING),
+            message: "unused variable".to_string(),
+            ..Default::default()
+        };
+        cx.lsp
+            .notify::<lsp::notification::PublishDiagnostics>(lsp::PublishDiagnosticsParams {
+                uri: lsp::Url::from_file_path("/root/dir/file.rs").unwrap(),
+                version: None,
+                diagnostics: vec![unused(0), unused(1)],
+            });
+        cx.run_until_parked();
+
+        // the fix renames the variable at the diagnostic it's asked about
+        cx.lsp
+            .handle_request::<lsp::request::CodeActionRequest, _, _>(|params, _| async move {
+                let fix = lsp::CodeAction {
+                    title: "Prefix with an underscore".to_string(),
+                    kind: Some(lsp::CodeActionKind::QUICKFIX),
+                    diagnostics: Some(params.context.diagnostics.clone()),
+                    edit: Some(lsp::WorkspaceEdit {
+                        changes: Some(
+                            [(
+                                params.text_document.uri.clone(),
+                                vec![lsp::TextEdit::new(
+                                    lsp::Range::new(params.range.start, params.range.start),
+                                    "_".to_string(),
+                                )],
+                            )]
+                            .into_iter()
+                            .collect(),
+                        ),
+                        ..Default::default()
+                    }),
+                    ..Default::default()
+                };
+                let refactor = lsp::CodeAction {
+                    title: "Inline variable".to_string(),
+                    kind: Some(lsp::CodeActionKind::REFACTOR_INLINE),
+                    ..Default::default()
+                };
+                Ok(Some(vec![
+                    lsp::CodeActionOrCommand::CodeAction(refactor),
+                    lsp::CodeActionOrCommand::CodeAction(fix),
+                ]))
+            });
+
+        // only the fixes are listed
+        cx.simulate_keystrokes(["z", "q"]);
+        cx.run_until_parked();
+        let titles = cx.workspace(|workspace, cx| {
+            let fix_list = workspace.active_modal::<FixList>(cx).unwrap();
+            let picker = fix_list.read(cx).picker.read(cx);
+            picker
+                .delegate
+                .matches
+                .iter()
+                .map(|mat| mat.string.clone())
+                .collect::<Vec<_>>()
+        });
+        assert_eq!(titles, ["Prefix with an underscore"]);
+        cx.simulate_keystrokes(["enter"]);
+        cx.run_until_parked();
+        assert_eq!(
+            cx.editor(|editor, cx| editor.text(cx)),
+            "let _a = 1;\nlet b = 2;"
+        );
+
+        // and . applies the same fix to the diagnostic under the cursor
+        cx.simulate_keystrokes(["j", "0", "w", "."]);
+        cx.run_until_parked();
+        assert_eq!(
+            cx.editor(|editor, cx| editor.text(cx)),
+            "let _a = 1;\nlet _b = 2;"
+        );
+    }
+}
@@ -0,0 +1,202 @@
+use std::sync::Arc;
+
+use editor::{Editor, MultiBufferSnapshot, ToOffset};
+use gpui::{AppContext, ViewContext, WindowContext};
+use language::{Language, Point};
+use settings::Settings;
+
+use crate::{normal::rewrap::CommentStyle, state::Mode, utils::screen_width, Vim, VimSettings};
+
+/// The `formatoptions` when they aren't set: vim's, without the `r` that's left to
+/// the editor's `extend_comment_on_newline` setting.
+const DEFAULT_FORMATOPTIONS: &str = "tcqj";
+
+/// The `formatoptions` and `textwidth` settings for a language: how comments are
+/// continued on new lines, and when text is wrapped.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(crate) struct FormatOptions {
+    /// `t`: typing past the `textwidth` wraps the line
+    pub wrap_text: bool,
+    /// `c`: typing past the `textwidth` in a comment wraps it, continuing the comment
+    pub wrap_comments: bool,
+    /// `r`: enter in insert mode continues a comment. `None` when `formatoptions`
+    /// isn't set, for the editor's `extend_comment_on_newline` setting to decide.
+    pub continue_on_enter: Option<bool>,
+    /// `o`: `o` and `O` continue a comment
+    pub continue_on_open: bool,
+    /// `q`: `gq` rewraps comments
+    pub format_comments: bool,
+    /// `j`: `J` drops the comment leader of the line it joins
+    pub join_comments: bool,
+    /// The column to wrap at, or 0 for none
+    pub textwidth: u32,
+}
+
+impl FormatOptions {
+    /// The options for the language, from its entry in the `languages` setting where
+    /// it has one.
+    pub(crate) fn new(language: Option<&Arc<Language>>, cx: &AppContext) -> Self {
+        let settings = VimSettings::get_global(cx);
+        let overrides = settings.language_options(language);
+        let flags = overrides
+            .and_then(|overrides| overrides.formatoptions.as_deref())
+            .or(settings.formatoptions.as_deref());
+        let continue_on_enter = flags.map(|flags| flags.contains('r'));
+        let flags = flags.unwrap_or(DEFAULT_FORMATOPTIONS);
+        let textwidth = overrides
+            .and_then(|overrides| overrides.textwidth)
+            .unwrap_or(settings.textwidth);
+        Self {
+            wrap_text: flags.contains('t'),
+            wrap_comments: flags.contains('c'),
+            continue_on_enter,
+            continue_on_open: flags.contains('o'),
+            format_comments: flags.contains('q'),
+            join_comments: flags.contains('j'),
+            textwidth,
+        }
+    }
+
+    /// The options for the language at the point.
+    pub(crate) fn at<T: ToOffset>(buffer: &MultiBufferSnapshot, point: T, cx: &AppContext) -> Self {
+        Self::new(buffer.language_at(point), cx)
+    }
+}
+
+/// Has enter continue comments (or not) by the options of the language at the
+/// newest cursor, leaving it to the editor's setting when `formatoptions` isn't set.
+pub(crate) fn sync_continue_on_enter(editor: &mut Editor, cx: &mut ViewContext<Editor>) {
+    let buffer = editor.buffer().read(cx).snapshot(cx);
+    let head = editor.selections.newest::<usize>(cx).head();
+    editor.set_extend_comment_on_newline(FormatOptions::at(&buffer, head, cx).continue_on_enter);
+}
+
+/// The comment leader (without the indent) to start a line opened next to the row with
+/// `o` (or `O`, when `above`) when the row is a comment, like vim's `o` flag.
+pub(crate) fn open_line_leader(
+    buffer: &MultiBufferSnapshot,
+    row: u32,
+    above: bool,
+    cx: &AppContext,
+) -> String {
+    let indent = Point::new(row, buffer.indent_size_for_line(row).len);
+    if !FormatOptions::at(buffer, indent, cx).continue_on_open {
+        return String::new();
+    }
+    let line = buffer
+        .text_for_range(Point::new(row, 0)..Point::new(row, buffer.line_len(row)))
+        .collect::<String>();
+    let comments = CommentStyle::new(buffer.language_scope_at(indent));
+    let (leader, continuation, _) = comments.split(&line, comments.in_block(buffer, row));
+    if leader.trim().is_empty() {
+        return String::new();
+    }
+    // above a comment's first line, the new line starts the comment instead
+    if above {
+        leader.trim_start().to_string()
+    } else {
+        continuation.trim_start().to_string()
+    }
+}
+
+/// Wraps the lines that typing in insert mode took past the `textwidth`, like vim's
+/// `t` and `c` flags.
+pub(crate) fn wrap_on_input(text: &str, cx: &mut WindowContext) {
+    if text.contains('\n') {
+        return;
+    }
+    Vim::update(cx, |vim, cx| {
+        if vim.state().mode != Mode::Insert {
+            return;
+        }
+        vim.update_active_editor(cx, |_, editor, cx| wrap_lines(editor, cx));
+    });
+}
+
+/// Breaks each cursor's line at the last space that leaves the text before it within
+/// the `textwidth` (or the first space, when a word is longer than that), starting the
+/// new line with the indent and any comment leader.
+fn wrap_lines(editor: &mut Editor, cx: &mut ViewContext<Editor>) {
+    let buffer = editor.buffer().read(cx).snapshot(cx);
+    let mut edits = Vec::new();
+    for selection in editor.selections.all::<Point>(cx) {
+        let cursor = selection.head();
+        if !selection.is_empty() {
+            continue;
+        }
+        let options = FormatOptions::at(&buffer, cursor, cx);
+        if options.textwidth == 0 {
+            continue;
+        }
+        let tab_size = buffer.settings_at(cursor, cx).tab_size.get() as usize;
+        let line = buffer
+            .text_for_range(Point::new(cursor.row, 0)..cursor)
+            .collect::<String>();
+        if screen_width(&line, tab_size) <= options.textwidth as usize {
+            continue;
+        }
+        let indent = buffer.indent_size_for_line(cursor.row).len;
+        let comments = CommentStyle::new(buffer.language_scope_at(Point::new(cursor.row, indent)));
+        let (leader, continuation, _) =
+            comments.split(&line, comments.in_block(&buffer, cursor.row));
+        let is_comment = !leader.trim().is_empty();
+        if !(if is_comment {
+            options.wrap_comments
+        } else {
+            options.wrap_text
+        }) {
+            continue;
+        }
+        let Some(space) = break_at(&line, leader.len(), options.textwidth as usize, tab_size)
+        else {
+            continue;
+        };
+        edits.push((
+            Point::new(cursor.row, space.start as u32)..Point::new(cursor.row, space.end as u32),
+            format!("\n{continuation}"),
+        ));
+    }
+    if !edits.is_empty() {
+        editor.edit(edits, cx);
+    }
+}
+
+/// The run of spaces after `start` to break the line at: the last one that starts
+/// within `width` columns, or else the first one. `None` when the text after the last
+/// run is blank, so there's no word to move to the new line yet.
+fn break_at(
+    line: &str,
+    start: usize,
+    width: usize,
+    tab_size: usize,
+) -> Option<std::ops::Range<usize>> {
+    let mut spaces = Vec::new();
+    let mut run_start = None;
+    for (ix, c) in line.char_indices().skip_while(|(ix, _)| *ix < start) {
+        match (c.is_whitespace(), run_start) {
+            (true, None) => run_start = Some(ix),
+            (false, Some(run)) => {
+                spaces.push(run..ix);
+                run_start = None;
+            }
+            _ => {}
+        }
+    }
+    spaces
+        .iter()
+        .rev()
+        .find(|space| screen_width(&line[..space.start], tab_size) <= width)
+        .or(spaces.first())
+        .cloned()
+}
+
+#[cfg(test)]
+mod test {
+    #[test]
+    fn test_break_at() {
+        assert_eq!(super::break_at("one two three", 0, 8, 4), Some(7..8));
+        assert_eq!(super::break_at("one two three", 0, 2, 4), Some(3..4));
+        assert_eq!(super::break_at("// one two", 3, 4, 4), Some(6..7));
+        assert_eq!(super::break_at("onetwothree ", 0, 4, 4), None);
+    }
+}
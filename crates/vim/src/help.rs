@@ -0,0 +1,412 @@
+use editor::{scroll::Autoscroll, Editor};
+use gpui::{impl_actions, Action, ViewContext, VisualContext, WindowContext};
+use language::Point;
+use serde_derive::Deserialize;
+use workspace::{SplitDirection, Workspace};
+
+use crate::{command::parse_command, key_notation::key_notation, Vim};
+
+/// Opens vim mode's help at a topic (`:help {topic}`), like an ex command or an
+/// action. The help is generated from the commands and actions vim mode has, so it
+/// lists what works, and it's opened read-only in a split above, to be searched
+/// and read with the usual keys.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct Help {
+    pub topic: String,
+}
+
+impl_actions!(vim, [Help]);
+
+/// The ex commands `:help` describes, as they're typed. Each is parsed to find the
+/// command and the action it runs, and the ones that aren't available are left out.
+const EX_COMMANDS: &[(&str, &str)] = &[
+    ("write", "Save the file"),
+    ("write!", "Save the file, even if it changed on disk"),
+    (
+        "write file",
+        "Save the buffer to another file, as a copy if it has its own (:w! file overwrites it)",
+    ),
+    (
+        "write !cmd",
+        "Write the lines to a shell command's input, as in :w !sudo tee % or :'<,'>w !pbcopy",
+    ),
+    ("update", "Save the file if it has changes"),
+    (
+        "wall",
+        "Save every file (:wall! even if they changed on disk)",
+    ),
+    ("quit", "Close the file, asking to save its changes"),
+    ("quit!", "Close the file without saving it"),
+    (
+        "wq",
+        "Save the file and close it (:wq! even if it changed on disk)",
+    ),
+    (
+        "exit",
+        "Save the file if it has changes, and close it (or :exit!)",
+    ),
+    ("quitall", "Close every file, asking to save their changes"),
+    ("quitall!", "Close every file without saving them"),
+    (
+        "xall",
+        "Save every file that has changes, and close them (or :xall!)",
+    ),
+    (
+        "wqall",
+        "Save every file and close them (:wqall! even if they changed on disk)",
+    ),
+    (
+        "cquit",
+        "Quit, or close every file without saving them to make a zed --wait waiting for one fail",
+    ),
+    (
+        "stop",
+        "Minimize the window, or hide the app (see `suspend`)",
+    ),
+    (
+        "file name",
+        "Rename the buffer, to be written there (:file! name moves the file, :0file clears it)",
+    ),
+    ("split", "Split the pane up"),
+    ("vsplit", "Split the pane left"),
+    ("new", "Open a new file in a split above"),
+    ("vnew", "Open a new file in a split to the left"),
+    ("pclose", "Close the preview tab"),
+    ("b 2", "Go to a buffer, numbering the pane's tabs from 1"),
+    ("b#", "Go to the buffer that was active before this one"),
+    ("bfirst", "Go to the first buffer"),
+    ("blast", "Go to the last buffer"),
+    (
+        "bnext",
+        "Go to the next buffer (:bprevious goes to the previous one)",
+    ),
+    (
+        "edit file",
+        "Open a file in the pane (:e! file discards the changes to the current one)",
+    ),
+    (
+        "bdelete",
+        "Close the buffer, or the buffers numbered, as in :bd 2 3 or :2,3bd",
+    ),
+    ("tabedit", "Open a new file (or :tabnew, or :enew)"),
+    ("tabnext", "Go to the next tab"),
+    ("tabprevious", "Go to the previous tab (or :tabNext)"),
+    ("tabclose", "Close the tab"),
+    (
+        "tabonly",
+        "Close the other tabs (:tabonly! without saving them)",
+    ),
+    ("only", "Close the other panes (:only! without saving them)"),
+    ("clist", "Show the diagnostics"),
+    ("cc", "Show the diagnostic under the cursor (or :ll)"),
+    ("cnext", "Go to the next entry of the quickfix list"),
+    (
+        "cprevious",
+        "Go to the previous entry of the quickfix list (or :cNext)",
+    ),
+    (
+        "cdo command",
+        "Run a command at each entry of the quickfix list (:cfdo, in each file)",
+    ),
+    (
+        "make",
+        "Run `makeprg`, and read its errors into the quickfix list",
+    ),
+    ("lopen", "Show the location list"),
+    ("lnext", "Go to the next entry of the location list"),
+    (
+        "lprevious",
+        "Go to the previous entry of the location list (or :lNext)",
+    ),
+    (
+        "ltag name",
+        "List the definitions of a symbol in the location list",
+    ),
+    (
+        "tag name",
+        "Go to a symbol of the project, pushing the tag stack (or :symbol name)",
+    ),
+    (
+        "tselect name",
+        "Pick from the symbols of the project called name (:tselect picks from the last tag's)",
+    ),
+    (
+        "wsymbol query",
+        "Fill the quickfix list with the symbols of the project that match (:ws! picks one)",
+    ),
+    (
+        "pop",
+        "Go back to where the last jump to a tag (ctrl-]) was made from",
+    ),
+    ("join", "Join lines"),
+    ("delete", "Delete lines"),
+    ("sort", "Sort lines"),
+    ("reverse", "Reverse the order of lines"),
+    (
+        "put a",
+        "Put a register on lines of its own below the cursor (:put! above)",
+    ),
+    (
+        "read !cmd",
+        "Insert the output of a shell command below the cursor",
+    ),
+    (
+        "!cmd",
+        "Filter lines through a shell command, as in :%!sort or :'<,'>!column -t",
+    ),
+    ("normal keys", "Type the keys as normal mode commands"),
+    (
+        "g/pattern/command",
+        "Run a command on every line that matches",
+    ),
+    (
+        "v/pattern/command",
+        "Run a command on every line that doesn't match",
+    ),
+    ("s/pattern/replacement/", "Replace a pattern"),
+    ("/pattern", "Search forward"),
+    ("?pattern", "Search backward"),
+    (
+        "nohlsearch",
+        "Stop highlighting the matches of the last search",
+    ),
+    ("42", "Go to a line"),
+    ("$", "Go to the last line (or :%, and :0 goes to the first)"),
+    ("ascii", "Show the character under the cursor"),
+    ("undolist", "List the branches of the undo tree"),
+    (
+        "view file",
+        "Open a file read-only (:view on its own makes the buffer read-only)",
+    ),
+    (
+        "set nomodifiable",
+        "Make the buffer read-only (:set modifiable undoes it)",
+    ),
+    (
+        "Rename name",
+        "Rename the symbol under the cursor across the project",
+    ),
+    ("registers", "Show the registers"),
+    ("messages", "Show the messages shown so far"),
+    ("delmarks a", "Delete marks"),
+    ("help topic", "Open this help at a topic"),
+    ("Tutor", "Open the tutor, whose lessons go through vim mode"),
+    (
+        "Explore",
+        "Focus the project panel (or :Sexplore, :Vexplore, :Hexplore, :Lexplore)",
+    ),
+    ("term", "Focus the terminal (or :Term)"),
+    ("Collab", "Focus the collaboration panel"),
+    ("Chat", "Focus the chat"),
+    ("Notifications", "Focus the notifications"),
+    ("AI", "Focus the assistant"),
+];
+
+pub(crate) fn register(workspace: &mut Workspace, _: &mut ViewContext<Workspace>) {
+    workspace.register_action(help);
+}
+
+fn help(workspace: &mut Workspace, action: &Help, cx: &mut ViewContext<Workspace>) {
+    let text = help_text(workspace, cx);
+    let topic = action.topic.trim().trim_start_matches(':');
+    let Some(row) = topic_row(&text, topic, cx) else {
+        Vim::update(cx, |vim, _| {
+            vim.show_message(format!("E149: Sorry, no help for {topic}"))
+        });
+        return;
+    };
+
+    let project = workspace.project().clone();
+    let Ok(buffer) = project.update(cx, |project, cx| project.create_buffer(&text, None, cx))
+    else {
+        return;
+    };
+    let editor = cx.new_view(|cx| {
+        let mut editor = Editor::for_buffer(buffer, Some(project), cx);
+        editor.set_read_only(true);
+        editor.change_selections(Some(Autoscroll::focused()), cx, |s| {
+            s.select_ranges([Point::new(row, 0)..Point::new(row, 0)])
+        });
+        editor
+    });
+    workspace.split_item(SplitDirection::Up, Box::new(editor), cx);
+}
+
+/// The help: the ex commands, with what they do and the action they run, and then
+/// the actions of vim mode, with the keys they're bound to in the active editor.
+fn help_text(workspace: &Workspace, cx: &WindowContext) -> String {
+    let mut text = String::from(
+        "VIM MODE\n\n\
+         This lists the commands and actions vim mode has. Search it with / and\n\
+         open it at a topic with :help {topic}, like :help sort.\n\n\
+         EX COMMANDS\n\n\
+         Type these after :. Most can be shortened as in vim, like :w for :write.\n\n",
+    );
+    for (command, description) in EX_COMMANDS {
+        if let Some((_, action)) = parse_command(command, cx) {
+            text.push_str(&format!(
+                ":{command:<24}{description:<56}{}\n",
+                action.name()
+            ));
+        }
+    }
+
+    text.push_str("\nACTIONS\n\nThe actions of vim mode, and the keys that run them here.\n\n");
+    let focus = workspace
+        .active_item_as::<Editor>(cx)
+        .map(|editor| editor.focus_handle(cx));
+    let mut names = cx
+        .all_action_names()
+        .iter()
+        .filter(|name| name.starts_with("vim::"))
+        .cloned()
+        .collect::<Vec<_>>();
+    names.sort();
+    for name in names {
+        let keys = cx
+            .build_action(&name, None)
+            .ok()
+            .zip(focus.as_ref())
+            .map(|(action, focus)| {
+                cx.bindings_for_action_in(action.as_ref(), focus)
+                    .iter()
+                    .map(|binding| key_notation(binding.keystrokes()))
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            })
+            .unwrap_or_default();
+        text.push_str(format!("{name:<40}{keys}").trim_end());
+        text.push('\n');
+    }
+    text
+}
+
+/// The line the help is opened at for a topic: the command it names (which can be
+/// shortened), or the command or action it's the start of, or else the first line
+/// that mentions it.
+fn topic_row(text: &str, topic: &str, cx: &WindowContext) -> Option<u32> {
+    if topic.is_empty() {
+        return Some(0);
+    }
+    let name = parse_command(topic, cx).map(|(name, _)| format!(":{name}"));
+    let entries = text
+        .lines()
+        .enumerate()
+        .filter_map(|(row, line)| Some((row as u32, line.split_whitespace().next()?)))
+        .collect::<Vec<_>>();
+    let is_topic = |entry: &str| {
+        name.as_deref() == Some(entry)
+            || entry.trim_start_matches(':') == topic
+            || entry.strip_prefix("vim::") == Some(topic)
+    };
+    let starts_with_topic = |entry: &str| {
+        entry.trim_start_matches(':').starts_with(topic)
+            || entry
+                .strip_prefix("vim::")
+                .is_some_and(|action| action.starts_with(topic))
+    };
+    let topic_lowercase = topic.to_lowercase();
+    entries
+        .iter()
+        .find(|(_, entry)| is_topic(entry))
+        .or_else(|| entries.iter().find(|(_, entry)| starts_with_topic(entry)))
+        .map(|(row, _)| *row)
+        .or_else(|| {
+            text.lines()
+                .position(|line| line.to_lowercase().contains(&topic_lowercase))
+                .map(|row| row as u32)
+        })
+}
+
+pub(crate) fn parse_help(query: &str) -> Option<Help> {
+    let name_len = query
+        .find(|c: char| !c.is_ascii_alphabetic())
+        .unwrap_or(query.len());
+    let (name, topic) = query.split_at(name_len);
+    if !matches!(name, "h" | "he" | "hel" | "help") || !(topic.is_empty() || topic.starts_with(' '))
+    {
+        return None;
+    }
+    Some(Help {
+        topic: topic.trim().to_string(),
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use editor::Editor;
+    use regex::Regex;
+
+    use super::{parse_help, EX_COMMANDS};
+    use crate::{state::Mode, test::VimTestContext};
+
+    #[test]
+    fn test_parse_help() {
+        assert_eq!(parse_help("h").unwrap().topic, "");
+        assert_eq!(parse_help("help :sort").unwrap().topic, ":sort");
+        assert!(parse_help("helpx").is_none());
+        assert!(parse_help("hide").is_none());
+    }
+
+    #[test]
+    fn test_ex_commands_have_help() {
+        // every command parse_command knows by name is listed, or mentioned in the
+        // description of the command it's a variant of
+        let source = include_str!("command.rs");
+        let start = source.find("pub(crate) fn parse_command").unwrap();
+        let end = start + source[start..].find("\n}\n").unwrap();
+        let names = Regex::new(r#"(?m)(?:=>\s*\{?\s*|^\s*)\(\s*"([^"]+)","#).unwrap();
+        for captures in names.captures_iter(&source[start..end]) {
+            let name = &captures[1];
+            let mention = format!(":{name}");
+            let documented = EX_COMMANDS.iter().any(|(command, description)| {
+                command.strip_prefix(name).is_some_and(|rest| {
+                    rest.is_empty()
+                        || rest.starts_with(' ')
+                        || !name.ends_with(char::is_alphanumeric)
+                }) || description.match_indices(&mention).any(|(ix, _)| {
+                    !description[ix + mention.len()..]
+                        .starts_with(|c: char| c.is_alphanumeric() || c == '!')
+                })
+            });
+            assert!(documented, ":{name} isn't in EX_COMMANDS");
+        }
+    }
+
+    #[gpui::test]
+    async fn test_help(cx: &mut gpui::TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+
+        cx.set_state("ˇone", Mode::Normal);
+        cx.simulate_keystrokes([":"]);
+        cx.simulate_input("help sor");
+        cx.simulate_keystrokes(["enter"]);
+        cx.run_until_parked();
+        cx.workspace(|workspace, cx| {
+            assert_eq!(workspace.panes().len(), 2);
+            let editor = workspace.active_item_as::<Editor>(cx).unwrap();
+            let editor = editor.read(cx);
+            assert!(editor.read_only(cx));
+            let text = editor.text(cx);
+            let cursor = editor.selections.newest::<language::Point>(cx).head();
+            let line = text.lines().nth(cursor.row as usize).unwrap();
+            assert!(line.starts_with(":sort "));
+            assert!(line.ends_with("vim::Sort"));
+            // the keys of actions are listed
+            assert!(text.lines().any(|line| line.starts_with("vim::JoinLines ")
+                && line.split_whitespace().any(|keys| keys == "J")));
+        });
+        // it's read with vim's keys
+        cx.simulate_keystrokes(["j"]);
+        assert_eq!(cx.mode(), Mode::Normal);
+
+        cx.simulate_keystrokes([":"]);
+        cx.simulate_input("help nosuchthing");
+        cx.simulate_keystrokes(["enter"]);
+        cx.run_until_parked();
+        assert_eq!(
+            cx.message().as_deref(),
+            Some("E149: Sorry, no help for nosuchthing")
+        );
+    }
+}
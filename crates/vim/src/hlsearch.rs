@@ -0,0 +1,194 @@
+use std::sync::Arc;
+
+use editor::Editor;
+use gpui::{actions, View, ViewContext, WindowContext};
+use search::BufferSearchBar;
+use workspace::{searchable::SearchableItem, Pane, Workspace};
+
+use crate::Vim;
+
+actions!(vim, [NoHighlightSearch]);
+
+pub(crate) fn register(workspace: &mut Workspace, _: &mut ViewContext<Workspace>) {
+    workspace.register_action(|workspace: &mut Workspace, _: &NoHighlightSearch, cx| {
+        clear_highlights(workspace, cx)
+    });
+}
+
+/// Stops highlighting the last search in every editor, until the next search (or `n`
+/// or `N`), as `:nohlsearch` does.
+pub(crate) fn clear_highlights(workspace: &mut Workspace, cx: &mut ViewContext<Workspace>) {
+    Vim::update(cx, |vim, _| {
+        vim.workspace_state.hlsearch = None;
+        vim.workspace_state.hlsearch_synced.clear();
+    });
+    for pane in workspace.panes() {
+        let editors = pane
+            .read(cx)
+            .items()
+            .filter_map(|item| item.act_as::<Editor>(cx))
+            .collect::<Vec<_>>();
+        for editor in editors {
+            editor.update(cx, |editor, cx| editor.clear_matches(cx));
+        }
+    }
+}
+
+/// Makes the active pane's search the one to highlight, and highlights it in the
+/// other panes' editors too.
+pub(crate) fn highlight_search(workspace: &mut Workspace, cx: &mut ViewContext<Workspace>) {
+    let query = workspace
+        .active_pane()
+        .read(cx)
+        .toolbar()
+        .read(cx)
+        .item_of_type::<BufferSearchBar>()
+        .and_then(|search_bar| search_bar.read(cx).active_search());
+    if query.is_none() {
+        return;
+    }
+    Vim::update(cx, |vim, _| vim.workspace_state.hlsearch = query);
+    let panes = workspace.panes().to_vec();
+    sync_highlights(&panes, cx);
+}
+
+/// After `n` or `N`, which highlight the matches in the active editor again, does the
+/// same in the other panes.
+pub(crate) fn search_repeated(cx: &mut WindowContext) {
+    if Vim::read(cx).workspace_state.hlsearch.is_some() {
+        return;
+    }
+    if let Some(workspace) = active_workspace(cx) {
+        workspace.update(cx, highlight_search);
+    }
+}
+
+/// Dismissing the search bar stops highlighting the search in the other panes too,
+/// as it does in its own.
+pub(crate) fn search_bar_dismissed(cx: &mut WindowContext) {
+    if Vim::read(cx).workspace_state.hlsearch.is_none() {
+        return;
+    }
+    if let Some(workspace) = active_workspace(cx) {
+        workspace.update(cx, clear_highlights);
+    }
+}
+
+fn active_workspace(cx: &WindowContext) -> Option<View<Workspace>> {
+    Vim::read(cx)
+        .active_editor
+        .as_ref()
+        .and_then(|editor| editor.upgrade())
+        .and_then(|editor| editor.read(cx).workspace())
+}
+
+/// Highlights the search in the editor each pane shows, unless the pane's search bar
+/// is open, since that highlights its own matches. Editors whose buffer hasn't changed
+/// since they were last highlighted aren't searched again.
+pub(crate) fn sync_highlights(panes: &[View<Pane>], cx: &mut WindowContext) {
+    let Some(query) = Vim::read(cx).workspace_state.hlsearch.clone() else {
+        return;
+    };
+    for pane in panes {
+        let pane = pane.read(cx);
+        let searching = pane
+            .toolbar()
+            .read(cx)
+            .item_of_type::<BufferSearchBar>()
+            .is_some_and(|search_bar| !search_bar.read(cx).is_dismissed());
+        let Some(editor) = pane
+            .active_item()
+            .and_then(|item| item.act_as::<Editor>(cx))
+        else {
+            continue;
+        };
+        let editor_id = editor.entity_id();
+        if searching {
+            Vim::update(cx, |vim, _| {
+                vim.workspace_state.hlsearch_synced.remove(&editor_id)
+            });
+            continue;
+        }
+        let edit_count = editor.read(cx).buffer().read(cx).snapshot(cx).edit_count();
+        let synced = Vim::read(cx)
+            .workspace_state
+            .hlsearch_synced
+            .get(&editor_id)
+            .is_some_and(|(synced_query, synced_count)| {
+                Arc::ptr_eq(synced_query, &query) && *synced_count == edit_count
+            });
+        if synced {
+            continue;
+        }
+        Vim::update(cx, |vim, _| {
+            vim.workspace_state
+                .hlsearch_synced
+                .insert(editor_id, (query.clone(), edit_count))
+        });
+        let matches = editor.update(cx, |editor, cx| editor.find_matches(query.clone(), cx));
+        let editor = editor.downgrade();
+        cx.spawn(|mut cx| async move {
+            let matches = matches.await;
+            editor.update(&mut cx, |editor, cx| editor.update_matches(matches, cx))
+        })
+        .detach_and_log_err(cx);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use editor::Editor;
+
+    use crate::{state::Mode, test::VimTestContext};
+
+    fn highlight_counts(cx: &mut VimTestContext) -> Vec<usize> {
+        let editors = cx.workspace(|workspace, cx| {
+            workspace
+                .panes()
+                .iter()
+                .filter_map(|pane| pane.read(cx).active_item()?.act_as::<Editor>(cx))
+                .collect::<Vec<_>>()
+        });
+        editors
+            .into_iter()
+            .map(|editor| {
+                cx.update(|cx| {
+                    editor.update(cx, |editor, cx| {
+                        editor.all_text_background_highlights(cx).len()
+                    })
+                })
+            })
+            .collect()
+    }
+
+    #[gpui::test]
+    async fn test_hlsearch_in_splits(cx: &mut gpui::TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+
+        cx.set_state("ˇaa\nbb\naa", Mode::Normal);
+        cx.simulate_keystrokes(["ctrl-w", "v", "/", "a", "a", "enter"]);
+        cx.run_until_parked();
+        assert_eq!(highlight_counts(&mut cx), [2, 2]);
+
+        cx.simulate_keystrokes([":"]);
+        cx.simulate_input("noh");
+        cx.simulate_keystrokes(["enter"]);
+        cx.run_until_parked();
+        assert_eq!(highlight_counts(&mut cx), [0, 0]);
+
+        // n highlights the matches everywhere again
+        cx.simulate_keystrokes(["n"]);
+        cx.run_until_parked();
+        assert_eq!(highlight_counts(&mut cx), [2, 2]);
+
+        // an edited buffer is searched again when a pane is activated
+        cx.simulate_keystrokes(["ctrl-w", "w", "o", "a", "a", "escape", "ctrl-w", "w"]);
+        cx.run_until_parked();
+        assert_eq!(highlight_counts(&mut cx), [3, 3]);
+
+        // dismissing the search bar clears the other pane's highlights too
+        cx.simulate_keystrokes(["/", "escape"]);
+        cx.run_until_parked();
+        assert_eq!(highlight_counts(&mut cx), [0, 0]);
+    }
+}
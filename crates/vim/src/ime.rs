@@ -0,0 +1,77 @@
+use gpui::AppContext;
+use settings::Settings;
+
+use crate::{state::Mode, VimSettings};
+
+/// Whether an input method (as for Chinese or Japanese) can compose text in the
+/// mode, as the `ime` setting gives. In modes where it can't, the text it's composing
+/// is discarded rather than shown, so that there's never a composition in progress
+/// for the keys to go to first: they run commands, and a command waiting for a
+/// character (like `f`) takes the text the input method commits.
+pub(crate) fn ime_composition_enabled(mode: Mode, cx: &AppContext) -> bool {
+    let ime = VimSettings::get_global(cx).ime;
+    match mode {
+        Mode::Normal => ime.normal,
+        Mode::Visual | Mode::VisualLine | Mode::VisualBlock => ime.visual,
+        Mode::Insert | Mode::Select => ime.insert,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use gpui::ViewInputHandler;
+    use settings::SettingsStore;
+
+    use crate::{state::Mode, test::VimTestContext, ImeSettings, VimSettings};
+
+    #[gpui::test]
+    async fn test_ime_in_normal_mode(cx: &mut gpui::TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+
+        // the text being composed in normal mode is discarded, rather than typed as keys
+        cx.set_state("ˇone two\nthree ｏ", Mode::Normal);
+        cx.update_editor(|editor, cx| {
+            editor.replace_and_mark_text_in_range(None, "k", None, cx);
+            editor.replace_and_mark_text_in_range(None, "か", None, cx);
+            assert!(editor.marked_text_range(cx).is_none());
+        });
+        cx.run_until_parked();
+        cx.assert_state("ˇone two\nthree ｏ", Mode::Normal);
+
+        // and the text it commits goes to a command waiting for a character
+        cx.simulate_keystrokes(["j", "f"]);
+        cx.update_editor(|editor, cx| {
+            editor.replace_and_mark_text_in_range(None, "ｏ", None, cx);
+            editor.replace_text_in_range(None, "ｏ", cx);
+        });
+        cx.run_until_parked();
+        cx.assert_state("one two\nthree ˇｏ", Mode::Normal);
+
+        // it composes text in insert mode
+        cx.simulate_keystrokes(["i"]);
+        cx.update_editor(|editor, cx| {
+            editor.replace_and_mark_text_in_range(None, "じ", None, cx);
+            assert!(editor.marked_text_range(cx).is_some());
+            editor.replace_text_in_range(None, "字", cx);
+        });
+        cx.assert_state("one two\nthree 字ˇｏ", Mode::Insert);
+
+        // and where it can't, only the text it commits is inserted
+        cx.update_global(|store: &mut SettingsStore, cx| {
+            store.update_user_settings::<VimSettings>(cx, |s| {
+                s.ime = Some(ImeSettings {
+                    normal: false,
+                    visual: false,
+                    insert: false,
+                })
+            });
+        });
+        cx.simulate_keystrokes(["escape", "a"]);
+        cx.update_editor(|editor, cx| {
+            editor.replace_and_mark_text_in_range(None, "k", None, cx);
+            editor.replace_and_mark_text_in_range(None, "か", None, cx);
+            editor.replace_text_in_range(None, "か", cx);
+        });
+        cx.assert_state("one two\nthree 字かˇｏ", Mode::Insert);
+    }
+}
@@ -1,13 +1,22 @@
-use crate::{normal::repeat, state::Mode, Vim};
-use editor::{scroll::Autoscroll, Bias};
-use gpui::{actions, Action, ViewContext};
+use crate::{
+    motion::previous_word_start,
+    normal::repeat,
+    state::Mode,
+    utils::{merge_transactions_since, KeywordChars},
+    CtrlCBehavior, Vim, VimSettings,
+};
+use editor::{movement, scroll::Autoscroll, Bias};
+use gpui::{actions, Action, ViewContext, WindowContext};
 use language::SelectionGoal;
+use settings::Settings;
 use workspace::Workspace;
 
-actions!(vim, [NormalBefore]);
+actions!(vim, [NormalBefore, Interrupt, DeleteToPreviousWordStart]);
 
 pub fn register(workspace: &mut Workspace, _: &mut ViewContext<Workspace>) {
     workspace.register_action(normal_before);
+    workspace.register_action(interrupt);
+    workspace.register_action(delete_to_previous_word_start);
 }
 
 fn normal_before(_: &mut Workspace, action: &NormalBefore, cx: &mut ViewContext<Workspace>) {
@@ -15,16 +24,7 @@ fn normal_before(_: &mut Workspace, action: &NormalBefore, cx: &mut ViewContext<
         let count = vim.take_count(cx).unwrap_or(1);
         vim.stop_recording_immediately(action.boxed_clone());
         if count <= 1 || vim.workspace_state.replaying {
-            vim.update_active_editor(cx, |_, editor, cx| {
-                editor.dismiss_menus_and_popups(cx);
-                editor.change_selections(Some(Autoscroll::fit()), cx, |s| {
-                    s.move_cursors_with(|map, mut cursor, _| {
-                        *cursor.column_mut() = cursor.column().saturating_sub(1);
-                        (map.clip_point(cursor, Bias::Left), SelectionGoal::None)
-                    });
-                });
-            });
-            vim.switch_mode(Mode::Normal, false, cx);
+            leave_insert(vim, cx);
             false
         } else {
             true
@@ -36,11 +36,101 @@ fn normal_before(_: &mut Workspace, action: &NormalBefore, cx: &mut ViewContext<
     }
 }
 
+/// `ctrl-c` interrupts whatever vim is doing, like vim's: it leaves insert mode
+/// without repeating the insert for a count, leaves visual and select mode, and
+/// abandons a pending count, register or operator. When the `ctrl_c` setting is
+/// "copy", it copies the selection instead. Either way, it first stops a long
+/// replay (like `1000@q`), or abandons an operation running in the background,
+/// like `:%s` on a very large buffer or a shell command (which is killed).
+fn interrupt(_: &mut Workspace, action: &Interrupt, cx: &mut ViewContext<Workspace>) {
+    if Vim::update(cx, |vim, _| {
+        vim.interrupt_replay() || vim.cancel_pending_operation()
+    }) {
+        return;
+    }
+    if VimSettings::get_global(cx).ctrl_c == CtrlCBehavior::Copy {
+        cx.dispatch_action(editor::actions::Copy.boxed_clone());
+        return;
+    }
+    Vim::update(cx, |vim, cx| {
+        if vim.state().mode == Mode::Insert {
+            vim.take_count(cx);
+            vim.stop_recording_immediately(action.boxed_clone());
+            leave_insert(vim, cx);
+        } else {
+            vim.clear_operator(cx);
+            vim.switch_mode(Mode::Normal, false, cx);
+        }
+    });
+}
+
+/// `ctrl-w` in insert mode deletes the word before the cursor, where words are what
+/// `b` moves over, or the line break at the start of a line. Between a pair of
+/// brackets that were closed automatically, it deletes the pair. The deletion is
+/// grouped with all that was typed since insert mode was entered, however long ago,
+/// so that `u` undoes the insert as one change.
+fn delete_to_previous_word_start(
+    _: &mut Workspace,
+    _: &DeleteToPreviousWordStart,
+    cx: &mut ViewContext<Workspace>,
+) {
+    Vim::update(cx, |vim, cx| {
+        let undo_base = vim.state().insert_undo_base;
+        vim.update_active_editor(cx, |_, editor, cx| {
+            let keyword_chars = KeywordChars::new(cx);
+            editor.transact(cx, |editor, cx| {
+                editor.select_autoclose_pair(cx);
+                editor.change_selections(Some(Autoscroll::fit()), cx, |s| {
+                    s.move_with(|map, selection| {
+                        if !selection.is_empty() {
+                            return;
+                        }
+                        let head = selection.head();
+                        let start = if head.column() == 0 {
+                            movement::left(map, head)
+                        } else {
+                            previous_word_start(map, head, false, &keyword_chars, 1)
+                        };
+                        selection.set_head(start, SelectionGoal::None);
+                    });
+                });
+                editor.insert("", cx);
+            });
+            merge_transactions_since(editor, undo_base, cx);
+        });
+    });
+}
+
+fn leave_insert(vim: &mut Vim, cx: &mut WindowContext) {
+    vim.update_active_editor(cx, |_, editor, cx| {
+        // the snippet outlives insert mode, so its tab stops can be reached from
+        // normal mode, and the cursor stays put so that it doesn't leave the
+        // current one.
+        editor.dismiss_menus_and_popups(true, cx);
+        let in_snippet = editor.has_active_snippet();
+        editor.change_selections(Some(Autoscroll::fit()), cx, |s| {
+            s.move_cursors_with(|map, mut cursor, _| {
+                if in_snippet {
+                    return (cursor, SelectionGoal::None);
+                }
+                *cursor.column_mut() = cursor.column().saturating_sub(1);
+                (map.clip_point(cursor, Bias::Left), SelectionGoal::None)
+            });
+        });
+    });
+    vim.switch_mode(Mode::Normal, false, cx);
+}
+
 #[cfg(test)]
 mod test {
+    use std::time::Duration;
+
+    use settings::SettingsStore;
+
     use crate::{
         state::Mode,
         test::{NeovimBackedTestContext, VimTestContext},
+        CtrlCBehavior, VimSettings,
     };
 
     #[gpui::test]
@@ -122,4 +212,86 @@ mod test {
         cx.run_until_parked();
         cx.assert_shared_state("hello\nkk\nkk\nkk\nkk\nkˇk\n").await;
     }
+
+    #[gpui::test]
+    async fn test_insert_ctrl_w(cx: &mut gpui::TestAppContext) {
+        let mut cx = VimTestContext::new_typescript(cx).await;
+
+        // words are what b moves over, with the language's word characters
+        cx.set_state("let $a = b.#cˇ", Mode::Insert);
+        cx.simulate_keystrokes(["ctrl-w"]);
+        cx.assert_state("let $a = b.ˇ", Mode::Insert);
+        cx.simulate_keystrokes(["ctrl-w", "ctrl-w"]);
+        cx.assert_state("let $a = ˇ", Mode::Insert);
+        cx.simulate_keystrokes(["ctrl-w", "ctrl-w"]);
+        cx.assert_state("let ˇ", Mode::Insert);
+
+        // at the start of a line it joins it to the one above
+        cx.set_state("one\nˇtwo", Mode::Insert);
+        cx.simulate_keystrokes(["ctrl-w"]);
+        cx.assert_state("oneˇtwo", Mode::Insert);
+
+        // a pair of brackets that was closed as it was typed goes together
+        cx.set_state("ˇ", Mode::Normal);
+        cx.simulate_keystrokes(["i", "f", "n", " ", "{"]);
+        cx.assert_state("fn {ˇ}", Mode::Insert);
+        cx.simulate_keystrokes(["a", "b", " ", "c", "d", "ctrl-w"]);
+        cx.assert_state("fn {ab ˇ}", Mode::Insert);
+        cx.simulate_keystrokes(["ctrl-w"]);
+        cx.assert_state("fn {ˇ}", Mode::Insert);
+        cx.simulate_keystrokes(["ctrl-w"]);
+        cx.assert_state("fn ˇ", Mode::Insert);
+    }
+
+    #[gpui::test]
+    async fn test_insert_ctrl_w_undo(cx: &mut gpui::TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+        // so that nothing is grouped by when it was typed, as after a pause
+        cx.update_buffer(|buffer, _| buffer.set_group_interval(Duration::ZERO));
+
+        // the deletion is undone with all that was typed in the insert
+        cx.set_state("ˇ", Mode::Normal);
+        cx.simulate_keystrokes(["i", "a", "b", " ", "c", "d", "ctrl-w", "escape"]);
+        cx.assert_state("abˇ ", Mode::Normal);
+        cx.simulate_keystrokes(["u"]);
+        cx.assert_state("ˇ", Mode::Normal);
+
+        // but not with the changes made before insert mode was entered
+        cx.simulate_keystrokes(["i", "a", "b", " ", "c", "d", "escape"]);
+        cx.simulate_keystrokes(["shift-a", "ctrl-w", "escape"]);
+        cx.assert_state("abˇ ", Mode::Normal);
+        cx.simulate_keystrokes(["u"]);
+        cx.assert_state("ab cˇd", Mode::Normal);
+    }
+
+    #[gpui::test]
+    async fn test_ctrl_c(cx: &mut gpui::TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+
+        // unlike escape, ctrl-c doesn't repeat an insert for its count
+        cx.set_state("ˇhello", Mode::Normal);
+        cx.simulate_keystrokes(["3", "i", "-", "ctrl-c"]);
+        cx.assert_state("ˇ-hello", Mode::Normal);
+
+        // it abandons a pending operator and count
+        cx.simulate_keystrokes(["2", "d", "ctrl-c", "x"]);
+        cx.assert_state("ˇhello", Mode::Normal);
+
+        cx.simulate_keystrokes(["v", "l", "ctrl-c"]);
+        cx.assert_state("hˇello", Mode::Normal);
+
+        cx.update_global(|store: &mut SettingsStore, cx| {
+            store.update_user_settings::<VimSettings>(cx, |settings| {
+                settings.ctrl_c = Some(CtrlCBehavior::Copy)
+            });
+        });
+        cx.simulate_keystrokes(["v", "l", "ctrl-c"]);
+        cx.assert_state("h«elˇ»lo", Mode::Visual);
+        assert_eq!(
+            cx.read_from_clipboard()
+                .map(|item| item.text().clone())
+                .unwrap(),
+            "el"
+        );
+    }
 }
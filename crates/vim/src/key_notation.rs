@@ -0,0 +1,209 @@
+use gpui::{Action, Keystroke, Modifiers, WindowContext};
+
+/// The names vim gives keys in its `<...>` notation, and the keys they name.
+const KEY_NAMES: &[(&str, &str)] = &[
+    ("Esc", "escape"),
+    ("CR", "enter"),
+    ("Tab", "tab"),
+    ("BS", "backspace"),
+    ("Del", "delete"),
+    ("Space", "space"),
+    ("Up", "up"),
+    ("Down", "down"),
+    ("Left", "left"),
+    ("Right", "right"),
+    ("Home", "home"),
+    ("End", "end"),
+    ("PageUp", "pageup"),
+    ("PageDown", "pagedown"),
+    ("Insert", "insert"),
+    ("lt", "<"),
+];
+
+/// Other names vim accepts for keys.
+const KEY_ALIASES: &[(&str, &str)] = &[
+    ("Enter", "enter"),
+    ("Return", "enter"),
+    ("Escape", "escape"),
+    ("Backspace", "backspace"),
+    ("Delete", "delete"),
+];
+
+/// The keystroke that types a character, as `:normal` and replaying a register
+/// that holds text type them.
+pub(crate) fn keystroke_for(c: char) -> Keystroke {
+    let (key, shift) = match c {
+        ' ' => ("space".to_string(), false),
+        '\n' | '\r' => ("enter".to_string(), false),
+        '\t' => ("tab".to_string(), false),
+        '\x1b' => ("escape".to_string(), false),
+        c if c.is_ascii_uppercase() => (c.to_ascii_lowercase().to_string(), true),
+        c => (c.to_string(), false),
+    };
+    Keystroke {
+        modifiers: Modifiers {
+            shift,
+            ..Default::default()
+        },
+        key,
+        ime_key: None,
+    }
+}
+
+/// Writes keys the way vim shows them in a register: characters as themselves, and
+/// other keys (or keys with modifiers) like `<Esc>`, `<CR>` or `<C-r>`.
+pub(crate) fn key_notation(keystrokes: &[Keystroke]) -> String {
+    let mut notation = String::new();
+    for keystroke in keystrokes {
+        let modifiers = keystroke.modifiers;
+        let name = KEY_NAMES
+            .iter()
+            .find(|(_, key)| *key == keystroke.key)
+            .map(|(name, _)| *name);
+        let typed = !modifiers.control && !modifiers.alt && !modifiers.command;
+        if typed && matches!(keystroke.key.as_str(), "space" | "<") {
+            notation.push_str(if keystroke.key == "<" { "<lt>" } else { " " });
+        } else if typed && name.is_none() && keystroke.key.chars().count() == 1 {
+            match &keystroke.ime_key {
+                Some(text) => notation.push_str(text),
+                None if modifiers.shift => notation.push_str(&keystroke.key.to_uppercase()),
+                None => notation.push_str(&keystroke.key),
+            }
+        } else {
+            notation.push('<');
+            for (pressed, prefix) in [
+                (modifiers.control, "C-"),
+                (modifiers.shift, "S-"),
+                (modifiers.alt, "M-"),
+                (modifiers.command, "D-"),
+            ] {
+                if pressed {
+                    notation.push_str(prefix);
+                }
+            }
+            match name {
+                Some(name) => notation.push_str(name),
+                None if keystroke.key.starts_with('f') && keystroke.key.len() > 1 => {
+                    notation.push_str(&keystroke.key.to_uppercase())
+                }
+                None => notation.push_str(&keystroke.key),
+            }
+            notation.push('>');
+        }
+    }
+    notation
+}
+
+/// Reads keys written in vim's notation, as `key_notation` writes them. A `<` that
+/// doesn't start a key name is typed as it is, as are any control characters.
+pub(crate) fn parse_key_notation(text: &str) -> Vec<Keystroke> {
+    let mut keystrokes = Vec::new();
+    let mut rest = text;
+    while let Some(c) = rest.chars().next() {
+        if c == '<' {
+            let keystroke = rest
+                .find('>')
+                .and_then(|end| Some((parse_key_name(&rest[1..end])?, end)));
+            if let Some((keystroke, end)) = keystroke {
+                keystrokes.push(keystroke);
+                rest = &rest[end + 1..];
+                continue;
+            }
+        }
+        keystrokes.push(keystroke_for(c));
+        rest = &rest[c.len_utf8()..];
+    }
+    keystrokes
+}
+
+/// Reads a key name from between `<` and `>`, like `Esc`, `C-r` or `S-Tab`.
+fn parse_key_name(name: &str) -> Option<Keystroke> {
+    let mut modifiers = Modifiers::default();
+    let mut key = name;
+    while key.len() > 2 && key.as_bytes()[1] == b'-' {
+        match key.as_bytes()[0].to_ascii_uppercase() {
+            b'C' => modifiers.control = true,
+            b'S' => modifiers.shift = true,
+            b'M' | b'A' => modifiers.alt = true,
+            b'D' => modifiers.command = true,
+            _ => return None,
+        }
+        key = &key[2..];
+    }
+
+    let key = if key.chars().count() == 1 && key != "<" && key != ">" {
+        // vim doesn't tell <C-R> from <C-r>
+        key.to_lowercase()
+    } else if let Some((_, key)) = KEY_NAMES
+        .iter()
+        .chain(KEY_ALIASES)
+        .find(|(name, _)| name.eq_ignore_ascii_case(key))
+    {
+        key.to_string()
+    } else if key.len() > 1
+        && key.starts_with(['F', 'f'])
+        && key[1..].chars().all(|c| c.is_ascii_digit())
+    {
+        key.to_lowercase()
+    } else {
+        return None;
+    };
+    if modifiers == Modifiers::default() && key.chars().count() == 1 {
+        // a character on its own, like <lt>, is typed
+        return Some(keystroke_for(key.chars().next()?));
+    }
+    Some(Keystroke {
+        modifiers,
+        key,
+        ime_key: None,
+    })
+}
+
+/// The keys that were typed for an action. Only the last one is observed with the
+/// action, as the ones before it were pending, so they're found from its binding.
+pub(crate) fn keystrokes_for_action(
+    action: &dyn Action,
+    keystroke: &Keystroke,
+    cx: &WindowContext,
+) -> Vec<Keystroke> {
+    cx.bindings_for_action(action)
+        .into_iter()
+        .rev()
+        .find(|binding| {
+            binding.keystrokes().last().is_some_and(|last| {
+                last.key == keystroke.key && last.modifiers == keystroke.modifiers
+            })
+        })
+        .map(|binding| binding.keystrokes().to_vec())
+        .unwrap_or_else(|| vec![keystroke.clone()])
+}
+
+#[cfg(test)]
+mod test {
+    use gpui::Keystroke;
+
+    use super::{key_notation, parse_key_notation};
+
+    #[test]
+    fn test_key_notation() {
+        let keystrokes = ["d", "w", "shift-a", "space", "escape", "ctrl-r", "enter"]
+            .map(|key| Keystroke::parse(key).unwrap());
+        assert_eq!(key_notation(&keystrokes), "dwA <Esc><C-r><CR>");
+        let keystrokes =
+            ["<", "shift-tab", "f5", "cmd-s"].map(|key| Keystroke::parse(key).unwrap());
+        assert_eq!(key_notation(&keystrokes), "<lt><S-Tab><F5><D-s>");
+
+        let parsed = parse_key_notation("iab<Esc><C-R>x<lt><S-Tab><F5><nope>");
+        assert_eq!(
+            key_notation(&parsed),
+            "iab<Esc><C-r>x<lt><S-Tab><F5><lt>nope>"
+        );
+        assert_eq!(
+            parse_key_notation("a\n")
+                .into_iter()
+                .map(|keystroke| keystroke.key)
+                .collect::<Vec<_>>(),
+            ["a", "enter"]
+        );
+    }
+}
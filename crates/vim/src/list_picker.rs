@@ -0,0 +1,71 @@
+use fuzzy::{match_strings, StringMatch, StringMatchCandidate};
+use gpui::{DismissEvent, EventEmitter, Task, ViewContext, WeakView};
+use picker::{Picker, PickerDelegate};
+
+/// The delegate of a picker that a modal view wraps, like vim's lists of
+/// occurrences, references and tags.
+pub(crate) trait ModalDelegate: PickerDelegate {
+    type Modal: EventEmitter<DismissEvent>;
+
+    fn modal(&self) -> &WeakView<Self::Modal>;
+}
+
+/// A modal picker over a fixed list of strings, which the query filters.
+pub(crate) trait StringListDelegate: ModalDelegate {
+    fn candidates(&self) -> &[StringMatchCandidate];
+
+    fn set_matches(&mut self, matches: Vec<StringMatch>);
+}
+
+/// Closes the modal, for `PickerDelegate::dismissed`.
+pub(crate) fn dismiss<D: ModalDelegate>(delegate: &D, cx: &mut ViewContext<Picker<D>>) {
+    delegate
+        .modal()
+        .update(cx, |_, cx| cx.emit(DismissEvent))
+        .ok();
+}
+
+/// Filters the list by the query, for `PickerDelegate::update_matches`: every
+/// candidate in order when the query is empty, otherwise the best fuzzy matches. The
+/// selection stays where it was, unless fewer matches are left.
+pub(crate) fn update_matches<D: StringListDelegate>(
+    delegate: &D,
+    query: String,
+    cx: &mut ViewContext<Picker<D>>,
+) -> Task<()> {
+    let background = cx.background_executor().clone();
+    let candidates = delegate.candidates().to_vec();
+    cx.spawn(|this, mut cx| async move {
+        let matches = if query.is_empty() {
+            candidates
+                .into_iter()
+                .map(|candidate| StringMatch {
+                    candidate_id: candidate.id,
+                    string: candidate.string,
+                    positions: Vec::new(),
+                    score: 0.0,
+                })
+                .collect()
+        } else {
+            match_strings(
+                &candidates,
+                &query,
+                false,
+                100,
+                &Default::default(),
+                background,
+            )
+            .await
+        };
+        this.update(&mut cx, |this, cx| {
+            let delegate = &mut this.delegate;
+            delegate.set_matches(matches);
+            let selected_index = delegate
+                .selected_index()
+                .min(delegate.match_count().saturating_sub(1));
+            delegate.set_selected_index(selected_index, cx);
+            cx.notify();
+        })
+        .ok();
+    })
+}
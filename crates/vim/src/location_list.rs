@@ -0,0 +1,472 @@
+use std::sync::Arc;
+
+use editor::{scroll::Autoscroll, Anchor, Editor, ToPoint};
+use fuzzy::{StringMatch, StringMatchCandidate};
+use gpui::{
+    actions, impl_actions, AppContext, DismissEvent, EntityId, EventEmitter, FocusHandle,
+    FocusableView, ParentElement, Render, Styled, Task, View, ViewContext, VisualContext, WeakView,
+};
+use language::Point;
+use picker::{Picker, PickerDelegate};
+use regex::Regex;
+use serde_derive::Deserialize;
+use settings::Settings;
+use ui::{prelude::*, HighlightedLabel, ListItem, ListItemSpacing};
+use workspace::{ModalView, Workspace};
+
+use crate::{
+    list_picker::{self, ModalDelegate, StringListDelegate},
+    navigate::step_in_list,
+    state::Mode,
+    Vim, VimSettings,
+};
+
+/// `:lt[ag] {name}` fills the location list with the symbols of the current file that
+/// are called `name` (or that match `/pattern`), and jumps to the first.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub(crate) struct LocationTag {
+    name: String,
+}
+
+impl_actions!(vim, [LocationTag]);
+
+actions!(vim, [OpenLocationList, NextLocation, PreviousLocation]);
+
+/// A pane's location list. Unlike the quickfix list (`:cnext`, which moves between
+/// the diagnostics of the project), it only holds places in the file it was made for:
+/// that file's diagnostics, or the symbols `:ltag` found in it.
+#[derive(Clone)]
+pub struct LocationList {
+    editor: WeakView<Editor>,
+    entries: Vec<LocationEntry>,
+    /// The entry last jumped to
+    current: Option<usize>,
+}
+
+impl LocationList {
+    /// Whether the list was made for the file in the editor.
+    pub(crate) fn is_for(&self, editor_id: EntityId) -> bool {
+        self.editor.entity_id() == editor_id
+    }
+}
+
+#[derive(Clone)]
+struct LocationEntry {
+    position: Anchor,
+    text: String,
+}
+
+pub(crate) fn register(workspace: &mut Workspace, _: &mut ViewContext<Workspace>) {
+    workspace.register_action(|workspace: &mut Workspace, _: &OpenLocationList, cx| {
+        Vim::update(cx, |vim, cx| vim.clear_operator(cx));
+        let Some(list) = current_list(workspace, cx) else {
+            return;
+        };
+        let pane_id = workspace.active_pane().entity_id();
+        let workspace_handle = cx.view().downgrade();
+        workspace.toggle_modal(cx, |cx| {
+            LocationListView::new(workspace_handle, pane_id, list, cx)
+        });
+    });
+    workspace.register_action(|workspace: &mut Workspace, _: &NextLocation, cx| {
+        let count = Vim::update(cx, |vim, cx| vim.take_count(cx)).unwrap_or(1);
+        move_in_list(workspace, count as isize, cx)
+    });
+    workspace.register_action(|workspace: &mut Workspace, _: &PreviousLocation, cx| {
+        let count = Vim::update(cx, |vim, cx| vim.take_count(cx)).unwrap_or(1);
+        move_in_list(workspace, -(count as isize), cx)
+    });
+    workspace.register_action(|workspace: &mut Workspace, action: &LocationTag, cx| {
+        let Some(editor) = workspace.active_item_as::<Editor>(cx) else {
+            return;
+        };
+        let entries = match tag_entries(&editor, &action.name, cx) {
+            Ok(entries) => entries,
+            Err(message) => {
+                Vim::update(cx, |vim, _| vim.show_message(message));
+                return;
+            }
+        };
+        let pane_id = workspace.active_pane().entity_id();
+        let list = LocationList {
+            editor: editor.downgrade(),
+            entries,
+            current: Some(0),
+        };
+        jump_to(workspace, &list, 0, cx);
+        Vim::update(cx, |vim, _| {
+            vim.workspace_state.location_lists.insert(pane_id, list)
+        });
+    });
+}
+
+/// The pane's location list, if it was made for the file in the pane. Otherwise the
+/// list is made afresh from the file's diagnostics.
+fn current_list(workspace: &Workspace, cx: &mut WindowContext) -> Option<LocationList> {
+    let editor = workspace.active_item_as::<Editor>(cx)?;
+    let pane_id = workspace.active_pane().entity_id();
+    let list = Vim::read(cx)
+        .workspace_state
+        .location_lists
+        .get(&pane_id)
+        .filter(|list| list.editor == editor.downgrade())
+        .cloned();
+    list.or_else(|| diagnostics_list(workspace, cx))
+}
+
+/// A location list of the diagnostics of the file in the active pane, sorted by
+/// where they start.
+fn diagnostics_list(workspace: &Workspace, cx: &mut WindowContext) -> Option<LocationList> {
+    let editor = workspace.active_item_as::<Editor>(cx)?;
+    let snapshot = editor.read(cx).buffer().read(cx).snapshot(cx);
+    let mut diagnostics = snapshot
+        .diagnostics_in_range::<_, Point>(0..snapshot.len(), false)
+        .filter(|entry| entry.diagnostic.is_primary)
+        .collect::<Vec<_>>();
+    diagnostics.sort_by_key(|entry| entry.range.start);
+    if diagnostics.is_empty() {
+        Vim::update(cx, |vim, _| vim.show_message("E776: No location list"));
+        return None;
+    }
+    let entries = diagnostics
+        .into_iter()
+        .map(|entry| LocationEntry {
+            position: snapshot.anchor_before(entry.range.start),
+            text: entry
+                .diagnostic
+                .message
+                .lines()
+                .next()
+                .unwrap_or_default()
+                .to_string(),
+        })
+        .collect();
+    Some(LocationList {
+        editor: editor.downgrade(),
+        entries,
+        current: None,
+    })
+}
+
+/// The symbols of the file in the editor whose name is `name`, or matches it as a
+/// regex when it starts with a `/`.
+fn tag_entries(
+    editor: &View<Editor>,
+    name: &str,
+    cx: &AppContext,
+) -> Result<Vec<LocationEntry>, String> {
+    let matches: Box<dyn Fn(&str) -> bool + '_> = if let Some(pattern) = name.strip_prefix('/') {
+        let regex = Regex::new(pattern).map_err(|_| format!("E486: Pattern not found: {name}"))?;
+        Box::new(move |symbol| regex.is_match(symbol))
+    } else {
+        Box::new(|symbol| symbol == name)
+    };
+    let snapshot = editor.read(cx).buffer().read(cx).snapshot(cx);
+    let entries = snapshot
+        .outline(None)
+        .map(|outline| outline.items)
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|item| {
+            item.name_ranges
+                .iter()
+                .any(|range| matches(&item.text[range.clone()]))
+        })
+        .map(|item| LocationEntry {
+            position: item.range.start,
+            text: item.text,
+        })
+        .collect::<Vec<_>>();
+    if entries.is_empty() {
+        return Err(format!("E426: Tag not found: {name}"));
+    }
+    Ok(entries)
+}
+
+/// `:lnext` and `]l` (or `:lprevious` and `[l`, with a negative delta) jump to the
+/// next entry of the list. In a list that hasn't been used yet, they start from the
+/// cursor.
+fn move_in_list(workspace: &mut Workspace, delta: isize, cx: &mut ViewContext<Workspace>) {
+    let Some(mut list) = current_list(workspace, cx) else {
+        return;
+    };
+    let Some(editor) = list.editor.upgrade() else {
+        return;
+    };
+    let current = match list.current {
+        Some(current) => current as isize,
+        None => {
+            let editor = editor.read(cx);
+            let snapshot = editor.buffer().read(cx).snapshot(cx);
+            let cursor = editor.selections.newest_anchor().head();
+            let after_cursor = list
+                .entries
+                .iter()
+                .position(|entry| entry.position.cmp(&cursor, &snapshot).is_gt());
+            match after_cursor {
+                // the first step forward lands on the first entry after the cursor
+                Some(ix) if delta > 0 => ix as isize - 1,
+                Some(ix) => ix as isize,
+                None if delta > 0 => list.entries.len() as isize - 1,
+                None => list.entries.len() as isize,
+            }
+        }
+    };
+    let wrap = VimSettings::get_global(cx).bracket_wrap.lists;
+    let Some(target) = step_in_list(current, delta, list.entries.len(), wrap) else {
+        Vim::update(cx, |vim, _| vim.show_message("E553: No more items"));
+        return;
+    };
+
+    list.current = Some(target);
+    jump_to(workspace, &list, target, cx);
+    let pane_id = workspace.active_pane().entity_id();
+    Vim::update(cx, |vim, _| {
+        vim.workspace_state.location_lists.insert(pane_id, list)
+    });
+}
+
+/// Moves the cursor to an entry of the list, and shows which it is like vim does.
+fn jump_to(
+    workspace: &mut Workspace,
+    list: &LocationList,
+    ix: usize,
+    cx: &mut ViewContext<Workspace>,
+) {
+    let Some(editor) = list.editor.upgrade() else {
+        return;
+    };
+    let entry = &list.entries[ix];
+    workspace.activate_item(&editor, cx);
+    Vim::update(cx, |vim, cx| vim.switch_mode(Mode::Normal, false, cx));
+    editor.update(cx, |editor, cx| {
+        editor.change_selections(Some(Autoscroll::center()), cx, |s| {
+            s.select_anchor_ranges([entry.position..entry.position])
+        });
+    });
+    let message = format!("({} of {}): {}", ix + 1, list.entries.len(), entry.text);
+    Vim::update(cx, |vim, _| vim.show_message(message));
+}
+
+pub(crate) fn parse_location_tag(query: &str) -> Option<LocationTag> {
+    let (name, tag) = query.split_once(' ')?;
+    if !matches!(name, "lt" | "lta" | "ltag") || tag.trim().is_empty() {
+        return None;
+    }
+    Some(LocationTag {
+        name: tag.trim().to_string(),
+    })
+}
+
+/// The window `:lopen` shows the location list in. Picking an entry jumps to it.
+pub struct LocationListView {
+    picker: View<Picker<LocationListDelegate>>,
+}
+
+impl LocationListView {
+    fn new(
+        workspace: WeakView<Workspace>,
+        pane_id: EntityId,
+        list: LocationList,
+        cx: &mut ViewContext<Self>,
+    ) -> Self {
+        let candidates = list
+            .entries
+            .iter()
+            .enumerate()
+            .map(|(id, entry)| StringMatchCandidate::new(id, entry.text.clone()))
+            .collect();
+        let delegate = LocationListDelegate {
+            location_list_view: cx.view().downgrade(),
+            workspace,
+            pane_id,
+            selected_index: list.current.unwrap_or(0),
+            list,
+            candidates,
+            matches: Vec::new(),
+        };
+        let picker = cx.new_view(|cx| Picker::uniform_list(delegate, cx));
+        Self { picker }
+    }
+}
+
+impl Render for LocationListView {
+    fn render(&mut self, _cx: &mut ViewContext<Self>) -> impl IntoElement {
+        v_flex().w(rems(34.)).child(self.picker.clone())
+    }
+}
+
+impl FocusableView for LocationListView {
+    fn focus_handle(&self, cx: &AppContext) -> FocusHandle {
+        self.picker.focus_handle(cx)
+    }
+}
+
+impl EventEmitter<DismissEvent> for LocationListView {}
+impl ModalView for LocationListView {}
+
+pub struct LocationListDelegate {
+    location_list_view: WeakView<LocationListView>,
+    workspace: WeakView<Workspace>,
+    pane_id: EntityId,
+    list: LocationList,
+    candidates: Vec<StringMatchCandidate>,
+    matches: Vec<StringMatch>,
+    selected_index: usize,
+}
+
+impl ModalDelegate for LocationListDelegate {
+    type Modal = LocationListView;
+
+    fn modal(&self) -> &WeakView<LocationListView> {
+        &self.location_list_view
+    }
+}
+
+impl StringListDelegate for LocationListDelegate {
+    fn candidates(&self) -> &[StringMatchCandidate] {
+        &self.candidates
+    }
+
+    fn set_matches(&mut self, matches: Vec<StringMatch>) {
+        self.matches = matches;
+    }
+}
+
+impl PickerDelegate for LocationListDelegate {
+    type ListItem = ListItem;
+
+    fn placeholder_text(&self, _cx: &mut WindowContext) -> Arc<str> {
+        "Filter the location list...".into()
+    }
+
+    fn match_count(&self) -> usize {
+        self.matches.len()
+    }
+
+    fn selected_index(&self) -> usize {
+        self.selected_index
+    }
+
+    fn set_selected_index(&mut self, ix: usize, _: &mut ViewContext<Picker<Self>>) {
+        self.selected_index = ix;
+    }
+
+    fn update_matches(&mut self, query: String, cx: &mut ViewContext<Picker<Self>>) -> Task<()> {
+        list_picker::update_matches(self, query, cx)
+    }
+
+    fn confirm(&mut self, _: bool, cx: &mut ViewContext<Picker<Self>>) {
+        if let Some(mat) = self.matches.get(self.selected_index) {
+            let mut list = self.list.clone();
+            let ix = mat.candidate_id;
+            list.current = Some(ix);
+            let pane_id = self.pane_id;
+            self.workspace
+                .update(cx, |workspace, cx| {
+                    jump_to(workspace, &list, ix, cx);
+                    Vim::update(cx, |vim, _| {
+                        vim.workspace_state.location_lists.insert(pane_id, list)
+                    });
+                })
+                .ok();
+        }
+        self.dismissed(cx);
+    }
+
+    fn dismissed(&mut self, cx: &mut ViewContext<Picker<Self>>) {
+        list_picker::dismiss(self, cx)
+    }
+
+    fn render_match(
+        &self,
+        ix: usize,
+        selected: bool,
+        cx: &mut ViewContext<Picker<Self>>,
+    ) -> Option<Self::ListItem> {
+        let mat = &self.matches[ix];
+        let entry = &self.list.entries[mat.candidate_id];
+        let row = self
+            .list
+            .editor
+            .upgrade()
+            .map(|editor| {
+                let snapshot = editor.read(cx).buffer().read(cx).snapshot(cx);
+                entry.position.to_point(&snapshot).row + 1
+            })
+            .unwrap_or_default();
+        let line_number = format!("{row}: ");
+        let positions = mat
+            .positions
+            .iter()
+            .map(|position| position + line_number.len())
+            .collect();
+        Some(
+            ListItem::new(ix)
+                .inset(true)
+                .spacing(ListItemSpacing::Sparse)
+                .selected(selected)
+                .child(HighlightedLabel::new(line_number + &entry.text, positions)),
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::LocationListView;
+    use crate::{state::Mode, test::VimTestContext};
+
+    #[gpui::test]
+    async fn test_location_list(cx: &mut gpui::TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+
+        cx.set_state("ˇone\ntwo\nthree\nfour", Mode::Normal);
+        cx.simulate_keystrokes(["]", "l"]);
+        assert_eq!(cx.message().as_deref(), Some("E776: No location list"));
+
+        let diagnostic = |row, message: &str| lsp::Diagnostic {
+            range: lsp::Range::new(lsp::Position::new(row, 1), lsp::Position::new(row, 2)),
+            severity: Some(lsp::DiagnosticSeverity::ERROR),
+            message: message.to_string(),
+            ..Default::default()
+        };
+        cx.lsp
+            .notify::<lsp::notification::PublishDiagnostics>(lsp::PublishDiagnosticsParams {
+                uri: lsp::Url::from_file_path("/root/dir/file.rs").unwrap(),
+                version: None,
+                diagnostics: vec![
+                    diagnostic(0, "first"),
+                    diagnostic(2, "second"),
+                    diagnostic(3, "third"),
+                ],
+            });
+        cx.run_until_parked();
+
+        // the first step goes to the entry after the cursor
+        cx.simulate_keystrokes(["j", "]", "l"]);
+        cx.assert_state("one\ntwo\ntˇhree\nfour", Mode::Normal);
+        assert_eq!(cx.message().as_deref(), Some("(2 of 3): second"));
+        cx.simulate_keystrokes(["]", "l"]);
+        cx.assert_state("one\ntwo\nthree\nfˇour", Mode::Normal);
+        cx.simulate_keystrokes(["]", "l"]);
+        assert_eq!(cx.message().as_deref(), Some("E553: No more items"));
+        cx.simulate_keystrokes(["2", "[", "l"]);
+        cx.assert_state("oˇne\ntwo\nthree\nfour", Mode::Normal);
+
+        cx.simulate_keystrokes([":"]);
+        cx.simulate_input("lnext");
+        cx.simulate_keystrokes(["enter"]);
+        cx.assert_state("one\ntwo\ntˇhree\nfour", Mode::Normal);
+
+        cx.simulate_keystrokes([":"]);
+        cx.simulate_input("lopen");
+        cx.simulate_keystrokes(["enter"]);
+        cx.run_until_parked();
+        assert!(
+            cx.workspace(|workspace, cx| workspace.active_modal::<LocationListView>(cx).is_some())
+        );
+        cx.simulate_keystrokes(["down", "enter"]);
+        cx.assert_state("one\ntwo\nthree\nfˇour", Mode::Normal);
+        assert_eq!(cx.message().as_deref(), Some("(3 of 3): third"));
+    }
+}
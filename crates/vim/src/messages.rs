@@ -0,0 +1,108 @@
+use editor::Editor;
+use gpui::{actions, ViewContext, VisualContext};
+use workspace::{SplitDirection, Workspace};
+
+use crate::Vim;
+
+actions!(vim, [ShowMessages, ShowLastOutput]);
+
+pub(crate) fn register(workspace: &mut Workspace, _: &mut ViewContext<Workspace>) {
+    workspace.register_action(show_messages);
+    workspace.register_action(show_last_output);
+}
+
+/// `:messages` lists the messages shown in the status bar, the last of them last.
+fn show_messages(workspace: &mut Workspace, _: &ShowMessages, cx: &mut ViewContext<Workspace>) {
+    let text = Vim::update(cx, |vim, cx| {
+        vim.clear_operator(cx);
+        vim.workspace_state.messages.join("\n")
+    });
+    if !text.is_empty() {
+        open_in_split(workspace, &text, cx);
+    }
+}
+
+/// `g<` shows the output of the last command that had some (like `:make`, `K` or
+/// `:registers`) again.
+fn show_last_output(
+    workspace: &mut Workspace,
+    _: &ShowLastOutput,
+    cx: &mut ViewContext<Workspace>,
+) {
+    let output = Vim::update(cx, |vim, cx| {
+        vim.clear_operator(cx);
+        vim.workspace_state.last_output.clone()
+    });
+    if let Some(output) = output {
+        open_in_split(workspace, &output, cx);
+    }
+}
+
+/// Shows a command's output read-only in a split below, and keeps it for `g<`.
+pub(crate) fn show_output(workspace: &mut Workspace, text: &str, cx: &mut ViewContext<Workspace>) {
+    Vim::update(cx, |vim, _| {
+        vim.workspace_state.last_output = Some(text.to_string())
+    });
+    open_in_split(workspace, text, cx);
+}
+
+fn open_in_split(workspace: &mut Workspace, text: &str, cx: &mut ViewContext<Workspace>) {
+    let project = workspace.project().clone();
+    let buffer = match project.update(cx, |project, cx| project.create_buffer(text, None, cx)) {
+        Ok(buffer) => buffer,
+        Err(err) => {
+            log::error!("failed to show the output: {err}");
+            return;
+        }
+    };
+    let editor = cx.new_view(|cx| {
+        let mut editor = Editor::for_buffer(buffer, Some(project), cx);
+        editor.set_read_only(true);
+        editor
+    });
+    workspace.split_item(SplitDirection::Down, Box::new(editor), cx);
+}
+
+#[cfg(test)]
+mod test {
+    use editor::Editor;
+
+    use crate::{state::Mode, test::VimTestContext};
+
+    #[gpui::test]
+    async fn test_messages(cx: &mut gpui::TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+        let active_text = |cx: &mut VimTestContext| {
+            cx.workspace(|workspace, cx| {
+                let editor = workspace.active_item_as::<Editor>(cx).unwrap();
+                let text = editor.read(cx).text(cx);
+                text
+            })
+        };
+
+        // the messages shown are kept
+        cx.set_state("ˇone", Mode::Normal);
+        cx.simulate_keystrokes(["g", "a", "l", "g", "a"]);
+        cx.simulate_keystrokes([":"]);
+        cx.simulate_input("messages");
+        cx.simulate_keystrokes(["enter"]);
+        cx.run_until_parked();
+        assert_eq!(
+            active_text(&mut cx),
+            "<o> 111, Hex 6f, Oct 157\n<n> 110, Hex 6e, Oct 156"
+        );
+
+        // and so is the output of the last command that had some
+        cx.simulate_keystrokes(["ctrl-w", "k", "y", "y"]);
+        cx.simulate_keystrokes([":"]);
+        cx.simulate_input("registers 0");
+        cx.simulate_keystrokes(["enter"]);
+        cx.run_until_parked();
+        let registers = active_text(&mut cx);
+        cx.simulate_keystrokes([":"]);
+        cx.simulate_input("q");
+        cx.simulate_keystrokes(["enter", "g", "<"]);
+        cx.run_until_parked();
+        assert_eq!(active_text(&mut cx), registers);
+    }
+}
@@ -1,11 +1,15 @@
 use gpui::{div, Element, Render, Subscription, ViewContext};
 use workspace::{item::ItemHandle, ui::prelude::*, StatusItemView};
 
-use crate::{state::Mode, Vim};
+use crate::{normal::registers::register_preview, state::Mode, Vim};
 
 /// The ModeIndicator displays the current mode in the status bar.
 pub struct ModeIndicator {
     pub(crate) mode: Option<Mode>,
+    pub(crate) pending: String,
+    /// What the register selected with `"` holds, shown after the pending command
+    pub(crate) register_preview: Option<String>,
+    message: Option<String>,
     _subscription: Subscription,
 }
 
@@ -15,6 +19,9 @@ impl ModeIndicator {
         let _subscription = cx.observe_global::<Vim>(|this, cx| this.update_mode(cx));
         let mut this = Self {
             mode: None,
+            pending: String::new(),
+            register_preview: None,
+            message: None,
             _subscription,
         };
         this.update_mode(cx);
@@ -29,8 +36,17 @@ impl ModeIndicator {
 
         if vim.enabled {
             self.mode = Some(vim.state().mode);
+            self.pending = vim.pending_command();
+            self.register_preview = vim
+                .workspace_state
+                .selected_register
+                .and_then(|register| register_preview(&vim.workspace_state, register));
+            self.message = vim.workspace_state.message.clone();
         } else {
             self.mode = None;
+            self.pending.clear();
+            self.register_preview = None;
+            self.message = None;
         }
     }
 }
@@ -41,8 +57,22 @@ impl Render for ModeIndicator {
             return div().into_any();
         };
 
-        Label::new(format!("-- {} --", mode))
-            .size(LabelSize::Small)
+        h_flex()
+            .gap_4()
+            .children(
+                self.message
+                    .as_ref()
+                    .map(|message| Label::new(message.clone()).size(LabelSize::Small)),
+            )
+            .when(!self.pending.is_empty(), |this| {
+                this.child(Label::new(self.pending.clone()).size(LabelSize::Small))
+            })
+            .children(self.register_preview.as_ref().map(|preview| {
+                Label::new(preview.clone())
+                    .size(LabelSize::Small)
+                    .color(Color::Muted)
+            }))
+            .child(Label::new(format!("-- {} --", mode)).size(LabelSize::Small))
             .into_any_element()
     }
 }
@@ -0,0 +1,261 @@
+use editor::Editor;
+use gpui::{impl_actions, AppContext, EntityId, View, ViewContext, WindowContext};
+use serde_derive::Deserialize;
+use workspace::Workspace;
+
+use crate::{utils::command_abs_path, Vim};
+
+/// `:view {file}` opens the file read-only, like `:e` would open it to edit, and
+/// `:view` on its own makes the current buffer read-only.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub(crate) struct ViewFile {
+    path: Option<String>,
+}
+
+/// `:set nomodifiable` (or `:set noma`) makes the current buffer read-only, and
+/// `:set modifiable` lets it be changed again.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub(crate) struct SetModifiable {
+    modifiable: bool,
+}
+
+impl_actions!(vim, [ViewFile, SetModifiable]);
+
+pub(crate) fn register(workspace: &mut Workspace, _: &mut ViewContext<Workspace>) {
+    workspace.register_action(view_file);
+    workspace.register_action(|workspace: &mut Workspace, action: &SetModifiable, cx| {
+        if let Some(editor) = workspace.active_item_as::<Editor>(cx) {
+            set_read_only(workspace, &editor, !action.modifiable, cx);
+        }
+    });
+}
+
+/// The buffer read-only is kept for: the file's buffer, which every editor opened on
+/// the file shares, or the editor's own multibuffer.
+fn buffer_id(editor: &Editor, cx: &AppContext) -> EntityId {
+    let buffer = editor.buffer().read(cx);
+    buffer
+        .as_singleton()
+        .map_or(editor.buffer().entity_id(), |buffer| buffer.entity_id())
+}
+
+/// Makes the editor's buffer read-only, or modifiable again, in every editor showing
+/// it, so that a split of it can't change it either.
+fn set_read_only(
+    workspace: &Workspace,
+    editor: &View<Editor>,
+    read_only: bool,
+    cx: &mut WindowContext,
+) {
+    let buffer = buffer_id(editor.read(cx), cx);
+    Vim::update(cx, |vim, _| {
+        if read_only {
+            vim.workspace_state.read_only_buffers.insert(buffer);
+        } else {
+            vim.workspace_state.read_only_buffers.remove(&buffer);
+        }
+    });
+    let editors = workspace.items_of_type::<Editor>(cx).collect::<Vec<_>>();
+    for editor in editors {
+        editor.update(cx, |editor, cx| {
+            if buffer_id(editor, cx) == buffer {
+                editor.set_read_only(read_only);
+            }
+        });
+    }
+}
+
+/// Makes an editor read-only when it's activated if its buffer was made read-only,
+/// since it may have been opened on the buffer since.
+pub(crate) fn sync_read_only(vim: &Vim, editor: &View<Editor>, cx: &mut WindowContext) {
+    let read_only_buffers = &vim.workspace_state.read_only_buffers;
+    if read_only_buffers.is_empty() {
+        return;
+    }
+    editor.update(cx, |editor, cx| {
+        if read_only_buffers.contains(&buffer_id(editor, cx)) {
+            editor.set_read_only(true);
+        }
+    });
+}
+
+fn view_file(workspace: &mut Workspace, action: &ViewFile, cx: &mut ViewContext<Workspace>) {
+    let editor = workspace.active_item_as::<Editor>(cx);
+    let Some(path) = &action.path else {
+        if let Some(editor) = editor {
+            set_read_only(workspace, &editor, true, cx);
+        }
+        return;
+    };
+
+    let Some(abs_path) = command_abs_path(workspace, path, cx) else {
+        Vim::update(cx, |vim, _| {
+            vim.show_message("E32: No project to open the file in")
+        });
+        return;
+    };
+
+    let open = workspace.open_abs_path(abs_path, true, cx);
+    cx.spawn(|workspace, mut cx| async move {
+        let item = open.await?;
+        workspace.update(&mut cx, |workspace, cx| {
+            if let Some(editor) = item.act_as::<Editor>(cx) {
+                set_read_only(workspace, &editor, true, cx);
+            }
+        })
+    })
+    .detach_and_log_err(cx);
+}
+
+/// Whether the active editor can be changed. Read-only editors, like those opened
+/// with `:view`, ignore edits, so the commands that would make one show E21 instead.
+pub(crate) fn is_modifiable(vim: &Vim, cx: &AppContext) -> bool {
+    vim.active_editor
+        .as_ref()
+        .and_then(|editor| editor.upgrade())
+        .map_or(true, |editor| !editor.read(cx).read_only(cx))
+}
+
+/// Shows E21 when the active editor is read-only, returning whether it can be changed.
+pub(crate) fn check_modifiable(vim: &mut Vim, cx: &AppContext) -> bool {
+    let modifiable = is_modifiable(vim, cx);
+    if !modifiable {
+        vim.show_message("E21: Cannot make changes, 'modifiable' is off");
+    }
+    modifiable
+}
+
+/// `:view`, with or without a file.
+pub(crate) fn parse_view_file(query: &str) -> Option<ViewFile> {
+    let name_len = query
+        .find(|c: char| !c.is_ascii_alphabetic())
+        .unwrap_or(query.len());
+    let (name, args) = query.split_at(name_len);
+    if !matches!(name, "vie" | "view") {
+        return None;
+    }
+    let path = args.trim();
+    if !path.is_empty() && !args.starts_with(' ') {
+        return None;
+    }
+    Some(ViewFile {
+        path: (!path.is_empty()).then(|| path.to_string()),
+    })
+}
+
+/// `:set modifiable` or `:set nomodifiable`, also as `:setlocal` and with the short
+/// name `ma`.
+pub(crate) fn parse_set_modifiable(query: &str) -> Option<SetModifiable> {
+    let (name, option) = query.split_once(' ')?;
+    if !matches!(name, "se" | "set" | "setl" | "setlocal") {
+        return None;
+    }
+    let modifiable = match option.trim() {
+        "ma" | "modifiable" => true,
+        "noma" | "nomodifiable" => false,
+        _ => return None,
+    };
+    Some(SetModifiable { modifiable })
+}
+
+#[cfg(test)]
+mod test {
+    use editor::Editor;
+
+    use crate::{state::Mode, test::VimTestContext};
+
+    #[gpui::test]
+    async fn test_nomodifiable(cx: &mut gpui::TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+
+        cx.set_state("ˇone two\nthree", Mode::Normal);
+        cx.simulate_keystrokes([":"]);
+        cx.simulate_input("set noma");
+        cx.simulate_keystrokes(["enter"]);
+
+        // changes are refused
+        cx.simulate_keystrokes(["d", "d"]);
+        cx.assert_state("ˇone two\nthree", Mode::Normal);
+        assert_eq!(
+            cx.message().as_deref(),
+            Some("E21: Cannot make changes, 'modifiable' is off")
+        );
+        cx.simulate_keystrokes(["x"]);
+        cx.assert_state("ˇone two\nthree", Mode::Normal);
+        cx.simulate_keystrokes(["i"]);
+        cx.assert_state("ˇone two\nthree", Mode::Normal);
+        cx.simulate_keystrokes(["v", "e", "d"]);
+        cx.assert_state("ˇone two\nthree", Mode::Normal);
+
+        // but motions, yanks and searches work
+        cx.simulate_keystrokes(["w", "y", "w", "/", "t", "h", "enter"]);
+        cx.assert_state("one two\nˇthree", Mode::Normal);
+        assert_eq!(cx.read_register('"').as_deref(), Some("two"));
+
+        cx.simulate_keystrokes([":"]);
+        cx.simulate_input("set modifiable");
+        cx.simulate_keystrokes(["enter", "d", "d"]);
+        cx.assert_state("ˇone two", Mode::Normal);
+    }
+
+    #[gpui::test]
+    async fn test_nomodifiable_in_splits(cx: &mut gpui::TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+
+        cx.set_state("ˇone\ntwo", Mode::Normal);
+        cx.simulate_keystrokes(["ctrl-w", "v", ":"]);
+        cx.simulate_input("set noma");
+        cx.simulate_keystrokes(["enter"]);
+
+        // the other pane shows the same buffer, so it can't change it either
+        cx.simulate_keystrokes(["ctrl-w", "w", "d", "d"]);
+        cx.assert_state("ˇone\ntwo", Mode::Normal);
+        assert_eq!(
+            cx.message().as_deref(),
+            Some("E21: Cannot make changes, 'modifiable' is off")
+        );
+
+        // and making it modifiable there does so in both
+        cx.simulate_keystrokes([":"]);
+        cx.simulate_input("set ma");
+        cx.simulate_keystrokes(["enter", "ctrl-w", "w", "d", "d"]);
+        cx.assert_state("ˇtwo", Mode::Normal);
+    }
+
+    #[gpui::test]
+    async fn test_view_file(cx: &mut gpui::TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+        let fs = cx.workspace(|workspace, cx| workspace.project().read(cx).fs().clone());
+        fs.as_fake()
+            .insert_file("/root/dir/generated.rs", "// generated".into())
+            .await;
+
+        cx.simulate_keystrokes([":"]);
+        cx.simulate_input("view generated.rs");
+        cx.simulate_keystrokes(["enter"]);
+        cx.run_until_parked();
+        cx.simulate_keystrokes(["d", "w", "x"]);
+        cx.workspace(|workspace, cx| {
+            let editor = workspace.active_item_as::<Editor>(cx).unwrap();
+            assert!(editor.read(cx).read_only(cx));
+            assert_eq!(editor.read(cx).text(cx), "// generated");
+        });
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(super::parse_view_file("view").unwrap().path, None);
+        assert_eq!(
+            super::parse_view_file("vie a.rs").unwrap().path.as_deref(),
+            Some("a.rs")
+        );
+        assert!(super::parse_view_file("viewx").is_none());
+        assert!(!super::parse_set_modifiable("setl noma").unwrap().modifiable);
+        assert!(
+            super::parse_set_modifiable("set modifiable")
+                .unwrap()
+                .modifiable
+        );
+        assert!(super::parse_set_modifiable("set wrap").is_none());
+    }
+}
@@ -1,21 +1,24 @@
+use std::ops::Range;
+
 use editor::{
     display_map::{DisplaySnapshot, FoldPoint, ToDisplayPoint},
     movement::{
         self, find_boundary, find_preceding_boundary_display_point, FindRange, TextLayoutDetails,
     },
-    Bias, DisplayPoint, ToOffset,
+    Anchor, Bias, DisplayPoint, ToOffset,
 };
 use gpui::{actions, impl_actions, px, ViewContext, WindowContext};
-use language::{char_kind, CharKind, Point, Selection, SelectionGoal};
+use language::{language_settings::SoftWrap, CharKind, Point, Selection, SelectionGoal};
 use serde::Deserialize;
+use settings::Settings;
 use workspace::Workspace;
 
 use crate::{
     normal::normal_motion,
     state::{Mode, Operator},
-    utils::coerce_punctuation,
+    utils::{char_kind, coerce_punctuation, KeywordChars},
     visual::visual_motion,
-    Vim,
+    FoldOpen, RepeatMotion, Vim, VimSettings,
 };
 
 #[derive(Clone, Debug, PartialEq, Eq)]
@@ -57,7 +60,9 @@ pub enum Motion {
     FirstNonWhitespace {
         display_lines: bool,
     },
-    CurrentLine,
+    CurrentLine {
+        display_lines: bool,
+    },
     StartOfLine {
         display_lines: bool,
     },
@@ -68,7 +73,12 @@ pub enum Motion {
     EndOfParagraph,
     StartOfDocument,
     EndOfDocument,
-    Matching,
+    NextExcerpt,
+    PreviousExcerpt,
+    /// `%`, with the pairs the `matchpairs` setting adds for the language
+    Matching {
+        match_pairs: Vec<(char, char)>,
+    },
     FindForward {
         before: bool,
         char: char,
@@ -81,11 +91,24 @@ pub enum Motion {
         mode: FindRange,
         smartcase: bool,
     },
+    /// A two-character search, like the vim-sneak plugin's `s` and `S`
+    Sneak {
+        first_char: char,
+        second_char: char,
+        smartcase: bool,
+    },
+    SneakBackward {
+        first_char: char,
+        second_char: char,
+        smartcase: bool,
+    },
+    /// `;`, with the last motion it repeats
     RepeatFind {
-        last_find: Box<Motion>,
+        last_motion: Box<Motion>,
     },
+    /// `,`, which repeats the motion the other way
     RepeatFindReversed {
-        last_find: Box<Motion>,
+        last_motion: Box<Motion>,
     },
     NextLineStart,
     StartOfLineDownward,
@@ -94,6 +117,10 @@ pub enum Motion {
     WindowTop,
     WindowMiddle,
     WindowBottom,
+    Jump {
+        anchor: Anchor,
+        line: bool,
+    },
 }
 
 #[derive(Clone, Deserialize, PartialEq)]
@@ -218,6 +245,8 @@ actions!(
         EndOfParagraph,
         StartOfDocument,
         EndOfDocument,
+        NextExcerpt,
+        PreviousExcerpt,
         Matching,
         NextLineStart,
         StartOfLineDownward,
@@ -278,7 +307,12 @@ pub fn register(workspace: &mut Workspace, _: &mut ViewContext<Workspace>) {
         )
     });
     workspace.register_action(|_: &mut Workspace, _: &CurrentLine, cx: _| {
-        motion(Motion::CurrentLine, cx)
+        motion(
+            Motion::CurrentLine {
+                display_lines: false,
+            },
+            cx,
+        )
     });
     workspace.register_action(|_: &mut Workspace, _: &StartOfParagraph, cx: _| {
         motion(Motion::StartOfParagraph, cx)
@@ -292,8 +326,16 @@ pub fn register(workspace: &mut Workspace, _: &mut ViewContext<Workspace>) {
     workspace.register_action(|_: &mut Workspace, _: &EndOfDocument, cx: _| {
         motion(Motion::EndOfDocument, cx)
     });
-    workspace
-        .register_action(|_: &mut Workspace, _: &Matching, cx: _| motion(Motion::Matching, cx));
+    workspace.register_action(|_: &mut Workspace, _: &NextExcerpt, cx: _| {
+        motion(Motion::NextExcerpt, cx)
+    });
+    workspace.register_action(|_: &mut Workspace, _: &PreviousExcerpt, cx: _| {
+        motion(Motion::PreviousExcerpt, cx)
+    });
+    workspace.register_action(|_: &mut Workspace, _: &Matching, cx: _| {
+        let match_pairs = match_pairs(cx);
+        motion(Motion::Matching { match_pairs }, cx)
+    });
 
     workspace.register_action(
         |_: &mut Workspace, &NextWordStart { ignore_punctuation }: &NextWordStart, cx: _| {
@@ -348,24 +390,14 @@ pub fn register(workspace: &mut Workspace, _: &mut ViewContext<Workspace>) {
         .register_action(|_: &mut Workspace, &GoToColumn, cx: _| motion(Motion::GoToColumn, cx));
 
     workspace.register_action(|_: &mut Workspace, _: &RepeatFind, cx: _| {
-        if let Some(last_find) = Vim::read(cx)
-            .workspace_state
-            .last_find
-            .clone()
-            .map(Box::new)
-        {
-            motion(Motion::RepeatFind { last_find }, cx);
+        if let Some(last_motion) = repeatable_motion(cx).map(Box::new) {
+            motion(Motion::RepeatFind { last_motion }, cx);
         }
     });
 
     workspace.register_action(|_: &mut Workspace, _: &RepeatFindReversed, cx: _| {
-        if let Some(last_find) = Vim::read(cx)
-            .workspace_state
-            .last_find
-            .clone()
-            .map(Box::new)
-        {
-            motion(Motion::RepeatFindReversed { last_find }, cx);
+        if let Some(last_motion) = repeatable_motion(cx).map(Box::new) {
+            motion(Motion::RepeatFindReversed { last_motion }, cx);
         }
     });
     workspace.register_action(|_: &mut Workspace, &WindowTop, cx: _| motion(Motion::WindowTop, cx));
@@ -378,24 +410,107 @@ pub fn register(workspace: &mut Workspace, _: &mut ViewContext<Workspace>) {
 }
 
 pub(crate) fn motion(motion: Motion, cx: &mut WindowContext) {
-    if let Some(Operator::FindForward { .. }) | Some(Operator::FindBackward { .. }) =
-        Vim::read(cx).active_operator()
+    if let Some(
+        Operator::FindForward { .. }
+        | Operator::FindBackward { .. }
+        | Operator::Sneak { .. }
+        | Operator::SneakBackward { .. },
+    ) = Vim::read(cx).active_operator()
     {
         Vim::update(cx, |vim, cx| vim.pop_operator(cx));
     }
+    if let Some(kind) = motion.repeat_kind() {
+        Vim::update(cx, |vim, _| {
+            let motions = &mut vim.workspace_state.repeatable_motions;
+            motions.retain(|motion| motion.repeat_kind() != Some(kind));
+            motions.insert(0, motion.clone());
+        });
+    }
 
     let count = Vim::update(cx, |vim, cx| vim.take_count(cx));
     let operator = Vim::read(cx).active_operator();
+    let motion = display_line_motion(motion, operator.is_some(), cx);
+    let motion = whichwrap_motion(motion, operator.is_some(), cx);
     match Vim::read(cx).state().mode {
         Mode::Normal => normal_motion(motion, operator, count, cx),
         Mode::Visual | Mode::VisualLine | Mode::VisualBlock => visual_motion(motion, count, cx),
-        Mode::Insert => {
+        Mode::Insert | Mode::Select => {
             // Shouldn't execute a motion in insert mode. Ignoring
         }
     }
     Vim::update(cx, |vim, cx| vim.clear_operator(cx));
 }
 
+/// The motion `;` and `,` repeat: the last one made, or the last of the kind the
+/// `repeat_motion` setting prefers.
+fn repeatable_motion(cx: &WindowContext) -> Option<Motion> {
+    let preferred = match VimSettings::get_global(cx).repeat_motion {
+        RepeatMotion::Last => None,
+        RepeatMotion::Find => Some(RepeatKind::Find),
+        RepeatMotion::Sneak => Some(RepeatKind::Sneak),
+    };
+    let motions = &Vim::read(cx).workspace_state.repeatable_motions;
+    motions
+        .iter()
+        .find(|motion| preferred.is_some() && motion.repeat_kind() == preferred)
+        .or_else(|| motions.first())
+        .cloned()
+}
+
+/// Swaps a line motion for its display line version when soft wrap is on, if the
+/// `display_lines` setting asks for it.
+fn display_line_motion(motion: Motion, after_operator: bool, cx: &mut WindowContext) -> Motion {
+    let settings = VimSettings::get_global(cx).display_lines;
+    let enabled = if after_operator {
+        settings.operators
+    } else {
+        settings.motions
+    };
+    let soft_wrapped = enabled
+        && Vim::update(cx, |vim, cx| {
+            vim.update_active_editor(cx, |_, editor, cx| {
+                editor.soft_wrap_mode(cx) != SoftWrap::None
+            })
+        })
+        .unwrap_or(false);
+    if !soft_wrapped {
+        return motion;
+    }
+
+    let display_lines = true;
+    match motion {
+        Motion::Down { .. } => Motion::Down { display_lines },
+        Motion::Up { .. } => Motion::Up { display_lines },
+        Motion::StartOfLine { .. } => Motion::StartOfLine { display_lines },
+        Motion::EndOfLine { .. } => Motion::EndOfLine { display_lines },
+        Motion::FirstNonWhitespace { .. } => Motion::FirstNonWhitespace { display_lines },
+        Motion::CurrentLine { .. } => Motion::CurrentLine { display_lines },
+        motion => motion,
+    }
+}
+
+/// Swaps `h` and `l` for the motions that go on to the previous and next line, and
+/// backspace and space for the ones that stop at the ends of it, as the `whichwrap`
+/// setting asks.
+fn whichwrap_motion(motion: Motion, after_operator: bool, cx: &WindowContext) -> Motion {
+    let whichwrap = &VimSettings::get_global(cx).whichwrap;
+    match motion {
+        Motion::Left if whichwrap.contains('h') => Motion::Backspace,
+        Motion::Right if whichwrap.contains('l') && !after_operator => Motion::Space,
+        Motion::Backspace if !whichwrap.contains('b') => Motion::Left,
+        Motion::Space if !whichwrap.contains('s') => Motion::Right,
+        motion => motion,
+    }
+}
+
+/// The kinds of motion `;` and `,` repeat, each of which has its own slot for the
+/// last one made.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RepeatKind {
+    Find,
+    Sneak,
+}
+
 // Motion handling is specified here:
 // https://github.com/vim/vim/blob/master/runtime/doc/motion.txt
 impl Motion {
@@ -406,16 +521,19 @@ impl Motion {
             | Up { .. }
             | StartOfDocument
             | EndOfDocument
-            | CurrentLine
             | NextLineStart
             | StartOfLineDownward
             | StartOfParagraph
             | WindowTop
             | WindowMiddle
             | WindowBottom
+            | NextExcerpt
+            | PreviousExcerpt
             | EndOfParagraph => true,
+            Jump { line, .. } => *line,
+            CurrentLine { display_lines } => !display_lines,
             EndOfLine { .. }
-            | Matching
+            | Matching { .. }
             | FindForward { .. }
             | Left
             | Backspace
@@ -434,6 +552,8 @@ impl Motion {
             | PreviousSubwordEnd { .. }
             | FirstNonWhitespace { .. }
             | FindBackward { .. }
+            | Sneak { .. }
+            | SneakBackward { .. }
             | RepeatFind { .. }
             | RepeatFindReversed { .. } => false,
         }
@@ -442,11 +562,11 @@ impl Motion {
     pub fn infallible(&self) -> bool {
         use Motion::*;
         match self {
-            StartOfDocument | EndOfDocument | CurrentLine => true,
+            StartOfDocument | EndOfDocument | CurrentLine { .. } | Jump { .. } => true,
             Down { .. }
             | Up { .. }
             | EndOfLine { .. }
-            | Matching
+            | Matching { .. }
             | FindForward { .. }
             | RepeatFind { .. }
             | Left
@@ -456,6 +576,8 @@ impl Motion {
             | StartOfLine { .. }
             | StartOfParagraph
             | EndOfParagraph
+            | NextExcerpt
+            | PreviousExcerpt
             | StartOfLineDownward
             | EndOfLineDownward
             | GoToColumn
@@ -469,6 +591,8 @@ impl Motion {
             | PreviousSubwordEnd { .. }
             | FirstNonWhitespace { .. }
             | FindBackward { .. }
+            | Sneak { .. }
+            | SneakBackward { .. }
             | RepeatFindReversed { .. }
             | WindowTop
             | WindowMiddle
@@ -477,6 +601,96 @@ impl Motion {
         }
     }
 
+    /// The kind of motion this is for the `foldopen` setting. Vertical motions like
+    /// `j` only open a closed fold they move into when it's `all`.
+    pub fn fold_open(&self) -> FoldOpen {
+        use Motion::*;
+        match self {
+            Left
+            | Backspace
+            | Right
+            | Space
+            | StartOfLine { .. }
+            | EndOfLine { .. }
+            | GoToColumn
+            | NextWordStart { .. }
+            | NextWordEnd { .. }
+            | PreviousWordStart { .. }
+            | PreviousWordEnd { .. }
+            | NextSubwordStart { .. }
+            | NextSubwordEnd { .. }
+            | PreviousSubwordStart { .. }
+            | PreviousSubwordEnd { .. }
+            | FirstNonWhitespace { .. }
+            | FindForward { .. }
+            | FindBackward { .. }
+            | Sneak { .. }
+            | SneakBackward { .. }
+            | RepeatFind { .. }
+            | RepeatFindReversed { .. } => FoldOpen::Hor,
+            StartOfParagraph | EndOfParagraph | NextExcerpt | PreviousExcerpt => FoldOpen::Block,
+            StartOfDocument | EndOfDocument => FoldOpen::Jump,
+            Matching { .. } => FoldOpen::Percent,
+            Jump { .. } => FoldOpen::Mark,
+            Down { .. }
+            | Up { .. }
+            | CurrentLine { .. }
+            | NextLineStart
+            | StartOfLineDownward
+            | EndOfLineDownward
+            | WindowTop
+            | WindowMiddle
+            | WindowBottom => FoldOpen::All,
+        }
+    }
+
+    /// Whether vim treats this motion as an error when it can't move (like `j`
+    /// on the last line), which stops a running macro.
+    pub fn fails_when_stuck(&self) -> bool {
+        use Motion::*;
+        match self {
+            Down { .. }
+            | Up { .. }
+            | Left
+            | Backspace
+            | Right
+            | Space
+            | Matching { .. }
+            | FindForward { .. }
+            | FindBackward { .. }
+            | Sneak { .. }
+            | SneakBackward { .. }
+            | RepeatFind { .. }
+            | RepeatFindReversed { .. }
+            | NextLineStart => true,
+            StartOfDocument
+            | EndOfDocument
+            | CurrentLine { .. }
+            | EndOfLine { .. }
+            | StartOfLine { .. }
+            | StartOfParagraph
+            | EndOfParagraph
+            | NextExcerpt
+            | PreviousExcerpt
+            | StartOfLineDownward
+            | EndOfLineDownward
+            | GoToColumn
+            | NextWordStart { .. }
+            | NextWordEnd { .. }
+            | PreviousWordStart { .. }
+            | PreviousWordEnd { .. }
+            | NextSubwordStart { .. }
+            | NextSubwordEnd { .. }
+            | PreviousSubwordStart { .. }
+            | PreviousSubwordEnd { .. }
+            | FirstNonWhitespace { .. }
+            | WindowTop
+            | WindowMiddle
+            | WindowBottom
+            | Jump { .. } => false,
+        }
+    }
+
     pub fn inclusive(&self) -> bool {
         use Motion::*;
         match self {
@@ -484,10 +698,10 @@ impl Motion {
             | Up { .. }
             | StartOfDocument
             | EndOfDocument
-            | CurrentLine
+            | CurrentLine { .. }
             | EndOfLine { .. }
             | EndOfLineDownward
-            | Matching
+            | Matching { .. }
             | FindForward { .. }
             | WindowTop
             | WindowMiddle
@@ -505,16 +719,33 @@ impl Motion {
             | StartOfLineDownward
             | StartOfParagraph
             | EndOfParagraph
+            | NextExcerpt
+            | PreviousExcerpt
             | GoToColumn
             | NextWordStart { .. }
             | PreviousWordStart { .. }
             | NextSubwordStart { .. }
             | PreviousSubwordStart { .. }
             | FirstNonWhitespace { .. }
-            | FindBackward { .. } => false,
-            RepeatFind { last_find: motion } | RepeatFindReversed { last_find: motion } => {
-                motion.inclusive()
+            | FindBackward { .. }
+            | Sneak { .. }
+            | SneakBackward { .. }
+            | Jump { .. } => false,
+            RepeatFind {
+                last_motion: motion,
             }
+            | RepeatFindReversed {
+                last_motion: motion,
+            } => motion.inclusive(),
+        }
+    }
+
+    /// The kind of motion `;` and `,` repeat this as, if they repeat it.
+    pub fn repeat_kind(&self) -> Option<RepeatKind> {
+        match self {
+            Motion::FindForward { .. } | Motion::FindBackward { .. } => Some(RepeatKind::Find),
+            Motion::Sneak { .. } | Motion::SneakBackward { .. } => Some(RepeatKind::Sneak),
+            _ => None,
         }
     }
 
@@ -525,6 +756,7 @@ impl Motion {
         goal: SelectionGoal,
         maybe_times: Option<usize>,
         text_layout_details: &TextLayoutDetails,
+        keyword_chars: &KeywordChars,
     ) -> Option<(DisplayPoint, SelectionGoal)> {
         let times = maybe_times.unwrap_or(1);
         use Motion::*;
@@ -547,35 +779,35 @@ impl Motion {
             Right => (right(map, point, times), SelectionGoal::None),
             Space => (space(map, point, times), SelectionGoal::None),
             NextWordStart { ignore_punctuation } => (
-                next_word_start(map, point, *ignore_punctuation, times),
+                next_word_start(map, point, *ignore_punctuation, keyword_chars, times),
                 SelectionGoal::None,
             ),
             NextWordEnd { ignore_punctuation } => (
-                next_word_end(map, point, *ignore_punctuation, times, true),
+                next_word_end(map, point, *ignore_punctuation, keyword_chars, times, true),
                 SelectionGoal::None,
             ),
             PreviousWordStart { ignore_punctuation } => (
-                previous_word_start(map, point, *ignore_punctuation, times),
+                previous_word_start(map, point, *ignore_punctuation, keyword_chars, times),
                 SelectionGoal::None,
             ),
             PreviousWordEnd { ignore_punctuation } => (
-                previous_word_end(map, point, *ignore_punctuation, times),
+                previous_word_end(map, point, *ignore_punctuation, keyword_chars, times),
                 SelectionGoal::None,
             ),
             NextSubwordStart { ignore_punctuation } => (
-                next_subword_start(map, point, *ignore_punctuation, times),
+                next_subword_start(map, point, *ignore_punctuation, keyword_chars, times),
                 SelectionGoal::None,
             ),
             NextSubwordEnd { ignore_punctuation } => (
-                next_subword_end(map, point, *ignore_punctuation, times, true),
+                next_subword_end(map, point, *ignore_punctuation, keyword_chars, times, true),
                 SelectionGoal::None,
             ),
             PreviousSubwordStart { ignore_punctuation } => (
-                previous_subword_start(map, point, *ignore_punctuation, times),
+                previous_subword_start(map, point, *ignore_punctuation, keyword_chars, times),
                 SelectionGoal::None,
             ),
             PreviousSubwordEnd { ignore_punctuation } => (
-                previous_subword_end(map, point, *ignore_punctuation, times),
+                previous_subword_end(map, point, *ignore_punctuation, keyword_chars, times),
                 SelectionGoal::None,
             ),
             FirstNonWhitespace { display_lines } => (
@@ -590,21 +822,28 @@ impl Motion {
                 end_of_line(map, *display_lines, point, times),
                 SelectionGoal::None,
             ),
-            StartOfParagraph => (
-                movement::start_of_paragraph(map, point, times),
+            StartOfParagraph => (start_of_paragraph(map, point, times), SelectionGoal::None),
+            EndOfParagraph => (
+                map.clip_at_line_end(end_of_paragraph(map, point, times)),
                 SelectionGoal::None,
             ),
-            EndOfParagraph => (
-                map.clip_at_line_end(movement::end_of_paragraph(map, point, times)),
+            CurrentLine {
+                display_lines: false,
+            } => (next_line_end(map, point, times), SelectionGoal::None),
+            CurrentLine {
+                display_lines: true,
+            } => (
+                next_display_line_end(map, point, times),
                 SelectionGoal::None,
             ),
-            CurrentLine => (next_line_end(map, point, times), SelectionGoal::None),
             StartOfDocument => (start_of_document(map, point, times), SelectionGoal::None),
             EndOfDocument => (
                 end_of_document(map, point, maybe_times),
                 SelectionGoal::None,
             ),
-            Matching => (matching(map, point), SelectionGoal::None),
+            NextExcerpt => (next_excerpt(map, point, times), SelectionGoal::None),
+            PreviousExcerpt => (previous_excerpt(map, point, times), SelectionGoal::None),
+            Matching { match_pairs } => (matching(map, point, match_pairs), SelectionGoal::None),
             // t f
             FindForward {
                 before,
@@ -625,8 +864,24 @@ impl Motion {
                 find_backward(map, point, *after, *char, times, *mode, *smartcase),
                 SelectionGoal::None,
             ),
-            // ; -- repeat the last find done with t, f, T, F
-            RepeatFind { last_find } => match **last_find {
+            Sneak {
+                first_char,
+                second_char,
+                smartcase,
+            } => {
+                return sneak(map, point, *first_char, *second_char, times, *smartcase)
+                    .map(|new_point| (new_point, SelectionGoal::None))
+            }
+            SneakBackward {
+                first_char,
+                second_char,
+                smartcase,
+            } => {
+                return sneak_backward(map, point, *first_char, *second_char, times, *smartcase)
+                    .map(|new_point| (new_point, SelectionGoal::None))
+            }
+            // ; -- repeat the last find done with t, f, T, F, or the last sneak
+            RepeatFind { last_motion } => match **last_motion {
                 Motion::FindForward {
                     before,
                     char,
@@ -658,10 +913,20 @@ impl Motion {
 
                     (new_point, SelectionGoal::None)
                 }
+                Motion::Sneak { .. } | Motion::SneakBackward { .. } => {
+                    return last_motion.move_point(
+                        map,
+                        point,
+                        goal,
+                        Some(times),
+                        text_layout_details,
+                        keyword_chars,
+                    )
+                }
                 _ => return None,
             },
-            // , -- repeat the last find done with t, f, T, F, in opposite direction
-            RepeatFindReversed { last_find } => match **last_find {
+            // , -- repeat the last find done with t, f, T, F, or the last sneak, in opposite direction
+            RepeatFindReversed { last_motion } => match **last_motion {
                 Motion::FindForward {
                     before,
                     char,
@@ -693,6 +958,22 @@ impl Motion {
 
                     return new_point.map(|new_point| (new_point, SelectionGoal::None));
                 }
+                Motion::Sneak {
+                    first_char,
+                    second_char,
+                    smartcase,
+                } => {
+                    return sneak_backward(map, point, first_char, second_char, times, smartcase)
+                        .map(|new_point| (new_point, SelectionGoal::None))
+                }
+                Motion::SneakBackward {
+                    first_char,
+                    second_char,
+                    smartcase,
+                } => {
+                    return sneak(map, point, first_char, second_char, times, smartcase)
+                        .map(|new_point| (new_point, SelectionGoal::None))
+                }
                 _ => return None,
             },
             NextLineStart => (next_line_start(map, point, times), SelectionGoal::None),
@@ -702,6 +983,14 @@ impl Motion {
             WindowTop => window_top(map, point, &text_layout_details, times - 1),
             WindowMiddle => window_middle(map, point, &text_layout_details),
             WindowBottom => window_bottom(map, point, &text_layout_details, times - 1),
+            Jump { anchor, line } => {
+                let point = anchor.to_display_point(map);
+                if *line {
+                    (first_non_whitespace(map, false, point), SelectionGoal::None)
+                } else {
+                    (point, SelectionGoal::None)
+                }
+            }
         };
 
         (new_point != point || infallible).then_some((new_point, goal))
@@ -715,17 +1004,77 @@ impl Motion {
         times: Option<usize>,
         expand_to_surrounding_newline: bool,
         text_layout_details: &TextLayoutDetails,
+        keyword_chars: &KeywordChars,
     ) -> bool {
+        self.expand_selection_linewise(
+            map,
+            selection,
+            times,
+            expand_to_surrounding_newline,
+            text_layout_details,
+            keyword_chars,
+        )
+        .is_some()
+    }
+
+    /// Like `expand_selection`, but returns whether the operator acts on whole lines
+    /// (or `None` if the motion failed). Besides the linewise motions, this is the case
+    /// for an exclusive motion that ends in column 0 and starts at or before the first
+    /// non-blank of its line, like `d}` from the start of a paragraph.
+    pub fn expand_selection_linewise(
+        &self,
+        map: &DisplaySnapshot,
+        selection: &mut Selection<DisplayPoint>,
+        times: Option<usize>,
+        expand_to_surrounding_newline: bool,
+        text_layout_details: &TextLayoutDetails,
+        keyword_chars: &KeywordChars,
+    ) -> Option<bool> {
+        // `dd` on display lines acts on the parts of the line that are shown, unless
+        // those are all of it (the line isn't wrapped), when it's linewise as usual.
+        if let Motion::CurrentLine {
+            display_lines: true,
+        } = self
+        {
+            let start = start_of_line(map, true, selection.head());
+            let end = next_display_line_end(map, selection.head(), times.unwrap_or(1));
+            let (start_point, end_point) = (start.to_point(map), end.to_point(map));
+            if start_point.column == 0
+                && end_point.column == map.buffer_snapshot.line_len(end_point.row)
+            {
+                let lines = end_point.row - start_point.row + 1;
+                return Motion::CurrentLine {
+                    display_lines: false,
+                }
+                .expand_selection_linewise(
+                    map,
+                    selection,
+                    Some(lines as usize),
+                    expand_to_surrounding_newline,
+                    text_layout_details,
+                    keyword_chars,
+                );
+            }
+            selection.start = start;
+            selection.end = end;
+            selection.reversed = false;
+            selection.goal = SelectionGoal::None;
+            return Some(false);
+        }
+
         if let Some((new_head, goal)) = self.move_point(
             map,
             selection.head(),
             selection.goal,
             times,
             &text_layout_details,
+            keyword_chars,
         ) {
             selection.set_head(new_head, goal);
 
-            if self.linewise() {
+            // like vim, an operator includes a closed fold as a whole, and then
+            // acts on whole lines, so `dl` on a fold deletes all of it
+            if self.linewise() || touches_fold(map, selection) {
                 selection.start = map.prev_line_boundary(selection.start.to_point(map)).1;
 
                 if expand_to_surrounding_newline {
@@ -734,7 +1083,7 @@ impl Motion {
                         *selection.end.column_mut() = 0;
                         selection.end = map.clip_point(selection.end, Bias::Right);
                         // Don't reset the end here
-                        return true;
+                        return Some(true);
                     } else if selection.start.row() > 0 {
                         *selection.start.row_mut() -= 1;
                         *selection.start.column_mut() = map.line_len(selection.start.row());
@@ -743,6 +1092,7 @@ impl Motion {
                 }
 
                 (_, selection.end) = map.next_line_boundary(selection.end.to_point(map));
+                return Some(true);
             } else {
                 // Another special case: When using the "w" motion in combination with an
                 // operator and the last word moved over is at the end of a line, the end of
@@ -770,6 +1120,20 @@ impl Motion {
                     && selection.end.row() > selection.start.row()
                     && selection.end.column() == 0
                 {
+                    // And if the motion also started at or before the first non-blank of
+                    // its line, it becomes linewise: "d}" from the start of a paragraph
+                    // deletes its lines, but leaves the blank line after it.
+                    let (indent, _) = map.line_indent(selection.start.row());
+                    if selection.start.column() <= indent {
+                        *selection.start.column_mut() = 0;
+                        if !expand_to_surrounding_newline {
+                            *selection.end.row_mut() -= 1;
+                            *selection.end.column_mut() = map.line_len(selection.end.row());
+                            selection.end = map.clip_point(selection.end, Bias::Left);
+                        }
+                        return Some(true);
+                    }
+
                     inclusive = true;
                     *selection.end.row_mut() -= 1;
                     *selection.end.column_mut() = 0;
@@ -783,9 +1147,9 @@ impl Motion {
                     *selection.end.column_mut() += 1;
                 }
             }
-            true
+            Some(false)
         } else {
-            false
+            None
         }
     }
 }
@@ -974,14 +1338,17 @@ pub(crate) fn next_word_start(
     map: &DisplaySnapshot,
     mut point: DisplayPoint,
     ignore_punctuation: bool,
+    keyword_chars: &KeywordChars,
     times: usize,
 ) -> DisplayPoint {
     let scope = map.buffer_snapshot.language_scope_at(point.to_point(map));
     for _ in 0..times {
         let mut crossed_newline = false;
         let new_point = movement::find_boundary(map, point, FindRange::MultiLine, |left, right| {
-            let left_kind = coerce_punctuation(char_kind(&scope, left), ignore_punctuation);
-            let right_kind = coerce_punctuation(char_kind(&scope, right), ignore_punctuation);
+            let left_kind =
+                coerce_punctuation(char_kind(&scope, left, keyword_chars), ignore_punctuation);
+            let right_kind =
+                coerce_punctuation(char_kind(&scope, right, keyword_chars), ignore_punctuation);
             let at_newline = right == '\n';
 
             let found = (left_kind != right_kind && right_kind != CharKind::Whitespace)
@@ -1003,6 +1370,7 @@ pub(crate) fn next_word_end(
     map: &DisplaySnapshot,
     mut point: DisplayPoint,
     ignore_punctuation: bool,
+    keyword_chars: &KeywordChars,
     times: usize,
     allow_cross_newline: bool,
 ) -> DisplayPoint {
@@ -1015,8 +1383,10 @@ pub(crate) fn next_word_end(
             new_point,
             FindRange::MultiLine,
             |left, right| {
-                let left_kind = coerce_punctuation(char_kind(&scope, left), ignore_punctuation);
-                let right_kind = coerce_punctuation(char_kind(&scope, right), ignore_punctuation);
+                let left_kind =
+                    coerce_punctuation(char_kind(&scope, left, keyword_chars), ignore_punctuation);
+                let right_kind =
+                    coerce_punctuation(char_kind(&scope, right, keyword_chars), ignore_punctuation);
                 let at_newline = right == '\n';
 
                 if !allow_cross_newline && at_newline {
@@ -1041,10 +1411,11 @@ pub(crate) fn next_word_end(
     point
 }
 
-fn previous_word_start(
+pub(crate) fn previous_word_start(
     map: &DisplaySnapshot,
     mut point: DisplayPoint,
     ignore_punctuation: bool,
+    keyword_chars: &KeywordChars,
     times: usize,
 ) -> DisplayPoint {
     let scope = map.buffer_snapshot.language_scope_at(point.to_point(map));
@@ -1056,8 +1427,10 @@ fn previous_word_start(
             point,
             FindRange::MultiLine,
             |left, right| {
-                let left_kind = coerce_punctuation(char_kind(&scope, left), ignore_punctuation);
-                let right_kind = coerce_punctuation(char_kind(&scope, right), ignore_punctuation);
+                let left_kind =
+                    coerce_punctuation(char_kind(&scope, left, keyword_chars), ignore_punctuation);
+                let right_kind =
+                    coerce_punctuation(char_kind(&scope, right, keyword_chars), ignore_punctuation);
 
                 (left_kind != right_kind && !right.is_whitespace()) || left == '\n'
             },
@@ -1074,6 +1447,7 @@ fn previous_word_end(
     map: &DisplaySnapshot,
     point: DisplayPoint,
     ignore_punctuation: bool,
+    keyword_chars: &KeywordChars,
     times: usize,
 ) -> DisplayPoint {
     let scope = map.buffer_snapshot.language_scope_at(point.to_point(map));
@@ -1088,8 +1462,10 @@ fn previous_word_end(
             point,
             FindRange::MultiLine,
             |left, right| {
-                let left_kind = coerce_punctuation(char_kind(&scope, left), ignore_punctuation);
-                let right_kind = coerce_punctuation(char_kind(&scope, right), ignore_punctuation);
+                let left_kind =
+                    coerce_punctuation(char_kind(&scope, left, keyword_chars), ignore_punctuation);
+                let right_kind =
+                    coerce_punctuation(char_kind(&scope, right, keyword_chars), ignore_punctuation);
                 match (left_kind, right_kind) {
                     (CharKind::Punctuation, CharKind::Whitespace)
                     | (CharKind::Punctuation, CharKind::Word)
@@ -1112,14 +1488,17 @@ fn next_subword_start(
     map: &DisplaySnapshot,
     mut point: DisplayPoint,
     ignore_punctuation: bool,
+    keyword_chars: &KeywordChars,
     times: usize,
 ) -> DisplayPoint {
     let scope = map.buffer_snapshot.language_scope_at(point.to_point(map));
     for _ in 0..times {
         let mut crossed_newline = false;
         let new_point = movement::find_boundary(map, point, FindRange::MultiLine, |left, right| {
-            let left_kind = coerce_punctuation(char_kind(&scope, left), ignore_punctuation);
-            let right_kind = coerce_punctuation(char_kind(&scope, right), ignore_punctuation);
+            let left_kind =
+                coerce_punctuation(char_kind(&scope, left, keyword_chars), ignore_punctuation);
+            let right_kind =
+                coerce_punctuation(char_kind(&scope, right, keyword_chars), ignore_punctuation);
             let at_newline = right == '\n';
 
             let is_word_start = (left_kind != right_kind) && !left.is_alphanumeric();
@@ -1145,6 +1524,7 @@ pub(crate) fn next_subword_end(
     map: &DisplaySnapshot,
     mut point: DisplayPoint,
     ignore_punctuation: bool,
+    keyword_chars: &KeywordChars,
     times: usize,
     allow_cross_newline: bool,
 ) -> DisplayPoint {
@@ -1156,8 +1536,10 @@ pub(crate) fn next_subword_end(
         let mut need_backtrack = false;
         let new_point =
             movement::find_boundary(map, new_point, FindRange::MultiLine, |left, right| {
-                let left_kind = coerce_punctuation(char_kind(&scope, left), ignore_punctuation);
-                let right_kind = coerce_punctuation(char_kind(&scope, right), ignore_punctuation);
+                let left_kind =
+                    coerce_punctuation(char_kind(&scope, left, keyword_chars), ignore_punctuation);
+                let right_kind =
+                    coerce_punctuation(char_kind(&scope, right, keyword_chars), ignore_punctuation);
                 let at_newline = right == '\n';
 
                 if !allow_cross_newline && at_newline {
@@ -1193,6 +1575,7 @@ fn previous_subword_start(
     map: &DisplaySnapshot,
     mut point: DisplayPoint,
     ignore_punctuation: bool,
+    keyword_chars: &KeywordChars,
     times: usize,
 ) -> DisplayPoint {
     let scope = map.buffer_snapshot.language_scope_at(point.to_point(map));
@@ -1205,8 +1588,10 @@ fn previous_subword_start(
             point,
             FindRange::MultiLine,
             |left, right| {
-                let left_kind = coerce_punctuation(char_kind(&scope, left), ignore_punctuation);
-                let right_kind = coerce_punctuation(char_kind(&scope, right), ignore_punctuation);
+                let left_kind =
+                    coerce_punctuation(char_kind(&scope, left, keyword_chars), ignore_punctuation);
+                let right_kind =
+                    coerce_punctuation(char_kind(&scope, right, keyword_chars), ignore_punctuation);
                 let at_newline = right == '\n';
 
                 let is_word_start = (left_kind != right_kind) && !left.is_alphanumeric();
@@ -1234,6 +1619,7 @@ fn previous_subword_end(
     map: &DisplaySnapshot,
     point: DisplayPoint,
     ignore_punctuation: bool,
+    keyword_chars: &KeywordChars,
     times: usize,
 ) -> DisplayPoint {
     let scope = map.buffer_snapshot.language_scope_at(point.to_point(map));
@@ -1248,8 +1634,10 @@ fn previous_subword_end(
             point,
             FindRange::MultiLine,
             |left, right| {
-                let left_kind = coerce_punctuation(char_kind(&scope, left), ignore_punctuation);
-                let right_kind = coerce_punctuation(char_kind(&scope, right), ignore_punctuation);
+                let left_kind =
+                    coerce_punctuation(char_kind(&scope, left, keyword_chars), ignore_punctuation);
+                let right_kind =
+                    coerce_punctuation(char_kind(&scope, right, keyword_chars), ignore_punctuation);
 
                 let is_subword_end =
                     left != '_' && right == '_' || left.is_lowercase() && right.is_uppercase();
@@ -1288,7 +1676,7 @@ pub(crate) fn first_non_whitespace(
 
         last_point = point;
 
-        if char_kind(&scope, ch) != CharKind::Whitespace {
+        if language::char_kind(&scope, ch) != CharKind::Whitespace {
             break;
         }
     }
@@ -1348,7 +1736,123 @@ fn end_of_document(
     map.clip_point(new_point.to_display_point(map), Bias::Left)
 }
 
-fn matching(map: &DisplaySnapshot, display_point: DisplayPoint) -> DisplayPoint {
+/// The rows that the excerpts of a multibuffer start on, including the first one's
+/// (row 0). An editor of a single buffer has no excerpt boundaries, so there are none.
+fn excerpt_start_rows(map: &DisplaySnapshot) -> Vec<u32> {
+    map.buffer_snapshot
+        .excerpt_boundaries_in_range(0usize..)
+        .map(|boundary| boundary.row)
+        .collect()
+}
+
+/// `{`, which also stops at the start of an excerpt in a multibuffer.
+fn start_of_paragraph(
+    map: &DisplaySnapshot,
+    mut point: DisplayPoint,
+    times: usize,
+) -> DisplayPoint {
+    let excerpt_starts = excerpt_start_rows(map);
+    if excerpt_starts.is_empty() {
+        return movement::start_of_paragraph(map, point, times);
+    }
+    for _ in 0..times {
+        let row = point.to_point(map).row;
+        let new_point = movement::start_of_paragraph(map, point, 1);
+        point = match excerpt_starts.iter().rev().find(|start| **start < row) {
+            Some(start) if new_point.to_point(map).row < *start => {
+                Point::new(*start, 0).to_display_point(map)
+            }
+            _ => new_point,
+        };
+    }
+    point
+}
+
+/// `}`, which also stops at the end of an excerpt in a multibuffer.
+fn end_of_paragraph(map: &DisplaySnapshot, mut point: DisplayPoint, times: usize) -> DisplayPoint {
+    let excerpt_starts = excerpt_start_rows(map);
+    if excerpt_starts.is_empty() {
+        return movement::end_of_paragraph(map, point, times);
+    }
+    for _ in 0..times {
+        let row = point.to_point(map).row;
+        let new_point = movement::end_of_paragraph(map, point, 1);
+        let excerpt_end = excerpt_starts
+            .iter()
+            .map(|start| start.saturating_sub(1))
+            .find(|end| *end > row);
+        point = match excerpt_end {
+            Some(end) if new_point.to_point(map).row > end => {
+                let end = Point::new(end, map.buffer_snapshot.line_len(end));
+                end.to_display_point(map)
+            }
+            _ => new_point,
+        };
+    }
+    point
+}
+
+/// `] ]` goes to the start of the next excerpt in a multibuffer (or the last line if
+/// there isn't one, as vim does when there are no more sections).
+fn next_excerpt(map: &DisplaySnapshot, point: DisplayPoint, times: usize) -> DisplayPoint {
+    let row = point.to_point(map).row;
+    let new_row = excerpt_start_rows(map)
+        .into_iter()
+        .filter(|start| *start > row)
+        .nth(times - 1)
+        .unwrap_or(map.max_buffer_row());
+    map.clip_point(Point::new(new_row, 0).to_display_point(map), Bias::Left)
+}
+
+/// `[ [` goes to the start of the excerpt (or the previous one, from its first line),
+/// or to the first line if there isn't one.
+fn previous_excerpt(map: &DisplaySnapshot, point: DisplayPoint, times: usize) -> DisplayPoint {
+    let row = point.to_point(map).row;
+    let new_row = excerpt_start_rows(map)
+        .into_iter()
+        .rev()
+        .filter(|start| *start < row)
+        .nth(times - 1)
+        .unwrap_or(0);
+    map.clip_point(Point::new(new_row, 0).to_display_point(map), Bias::Left)
+}
+
+/// The pairs the `matchpairs` setting adds for the language at the cursor, written
+/// like `<:>`, which `%` and the text objects match along with the language's brackets.
+pub(crate) fn match_pairs(cx: &mut WindowContext) -> Vec<(char, char)> {
+    let language = Vim::update(cx, |vim, cx| {
+        vim.update_active_editor(cx, |_, editor, cx| {
+            let head = editor.selections.newest::<Point>(cx).head();
+            let snapshot = editor.buffer().read(cx).snapshot(cx);
+            snapshot.language_at(head).map(|language| language.name())
+        })
+    })
+    .flatten();
+    let Some(language) = language else {
+        return Vec::new();
+    };
+    VimSettings::get_global(cx)
+        .matchpairs
+        .get(language.as_ref())
+        .into_iter()
+        .flatten()
+        .filter_map(|pair| parse_match_pair(pair))
+        .collect()
+}
+
+fn parse_match_pair(pair: &str) -> Option<(char, char)> {
+    let mut chars = pair.chars();
+    match (chars.next(), chars.next(), chars.next(), chars.next()) {
+        (Some(open), Some(':'), Some(close), None) if open != close => Some((open, close)),
+        _ => None,
+    }
+}
+
+fn matching(
+    map: &DisplaySnapshot,
+    display_point: DisplayPoint,
+    match_pairs: &[(char, char)],
+) -> DisplayPoint {
     // https://github.com/vim/vim/blob/1d87e11a1ef201b26ed87585fba70182ad0c468a/runtime/doc/motion.txt#L1200
     let point = display_point.to_point(map);
     let offset = point.to_offset(&map.buffer_snapshot);
@@ -1365,40 +1869,105 @@ fn matching(map: &DisplaySnapshot, display_point: DisplayPoint) -> DisplayPoint
     let ranges = map
         .buffer_snapshot
         .bracket_ranges(visible_line_range.clone());
-    if let Some(ranges) = ranges {
-        let line_range = line_range.start.to_offset(&map.buffer_snapshot)
-            ..line_range.end.to_offset(&map.buffer_snapshot);
-        let mut closest_pair_destination = None;
-        let mut closest_distance = usize::MAX;
-
-        for (open_range, close_range) in ranges {
-            if open_range.start >= offset && line_range.contains(&open_range.start) {
-                let distance = open_range.start - offset;
-                if distance < closest_distance {
-                    closest_pair_destination = Some(close_range.start);
-                    closest_distance = distance;
-                    continue;
-                }
+    let line_range = line_range.start.to_offset(&map.buffer_snapshot)
+        ..line_range.end.to_offset(&map.buffer_snapshot);
+    let mut closest_pair_destination = None;
+    let mut closest_distance = usize::MAX;
+
+    for (open_range, close_range) in ranges.into_iter().flatten() {
+        if open_range.start >= offset && line_range.contains(&open_range.start) {
+            let distance = open_range.start - offset;
+            if distance < closest_distance {
+                closest_pair_destination = Some(close_range.start);
+                closest_distance = distance;
+                continue;
             }
+        }
 
-            if close_range.start >= offset && line_range.contains(&close_range.start) {
-                let distance = close_range.start - offset;
-                if distance < closest_distance {
-                    closest_pair_destination = Some(open_range.start);
-                    closest_distance = distance;
-                    continue;
-                }
+        if close_range.start >= offset && line_range.contains(&close_range.start) {
+            let distance = close_range.start - offset;
+            if distance < closest_distance {
+                closest_pair_destination = Some(open_range.start);
+                closest_distance = distance;
+                continue;
             }
+        }
 
-            continue;
+        continue;
+    }
+
+    // the pairs from `matchpairs` are matched by counting them, as vim does
+    if let Some((distance, destination)) =
+        match_pair_destination(map, offset, &line_range, match_pairs)
+    {
+        if distance < closest_distance {
+            closest_pair_destination = Some(destination);
         }
+    }
 
-        closest_pair_destination
-            .map(|destination| destination.to_display_point(map))
-            .unwrap_or(display_point)
+    closest_pair_destination
+        .map(|destination| destination.to_display_point(map))
+        .unwrap_or(display_point)
+}
+
+/// The first of the pairs at or after the offset on its line, and where the other
+/// half of it is, skipping over nested pairs.
+fn match_pair_destination(
+    map: &DisplaySnapshot,
+    offset: usize,
+    line_range: &Range<usize>,
+    match_pairs: &[(char, char)],
+) -> Option<(usize, usize)> {
+    if match_pairs.is_empty() {
+        return None;
+    }
+    let buffer = &map.buffer_snapshot;
+    let (start, c) = buffer
+        .chars_at(offset)
+        .scan(offset, |ix, c| {
+            let start = *ix;
+            *ix += c.len_utf8();
+            Some((start, c))
+        })
+        .take_while(|(ix, _)| *ix < line_range.end)
+        .find(|(_, c)| {
+            match_pairs
+                .iter()
+                .any(|(open, close)| c == open || c == close)
+        })?;
+    let &(open, close) = match_pairs
+        .iter()
+        .find(|(open, close)| c == *open || c == *close)?;
+
+    let mut depth = 0;
+    if c == open {
+        let mut ix = start;
+        for c in buffer.chars_at(start) {
+            if c == open {
+                depth += 1;
+            } else if c == close {
+                depth -= 1;
+                if depth == 0 {
+                    return Some((start - offset, ix));
+                }
+            }
+            ix += c.len_utf8();
+        }
     } else {
-        display_point
+        let mut ix = start + c.len_utf8();
+        for c in buffer.reversed_chars_at(ix) {
+            ix -= c.len_utf8();
+            if c == close {
+                depth += 1;
+            } else if c == open {
+                depth -= 1;
+                if depth == 0 {
+                    return Some((start - offset, ix));
+                }
+            }
+        }
     }
+    None
 }
 
 fn find_forward(
@@ -1471,6 +2040,64 @@ fn find_backward(
     }
 }
 
+/// Where the `times`th match of the two characters after `from` starts.
+fn sneak(
+    map: &DisplaySnapshot,
+    from: DisplayPoint,
+    first_char: char,
+    second_char: char,
+    times: usize,
+    smartcase: bool,
+) -> Option<DisplayPoint> {
+    let start = from.to_offset(map, Bias::Left);
+    let mut chars = map.buffer_snapshot.chars_at(start).peekable();
+    let mut offset = start;
+    let mut count = 0;
+    while let Some(char) = chars.next() {
+        if offset > start
+            && is_character_match(first_char, char, smartcase)
+            && chars
+                .peek()
+                .is_some_and(|next| is_character_match(second_char, *next, smartcase))
+        {
+            count += 1;
+            if count == times {
+                return Some(offset.to_display_point(map));
+            }
+        }
+        offset += char.len_utf8();
+    }
+    None
+}
+
+/// Where the `times`th match of the two characters before `from` starts.
+fn sneak_backward(
+    map: &DisplaySnapshot,
+    from: DisplayPoint,
+    first_char: char,
+    second_char: char,
+    times: usize,
+    smartcase: bool,
+) -> Option<DisplayPoint> {
+    let start = from.to_offset(map, Bias::Left);
+    let mut next = map.buffer_snapshot.chars_at(start).next();
+    let mut offset = start;
+    let mut count = 0;
+    for char in map.buffer_snapshot.reversed_chars_at(start) {
+        offset -= char.len_utf8();
+        if is_character_match(first_char, char, smartcase)
+            && next.is_some_and(|next| is_character_match(second_char, next, smartcase))
+        {
+            count += 1;
+            if count == times {
+                return Some(offset.to_display_point(map));
+            }
+        }
+        next = Some(char);
+    }
+    None
+}
+
 fn is_character_match(target: char, other: char, smartcase: bool) -> bool {
     if smartcase {
         if target.is_uppercase() {
@@ -1493,6 +2120,13 @@ fn go_to_column(map: &DisplaySnapshot, point: DisplayPoint, times: usize) -> Dis
     right(map, correct_line, times.saturating_sub(1))
 }
 
+/// Whether the selection starts or ends inside a closed fold.
+pub(crate) fn touches_fold(map: &DisplaySnapshot, selection: &Selection<DisplayPoint>) -> bool {
+    let start = selection.start.to_offset(map, Bias::Left);
+    let end = selection.end.to_offset(map, Bias::Left);
+    map.intersects_fold(start) || (end > start && map.intersects_fold(end - 1))
+}
+
 pub(crate) fn next_line_end(
     map: &DisplaySnapshot,
     mut point: DisplayPoint,
@@ -1504,6 +2138,13 @@ pub(crate) fn next_line_end(
     end_of_line(map, false, point, 1)
 }
 
+/// The end of the display line `times - 1` below the point, so that `dd` can act on
+/// the parts of a soft-wrapped line.
+fn next_display_line_end(map: &DisplaySnapshot, point: DisplayPoint, times: usize) -> DisplayPoint {
+    let row = (point.row() + times as u32 - 1).min(map.max_point().row());
+    map.clip_point(DisplayPoint::new(row, map.line_len(row)), Bias::Left)
+}
+
 fn window_top(
     map: &DisplaySnapshot,
     point: DisplayPoint,
@@ -1597,8 +2238,46 @@ fn window_bottom(
 #[cfg(test)]
 mod test {
 
-    use crate::test::NeovimBackedTestContext;
+    use crate::{
+        state::{Mode, Operator},
+        test::{NeovimBackedTestContext, VimTestContext},
+        PushOperator, RepeatMotion, VimSettings,
+    };
+    use collections::HashMap;
+    use editor::{Editor, ExcerptRange, MultiBuffer};
+    use gpui::{Context, KeyBinding, VisualContext};
     use indoc::indoc;
+    use language::{Buffer, BufferId, Capability, Point};
+    use settings::SettingsStore;
+
+    #[gpui::test]
+    async fn test_whichwrap(cx: &mut gpui::TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+
+        // by default only backspace and space go on to the next line
+        cx.set_state("onˇe\ntwo", Mode::Normal);
+        cx.simulate_keystrokes(["l"]);
+        cx.assert_state("onˇe\ntwo", Mode::Normal);
+        cx.simulate_keystrokes(["space"]);
+        cx.assert_state("one\nˇtwo", Mode::Normal);
+        cx.simulate_keystrokes(["h"]);
+        cx.assert_state("one\nˇtwo", Mode::Normal);
+        cx.simulate_keystrokes(["backspace"]);
+        cx.assert_state("onˇe\ntwo", Mode::Normal);
+
+        cx.update_global(|store: &mut SettingsStore, cx| {
+            store.update_user_settings::<VimSettings>(cx, |s| s.whichwrap = Some("h,l".into()));
+        });
+        cx.simulate_keystrokes(["l"]);
+        cx.assert_state("one\nˇtwo", Mode::Normal);
+        cx.simulate_keystrokes(["backspace"]);
+        cx.assert_state("one\nˇtwo", Mode::Normal);
+        cx.simulate_keystrokes(["h"]);
+        cx.assert_state("onˇe\ntwo", Mode::Normal);
+        // but dl still only deletes the character
+        cx.simulate_keystrokes(["d", "l"]);
+        cx.assert_state("oˇn\ntwo", Mode::Normal);
+    }
 
     #[gpui::test]
     async fn test_start_end_of_paragraph(cx: &mut gpui::TestAppContext) {
@@ -1677,6 +2356,47 @@ mod test {
             .await
     }
 
+    #[gpui::test]
+    async fn test_paragraph_motions_with_operators(cx: &mut gpui::TestAppContext) {
+        let mut cx = NeovimBackedTestContext::new(cx).await;
+
+        // from the start of a paragraph, the lines are deleted but the blank line stays
+        cx.set_shared_state("ˇabc\ndef\n\nparagraph\nthe second")
+            .await;
+        cx.simulate_shared_keystrokes(["d", "}"]).await;
+        cx.assert_shared_state("ˇ\nparagraph\nthe second").await;
+
+        // from the middle of a line, up to the end of the paragraph
+        cx.set_shared_state("abc\ndˇef\n\nparagraph").await;
+        cx.simulate_shared_keystrokes(["d", "}"]).await;
+        cx.assert_shared_state("abc\nˇd\n\nparagraph").await;
+
+        // the last paragraph is deleted up to the end of the file
+        cx.set_shared_state("abc\n\nˇlast\nline").await;
+        cx.simulate_shared_keystrokes(["d", "}"]).await;
+        cx.assert_shared_state("abc\n\nˇ").await;
+
+        // the blank lines at the end of the file are kept
+        cx.set_shared_state("abc\nˇdef\n\n").await;
+        cx.simulate_shared_keystrokes(["d", "}"]).await;
+        cx.assert_shared_state("abc\nˇ\n").await;
+
+        // and those at the start
+        cx.set_shared_state("\n\nabc\nˇdef").await;
+        cx.simulate_shared_keystrokes(["d", "{"]).await;
+        cx.assert_shared_state("\nˇdef").await;
+
+        // up to the start of the file
+        cx.set_shared_state("abc\nˇdef").await;
+        cx.simulate_shared_keystrokes(["d", "{"]).await;
+        cx.assert_shared_state("ˇdef").await;
+
+        // the lines are yanked linewise
+        cx.set_shared_state("ˇabc\ndef\n\nx").await;
+        cx.simulate_shared_keystrokes(["y", "}", "p"]).await;
+        cx.assert_shared_state("abc\nˇabc\ndef\ndef\n\nx").await;
+    }
+
     #[gpui::test]
     async fn test_matching(cx: &mut gpui::TestAppContext) {
         let mut cx = NeovimBackedTestContext::new(cx).await;
@@ -2105,4 +2825,149 @@ mod test {
         "})
             .await;
     }
+
+    #[gpui::test]
+    async fn test_excerpt_motions(cx: &mut gpui::TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+
+        let buffer =
+            cx.new_model(|_| Buffer::new(0, BufferId::new(1).unwrap(), "a\nb\nc\nd\ne\nf\ng\nh"));
+        let multibuffer = cx.new_model(|cx| {
+            let mut multibuffer = MultiBuffer::new(0, Capability::ReadWrite);
+            multibuffer.push_excerpts(
+                buffer,
+                [
+                    ExcerptRange {
+                        context: Point::new(0, 0)..Point::new(2, 1),
+                        primary: None,
+                    },
+                    ExcerptRange {
+                        context: Point::new(4, 0)..Point::new(5, 1),
+                        primary: None,
+                    },
+                    ExcerptRange {
+                        context: Point::new(7, 0)..Point::new(7, 1),
+                        primary: None,
+                    },
+                ],
+                cx,
+            );
+            multibuffer
+        });
+        let editor = cx.workspace(|workspace, cx| {
+            let editor = cx.new_view(|cx| Editor::for_multibuffer(multibuffer, None, cx));
+            workspace.add_item_to_active_pane(Box::new(editor.clone()), cx);
+            editor
+        });
+        cx.run_until_parked();
+
+        let cursor = |cx: &mut VimTestContext| {
+            cx.update_view(editor.clone(), |editor, cx| {
+                editor.selections.newest::<Point>(cx).head()
+            })
+        };
+        assert_eq!(cursor(&mut cx), Point::new(0, 0));
+
+        // ] ] and [ [ go between the excerpts (the multibuffer is "a b c | e f | h")
+        cx.simulate_keystrokes(["]", "]"]);
+        assert_eq!(cursor(&mut cx), Point::new(3, 0));
+        cx.simulate_keystrokes(["]", "]"]);
+        assert_eq!(cursor(&mut cx), Point::new(5, 0));
+        cx.simulate_keystrokes(["[", "["]);
+        assert_eq!(cursor(&mut cx), Point::new(3, 0));
+        cx.simulate_keystrokes(["2", "[", "["]);
+        assert_eq!(cursor(&mut cx), Point::new(0, 0));
+        cx.simulate_keystrokes(["2", "]", "]"]);
+        assert_eq!(cursor(&mut cx), Point::new(5, 0));
+
+        // paragraphs end at the edges of excerpts
+        cx.simulate_keystrokes(["g", "g", "}"]);
+        assert_eq!(cursor(&mut cx), Point::new(2, 0));
+        cx.simulate_keystrokes(["}"]);
+        assert_eq!(cursor(&mut cx), Point::new(4, 0));
+        cx.simulate_keystrokes(["{"]);
+        assert_eq!(cursor(&mut cx), Point::new(3, 0));
+        cx.simulate_keystrokes(["{"]);
+        assert_eq!(cursor(&mut cx), Point::new(0, 0));
+    }
+
+    #[gpui::test]
+    async fn test_matchpairs(cx: &mut gpui::TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+        cx.update_global(|store: &mut SettingsStore, cx| {
+            store.update_user_settings::<VimSettings>(cx, |settings| {
+                settings.matchpairs = Some(HashMap::from_iter([(
+                    "Rust".to_string(),
+                    vec!["«:»".to_string()],
+                )]))
+            });
+        });
+
+        // % goes to the other half of the pair, past nested ones
+        cx.set_state("ˇa «b «c» d» e", Mode::Normal);
+        cx.simulate_keystrokes(["%"]);
+        cx.assert_state("a «b «c» dˇ» e", Mode::Normal);
+        cx.simulate_keystrokes(["%"]);
+        cx.assert_state("a ˇ«b «c» d» e", Mode::Normal);
+
+        // and text objects select inside or around them
+        cx.set_state("a «b «cˇ» d» e", Mode::Normal);
+        cx.simulate_keystrokes(["d", "i", "«"]);
+        cx.assert_state("a «b «ˇ» d» e", Mode::Normal);
+        cx.set_state("a «b «c» ˇd» e", Mode::Normal);
+        cx.simulate_keystrokes(["d", "a", "»"]);
+        cx.assert_state("a ˇ e", Mode::Normal);
+    }
+
+    #[gpui::test]
+    async fn test_sneak(cx: &mut gpui::TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+        cx.update(|cx| {
+            cx.bind_keys([
+                KeyBinding::new(
+                    "s",
+                    PushOperator(Operator::Sneak { first_char: None }),
+                    Some("Editor && VimControl && !VimWaiting"),
+                ),
+                KeyBinding::new(
+                    "shift-s",
+                    PushOperator(Operator::SneakBackward { first_char: None }),
+                    Some("Editor && VimControl && !VimWaiting"),
+                ),
+            ])
+        });
+
+        // the match under the cursor is skipped, and ; and , repeat the sneak
+        cx.set_state("ˇab cd ab cd ab", Mode::Normal);
+        cx.simulate_keystrokes(["s", "a", "b"]);
+        cx.assert_state("ab cd ˇab cd ab", Mode::Normal);
+        cx.simulate_keystrokes([";"]);
+        cx.assert_state("ab cd ab cd ˇab", Mode::Normal);
+        cx.simulate_keystrokes(["shift-s", "c", "d"]);
+        cx.assert_state("ab cd ab ˇcd ab", Mode::Normal);
+        cx.simulate_keystrokes([";"]);
+        cx.assert_state("ab ˇcd ab cd ab", Mode::Normal);
+        cx.simulate_keystrokes([","]);
+        cx.assert_state("ab cd ab ˇcd ab", Mode::Normal);
+
+        // until a find is made, which they repeat instead
+        cx.simulate_keystrokes(["shift-f", "d"]);
+        cx.assert_state("ab cˇd ab cd ab", Mode::Normal);
+        cx.simulate_keystrokes([","]);
+        cx.assert_state("ab cd ab cˇd ab", Mode::Normal);
+
+        // unless they're set to prefer sneaks
+        cx.update_global(|store: &mut SettingsStore, cx| {
+            store.update_user_settings::<VimSettings>(cx, |settings| {
+                settings.repeat_motion = Some(RepeatMotion::Sneak)
+            });
+        });
+        cx.simulate_keystrokes([";"]);
+        cx.assert_state("ab cd ab ˇcd ab", Mode::Normal);
+
+        // a sneak is exclusive after an operator
+        cx.set_state("ˇab cd ab", Mode::Normal);
+        cx.simulate_keystrokes(["d", "s", "a", "b"]);
+        cx.assert_state("ˇab", Mode::Normal);
+    }
 }
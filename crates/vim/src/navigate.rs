@@ -0,0 +1,368 @@
+use std::ops::Range;
+
+use editor::{scroll::Autoscroll, Editor};
+use gpui::{actions, ViewContext, WindowContext};
+use language::{BufferSnapshot, Point, ToOffset, ToPoint};
+use settings::Settings;
+use workspace::Workspace;
+
+use crate::{Vim, VimSettings};
+
+actions!(
+    vim,
+    [
+        NextDiagnostic,
+        PreviousDiagnostic,
+        NextChange,
+        PreviousChange,
+        NextMethod,
+        PreviousMethod
+    ]
+);
+
+pub(crate) fn register(workspace: &mut Workspace, _: &mut ViewContext<Workspace>) {
+    workspace.register_action(|workspace: &mut Workspace, _: &NextDiagnostic, cx| {
+        move_to_item(workspace, Item::Diagnostic, true, cx)
+    });
+    workspace.register_action(|workspace: &mut Workspace, _: &PreviousDiagnostic, cx| {
+        move_to_item(workspace, Item::Diagnostic, false, cx)
+    });
+    workspace.register_action(|workspace: &mut Workspace, _: &NextChange, cx| {
+        move_to_item(workspace, Item::Change, true, cx)
+    });
+    workspace.register_action(|workspace: &mut Workspace, _: &PreviousChange, cx| {
+        move_to_item(workspace, Item::Change, false, cx)
+    });
+    workspace.register_action(|workspace: &mut Workspace, _: &NextMethod, cx| {
+        move_to_item(workspace, Item::Method, true, cx)
+    });
+    workspace.register_action(|workspace: &mut Workspace, _: &PreviousMethod, cx| {
+        move_to_item(workspace, Item::Method, false, cx)
+    });
+}
+
+/// The things `]d`, `]c` and `]m` go to.
+#[derive(Clone, Copy)]
+enum Item {
+    Diagnostic,
+    /// A hunk of the file's changes since the last commit
+    Change,
+    /// A function or a method of the language's outline
+    Method,
+}
+
+impl Item {
+    /// Where the items in the editor start, in order.
+    fn starts(self, editor: &Editor, cx: &WindowContext) -> Vec<Point> {
+        let snapshot = editor.buffer().read(cx).snapshot(cx);
+        let mut starts = match self {
+            Item::Diagnostic => snapshot
+                .diagnostics_in_range::<_, Point>(0..snapshot.len(), false)
+                .filter(|entry| entry.diagnostic.is_primary)
+                .map(|entry| entry.range.start)
+                .collect::<Vec<_>>(),
+            Item::Change => snapshot
+                .git_diff_hunks_in_range(0..u32::MAX)
+                .map(|hunk| Point::new(hunk.associated_range.start, 0))
+                .collect(),
+            Item::Method => snapshot
+                .as_singleton()
+                .and_then(|(_, _, buffer)| {
+                    let outline = buffer.outline(None)?;
+                    Some(
+                        outline
+                            .items
+                            .iter()
+                            .filter(|item| {
+                                let range = item.range.start.to_offset(buffer)
+                                    ..item.range.end.to_offset(buffer);
+                                is_function(buffer, range)
+                            })
+                            .map(|item| item.range.start.to_point(buffer))
+                            .collect(),
+                    )
+                })
+                .unwrap_or_default(),
+        };
+        starts.sort();
+        starts.dedup();
+        starts
+    }
+
+    fn none_message(self) -> &'static str {
+        match self {
+            Item::Diagnostic => "No diagnostics",
+            Item::Change => "No changes",
+            Item::Method => "No functions",
+        }
+    }
+}
+
+/// Whether an outline item is a function or a method rather than, say, a type or a
+/// module: whether the syntax node it spans is a kind of function, like Rust's
+/// `function_item` or JavaScript's `method_definition`.
+fn is_function(buffer: &BufferSnapshot, range: Range<usize>) -> bool {
+    let Some(layer) = buffer.syntax_layer_at(range.start) else {
+        return false;
+    };
+    let mut node = layer
+        .node()
+        .descendant_for_byte_range(range.start, range.end);
+    while let Some(item) = node.filter(|node| node.byte_range() == range) {
+        if item.kind().contains("function") || item.kind().contains("method") {
+            return true;
+        }
+        node = item.parent();
+    }
+    false
+}
+
+/// `]d`, `]c` and `]m` move to the start of the next diagnostic, change or function
+/// (the count-th one), and `[d`, `[c` and `[m` to the previous one.
+fn move_to_item(
+    workspace: &mut Workspace,
+    item: Item,
+    forwards: bool,
+    cx: &mut ViewContext<Workspace>,
+) {
+    let count = Vim::update(cx, |vim, cx| {
+        let count = vim.take_count(cx).unwrap_or(1);
+        vim.clear_operator(cx);
+        count
+    });
+    let Some(editor) = workspace.active_item_as::<Editor>(cx) else {
+        return;
+    };
+    let wrap = VimSettings::get_global(cx).bracket_wrap.in_file;
+    let moved = editor.update(cx, |editor, cx| {
+        let starts = item.starts(editor, cx);
+        if starts.is_empty() {
+            return Err(item.none_message());
+        }
+        let cursor = editor.selections.newest::<Point>(cx).head();
+        let Some(target) = nth_item(&starts, cursor, forwards, count, wrap) else {
+            return Err(hit_end_message(forwards));
+        };
+        editor.change_selections(Some(Autoscroll::fit()), cx, |s| {
+            s.select_ranges([target..target])
+        });
+        Ok(())
+    });
+    if let Err(message) = moved {
+        Vim::update(cx, |vim, _| vim.show_message(message));
+    }
+}
+
+/// The count-th of the (sorted) items after the cursor, or before it, as the bracket
+/// commands like `]d` and `]s` find them. Past the last item they wrap around to the
+/// first if `wrap` is set, and otherwise stop at the last one there is. Nothing is
+/// found when there are none to go to.
+pub(crate) fn nth_item<T: Ord + Copy>(
+    items: &[T],
+    cursor: T,
+    forwards: bool,
+    count: usize,
+    wrap: bool,
+) -> Option<T> {
+    let mut found = None;
+    for _ in 0..count.max(1) {
+        let from = found.unwrap_or(cursor);
+        let next = if forwards {
+            items
+                .iter()
+                .find(|item| **item > from)
+                .or(items.first().filter(|_| wrap))
+        } else {
+            items
+                .iter()
+                .rev()
+                .find(|item| **item < from)
+                .or(items.last().filter(|_| wrap))
+        };
+        match next {
+            Some(next) => found = Some(*next),
+            None => break,
+        }
+    }
+    found
+}
+
+/// What vim says when a search doesn't wrap around the end of the file.
+pub(crate) fn hit_end_message(forwards: bool) -> &'static str {
+    if forwards {
+        "E385: Search hit BOTTOM without match"
+    } else {
+        "E384: Search hit TOP without match"
+    }
+}
+
+/// The entry `delta` entries on from the current one of a list of `len` entries, as
+/// `]q` and `]l` step through the quickfix and location lists. Like `nth_item`, it
+/// wraps around the ends of the list if `wrap` is set, and otherwise stops at them.
+/// The current entry can be just before or after the list (-1 or `len`), when the
+/// cursor isn't on one.
+pub(crate) fn step_in_list(current: isize, delta: isize, len: usize, wrap: bool) -> Option<usize> {
+    if len == 0 {
+        return None;
+    }
+    let last = len as isize - 1;
+    let target = current + delta;
+    if wrap {
+        Some(target.rem_euclid(len as isize) as usize)
+    } else if (target > last && current >= last) || (target < 0 && current <= 0) {
+        None
+    } else {
+        Some(target.clamp(0, last) as usize)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use indoc::indoc;
+    use language::{Language, LanguageConfig};
+    use settings::SettingsStore;
+
+    use super::{nth_item, step_in_list};
+    use crate::{state::Mode, test::VimTestContext, BracketWrap, VimSettings};
+
+    #[test]
+    fn test_nth_item() {
+        let items = [2, 5, 9];
+        assert_eq!(nth_item(&items, 0, true, 1, true), Some(2));
+        assert_eq!(nth_item(&items, 2, true, 2, true), Some(9));
+        assert_eq!(nth_item(&items, 5, true, 2, true), Some(2));
+        assert_eq!(nth_item(&items, 5, true, 2, false), Some(9));
+        assert_eq!(nth_item(&items, 9, true, 1, false), None);
+        assert_eq!(nth_item(&items, 6, false, 3, true), Some(9));
+        assert_eq!(nth_item(&items, 1, false, 1, true), Some(9));
+        assert_eq!(nth_item(&items, 1, false, 1, false), None);
+        assert_eq!(nth_item::<usize>(&[], 1, true, 1, true), None);
+
+        assert_eq!(step_in_list(0, 2, 3, false), Some(2));
+        assert_eq!(step_in_list(1, 5, 3, false), Some(2));
+        assert_eq!(step_in_list(2, 1, 3, false), None);
+        assert_eq!(step_in_list(2, 1, 3, true), Some(0));
+        assert_eq!(step_in_list(0, -2, 3, true), Some(1));
+        assert_eq!(step_in_list(-1, 1, 3, false), Some(0));
+    }
+
+    #[gpui::test]
+    async fn test_next_diagnostic(cx: &mut gpui::TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+
+        cx.set_state("ˇone\ntwo\nthree\nfour", Mode::Normal);
+        cx.simulate_keystrokes(["]", "d"]);
+        assert_eq!(cx.message().as_deref(), Some("No diagnostics"));
+
+        let diagnostic = |row| lsp::Diagnostic {
+            range: lsp::Range::new(lsp::Position::new(row, 1), lsp::Position::new(row, 2)),
+            severity: Some(lsp::DiagnosticSeverity::ERROR),
+            message: "error".to_string(),
+            ..Default::default()
+        };
+        cx.lsp
+            .notify::<lsp::notification::PublishDiagnostics>(lsp::PublishDiagnosticsParams {
+                uri: lsp::Url::from_file_path("/root/dir/file.rs").unwrap(),
+                version: None,
+                diagnostics: vec![diagnostic(0), diagnostic(1), diagnostic(3)],
+            });
+        cx.run_until_parked();
+
+        cx.simulate_keystrokes(["2", "]", "d"]);
+        cx.assert_state("one\ntˇwo\nthree\nfour", Mode::Normal);
+        cx.simulate_keystrokes(["]", "d"]);
+        cx.assert_state("one\ntwo\nthree\nfˇour", Mode::Normal);
+        // it wraps around the end of the file
+        cx.simulate_keystrokes(["]", "d"]);
+        cx.assert_state("oˇne\ntwo\nthree\nfour", Mode::Normal);
+        cx.simulate_keystrokes(["[", "d"]);
+        cx.assert_state("one\ntwo\nthree\nfˇour", Mode::Normal);
+
+        // unless it's set not to
+        cx.update_global(|store: &mut SettingsStore, cx| {
+            store.update_user_settings::<VimSettings>(cx, |settings| {
+                settings.bracket_wrap = Some(BracketWrap {
+                    in_file: false,
+                    lists: false,
+                })
+            });
+        });
+        cx.simulate_keystrokes(["]", "d"]);
+        cx.assert_state("one\ntwo\nthree\nfˇour", Mode::Normal);
+        assert_eq!(
+            cx.message().as_deref(),
+            Some("E385: Search hit BOTTOM without match")
+        );
+        cx.simulate_keystrokes(["9", "[", "d"]);
+        cx.assert_state("oˇne\ntwo\nthree\nfour", Mode::Normal);
+    }
+
+    #[gpui::test]
+    async fn test_next_method(cx: &mut gpui::TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+
+        // an outline that lists types and impls too, which ]m skips
+        cx.update_editor(|editor, cx| {
+            let buffer = editor.buffer().read(cx).as_singleton().unwrap();
+            let grammar = buffer.read(cx).language().unwrap().grammar().unwrap();
+            let language = Language::new(
+                LanguageConfig {
+                    name: "Rust".into(),
+                    ..Default::default()
+                },
+                Some(grammar.ts_language.clone()),
+            )
+            .with_outline_query(indoc! {r#"
+                (struct_item "struct" @context name: (_) @name) @item
+                (impl_item "impl" @context type: (_) @name) @item
+                (function_item "fn" @context name: (_) @name) @item"#})
+            .unwrap();
+            buffer.update(cx, |buffer, cx| {
+                buffer.set_language(Some(Arc::new(language)), cx)
+            });
+        });
+
+        cx.set_state(
+            indoc! {"
+                ˇstruct A;
+                impl A {
+                    fn a() {}
+                }
+                fn b() {}"},
+            Mode::Normal,
+        );
+        cx.run_until_parked();
+        cx.simulate_keystrokes(["]", "m"]);
+        cx.assert_state(
+            indoc! {"
+                struct A;
+                impl A {
+                    ˇfn a() {}
+                }
+                fn b() {}"},
+            Mode::Normal,
+        );
+        cx.simulate_keystrokes(["]", "m"]);
+        cx.assert_state(
+            indoc! {"
+                struct A;
+                impl A {
+                    fn a() {}
+                }
+                ˇfn b() {}"},
+            Mode::Normal,
+        );
+        cx.simulate_keystrokes(["[", "m"]);
+        cx.assert_state(
+            indoc! {"
+                struct A;
+                impl A {
+                    ˇfn a() {}
+                }
+                fn b() {}"},
+            Mode::Normal,
+        );
+    }
+}
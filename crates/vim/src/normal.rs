@@ -1,34 +1,60 @@
+mod auto_indent;
 mod case;
 mod change;
-mod delete;
+pub(crate) mod character_info;
+pub(crate) mod counts;
+pub(crate) mod custom_operator;
+pub(crate) mod delete;
+mod escape;
+pub(crate) mod fold;
+mod go_to_file;
 mod increment;
-mod paste;
+mod join;
+mod keyword_lookup;
+pub(crate) mod mark;
+pub(crate) mod paste;
+mod quit;
+pub(crate) mod registers;
 pub(crate) mod repeat;
+pub(crate) mod rewrap;
 mod scroll;
 pub(crate) mod search;
+mod shift;
 pub mod substitute;
 mod yank;
 
 use std::sync::Arc;
 
 use crate::{
+    filter::{filter_motion, filter_object},
+    formatoptions::open_line_leader,
     motion::{self, first_non_whitespace, next_line_end, right, Motion},
     object::Object,
     state::{Mode, Operator},
-    Vim,
+    utils::KeywordChars,
+    NormalModeTab, Vim, VimSettings,
 };
 use collections::HashSet;
 use editor::scroll::Autoscroll;
 use editor::{Bias, DisplayPoint};
-use gpui::{actions, ViewContext, WindowContext};
+use gpui::{actions, Action, ViewContext, WindowContext};
 use language::SelectionGoal;
 use log::error;
-use workspace::Workspace;
+use settings::Settings;
+use workspace::{pane, Workspace};
 
 use self::{
-    case::{change_case, convert_to_lower_case, convert_to_upper_case},
+    auto_indent::{auto_indent_motion, auto_indent_object},
+    case::{
+        change_case, change_case_motion, change_case_object, convert_to_lower_case,
+        convert_to_upper_case, CaseTarget,
+    },
     change::{change_motion, change_object},
+    custom_operator::{custom_operator_motion, custom_operator_object},
     delete::{delete_motion, delete_object},
+    fold::{fold_motion, fold_object, open_folds_at_cursors},
+    rewrap::{rewrap_motion, rewrap_object},
+    shift::shift_lines,
     yank::{yank_motion, yank_object},
 };
 
@@ -51,8 +77,11 @@ actions!(
         ConvertToUpperCase,
         ConvertToLowerCase,
         JoinLines,
+        JoinLinesNoWhitespace,
         Indent,
         Outdent,
+        NextSnippetTabstop,
+        PreviousSnippetTabstop,
     ]
 );
 
@@ -110,35 +139,14 @@ pub(crate) fn register(workspace: &mut Workspace, cx: &mut ViewContext<Workspace
             );
         })
     });
-    workspace.register_action(|_: &mut Workspace, _: &JoinLines, cx| {
-        Vim::update(cx, |vim, cx| {
-            vim.record_current_action(cx);
-            let mut times = vim.take_count(cx).unwrap_or(1);
-            if vim.state().mode.is_visual() {
-                times = 1;
-            } else if times > 1 {
-                // 2J joins two lines together (same as J or 1J)
-                times -= 1;
-            }
-
-            vim.update_active_editor(cx, |_, editor, cx| {
-                editor.transact(cx, |editor, cx| {
-                    for _ in 0..times {
-                        editor.join_lines(&Default::default(), cx)
-                    }
-                })
-            });
-            if vim.state().mode.is_visual() {
-                vim.switch_mode(Mode::Normal, false, cx)
-            }
-        });
-    });
-
     workspace.register_action(|_: &mut Workspace, _: &Indent, cx| {
         Vim::update(cx, |vim, cx| {
             vim.record_current_action(cx);
+            let skip_empty = vim.state().mode != Mode::Insert;
             vim.update_active_editor(cx, |_, editor, cx| {
-                editor.transact(cx, |editor, cx| editor.indent(&Default::default(), cx))
+                if !shift_lines(editor, true, skip_empty, cx) {
+                    editor.transact(cx, |editor, cx| editor.indent(&Default::default(), cx))
+                }
             });
             if vim.state().mode.is_visual() {
                 vim.switch_mode(Mode::Normal, false, cx)
@@ -150,7 +158,9 @@ pub(crate) fn register(workspace: &mut Workspace, cx: &mut ViewContext<Workspace
         Vim::update(cx, |vim, cx| {
             vim.record_current_action(cx);
             vim.update_active_editor(cx, |_, editor, cx| {
-                editor.transact(cx, |editor, cx| editor.outdent(&Default::default(), cx))
+                if !shift_lines(editor, false, true, cx) {
+                    editor.transact(cx, |editor, cx| editor.outdent(&Default::default(), cx))
+                }
             });
             if vim.state().mode.is_visual() {
                 vim.switch_mode(Mode::Normal, false, cx)
@@ -158,12 +168,67 @@ pub(crate) fn register(workspace: &mut Workspace, cx: &mut ViewContext<Workspace
         });
     });
 
+    workspace.register_action(|_: &mut Workspace, _: &NextSnippetTabstop, cx| {
+        move_to_snippet_tabstop(Bias::Right, cx)
+    });
+    workspace.register_action(|_: &mut Workspace, _: &PreviousSnippetTabstop, cx| {
+        move_to_snippet_tabstop(Bias::Left, cx)
+    });
+
     paste::register(workspace, cx);
+    character_info::register(workspace, cx);
+    counts::register(workspace, cx);
+    custom_operator::register(workspace, cx);
+    fold::register(workspace, cx);
+    escape::register(workspace, cx);
+    auto_indent::register(workspace, cx);
+    go_to_file::register(workspace, cx);
+    mark::register(workspace, cx);
+    quit::register(workspace, cx);
+    registers::register(workspace, cx);
     repeat::register(workspace, cx);
+    rewrap::register(workspace, cx);
     scroll::register(workspace, cx);
     search::register(workspace, cx);
     substitute::register(workspace, cx);
     increment::register(workspace, cx);
+    join::register(workspace, cx);
+    keyword_lookup::register(workspace, cx);
+}
+
+/// Selects the next (or previous) tab stop of the active snippet, in select mode
+/// (or in insert mode if it has no placeholder).
+///
+/// Tab and shift-tab go through the tab stops of a snippet in normal mode. Outside
+/// of one, tab does what the `normal_mode_tab` setting asks, as many times as the
+/// count.
+fn move_to_snippet_tabstop(bias: Bias, cx: &mut WindowContext) {
+    Vim::update(cx, |vim, cx| {
+        let times = vim.take_count(cx).unwrap_or(1);
+        let in_snippet = vim
+            .update_active_editor(cx, |_, editor, _| editor.has_active_snippet())
+            .unwrap_or(false);
+        if !in_snippet {
+            let action = match (VimSettings::get_global(cx).normal_mode_tab, bias) {
+                (NormalModeTab::JumpForward, Bias::Right) => pane::GoForward.boxed_clone(),
+                (NormalModeTab::JumpForward, Bias::Left) => return,
+                (NormalModeTab::Indent, Bias::Right) => Indent.boxed_clone(),
+                (NormalModeTab::Indent, Bias::Left) => Outdent.boxed_clone(),
+            };
+            for _ in 0..times {
+                cx.dispatch_action(action.boxed_clone());
+            }
+            return;
+        }
+        vim.switch_mode(Mode::Select, true, cx);
+        vim.update_active_editor(cx, |_, editor, cx| {
+            for _ in 0..times {
+                if !editor.move_to_snippet_tabstop(bias, cx) {
+                    break;
+                }
+            }
+        });
+    });
 }
 
 pub fn normal_motion(
@@ -174,10 +239,28 @@ pub fn normal_motion(
 ) {
     Vim::update(cx, |vim, cx| {
         match operator {
-            None => move_cursor(vim, motion, times, cx),
+            None => {
+                let fold_open = motion.fold_open();
+                move_cursor(vim, motion, times, cx);
+                open_folds_at_cursors(vim, fold_open, cx);
+            }
             Some(Operator::Change) => change_motion(vim, motion, times, cx),
             Some(Operator::Delete) => delete_motion(vim, motion, times, cx),
             Some(Operator::Yank) => yank_motion(vim, motion, times, cx),
+            Some(Operator::Fold) => fold_motion(vim, motion, times, cx),
+            Some(Operator::Rewrap) => rewrap_motion(vim, motion, times, cx),
+            Some(Operator::Filter) => filter_motion(vim, motion, times, cx),
+            Some(Operator::AutoIndent) => auto_indent_motion(vim, motion, times, cx),
+            Some(Operator::Uppercase) => {
+                change_case_motion(vim, motion, times, CaseTarget::Upper, cx)
+            }
+            Some(Operator::Lowercase) => {
+                change_case_motion(vim, motion, times, CaseTarget::Lower, cx)
+            }
+            Some(Operator::OppositeCase) => {
+                change_case_motion(vim, motion, times, CaseTarget::Opposite, cx)
+            }
+            Some(Operator::Custom) => custom_operator_motion(vim, motion, times, cx),
             Some(operator) => {
                 // Can't do anything for text objects, Ignoring
                 error!("Unexpected normal mode motion operator: {:?}", operator)
@@ -193,6 +276,20 @@ pub fn normal_object(object: Object, cx: &mut WindowContext) {
                 Some(Operator::Change) => change_object(vim, object, around, cx),
                 Some(Operator::Delete) => delete_object(vim, object, around, cx),
                 Some(Operator::Yank) => yank_object(vim, object, around, cx),
+                Some(Operator::Fold) => fold_object(vim, object, around, cx),
+                Some(Operator::Rewrap) => rewrap_object(vim, object, around, cx),
+                Some(Operator::Filter) => filter_object(vim, object, around, cx),
+                Some(Operator::AutoIndent) => auto_indent_object(vim, object, around, cx),
+                Some(Operator::Uppercase) => {
+                    change_case_object(vim, object, around, CaseTarget::Upper, cx)
+                }
+                Some(Operator::Lowercase) => {
+                    change_case_object(vim, object, around, CaseTarget::Lower, cx)
+                }
+                Some(Operator::OppositeCase) => {
+                    change_case_object(vim, object, around, CaseTarget::Opposite, cx)
+                }
+                Some(Operator::Custom) => custom_operator_object(vim, object, around, cx),
                 _ => {
                     // Can't do anything for namespace operators. Ignoring
                 }
@@ -211,16 +308,31 @@ pub(crate) fn move_cursor(
     times: Option<usize>,
     cx: &mut WindowContext,
 ) {
+    let mut stuck = false;
     vim.update_active_editor(cx, |_, editor, cx| {
         let text_layout_details = editor.text_layout_details(cx);
+        let keyword_chars = KeywordChars::new(cx);
         editor.change_selections(Some(Autoscroll::fit()), cx, |s| {
             s.move_cursors_with(|map, cursor, goal| {
                 motion
-                    .move_point(map, cursor, goal, times, &text_layout_details)
-                    .unwrap_or((cursor, goal))
+                    .move_point(
+                        map,
+                        cursor,
+                        goal,
+                        times,
+                        &text_layout_details,
+                        &keyword_chars,
+                    )
+                    .unwrap_or_else(|| {
+                        stuck = true;
+                        (cursor, goal)
+                    })
             })
         })
     });
+    if stuck && motion.fails_when_stuck() {
+        vim.abort_replay();
+    }
 }
 
 fn insert_after(_: &mut Workspace, _: &InsertAfter, cx: &mut ViewContext<Workspace>) {
@@ -288,15 +400,24 @@ fn insert_line_above(_: &mut Workspace, _: &InsertLineAbove, cx: &mut ViewContex
                     .into_iter()
                     .map(|selection| selection.start.row())
                     .collect();
-                let edits = selection_start_rows.into_iter().map(|row| {
-                    let (indent, _) = map.line_indent(row);
-                    let start_of_line =
-                        motion::start_of_line(&map, false, DisplayPoint::new(row, 0))
-                            .to_point(&map);
-                    let mut new_text = " ".repeat(indent as usize);
-                    new_text.push('\n');
-                    (start_of_line..start_of_line, new_text)
-                });
+                let edits = selection_start_rows
+                    .into_iter()
+                    .map(|row| {
+                        let (indent, _) = map.line_indent(row);
+                        let start_of_line =
+                            motion::start_of_line(&map, false, DisplayPoint::new(row, 0))
+                                .to_point(&map);
+                        let mut new_text = " ".repeat(indent as usize);
+                        new_text.push_str(&open_line_leader(
+                            &map.buffer_snapshot,
+                            start_of_line.row,
+                            true,
+                            cx,
+                        ));
+                        new_text.push('\n');
+                        (start_of_line..start_of_line, new_text)
+                    })
+                    .collect::<Vec<_>>();
                 editor.edit_with_autoindent(edits, cx);
                 editor.change_selections(Some(Autoscroll::fit()), cx, |s| {
                     s.move_cursors_with(|map, cursor, _| {
@@ -323,24 +444,37 @@ fn insert_line_below(_: &mut Workspace, _: &InsertLineBelow, cx: &mut ViewContex
                     .into_iter()
                     .map(|selection| selection.end.row())
                     .collect();
-                let edits = selection_end_rows.into_iter().map(|row| {
-                    let (indent, _) = map.line_indent(row);
-                    let end_of_line =
-                        motion::end_of_line(&map, false, DisplayPoint::new(row, 0), 1)
-                            .to_point(&map);
-
-                    let mut new_text = "\n".to_string();
-                    new_text.push_str(&" ".repeat(indent as usize));
-                    (end_of_line..end_of_line, new_text)
-                });
+                let edits = selection_end_rows
+                    .into_iter()
+                    .map(|row| {
+                        let (indent, _) = map.line_indent(row);
+                        let end_of_line =
+                            motion::end_of_line(&map, false, DisplayPoint::new(row, 0), 1)
+                                .to_point(&map);
+
+                        let mut new_text = "\n".to_string();
+                        new_text.push_str(&" ".repeat(indent as usize));
+                        new_text.push_str(&open_line_leader(
+                            &map.buffer_snapshot,
+                            end_of_line.row,
+                            false,
+                            cx,
+                        ));
+                        (end_of_line..end_of_line, new_text)
+                    })
+                    .collect::<Vec<_>>();
                 editor.change_selections(Some(Autoscroll::fit()), cx, |s| {
                     s.maybe_move_cursors_with(|map, cursor, goal| {
-                        Motion::CurrentLine.move_point(
+                        Motion::CurrentLine {
+                            display_lines: false,
+                        }
+                        .move_point(
                             map,
                             cursor,
                             goal,
                             None,
                             &text_layout_details,
+                            &KeywordChars::default(),
                         )
                     });
                 });
@@ -353,7 +487,14 @@ fn insert_line_below(_: &mut Workspace, _: &InsertLineBelow, cx: &mut ViewContex
 fn yank_line(_: &mut Workspace, _: &YankLine, cx: &mut ViewContext<Workspace>) {
     Vim::update(cx, |vim, cx| {
         let count = vim.take_count(cx);
-        yank_motion(vim, motion::Motion::CurrentLine, count, cx)
+        yank_motion(
+            vim,
+            motion::Motion::CurrentLine {
+                display_lines: false,
+            },
+            count,
+            cx,
+        )
     })
 }
 
@@ -392,9 +533,7 @@ pub(crate) fn normal_replace(text: Arc<str>, cx: &mut WindowContext) {
                     })
                     .collect::<Vec<_>>();
 
-                editor.buffer().update(cx, |buffer, cx| {
-                    buffer.edit(edits, None, cx);
-                });
+                editor.edit(edits, cx);
                 editor.set_clip_at_line_ends(true, cx);
                 editor.change_selections(None, cx, |s| {
                     s.select_anchor_ranges(stable_anchors);
@@ -415,9 +554,47 @@ mod test {
         motion,
         state::Mode::{self},
         test::{NeovimBackedTestContext, VimTestContext},
-        VimSettings,
+        NormalModeTab, VimSettings,
     };
 
+    #[gpui::test]
+    async fn test_normal_mode_tab(cx: &mut gpui::TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+
+        // like ctrl-i, tab goes forward through the jump list
+        cx.set_state(&format!("ˇone{}\nlast", "\n".repeat(20)), Mode::Normal);
+        cx.simulate_keystrokes(["shift-g", "ctrl-o"]);
+        cx.run_until_parked();
+        cx.assert_state(&format!("ˇone{}\nlast", "\n".repeat(20)), Mode::Normal);
+        cx.simulate_keystrokes(["tab"]);
+        cx.run_until_parked();
+        cx.assert_state(&format!("one{}\nˇlast", "\n".repeat(20)), Mode::Normal);
+
+        // or it indents, for those who'd rather
+        cx.update_global(|store: &mut SettingsStore, cx| {
+            store.update_user_settings::<VimSettings>(cx, |s| {
+                s.normal_mode_tab = Some(NormalModeTab::Indent)
+            });
+        });
+        cx.set_state("ˇone", Mode::Normal);
+        cx.simulate_keystrokes(["tab"]);
+        cx.run_until_parked();
+        cx.assert_state("    ˇone", Mode::Normal);
+        cx.simulate_keystrokes(["shift-tab"]);
+        cx.run_until_parked();
+        cx.assert_state("ˇone", Mode::Normal);
+        cx.simulate_keystrokes(["2", "tab"]);
+        cx.run_until_parked();
+        cx.assert_state("        ˇone", Mode::Normal);
+        cx.simulate_keystrokes(["2", "shift-tab"]);
+        cx.run_until_parked();
+        cx.assert_state("ˇone", Mode::Normal);
+
+        // while insert mode keeps its tab
+        cx.simulate_keystrokes(["i", "tab", "escape"]);
+        cx.assert_state("   ˇ one", Mode::Normal);
+    }
+
     #[gpui::test]
     async fn test_h(cx: &mut gpui::TestAppContext) {
         let mut cx = NeovimBackedTestContext::new(cx).await.binding(["h"]);
@@ -0,0 +1,152 @@
+use editor::{display_map::ToDisplayPoint, Editor};
+use gpui::{actions, ViewContext, WindowContext};
+use language::SelectionGoal;
+use workspace::Workspace;
+
+use crate::{
+    motion::{first_non_whitespace, Motion},
+    object::Object,
+    state::Mode,
+    utils::KeywordChars,
+    Vim,
+};
+
+actions!(vim, [VisualAutoIndent]);
+
+pub(crate) fn register(workspace: &mut Workspace, _: &mut ViewContext<Workspace>) {
+    workspace.register_action(|_: &mut Workspace, _: &VisualAutoIndent, cx| {
+        Vim::update(cx, |vim, cx| {
+            vim.record_current_action(cx);
+            vim.update_active_editor(cx, |_, editor, cx| {
+                editor.transact(cx, |editor, cx| auto_indent_selections(editor, cx))
+            });
+            vim.switch_mode(Mode::Normal, true, cx);
+        })
+    });
+}
+
+/// `={motion}`
+pub fn auto_indent_motion(
+    vim: &mut Vim,
+    motion: Motion,
+    times: Option<usize>,
+    cx: &mut WindowContext,
+) {
+    vim.stop_recording();
+    vim.update_active_editor(cx, |_, editor, cx| {
+        let text_layout_details = editor.text_layout_details(cx);
+        let keyword_chars = KeywordChars::new(cx);
+        editor.transact(cx, |editor, cx| {
+            editor.change_selections(None, cx, |s| {
+                s.move_with(|map, selection| {
+                    motion.expand_selection(
+                        map,
+                        selection,
+                        times,
+                        true,
+                        &text_layout_details,
+                        &keyword_chars,
+                    );
+                });
+            });
+            auto_indent_selections(editor, cx);
+        });
+    });
+}
+
+/// `=ip` and friends
+pub fn auto_indent_object(vim: &mut Vim, object: Object, around: bool, cx: &mut WindowContext) {
+    vim.stop_recording();
+    vim.update_active_editor(cx, |_, editor, cx| {
+        let keyword_chars = KeywordChars::new(cx);
+        editor.transact(cx, |editor, cx| {
+            editor.change_selections(None, cx, |s| {
+                s.move_with(|map, selection| {
+                    object.expand_selection(map, selection, around, &keyword_chars);
+                });
+            });
+            auto_indent_selections(editor, cx);
+        });
+    });
+}
+
+/// Reindents the lines of each selection (each line with the indentation of the
+/// language it's in, so scripts in HTML are indented as JavaScript), and leaves the
+/// cursor on the first non-blank of the first line, like vim's `=`.
+fn auto_indent_selections(editor: &mut Editor, cx: &mut ViewContext<Editor>) {
+    editor.autoindent(&Default::default(), cx);
+    editor.change_selections(None, cx, |s| {
+        s.move_with(|map, selection| {
+            let start = selection.start.to_point(map);
+            let cursor = first_non_whitespace(
+                map,
+                false,
+                language::Point::new(start.row, 0).to_display_point(map),
+            );
+            selection.collapse_to(cursor, SelectionGoal::None);
+        });
+    });
+}
+
+#[cfg(test)]
+mod test {
+    use indoc::indoc;
+
+    use crate::{state::Mode, test::VimTestContext};
+
+    #[gpui::test]
+    async fn test_auto_indent(cx: &mut gpui::TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+
+        cx.set_state(
+            indoc! {"
+                fn a() {
+                ˇb();
+                        if c {
+                d();
+
+                  }
+                }"},
+            Mode::Normal,
+        );
+        cx.simulate_keystrokes(["=", "="]);
+        cx.assert_state(
+            indoc! {"
+                fn a() {
+                    ˇb();
+                        if c {
+                d();
+
+                  }
+                }"},
+            Mode::Normal,
+        );
+
+        cx.simulate_keystrokes(["k", "=", "i", "p"]);
+        cx.assert_state(
+            indoc! {"
+                ˇfn a() {
+                    b();
+                    if c {
+                        d();
+
+                  }
+                }"},
+            Mode::Normal,
+        );
+
+        // blank lines are left blank
+        cx.simulate_keystrokes(["3", "j", "shift-v", "j", "j", "="]);
+        cx.assert_state(
+            indoc! {"
+                fn a() {
+                    b();
+                    if c {
+                        ˇd();
+
+                    }
+                }"},
+            Mode::Normal,
+        );
+    }
+}
@@ -1,20 +1,49 @@
 use editor::scroll::Autoscroll;
-use gpui::ViewContext;
+use gpui::{ViewContext, WindowContext};
 use language::{Bias, Point};
+use settings::Settings;
 use workspace::Workspace;
 
 use crate::{
-    normal::ChangeCase, normal::ConvertToLowerCase, normal::ConvertToUpperCase, state::Mode, Vim,
+    motion::Motion, normal::ChangeCase, normal::ConvertToLowerCase, normal::ConvertToUpperCase,
+    object::Object, state::Mode, utils::KeywordChars, CaseLocale, Vim, VimSettings,
 };
 
-pub fn change_case(_: &mut Workspace, _: &ChangeCase, cx: &mut ViewContext<Workspace>) {
-    manipulate_text(cx, |c| {
-        if c.is_lowercase() {
-            c.to_uppercase().collect::<Vec<char>>()
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CaseTarget {
+    Upper,
+    Lower,
+    Opposite,
+}
+
+impl CaseTarget {
+    /// Uses the full Unicode case mapping, so one character may become several (ß
+    /// becomes SS). With the Turkish locale, i and ı are the lowercase forms of İ and I.
+    fn convert(self, c: char, locale: CaseLocale) -> Vec<char> {
+        let upper = match self {
+            CaseTarget::Upper => true,
+            CaseTarget::Lower => false,
+            CaseTarget::Opposite => c.is_lowercase(),
+        };
+        if locale == CaseLocale::Turkish {
+            match (c, upper) {
+                ('i', true) => return vec!['İ'],
+                ('I', false) => return vec!['ı'],
+                ('İ', false) => return vec!['i'],
+                _ => {}
+            }
+        }
+        if upper {
+            c.to_uppercase().collect()
         } else {
-            c.to_lowercase().collect::<Vec<char>>()
+            c.to_lowercase().collect()
         }
-    })
+    }
+}
+
+pub fn change_case(_: &mut Workspace, _: &ChangeCase, cx: &mut ViewContext<Workspace>) {
+    let locale = VimSettings::get_global(cx).case_locale;
+    manipulate_text(cx, move |c| CaseTarget::Opposite.convert(c, locale))
 }
 
 pub fn convert_to_upper_case(
@@ -22,7 +51,8 @@ pub fn convert_to_upper_case(
     _: &ConvertToUpperCase,
     cx: &mut ViewContext<Workspace>,
 ) {
-    manipulate_text(cx, |c| c.to_uppercase().collect::<Vec<char>>())
+    let locale = VimSettings::get_global(cx).case_locale;
+    manipulate_text(cx, move |c| CaseTarget::Upper.convert(c, locale))
 }
 
 pub fn convert_to_lower_case(
@@ -30,7 +60,98 @@ pub fn convert_to_lower_case(
     _: &ConvertToLowerCase,
     cx: &mut ViewContext<Workspace>,
 ) {
-    manipulate_text(cx, |c| c.to_lowercase().collect::<Vec<char>>())
+    let locale = VimSettings::get_global(cx).case_locale;
+    manipulate_text(cx, move |c| CaseTarget::Lower.convert(c, locale))
+}
+
+/// `gU{motion}`, `gu{motion}` and `g~{motion}`
+pub fn change_case_motion(
+    vim: &mut Vim,
+    motion: Motion,
+    times: Option<usize>,
+    target: CaseTarget,
+    cx: &mut WindowContext,
+) {
+    vim.stop_recording();
+    let locale = VimSettings::get_global(cx).case_locale;
+    vim.update_active_editor(cx, |_, editor, cx| {
+        let text_layout_details = editor.text_layout_details(cx);
+        let keyword_chars = KeywordChars::new(cx);
+        editor.transact(cx, |editor, cx| {
+            editor.set_clip_at_line_ends(false, cx);
+            editor.change_selections(None, cx, |s| {
+                s.move_with(|map, selection| {
+                    motion.expand_selection(
+                        map,
+                        selection,
+                        times,
+                        true,
+                        &text_layout_details,
+                        &keyword_chars,
+                    );
+                });
+            });
+            convert_selections(editor, target, locale, cx);
+        });
+    });
+}
+
+/// `gUiw` and friends
+pub fn change_case_object(
+    vim: &mut Vim,
+    object: Object,
+    around: bool,
+    target: CaseTarget,
+    cx: &mut WindowContext,
+) {
+    vim.stop_recording();
+    let locale = VimSettings::get_global(cx).case_locale;
+    vim.update_active_editor(cx, |_, editor, cx| {
+        let keyword_chars = KeywordChars::new(cx);
+        editor.transact(cx, |editor, cx| {
+            editor.set_clip_at_line_ends(false, cx);
+            editor.change_selections(None, cx, |s| {
+                s.move_with(|map, selection| {
+                    object.expand_selection(map, selection, around, &keyword_chars);
+                });
+            });
+            convert_selections(editor, target, locale, cx);
+        });
+    });
+}
+
+/// Converts the text of each selection, and leaves the cursor at its start.
+fn convert_selections(
+    editor: &mut editor::Editor,
+    target: CaseTarget,
+    locale: CaseLocale,
+    cx: &mut ViewContext<editor::Editor>,
+) {
+    let snapshot = editor.buffer().read(cx).snapshot(cx);
+    let selections = editor.selections.all::<Point>(cx);
+    let edits = selections
+        .iter()
+        .map(|selection| {
+            let text = snapshot
+                .text_for_range(selection.start..selection.end)
+                .flat_map(|s| s.chars())
+                .flat_map(|c| target.convert(c, locale))
+                .collect::<String>();
+            (selection.start..selection.end, text)
+        })
+        .collect::<Vec<_>>();
+    let starts = selections
+        .iter()
+        .map(|selection| {
+            let start = snapshot.anchor_before(selection.start);
+            start..start
+        })
+        .collect::<Vec<_>>();
+    editor.edit(edits, cx);
+    editor.set_clip_at_line_ends(true, cx);
+    editor.change_selections(Some(Autoscroll::fit()), cx, |s| {
+        s.select_anchor_ranges(starts)
+    });
 }
 
 fn manipulate_text<F>(cx: &mut ViewContext<Workspace>, transform: F)
@@ -40,6 +161,7 @@ where
     Vim::update(cx, |vim, cx| {
         vim.record_current_action(cx);
         let count = vim.take_count(cx).unwrap_or(1) as u32;
+        let wrap = VimSettings::get_global(cx).whichwrap.contains('~');
         vim.update_active_editor(cx, |vim, editor, cx| {
             let mut ranges = Vec::new();
             let mut cursor_positions = Vec::new();
@@ -63,16 +185,27 @@ where
                             cursor_positions.push(selection.start..selection.start);
                         }
                     }
-                    Mode::Insert | Mode::Normal => {
-                        let start = selection.start;
+                    Mode::Insert | Mode::Normal | Mode::Select => {
+                        let mut start = selection.start;
                         let mut end = start;
                         for _ in 0..count {
+                            // with whichwrap's '~', the count goes on at the next line
+                            let at_line_end = end.column == snapshot.line_len(end.row);
+                            if wrap && at_line_end && end.row < snapshot.max_point().row {
+                                ranges.push(start..end);
+                                start = Point::new(end.row + 1, 0);
+                                end = start;
+                            }
                             end = snapshot.clip_point(end + Point::new(0, 1), Bias::Right);
                         }
                         ranges.push(start..end);
 
                         if end.column == snapshot.line_len(end.row) {
-                            end = snapshot.clip_point(end - Point::new(0, 1), Bias::Left);
+                            end = if wrap && end.row < snapshot.max_point().row {
+                                Point::new(end.row + 1, 0)
+                            } else {
+                                snapshot.clip_point(end - Point::new(0, 1), Bias::Left)
+                            };
                         }
                         cursor_positions.push(end..end)
                     }
@@ -81,15 +214,12 @@ where
             editor.transact(cx, |editor, cx| {
                 for range in ranges.into_iter().rev() {
                     let snapshot = editor.buffer().read(cx).snapshot(cx);
-                    editor.buffer().update(cx, |buffer, cx| {
-                        let text = snapshot
-                            .text_for_range(range.start..range.end)
-                            .flat_map(|s| s.chars())
-                            .flat_map(|c| transform(c))
-                            .collect::<String>();
-
-                        buffer.edit([(range, text)], None, cx)
-                    })
+                    let text = snapshot
+                        .text_for_range(range.start..range.end)
+                        .flat_map(|s| s.chars())
+                        .flat_map(|c| transform(c))
+                        .collect::<String>();
+                    editor.edit([(range, text)], cx)
                 }
                 editor.change_selections(Some(Autoscroll::fit()), cx, |s| {
                     s.select_ranges(cursor_positions)
@@ -102,7 +232,13 @@ where
 
 #[cfg(test)]
 mod test {
-    use crate::{state::Mode, test::NeovimBackedTestContext};
+    use settings::SettingsStore;
+
+    use crate::{
+        state::Mode,
+        test::{NeovimBackedTestContext, VimTestContext},
+        CaseLocale, VimSettings,
+    };
 
     #[gpui::test]
     async fn test_change_case(cx: &mut gpui::TestAppContext) {
@@ -177,4 +313,60 @@ mod test {
         cx.simulate_shared_keystrokes(["ctrl-v", "j", "u"]).await;
         cx.assert_shared_state("ˇaa\nbb\nCc").await;
     }
+
+    #[gpui::test]
+    async fn test_change_case_operators(cx: &mut gpui::TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+
+        cx.set_state("ˇstraße café\nnext", Mode::Normal);
+        cx.simulate_keystrokes(["g", "shift-u", "i", "w"]);
+        cx.assert_state("ˇSTRASSE café\nnext", Mode::Normal);
+        cx.simulate_keystrokes(["w", "g", "~", "~"]);
+        cx.assert_state("ˇstrasse CAFÉ\nnext", Mode::Normal);
+        cx.simulate_keystrokes(["g", "u", "j"]);
+        cx.assert_state("ˇstrasse café\nnext", Mode::Normal);
+        cx.simulate_keystrokes(["g", "shift-u", "shift-u"]);
+        cx.assert_state("ˇSTRASSE CAFÉ\nnext", Mode::Normal);
+    }
+
+    #[gpui::test]
+    async fn test_turkish_case(cx: &mut gpui::TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+
+        cx.set_state("ˇiı İI", Mode::Normal);
+        cx.simulate_keystrokes(["g", "shift-u", "w"]);
+        cx.assert_state("ˇII İI", Mode::Normal);
+
+        cx.update_global(|store: &mut SettingsStore, cx| {
+            store.update_user_settings::<VimSettings>(cx, |s| {
+                s.case_locale = Some(CaseLocale::Turkish)
+            });
+        });
+        cx.set_state("ˇiı İI", Mode::Normal);
+        cx.simulate_keystrokes(["g", "shift-u", "w"]);
+        cx.assert_state("ˇİI İI", Mode::Normal);
+        cx.simulate_keystrokes(["g", "u", "u"]);
+        cx.assert_state("ˇiı iı", Mode::Normal);
+        cx.simulate_keystrokes(["~", "~"]);
+        cx.assert_state("İIˇ iı", Mode::Normal);
+    }
+
+    #[gpui::test]
+    async fn test_change_case_whichwrap(cx: &mut gpui::TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+
+        cx.set_state("aˇb\ncd", Mode::Normal);
+        cx.simulate_keystrokes(["3", "~"]);
+        cx.assert_state("aˇB\ncd", Mode::Normal);
+
+        cx.update_global(|store: &mut SettingsStore, cx| {
+            store.update_user_settings::<VimSettings>(cx, |s| s.whichwrap = Some("~".into()));
+        });
+        cx.set_state("aˇb\ncd", Mode::Normal);
+        cx.simulate_keystrokes(["~"]);
+        cx.assert_state("aB\nˇcd", Mode::Normal);
+        cx.set_state("aˇb\ncd", Mode::Normal);
+        cx.simulate_keystrokes(["3", "~"]);
+        cx.assert_state("aB\nCˇD", Mode::Normal);
+    }
 }
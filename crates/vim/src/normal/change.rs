@@ -1,15 +1,15 @@
 use crate::{
-    motion::{self, Motion},
+    motion::Motion,
     object::Object,
     state::Mode,
-    utils::copy_selections_content,
+    utils::{char_kind, coerce_punctuation, copy_selections_content, KeywordChars},
     Vim,
 };
 use editor::{
     display_map::DisplaySnapshot, movement::TextLayoutDetails, scroll::Autoscroll, DisplayPoint,
 };
 use gpui::WindowContext;
-use language::{char_kind, CharKind, Selection};
+use language::{CharKind, Selection};
 
 pub fn change_motion(vim: &mut Vim, motion: Motion, times: Option<usize>, cx: &mut WindowContext) {
     // Some motions ignore failure when switching to normal mode
@@ -23,9 +23,11 @@ pub fn change_motion(vim: &mut Vim, motion: Motion, times: Option<usize>, cx: &m
     );
     vim.update_active_editor(cx, |vim, editor, cx| {
         let text_layout_details = editor.text_layout_details(cx);
+        let keyword_chars = KeywordChars::new(cx);
         editor.transact(cx, |editor, cx| {
             // We are swapping to insert mode anyway. Just set the line end clipping behavior now
             editor.set_clip_at_line_ends(false, cx);
+            let mut linewise = motion.linewise();
             editor.change_selections(Some(Autoscroll::fit()), cx, |s| {
                 s.move_with(|map, selection| {
                     motion_succeeded |= if let Motion::NextWordStart { ignore_punctuation } = motion
@@ -36,6 +38,7 @@ pub fn change_motion(vim: &mut Vim, motion: Motion, times: Option<usize>, cx: &m
                             times,
                             ignore_punctuation,
                             &text_layout_details,
+                            &keyword_chars,
                             false,
                         )
                     } else if let Motion::NextSubwordStart { ignore_punctuation } = motion {
@@ -45,14 +48,24 @@ pub fn change_motion(vim: &mut Vim, motion: Motion, times: Option<usize>, cx: &m
                             times,
                             ignore_punctuation,
                             &text_layout_details,
+                            &keyword_chars,
                             true,
                         )
                     } else {
-                        motion.expand_selection(map, selection, times, false, &text_layout_details)
+                        let expanded = motion.expand_selection_linewise(
+                            map,
+                            selection,
+                            times,
+                            false,
+                            &text_layout_details,
+                            &keyword_chars,
+                        );
+                        linewise |= expanded.unwrap_or(false);
+                        expanded.is_some()
                     };
                 });
             });
-            copy_selections_content(vim, editor, motion.linewise(), cx);
+            copy_selections_content(vim, editor, linewise, cx);
             editor.insert("", cx);
         });
     });
@@ -67,12 +80,14 @@ pub fn change_motion(vim: &mut Vim, motion: Motion, times: Option<usize>, cx: &m
 pub fn change_object(vim: &mut Vim, object: Object, around: bool, cx: &mut WindowContext) {
     let mut objects_found = false;
     vim.update_active_editor(cx, |vim, editor, cx| {
+        let keyword_chars = KeywordChars::new(cx);
         // We are swapping to insert mode anyway. Just set the line end clipping behavior now
         editor.set_clip_at_line_ends(false, cx);
         editor.transact(cx, |editor, cx| {
             editor.change_selections(Some(Autoscroll::fit()), cx, |s| {
                 s.move_with(|map, selection| {
-                    objects_found |= object.expand_selection(map, selection, around);
+                    objects_found |=
+                        object.expand_selection(map, selection, around, &keyword_chars);
                 });
             });
             if objects_found {
@@ -101,51 +116,95 @@ fn expand_changed_word_selection(
     times: Option<usize>,
     ignore_punctuation: bool,
     text_layout_details: &TextLayoutDetails,
+    keyword_chars: &KeywordChars,
     use_subword: bool,
 ) -> bool {
-    if times.is_none() || times.unwrap() == 1 {
-        let scope = map
-            .buffer_snapshot
-            .language_scope_at(selection.start.to_point(map));
-        let in_word = map
-            .chars_at(selection.head())
-            .next()
-            .map(|(c, _)| char_kind(&scope, c) != CharKind::Whitespace)
-            .unwrap_or_default();
-
-        if in_word {
-            if !use_subword {
-                selection.end =
-                    motion::next_word_end(map, selection.end, ignore_punctuation, 1, false);
-            } else {
-                selection.end =
-                    motion::next_subword_end(map, selection.end, ignore_punctuation, 1, false);
-            }
-            selection.end = motion::next_char(map, selection.end, false);
-            true
-        } else {
-            let motion = if use_subword {
-                Motion::NextSubwordStart { ignore_punctuation }
-            } else {
-                Motion::NextWordStart { ignore_punctuation }
-            };
-            motion.expand_selection(map, selection, None, false, &text_layout_details)
-        }
+    let scope = map
+        .buffer_snapshot
+        .language_scope_at(selection.start.to_point(map));
+    let in_word = map
+        .chars_at(selection.head())
+        .next()
+        .map(|(c, _)| language::char_kind(&scope, c) != CharKind::Whitespace)
+        .unwrap_or_default();
+
+    if in_word {
+        selection.end = changed_words_end(
+            map,
+            selection.head(),
+            times.unwrap_or(1),
+            ignore_punctuation,
+            keyword_chars,
+            use_subword,
+        );
+        true
     } else {
         let motion = if use_subword {
             Motion::NextSubwordStart { ignore_punctuation }
         } else {
             Motion::NextWordStart { ignore_punctuation }
         };
-        motion.expand_selection(map, selection, times, false, &text_layout_details)
+        motion.expand_selection(
+            map,
+            selection,
+            times,
+            false,
+            text_layout_details,
+            keyword_chars,
+        )
     }
 }
 
+/// Where `cw` stops changing: after the last character of the `times`th word,
+/// where the word under the cursor is the first one (even from its last character,
+/// when `e` would go on to the next word), like vim's `end_word` with `stop` set.
+fn changed_words_end(
+    map: &DisplaySnapshot,
+    point: DisplayPoint,
+    times: usize,
+    ignore_punctuation: bool,
+    keyword_chars: &KeywordChars,
+    use_subword: bool,
+) -> DisplayPoint {
+    let scope = map.buffer_snapshot.language_scope_at(point.to_point(map));
+    let kind = |c| coerce_punctuation(char_kind(&scope, c, keyword_chars), ignore_punctuation);
+
+    let mut end = point;
+    let mut words = 0;
+    let mut previous: Option<char> = None;
+    for (c, point) in map.chars_at(point) {
+        if kind(c) == CharKind::Whitespace {
+            if words == times {
+                break;
+            }
+            previous = None;
+            continue;
+        }
+        let word_start = previous.map_or(true, |previous| {
+            kind(previous) != kind(c)
+                || use_subword
+                    && (previous != '_' && c == '_' || previous.is_lowercase() && c.is_uppercase())
+        });
+        if word_start {
+            if words == times {
+                break;
+            }
+            words += 1;
+        }
+        end = DisplayPoint::new(point.row(), point.column() + c.len_utf8() as u32);
+        previous = Some(c);
+    }
+    end
+}
+
 #[cfg(test)]
 mod test {
     use indoc::indoc;
 
-    use crate::test::NeovimBackedTestContext;
+    use crate::{
+        state::Mode,
+        test::{NeovimBackedTestContext, VimTestContext},
+    };
 
     #[gpui::test]
     async fn test_change_h(cx: &mut gpui::TestAppContext) {
@@ -204,6 +263,38 @@ mod test {
         cx.assert("Test teˇst-test test").await;
     }
 
+    #[gpui::test]
+    async fn test_change_w_special_cases(cx: &mut gpui::TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+
+        for (before, keys, after) in [
+            // the word under the cursor is changed, even from its last character
+            ("Tesˇt test", "cw", "Tesˇ test"),
+            ("ˇa b", "cw", "ˇ b"),
+            ("foo.ˇbar baz", "cw", "foo.ˇ baz"),
+            ("ˇfoo.bar baz", "cW", "ˇ baz"),
+            // with a count, the whitespace after the last word is still left alone
+            ("ˇone two three", "c2w", "ˇ three"),
+            ("onˇe two\nthree", "c2w", "onˇ\nthree"),
+            ("one ˇtwo\nthree four", "c2w", "one ˇ four"),
+            ("one ˇt three", "c2w", "one ˇ"),
+            // on a blank, it changes the whitespace like dw
+            ("Testˇ test", "cw", "Testˇtest"),
+            ("ˇ  one two", "c2w", "ˇtwo"),
+        ] {
+            cx.set_state(before, Mode::Normal);
+            for key in keys.chars() {
+                let key = if key.is_ascii_uppercase() {
+                    format!("shift-{}", key.to_ascii_lowercase())
+                } else {
+                    key.to_string()
+                };
+                cx.simulate_keystroke(&key);
+            }
+            cx.assert_state(after, Mode::Insert);
+        }
+    }
+
     #[gpui::test]
     async fn test_change_e(cx: &mut gpui::TestAppContext) {
         let mut cx = NeovimBackedTestContext::new(cx).await.binding(["c", "e"]);
@@ -0,0 +1,260 @@
+use gpui::{actions, ViewContext, WindowContext};
+use workspace::Workspace;
+
+use crate::Vim;
+
+actions!(vim, [ShowCharacterInfo, ShowCharacterBytes]);
+
+pub(crate) fn register(workspace: &mut Workspace, _: &mut ViewContext<Workspace>) {
+    workspace.register_action(|_: &mut Workspace, _: &ShowCharacterInfo, cx| {
+        show_character(cx, character_info)
+    });
+    workspace.register_action(|_: &mut Workspace, _: &ShowCharacterBytes, cx| {
+        show_character(cx, character_bytes)
+    });
+}
+
+fn show_character(cx: &mut WindowContext, describe: fn(&[char]) -> String) {
+    Vim::update(cx, |vim, cx| {
+        let Some(chars) = vim.update_active_editor(cx, |_, editor, cx| {
+            let snapshot = editor.buffer().read(cx).snapshot(cx);
+            let offset = editor.selections.newest::<usize>(cx).head();
+            character_under_cursor(snapshot.chars_at(offset))
+        }) else {
+            return;
+        };
+        vim.clear_operator(cx);
+        vim.show_message(if chars.is_empty() {
+            "NUL".to_string()
+        } else {
+            describe(&chars)
+        });
+    })
+}
+
+/// The character under the cursor, and any combining characters that follow it
+/// (vim treats them as one character).
+fn character_under_cursor(mut chars: impl Iterator<Item = char>) -> Vec<char> {
+    let Some(first) = chars.next().filter(|c| *c != '\n') else {
+        return Vec::new();
+    };
+    let mut result = vec![first];
+    result.extend(chars.take_while(|c| is_combining(*c)));
+    result
+}
+
+fn is_combining(c: char) -> bool {
+    matches!(
+        c as u32,
+        0x0300..=0x036F | 0x1AB0..=0x1AFF | 0x1DC0..=0x1DFF | 0x20D0..=0x20FF | 0xFE20..=0xFE2F
+    )
+}
+
+/// Like vim's `ga`: `<é> 233, Hex 00e9, Oct 351, Digr e'`
+fn character_info(chars: &[char]) -> String {
+    chars
+        .iter()
+        .map(|&c| {
+            let code = c as u32;
+            let shown = if (c as u32) < 0x20 {
+                format!("^{}", char::from_u32(code + 0x40).unwrap_or('?'))
+            } else if c == '\u{7f}' {
+                "^?".to_string()
+            } else if is_combining(c) {
+                format!(" {c}")
+            } else {
+                c.to_string()
+            };
+            let hex = match code {
+                0..=0xff if c.is_ascii() => format!("{code:02x}"),
+                0..=0xffff => format!("{code:04x}"),
+                _ => format!("{code:08x}"),
+            };
+            let mut info = format!("<{shown}> {code}, Hex {hex}, Oct {code:03o}");
+            if let Some(digraph) = digraph(c) {
+                info.push_str(", Digr ");
+                info.push_str(digraph);
+            }
+            info
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Like vim's `g8`: the UTF-8 bytes, with combining characters after a `+`.
+fn character_bytes(chars: &[char]) -> String {
+    chars
+        .iter()
+        .map(|c| {
+            let mut bytes = [0; 4];
+            c.encode_utf8(&mut bytes)
+                .bytes()
+                .map(|byte| format!("{byte:02x}"))
+                .collect::<Vec<_>>()
+                .join(" ")
+        })
+        .collect::<Vec<_>>()
+        .join(" + ")
+}
+
+fn digraph(c: char) -> Option<&'static str> {
+    DIGRAPHS
+        .iter()
+        .find(|(_, digraph_char)| *digraph_char == c)
+        .map(|(digraph, _)| *digraph)
+}
+
+// The RFC1345 digraphs from vim's `:digraphs` that people most often meet.
+const DIGRAPHS: &[(&str, char)] = &[
+    ("NS", '\u{a0}'),
+    ("!I", '¡'),
+    ("Ct", '¢'),
+    ("Pd", '£'),
+    ("Ye", '¥'),
+    ("SE", '§'),
+    ("Co", '©'),
+    ("<<", '«'),
+    ("NO", '¬'),
+    ("--", '\u{ad}'),
+    ("Rg", '®'),
+    ("DG", '°'),
+    ("+-", '±'),
+    ("2S", '²'),
+    ("3S", '³'),
+    ("My", 'µ'),
+    ("PI", '¶'),
+    (".M", '·'),
+    ("1S", '¹'),
+    (">>", '»'),
+    ("14", '¼'),
+    ("12", '½'),
+    ("34", '¾'),
+    ("?I", '¿'),
+    ("A!", 'À'),
+    ("A'", 'Á'),
+    ("A>", 'Â'),
+    ("A?", 'Ã'),
+    ("A:", 'Ä'),
+    ("AA", 'Å'),
+    ("AE", 'Æ'),
+    ("C,", 'Ç'),
+    ("E!", 'È'),
+    ("E'", 'É'),
+    ("E>", 'Ê'),
+    ("E:", 'Ë'),
+    ("I!", 'Ì'),
+    ("I'", 'Í'),
+    ("I>", 'Î'),
+    ("I:", 'Ï'),
+    ("N?", 'Ñ'),
+    ("O!", 'Ò'),
+    ("O'", 'Ó'),
+    ("O>", 'Ô'),
+    ("O?", 'Õ'),
+    ("O:", 'Ö'),
+    ("*X", '×'),
+    ("O/", 'Ø'),
+    ("U!", 'Ù'),
+    ("U'", 'Ú'),
+    ("U>", 'Û'),
+    ("U:", 'Ü'),
+    ("Y'", 'Ý'),
+    ("ss", 'ß'),
+    ("a!", 'à'),
+    ("a'", 'á'),
+    ("a>", 'â'),
+    ("a?", 'ã'),
+    ("a:", 'ä'),
+    ("aa", 'å'),
+    ("ae", 'æ'),
+    ("c,", 'ç'),
+    ("e!", 'è'),
+    ("e'", 'é'),
+    ("e>", 'ê'),
+    ("e:", 'ë'),
+    ("i!", 'ì'),
+    ("i'", 'í'),
+    ("i>", 'î'),
+    ("i:", 'ï'),
+    ("n?", 'ñ'),
+    ("o!", 'ò'),
+    ("o'", 'ó'),
+    ("o>", 'ô'),
+    ("o?", 'õ'),
+    ("o:", 'ö'),
+    ("-:", '÷'),
+    ("o/", 'ø'),
+    ("u!", 'ù'),
+    ("u'", 'ú'),
+    ("u>", 'û'),
+    ("u:", 'ü'),
+    ("y'", 'ý'),
+    ("y:", 'ÿ'),
+    ("a*", 'α'),
+    ("b*", 'β'),
+    ("g*", 'γ'),
+    ("d*", 'δ'),
+    ("e*", 'ε'),
+    ("l*", 'λ'),
+    ("m*", 'μ'),
+    ("p*", 'π'),
+    ("s*", 'σ'),
+    ("-N", '–'),
+    ("-M", '—'),
+    ("'6", '‘'),
+    ("'9", '’'),
+    ("\"6", '“'),
+    ("\"9", '”'),
+    ("..", '‥'),
+    (",.", '…'),
+    ("Eu", '€'),
+    ("TM", '™'),
+    ("->", '→'),
+    ("<-", '←'),
+    ("!=", '≠'),
+    ("=<", '≤'),
+    (">=", '≥'),
+    ("OK", '✓'),
+];
+
+#[cfg(test)]
+mod test {
+    use crate::{state::Mode, test::VimTestContext};
+
+    #[gpui::test]
+    async fn test_character_info(cx: &mut gpui::TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+
+        cx.set_state("ˇa\tcafé", Mode::Normal);
+        cx.simulate_keystrokes([":", "a", "s", "c", "i", "i", "enter"]);
+        assert_eq!(cx.message().as_deref(), Some("<a> 97, Hex 61, Oct 141"));
+
+        cx.simulate_keystrokes(["l", "g", "8"]);
+        assert_eq!(cx.message().as_deref(), Some("09"));
+        // the message is cleared by the next keystroke
+        cx.simulate_keystrokes(["l"]);
+        assert_eq!(cx.message(), None);
+
+        cx.simulate_keystrokes(["$", "g", "8"]);
+        assert_eq!(cx.message().as_deref(), Some("c3 a9"));
+        cx.simulate_keystrokes([":", "a", "s", "enter"]);
+        assert_eq!(
+            cx.message().as_deref(),
+            Some("<é> 233, Hex 00e9, Oct 351, Digr e'")
+        );
+    }
+
+    #[gpui::test]
+    async fn test_combining_character_info(cx: &mut gpui::TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+
+        cx.set_state("ˇe\u{301}!", Mode::Normal);
+        cx.simulate_keystrokes(["g", "8"]);
+        assert_eq!(cx.message().as_deref(), Some("65 + cc 81"));
+        cx.simulate_keystrokes([":", "a", "s", "enter"]);
+        assert_eq!(
+            cx.message().as_deref(),
+            Some("<e> 101, Hex 65, Oct 145 < \u{301}> 769, Hex 0301, Oct 1401")
+        );
+    }
+}
@@ -0,0 +1,255 @@
+use std::ops::Range;
+
+use editor::{Editor, MultiBufferSnapshot};
+use gpui::{actions, AppContext, ViewContext};
+use language::Point;
+use workspace::Workspace;
+
+use crate::{state::Mode, Vim};
+
+actions!(vim, [ShowCounts, ShowFileInfo]);
+
+pub(crate) fn register(workspace: &mut Workspace, _: &mut ViewContext<Workspace>) {
+    workspace.register_action(|_: &mut Workspace, _: &ShowCounts, cx| {
+        Vim::update(cx, |vim, cx| {
+            let mode = vim.state().mode;
+            let Some(message) = vim.update_active_editor(cx, |_, editor, cx| {
+                let snapshot = editor.buffer().read(cx).snapshot(cx);
+                if mode.is_visual() {
+                    let selections = editor.selections.all::<Point>(cx);
+                    let ranges = selections.iter().map(|s| s.start..s.end);
+                    selection_counts(&snapshot, ranges, mode == Mode::VisualBlock)
+                } else {
+                    let cursor = editor.selections.newest::<Point>(cx).head();
+                    cursor_counts(&snapshot, cursor)
+                }
+            }) else {
+                return;
+            };
+            vim.clear_operator(cx);
+            vim.show_message(message);
+        })
+    });
+    workspace.register_action(|_: &mut Workspace, _: &ShowFileInfo, cx| {
+        Vim::update(cx, |vim, cx| {
+            let full_path = vim.take_count(cx).is_some();
+            let Some(message) =
+                vim.update_active_editor(cx, |_, editor, cx| file_info(editor, full_path, cx))
+            else {
+                return;
+            };
+            vim.clear_operator(cx);
+            vim.show_message(message);
+        })
+    });
+}
+
+/// Like vim's `ctrl-g`: `"src/main.rs" [Modified] 42 lines --30%--`, where the path is
+/// relative to the project (or, given a count, the full path).
+pub(crate) fn file_info(editor: &Editor, full_path: bool, cx: &AppContext) -> String {
+    let buffer = editor.buffer().read(cx);
+    let name = match buffer.as_singleton() {
+        Some(singleton) => match singleton.read(cx).file() {
+            Some(file) if full_path => file
+                .as_local()
+                .map_or_else(|| file.full_path(cx), |file| file.abs_path(cx))
+                .to_string_lossy()
+                .to_string(),
+            Some(file) => file.path().to_string_lossy().to_string(),
+            None => "[No Name]".to_string(),
+        },
+        None => buffer.title(cx).to_string(),
+    };
+
+    let mut message = format!("\"{name}\"");
+    if buffer.is_dirty(cx) {
+        message.push_str(" [Modified]");
+    }
+    if editor.read_only(cx) {
+        message.push_str(" [readonly]");
+    }
+    let snapshot = buffer.snapshot(cx);
+    if snapshot.len() == 0 {
+        message.push_str(" --No lines in buffer--");
+        return message;
+    }
+    let lines = snapshot.max_point().row + 1;
+    let row = editor.selections.newest::<Point>(cx).head().row + 1;
+    message.push_str(&format!(
+        " {} line{} --{}%--",
+        lines,
+        if lines == 1 { "" } else { "s" },
+        row * 100 / lines
+    ));
+    message
+}
+
+/// The totals for a buffer (or a part of one), as `g ctrl-g` counts them.
+#[derive(Default, Debug, PartialEq)]
+struct Counts {
+    words: usize,
+    chars: usize,
+    bytes: usize,
+}
+
+impl Counts {
+    /// Like vim, a word is any run of non-blank characters.
+    fn of(chars: impl Iterator<Item = char>) -> Self {
+        let mut counts = Counts::default();
+        let mut in_word = false;
+        for c in chars {
+            if c.is_whitespace() {
+                in_word = false;
+            } else if !in_word {
+                in_word = true;
+                counts.words += 1;
+            }
+            counts.chars += 1;
+            counts.bytes += c.len_utf8();
+        }
+        counts
+    }
+}
+
+/// `Col 5 of 11; Line 2 of 2; Word 4 of 5; Byte 17 of 23`
+fn cursor_counts(snapshot: &MultiBufferSnapshot, cursor: Point) -> String {
+    let total = Counts::of(snapshot.chars_at(0));
+    let offset = snapshot.point_to_offset(cursor);
+    // the character under the cursor counts as being before it, so that
+    // the first word is word 1.
+    let cursor_len = snapshot
+        .chars_at(offset)
+        .next()
+        .filter(|c| *c != '\n')
+        .map_or(0, |c| c.len_utf8());
+    let before = Counts::of(
+        snapshot
+            .text_for_range(0..offset + cursor_len)
+            .flat_map(str::chars),
+    );
+
+    let line_len = snapshot.line_len(cursor.row);
+    let column = if line_len == 0 { 0 } else { cursor.column + 1 };
+    let mut message = format!(
+        "Col {} of {}; Line {} of {}; Word {} of {}; ",
+        column,
+        line_len,
+        cursor.row + 1,
+        snapshot.max_point().row + 1,
+        before.words,
+        total.words,
+    );
+    if total.chars != total.bytes {
+        message.push_str(&format!("Char {} of {}; ", before.chars, total.chars));
+    }
+    // like vim, the byte is where the character starts
+    let byte = (offset + 1).min(total.bytes);
+    message.push_str(&format!("Byte {} of {}", byte, total.bytes));
+    message
+}
+
+/// `Selected 2 of 3 Lines; 4 of 5 Words; 20 of 30 Bytes`
+fn selection_counts(
+    snapshot: &MultiBufferSnapshot,
+    ranges: impl Iterator<Item = Range<Point>>,
+    block: bool,
+) -> String {
+    let total = Counts::of(snapshot.chars_at(0));
+    let mut selected = Counts::default();
+    let mut lines = 0;
+    let mut columns = 0;
+    for range in ranges {
+        let counts = Counts::of(snapshot.text_for_range(range.clone()).flat_map(str::chars));
+        selected.words += counts.words;
+        selected.chars += counts.chars;
+        selected.bytes += counts.bytes;
+        columns = columns.max(counts.chars);
+        // a selection that ends at the start of a line doesn't include it
+        lines += range.end.row - range.start.row + 1;
+        if range.end.column == 0 && range.end.row > range.start.row {
+            lines -= 1;
+        }
+    }
+
+    let mut message = "Selected ".to_string();
+    if block {
+        message.push_str(&format!("{} Cols; ", columns));
+    }
+    message.push_str(&format!(
+        "{} of {} Lines; {} of {} Words; ",
+        lines,
+        snapshot.max_point().row + 1,
+        selected.words,
+        total.words,
+    ));
+    if total.chars != total.bytes {
+        message.push_str(&format!("{} of {} Chars; ", selected.chars, total.chars));
+    }
+    message.push_str(&format!("{} of {} Bytes", selected.bytes, total.bytes));
+    message
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{state::Mode, test::VimTestContext};
+
+    #[gpui::test]
+    async fn test_show_counts(cx: &mut gpui::TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+
+        cx.set_state("hello world\nfoo ˇbar baz", Mode::Normal);
+        cx.simulate_keystrokes(["g", "ctrl-g"]);
+        assert_eq!(
+            cx.message().as_deref(),
+            Some("Col 5 of 11; Line 2 of 2; Word 4 of 5; Byte 17 of 23")
+        );
+
+        cx.set_state("ˇcafé au lait", Mode::Normal);
+        cx.simulate_keystrokes(["g", "ctrl-g"]);
+        assert_eq!(
+            cx.message().as_deref(),
+            Some("Col 1 of 13; Line 1 of 1; Word 1 of 3; Char 1 of 12; Byte 1 of 13")
+        );
+    }
+
+    #[gpui::test]
+    async fn test_show_file_info(cx: &mut gpui::TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+
+        cx.set_state("one\ntwo\nˇthree\nfour", Mode::Normal);
+        cx.simulate_keystrokes(["ctrl-g"]);
+        assert_eq!(
+            cx.message().as_deref(),
+            Some("\"file.rs\" [Modified] 4 lines --75%--")
+        );
+
+        // with a count, the path is the full one
+        cx.set_state("ˇone", Mode::Normal);
+        cx.simulate_keystrokes(["1", "ctrl-g"]);
+        assert_eq!(
+            cx.message().as_deref(),
+            Some("\"/root/dir/file.rs\" [Modified] 1 line --100%--")
+        );
+
+        cx.set_state("ˇ", Mode::Normal);
+        cx.simulate_keystrokes(["ctrl-g"]);
+        assert_eq!(
+            cx.message().as_deref(),
+            Some("\"file.rs\" [Modified] --No lines in buffer--")
+        );
+    }
+
+    #[gpui::test]
+    async fn test_show_selection_counts(cx: &mut gpui::TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+
+        cx.set_state("ˇhello world\nfoo bar baz", Mode::Normal);
+        cx.simulate_keystrokes(["v", "e", "g", "ctrl-g"]);
+        assert_eq!(
+            cx.message().as_deref(),
+            Some("Selected 1 of 2 Lines; 1 of 5 Words; 5 of 23 Bytes")
+        );
+        // visual mode is kept
+        cx.assert_state("«hellˇo» world\nfoo bar baz", Mode::Visual);
+    }
+}
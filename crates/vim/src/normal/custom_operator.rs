@@ -0,0 +1,188 @@
+use editor::{scroll::Autoscroll, Editor};
+use gpui::{impl_actions, ViewContext, WindowContext};
+use serde_derive::Deserialize;
+use settings::Settings;
+use workspace::Workspace;
+
+use crate::{
+    motion::Motion,
+    object::Object,
+    state::{Mode, Operator},
+    utils::KeywordChars,
+    Vim, VimSettings,
+};
+
+/// Starts one of the operators the `operators` setting defines, which runs a Zed
+/// action over the text a motion or text object covers, as if it were selected (so
+/// `"operators": {"sort": "editor::SortLinesCaseSensitive"}`, bound to `gs`, makes
+/// `gsip` sort a paragraph). In visual mode it runs over the selection. Like vim's
+/// own operators, it takes a count and `.` repeats it.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct CustomOperator {
+    pub name: String,
+}
+
+impl_actions!(vim, [CustomOperator]);
+
+pub(crate) fn register(workspace: &mut Workspace, _: &mut ViewContext<Workspace>) {
+    workspace.register_action(|_: &mut Workspace, action: &CustomOperator, cx| {
+        Vim::update(cx, |vim, cx| {
+            if !VimSettings::get_global(cx)
+                .operators
+                .contains_key(&action.name)
+            {
+                vim.clear_operator(cx);
+                vim.show_message(format!("E117: Unknown operator: {}", action.name));
+                return;
+            }
+            if vim.state().mode.is_visual() {
+                vim.record_current_action(cx);
+                run_custom_operator(vim, &action.name, cx);
+                return;
+            }
+            let name = action.name.clone();
+            vim.update_state(|state| state.custom_operator = Some(name));
+            vim.push_operator(Operator::Custom, cx);
+        })
+    });
+}
+
+pub fn custom_operator_motion(
+    vim: &mut Vim,
+    motion: Motion,
+    times: Option<usize>,
+    cx: &mut WindowContext,
+) {
+    vim.stop_recording();
+    let Some(name) = vim.update_state(|state| state.custom_operator.take()) else {
+        return;
+    };
+    vim.update_active_editor(cx, |_, editor, cx| {
+        let text_layout_details = editor.text_layout_details(cx);
+        let keyword_chars = KeywordChars::new(cx);
+        editor.change_selections(None, cx, |s| {
+            s.move_with(|map, selection| {
+                motion.expand_selection(
+                    map,
+                    selection,
+                    times,
+                    true,
+                    &text_layout_details,
+                    &keyword_chars,
+                );
+            });
+        });
+    });
+    run_custom_operator(vim, &name, cx);
+}
+
+pub fn custom_operator_object(vim: &mut Vim, object: Object, around: bool, cx: &mut WindowContext) {
+    vim.stop_recording();
+    let Some(name) = vim.update_state(|state| state.custom_operator.take()) else {
+        return;
+    };
+    vim.update_active_editor(cx, |_, editor, cx| {
+        let keyword_chars = KeywordChars::new(cx);
+        editor.change_selections(None, cx, |s| {
+            s.move_with(|map, selection| {
+                object.expand_selection(map, selection, around, &keyword_chars);
+            });
+        });
+    });
+    run_custom_operator(vim, &name, cx);
+}
+
+/// Dispatches the operator's action to the editor, with the text it covers
+/// selected, and then puts the cursor at the start of that text, in normal mode.
+fn run_custom_operator(vim: &mut Vim, name: &str, cx: &mut WindowContext) {
+    let Some(action_name) = VimSettings::get_global(cx).operators.get(name).cloned() else {
+        return;
+    };
+    let Ok(action) = cx.build_action(&action_name, None) else {
+        vim.show_message(format!("E492: Not an editor command: {action_name}"));
+        return;
+    };
+    let Some(starts) = vim.update_active_editor(cx, |_, editor, cx| {
+        let starts = editor
+            .selections
+            .disjoint_anchors()
+            .iter()
+            .map(|selection| selection.start)
+            .collect::<Vec<_>>();
+        (cx.view().downgrade(), starts)
+    }) else {
+        return;
+    };
+
+    // the action is dispatched once this one is over, as a key binding would be,
+    // and the cursor put back after that
+    cx.dispatch_action(action);
+    cx.defer(move |cx| {
+        let (editor, starts) = starts;
+        editor
+            .update(cx, |editor: &mut Editor, cx| {
+                editor.change_selections(Some(Autoscroll::fit()), cx, |s| {
+                    s.select_anchor_ranges(starts.into_iter().map(|start| start..start))
+                });
+            })
+            .ok();
+        Vim::update(cx, |vim, cx| {
+            if vim.state().mode != Mode::Normal {
+                vim.switch_mode(Mode::Normal, true, cx)
+            }
+        });
+    });
+}
+
+#[cfg(test)]
+mod test {
+    use collections::HashMap;
+    use gpui::KeyBinding;
+    use settings::SettingsStore;
+
+    use super::CustomOperator;
+    use crate::{state::Mode, test::VimTestContext, VimSettings};
+
+    #[gpui::test]
+    async fn test_custom_operator(cx: &mut gpui::TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+        cx.update_global(|store: &mut SettingsStore, cx| {
+            store.update_user_settings::<VimSettings>(cx, |settings| {
+                settings.operators = Some(HashMap::from_iter([(
+                    "sort".to_string(),
+                    "editor::SortLinesCaseSensitive".to_string(),
+                )]))
+            });
+        });
+        cx.update(|cx| {
+            cx.bind_keys([KeyBinding::new(
+                "g s",
+                CustomOperator {
+                    name: "sort".to_string(),
+                },
+                Some("Editor && VimControl && !VimWaiting && !menu"),
+            )])
+        });
+
+        // it runs over the lines a motion covers
+        cx.set_state("d\nˇc\nb\na\n\nz\ny", Mode::Normal);
+        cx.simulate_keystrokes(["g", "s", "j"]);
+        cx.run_until_parked();
+        cx.assert_state("d\nˇb\nc\na\n\nz\ny", Mode::Normal);
+
+        // and . repeats it
+        cx.simulate_keystrokes(["j", "."]);
+        cx.run_until_parked();
+        cx.assert_state("d\nb\nˇa\nc\n\nz\ny", Mode::Normal);
+
+        // or over a text object
+        cx.simulate_keystrokes(["g", "s", "i", "p"]);
+        cx.run_until_parked();
+        cx.assert_state("ˇa\nb\nc\nd\n\nz\ny", Mode::Normal);
+
+        // or the selection
+        cx.simulate_keystrokes(["G", "V", "k", "g", "s"]);
+        cx.run_until_parked();
+        cx.assert_state("a\nb\nc\nd\n\nˇy\nz", Mode::Normal);
+    }
+}
@@ -1,4 +1,9 @@
-use crate::{motion::Motion, object::Object, utils::copy_selections_content, Vim};
+use crate::{
+    motion::Motion,
+    object::Object,
+    utils::{copy_selections_content, KeywordChars},
+    Vim,
+};
 use collections::{HashMap, HashSet};
 use editor::{
     display_map::{DisplaySnapshot, ToDisplayPoint},
@@ -12,14 +17,25 @@ pub fn delete_motion(vim: &mut Vim, motion: Motion, times: Option<usize>, cx: &m
     vim.stop_recording();
     vim.update_active_editor(cx, |vim, editor, cx| {
         let text_layout_details = editor.text_layout_details(cx);
+        let keyword_chars = KeywordChars::new(cx);
         editor.transact(cx, |editor, cx| {
             editor.set_clip_at_line_ends(false, cx);
             let mut original_columns: HashMap<_, _> = Default::default();
+            let mut linewise = motion.linewise();
             editor.change_selections(Some(Autoscroll::fit()), cx, |s| {
                 s.move_with(|map, selection| {
                     let original_head = selection.head();
                     original_columns.insert(selection.id, original_head.column());
-                    motion.expand_selection(map, selection, times, true, &text_layout_details);
+                    linewise |= motion
+                        .expand_selection_linewise(
+                            map,
+                            selection,
+                            times,
+                            true,
+                            &text_layout_details,
+                            &keyword_chars,
+                        )
+                        .unwrap_or(false);
 
                     // Motion::NextWordStart on an empty line should delete it.
                     if let Motion::NextWordStart {
@@ -43,7 +59,7 @@ pub fn delete_motion(vim: &mut Vim, motion: Motion, times: Option<usize>, cx: &m
                     }
                 });
             });
-            copy_selections_content(vim, editor, motion.linewise(), cx);
+            copy_selections_content(vim, editor, linewise, cx);
             editor.insert("", cx);
 
             // Fixup cursor position after the deletion
@@ -51,7 +67,7 @@ pub fn delete_motion(vim: &mut Vim, motion: Motion, times: Option<usize>, cx: &m
             editor.change_selections(Some(Autoscroll::fit()), cx, |s| {
                 s.move_with(|map, selection| {
                     let mut cursor = selection.head();
-                    if motion.linewise() {
+                    if linewise {
                         if let Some(column) = original_columns.get(&selection.id) {
                             *cursor.column_mut() = *column
                         }
@@ -67,6 +83,7 @@ pub fn delete_motion(vim: &mut Vim, motion: Motion, times: Option<usize>, cx: &m
 pub fn delete_object(vim: &mut Vim, object: Object, around: bool, cx: &mut WindowContext) {
     vim.stop_recording();
     vim.update_active_editor(cx, |vim, editor, cx| {
+        let keyword_chars = KeywordChars::new(cx);
         editor.transact(cx, |editor, cx| {
             editor.set_clip_at_line_ends(false, cx);
             // Emulates behavior in vim where if we expanded backwards to include a newline
@@ -74,7 +91,7 @@ pub fn delete_object(vim: &mut Vim, object: Object, around: bool, cx: &mut Windo
             let mut should_move_to_start: HashSet<_> = Default::default();
             editor.change_selections(Some(Autoscroll::fit()), cx, |s| {
                 s.move_with(|map, selection| {
-                    object.expand_selection(map, selection, around);
+                    object.expand_selection(map, selection, around, &keyword_chars);
                     let offset_range = selection.map(|p| p.to_offset(map, Bias::Left)).range();
                     let mut move_selection_start_to_previous_line =
                         |map: &DisplaySnapshot, selection: &mut Selection<DisplayPoint>| {
@@ -511,4 +528,18 @@ mod test {
         cx.assert_neovim_compatible("ˇax", ["d", "t", "x"]).await;
         cx.assert_neovim_compatible("aˇx", ["d", "t", "x"]).await;
     }
+
+    #[gpui::test]
+    async fn test_delete_large_buffer(cx: &mut gpui::TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+        let text = "the quick brown fox jumps over the lazy dog\n".repeat(30_000);
+
+        // the deleted text is put in the register in the background
+        cx.set_state(&format!("{text}ˇend"), Mode::Normal);
+        cx.simulate_keystrokes(["g", "g", "d", "shift-g"]);
+        cx.assert_state("ˇ", Mode::Normal);
+        cx.run_until_parked();
+        cx.simulate_keystrokes(["p"]);
+        cx.assert_state(&format!("\nˇ{text}end"), Mode::Normal);
+    }
 }
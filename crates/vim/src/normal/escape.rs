@@ -0,0 +1,124 @@
+use editor::{scroll::Autoscroll, Editor};
+use gpui::{actions, ViewContext};
+use search::{buffer_search::Dismiss, BufferSearchBar};
+use settings::Settings;
+use workspace::Workspace;
+
+use crate::{
+    hlsearch::clear_highlights, normal::search::search_dismissed, EscapeLayer, Vim, VimSettings,
+};
+
+actions!(vim, [Escape]);
+
+pub(crate) fn register(workspace: &mut Workspace, _: &mut ViewContext<Workspace>) {
+    workspace.register_action(escape);
+}
+
+/// `escape` in normal mode abandons an operation running in the background (like
+/// `:%s` on a very large buffer), or a pending count or register. Otherwise it
+/// clears the first of the layers the `escape` setting lists that has something to
+/// clear: popups (like hovers and completions), then search highlights, then extra
+/// cursors, so that pressing it again and again resets the editor a step at a time.
+fn escape(workspace: &mut Workspace, _: &Escape, cx: &mut ViewContext<Workspace>) {
+    let pending = Vim::update(cx, |vim, cx| {
+        if vim.cancel_pending_operation() {
+            return true;
+        }
+        let pending =
+            vim.state().pre_count.is_some() || vim.workspace_state.selected_register.is_some();
+        vim.clear_operator(cx);
+        pending
+    });
+    if pending {
+        return;
+    }
+    let Some(editor) = workspace.active_item_as::<Editor>(cx) else {
+        cx.propagate();
+        return;
+    };
+    let search_bar = workspace
+        .active_pane()
+        .read(cx)
+        .toolbar()
+        .read(cx)
+        .item_of_type::<BufferSearchBar>()
+        .filter(|search_bar| !search_bar.read(cx).is_dismissed());
+
+    for layer in VimSettings::get_global(cx).escape.clone() {
+        let cleared = match layer {
+            EscapeLayer::Popups => {
+                editor.update(cx, |editor, cx| editor.dismiss_menus_and_popups(false, cx))
+            }
+            EscapeLayer::Highlights => match &search_bar {
+                Some(search_bar) => {
+                    search_bar.update(cx, |search_bar, cx| search_bar.dismiss(&Dismiss, cx));
+                    search_dismissed(cx);
+                    clear_highlights(workspace, cx);
+                    true
+                }
+                None => false,
+            },
+            EscapeLayer::Cursors => editor.update(cx, |editor, cx| {
+                editor.change_selections(Some(Autoscroll::fit()), cx, |s| s.try_cancel())
+            }),
+        };
+        if cleared {
+            return;
+        }
+    }
+    cx.propagate();
+}
+
+#[cfg(test)]
+mod test {
+    use settings::SettingsStore;
+
+    use crate::{state::Mode, test::VimTestContext, EscapeLayer, VimSettings};
+
+    #[gpui::test]
+    async fn test_escape_layers(cx: &mut gpui::TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+
+        cx.set_state("ˇone two\none two", Mode::Normal);
+        cx.simulate_keystrokes(["/", "t", "w", "o", "enter"]);
+        cx.run_until_parked();
+        cx.update_editor(|editor, cx| {
+            editor.change_selections(None, cx, |s| s.select_ranges([0..0, 4..4]))
+        });
+
+        // the search highlights are cleared first
+        cx.simulate_keystrokes(["escape"]);
+        cx.update_editor(|editor, cx| {
+            assert!(editor.all_text_background_highlights(cx).is_empty())
+        });
+        cx.assert_state("ˇone ˇtwo\none two", Mode::Normal);
+
+        // and then the extra cursors
+        cx.simulate_keystrokes(["escape"]);
+        cx.assert_state("ˇone two\none two", Mode::Normal);
+
+        // in the order the setting gives
+        cx.update_global(|store: &mut SettingsStore, cx| {
+            store.update_user_settings::<VimSettings>(cx, |settings| {
+                settings.escape = Some(vec![EscapeLayer::Cursors, EscapeLayer::Highlights])
+            });
+        });
+        cx.simulate_keystrokes(["/", "t", "w", "o", "enter"]);
+        cx.run_until_parked();
+        cx.update_editor(|editor, cx| {
+            editor.change_selections(None, cx, |s| s.select_ranges([0..0, 4..4]))
+        });
+        cx.simulate_keystrokes(["escape"]);
+        cx.assert_state("ˇone two\none two", Mode::Normal);
+        cx.update_editor(|editor, cx| {
+            assert!(!editor.all_text_background_highlights(cx).is_empty())
+        });
+
+        // a count is abandoned before anything else
+        cx.simulate_keystrokes(["2", "escape", "j"]);
+        cx.assert_state("one two\nˇone two", Mode::Normal);
+        cx.update_editor(|editor, cx| {
+            assert!(!editor.all_text_background_highlights(cx).is_empty())
+        });
+    }
+}
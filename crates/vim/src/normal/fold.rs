@@ -0,0 +1,408 @@
+use std::ops::Range;
+
+use editor::{
+    actions::UnfoldLines,
+    display_map::{DisplaySnapshot, ToDisplayPoint},
+    Editor, MultiBufferSnapshot,
+};
+use gpui::{actions, ViewContext, WindowContext};
+use language::{Point, SelectionGoal};
+use settings::Settings;
+use workspace::Workspace;
+
+use crate::{
+    motion::Motion, object::Object, state::Mode, utils::KeywordChars, FoldOpen, Vim, VimSettings,
+};
+
+actions!(
+    vim,
+    [
+        VisualFold,
+        DeleteFold,
+        DeleteAllFolds,
+        FoldMore,
+        FoldLess,
+        CloseAllFolds,
+        OpenAllFolds
+    ]
+);
+
+pub(crate) fn register(workspace: &mut Workspace, _: &mut ViewContext<Workspace>) {
+    workspace.register_action(|_: &mut Workspace, _: &VisualFold, cx| {
+        Vim::update(cx, |vim, cx| {
+            vim.update_active_editor(cx, |_, editor, cx| {
+                let buffer = editor.buffer().read(cx).snapshot(cx);
+                let ranges = editor
+                    .selections
+                    .all::<Point>(cx)
+                    .into_iter()
+                    .map(|selection| line_range(&buffer, selection.start..selection.end))
+                    .collect::<Vec<_>>();
+                fold_lines(editor, ranges, cx);
+            });
+            vim.switch_mode(Mode::Normal, true, cx);
+        })
+    });
+
+    workspace.register_action(|_: &mut Workspace, _: &DeleteFold, cx| {
+        Vim::update(cx, |vim, cx| {
+            vim.update_active_editor(cx, |_, editor, cx| {
+                editor.unfold_lines(&UnfoldLines, cx);
+            });
+            if vim.state().mode.is_visual() {
+                vim.switch_mode(Mode::Normal, false, cx);
+            }
+        })
+    });
+
+    workspace.register_action(|_: &mut Workspace, _: &DeleteAllFolds, cx| {
+        Vim::update(cx, |vim, cx| {
+            vim.update_active_editor(cx, |_, editor, cx| {
+                let len = editor.buffer().read(cx).len(cx);
+                editor.unfold_ranges([0..len], true, true, cx);
+            });
+        })
+    });
+
+    workspace.register_action(|_: &mut Workspace, _: &FoldMore, cx| {
+        change_fold_level(cx, |current, max, count| {
+            current.unwrap_or(max).saturating_sub(count)
+        })
+    });
+    workspace.register_action(|_: &mut Workspace, _: &FoldLess, cx| {
+        change_fold_level(cx, |current, max, count| current.unwrap_or(max) + count)
+    });
+    workspace.register_action(|_: &mut Workspace, _: &CloseAllFolds, cx| {
+        change_fold_level(cx, |_, _, _| 0)
+    });
+    workspace.register_action(|_: &mut Workspace, _: &OpenAllFolds, cx| {
+        change_fold_level(cx, |_, max, _| max)
+    });
+}
+
+/// `zm` and `zr` change the fold level of the editor by the count. Until one of them
+/// (or `zM`/`zR`) is used, the level is the deepest one, with every fold open.
+fn change_fold_level(cx: &mut WindowContext, level: fn(Option<u32>, u32, u32) -> u32) {
+    Vim::update(cx, |vim, cx| {
+        let count = vim.take_count(cx).unwrap_or(1) as u32;
+        let current = vim.state().fold_level;
+        let Some(new_level) = vim.update_active_editor(cx, |_, editor, cx| {
+            set_fold_level(editor, |max| level(current, max, count), cx)
+        }) else {
+            return;
+        };
+        vim.update_state(|state| state.fold_level = Some(new_level));
+    })
+}
+
+/// Closes the folds that are `level` or more deep, and opens all others, so that
+/// opening a closed fold leaves the ones inside it closed, as in vim. The folds come
+/// from the indentation, like vim's `foldmethod=indent`. Returns the level, clamped to
+/// the deepest fold.
+pub(crate) fn set_fold_level(
+    editor: &mut Editor,
+    level: impl FnOnce(u32) -> u32,
+    cx: &mut ViewContext<Editor>,
+) -> u32 {
+    let len = editor.buffer().read(cx).len(cx);
+    editor.unfold_ranges([0..len], true, false, cx);
+
+    let folds = nested_folds(&editor.snapshot(cx).display_snapshot);
+    let max = folds.iter().map(|(depth, _)| depth + 1).max().unwrap_or(0);
+    let level = level(max).min(max);
+    let ranges = folds
+        .into_iter()
+        .filter(|(depth, _)| *depth >= level)
+        .map(|(_, range)| range);
+    editor.fold_ranges(ranges, true, cx);
+    level
+}
+
+/// Every foldable range in the buffer, with how many other ranges it is inside of.
+fn nested_folds(map: &DisplaySnapshot) -> Vec<(u32, Range<Point>)> {
+    let mut folds = Vec::new();
+    let mut enclosing: Vec<Point> = Vec::new();
+    for row in 0..=map.buffer_snapshot.max_point().row {
+        if let Some(range) = map.foldable_range(row) {
+            while enclosing.last().is_some_and(|end| *end < range.end) {
+                enclosing.pop();
+            }
+            folds.push((enclosing.len() as u32, range.clone()));
+            enclosing.push(range.end);
+        }
+    }
+    folds
+}
+
+pub fn fold_motion(vim: &mut Vim, motion: Motion, times: Option<usize>, cx: &mut WindowContext) {
+    vim.update_active_editor(cx, |_, editor, cx| {
+        let text_layout_details = editor.text_layout_details(cx);
+        let keyword_chars = KeywordChars::new(cx);
+        let mut ranges = Vec::new();
+        editor.change_selections(None, cx, |s| {
+            s.move_with(|map, selection| {
+                motion.expand_selection(
+                    map,
+                    selection,
+                    times,
+                    true,
+                    &text_layout_details,
+                    &keyword_chars,
+                );
+                let range = selection.start.to_point(map)..selection.end.to_point(map);
+                ranges.push(line_range(&map.buffer_snapshot, range));
+            });
+        });
+        fold_lines(editor, ranges, cx);
+    });
+}
+
+pub fn fold_object(vim: &mut Vim, object: Object, around: bool, cx: &mut WindowContext) {
+    vim.update_active_editor(cx, |_, editor, cx| {
+        let keyword_chars = KeywordChars::new(cx);
+        let mut ranges = Vec::new();
+        editor.change_selections(None, cx, |s| {
+            s.move_with(|map, selection| {
+                object.expand_selection(map, selection, around, &keyword_chars);
+                let range = selection.start.to_point(map)..selection.end.to_point(map);
+                ranges.push(line_range(&map.buffer_snapshot, range));
+            });
+        });
+        fold_lines(editor, ranges, cx);
+    });
+}
+
+/// Opens the closed folds the cursors are in after a command of the given kind,
+/// when the `foldopen` setting lists it, as in vim.
+pub(crate) fn open_folds_at_cursors(vim: &mut Vim, kind: FoldOpen, cx: &mut WindowContext) {
+    let foldopen = &VimSettings::get_global(cx).foldopen;
+    if !foldopen.contains(&kind) && !foldopen.contains(&FoldOpen::All) {
+        return;
+    }
+    vim.update_active_editor(cx, |_, editor, cx| {
+        let map = editor.snapshot(cx).display_snapshot;
+        let cursors = editor
+            .selections
+            .all::<usize>(cx)
+            .into_iter()
+            .map(|selection| selection.head())
+            .filter(|cursor| map.intersects_fold(*cursor))
+            .map(|cursor| cursor..cursor)
+            .collect::<Vec<_>>();
+        editor.unfold_ranges(cursors, true, true, cx);
+    });
+}
+
+/// Vim's manual folds always cover whole lines. A range that ends at the start of a
+/// line (as linewise motions do) doesn't include that line.
+fn line_range(buffer: &MultiBufferSnapshot, range: Range<Point>) -> Range<Point> {
+    let mut end_row = range.end.row;
+    if range.end.column == 0 && end_row > range.start.row {
+        end_row -= 1;
+    }
+    Point::new(range.start.row, 0)..Point::new(end_row, buffer.line_len(end_row))
+}
+
+/// Folds the ranges, leaving each cursor at the start of its fold.
+fn fold_lines(editor: &mut Editor, ranges: Vec<Range<Point>>, cx: &mut ViewContext<Editor>) {
+    let starts = ranges.iter().map(|range| range.start).collect::<Vec<_>>();
+    editor.fold_ranges(ranges, true, cx);
+    editor.change_selections(None, cx, |s| {
+        let mut starts = starts.into_iter();
+        s.move_with(|map, selection| {
+            if let Some(start) = starts.next() {
+                selection.collapse_to(start.to_display_point(map), SelectionGoal::None);
+            }
+        });
+    });
+}
+
+#[cfg(test)]
+mod test {
+    use indoc::indoc;
+    use settings::SettingsStore;
+
+    use crate::{state::Mode, test::VimTestContext, FoldOpen, VimSettings};
+
+    #[gpui::test]
+    async fn test_fold_motion(cx: &mut gpui::TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+
+        cx.set_state(
+            indoc! {"
+                fn boop() {
+                  baˇrp()
+                  bazp()
+                }
+                last"},
+            Mode::Normal,
+        );
+        cx.simulate_keystrokes(["z", "f", "j"]);
+        cx.assert_editor_state(indoc! {"
+            fn boop() {
+            ˇ  barp()
+              bazp()
+            }
+            last"});
+        // the fold is skipped over
+        cx.simulate_keystrokes(["j"]);
+        cx.assert_state(
+            indoc! {"
+            fn boop() {
+              barp()
+              bazp()
+            ˇ}
+            last"},
+            Mode::Normal,
+        );
+
+        cx.simulate_keystrokes(["z", "shift-e", "k"]);
+        cx.assert_state(
+            indoc! {"
+            fn boop() {
+              barp()
+            ˇ  bazp()
+            }
+            last"},
+            Mode::Normal,
+        );
+
+        // with a text object
+        cx.simulate_keystrokes(["z", "f", "a", "{", "j"]);
+        cx.assert_state(
+            indoc! {"
+            fn boop() {
+              barp()
+              bazp()
+            }
+            ˇlast"},
+            Mode::Normal,
+        );
+        cx.simulate_keystrokes(["k", "z", "d", "j"]);
+        cx.assert_state(
+            indoc! {"
+            fn boop() {
+            ˇ  barp()
+              bazp()
+            }
+            last"},
+            Mode::Normal,
+        );
+    }
+
+    #[gpui::test]
+    async fn test_fold_level(cx: &mut gpui::TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+
+        cx.set_state(
+            indoc! {"
+                ˇfn a() {
+                    if x {
+                        y
+                    }
+                }
+                last"},
+            Mode::Normal,
+        );
+        let display_text =
+            |cx: &mut VimTestContext| cx.update_editor(|editor, cx| editor.display_text(cx));
+
+        cx.simulate_keystrokes(["z", "shift-m"]);
+        assert_eq!(display_text(&mut cx), "fn a() {⋯\n}\nlast");
+        cx.simulate_keystrokes(["z", "r"]);
+        assert_eq!(
+            display_text(&mut cx),
+            "fn a() {\n    if x {⋯\n    }\n}\nlast"
+        );
+        cx.simulate_keystrokes(["z", "r"]);
+        assert_eq!(display_text(&mut cx), cx.buffer_text());
+
+        // the level doesn't go above the deepest fold
+        cx.simulate_keystrokes(["5", "z", "r", "z", "m"]);
+        assert_eq!(
+            display_text(&mut cx),
+            "fn a() {\n    if x {⋯\n    }\n}\nlast"
+        );
+        cx.simulate_keystrokes(["z", "shift-r"]);
+        assert_eq!(display_text(&mut cx), cx.buffer_text());
+
+        // folds inside a closed fold are closed too, and stay closed when it opens
+        cx.simulate_keystrokes(["z", "shift-m", "z", "o"]);
+        assert_eq!(
+            display_text(&mut cx),
+            "fn a() {\n    if x {⋯\n    }\n}\nlast"
+        );
+    }
+
+    #[gpui::test]
+    async fn test_operators_on_folds(cx: &mut gpui::TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+        let text = indoc! {"
+            fn boop() {
+              ˇbarp()
+              bazp()
+            }
+            last"};
+
+        // dd on a closed fold deletes all of it
+        cx.set_state(text, Mode::Normal);
+        cx.simulate_keystrokes(["z", "f", "j", "d", "d"]);
+        cx.assert_state("fn boop() {\nˇ}\nlast", Mode::Normal);
+        assert_eq!(
+            cx.read_register('"'),
+            Some("  barp()\n  bazp()\n".to_string())
+        );
+
+        // and so do other operators, which act on its lines
+        cx.set_state(text, Mode::Normal);
+        cx.simulate_keystrokes(["z", "f", "j", "d", "l"]);
+        cx.assert_state("fn boop() {\nˇ}\nlast", Mode::Normal);
+
+        // a count counts a closed fold as one line
+        cx.set_state(text, Mode::Normal);
+        cx.simulate_keystrokes(["z", "f", "j", "2", "d", "d"]);
+        cx.assert_state("fn boop() {\nˇlast", Mode::Normal);
+    }
+
+    #[gpui::test]
+    async fn test_foldopen(cx: &mut gpui::TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+        let display_text =
+            |cx: &mut VimTestContext| cx.update_editor(|editor, cx| editor.display_text(cx));
+        let text = indoc! {"
+            fn boop() {
+              ˇbarp()
+              bazp()
+            }"};
+
+        // horizontal motions open the fold the cursor is in, vertical ones don't
+        cx.set_state(text, Mode::Normal);
+        cx.simulate_keystrokes(["z", "f", "j", "k", "j"]);
+        assert_eq!(display_text(&mut cx), "fn boop() {\n⋯\n}");
+        cx.simulate_keystrokes(["l"]);
+        assert_eq!(display_text(&mut cx), cx.buffer_text());
+
+        cx.update_global(|store: &mut SettingsStore, cx| {
+            store.update_user_settings::<VimSettings>(cx, |settings| {
+                settings.foldopen = Some(vec![FoldOpen::Insert])
+            });
+        });
+        cx.set_state(text, Mode::Normal);
+        cx.simulate_keystrokes(["z", "f", "j", "l"]);
+        assert_eq!(display_text(&mut cx), "fn boop() {\n⋯\n}");
+        cx.simulate_keystrokes(["i"]);
+        assert_eq!(display_text(&mut cx), cx.buffer_text());
+
+        // with all, vertical motions open it too
+        cx.update_global(|store: &mut SettingsStore, cx| {
+            store.update_user_settings::<VimSettings>(cx, |settings| {
+                settings.foldopen = Some(vec![FoldOpen::All])
+            });
+        });
+        cx.set_state(text, Mode::Normal);
+        cx.simulate_keystrokes(["z", "f", "j", "k"]);
+        assert_eq!(display_text(&mut cx), "fn boop() {\n⋯\n}");
+        cx.simulate_keystrokes(["j"]);
+        assert_eq!(display_text(&mut cx), cx.buffer_text());
+    }
+}
@@ -0,0 +1,134 @@
+use std::path::{Path, PathBuf};
+
+use editor::Editor;
+use gpui::{actions, ViewContext};
+use project::Fs;
+use workspace::Workspace;
+
+use crate::{completion::is_path_char, utils::worktree_root, Vim};
+
+actions!(vim, [GoToFile, GoToFileSplit]);
+
+pub(crate) fn register(workspace: &mut Workspace, _: &mut ViewContext<Workspace>) {
+    workspace.register_action(|workspace: &mut Workspace, _: &GoToFile, cx| {
+        go_to_file(workspace, false, cx)
+    });
+    workspace.register_action(|workspace: &mut Workspace, _: &GoToFileSplit, cx| {
+        go_to_file(workspace, true, cx)
+    });
+}
+
+/// `gf` opens the file whose name is under the cursor, and `ctrl-w f` opens it in a
+/// split. Like vim, a relative name is looked for next to the current file, and then
+/// from the root of the project.
+fn go_to_file(workspace: &mut Workspace, split: bool, cx: &mut ViewContext<Workspace>) {
+    let Some(editor) = workspace.active_item_as::<Editor>(cx) else {
+        return;
+    };
+    let project = workspace.project().clone();
+    let (name, candidates) = editor.update(cx, |editor, cx| {
+        let snapshot = editor.buffer().read(cx).snapshot(cx);
+        let offset = editor.selections.newest::<usize>(cx).head();
+        let start = snapshot
+            .reversed_chars_at(offset)
+            .take_while(|c| is_path_char(*c))
+            .map(char::len_utf8)
+            .sum::<usize>();
+        let end = snapshot
+            .chars_at(offset)
+            .take_while(|c| is_path_char(*c))
+            .map(char::len_utf8)
+            .sum::<usize>();
+        let name = snapshot
+            .text_for_range(offset - start..offset + end)
+            .collect::<String>();
+
+        let mut candidates = Vec::new();
+        if Path::new(&name).is_absolute() {
+            candidates.push(PathBuf::from(&name));
+        } else if let Some((buffer, _, _)) =
+            editor.buffer().read(cx).point_to_buffer_offset(offset, cx)
+        {
+            if let Some(file) = buffer.read(cx).file().and_then(|file| file.as_local()) {
+                if let Some(directory) = file.abs_path(cx).parent() {
+                    candidates.push(directory.join(&name));
+                }
+            }
+            if let Some(root) = worktree_root(&buffer, &project, cx) {
+                candidates.push(root.join(&name));
+            }
+        }
+        (name, candidates)
+    });
+
+    if name.is_empty() {
+        Vim::update(cx, |vim, _| {
+            vim.show_message("E446: No file name under cursor")
+        });
+        return;
+    }
+
+    let fs = project.read(cx).fs().clone();
+    cx.spawn(|workspace, mut cx| async move {
+        let mut found = None;
+        for candidate in candidates {
+            if fs.is_file(&candidate).await {
+                found = Some(candidate);
+                break;
+            }
+        }
+        let Some(path) = found else {
+            workspace.update(&mut cx, |_, cx| {
+                Vim::update(cx, |vim, _| {
+                    vim.show_message(format!("E447: Can't find file \"{name}\" in path"))
+                })
+            })?;
+            return Ok(());
+        };
+        workspace
+            .update(&mut cx, |workspace, cx| {
+                if split {
+                    workspace.split_abs_path(path, false, cx)
+                } else {
+                    workspace.open_abs_path(path, false, cx)
+                }
+            })?
+            .await?;
+        anyhow::Ok(())
+    })
+    .detach_and_log_err(cx);
+}
+
+#[cfg(test)]
+mod test {
+    use editor::Editor;
+
+    use crate::{state::Mode, test::VimTestContext};
+
+    #[gpui::test]
+    async fn test_go_to_file(cx: &mut gpui::TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+        let fs = cx.workspace(|workspace, cx| workspace.project().read(cx).fs().clone());
+        fs.as_fake()
+            .insert_file("/root/dir/lib.rs", "// lib".into())
+            .await;
+
+        cx.set_state("ˇmissing.rs", Mode::Normal);
+        cx.simulate_keystrokes(["g", "f"]);
+        cx.run_until_parked();
+        assert_eq!(
+            cx.message().as_deref(),
+            Some("E447: Can't find file \"missing.rs\" in path")
+        );
+
+        // relative to the current file
+        cx.set_state("mod ˇlib.rs;", Mode::Normal);
+        cx.simulate_keystrokes(["ctrl-w", "f"]);
+        cx.run_until_parked();
+        cx.workspace(|workspace, cx| {
+            assert_eq!(workspace.panes().len(), 2);
+            let editor = workspace.active_item_as::<Editor>(cx).unwrap();
+            assert_eq!(editor.read(cx).text(cx), "// lib");
+        });
+    }
+}
@@ -1,12 +1,13 @@
 use std::ops::Range;
 
 use editor::{scroll::Autoscroll, MultiBufferSnapshot, ToOffset, ToPoint};
-use gpui::{impl_actions, ViewContext, WindowContext};
+use gpui::{impl_actions, AppContext, ViewContext, WindowContext};
 use language::{Bias, Point};
 use serde::Deserialize;
+use settings::Settings;
 use workspace::Workspace;
 
-use crate::{state::Mode, Vim};
+use crate::{state::Mode, NumberFormat, Vim, VimSettings};
 
 #[derive(Clone, Deserialize, PartialEq)]
 #[serde(rename_all = "camelCase")]
@@ -44,6 +45,7 @@ pub fn register(workspace: &mut Workspace, _: &mut ViewContext<Workspace>) {
 }
 
 fn increment(vim: &mut Vim, mut delta: i32, step: i32, cx: &mut WindowContext) {
+    let formats = VimSettings::get_global(cx).nrformats.clone();
     vim.update_active_editor(cx, |vim, editor, cx| {
         let mut edits = Vec::new();
         let mut new_anchors = Vec::new();
@@ -62,27 +64,23 @@ fn increment(vim: &mut Vim, mut delta: i32, step: i32, cx: &mut WindowContext) {
                     Point::new(row, 0)
                 };
 
-                if let Some((range, num, radix)) = find_number(&snapshot, start) {
+                if let Some((range, num, radix)) = find_number(&snapshot, start, &formats) {
                     if let Ok(val) = i32::from_str_radix(&num, radix) {
                         let result = val + delta;
                         delta += step;
-                        let replace = match radix {
-                            10 => format!("{}", result),
-                            16 => {
-                                if num.to_ascii_lowercase() == num {
-                                    format!("{:x}", result)
-                                } else {
-                                    format!("{:X}", result)
-                                }
-                            }
-                            2 => format!("{:b}", result),
-                            _ => unreachable!(),
-                        };
-                        edits.push((range.clone(), replace));
+                        edits.push((range.clone(), format_number(result, &num, radix)));
                     }
                     if selection.is_empty() {
                         new_anchors.push((false, snapshot.anchor_after(range.end)))
                     }
+                } else if let Some((range, word)) = selection
+                    .is_empty()
+                    .then(|| find_toggle(&snapshot, start, delta, cx))
+                    .flatten()
+                {
+                    // with no number to change, a word from `toggles` is cycled
+                    edits.push((range.clone(), word));
+                    new_anchors.push((false, snapshot.anchor_after(range.end)));
                 } else {
                     if selection.is_empty() {
                         new_anchors.push((true, snapshot.anchor_after(start)))
@@ -111,9 +109,101 @@ fn increment(vim: &mut Vim, mut delta: i32, step: i32, cx: &mut WindowContext) {
     vim.switch_mode(Mode::Normal, true, cx)
 }
 
+/// The word from the `toggles` setting (or the language's) that the cursor is on, and
+/// the word `delta` places along its group to replace it with. Where the cursor is on
+/// more than one, like `pub` of `pub(crate)`, the longest is taken.
+fn find_toggle(
+    snapshot: &MultiBufferSnapshot,
+    cursor: Point,
+    delta: i32,
+    cx: &AppContext,
+) -> Option<(Range<Point>, String)> {
+    let settings = VimSettings::get_global(cx);
+    let language_toggles = settings
+        .language_options(snapshot.language_at(cursor))
+        .and_then(|options| options.toggles.as_ref());
+    let line = snapshot
+        .text_for_range(
+            Point::new(cursor.row, 0)..Point::new(cursor.row, snapshot.line_len(cursor.row)),
+        )
+        .collect::<String>();
+    let column = cursor.column as usize;
+
+    let mut found: Option<(Range<usize>, &Vec<String>, usize)> = None;
+    for group in language_toggles
+        .into_iter()
+        .flatten()
+        .chain(&settings.toggles)
+    {
+        for (ix, word) in group
+            .iter()
+            .enumerate()
+            .filter(|(_, word)| !word.is_empty())
+        {
+            for (start, _) in line.match_indices(word.as_str()) {
+                let range = start..start + word.len();
+                if range.contains(&column)
+                    && is_whole_word(&line, range.clone())
+                    && found
+                        .as_ref()
+                        .map_or(true, |(found, _, _)| found.len() < range.len())
+                {
+                    found = Some((range, group, ix));
+                }
+            }
+        }
+    }
+    let (range, group, ix) = found?;
+    let next = (ix as i64 + delta as i64).rem_euclid(group.len() as i64) as usize;
+    Some((
+        Point::new(cursor.row, range.start as u32)..Point::new(cursor.row, range.end as u32),
+        group[next].clone(),
+    ))
+}
+
+/// Whether the text isn't part of a longer word, so `true` doesn't match in `untrue`.
+fn is_whole_word(line: &str, range: Range<usize>) -> bool {
+    let is_word = |c: char| c.is_alphanumeric() || c == '_';
+    let text = &line[range.clone()];
+    let joins_before = line[..range.start].chars().next_back().is_some_and(is_word)
+        && text.chars().next().is_some_and(is_word);
+    let joins_after = line[range.end..].chars().next().is_some_and(is_word)
+        && text.chars().next_back().is_some_and(is_word);
+    !joins_before && !joins_after
+}
+
+/// Like vim, a number with leading zeros (and any hex or binary number) keeps its
+/// width, so `0099` becomes `0100`, and `0x10` becomes `0x0f`.
+fn format_number(value: i32, original: &str, radix: u32) -> String {
+    let digits = original.trim_start_matches('-');
+    let width = if radix == 16 || radix == 2 || digits.starts_with('0') {
+        digits.len()
+    } else {
+        0
+    };
+    let sign = if value < 0 { "-" } else { "" };
+    match radix {
+        10 => format!("{sign}{:0width$}", value.unsigned_abs()),
+        8 => format!("{sign}{:0width$o}", value.unsigned_abs()),
+        16 => {
+            if original.to_ascii_lowercase() == original {
+                format!("{:0width$x}", value)
+            } else {
+                format!("{:0width$X}", value)
+            }
+        }
+        2 => format!("{:0width$b}", value),
+        _ => unreachable!(),
+    }
+}
+
+/// Finds the number the cursor is on, or the next one on its line. Which prefixes
+/// mark hex and binary numbers, and whether a leading zero makes one octal, depends
+/// on the `nrformats` setting.
 fn find_number(
     snapshot: &MultiBufferSnapshot,
     start: Point,
+    formats: &[NumberFormat],
 ) -> Option<(Range<Point>, String, u32)> {
     let mut offset = start.to_offset(snapshot);
 
@@ -134,12 +224,22 @@ fn find_number(
     let mut chars = snapshot.chars_at(offset).peekable();
     // find the next number on the line (may start after the original cursor position)
     while let Some(ch) = chars.next() {
-        if num == "0" && ch == 'b' && chars.peek().is_some() && chars.peek().unwrap().is_digit(2) {
+        if formats.contains(&NumberFormat::Bin)
+            && num == "0"
+            && ch == 'b'
+            && chars.peek().is_some()
+            && chars.peek().unwrap().is_digit(2)
+        {
             radix = 2;
             begin = None;
             num = String::new();
         }
-        if num == "0" && ch == 'x' && chars.peek().is_some() && chars.peek().unwrap().is_digit(16) {
+        if formats.contains(&NumberFormat::Hex)
+            && num == "0"
+            && ch == 'x'
+            && chars.peek().is_some()
+            && chars.peek().unwrap().is_digit(16)
+        {
             radix = 16;
             begin = None;
             num = String::new();
@@ -167,6 +267,14 @@ fn find_number(
     }
     if let Some(begin) = begin {
         let end = end.unwrap_or(offset);
+        if radix == 10
+            && formats.contains(&NumberFormat::Octal)
+            && num.len() > 1
+            && num.starts_with('0')
+            && num.chars().all(|ch| ch.is_digit(8))
+        {
+            radix = 8;
+        }
         Some((begin.to_point(snapshot)..end.to_point(snapshot), num, radix))
     } else {
         None
@@ -176,8 +284,13 @@ fn find_number(
 #[cfg(test)]
 mod test {
     use indoc::indoc;
+    use settings::SettingsStore;
 
-    use crate::test::NeovimBackedTestContext;
+    use crate::{
+        state::Mode,
+        test::{NeovimBackedTestContext, VimTestContext},
+        NumberFormat, VimSettings,
+    };
 
     #[gpui::test]
     async fn test_increment(cx: &mut gpui::TestAppContext) {
@@ -275,4 +388,78 @@ mod test {
             0"})
             .await;
     }
+
+    #[gpui::test]
+    async fn test_increment_nrformats(cx: &mut gpui::TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+
+        // the next number on the line is used when the cursor isn't on one
+        cx.set_state("ˇfoo 12 bar", Mode::Normal);
+        cx.simulate_keystrokes(["ctrl-a"]);
+        cx.assert_state("foo 1ˇ3 bar", Mode::Normal);
+
+        // leading zeros are kept, and don't make a number octal by default
+        cx.set_state("ˇ007", Mode::Normal);
+        cx.simulate_keystrokes(["ctrl-a"]);
+        cx.assert_state("00ˇ8", Mode::Normal);
+        cx.simulate_keystrokes(["9", "2", "ctrl-a"]);
+        cx.assert_state("10ˇ0", Mode::Normal);
+        cx.set_state("ˇ0x10", Mode::Normal);
+        cx.simulate_keystrokes(["ctrl-x"]);
+        cx.assert_state("0x0ˇf", Mode::Normal);
+
+        cx.update_global(|store: &mut SettingsStore, cx| {
+            store.update_user_settings::<VimSettings>(cx, |s| {
+                s.nrformats = Some(vec![NumberFormat::Octal])
+            });
+        });
+        cx.set_state("ˇ007", Mode::Normal);
+        cx.simulate_keystrokes(["ctrl-a"]);
+        cx.assert_state("01ˇ0", Mode::Normal);
+        // without hex, 0x10 is a 0 followed by x10
+        cx.set_state("ˇ0x10", Mode::Normal);
+        cx.simulate_keystrokes(["ctrl-a"]);
+        cx.assert_state("ˇ1x10", Mode::Normal);
+    }
+
+    #[gpui::test]
+    async fn test_increment_toggles(cx: &mut gpui::TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+
+        cx.set_state("let a = ˇtrue;", Mode::Normal);
+        cx.simulate_keystrokes(["ctrl-a"]);
+        cx.assert_state("let a = falsˇe;", Mode::Normal);
+        cx.simulate_keystrokes(["ctrl-x"]);
+        cx.assert_state("let a = truˇe;", Mode::Normal);
+        // only when there's no number to change
+        cx.set_state("assert_eq!(ˇtrue, 1);", Mode::Normal);
+        cx.simulate_keystrokes(["ctrl-a"]);
+        cx.assert_state("assert_eq!(true, ˇ2);", Mode::Normal);
+        // and only on the word itself, not within a longer one
+        cx.set_state("untrˇue", Mode::Normal);
+        cx.simulate_keystrokes(["ctrl-a"]);
+        cx.assert_state("untrˇue", Mode::Normal);
+        cx.set_state("untrˇue 1", Mode::Normal);
+        cx.simulate_keystrokes(["ctrl-a"]);
+        cx.assert_state("untrue ˇ2", Mode::Normal);
+
+        // the language's groups come first, and the longest word under the cursor wins
+        cx.set_state("ˇpub fn a() {}", Mode::Normal);
+        cx.simulate_keystrokes(["ctrl-a"]);
+        cx.assert_state("pub(crateˇ) fn a() {}", Mode::Normal);
+        cx.simulate_keystrokes(["ctrl-a"]);
+        cx.assert_state("puˇb fn a() {}", Mode::Normal);
+        cx.set_state("Sˇome(a)", Mode::Normal);
+        cx.simulate_keystrokes(["2", "ctrl-a"]);
+        cx.assert_state("Somˇe(a)", Mode::Normal);
+
+        cx.update_global(|store: &mut SettingsStore, cx| {
+            store.update_user_settings::<VimSettings>(cx, |s| {
+                s.toggles = Some(vec![vec!["left".into(), "right".into(), "center".into()]])
+            });
+        });
+        cx.set_state("ˇleft", Mode::Normal);
+        cx.simulate_keystrokes(["ctrl-x"]);
+        cx.assert_state("centeˇr", Mode::Normal);
+    }
 }
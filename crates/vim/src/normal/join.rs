@@ -0,0 +1,308 @@
+use editor::scroll::Autoscroll;
+use gpui::{ViewContext, WindowContext};
+use language::Point;
+use settings::Settings;
+use workspace::Workspace;
+
+use crate::{formatoptions::FormatOptions, state::Mode, Vim, VimSettings};
+
+use super::{rewrap::CommentStyle, JoinLines, JoinLinesNoWhitespace};
+
+pub(crate) fn register(workspace: &mut Workspace, _: &mut ViewContext<Workspace>) {
+    workspace.register_action(|_: &mut Workspace, _: &JoinLines, cx| {
+        Vim::update(cx, |vim, cx| join_lines(vim, true, cx))
+    });
+    workspace.register_action(|_: &mut Workspace, _: &JoinLinesNoWhitespace, cx| {
+        Vim::update(cx, |vim, cx| join_lines(vim, false, cx))
+    });
+}
+
+/// `J` joins count lines (at least two), or the lines of a visual selection. The next
+/// line's indent is removed, along with its comment leader when both lines are
+/// comments, and a space is put between the lines unless the first ends in whitespace
+/// or the next starts with a `)` (or two after a sentence, with `joinspaces`). `gJ`
+/// joins the lines as they are. The cursor is left where the last lines were joined.
+fn join_lines(vim: &mut Vim, whitespace: bool, cx: &mut WindowContext) {
+    vim.record_current_action(cx);
+    let mut times = vim.take_count(cx).unwrap_or(1);
+    if vim.state().mode.is_visual() {
+        times = 1;
+    } else if times > 1 {
+        // 2J joins two lines together (same as J or 1J)
+        times -= 1;
+    }
+    let joinspaces = VimSettings::get_global(cx).joinspaces;
+
+    vim.update_active_editor(cx, |_, editor, cx| {
+        let snapshot = editor.buffer().read(cx).snapshot(cx);
+        let max_row = snapshot.max_point().row;
+        let mut row_ranges = Vec::<(u32, u32)>::new();
+        for selection in editor.selections.all::<Point>(cx) {
+            let start = selection.start.row;
+            let end = if selection.start.row == selection.end.row {
+                start + times as u32
+            } else {
+                selection.end.row
+            }
+            .min(max_row);
+            if let Some(last) = row_ranges.last_mut() {
+                if start <= last.1 {
+                    last.1 = last.1.max(end);
+                    continue;
+                }
+            }
+            row_ranges.push((start, end));
+        }
+
+        let mut edits = Vec::new();
+        let mut cursors = Vec::new();
+        for (start, end) in row_ranges {
+            if start == end {
+                continue;
+            }
+            for row in start..end {
+                let end_of_line = Point::new(row, snapshot.line_len(row));
+                if !whitespace {
+                    edits.push((end_of_line..Point::new(row + 1, 0), ""));
+                    continue;
+                }
+                let line = line_text(&snapshot, row);
+                let next = line_text(&snapshot, row + 1);
+                let point = Point::new(row, 0);
+                let comments = if FormatOptions::at(&snapshot, point, cx).join_comments {
+                    CommentStyle::new(snapshot.language_scope_at(point))
+                } else {
+                    CommentStyle::default()
+                };
+                let in_block = comments.in_block(&snapshot, row);
+                let (leader_len, spaces) =
+                    join_point(&line, &next, &comments, in_block, joinspaces);
+                edits.push((end_of_line..Point::new(row + 1, leader_len as u32), spaces));
+            }
+            let join_point = Point::new(end - 1, snapshot.line_len(end - 1));
+            let anchor = snapshot.anchor_before(join_point);
+            cursors.push(anchor..anchor);
+        }
+        if edits.is_empty() {
+            return;
+        }
+
+        editor.transact(cx, |editor, cx| {
+            editor.edit(edits, cx);
+            editor.change_selections(Some(Autoscroll::fit()), cx, |s| {
+                s.select_anchor_ranges(cursors)
+            });
+        });
+    });
+    if vim.state().mode.is_visual() {
+        vim.switch_mode(Mode::Normal, false, cx)
+    }
+}
+
+fn line_text(snapshot: &editor::MultiBufferSnapshot, row: u32) -> String {
+    snapshot
+        .text_for_range(Point::new(row, 0)..Point::new(row, snapshot.line_len(row)))
+        .collect()
+}
+
+/// How `J` joins a line to the next one: how much of the start of the next line to
+/// remove (its indent, and its comment leader when both lines are comments), and the
+/// spaces to put between the lines. `in_block` is whether the line is inside a block
+/// comment.
+fn join_point(
+    line: &str,
+    next: &str,
+    comments: &CommentStyle,
+    in_block: bool,
+    joinspaces: bool,
+) -> (usize, &'static str) {
+    let is_comment = |line: &str, in_block| !comments.split(line, in_block).0.trim().is_empty();
+    let next_in_block = comments.continues_block(line, in_block);
+    let (next_leader, _, _) = comments.split(next, next_in_block);
+    let leader_len = if is_comment(line, in_block) && is_comment(next, next_in_block) {
+        next_leader.len()
+    } else {
+        next.len() - next.trim_start().len()
+    };
+
+    let rest = &next[leader_len..];
+    let spaces = match line.chars().last() {
+        _ if rest.is_empty() || rest.starts_with(')') => "",
+        None => "",
+        Some(c) if c.is_whitespace() => "",
+        Some('.' | '!' | '?') if joinspaces => "  ",
+        Some(_) => " ",
+    };
+    (leader_len, spaces)
+}
+
+#[cfg(test)]
+mod test {
+    use editor::test::editor_lsp_test_context::EditorLspTestContext;
+    use indoc::indoc;
+    use language::{Language, LanguageConfig, LanguageMatcher};
+    use settings::SettingsStore;
+
+    use std::sync::Arc;
+
+    use super::{join_point, CommentStyle};
+    use crate::{state::Mode, test::VimTestContext, VimSettings};
+
+    #[test]
+    fn test_join_point() {
+        let none = CommentStyle::default();
+        assert_eq!(join_point("one", "    two", &none, false, false), (4, " "));
+        assert_eq!(join_point("one", "  ", &none, false, false), (2, ""));
+        assert_eq!(join_point("", "two", &none, false, false), (0, ""));
+        assert_eq!(join_point("f(a", "  )", &none, false, false), (2, ""));
+        // multibyte whitespace counts as whitespace, at either end
+        assert_eq!(
+            join_point("one\u{3000}", "two", &none, false, false),
+            (0, "")
+        );
+        assert_eq!(
+            join_point("one", "\u{3000}two", &none, false, false),
+            (3, " ")
+        );
+        assert_eq!(join_point("end.", "next", &none, false, false), (0, " "));
+        assert_eq!(join_point("end.", "next", &none, false, true), (0, "  "));
+        // comment leaders are removed when both lines are comments
+        let language = Arc::new(Language::new(
+            LanguageConfig {
+                line_comments: vec!["// ".into()],
+                block_comment: Some(("/* ".into(), " */".into())),
+                ..Default::default()
+            },
+            None,
+        ));
+        let comments = CommentStyle::new(Some(language.default_scope()));
+        assert_eq!(
+            join_point("  // one", "  // two", &comments, false, false),
+            (5, " ")
+        );
+        assert_eq!(
+            join_point("/// one", "/// two", &comments, false, false),
+            (4, " ")
+        );
+        assert_eq!(
+            join_point("one", "// two", &comments, false, false),
+            (0, " ")
+        );
+        assert_eq!(
+            join_point("/* one", " * two", &comments, false, false),
+            (3, " ")
+        );
+        // a `*` only leads a line inside a block comment, and with a blank after it
+        assert_eq!(
+            join_point("a = 1;", "*b = 2;", &comments, false, false),
+            (0, " ")
+        );
+        assert_eq!(
+            join_point(" * one", " *two", &comments, true, false),
+            (1, " ")
+        );
+    }
+
+    #[gpui::test]
+    async fn test_join_lines_with_count_and_repeat(cx: &mut gpui::TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+
+        cx.set_state(
+            indoc! {"
+                ˇone
+                  two
+                three
+                four
+                five
+                six
+                seven"},
+            Mode::Normal,
+        );
+        cx.simulate_keystrokes(["3", "shift-j"]);
+        cx.assert_state(
+            indoc! {"
+                one twoˇ three
+                four
+                five
+                six
+                seven"},
+            Mode::Normal,
+        );
+        cx.simulate_keystrokes(["j", "."]);
+        cx.assert_state(
+            indoc! {"
+                one two three
+                four fiveˇ six
+                seven"},
+            Mode::Normal,
+        );
+        // a count past the end of the buffer joins the rest of it
+        cx.simulate_keystrokes(["9", "shift-j"]);
+        cx.assert_state(
+            indoc! {"
+                one two three
+                four five sixˇ seven"},
+            Mode::Normal,
+        );
+
+        cx.set_state("ˇone\n  two\nthree", Mode::Normal);
+        cx.simulate_keystrokes(["3", "g", "shift-j"]);
+        cx.assert_state("one  twoˇthree", Mode::Normal);
+    }
+
+    #[gpui::test]
+    async fn test_join_comments(cx: &mut gpui::TestAppContext) {
+        VimTestContext::init(cx);
+        let language = Language::new(
+            LanguageConfig {
+                name: "Rust".into(),
+                matcher: LanguageMatcher {
+                    path_suffixes: vec!["rs".to_string()],
+                    ..Default::default()
+                },
+                line_comments: vec!["// ".into()],
+                block_comment: Some(("/* ".into(), " */".into())),
+                ..Default::default()
+            },
+            None,
+        );
+        let lsp = EditorLspTestContext::new(language, Default::default(), cx).await;
+        let mut cx = VimTestContext::new_with_lsp(lsp, true);
+
+        cx.set_state(
+            indoc! {"
+                fn main() {
+                    ˇ// one
+                    // two
+                    /// three
+                }"},
+            Mode::Normal,
+        );
+        cx.simulate_keystrokes(["shift-j"]);
+        cx.assert_state(
+            indoc! {"
+                fn main() {
+                    // oneˇ two
+                    /// three
+                }"},
+            Mode::Normal,
+        );
+
+        // a dereference isn't a block comment's leader
+        cx.set_state("ˇa = 1;\n*b = 2;", Mode::Normal);
+        cx.simulate_keystrokes(["shift-j"]);
+        cx.assert_state("a = 1;ˇ *b = 2;", Mode::Normal);
+        cx.set_state("/* one\nˇ * two\n * three */", Mode::Normal);
+        cx.simulate_keystrokes(["shift-j"]);
+        cx.assert_state("/* one\n * twoˇ three */", Mode::Normal);
+
+        cx.update_global(|store: &mut SettingsStore, cx| {
+            store.update_user_settings::<VimSettings>(cx, |settings| {
+                settings.joinspaces = Some(true)
+            });
+        });
+        cx.set_state("ˇEnd.\nNext", Mode::Normal);
+        cx.simulate_keystrokes(["shift-j"]);
+        cx.assert_state("End.ˇ  Next", Mode::Normal);
+    }
+}
@@ -0,0 +1,181 @@
+use editor::Editor;
+use gpui::{actions, Action, ViewContext};
+use language::Point;
+use settings::Settings;
+use workspace::Workspace;
+
+use crate::{
+    completion::{keyword_chars, keywords},
+    messages::show_output,
+    state::Mode,
+    utils::{run_with_input, worktree_root},
+    Vim, VimSettings,
+};
+
+actions!(vim, [LookUpKeyword]);
+
+/// `K` shows the documentation of the keyword under the cursor (or of the selection in
+/// visual mode). That's the language server's hover, unless the `keywordprg` setting
+/// names a command for the language, like `man` for C: then the keyword is passed to
+/// the command and its output is shown in a split below.
+pub(crate) fn register(workspace: &mut Workspace, _: &mut ViewContext<Workspace>) {
+    workspace.register_action(look_up_keyword);
+}
+
+fn look_up_keyword(workspace: &mut Workspace, _: &LookUpKeyword, cx: &mut ViewContext<Workspace>) {
+    let Some(editor) = workspace.active_item_as::<Editor>(cx) else {
+        return;
+    };
+    let project = workspace.project().clone();
+    let (keyword, program, root) = editor.update(cx, |editor, cx| {
+        let buffer = editor.buffer().read(cx);
+        let snapshot = buffer.snapshot(cx);
+        let selection = editor.selections.newest::<Point>(cx);
+        let keyword = if selection.is_empty() || !Vim::read(cx).state().mode.is_visual() {
+            let cursor = selection.head();
+            let line = snapshot
+                .text_for_range(
+                    Point::new(cursor.row, 0)
+                        ..Point::new(cursor.row, snapshot.line_len(cursor.row)),
+                )
+                .collect::<String>();
+            let extra = keyword_chars(snapshot.language_at(cursor), cx);
+            keywords(&line, &extra)
+                .into_iter()
+                .find(|(start, word)| start + word.len() > cursor.column as usize)
+                .map(|(_, word)| word.to_string())
+        } else {
+            Some(
+                snapshot
+                    .text_for_range(selection.range())
+                    .collect::<String>(),
+            )
+        };
+        let program = snapshot
+            .language_at(selection.head())
+            .and_then(|language| {
+                VimSettings::get_global(cx)
+                    .keywordprg
+                    .get(language.name().as_ref())
+                    .cloned()
+            })
+            .filter(|program| !program.trim().is_empty());
+        let root = buffer
+            .as_singleton()
+            .and_then(|buffer| worktree_root(&buffer, &project, cx));
+        (keyword, program, root)
+    });
+
+    Vim::update(cx, |vim, cx| {
+        vim.clear_operator(cx);
+        if vim.state().mode.is_visual() {
+            vim.switch_mode(Mode::Normal, false, cx);
+        }
+    });
+    let Some(keyword) = keyword.filter(|keyword| !keyword.trim().is_empty()) else {
+        Vim::update(cx, |vim, _| {
+            vim.show_message("E349: No identifier under cursor")
+        });
+        return;
+    };
+    let Some(program) = program else {
+        cx.dispatch_action(editor::actions::Hover.boxed_clone());
+        return;
+    };
+
+    if Vim::update(cx, |vim, _| vim.refuse_pending_operation()) {
+        return;
+    }
+    // interrupting it (`ctrl-c`) kills the command
+    let command = keyword_command(&program, &keyword);
+    let task = cx.spawn(|workspace, mut cx| async move {
+        let output = run_with_input(&command, "", root.as_deref()).await;
+        workspace
+            .update(&mut cx, |workspace, cx| {
+                Vim::update(cx, |vim, _| vim.finish_pending_operation());
+                let output = match output {
+                    Ok(output) => output,
+                    Err(error) => {
+                        return Vim::update(cx, |vim, _| vim.show_message(error.to_string()));
+                    }
+                };
+                let text = String::from_utf8_lossy(&output.stdout).to_string();
+                if text.trim().is_empty() {
+                    let error = String::from_utf8_lossy(&output.stderr);
+                    let message = error
+                        .lines()
+                        .next()
+                        .map(str::to_string)
+                        .unwrap_or_else(|| format!("E149: Sorry, no help for {keyword}"));
+                    Vim::update(cx, |vim, _| vim.show_message(message));
+                    return;
+                }
+                show_output(workspace, &text, cx);
+            })
+            .ok();
+    });
+    Vim::update(cx, |vim, _| vim.pending_operation = Some(task));
+}
+
+/// The command line `K` runs: the program followed by the keyword, quoted for the shell.
+fn keyword_command(program: &str, keyword: &str) -> String {
+    format!("{} '{}'", program.trim(), keyword.replace('\'', r"'\''"))
+}
+
+#[cfg(test)]
+mod test {
+    use futures::StreamExt;
+    use settings::SettingsStore;
+
+    use super::keyword_command;
+    use crate::{state::Mode, test::VimTestContext, Vim, VimSettings};
+
+    #[test]
+    fn test_keyword_command() {
+        assert_eq!(keyword_command("man 3", "printf"), "man 3 'printf'");
+        assert_eq!(keyword_command("man", "it's"), r"man 'it'\''s'");
+    }
+
+    #[gpui::test]
+    async fn test_look_up_keyword_with_hover(cx: &mut gpui::TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+
+        // without a keywordprg for the language, K asks the language server
+        cx.set_state("fn ˇmain() {}", Mode::Normal);
+        let mut requests = cx
+            .lsp
+            .handle_request::<lsp::request::HoverRequest, _, _>(|_, _| async move { Ok(None) });
+        cx.simulate_keystrokes(["shift-k"]);
+        cx.run_until_parked();
+        assert!(requests.next().await.is_some());
+        cx.assert_state("fn ˇmain() {}", Mode::Normal);
+
+        cx.set_state("ˇ  ", Mode::Normal);
+        cx.simulate_keystrokes(["shift-k"]);
+        assert_eq!(
+            cx.message().as_deref(),
+            Some("E349: No identifier under cursor")
+        );
+    }
+
+    #[gpui::test]
+    async fn test_look_up_keyword_with_program(cx: &mut gpui::TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+        cx.update_global(|store: &mut SettingsStore, cx| {
+            store.update_user_settings::<VimSettings>(cx, |s| {
+                s.keywordprg = Some(
+                    [("Rust".to_string(), "echo doc:".to_string())]
+                        .into_iter()
+                        .collect(),
+                )
+            });
+        });
+
+        cx.set_state("fn ˇmain() {}", Mode::Normal);
+        cx.simulate_keystrokes(["shift-k"]);
+        cx.run_until_operation_finishes();
+        let last_output =
+            cx.update_global(|vim: &mut Vim, _| vim.workspace_state.last_output.clone());
+        assert_eq!(last_output.as_deref(), Some("doc: main\n"));
+    }
+}
@@ -0,0 +1,212 @@
+use std::sync::Arc;
+
+use gpui::{impl_actions, ViewContext, WindowContext};
+use serde_derive::Deserialize;
+use workspace::Workspace;
+
+use crate::{
+    motion::{self, Motion},
+    Vim,
+};
+
+/// `:delm[arks] {marks}` deletes the named marks (`a-d` is a range of them), and
+/// `:delm[arks]!` deletes all of them.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub(crate) struct DeleteMarks {
+    marks: String,
+    all: bool,
+}
+
+impl_actions!(vim, [DeleteMarks]);
+
+pub(crate) fn register(workspace: &mut Workspace, _: &mut ViewContext<Workspace>) {
+    workspace.register_action(|_: &mut Workspace, action: &DeleteMarks, cx| {
+        Vim::update(cx, |vim, cx| {
+            vim.clear_operator(cx);
+            let names = match parse_mark_names(&action.marks) {
+                Ok(names) => names,
+                Err(message) => return vim.show_message(message),
+            };
+            if action.all && !names.is_empty() {
+                return vim.show_message("E474: Invalid argument");
+            } else if !action.all && names.is_empty() {
+                return vim.show_message("E471: Argument required");
+            }
+            vim.update_state(|state| {
+                state
+                    .marks
+                    .retain(|name, _| !action.all && !names.contains(name))
+            });
+            vim.sync_vim_settings(cx);
+        })
+    });
+}
+
+/// `m{a-z}` sets a mark at the cursor.
+pub(crate) fn create_mark(vim: &mut Vim, text: Arc<str>, cx: &mut WindowContext) {
+    let name = text.chars().next().filter(char::is_ascii_lowercase);
+    let anchor =
+        vim.update_active_editor(cx, |_, editor, _| editor.selections.newest_anchor().head());
+    if let Some((name, anchor)) = name.zip(anchor) {
+        vim.update_state(|state| state.marks.insert(name, anchor));
+    }
+    vim.clear_operator(cx);
+}
+
+/// `'{a-z}` jumps to the first non-blank of the mark's line, and `` `{a-z} `` to
+/// the mark itself. Either can follow an operator.
+pub(crate) fn jump_to_mark(text: Arc<str>, line: bool, cx: &mut WindowContext) {
+    let anchor = Vim::update(cx, |vim, cx| {
+        vim.pop_operator(cx);
+        let anchor = text
+            .chars()
+            .next()
+            .and_then(|name| vim.state().marks.get(&name).copied());
+        if anchor.is_none() {
+            vim.clear_operator(cx);
+            vim.show_message("E20: Mark not set");
+        }
+        anchor
+    });
+    if let Some(anchor) = anchor {
+        motion::motion(Motion::Jump { anchor, line }, cx)
+    }
+}
+
+pub(crate) fn parse_delete_marks(query: &str) -> Option<DeleteMarks> {
+    let name_len = query
+        .find(|c: char| !c.is_ascii_alphabetic())
+        .unwrap_or(query.len());
+    let (name, rest) = query.split_at(name_len);
+    if !matches!(name, "delm" | "delma" | "delmar" | "delmark" | "delmarks") {
+        return None;
+    }
+    let (all, marks) = match rest.strip_prefix('!') {
+        Some(marks) => (true, marks),
+        None => (false, rest),
+    };
+    Some(DeleteMarks {
+        marks: marks.trim().to_string(),
+        all,
+    })
+}
+
+/// The marks named by `:delmarks`' argument, like `a b` or `a-dx`.
+fn parse_mark_names(marks: &str) -> Result<Vec<char>, String> {
+    let chars = marks
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .collect::<Vec<_>>();
+    let mut names = Vec::new();
+    let mut ix = 0;
+    while ix < chars.len() {
+        let start = chars[ix];
+        let end = if chars.get(ix + 1) == Some(&'-') {
+            ix += 2;
+            chars.get(ix).copied().unwrap_or('-')
+        } else {
+            start
+        };
+        if !start.is_ascii_lowercase() || !end.is_ascii_lowercase() || end < start {
+            return Err(format!("E475: Invalid argument: {}", &marks));
+        }
+        names.extend(start..=end);
+        ix += 1;
+    }
+    Ok(names)
+}
+
+#[cfg(test)]
+mod test {
+    use indoc::indoc;
+    use settings::SettingsStore;
+    use ui::ActiveTheme;
+
+    use crate::{state::Mode, test::VimTestContext, VimSettings};
+
+    fn mark_signs(cx: &mut VimTestContext) -> Vec<(u32, String)> {
+        cx.update_editor(|editor, cx| {
+            let snapshot = editor.snapshot(cx);
+            editor
+                .gutter_signs_in_range(0..u32::MAX, &snapshot.display_snapshot, cx.theme().colors())
+                .into_iter()
+                .map(|(row, (text, _))| (row, text))
+                .collect()
+        })
+    }
+
+    #[gpui::test]
+    async fn test_marks(cx: &mut gpui::TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+
+        cx.set_state(
+            indoc! {"
+                one
+                  twˇo
+                three"},
+            Mode::Normal,
+        );
+        cx.simulate_keystrokes(["m", "a", "j", "m", "b", "g", "g"]);
+        cx.simulate_keystrokes(["'", "a"]);
+        cx.assert_state(
+            indoc! {"
+                one
+                  ˇtwo
+                three"},
+            Mode::Normal,
+        );
+        cx.simulate_keystrokes(["`", "b"]);
+        cx.assert_state(
+            indoc! {"
+                one
+                  two
+                threˇe"},
+            Mode::Normal,
+        );
+        cx.simulate_keystrokes(["d", "'", "a"]);
+        cx.assert_state("ˇone", Mode::Normal);
+
+        cx.simulate_keystrokes(["`", "c"]);
+        assert_eq!(cx.message().as_deref(), Some("E20: Mark not set"));
+    }
+
+    #[gpui::test]
+    async fn test_delete_marks(cx: &mut gpui::TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+        cx.update_global(|store: &mut SettingsStore, cx| {
+            store.update_user_settings::<VimSettings>(cx, |s| s.show_marks_in_gutter = Some(true));
+        });
+
+        cx.set_state("ˇa\nb\nc\nd", Mode::Normal);
+        cx.simulate_keystrokes(["m", "a", "j", "m", "b", "j", "m", "c", "j", "m", "x"]);
+        assert_eq!(
+            mark_signs(&mut cx),
+            vec![
+                (0, "a".to_string()),
+                (1, "b".to_string()),
+                (2, "c".to_string()),
+                (3, "x".to_string())
+            ]
+        );
+
+        cx.simulate_keystrokes([":"]);
+        cx.simulate_input("delm a-b");
+        cx.simulate_keystrokes(["enter"]);
+        assert_eq!(
+            mark_signs(&mut cx),
+            vec![(2, "c".to_string()), (3, "x".to_string())]
+        );
+        cx.simulate_keystrokes(["'", "a"]);
+        assert_eq!(cx.message().as_deref(), Some("E20: Mark not set"));
+
+        cx.simulate_keystrokes([":"]);
+        cx.simulate_input("delmarks!");
+        cx.simulate_keystrokes(["enter"]);
+        assert_eq!(mark_signs(&mut cx), vec![]);
+
+        cx.simulate_keystrokes([":"]);
+        cx.simulate_input("delm");
+        cx.simulate_keystrokes(["enter"]);
+        assert_eq!(cx.message().as_deref(), Some("E471: Argument required"));
+    }
+}
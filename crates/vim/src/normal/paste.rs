@@ -1,15 +1,20 @@
-use std::cmp;
+use std::{cmp, ops::Range};
 
 use editor::{
     display_map::ToDisplayPoint, movement, scroll::Autoscroll, ClipboardSelection, DisplayPoint,
+    Editor,
 };
-use gpui::{impl_actions, AppContext, ViewContext};
-use language::{Bias, SelectionGoal};
+use gpui::{impl_actions, ViewContext};
+use language::{Bias, IndentSize, Point, SelectionGoal};
 use serde::Deserialize;
 use settings::Settings;
 use workspace::Workspace;
 
-use crate::{state::Mode, utils::copy_selections_content, UseSystemClipboard, Vim, VimSettings};
+use crate::{
+    state::{Mode, RegisterKind},
+    utils::{char_width, copy_selections_content, screen_width},
+    MulticursorRegisters, UseSystemClipboard, Vim, VimSettings,
+};
 
 #[derive(Clone, Deserialize, PartialEq)]
 #[serde(rename_all = "camelCase")]
@@ -20,55 +25,282 @@ struct Paste {
     preserve_clipboard: bool,
 }
 
-impl_actions!(vim, [Paste]);
+/// `]p` and `[p` (or `]P` and `[P`) put a register's lines below or above the cursor's
+/// line, indented to fit there. Other registers are put like `p` and `P`.
+#[derive(Clone, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+struct PutIndented {
+    #[serde(default)]
+    before: bool,
+}
+
+/// `:[line]put [x]` puts the text of a register as lines below the line (or the
+/// cursor's), whatever kind of register it is, and `:put!` puts them above it.
+/// `:0put` puts them above the first line.
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+pub(crate) struct PutLines {
+    pub register: Option<char>,
+    pub before: bool,
+    /// The line, counting from 1, or the cursor's line
+    pub line: Option<u32>,
+}
+
+impl_actions!(vim, [Paste, PutIndented, PutLines]);
 
 pub(crate) fn register(workspace: &mut Workspace, _: &mut ViewContext<Workspace>) {
     workspace.register_action(paste);
+    workspace.register_action(put_indented);
+    workspace.register_action(|_: &mut Workspace, action: &PutLines, cx| {
+        Vim::update(cx, |vim, cx| {
+            let register = action.register.unwrap_or('"');
+            let Some(text) = vim
+                .workspace_state
+                .read_register(register)
+                .filter(|text| !text.is_empty())
+                .cloned()
+            else {
+                vim.show_message(format!("E353: Nothing in register {register}"));
+                return;
+            };
+            vim.switch_mode(Mode::Normal, false, cx);
+            vim.update_active_editor(cx, |_, editor, cx| {
+                let line = action
+                    .line
+                    .unwrap_or_else(|| editor.selections.newest::<Point>(cx).head().row + 1);
+                let row = if action.before {
+                    line.saturating_sub(1)
+                } else {
+                    line
+                };
+                editor.transact(cx, |editor, cx| {
+                    let rows = insert_lines(editor, row, &text, cx);
+                    // like vim, the cursor goes to the last line put
+                    move_to_first_non_blank(editor, rows.end - 1, cx);
+                });
+            });
+        })
+    });
 }
 
-fn system_clipboard_is_newer(vim: &Vim, cx: &mut AppContext) -> bool {
-    cx.read_from_clipboard().is_some_and(|item| {
-        if let Some(last_state) = vim.workspace_state.registers.get(".system.") {
-            last_state != item.text()
-        } else {
-            true
-        }
-    })
+fn put_indented(workspace: &mut Workspace, action: &PutIndented, cx: &mut ViewContext<Workspace>) {
+    let vim = Vim::read(cx);
+    let register = vim.workspace_state.selected_register.unwrap_or('"');
+    let linewise = vim.workspace_state.register_kind(register) == RegisterKind::Linewise;
+    let text = vim
+        .workspace_state
+        .read_register(register)
+        .filter(|_| linewise && !vim.state().mode.is_visual())
+        .cloned();
+    let Some(text) = text else {
+        let paste_action = Paste {
+            before: action.before,
+            preserve_clipboard: false,
+        };
+        return paste(workspace, &paste_action, cx);
+    };
+
+    Vim::update(cx, |vim, cx| {
+        vim.record_current_action(cx);
+        vim.workspace_state.selected_register.take();
+        let count = vim.take_count(cx).unwrap_or(1);
+        vim.update_active_editor(cx, |_, editor, cx| {
+            editor.transact(cx, |editor, cx| {
+                let cursor_row = editor.selections.newest::<Point>(cx).head().row;
+                let indent = editor
+                    .buffer()
+                    .read(cx)
+                    .snapshot(cx)
+                    .indent_size_for_line(cursor_row);
+                let row = if action.before {
+                    cursor_row
+                } else {
+                    cursor_row + 1
+                };
+                let rows = insert_lines(editor, row, &text.repeat(count), cx);
+                reindent_lines(editor, rows.clone(), indent, cx);
+                move_to_first_non_blank(editor, rows.start, cx);
+            });
+        });
+    });
+}
+
+/// Puts the text as whole lines above the row (or below the last line, when the row
+/// is past it), returning the rows they're on.
+fn insert_lines(
+    editor: &mut Editor,
+    row: u32,
+    text: &str,
+    cx: &mut ViewContext<Editor>,
+) -> Range<u32> {
+    let text = text.strip_suffix('\n').unwrap_or(text);
+    let line_count = text.split('\n').count() as u32;
+    let max_point = editor.buffer().read(cx).snapshot(cx).max_point();
+    if row > max_point.row {
+        editor.edit([(max_point..max_point, format!("\n{text}"))], cx);
+    } else {
+        let start = Point::new(row, 0);
+        editor.edit([(start..start, format!("{text}\n"))], cx);
+    }
+    let row = row.min(max_point.row + 1);
+    row..row + line_count
+}
+
+/// Shifts the lines so that the first non-blank one has the indent the language's
+/// indents query suggests for it, or where it has none, the indent of the line the
+/// cursor was on (`indent`). The lines keep their indentation relative to each other.
+fn reindent_lines(
+    editor: &mut Editor,
+    rows: Range<u32>,
+    indent: IndentSize,
+    cx: &mut ViewContext<Editor>,
+) {
+    let snapshot = editor.buffer().read(cx).snapshot(cx);
+    let Some(first) = rows.clone().find(|row| !snapshot.is_line_blank(*row)) else {
+        return;
+    };
+    let has_indents_query = snapshot
+        .language_at(Point::new(first, 0))
+        .and_then(|language| language.grammar())
+        .is_some_and(|grammar| grammar.has_indents_query());
+    let target = has_indents_query
+        .then(|| snapshot.suggested_indents([first], cx).remove(&first))
+        .flatten()
+        .unwrap_or(indent);
+    let current = snapshot.indent_size_for_line(first);
+    if current == target {
+        return;
+    }
+
+    let edits = rows
+        .filter(|row| !snapshot.is_line_blank(*row))
+        .map(|row| {
+            let old = snapshot.indent_size_for_line(row);
+            let len = (old.len + target.len).saturating_sub(current.len);
+            let new_indent = target.char().to_string().repeat(len as usize);
+            (Point::new(row, 0)..Point::new(row, old.len), new_indent)
+        })
+        .collect::<Vec<_>>();
+    editor.edit(edits, cx);
+}
+
+fn move_to_first_non_blank(editor: &mut Editor, row: u32, cx: &mut ViewContext<Editor>) {
+    let snapshot = editor.buffer().read(cx).snapshot(cx);
+    let point = Point::new(row, snapshot.indent_size_for_line(row).len);
+    editor.change_selections(Some(Autoscroll::fit()), cx, |s| {
+        s.select_ranges([point..point])
+    });
+}
+
+/// `:put`, `:put!`, and either with a register, as in `:put a`.
+pub(crate) fn parse_put(query: &str) -> Option<PutLines> {
+    let name_len = query
+        .find(|c: char| !c.is_ascii_alphabetic())
+        .unwrap_or(query.len());
+    let (name, args) = query.split_at(name_len);
+    if !matches!(name, "pu" | "put") {
+        return None;
+    }
+    let (before, args) = match args.strip_prefix('!') {
+        Some(args) => (true, args),
+        None => (false, args),
+    };
+    let mut register = args.trim().chars();
+    let line = PutLines {
+        register: register.next(),
+        before,
+        line: None,
+    };
+    register.next().is_none().then_some(line)
 }
 
 fn paste(_: &mut Workspace, action: &Paste, cx: &mut ViewContext<Workspace>) {
     Vim::update(cx, |vim, cx| {
         vim.record_current_action(cx);
+        let count = vim.take_count(cx).unwrap_or(1);
         vim.update_active_editor(cx, |vim, editor, cx| {
             let text_layout_details = editor.text_layout_details(cx);
             editor.transact(cx, |editor, cx| {
                 editor.set_clip_at_line_ends(false, cx);
 
-                let (clipboard_text, clipboard_selections): (String, Option<_>) =
-                    if VimSettings::get_global(cx).use_system_clipboard == UseSystemClipboard::Never
-                        || VimSettings::get_global(cx).use_system_clipboard
-                            == UseSystemClipboard::OnYank
-                            && !system_clipboard_is_newer(vim, cx)
-                    {
+                let register = vim
+                    .workspace_state
+                    .selected_register
+                    .take()
+                    .filter(|register| *register != '"');
+                let setting = VimSettings::get_global(cx).use_system_clipboard;
+                let per_cursor = VimSettings::get_global(cx).multicursor_registers
+                    == MulticursorRegisters::PerCursor
+                    && vim.state().mode != Mode::VisualLine;
+                // a register yanked into with multiple cursors puts the text from
+                // each cursor at the matching one, when there are as many, as Zed's
+                // paste does
+                let cursor_count = editor.selections.count();
+                let register_selections = |vim: &Vim, register: char| {
+                    let kind = vim.workspace_state.register_kind(register);
+                    vim.workspace_state
+                        .register_pieces(register)
+                        .filter(|pieces| per_cursor && pieces.len() == cursor_count)
+                        .map(|pieces| {
+                            pieces
+                                .iter()
+                                .map(|piece| ClipboardSelection {
+                                    len: piece.len,
+                                    is_entire_line: kind == RegisterKind::Linewise,
+                                    first_line_indent: piece.first_line_indent,
+                                })
+                                .collect::<Vec<_>>()
+                        })
+                };
+                let (clipboard_text, clipboard_selections, kind): (String, Option<_>, _) =
+                    if let Some(register) = register {
                         (
                             vim.workspace_state
-                                .registers
-                                .get("\"")
+                                .read_register(register)
                                 .cloned()
-                                .unwrap_or_else(|| "".to_string()),
-                            None,
+                                .unwrap_or_default(),
+                            register_selections(vim, register),
+                            vim.workspace_state.register_kind(register),
                         )
                     } else {
-                        if let Some(item) = cx.read_from_clipboard() {
-                            let clipboard_selections = item
-                                .metadata::<Vec<ClipboardSelection>>()
-                                .filter(|clipboard_selections| {
-                                    clipboard_selections.len() > 1
-                                        && vim.state().mode != Mode::VisualLine
-                                });
-                            (item.text().clone(), clipboard_selections)
-                        } else {
-                            ("".into(), None)
+                        // the system clipboard can be slow to read, so it's read once,
+                        // and only when it might be put
+                        let clipboard = match setting {
+                            UseSystemClipboard::Never => None,
+                            _ => cx.read_from_clipboard(),
+                        };
+                        let clipboard_is_newer = clipboard.as_ref().is_some_and(|item| {
+                            vim.workspace_state.system_clipboard_is_newer(item.text())
+                        });
+                        match clipboard {
+                            Some(item)
+                                if setting == UseSystemClipboard::Always || clipboard_is_newer =>
+                            {
+                                let clipboard_selections = item
+                                    .metadata::<Vec<ClipboardSelection>>()
+                                    .filter(|clipboard_selections| {
+                                        clipboard_selections.len() > 1 && per_cursor
+                                    });
+                                // the clipboard still holds vim's last yank, so it's put
+                                // the same way
+                                let kind = if clipboard_is_newer {
+                                    RegisterKind::Charwise
+                                } else {
+                                    vim.workspace_state.register_kind('"')
+                                };
+                                (item.text().clone(), clipboard_selections, kind)
+                            }
+                            None if setting == UseSystemClipboard::Always => {
+                                ("".into(), None, RegisterKind::Charwise)
+                            }
+                            _ => (
+                                vim.workspace_state
+                                    .registers
+                                    .get("\"")
+                                    .cloned()
+                                    .unwrap_or_else(|| "".to_string()),
+                                register_selections(vim, '"'),
+                                vim.workspace_state.register_kind('"'),
+                            ),
                         }
                     };
 
@@ -76,6 +308,11 @@ fn paste(_: &mut Workspace, action: &Paste, cx: &mut ViewContext<Workspace>) {
                     return;
                 }
 
+                if kind == RegisterKind::Blockwise && !vim.state().mode.is_visual() {
+                    paste_block(editor, &clipboard_text, action.before, count, cx);
+                    return;
+                }
+
                 if !action.preserve_clipboard && vim.state().mode.is_visual() {
                     copy_selections_content(vim, editor, vim.state().mode == Mode::VisualLine, cx);
                 }
@@ -135,6 +372,8 @@ fn paste(_: &mut Workspace, action: &Paste, cx: &mut ViewContext<Workspace>) {
                         } else {
                             (clipboard_text.to_string(), first_selection_indent_column)
                         };
+                    // like vim, `3p` puts three copies
+                    to_insert = to_insert.repeat(count);
                     let line_mode = to_insert.ends_with('\n');
                     let is_multiline = to_insert.contains('\n');
 
@@ -223,12 +462,154 @@ fn paste(_: &mut Workspace, action: &Paste, cx: &mut ViewContext<Workspace>) {
     });
 }
 
+/// Puts a blockwise register's lines one below the other, at the cursor's screen
+/// column (or after the character under it), so that they line up on screen
+/// whatever tabs or wide characters the lines hold. Lines that end before the
+/// column are padded with spaces, and a tab the column falls inside of is split
+/// into spaces, as in vim.
+fn paste_block(
+    editor: &mut Editor,
+    text: &str,
+    before: bool,
+    count: usize,
+    cx: &mut ViewContext<Editor>,
+) {
+    let snapshot = editor.buffer().read(cx).snapshot(cx);
+    let line = |row: u32| {
+        snapshot
+            .text_for_range(Point::new(row, 0)..Point::new(row, snapshot.line_len(row)))
+            .collect::<String>()
+    };
+    let cursor = editor.selections.newest::<Point>(cx).head();
+    let tab_size = snapshot.settings_at(cursor, cx).tab_size.get() as usize;
+    let first_line = line(cursor.row);
+    let mut column = screen_width(&first_line[..cursor.column as usize], tab_size);
+    if !before {
+        if let Some(c) = first_line[cursor.column as usize..].chars().next() {
+            column += char_width(c, column, tab_size);
+        }
+    }
+
+    let mut pieces = text.split('\n').map(str::to_string).collect::<Vec<_>>();
+    let mut width = pieces
+        .iter()
+        .map(|piece| screen_width(piece, tab_size))
+        .max()
+        .unwrap_or_default();
+    // with a count, the copies are put side by side, each padded to the block's width
+    if count > 1 {
+        for piece in &mut pieces {
+            let padded = format!(
+                "{piece}{}",
+                " ".repeat(width - screen_width(piece, tab_size))
+            );
+            *piece = padded.repeat(count - 1) + piece.as_str();
+        }
+        width *= count;
+    }
+    let mut edits = Vec::new();
+    let mut new_lines = String::new();
+    for (ix, piece) in pieces.into_iter().enumerate() {
+        let row = cursor.row + ix as u32;
+        if row > snapshot.max_point().row {
+            new_lines.push('\n');
+            new_lines.push_str(&" ".repeat(column));
+            new_lines.push_str(&piece);
+            continue;
+        }
+        let line = line(row);
+        let padding = " ".repeat(width - screen_width(&piece, tab_size));
+        let (range, text) = match offset_at_screen_column(&line, column, tab_size) {
+            ScreenOffset::Past { width } => {
+                (line.len()..line.len(), " ".repeat(column - width) + &piece)
+            }
+            ScreenOffset::At(offset) if offset == line.len() => (offset..offset, piece.clone()),
+            ScreenOffset::At(offset) => (offset..offset, format!("{piece}{padding}")),
+            ScreenOffset::InTab {
+                offset,
+                before,
+                after,
+            } => (
+                offset..offset + 1,
+                format!(
+                    "{}{piece}{padding}{}",
+                    " ".repeat(before),
+                    " ".repeat(after)
+                ),
+            ),
+        };
+        edits.push((
+            Point::new(row, range.start as u32)..Point::new(row, range.end as u32),
+            text,
+        ));
+    }
+    if !new_lines.is_empty() {
+        let end = snapshot.max_point();
+        edits.push((end..end, new_lines));
+    }
+    editor.edit(edits, cx);
+
+    // the cursor goes to the top left of the block
+    let snapshot = editor.buffer().read(cx).snapshot(cx);
+    let first_line = snapshot
+        .text_for_range(
+            Point::new(cursor.row, 0)..Point::new(cursor.row, snapshot.line_len(cursor.row)),
+        )
+        .collect::<String>();
+    let offset = match offset_at_screen_column(&first_line, column, tab_size) {
+        ScreenOffset::At(offset) | ScreenOffset::InTab { offset, .. } => offset,
+        ScreenOffset::Past { .. } => first_line.len(),
+    };
+    let point = Point::new(cursor.row, offset as u32);
+    editor.change_selections(Some(Autoscroll::fit()), cx, |s| {
+        s.select_ranges([point..point])
+    });
+}
+
+/// Where a screen column falls in a line.
+enum ScreenOffset {
+    /// At the start of the character at this offset, or at the end of the line
+    At(usize),
+    /// Inside of the tab at this offset, this many columns after the start of the
+    /// tab and before its end
+    InTab {
+        offset: usize,
+        before: usize,
+        after: usize,
+    },
+    /// After the end of the line, which is this many columns wide
+    Past { width: usize },
+}
+
+fn offset_at_screen_column(line: &str, column: usize, tab_size: usize) -> ScreenOffset {
+    let mut start = 0;
+    for (offset, c) in line.char_indices() {
+        if start >= column {
+            return ScreenOffset::At(offset);
+        }
+        let end = start + char_width(c, start, tab_size);
+        if c == '\t' && column < end {
+            return ScreenOffset::InTab {
+                offset,
+                before: column - start,
+                after: end - column,
+            };
+        }
+        start = end;
+    }
+    if start >= column {
+        ScreenOffset::At(line.len())
+    } else {
+        ScreenOffset::Past { width: start }
+    }
+}
+
 #[cfg(test)]
 mod test {
     use crate::{
         state::Mode,
         test::{NeovimBackedTestContext, VimTestContext},
-        UseSystemClipboard, VimSettings,
+        MulticursorRegisters, UseSystemClipboard, VimSettings,
     };
     use gpui::ClipboardItem;
     use indoc::indoc;
@@ -357,6 +738,31 @@ mod test {
         assert_eq!(cx.read_from_clipboard(), None);
     }
 
+    #[gpui::test]
+    async fn test_native_copy_and_cut(cx: &mut gpui::TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+
+        cx.update_global(|store: &mut SettingsStore, cx| {
+            store.update_user_settings::<VimSettings>(cx, |s| {
+                s.use_system_clipboard = Some(UseSystemClipboard::Never)
+            });
+        });
+
+        // zed copies the whole line when nothing is selected, which p puts linewise
+        cx.set_state("ˇone\ntwo\nthree", Mode::Normal);
+        cx.simulate_keystrokes(["cmd-c", "j", "p"]);
+        cx.assert_state("one\ntwo\nˇone\nthree", Mode::Normal);
+        assert_eq!(cx.read_register('0').as_deref(), Some("one\n"));
+
+        cx.simulate_keystrokes(["k", "cmd-x", "g", "g", "shift-p"]);
+        cx.assert_state("ˇtwo\none\none\nthree", Mode::Normal);
+        assert_eq!(cx.read_register('1').as_deref(), Some("two\n"));
+
+        // a register given beforehand is used
+        cx.simulate_keystrokes(["\"", "a", "cmd-c"]);
+        assert_eq!(cx.read_register('a').as_deref(), Some("two\n"));
+    }
+
     #[gpui::test]
     async fn test_yank_system_clipboard_on_yank(cx: &mut gpui::TestAppContext) {
         let mut cx = VimTestContext::new(cx, true).await;
@@ -416,6 +822,46 @@ mod test {
                 test-copˇyfox jjumpsumps over"},
             Mode::Normal,
         );
+
+        // a delete after copying outside of vim is put instead of the clipboard
+        cx.write_to_clipboard(ClipboardItem::new("other-copy".to_string()));
+        cx.simulate_keystrokes(["k", "d", "d", "p"]);
+        cx.assert_state(
+            indoc! {"
+                The quick brown
+                test-copyfox jjumpsumps over
+                ˇthe lazy dog"},
+            Mode::Normal,
+        );
+        // but a copy made after vim last looked at the clipboard is put
+        cx.write_to_clipboard(ClipboardItem::new("third-copy".to_string()));
+        cx.simulate_keystroke("shift-p");
+        cx.assert_state(
+            indoc! {"
+                The quick brown
+                test-copyfox jjumpsumps over
+                third-copˇythe lazy dog"},
+            Mode::Normal,
+        );
+
+        // text copied in another app after a delete is put
+        cx.simulate_keystrokes(["d", "d"]);
+        cx.write_to_clipboard(ClipboardItem::new("fourth-copy".to_string()));
+        cx.simulate_keystroke("p");
+        cx.assert_state(
+            indoc! {"
+                The quick brown
+                tfourth-copˇyest-copyfox jjumpsumps over"},
+            Mode::Normal,
+        );
+        // while a delete after it is put
+        cx.simulate_keystrokes(["d", "d", "p"]);
+        cx.assert_state(
+            indoc! {"
+                The quick brown
+                ˇtfourth-copyest-copyfox jjumpsumps over"},
+            Mode::Normal,
+        );
     }
 
     #[gpui::test]
@@ -555,6 +1001,90 @@ mod test {
             .await;
     }
 
+    #[gpui::test]
+    async fn test_paste_block(cx: &mut gpui::TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+
+        cx.set_state("ˇab\ncd", Mode::Normal);
+        cx.simulate_keystrokes(["ctrl-v", "j", "l", "y"]);
+
+        // the block is put as a column, with lines added at the end of the buffer
+        cx.simulate_keystrokes(["j", "$", "p"]);
+        cx.assert_state("ab\ncdˇab\n  cd", Mode::Normal);
+
+        cx.set_state("ˇxy\nz", Mode::Normal);
+        cx.simulate_keystrokes(["p"]);
+        cx.assert_state("xˇaby\nzcd", Mode::Normal);
+
+        // short lines are padded with spaces
+        cx.set_state("ˇxyz\n\nq", Mode::Normal);
+        cx.simulate_keystrokes(["$", "p"]);
+        cx.assert_state("xyzˇab\n   cd\nq", Mode::Normal);
+
+        // as are the lines of the block, when there is text after it
+        cx.set_state("ˇa\nbcd", Mode::Normal);
+        cx.simulate_keystrokes(["ctrl-v", "j", "l", "l", "\"", "x", "y"]);
+        cx.set_state("ˇ12\n34", Mode::Normal);
+        cx.simulate_keystrokes(["\"", "x", "shift-p"]);
+        cx.assert_state("ˇa  12\nbcd34", Mode::Normal);
+    }
+
+    #[gpui::test]
+    async fn test_paste_block_screen_columns(cx: &mut gpui::TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+
+        // the block lines up on screen with the character under the cursor,
+        // which is after a tab on one line and after four characters on the next
+        cx.set_state("ˇAB\nCD\n\tx\n12345y", Mode::Normal);
+        cx.simulate_keystrokes(["ctrl-v", "j", "l", "y", "j", "j", "l", "shift-p"]);
+        cx.assert_state("AB\nCD\n\tˇABx\n1234CD5y", Mode::Normal);
+
+        // a tab the column falls inside of is split into spaces
+        cx.set_state("ˇab\ncd", Mode::Normal);
+        cx.simulate_keystrokes(["ctrl-v", "j", "y"]);
+        cx.set_state("12ˇ3\n\tx", Mode::Normal);
+        cx.simulate_keystrokes(["p"]);
+        cx.assert_state("123ˇa\n   c x", Mode::Normal);
+
+        // wide characters take two columns
+        cx.set_state("日ˇx\nabc", Mode::Normal);
+        cx.simulate_keystrokes(["shift-p"]);
+        cx.assert_state("日ˇax\nabcc", Mode::Normal);
+
+        // and deleting a block takes the same columns from each line
+        cx.set_state("\tˇabc\n1234abcd", Mode::Normal);
+        cx.simulate_keystrokes(["ctrl-v", "j", "l", "d"]);
+        cx.assert_state("\tˇc\n1234cd", Mode::Normal);
+    }
+
+    #[gpui::test]
+    async fn test_paste_multiple_cursors(cx: &mut gpui::TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+
+        // each cursor puts what was yanked at the matching one
+        cx.set_state("ˇa b\nˇc d", Mode::Normal);
+        cx.simulate_keystrokes(["y", "i", "w", "$", "p"]);
+        cx.assert_state("a bˇa\nc dˇc", Mode::Normal);
+        cx.set_state("ˇa b\nˇc d", Mode::Normal);
+        cx.simulate_keystrokes(["\"", "x", "y", "i", "w", "$", "\"", "x", "p"]);
+        cx.assert_state("a bˇa\nc dˇc", Mode::Normal);
+
+        // unless there are more or fewer cursors
+        cx.set_state("ˇx", Mode::Normal);
+        cx.simulate_keystrokes(["\"", "x", "p"]);
+        cx.assert_state("xˇa\nc", Mode::Normal);
+
+        // or the setting says all of it is put at each cursor
+        cx.update_global(|store: &mut SettingsStore, cx| {
+            store.update_user_settings::<VimSettings>(cx, |settings| {
+                settings.multicursor_registers = Some(MulticursorRegisters::Shared)
+            });
+        });
+        cx.set_state("ˇa b\nˇc d", Mode::Normal);
+        cx.simulate_keystrokes(["y", "i", "w", "$", "p"]);
+        cx.assert_state("a bˇa\nc\nc dˇa\nc", Mode::Normal);
+    }
+
     #[gpui::test]
     async fn test_paste_indent(cx: &mut gpui::TestAppContext) {
         let mut cx = VimTestContext::new_typescript(cx).await;
@@ -599,4 +1129,173 @@ mod test {
             Mode::Normal,
         );
     }
+
+    #[gpui::test]
+    async fn test_paste_count(cx: &mut gpui::TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+
+        cx.set_state("ˇone two", Mode::Normal);
+        cx.simulate_keystrokes(["y", "w", "3", "p"]);
+        cx.assert_state("oone one oneˇ ne two", Mode::Normal);
+
+        cx.set_state("ˇa\nb", Mode::Normal);
+        cx.simulate_keystrokes(["y", "y", "2", "p"]);
+        cx.assert_state("a\nˇa\na\nb", Mode::Normal);
+
+        // the copies of a block are put side by side
+        cx.set_state("ˇab\ncd", Mode::Normal);
+        cx.simulate_keystrokes(["ctrl-v", "j", "y", "2", "p"]);
+        cx.assert_state("aˇaab\ncccd", Mode::Normal);
+    }
+
+    #[gpui::test]
+    async fn test_put_lines(cx: &mut gpui::TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+
+        // even characters are put as lines
+        cx.set_state("ˇone\ntwo", Mode::Normal);
+        cx.simulate_keystrokes(["y", "w", ":"]);
+        cx.simulate_input("put");
+        cx.simulate_keystrokes(["enter"]);
+        cx.assert_state("one\nˇone\ntwo", Mode::Normal);
+
+        cx.simulate_keystrokes([":"]);
+        cx.simulate_input("$put");
+        cx.simulate_keystrokes(["enter"]);
+        cx.assert_state("one\none\ntwo\nˇone", Mode::Normal);
+        cx.simulate_keystrokes([":"]);
+        cx.simulate_input("2put!");
+        cx.simulate_keystrokes(["enter"]);
+        cx.assert_state("one\nˇone\none\ntwo\none", Mode::Normal);
+        cx.simulate_keystrokes([":"]);
+        cx.simulate_input("0put");
+        cx.simulate_keystrokes(["enter"]);
+        cx.assert_state("ˇone\none\none\none\ntwo\none", Mode::Normal);
+
+        cx.simulate_keystrokes([":"]);
+        cx.simulate_input("put a");
+        cx.simulate_keystrokes(["enter"]);
+        assert_eq!(cx.message().as_deref(), Some("E353: Nothing in register a"));
+    }
+
+    #[gpui::test]
+    async fn test_put_indented(cx: &mut gpui::TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+
+        // the lines are indented as the language suggests, keeping their shape
+        cx.set_state(
+            indoc! {"
+                fn a() {
+                }
+                fn b() {
+                        ˇif x {
+                            y();
+                        }
+                }"},
+            Mode::Normal,
+        );
+        cx.simulate_keystrokes(["y", "2", "j", "g", "g", "]", "p"]);
+        cx.assert_state(
+            indoc! {"
+                fn a() {
+                    ˇif x {
+                        y();
+                    }
+                }
+                fn b() {
+                        if x {
+                            y();
+                        }
+                }"},
+            Mode::Normal,
+        );
+
+        // characters are put like p
+        cx.set_state("ˇone two", Mode::Normal);
+        cx.simulate_keystrokes(["y", "w", "]", "p"]);
+        cx.assert_state("ooneˇ ne two", Mode::Normal);
+    }
+
+    #[gpui::test]
+    async fn test_numbered_registers(cx: &mut gpui::TestAppContext) {
+        let mut cx = NeovimBackedTestContext::new(cx).await;
+
+        cx.set_shared_state(indoc! {"
+            ˇone
+            two
+            three
+            four five
+            six"})
+            .await;
+        // deleting lines shifts the numbered registers
+        cx.simulate_shared_keystrokes(["d", "d", "d", "d", "\"", "1", "p", "\"", "2", "p"])
+            .await;
+        cx.assert_shared_state(indoc! {"
+            three
+            two
+            ˇone
+            four five
+            six"})
+            .await;
+
+        // yanks go in "0, and small deletes in "-
+        cx.simulate_shared_keystrokes(["y", "y", "j", "d", "w", "\"", "0", "shift-p"])
+            .await;
+        cx.simulate_shared_keystrokes(["\"", "-", "p"]).await;
+        cx.assert_shared_state(indoc! {"
+            three
+            two
+            one
+            ofourˇ ne
+            five
+            six"})
+            .await;
+
+        // a named register still gets the big delete in "1, but not a small one in "-
+        cx.simulate_shared_keystrokes(["\"", "a", "d", "d", "\"", "b", "x"])
+            .await;
+        cx.simulate_shared_keystrokes(["\"", "1", "p", "\"", "-", "p", "\"", "3", "p"])
+            .await;
+        cx.assert_shared_state(indoc! {"
+            three
+            two
+            one
+            ive
+            ofour four ne
+            ˇone
+            six"})
+            .await;
+        cx.simulate_shared_keystrokes(["\"", "a", "p", "\"", "b", "p", "p"])
+            .await;
+        cx.assert_shared_state(indoc! {"
+            three
+            two
+            one
+            ive
+            ofour four ne
+            one
+            ofˇffour ne
+            six"})
+            .await;
+    }
+
+    #[gpui::test]
+    async fn test_named_registers(cx: &mut gpui::TestAppContext) {
+        let mut cx = NeovimBackedTestContext::new(cx).await;
+
+        cx.set_shared_state("ˇone two three").await;
+        cx.simulate_shared_keystrokes(["\"", "a", "y", "w", "w"])
+            .await;
+        // uppercase appends, and "" holds the whole register
+        cx.simulate_shared_keystrokes(["\"", "shift-a", "y", "w", "w"])
+            .await;
+        // the black hole register keeps "" as it was
+        cx.simulate_shared_keystrokes(["\"", "_", "d", "w"]).await;
+        cx.assert_shared_state("one twoˇ ").await;
+        cx.simulate_shared_keystrokes(["p"]).await;
+        cx.assert_shared_state("one two one twoˇ ").await;
+        cx.simulate_shared_keystrokes(["0", "\"", "a", "shift-p"])
+            .await;
+        cx.assert_shared_state("one twoˇ one two one two ").await;
+    }
 }
@@ -0,0 +1,85 @@
+use gpui::{actions, PromptLevel, ViewContext};
+use workspace::{SaveIntent, Workspace};
+
+actions!(vim, [CloseWithoutWriting]);
+
+pub(crate) fn register(workspace: &mut Workspace, _: &mut ViewContext<Workspace>) {
+    workspace.register_action(close_without_writing);
+}
+
+/// `ZQ` closes the current buffer, throwing away any changes. Like vim it doesn't ask,
+/// unless this is the last buffer open, where a slip of the finger loses work with
+/// nothing left on screen to show for it.
+fn close_without_writing(
+    workspace: &mut Workspace,
+    _: &CloseWithoutWriting,
+    cx: &mut ViewContext<Workspace>,
+) {
+    let pane = workspace.active_pane().clone();
+    let Some(item) = pane.read(cx).active_item() else {
+        return;
+    };
+    let open_items: usize = workspace
+        .panes()
+        .iter()
+        .map(|pane| pane.read(cx).items_len())
+        .sum();
+    let needs_confirmation = open_items == 1 && item.is_dirty(cx);
+
+    cx.spawn(|_, mut cx| async move {
+        if needs_confirmation {
+            let answer = pane.update(&mut cx, |_, cx| {
+                cx.prompt(
+                    PromptLevel::Warning,
+                    "Discard your changes?",
+                    Some("This is the last open buffer."),
+                    &["Discard", "Cancel"],
+                )
+            })?;
+            if !matches!(answer.await, Ok(0)) {
+                return Ok(());
+            }
+        }
+        pane.update(&mut cx, |pane, cx| {
+            pane.close_item_by_id(item.item_id(), SaveIntent::Skip, cx)
+        })?
+        .await
+    })
+    .detach_and_log_err(cx);
+}
+
+#[cfg(test)]
+mod test {
+    use crate::test::VimTestContext;
+
+    #[gpui::test]
+    async fn test_close_last_buffer_without_writing(cx: &mut gpui::TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+
+        cx.simulate_keystrokes(["i", "@", "escape"]);
+        cx.simulate_keystrokes(["shift-z", "shift-q"]);
+        assert!(cx.has_pending_prompt());
+        // "Cancel"
+        cx.simulate_prompt_answer(1);
+        cx.run_until_parked();
+        cx.workspace(|workspace, cx| assert_eq!(workspace.items(cx).count(), 1));
+
+        cx.simulate_keystrokes(["shift-z", "shift-q"]);
+        // "Discard"
+        cx.simulate_prompt_answer(0);
+        cx.run_until_parked();
+        cx.workspace(|workspace, cx| assert_eq!(workspace.items(cx).count(), 0));
+    }
+
+    #[gpui::test]
+    async fn test_close_without_writing(cx: &mut gpui::TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+
+        cx.simulate_keystrokes([":", "n", "e", "w", "enter"]);
+        cx.simulate_keystrokes(["i", "@", "escape"]);
+        cx.simulate_keystrokes(["shift-z", "shift-q"]);
+        assert!(!cx.has_pending_prompt());
+        cx.run_until_parked();
+        cx.workspace(|workspace, cx| assert_eq!(workspace.items(cx).count(), 1));
+    }
+}
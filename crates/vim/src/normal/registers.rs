@@ -0,0 +1,207 @@
+use gpui::{impl_actions, AppContext, ViewContext};
+use serde_derive::Deserialize;
+use workspace::Workspace;
+
+use crate::{
+    messages::show_output,
+    state::{RegisterKind, WorkspaceState},
+    Vim,
+};
+
+/// `:reg[isters] {names}` (or `:di[splay]`) lists the registers, or just the named
+/// ones, with the kind of text each holds.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub(crate) struct ShowRegisters {
+    names: String,
+}
+
+/// Sets a register, so that key bindings can fill one with text of a given kind:
+/// `["vim::SetRegister", { "register": "a", "text": "fn ", "kind": "linewise" }]`.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub(crate) struct SetRegister {
+    register: char,
+    text: String,
+    #[serde(default)]
+    kind: RegisterKind,
+}
+
+impl_actions!(vim, [ShowRegisters, SetRegister]);
+
+pub(crate) fn register(workspace: &mut Workspace, _: &mut ViewContext<Workspace>) {
+    workspace.register_action(show_registers);
+    workspace.register_action(|_: &mut Workspace, action: &SetRegister, cx| {
+        Vim::update(cx, |vim, _| {
+            vim.workspace_state
+                .set_register(action.register, action.text.clone(), action.kind)
+        })
+    });
+}
+
+/// Sets a register to text of the given kind, converting the text to suit it (so a
+/// linewise register's text ends with a newline).
+pub fn set_register(register: char, text: String, kind: RegisterKind, cx: &mut AppContext) {
+    if cx.has_global::<Vim>() {
+        cx.update_global(|vim: &mut Vim, _| vim.workspace_state.set_register(register, text, kind))
+    }
+}
+
+fn show_registers(
+    workspace: &mut Workspace,
+    action: &ShowRegisters,
+    cx: &mut ViewContext<Workspace>,
+) {
+    let names = action
+        .names
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .collect::<Vec<_>>();
+    let text = Vim::update(cx, |vim, cx| {
+        vim.clear_operator(cx);
+        registers_text(&vim.workspace_state, &names)
+    });
+    show_output(workspace, &text, cx);
+}
+
+/// The registers as vim's `:registers` shows them, one per line after a header.
+fn registers_text(state: &WorkspaceState, names: &[char]) -> String {
+    let mut text = "Type Name Content".to_string();
+    for name in "\"0123456789abcdefghijklmnopqrstuvwxyz-:".chars() {
+        if !names.is_empty() && !names.contains(&name) {
+            continue;
+        }
+        let Some(content) = state.read_register(name).filter(|text| !text.is_empty()) else {
+            continue;
+        };
+        let kind = match state.register_kind(name) {
+            RegisterKind::Charwise => 'c',
+            RegisterKind::Linewise => 'l',
+            RegisterKind::Blockwise => 'b',
+        };
+        text.push_str(&format!("\n  {kind}  \"{name}   "));
+        push_register_content(&mut text, content.chars());
+    }
+    text
+}
+
+/// How much of a register the status bar shows while it's selected with `"`.
+const PREVIEW_LEN: usize = 30;
+
+/// The start of what the register holds, as `:registers` shows it, which the
+/// status bar shows after `"{register}` until the command it's for is typed.
+pub(crate) fn register_preview(state: &WorkspaceState, name: char) -> Option<String> {
+    let content = state.read_register(name).filter(|text| !text.is_empty())?;
+    let mut preview = String::new();
+    push_register_content(&mut preview, content.chars().take(PREVIEW_LEN));
+    if content.chars().nth(PREVIEW_LEN).is_some() {
+        preview.push('…');
+    }
+    Some(preview)
+}
+
+fn push_register_content(text: &mut String, content: impl Iterator<Item = char>) {
+    for c in content {
+        // control characters are shown like ^J (for a newline)
+        if c.is_ascii_control() {
+            text.push('^');
+            text.push((c as u8 ^ 0x40) as char);
+        } else {
+            text.push(c);
+        }
+    }
+}
+
+pub(crate) fn parse_show_registers(query: &str) -> Option<ShowRegisters> {
+    let name_len = query
+        .find(|c: char| !c.is_ascii_alphabetic())
+        .unwrap_or(query.len());
+    let (name, names) = query.split_at(name_len);
+    if !matches!(
+        name,
+        "reg"
+            | "regi"
+            | "regis"
+            | "regist"
+            | "registe"
+            | "register"
+            | "registers"
+            | "di"
+            | "dis"
+            | "disp"
+            | "displ"
+            | "displa"
+            | "display"
+    ) {
+        return None;
+    }
+    Some(ShowRegisters {
+        names: names.trim().to_string(),
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use editor::Editor;
+
+    use crate::{
+        state::{Mode, RegisterKind},
+        test::VimTestContext,
+    };
+
+    #[gpui::test]
+    async fn test_show_registers(cx: &mut gpui::TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+
+        cx.set_state("ˇone\ntwo\tthree", Mode::Normal);
+        cx.simulate_keystrokes(["\"", "a", "y", "y", "j", "\"", "b", "y", "w"]);
+        cx.simulate_keystrokes(["ctrl-v", "l", "\"", "c", "y"]);
+        cx.simulate_keystrokes([":"]);
+        cx.simulate_input("reg abc");
+        cx.simulate_keystrokes(["enter"]);
+        cx.run_until_parked();
+        cx.workspace(|workspace, cx| {
+            let editor = workspace.active_item_as::<Editor>(cx).unwrap();
+            assert_eq!(
+                editor.read(cx).text(cx),
+                "Type Name Content\n  l  \"a   one^J\n  c  \"b   two^I\n  b  \"c   tw"
+            );
+        });
+    }
+
+    #[gpui::test]
+    async fn test_set_register(cx: &mut gpui::TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+
+        // the text is converted to suit the kind, and appending keeps linewise text on
+        // lines of its own
+        cx.update(|cx| {
+            crate::set_register('a', "one".to_string(), crate::RegisterKind::Linewise, cx);
+            crate::set_register('A', "two".to_string(), crate::RegisterKind::Charwise, cx);
+        });
+        assert_eq!(cx.read_register('a').as_deref(), Some("one\ntwo\n"));
+
+        cx.set_state("ˇx", Mode::Normal);
+        cx.simulate_keystrokes(["\"", "a", "p"]);
+        cx.assert_state("x\nˇone\ntwo", Mode::Normal);
+    }
+
+    #[gpui::test]
+    async fn test_macro_registers(cx: &mut gpui::TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+
+        // a recorded macro's keys are the register's text
+        cx.set_state("ˇone two three", Mode::Normal);
+        cx.simulate_keystrokes(["q", "a", "d", "w", "i", "x", "escape", "q"]);
+        cx.assert_state("ˇxtwo three", Mode::Normal);
+        assert_eq!(cx.read_register('a').as_deref(), Some("dwix<Esc>"));
+
+        // and text set in a register is typed as keys when it's replayed
+        cx.update(|cx| crate::set_register('b', "A!<Esc>".to_string(), RegisterKind::Charwise, cx));
+        cx.simulate_keystrokes(["@", "b"]);
+        cx.assert_state("xtwo threeˇ!", Mode::Normal);
+
+        // which replaces the macro that was recorded in it
+        cx.update(|cx| crate::set_register('a', "x".to_string(), RegisterKind::Charwise, cx));
+        cx.simulate_keystrokes(["0", "@", "a"]);
+        cx.assert_state("ˇtwo three!", Mode::Normal);
+    }
+}
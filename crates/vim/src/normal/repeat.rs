@@ -1,14 +1,23 @@
+use std::{cell::RefCell, collections::VecDeque, mem, rc::Rc};
+
 use crate::{
+    command::ExCommand,
     insert::NormalBefore,
+    key_notation::parse_key_notation,
     motion::Motion,
-    state::{Mode, RecordedSelection, ReplayableAction},
+    normal::search::Search,
+    rename::RenameSymbol,
+    state::{Mode, Operator, RecordedSelection, ReplayableAction, WorkspaceState},
+    utils::{end_undo_group, start_undo_group},
     visual::visual_motion,
     Vim,
 };
-use gpui::{actions, Action, ViewContext, WindowContext};
+use command_palette_hooks::ToggleWithQuery;
+use editor::Editor;
+use gpui::{actions, Action, Keystroke, ViewContext, WeakView, WindowContext};
 use workspace::Workspace;
 
-actions!(vim, [Repeat, EndRepeat]);
+actions!(vim, [Repeat, EndRepeat, ToggleRecord]);
 
 fn should_replay(action: &Box<dyn Action>) -> bool {
     // skip so that we don't leave the character palette open
@@ -18,6 +27,24 @@ fn should_replay(action: &Box<dyn Action>) -> bool {
     true
 }
 
+/// Whether the action works the completion menu in insert mode. These aren't
+/// recorded for `.` or macros: the text the completion inserted is, so that
+/// replaying doesn't depend on what the menu offers at the time.
+pub(crate) fn is_completion_menu_action(action: &dyn Action, mode: Mode) -> bool {
+    match action.name() {
+        "editor::ContextMenuFirst"
+        | "editor::ContextMenuLast"
+        | "editor::ContextMenuNext"
+        | "editor::ContextMenuPrev"
+        | "editor::ConfirmCompletion"
+        | "editor::ComposeCompletion"
+        | "editor::ShowCompletions" => true,
+        // escape is only bound to `editor::Cancel` in insert mode while a menu is open
+        "editor::Cancel" => mode == Mode::Insert,
+        _ => false,
+    }
+}
+
 fn repeatable_insert(action: &ReplayableAction) -> Option<Box<dyn Action>> {
     match action {
         ReplayableAction::Action(action) => {
@@ -35,7 +62,7 @@ fn repeatable_insert(action: &ReplayableAction) -> Option<Box<dyn Action>> {
                 None
             }
         }
-        ReplayableAction::Insertion { .. } => None,
+        ReplayableAction::Insertion { .. } | ReplayableAction::Keystroke(_) => None,
     }
 }
 
@@ -48,6 +75,369 @@ pub(crate) fn register(workspace: &mut Workspace, _: &mut ViewContext<Workspace>
     });
 
     workspace.register_action(|_: &mut Workspace, _: &Repeat, cx| repeat(cx, false));
+
+    // `q` stops a recording (and isn't recorded itself, as the recording has
+    // stopped by the time the keystroke is observed), or else waits for the
+    // register to start one in.
+    workspace.register_action(|_: &mut Workspace, _: &ToggleRecord, cx| {
+        Vim::update(cx, |vim, cx| {
+            // nothing a replay does is recorded, so a recording it started would
+            // be empty, and one it stopped would be missing the rest of the replay.
+            if vim.is_replaying() {
+                vim.clear_operator(cx);
+                vim.show_message("Can't start or stop recording while a macro is replayed");
+                vim.abort_replay();
+                return;
+            }
+            if let Some(register) = vim.workspace_state.recording_register.take() {
+                let recorded = mem::take(&mut vim.workspace_state.recorded_macro);
+                let keys = mem::take(&mut vim.workspace_state.recorded_keys);
+                vim.workspace_state.store_macro(register, recorded, keys);
+                vim.workspace_state.recording_prompt = false;
+                vim.clear_operator(cx);
+            } else {
+                vim.push_operator(Operator::RecordRegister, cx)
+            }
+        })
+    });
+}
+
+/// A step of a replay: a recorded action, a keystroke (for `:normal`), or
+/// a callback (for `:g`, to move to the next matching line).
+pub(crate) enum ReplayStep {
+    Replay(ReplayableAction),
+    Keystroke(Keystroke),
+    Run(Box<dyn FnOnce(&mut WindowContext)>),
+}
+
+/// Runs the steps of a replay one at a time, so that each step sees the
+/// effects of the one before it. Steps queued while a replay is running
+/// (a macro that runs another one, or `:g` running a macro on each line)
+/// run before the remaining steps.
+#[derive(Clone, Default)]
+pub struct Replayer(Rc<RefCell<ReplayerState>>);
+
+#[derive(Default)]
+struct ReplayerState {
+    steps: VecDeque<ReplayStep>,
+    running: bool,
+    /// Set while a step waits for something, like the matches of a search
+    paused: bool,
+    /// Set when the next step was due while paused, so resuming runs it
+    stalled: bool,
+    /// Set while the replay lets the editor draw and handle input, between batches
+    /// of steps
+    yielded: bool,
+    replayed: usize,
+    on_finish: Vec<Box<dyn FnOnce(&mut WindowContext)>>,
+}
+
+// a macro that runs itself only stops when one of its motions fails (or ctrl-c
+// is pressed), so give up eventually.
+const MAX_REPLAYED_STEPS: usize = 1_000_000;
+
+// how many steps run before the editor gets to draw and handle input, so that long
+// replays (like `1000@q`) don't freeze it
+const STEPS_PER_BATCH: usize = 100;
+
+impl Replayer {
+    pub(crate) fn is_running(&self) -> bool {
+        self.0.borrow().running
+    }
+
+    /// Drops the remaining steps, as vim does when a command in a macro fails.
+    pub(crate) fn abort(&self) {
+        self.0.borrow_mut().steps.clear();
+    }
+
+    /// Stops the replay when `ctrl-c` is pressed, and returns whether it did. Only
+    /// a replay waiting between steps is stopped, as otherwise the `ctrl-c` was
+    /// replayed (from a macro that leaves insert mode with it).
+    pub(crate) fn interrupt(&self) -> bool {
+        let mut state = self.0.borrow_mut();
+        if !state.yielded && !state.paused {
+            return false;
+        }
+        state.steps.clear();
+        true
+    }
+
+    /// Holds back the next step until `resume` is called.
+    pub(crate) fn pause(&self) {
+        self.0.borrow_mut().paused = true;
+    }
+
+    pub(crate) fn resume(&self, cx: &mut WindowContext) {
+        let stalled = {
+            let mut state = self.0.borrow_mut();
+            state.paused = false;
+            mem::take(&mut state.stalled)
+        };
+        if stalled {
+            self.clone().next(cx);
+        }
+    }
+
+    /// Runs the callback once the replay has finished, or has been aborted.
+    pub(crate) fn on_finish(&self, callback: impl FnOnce(&mut WindowContext) + 'static) {
+        self.0.borrow_mut().on_finish.push(Box::new(callback));
+    }
+
+    fn queue(&self, steps: Vec<ReplayStep>) {
+        let mut state = self.0.borrow_mut();
+        for step in steps.into_iter().rev() {
+            state.steps.push_front(step);
+        }
+    }
+
+    fn next(self, cx: &mut WindowContext) {
+        let step = {
+            let mut state = self.0.borrow_mut();
+            if state.paused {
+                state.stalled = true;
+                return;
+            }
+            state.running = true;
+            state.yielded = false;
+            state.replayed += 1;
+            if state.replayed > MAX_REPLAYED_STEPS {
+                log::error!("aborting replay after {MAX_REPLAYED_STEPS} steps");
+                state.steps.clear();
+            }
+            state.steps.pop_front()
+        };
+
+        let Some(step) = step else {
+            Vim::update(cx, |vim, _| vim.workspace_state.replayer.take());
+            let on_finish = mem::take(&mut self.0.borrow_mut().on_finish);
+            for callback in on_finish {
+                callback(cx);
+            }
+            return;
+        };
+
+        match step {
+            ReplayStep::Replay(ReplayableAction::Action(action)) => {
+                if should_replay(&action) {
+                    cx.dispatch_action(action.boxed_clone());
+                    // replayed actions aren't observed as keystrokes, but
+                    // should still be recorded for `.`
+                    cx.defer(move |cx| Vim::update(cx, |vim, _| vim.observe_action(action)));
+                }
+            }
+            ReplayStep::Replay(ReplayableAction::Insertion {
+                text,
+                utf16_range_to_replace,
+            }) => {
+                let editor = Vim::read(cx)
+                    .active_editor
+                    .clone()
+                    .and_then(|editor| editor.upgrade());
+                if let Some(editor) = editor {
+                    editor.update(cx, |editor, cx| {
+                        editor.replay_insert_event(&text, utf16_range_to_replace, cx)
+                    })
+                }
+            }
+            ReplayStep::Keystroke(keystroke)
+            | ReplayStep::Replay(ReplayableAction::Keystroke(keystroke)) => {
+                cx.dispatch_keystroke(keystroke);
+            }
+            ReplayStep::Run(callback) => callback(cx),
+        }
+
+        if self.0.borrow().replayed % STEPS_PER_BATCH != 0 {
+            cx.defer(move |cx| self.next(cx));
+            return;
+        }
+        // once the step's action has run, let the editor draw and handle input
+        // before the next batch
+        cx.defer(move |cx| {
+            self.0.borrow_mut().yielded = true;
+            cx.spawn(|mut cx| async move { cx.update(|cx| self.next(cx)).ok() })
+                .detach();
+        });
+    }
+}
+
+/// Replays the steps. If a replay is already running, they run before its
+/// remaining steps.
+pub(crate) fn replay(steps: Vec<ReplayStep>, cx: &mut WindowContext) -> Replayer {
+    if let Some(replayer) = Vim::read(cx).workspace_state.replayer.clone() {
+        replayer.queue(steps);
+        return replayer;
+    }
+
+    let replayer = Replayer::default();
+    replayer.queue(steps);
+    // completions popping up while replaying would take the keys meant for the
+    // buffer, so they're kept closed until the replay is over.
+    let editor = Vim::update(cx, |vim, cx| {
+        vim.workspace_state.replayer = Some(replayer.clone());
+        vim.update_active_editor(cx, |_, editor, cx| {
+            editor.set_show_completions_on_input(Some(false));
+            editor.dismiss_menus_and_popups(true, cx);
+            cx.view().downgrade()
+        })
+    });
+    if let Some(editor) = editor {
+        replayer.on_finish(move |cx| {
+            editor
+                .update(cx, |editor, _| editor.set_show_completions_on_input(None))
+                .ok();
+        });
+    }
+    let next = replayer.clone();
+    cx.defer(move |cx| next.next(cx));
+    replayer
+}
+
+/// Records an action typed while a macro is being recorded. The keys typed into the
+/// command palette after `:`, or into the search bar after `/`, aren't recorded
+/// (and neither is the completion or history they used): the action the palette
+/// runs (an ex command or any other) is recorded by `record_palette_action`, and the
+/// search handler records the search that was submitted, so that replaying the
+/// macro runs them again rather than reopening an empty prompt.
+pub(crate) fn record_macro_action(vim: &mut Vim, action: &dyn Action, cx: &mut WindowContext) {
+    if is_completion_menu_action(action, vim.state().mode) {
+        return;
+    }
+    if action.name() == "command_palette::Toggle"
+        || action.as_any().is::<ToggleWithQuery>()
+        || action.as_any().is::<Search>()
+        || action.as_any().is::<RenameSymbol>()
+    {
+        vim.workspace_state.recording_prompt = true;
+        return;
+    }
+    if vim.workspace_state.recording_prompt {
+        if matches!(
+            action.name(),
+            "menu::Confirm" | "menu::Cancel" | "buffer_search::Dismiss" | "vim::SearchSubmit"
+        ) {
+            // the command picked in the palette is dispatched after this, and
+            // should still be recorded.
+            cx.defer(|cx| Vim::update(cx, |vim, _| vim.workspace_state.recording_prompt = false));
+        }
+        return;
+    }
+    vim.workspace_state
+        .recorded_macro
+        .push(ReplayableAction::Action(action.boxed_clone()));
+}
+
+/// Records the action the command palette runs while a macro is being recorded.
+pub(crate) fn record_palette_action(action: &dyn Action, cx: &mut WindowContext) {
+    Vim::update(cx, |vim, _| {
+        if vim.workspace_state.recording_register.is_some() && !vim.is_replaying() {
+            vim.workspace_state
+                .recorded_macro
+                .push(ReplayableAction::Action(action.boxed_clone()));
+        }
+    });
+}
+
+/// Starts recording a macro into the register (`q{register}`). Recording into
+/// an uppercase register appends to the lowercase one.
+pub(crate) fn record_register(register: char, cx: &mut WindowContext) {
+    Vim::update(cx, |vim, cx| {
+        vim.clear_operator(cx);
+        if !register.is_ascii_alphanumeric() && register != '"' {
+            return;
+        }
+        let name = register.to_ascii_lowercase();
+        (
+            vim.workspace_state.recorded_macro,
+            vim.workspace_state.recorded_keys,
+        ) = if register.is_ascii_uppercase() {
+            (
+                macro_in_register(&vim.workspace_state, name).unwrap_or_default(),
+                vim.workspace_state
+                    .read_register(name)
+                    .cloned()
+                    .unwrap_or_default(),
+            )
+        } else {
+            Default::default()
+        };
+        vim.workspace_state.recording_register = Some(name);
+        vim.workspace_state.recording_prompt = false;
+        vim.workspace_state.ignore_current_insertion = true;
+        vim.sync_vim_settings(cx);
+    })
+}
+
+/// The macro recorded in a register, or else the keys in its text, written in vim's
+/// notation (like `dw<Esc>`) as recording a macro writes them, or yanked from a buffer.
+fn macro_in_register(state: &WorkspaceState, register: char) -> Option<Vec<ReplayableAction>> {
+    if let Some(actions) = state.recordings.get(&register) {
+        return Some(actions.clone());
+    }
+    let keys = state.read_register(register)?;
+    Some(
+        parse_key_notation(keys)
+            .into_iter()
+            .map(ReplayableAction::Keystroke)
+            .collect(),
+    )
+}
+
+/// Replays the macro in the register (`@{register}`), count times. `@:` runs the
+/// last command line again instead, and `@@` replays whichever register was last.
+pub(crate) fn replay_register(register: char, cx: &mut WindowContext) {
+    let Some((steps, undo_group)) = Vim::update(cx, |vim, cx| {
+        let count = vim.take_count(cx).unwrap_or(1);
+        vim.clear_operator(cx);
+        let register = if register == '@' {
+            let Some(register) = vim.workspace_state.last_replayed_register else {
+                vim.show_message("E748: No previously used register");
+                vim.abort_replay();
+                return None;
+            };
+            register
+        } else {
+            register.to_ascii_lowercase()
+        };
+
+        let actions = if register == ':' {
+            let Some(command) = vim.workspace_state.read_register(':') else {
+                vim.show_message("E30: No previous command line");
+                vim.abort_replay();
+                return None;
+            };
+            vec![ReplayableAction::Action(
+                ExCommand {
+                    command: command.clone(),
+                }
+                .boxed_clone(),
+            )]
+        } else if let Some(actions) = macro_in_register(&vim.workspace_state, register) {
+            actions
+        } else {
+            vim.abort_replay();
+            return None;
+        };
+        vim.workspace_state.last_replayed_register = Some(register);
+
+        let mut steps = Vec::new();
+        for _ in 0..count {
+            steps.extend(actions.iter().cloned().map(ReplayStep::Replay));
+        }
+        // like vim, the changes a macro makes are undone together, unless it's
+        // replayed by another one
+        let undo_group = if vim.workspace_state.replayer.is_none() {
+            start_undo_group(vim, cx)
+        } else {
+            None
+        };
+        Some((steps, undo_group))
+    }) else {
+        return;
+    };
+    let replayer = replay(steps, cx);
+    if let Some(undo_group) = undo_group {
+        replayer.on_finish(move |cx| end_undo_group(&undo_group, cx));
+    }
 }
 
 pub(crate) fn repeat(cx: &mut WindowContext, from_insert_mode: bool) {
@@ -61,6 +451,20 @@ pub(crate) fn repeat(cx: &mut WindowContext, from_insert_mode: bool) {
             return None;
         };
         let count = vim.take_count(cx);
+        if !from_insert_mode {
+            // a register given to `.` replaces the one the change was made with,
+            // and repeating a put from a numbered register puts the next one, so
+            // that `"1p...` puts the last four deletes.
+            let register = vim.workspace_state.selected_register.or_else(|| {
+                let register = vim.workspace_state.recorded_register?;
+                Some(match register.to_digit(10) {
+                    Some(number @ 1..=8) => char::from_digit(number + 1, 10)?,
+                    _ => register,
+                })
+            });
+            vim.workspace_state.recorded_register = register;
+            vim.workspace_state.selected_register = register;
+        }
 
         let selection = vim.workspace_state.recorded_selection.clone();
         match selection {
@@ -166,47 +570,51 @@ pub(crate) fn repeat(cx: &mut WindowContext, from_insert_mode: bool) {
     }
 
     Vim::update(cx, |vim, _| vim.workspace_state.replaying = true);
-    let window = cx.window_handle();
-    cx.spawn(move |mut cx| async move {
-        editor.update(&mut cx, |editor, _| {
-            editor.show_local_selections = false;
-        })?;
-        for action in actions {
-            match action {
-                ReplayableAction::Action(action) => {
-                    if should_replay(&action) {
-                        window.update(&mut cx, |_, cx| cx.dispatch_action(action))
-                    } else {
-                        Ok(())
-                    }
-                }
-                ReplayableAction::Insertion {
-                    text,
-                    utf16_range_to_replace,
-                } => editor.update(&mut cx, |editor, cx| {
-                    editor.replay_insert_event(&text, utf16_range_to_replace.clone(), cx)
-                }),
-            }?
+
+    let show_local_selections = |editor: WeakView<Editor>, show: bool| {
+        move |cx: &mut WindowContext| {
+            editor
+                .update(cx, |editor, _| editor.show_local_selections = show)
+                .ok();
         }
-        editor.update(&mut cx, |editor, _| {
-            editor.show_local_selections = true;
-        })?;
-        window.update(&mut cx, |_, cx| cx.dispatch_action(EndRepeat.boxed_clone()))
-    })
-    .detach_and_log_err(cx);
+    };
+    let mut steps = vec![ReplayStep::Run(Box::new(show_local_selections(
+        editor.clone(),
+        false,
+    )))];
+    steps.extend(actions.into_iter().map(ReplayStep::Replay));
+    steps.push(ReplayStep::Run(Box::new(show_local_selections(
+        editor.clone(),
+        true,
+    ))));
+    steps.push(ReplayStep::Replay(ReplayableAction::Action(
+        EndRepeat.boxed_clone(),
+    )));
+
+    let replayer = replay(steps, cx);
+    // if the replay is aborted, we still need to leave replaying mode.
+    replayer.on_finish(move |cx| {
+        show_local_selections(editor, true)(cx);
+        Vim::update(cx, |vim, _| vim.workspace_state.replaying = false);
+    });
 }
 
 #[cfg(test)]
 mod test {
-    use editor::test::editor_lsp_test_context::EditorLspTestContext;
+    use editor::{
+        test::editor_lsp_test_context::EditorLspTestContext, Editor, ExcerptRange, MultiBuffer,
+    };
     use futures::StreamExt;
     use indoc::indoc;
+    use language::{Buffer, BufferId, Capability, Point};
 
-    use gpui::ViewInputHandler;
+    use gpui::{Context, ViewInputHandler, VisualContext};
 
+    use super::ToggleRecord;
     use crate::{
-        state::Mode,
+        state::{Mode, RegisterKind, ReplayableAction},
         test::{NeovimBackedTestContext, VimTestContext},
+        Vim,
     };
 
     #[gpui::test]
@@ -243,6 +651,55 @@ mod test {
         cx.assert_shared_state("THE QUICK ˇbrown fox").await;
     }
 
+    #[gpui::test]
+    async fn test_dot_repeat_multiple_cursors(cx: &mut gpui::TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+
+        // operators apply at each cursor, and so does repeating them
+        cx.set_state("ˇa b c\nˇa b c", Mode::Normal);
+        cx.simulate_keystrokes(["d", "w"]);
+        cx.assert_state("ˇb c\nˇb c", Mode::Normal);
+        cx.simulate_keystrokes(["."]);
+        cx.assert_state("ˇc\nˇc", Mode::Normal);
+
+        cx.set_state("ˇone\nˇtwo", Mode::Normal);
+        cx.simulate_keystrokes(["i", "-", "escape"]);
+        cx.assert_state("ˇ-one\nˇ-two", Mode::Normal);
+        cx.simulate_keystrokes(["."]);
+        cx.assert_state("ˇ--one\nˇ--two", Mode::Normal);
+    }
+
+    #[gpui::test]
+    async fn test_repeat_insert_entry_points(cx: &mut gpui::TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+
+        // each of A, I, o and O is replayed along with the text typed after it
+        cx.set_state("ˇa\nb\nc", Mode::Normal);
+        cx.simulate_keystrokes(["shift-a", ";", "escape", "j", "."]);
+        cx.assert_state("a;\nbˇ;\nc", Mode::Normal);
+        cx.simulate_keystrokes(["j", "2", "."]);
+        cx.assert_state("a;\nb;\nc;ˇ;", Mode::Normal);
+
+        cx.set_state("  ˇone\n  two", Mode::Normal);
+        cx.simulate_keystrokes(["shift-i", "-", " ", "escape", "j", "$", "."]);
+        cx.assert_state("  - one\n  -ˇ two", Mode::Normal);
+
+        cx.set_state("ˇa\nb", Mode::Normal);
+        cx.simulate_keystrokes(["o", "x", "escape", "j", "."]);
+        cx.assert_state("a\nx\nb\nˇx", Mode::Normal);
+
+        cx.set_state("a\nˇb", Mode::Normal);
+        cx.simulate_keystrokes(["shift-o", "y", "escape", "g", "g", "."]);
+        cx.assert_state("ˇy\na\ny\nb", Mode::Normal);
+
+        // a count given to the entry command is kept, and one given to . replaces it
+        cx.set_state("ˇa\nb", Mode::Normal);
+        cx.simulate_keystrokes(["2", "o", "x", "escape", "g", "g", "."]);
+        cx.assert_state("a\nx\nˇx\nx\nx\nb", Mode::Normal);
+        cx.simulate_keystrokes(["shift-g", "3", "."]);
+        cx.assert_state("a\nx\nx\nx\nx\nb\nx\nx\nˇx", Mode::Normal);
+    }
+
     #[gpui::test]
     async fn test_repeat_ime(cx: &mut gpui::TestAppContext) {
         let mut cx = VimTestContext::new(cx, true).await;
@@ -334,6 +791,89 @@ mod test {
         );
     }
 
+    #[gpui::test]
+    async fn test_macro_completion(cx: &mut gpui::TestAppContext) {
+        VimTestContext::init(cx);
+        let cx = EditorLspTestContext::new_rust(
+            lsp::ServerCapabilities {
+                completion_provider: Some(lsp::CompletionOptions {
+                    trigger_characters: Some(vec![".".to_string(), ":".to_string()]),
+                    resolve_provider: Some(true),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            },
+            cx,
+        )
+        .await;
+        let mut cx = VimTestContext::new_with_lsp(cx, true);
+
+        cx.set_state(
+            indoc! {"
+            onˇe
+            two
+            three
+        "},
+            Mode::Normal,
+        );
+
+        let mut request =
+            cx.handle_request::<lsp::request::Completion, _, _>(move |_, params, _| async move {
+                let position = params.text_document_position.position;
+                Ok(Some(lsp::CompletionResponse::Array(vec![
+                    lsp::CompletionItem {
+                        label: "first".to_string(),
+                        text_edit: Some(lsp::CompletionTextEdit::Edit(lsp::TextEdit {
+                            range: lsp::Range::new(position, position),
+                            new_text: "first".to_string(),
+                        })),
+                        ..Default::default()
+                    },
+                    lsp::CompletionItem {
+                        label: "second".to_string(),
+                        text_edit: Some(lsp::CompletionTextEdit::Edit(lsp::TextEdit {
+                            range: lsp::Range::new(position, position),
+                            new_text: "second".to_string(),
+                        })),
+                        ..Default::default()
+                    },
+                ])))
+            });
+        cx.simulate_keystrokes(["q", "a", "a", "."]);
+        request.next().await;
+        cx.condition(|editor, _| editor.context_menu_visible())
+            .await;
+        cx.simulate_keystrokes(["down", "enter", "!", "escape", "j", "q"]);
+        cx.assert_state(
+            indoc! {"
+                one.second!
+                twˇo
+                three
+            "},
+            Mode::Normal,
+        );
+
+        // the menu doesn't open during the replay, and the keys that picked
+        // the completion aren't replayed: the text it inserted is.
+        cx.simulate_keystrokes(["@", "a"]);
+        cx.run_until_parked();
+        assert!(!cx.update_editor(|editor, _| editor.context_menu_visible()));
+        cx.assert_state(
+            indoc! {"
+                one.second!
+                two.second!
+                thrˇee
+            "},
+            Mode::Normal,
+        );
+
+        // completions pop up again once the replay is over
+        cx.simulate_keystrokes(["a", "."]);
+        request.next().await;
+        cx.condition(|editor, _| editor.context_menu_visible())
+            .await;
+    }
+
     #[gpui::test]
     async fn test_repeat_visual(cx: &mut gpui::TestAppContext) {
         let mut cx = NeovimBackedTestContext::new(cx).await;
@@ -494,6 +1034,284 @@ mod test {
         cx.assert_state("ˇjhello\n", Mode::Normal);
     }
 
+    #[gpui::test]
+    async fn test_record_replay(cx: &mut gpui::TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+
+        cx.set_state("ˇone two\nthree four\nfive six", Mode::Normal);
+        cx.simulate_keystrokes(["q", "w", "d", "w", "j", "q"]);
+        cx.assert_state("two\nˇthree four\nfive six", Mode::Normal);
+        cx.simulate_keystrokes(["@", "w"]);
+        cx.assert_state("two\nfour\nˇfive six", Mode::Normal);
+
+        // j fails on the last line, which stops the macro
+        cx.simulate_keystrokes(["2", "@", "w"]);
+        cx.assert_state("two\nfour\nˇsix", Mode::Normal);
+    }
+
+    #[gpui::test]
+    async fn test_record_command_line_and_search(cx: &mut gpui::TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+
+        // the command that was run is recorded, not the keys typed to pick it
+        cx.set_state("ˇa a\na a\na a", Mode::Normal);
+        cx.simulate_keystrokes(["q", "a", ":"]);
+        cx.simulate_input("s/a/b/g");
+        cx.simulate_keystrokes(["enter", "j", "q"]);
+        cx.run_until_parked();
+        cx.assert_state("b b\nˇa a\na a", Mode::Normal);
+        cx.simulate_keystrokes(["@", "a"]);
+        cx.run_until_parked();
+        cx.assert_state("b b\nb b\nˇa a", Mode::Normal);
+
+        // a search typed in the macro is searched for again, and the rest of
+        // the macro waits for it
+        cx.set_state("ˇone\nfoo two\nfoo three", Mode::Normal);
+        cx.simulate_keystrokes(["q", "b", "/"]);
+        cx.simulate_input("foo");
+        cx.run_until_parked();
+        cx.simulate_keystrokes(["enter", "x", "q"]);
+        cx.run_until_parked();
+        cx.assert_state("one\nˇoo two\nfoo three", Mode::Normal);
+        cx.simulate_keystrokes(["@", "b"]);
+        cx.run_until_parked();
+        cx.assert_state("one\noo two\nˇoo three", Mode::Normal);
+
+        // a prompt that is closed records nothing
+        cx.set_state("ˇone two", Mode::Normal);
+        cx.simulate_keystrokes(["q", "c", ":"]);
+        cx.simulate_input("d");
+        cx.simulate_keystrokes(["escape", "w", "q"]);
+        cx.run_until_parked();
+        cx.simulate_keystrokes(["0", "@", "c"]);
+        cx.run_until_parked();
+        cx.assert_state("one ˇtwo", Mode::Normal);
+
+        // as is an action that isn't an ex command, picked in the palette
+        cx.set_state("ˇone\ntwo\nthree", Mode::Normal);
+        cx.simulate_keystrokes(["q", "d", ":"]);
+        cx.simulate_input("editor: delete line");
+        cx.run_until_parked();
+        cx.simulate_keystrokes(["enter", "q"]);
+        cx.run_until_parked();
+        cx.assert_state("ˇtwo\nthree", Mode::Normal);
+        cx.simulate_keystrokes(["@", "d"]);
+        cx.run_until_parked();
+        cx.assert_state("ˇthree", Mode::Normal);
+    }
+
+    #[gpui::test]
+    async fn test_repeat_command_line(cx: &mut gpui::TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+
+        cx.set_state("ˇa\nb\nc\nd\ne\nf", Mode::Normal);
+        cx.simulate_keystrokes(["@", "@"]);
+        assert_eq!(
+            cx.message().as_deref(),
+            Some("E748: No previously used register")
+        );
+        cx.simulate_keystrokes(["@", ":"]);
+        assert_eq!(
+            cx.message().as_deref(),
+            Some("E30: No previous command line")
+        );
+
+        cx.simulate_keystrokes([":", "d", "enter"]);
+        cx.assert_state("ˇb\nc\nd\ne\nf", Mode::Normal);
+        cx.simulate_keystrokes(["@", ":"]);
+        cx.assert_state("ˇc\nd\ne\nf", Mode::Normal);
+        cx.simulate_keystrokes(["@", "@"]);
+        cx.assert_state("ˇd\ne\nf", Mode::Normal);
+
+        // @@ follows the last register, not the last command line
+        cx.simulate_keystrokes(["q", "w", "x", "q", "j", "@", "w"]);
+        cx.assert_state("\nˇ\nf", Mode::Normal);
+        cx.simulate_keystrokes(["j", "@", "@"]);
+        cx.assert_state("\n\nˇ", Mode::Normal);
+    }
+
+    #[gpui::test]
+    async fn test_recursive_macro(cx: &mut gpui::TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+
+        cx.set_state("ˇ1a\n2b\n3c\n4d", Mode::Normal);
+        cx.simulate_keystrokes(["q", "a", "q"]);
+        cx.simulate_keystrokes(["q", "a", "x", "j", "@", "a", "q"]);
+        cx.assert_state("a\nˇ2b\n3c\n4d", Mode::Normal);
+
+        cx.simulate_keystrokes(["@", "a"]);
+        cx.assert_state("a\nb\nc\nˇd", Mode::Normal);
+    }
+
+    #[gpui::test]
+    async fn test_long_replay(cx: &mut gpui::TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+        let line = "a".repeat(1000);
+
+        // the changes a macro makes are undone together
+        cx.set_state(&format!("ˇ{line}"), Mode::Normal);
+        cx.simulate_keystrokes(["q", "q", "x", "q", "3", "@", "q"]);
+        cx.assert_state(&format!("ˇ{}", &line[4..]), Mode::Normal);
+        cx.simulate_keystrokes(["u"]);
+        cx.assert_state(&format!("ˇ{}", &line[1..]), Mode::Normal);
+
+        // a long replay lets the editor handle input between batches of steps, so
+        // ctrl-c can stop it
+        for key in ["5", "0", "0", "@", "q"] {
+            cx.simulate_keystroke(key);
+        }
+        cx.simulate_keystroke("ctrl-c");
+        cx.run_until_parked();
+        assert_eq!(cx.message().as_deref(), Some("Interrupted"));
+        let len = cx.update_editor(|editor, cx| editor.text(cx).len());
+        assert!(len < 999 && len > 499, "{len}");
+
+        // and the macro is undone together all the same
+        cx.simulate_keystrokes(["u"]);
+        cx.assert_state(&format!("ˇ{}", &line[1..]), Mode::Normal);
+    }
+
+    #[gpui::test]
+    async fn test_macro_that_undoes(cx: &mut gpui::TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+
+        // a macro can undo its own changes, and what's left of them is undone together
+        cx.set_state("ˇabcdef", Mode::Normal);
+        cx.simulate_keystrokes(["q", "a", "x", "u", "x", "x", "q"]);
+        cx.assert_state("ˇcdef", Mode::Normal);
+        cx.simulate_keystrokes(["@", "a"]);
+        cx.assert_state("ˇef", Mode::Normal);
+        cx.simulate_keystrokes(["u"]);
+        cx.assert_state("ˇcdef", Mode::Normal);
+        cx.simulate_keystrokes(["ctrl-r"]);
+        cx.assert_state("ˇef", Mode::Normal);
+    }
+
+    #[gpui::test]
+    async fn test_macro_undo_in_multibuffer(cx: &mut gpui::TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+
+        let buffers = [(1, "a\nb"), (2, "c\nd")]
+            .map(|(id, text)| cx.new_model(|_| Buffer::new(0, BufferId::new(id).unwrap(), text)));
+        let multibuffer = cx.new_model(|cx| {
+            let mut multibuffer = MultiBuffer::new(0, Capability::ReadWrite);
+            for buffer in &buffers {
+                multibuffer.push_excerpts(
+                    buffer.clone(),
+                    [ExcerptRange {
+                        context: Point::new(0, 0)..Point::new(1, 1),
+                        primary: None,
+                    }],
+                    cx,
+                );
+            }
+            multibuffer
+        });
+        let editor = cx.workspace(|workspace, cx| {
+            let editor = cx.new_view(|cx| Editor::for_multibuffer(multibuffer, None, cx));
+            workspace.add_item_to_active_pane(Box::new(editor.clone()), cx);
+            editor
+        });
+        cx.run_until_parked();
+        let state = |cx: &mut VimTestContext| {
+            cx.update_view(editor.clone(), |editor, cx| {
+                (
+                    editor.text(cx),
+                    editor.selections.newest::<Point>(cx).head(),
+                )
+            })
+        };
+
+        cx.simulate_keystrokes(["q", "a", "x", "j", "q"]);
+        assert_eq!(state(&mut cx), ("\nb\nc\nd".to_string(), Point::new(1, 0)));
+
+        // the replay's edits, in both buffers, are undone together
+        cx.simulate_keystrokes(["2", "@", "a"]);
+        assert_eq!(state(&mut cx), ("\n\n\nd".to_string(), Point::new(3, 0)));
+        cx.simulate_keystrokes(["u"]);
+        assert_eq!(state(&mut cx), ("\nb\nc\nd".to_string(), Point::new(1, 0)));
+    }
+
+    #[gpui::test]
+    async fn test_record_macro_that_replays_another(cx: &mut gpui::TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+
+        cx.set_state("ˇone two three four five six", Mode::Normal);
+        cx.simulate_keystrokes(["q", "b", "d", "w", "q"]);
+        cx.assert_state("ˇtwo three four five six", Mode::Normal);
+        // only `@b` is recorded, not what it does, and the `q` that stops
+        // the recording isn't recorded either
+        cx.simulate_keystrokes(["q", "a", "@", "b", "w", "q"]);
+        cx.assert_state("three ˇfour five six", Mode::Normal);
+        cx.update_global(|vim: &mut Vim, _| {
+            assert_eq!(vim.workspace_state.recording_register, None);
+            assert!(!vim.workspace_state.recordings[&'a']
+                .iter()
+                .any(|action| matches!(
+                    action,
+                    ReplayableAction::Action(action) if action.as_any().is::<ToggleRecord>()
+                )));
+        });
+        cx.simulate_keystrokes(["@", "a"]);
+        cx.assert_state("three five ˇsix", Mode::Normal);
+
+        // a replay can't start a recording
+        cx.set_state("ˇone two", Mode::Normal);
+        cx.simulate_keystrokes([":"]);
+        cx.simulate_input("normal qcx");
+        cx.simulate_keystrokes(["enter"]);
+        cx.run_until_parked();
+        cx.assert_state("ˇone two", Mode::Normal);
+        assert_eq!(
+            cx.message().as_deref(),
+            Some("Can't start or stop recording while a macro is replayed")
+        );
+        cx.update_global(|vim: &mut Vim, _| {
+            assert_eq!(vim.workspace_state.recording_register, None)
+        });
+    }
+
+    #[gpui::test]
+    async fn test_repeat_registers(cx: &mut gpui::TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+
+        cx.update(|cx| {
+            crate::set_register('1', "one".to_string(), RegisterKind::Linewise, cx);
+            crate::set_register('2', "two".to_string(), RegisterKind::Linewise, cx);
+            crate::set_register('3', "three".to_string(), RegisterKind::Linewise, cx);
+            crate::set_register('a', "a".to_string(), RegisterKind::Charwise, cx);
+        });
+        cx.set_state("ˇx", Mode::Normal);
+        cx.simulate_keystrokes(["\"", "1", "p", ".", "."]);
+        cx.assert_state("x\none\ntwo\nˇthree", Mode::Normal);
+
+        // a register given to `.` is used instead, and from then on
+        cx.set_state("ˇxyz", Mode::Normal);
+        cx.simulate_keystrokes(["\"", "a", "d", "l", "\"", "b", "."]);
+        cx.assert_state("ˇz", Mode::Normal);
+        assert_eq!(cx.read_register('a').as_deref(), Some("x"));
+        assert_eq!(cx.read_register('b').as_deref(), Some("y"));
+        cx.simulate_keystrokes(["."]);
+        assert_eq!(cx.read_register('b').as_deref(), Some("z"));
+    }
+
+    #[gpui::test]
+    async fn test_repeat_in_other_pane(cx: &mut gpui::TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+
+        cx.set_state("ˇone two three", Mode::Normal);
+        cx.simulate_keystrokes(["d", "w", "ctrl-w", "v"]);
+        let editor = cx.editor.clone();
+        let other = cx.workspace(|workspace, cx| workspace.active_item_as::<Editor>(cx).unwrap());
+        assert!(other != editor);
+        // `.` changes the editor that's active now
+        cx.simulate_keystrokes(["w", "."]);
+        cx.workspace(|_, cx| {
+            assert_eq!(other.read(cx).text(cx), "two ");
+            assert_eq!(editor.read(cx).text(cx), "two ");
+        });
+    }
+
     #[gpui::test]
     async fn test_repeat_over_blur(cx: &mut gpui::TestAppContext) {
         let mut cx = NeovimBackedTestContext::new(cx).await;
@@ -0,0 +1,668 @@
+use std::ops::Range;
+
+use editor::{scroll::Autoscroll, Anchor, Editor, MultiBufferSnapshot};
+use gpui::{actions, ViewContext, WindowContext};
+use language::{LanguageScope, Point};
+use settings::Settings;
+use workspace::Workspace;
+
+use crate::{
+    filter::filter_rows,
+    formatoptions::FormatOptions,
+    motion::Motion,
+    object::Object,
+    state::Mode,
+    utils::{screen_width, KeywordChars},
+    Vim, VimSettings,
+};
+
+actions!(vim, [VisualRewrap]);
+
+pub(crate) fn register(workspace: &mut Workspace, _: &mut ViewContext<Workspace>) {
+    workspace.register_action(|_: &mut Workspace, _: &VisualRewrap, cx| {
+        Vim::update(cx, |vim, cx| {
+            vim.record_current_action(cx);
+            vim.update_active_editor(cx, |vim, editor, cx| {
+                let rows = editor
+                    .selections
+                    .all::<Point>(cx)
+                    .into_iter()
+                    .map(|selection| line_rows(selection.start..selection.end))
+                    .collect();
+                format_rows(vim, editor, rows, cx);
+            });
+            vim.switch_mode(Mode::Normal, true, cx);
+        })
+    });
+}
+
+/// `gq{motion}`
+pub fn rewrap_motion(vim: &mut Vim, motion: Motion, times: Option<usize>, cx: &mut WindowContext) {
+    vim.stop_recording();
+    vim.update_active_editor(cx, |vim, editor, cx| {
+        let text_layout_details = editor.text_layout_details(cx);
+        let keyword_chars = KeywordChars::new(cx);
+        let mut rows = Vec::new();
+        editor.change_selections(None, cx, |s| {
+            s.move_with(|map, selection| {
+                motion.expand_selection(
+                    map,
+                    selection,
+                    times,
+                    true,
+                    &text_layout_details,
+                    &keyword_chars,
+                );
+                rows.push(line_rows(
+                    selection.start.to_point(map)..selection.end.to_point(map),
+                ));
+            });
+        });
+        format_rows(vim, editor, rows, cx);
+    });
+}
+
+/// `gqip` and friends
+pub fn rewrap_object(vim: &mut Vim, object: Object, around: bool, cx: &mut WindowContext) {
+    vim.stop_recording();
+    vim.update_active_editor(cx, |vim, editor, cx| {
+        let keyword_chars = KeywordChars::new(cx);
+        let mut rows = Vec::new();
+        editor.change_selections(None, cx, |s| {
+            s.move_with(|map, selection| {
+                object.expand_selection(map, selection, around, &keyword_chars);
+                rows.push(line_rows(
+                    selection.start.to_point(map)..selection.end.to_point(map),
+                ));
+            });
+        });
+        format_rows(vim, editor, rows, cx);
+    });
+}
+
+/// The rows a range covers. A range that ends at the start of a line (as linewise
+/// motions do) doesn't include that line.
+pub(crate) fn line_rows(range: Range<Point>) -> Range<u32> {
+    let mut end_row = range.end.row;
+    if range.end.column == 0 && end_row > range.start.row {
+        end_row -= 1;
+    }
+    range.start.row..end_row + 1
+}
+
+/// Pipes the lines through the language's `formatprg` command when it has one, and
+/// rewraps them otherwise. Unlike `=`, this never asks the language server.
+fn format_rows(
+    vim: &mut Vim,
+    editor: &mut Editor,
+    rows: Vec<Range<u32>>,
+    cx: &mut ViewContext<Editor>,
+) {
+    let buffer = editor.buffer().read(cx).snapshot(cx);
+    let program = rows
+        .first()
+        .and_then(|rows| buffer.language_at(Point::new(rows.start, 0)))
+        .and_then(|language| {
+            VimSettings::get_global(cx)
+                .formatprg
+                .get(language.name().as_ref())
+                .cloned()
+        })
+        .filter(|program| !program.trim().is_empty());
+    match program {
+        Some(program) => filter_rows(vim, editor, rows, program, true, cx),
+        None => rewrap_rows(editor, rows, cx),
+    }
+}
+
+/// Rewraps the lines to the `textwidth` (or the language's `preferred_line_length`
+/// without one), and leaves each cursor
+/// on the first non-blank of the last line it rewrapped, like vim's `gq`.
+fn rewrap_rows(editor: &mut Editor, rows: Vec<Range<u32>>, cx: &mut ViewContext<Editor>) {
+    let buffer = editor.buffer().read(cx).snapshot(cx);
+    let mut edits = Vec::new();
+    let mut ends = Vec::new();
+    for rows in rows {
+        let start = Point::new(rows.start, 0);
+        let end = Point::new(rows.end - 1, buffer.line_len(rows.end - 1));
+        let settings = buffer.settings_at(start, cx);
+        let options = FormatOptions::at(&buffer, start, cx);
+        let width = match options.textwidth {
+            0 => settings.preferred_line_length as usize,
+            textwidth => textwidth as usize,
+        };
+        let tab_size = settings.tab_size.get() as usize;
+        let indent = buffer.indent_size_for_line(rows.start).len;
+        let comments = CommentStyle::new(buffer.language_scope_at(Point::new(rows.start, indent)));
+        let in_block = comments.in_block(&buffer, rows.start);
+
+        let text = buffer.text_for_range(start..end).collect::<String>();
+        let new_text = rewrap_text(
+            &text,
+            width,
+            tab_size,
+            &comments,
+            in_block,
+            options.format_comments,
+        );
+        if new_text != text {
+            edits.push((start..end, new_text));
+        }
+        ends.push(buffer.anchor_after(end));
+    }
+
+    editor.transact(cx, |editor, cx| {
+        editor.edit(edits, cx);
+        move_to_lines(editor, ends, cx);
+    });
+}
+
+/// Puts a cursor on the first non-blank of the line each anchor is on.
+pub(crate) fn move_to_lines(
+    editor: &mut Editor,
+    anchors: Vec<Anchor>,
+    cx: &mut ViewContext<Editor>,
+) {
+    let buffer = editor.buffer().read(cx).snapshot(cx);
+    let cursors = anchors
+        .into_iter()
+        .map(|anchor| {
+            let row = anchor.to_point(&buffer).row;
+            let cursor = Point::new(row, buffer.indent_size_for_line(row).len);
+            cursor..cursor
+        })
+        .collect::<Vec<_>>();
+    editor.change_selections(Some(Autoscroll::fit()), cx, |s| s.select_ranges(cursors));
+}
+
+/// The comment markers that `gq` keeps at the start of each line it wraps.
+#[derive(Default)]
+pub(crate) struct CommentStyle {
+    /// Like `//`, without the space after it
+    line_prefixes: Vec<String>,
+    /// Like `/*` and `*/`
+    block: Option<(String, String)>,
+}
+
+impl CommentStyle {
+    pub(crate) fn new(scope: Option<LanguageScope>) -> Self {
+        let Some(scope) = scope else {
+            return Self::default();
+        };
+        let mut line_prefixes = scope
+            .line_comment_prefixes()
+            .into_iter()
+            .flatten()
+            .map(|prefix| prefix.trim().to_string())
+            .filter(|prefix| !prefix.is_empty())
+            .collect::<Vec<_>>();
+        // longest first, so `--` isn't taken for `-`
+        line_prefixes.sort_by_key(|prefix| std::cmp::Reverse(prefix.len()));
+        let block = scope
+            .block_comment_delimiters()
+            .map(|(start, end)| (start.trim().to_string(), end.trim().to_string()));
+        Self {
+            line_prefixes,
+            block,
+        }
+    }
+
+    /// Whether the line after this one is inside a block comment, given whether this
+    /// one is: after a line that opens one without closing it, or after a ` * ` line
+    /// that doesn't close it.
+    pub(crate) fn continues_block(&self, line: &str, in_block: bool) -> bool {
+        let Some((start, end)) = self.block.as_ref() else {
+            return false;
+        };
+        let rest = line.trim_start();
+        match rest.strip_prefix(start.as_str()) {
+            Some(comment) => !comment.contains(end.as_str()),
+            None => in_block && is_block_continuation(rest) && !rest.contains(end.as_str()),
+        }
+    }
+
+    /// Whether the row is inside a block comment, going up through the ` * ` lines
+    /// above it to the one that opens the comment.
+    pub(crate) fn in_block(&self, buffer: &MultiBufferSnapshot, row: u32) -> bool {
+        let Some((start, _)) = self.block.as_ref() else {
+            return false;
+        };
+        for row in (0..row).rev() {
+            let line = buffer
+                .text_for_range(Point::new(row, 0)..Point::new(row, buffer.line_len(row)))
+                .collect::<String>();
+            if line.trim_start().starts_with(start.as_str()) {
+                return self.continues_block(&line, false);
+            }
+            if !self.continues_block(&line, true) {
+                return false;
+            }
+        }
+        false
+    }
+
+    /// Splits a line into its leader (the indent and any comment marker, with the
+    /// space after it) and its text. Also returns the leader for the lines that
+    /// continue it, which differs after the start of a block comment (`/** ` is
+    /// continued by ` * `). A `*` is only a leader inside a block comment, and with a
+    /// blank after it (like vim's `mb:*`), so that `*ptr` isn't taken for one.
+    pub(crate) fn split<'a>(&self, line: &'a str, in_block: bool) -> (&'a str, String, &'a str) {
+        let indent_len = line.len() - line.trim_start().len();
+        let (indent, rest) = line.split_at(indent_len);
+        let marker_len = |prefix: &str| {
+            // doc comments repeat the marker or add a `!`, like `///` and `//!`
+            let extra = rest[prefix.len()..]
+                .chars()
+                .take_while(|c| prefix.contains(*c) || *c == '!')
+                .map(char::len_utf8)
+                .sum::<usize>();
+            let marker_len = prefix.len() + extra;
+            let space = rest[marker_len..].len() - rest[marker_len..].trim_start().len();
+            marker_len + space
+        };
+
+        let leader_len = if let Some(prefix) = self
+            .line_prefixes
+            .iter()
+            .find(|prefix| rest.starts_with(prefix.as_str()))
+        {
+            indent_len + marker_len(prefix)
+        } else if let Some((start, _)) = self.block.as_ref() {
+            if rest.starts_with(start.as_str()) {
+                let leader_len = indent_len + marker_len(start);
+                return (
+                    &line[..leader_len],
+                    format!("{indent} * "),
+                    &line[leader_len..],
+                );
+            } else if in_block && is_block_continuation(rest) {
+                indent_len + marker_len("*")
+            } else {
+                indent_len
+            }
+        } else {
+            indent_len
+        };
+        let (leader, text) = line.split_at(leader_len);
+        (leader, leader.to_string(), text)
+    }
+}
+
+/// Whether the text starts with a `*` followed by a blank, or nothing.
+fn is_block_continuation(text: &str) -> bool {
+    text.strip_prefix('*')
+        .is_some_and(|rest| rest.is_empty() || rest.starts_with(char::is_whitespace))
+}
+
+/// Joins each paragraph of the text and splits it again into lines that fit in
+/// `width` columns. A paragraph is a run of lines with the same comment leader, which
+/// is kept at the start of each new line. Blank lines (and lines with only a
+/// comment marker) separate paragraphs, and are kept as they are, as are comments
+/// unless `format_comments` is set. `in_block` is whether the text starts inside a
+/// block comment.
+fn rewrap_text(
+    text: &str,
+    width: usize,
+    tab_size: usize,
+    comments: &CommentStyle,
+    mut in_block: bool,
+    format_comments: bool,
+) -> String {
+    let mut lines = Vec::new();
+    let mut paragraph: Option<(String, String, Vec<&str>)> = None;
+    for line in text.split('\n') {
+        let (leader, continuation, text) = comments.split(line, in_block);
+        in_block = comments.continues_block(line, in_block);
+        let is_comment = !leader.trim().is_empty();
+        if text.trim().is_empty() || (is_comment && !format_comments) {
+            if let Some((first, rest, words)) = paragraph.take() {
+                fill(&mut lines, first, rest, words, width, tab_size);
+            }
+            lines.push(line.to_string());
+            continue;
+        }
+        match paragraph.as_mut() {
+            Some((_, rest, words)) if rest.trim_end() == leader.trim_end() => {
+                words.extend(text.split_whitespace())
+            }
+            _ => {
+                if let Some((first, rest, words)) = paragraph.take() {
+                    fill(&mut lines, first, rest, words, width, tab_size);
+                }
+                let words = text.split_whitespace().collect();
+                paragraph = Some((leader.to_string(), continuation, words));
+            }
+        }
+    }
+    if let Some((first, rest, words)) = paragraph.take() {
+        fill(&mut lines, first, rest, words, width, tab_size);
+    }
+    lines.join("\n")
+}
+
+/// Adds lines holding as many of the words as fit, the first starting with `first`
+/// and the others with `rest`. A word longer than the line gets a line of its own.
+fn fill(
+    lines: &mut Vec<String>,
+    first: String,
+    rest: String,
+    words: Vec<&str>,
+    width: usize,
+    tab_size: usize,
+) {
+    let columns = |text: &str| screen_width(text, tab_size);
+    let mut line = first;
+    let mut line_width = columns(&line);
+    let mut empty = true;
+    for word in words {
+        let word_width = columns(word);
+        if !empty && line_width + 1 + word_width > width {
+            lines.push(line);
+            line = rest.clone();
+            line_width = columns(&line);
+            empty = true;
+        }
+        if !empty {
+            line.push(' ');
+            line_width += 1;
+        }
+        line.push_str(word);
+        line_width += word_width;
+        empty = false;
+    }
+    lines.push(line);
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use editor::test::editor_lsp_test_context::EditorLspTestContext;
+    use indoc::indoc;
+    use language::{
+        language_settings::AllLanguageSettings, Language, LanguageConfig, LanguageMatcher,
+    };
+    use settings::SettingsStore;
+
+    use crate::{state::Mode, test::VimTestContext, Vim, VimSettings};
+
+    async fn rewrap_test_context(cx: &mut gpui::TestAppContext) -> VimTestContext {
+        VimTestContext::init(cx);
+        let language = Language::new(
+            LanguageConfig {
+                name: "Rust".into(),
+                matcher: LanguageMatcher {
+                    path_suffixes: vec!["rs".to_string()],
+                    ..Default::default()
+                },
+                line_comments: vec!["// ".into()],
+                block_comment: Some(("/* ".into(), " */".into())),
+                ..Default::default()
+            },
+            None,
+        );
+        let lsp = EditorLspTestContext::new(language, Default::default(), cx).await;
+        let mut cx = VimTestContext::new_with_lsp(lsp, true);
+        cx.update_global(|store: &mut SettingsStore, cx| {
+            store.update_user_settings::<AllLanguageSettings>(cx, |settings| {
+                settings.defaults.preferred_line_length = Some(20);
+            });
+        });
+        cx
+    }
+
+    #[gpui::test]
+    async fn test_rewrap(cx: &mut gpui::TestAppContext) {
+        let mut cx = rewrap_test_context(cx).await;
+
+        cx.set_state(
+            indoc! {"
+                ˇone two three four five six
+                seven
+
+                eight"},
+            Mode::Normal,
+        );
+        cx.simulate_keystrokes(["g", "q", "i", "p"]);
+        cx.assert_state(
+            indoc! {"
+                one two three four
+                ˇfive six seven
+
+                eight"},
+            Mode::Normal,
+        );
+
+        cx.set_state(
+            indoc! {"
+                ˇa b
+                c d"},
+            Mode::Normal,
+        );
+        cx.simulate_keystrokes(["g", "q", "q"]);
+        cx.assert_state(
+            indoc! {"
+                ˇa b
+                c d"},
+            Mode::Normal,
+        );
+        cx.simulate_keystrokes(["shift-v", "j", "g", "q"]);
+        cx.assert_state("ˇa b c d", Mode::Normal);
+    }
+
+    #[gpui::test]
+    async fn test_rewrap_comments(cx: &mut gpui::TestAppContext) {
+        let mut cx = rewrap_test_context(cx).await;
+
+        // the comment leader is kept, and comments of different kinds aren't joined
+        cx.set_state(
+            indoc! {"
+                ˇ    // one two three four
+                    // five
+                    //
+                    /// six seven eight nine
+                    //! ten"},
+            Mode::Normal,
+        );
+        cx.simulate_keystrokes(["g", "q", "4", "j"]);
+        cx.assert_state(
+            indoc! {"
+                    // one two three
+                    // four five
+                    //
+                    /// six seven
+                    /// eight nine
+                    ˇ//! ten"},
+            Mode::Normal,
+        );
+
+        // block comments continue with a star
+        cx.set_state(
+            indoc! {"
+                ˇ/** one two three four five
+                 * six
+                 */"},
+            Mode::Normal,
+        );
+        cx.simulate_keystrokes(["g", "q", "2", "j"]);
+        cx.assert_state(
+            indoc! {"
+                /** one two three
+                 * four five six
+                 ˇ*/"},
+            Mode::Normal,
+        );
+
+        // outside of one, a star is text, like a dereference
+        cx.set_state("ˇ*ptr = a + b + c + d + e;", Mode::Normal);
+        cx.simulate_keystrokes(["g", "q", "q"]);
+        cx.assert_state("*ptr = a + b + c + d\nˇ+ e;", Mode::Normal);
+    }
+
+    #[gpui::test]
+    async fn test_formatoptions(cx: &mut gpui::TestAppContext) {
+        let mut cx = rewrap_test_context(cx).await;
+
+        // unset, they leave whether enter continues a comment to the editor's setting
+        cx.set_state("// oneˇ", Mode::Insert);
+        cx.simulate_keystrokes(["enter"]);
+        cx.assert_state("// one\n// ˇ", Mode::Insert);
+        cx.update_global(|store: &mut SettingsStore, cx| {
+            store.update_user_settings::<AllLanguageSettings>(cx, |settings| {
+                settings.defaults.extend_comment_on_newline = Some(false);
+            });
+        });
+        cx.set_state("// oneˇ", Mode::Insert);
+        cx.simulate_keystrokes(["enter"]);
+        cx.assert_state("// one\nˇ", Mode::Insert);
+
+        cx.update_global(|store: &mut SettingsStore, cx| {
+            store.update_user_settings::<VimSettings>(cx, |s| {
+                s.formatoptions = Some("tcqjro".to_string());
+                s.textwidth = Some(10);
+            });
+        });
+
+        // o and O continue a comment
+        cx.set_state("// oneˇ", Mode::Normal);
+        cx.simulate_keystrokes(["o"]);
+        cx.assert_state("// one\n// ˇ", Mode::Insert);
+        cx.set_state("let ˇa = 1;", Mode::Normal);
+        cx.simulate_keystrokes(["o"]);
+        cx.assert_state("let a = 1;\nˇ", Mode::Insert);
+        cx.set_state("*p = ˇ1;", Mode::Normal);
+        cx.simulate_keystrokes(["o"]);
+        cx.assert_state("*p = 1;\nˇ", Mode::Insert);
+
+        // typing past the textwidth wraps the line, continuing a comment
+        cx.set_state("ˇ", Mode::Insert);
+        cx.simulate_input("one two three");
+        cx.assert_state("one two\nthreeˇ", Mode::Insert);
+        cx.set_state("// ˇ", Mode::Insert);
+        cx.simulate_input("one two three");
+        cx.assert_state("// one two\n// threeˇ", Mode::Insert);
+
+        // gq rewraps to the textwidth, and J drops the comment leader
+        cx.set_state("ˇone two three", Mode::Normal);
+        cx.simulate_keystrokes(["g", "q", "q"]);
+        cx.assert_state("one two\nˇthree", Mode::Normal);
+        cx.set_state("ˇ// one\n// two", Mode::Normal);
+        cx.simulate_keystrokes(["shift-j"]);
+        cx.assert_state("// oneˇ two", Mode::Normal);
+
+        // a language's options replace the global ones
+        cx.update_global(|store: &mut SettingsStore, cx| {
+            store.update_user_settings::<VimSettings>(cx, |s| {
+                s.languages = Some(
+                    [(
+                        "Rust".to_string(),
+                        crate::LanguageOptions {
+                            formatoptions: Some("t".to_string()),
+                            ..Default::default()
+                        },
+                    )]
+                    .into(),
+                );
+            });
+        });
+        cx.set_state("// oneˇ", Mode::Normal);
+        cx.simulate_keystrokes(["o"]);
+        cx.assert_state("// one\nˇ", Mode::Insert);
+        cx.set_state("// ˇ", Mode::Insert);
+        cx.simulate_input("one two three");
+        cx.assert_state("// one two threeˇ", Mode::Insert);
+        cx.set_state("ˇ// one two three", Mode::Normal);
+        cx.simulate_keystrokes(["g", "q", "q"]);
+        cx.assert_state("ˇ// one two three", Mode::Normal);
+        cx.set_state("ˇ// one\n// two", Mode::Normal);
+        cx.simulate_keystrokes(["shift-j"]);
+        cx.assert_state("// oneˇ // two", Mode::Normal);
+        cx.set_state("// oneˇ", Mode::Insert);
+        cx.simulate_keystrokes(["enter"]);
+        cx.assert_state("// one\nˇ", Mode::Insert);
+
+        // and when the buffer's language changes, the options change with it
+        let c = Arc::new(Language::new(
+            LanguageConfig {
+                name: "C".into(),
+                line_comments: vec!["// ".into()],
+                ..Default::default()
+            },
+            None,
+        ));
+        cx.set_state("// oneˇ", Mode::Insert);
+        cx.update_editor(|editor, cx| {
+            let buffer = editor.buffer().read(cx).as_singleton().unwrap();
+            buffer.update(cx, |buffer, cx| buffer.set_language(Some(c), cx));
+        });
+        cx.simulate_keystrokes(["enter"]);
+        cx.assert_state("// one\n// ˇ", Mode::Insert);
+    }
+
+    #[gpui::test]
+    async fn test_formatprg(cx: &mut gpui::TestAppContext) {
+        let mut cx = rewrap_test_context(cx).await;
+        cx.update_global(|store: &mut SettingsStore, cx| {
+            store.update_user_settings::<VimSettings>(cx, |s| {
+                s.formatprg = Some([("Rust".to_string(), "tr a-z A-Z".to_string())].into());
+            });
+        });
+
+        cx.set_state(
+            indoc! {"
+                ˇone two three four five six
+                seven
+
+                eight"},
+            Mode::Normal,
+        );
+        cx.simulate_keystrokes(["g", "q", "i", "p"]);
+        cx.run_until_parked();
+        cx.assert_state(
+            indoc! {"
+                ONE TWO THREE FOUR FIVE SIX
+                ˇSEVEN
+
+                eight"},
+            Mode::Normal,
+        );
+        cx.simulate_keystrokes(["u"]);
+        cx.assert_state(
+            indoc! {"
+                ˇone two three four five six
+                seven
+
+                eight"},
+            Mode::Normal,
+        );
+
+        // a command that fails leaves the lines as they are
+        cx.update_global(|store: &mut SettingsStore, cx| {
+            store.update_user_settings::<VimSettings>(cx, |s| {
+                s.formatprg = Some(
+                    [(
+                        "Rust".to_string(),
+                        "echo 'no formatter' >&2; exit 1".to_string(),
+                    )]
+                    .into(),
+                );
+            });
+        });
+        cx.simulate_keystrokes(["g", "q", "q"]);
+        cx.run_until_parked();
+        cx.assert_state(
+            indoc! {"
+                ˇone two three four five six
+                seven
+
+                eight"},
+            Mode::Normal,
+        );
+        cx.update(|cx| {
+            assert_eq!(
+                Vim::read(cx).workspace_state.message.as_deref(),
+                Some("no formatter")
+            )
+        });
+    }
+}
@@ -1,15 +1,36 @@
-use gpui::{actions, impl_actions, ViewContext};
+use std::{ops::Range, sync::Arc, time::Duration};
+
+use anyhow::anyhow;
+use editor::{scroll::Autoscroll, Anchor, Editor, MultiBufferSnapshot, ToPoint};
+use gpui::{actions, impl_actions, ViewContext, WindowContext};
+use language::Point;
+use regex::{Captures, Regex, RegexBuilder};
 use search::{buffer_search, BufferSearchBar, SearchMode, SearchOptions};
 use serde_derive::Deserialize;
+use settings::Settings;
 use workspace::{searchable::Direction, Workspace};
 
 use crate::{
-    motion::Motion,
-    normal::move_cursor,
-    state::{Mode, SearchState},
-    Vim,
+    completion::keyword_at_cursor,
+    hlsearch::highlight_search,
+    motion::{self, Motion},
+    state::{Mode, ReplayableAction, SearchState},
+    utils::{end_undo_group, UndoGroup},
+    visual::visual_selection_ranges,
+    Vim, VimSettings,
 };
 
+/// Like vim's, `\%V` in a pattern limits it to the last visual selection.
+const VISUAL_SCOPE: &str = r"\%V";
+/// From this many bytes, `:%s` substitutes in the background
+const LARGE_BUFFER_LEN: usize = 1 << 20;
+/// How many lines of a large buffer are searched at a time
+const SUBSTITUTE_CHUNK_ROWS: u32 = 10_000;
+/// How many lines past the end of a chunk a match across lines can run on
+const MULTILINE_MATCH_ROWS: u32 = 100;
+/// How many replacements are made in a large buffer at a time
+const SUBSTITUTE_BATCH_LEN: usize = 10_000;
+
 #[derive(Clone, Deserialize, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub(crate) struct MoveToNext {
@@ -28,6 +49,17 @@ pub(crate) struct MoveToPrev {
 pub(crate) struct Search {
     #[serde(default)]
     backwards: bool,
+    /// Searches only in the function around the cursor (`g/`).
+    #[serde(default)]
+    in_function: bool,
+}
+
+/// A search typed after `/` or `?` while recording a macro, which replaying the
+/// macro submits again.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub(crate) struct ReplaySearch {
+    query: String,
+    backwards: bool,
 }
 
 #[derive(Debug, Clone, PartialEq, Deserialize)]
@@ -47,12 +79,22 @@ struct Replacement {
     replacement: String,
     should_replace_all: bool,
     is_case_sensitive: bool,
+    /// `%s` changes the whole buffer, and `s` the current line, or the visual
+    /// selection.
+    whole_buffer: bool,
 }
 
 actions!(vim, [SearchSubmit]);
 impl_actions!(
     vim,
-    [FindCommand, ReplaceCommand, Search, MoveToPrev, MoveToNext]
+    [
+        FindCommand,
+        ReplaceCommand,
+        Search,
+        ReplaySearch,
+        MoveToPrev,
+        MoveToNext
+    ]
 );
 
 pub(crate) fn register(workspace: &mut Workspace, _: &mut ViewContext<Workspace>) {
@@ -60,6 +102,7 @@ pub(crate) fn register(workspace: &mut Workspace, _: &mut ViewContext<Workspace>
     workspace.register_action(move_to_prev);
     workspace.register_action(search);
     workspace.register_action(search_submit);
+    workspace.register_action(replay_search);
     workspace.register_action(search_deploy);
 
     workspace.register_action(find_command);
@@ -83,6 +126,44 @@ fn search(workspace: &mut Workspace, action: &Search, cx: &mut ViewContext<Works
     };
     Vim::update(cx, |vim, cx| {
         let count = vim.take_count(cx).unwrap_or(1);
+        let scope = if action.in_function {
+            let scope = vim
+                .update_active_editor(cx, |_, editor, cx| function_scope(editor, cx))
+                .flatten();
+            let Some(scope) = scope else {
+                vim.show_message("Not in a function");
+                return;
+            };
+            Some(vec![scope])
+        } else if vim.state().mode.is_visual() && VimSettings::get_global(cx).search_in_selection {
+            let selection =
+                vim.update_active_editor(cx, |_, editor, cx| visual_selection_ranges(editor, cx));
+            vim.switch_mode(Mode::Normal, false, cx);
+            selection
+        } else {
+            None
+        };
+        vim.update_active_editor(cx, |_, editor, cx| editor.set_search_scope(scope, cx));
+        // The operator waits for the search to be submitted, rather than being
+        // cleared when the buffer loses focus to the search bar.
+        let prior_operator = vim.active_operator();
+        let prior_register = vim.workspace_state.selected_register;
+        let prior_selections = if prior_operator.is_some() {
+            vim.update_state(|state| state.operator_stack.clear());
+            vim.workspace_state.selected_register = None;
+            vim.sync_vim_settings(cx);
+            vim.update_active_editor(cx, |_, editor, _| {
+                editor
+                    .selections
+                    .disjoint_anchors()
+                    .iter()
+                    .map(|selection| selection.range())
+                    .collect()
+            })
+            .unwrap_or_default()
+        } else {
+            Vec::new()
+        };
         pane.update(cx, |pane, cx| {
             if let Some(search_bar) = pane.toolbar().read(cx).item_of_type::<BufferSearchBar>() {
                 search_bar.update(cx, |search_bar, cx| {
@@ -102,6 +183,9 @@ fn search(workspace: &mut Workspace, action: &Search, cx: &mut ViewContext<Works
                         direction,
                         count,
                         initial_query: query.clone(),
+                        prior_operator,
+                        prior_register,
+                        prior_selections,
                     };
                 });
             }
@@ -111,13 +195,48 @@ fn search(workspace: &mut Workspace, action: &Search, cx: &mut ViewContext<Works
 
 // hook into the existing to clear out any vim search state on cmd+f or edit -> find.
 fn search_deploy(_: &mut Workspace, _: &buffer_search::Deploy, cx: &mut ViewContext<Workspace>) {
-    Vim::update(cx, |vim, _| vim.workspace_state.search = Default::default());
+    Vim::update(cx, |vim, cx| {
+        vim.workspace_state.search = Default::default();
+        vim.update_active_editor(cx, |_, editor, cx| editor.set_search_scope(None, cx));
+    });
     cx.propagate();
 }
 
 fn search_submit(workspace: &mut Workspace, _: &SearchSubmit, cx: &mut ViewContext<Workspace>) {
-    Vim::update(cx, |vim, cx| {
+    let Some(search_bar) = workspace
+        .active_pane()
+        .read(cx)
+        .toolbar()
+        .read(cx)
+        .item_of_type::<BufferSearchBar>()
+    else {
+        return;
+    };
+    let query = search_bar.read(cx).query(cx);
+    if query.contains(VISUAL_SCOPE) {
+        // the search is run again inside the selection, and then submitted
+        let query = scope_query(&query, cx);
+        let search = search_bar.update(cx, |search_bar, cx| search_bar.search(&query, None, cx));
+        cx.spawn(|workspace, mut cx| async move {
+            let found = search.await;
+            workspace.update(&mut cx, |workspace, cx| {
+                if found.is_ok() {
+                    search_bar.update(cx, |search_bar, cx| search_bar.activate_current_match(cx));
+                    search_submit(workspace, &SearchSubmit, cx);
+                }
+            })
+        })
+        .detach_and_log_err(cx);
+        return;
+    }
+
+    let jump = Vim::update(cx, |vim, cx| {
         let pane = workspace.active_pane().clone();
+        let state = &mut vim.workspace_state.search;
+        let prior_operator = state.prior_operator.take();
+        let prior_register = state.prior_register.take();
+        let prior_selections = std::mem::take(&mut state.prior_selections);
+        let mut found = false;
         pane.update(cx, |pane, cx| {
             if let Some(search_bar) = pane.toolbar().read(cx).item_of_type::<BufferSearchBar>() {
                 search_bar.update(cx, |search_bar, cx| {
@@ -133,11 +252,152 @@ fn search_submit(workspace: &mut Workspace, _: &SearchSubmit, cx: &mut ViewConte
                         count = count.saturating_sub(1)
                     }
                     state.count = 1;
+                    if vim.workspace_state.recording_prompt
+                        && vim.workspace_state.recording_register.is_some()
+                        && !vim.is_replaying()
+                    {
+                        vim.workspace_state
+                            .recorded_macro
+                            .push(ReplayableAction::Action(Box::new(ReplaySearch {
+                                query: search_bar.query(cx),
+                                backwards: direction == Direction::Prev,
+                            })));
+                    }
+                    vim.workspace_state.record_search(search_bar.query(cx));
+                    found = search_bar.active_match_index().is_some();
                     search_bar.select_match(direction, count, cx);
                     search_bar.focus_editor(&Default::default(), cx);
                 });
             }
         });
+
+        let operator = prior_operator?;
+        // The operator applies from where the cursor was before the search to the
+        // start of the match, and is abandoned if there was no match.
+        let match_start = vim.update_active_editor(cx, |_, editor, cx| {
+            let match_start = editor.selections.newest_anchor().start;
+            editor.change_selections(None, cx, |s| s.select_anchor_ranges(prior_selections));
+            match_start
+        })?;
+        if !found {
+            vim.show_message("E486: Pattern not found");
+            return None;
+        }
+        vim.workspace_state.selected_register = prior_register;
+        vim.push_operator(operator, cx);
+        Some(match_start)
+    });
+
+    highlight_search(workspace, cx);
+    if let Some(anchor) = jump {
+        motion::motion(
+            Motion::Jump {
+                anchor,
+                line: false,
+            },
+            cx,
+        )
+    }
+}
+
+/// Opens the search bar as `/` or `?` does, and submits the query once its matches
+/// have been found. The replay waits for that before running its next step.
+fn replay_search(
+    workspace: &mut Workspace,
+    action: &ReplaySearch,
+    cx: &mut ViewContext<Workspace>,
+) {
+    search(
+        workspace,
+        &Search {
+            backwards: action.backwards,
+            in_function: false,
+        },
+        cx,
+    );
+    let Some(search_bar) = workspace
+        .active_pane()
+        .read(cx)
+        .toolbar()
+        .read(cx)
+        .item_of_type::<BufferSearchBar>()
+    else {
+        return;
+    };
+    let search = search_bar.update(cx, |search_bar, cx| {
+        search_bar.search(&action.query, None, cx)
+    });
+    let replayer = Vim::read(cx).workspace_state.replayer.clone();
+    if let Some(replayer) = &replayer {
+        replayer.pause();
+    }
+    cx.spawn(|workspace, mut cx| async move {
+        let found = search.await;
+        workspace.update(&mut cx, |workspace, cx| {
+            if found.is_ok() {
+                search_bar.update(cx, |search_bar, cx| search_bar.activate_current_match(cx));
+                search_submit(workspace, &SearchSubmit, cx);
+            }
+            if let Some(replayer) = replayer {
+                replayer.resume(cx);
+            }
+        })
+    })
+    .detach_and_log_err(cx);
+}
+
+/// Takes `\%V` out of a query, as Zed's regex syntax doesn't have it, and limits
+/// the search to the last visual selection (or the visual selection, in visual mode)
+/// if it was there.
+fn scope_query(query: &str, cx: &mut WindowContext) -> String {
+    if !query.contains(VISUAL_SCOPE) {
+        return query.to_string();
+    }
+    Vim::update(cx, |vim, cx| {
+        let scope = visual_scope(vim, cx);
+        vim.update_active_editor(cx, |_, editor, cx| editor.set_search_scope(Some(scope), cx));
+    });
+    query.replace(VISUAL_SCOPE, "")
+}
+
+fn visual_scope(vim: &mut Vim, cx: &mut WindowContext) -> Vec<Range<Anchor>> {
+    if vim.state().mode.is_visual() {
+        vim.update_active_editor(cx, |_, editor, cx| visual_selection_ranges(editor, cx))
+            .unwrap_or_default()
+    } else {
+        vim.state().last_visual_selection.clone()
+    }
+}
+
+/// The innermost item of the language's outline (like a function) around the cursor.
+fn function_scope(editor: &Editor, cx: &WindowContext) -> Option<Range<Anchor>> {
+    let snapshot = editor.buffer().read(cx).snapshot(cx);
+    let cursor = editor.selections.newest_anchor().head();
+    let (_, items) = snapshot.symbols_containing(cursor, None)?;
+    items
+        .into_iter()
+        .rev()
+        .map(|item| item.range)
+        .find(|range| {
+            range.start.cmp(&cursor, &snapshot).is_le() && cursor.cmp(&range.end, &snapshot).is_le()
+        })
+}
+
+/// Escaping from a search started after an operator (as in `d/`) abandons the
+/// operator, and puts the cursor back where it was. The search's scope is no longer
+/// highlighted.
+pub(crate) fn search_dismissed(cx: &mut WindowContext) {
+    Vim::update(cx, |vim, cx| {
+        vim.update_active_editor(cx, |_, editor, cx| editor.set_search_scope(None, cx));
+        let state = &mut vim.workspace_state.search;
+        if state.prior_operator.take().is_none() {
+            return;
+        }
+        state.prior_register = None;
+        let prior_selections = std::mem::take(&mut state.prior_selections);
+        vim.update_active_editor(cx, |_, editor, cx| {
+            editor.change_selections(None, cx, |s| s.select_anchor_ranges(prior_selections))
+        });
     })
 }
 
@@ -147,9 +407,19 @@ pub fn move_to_internal(
     whole_word: bool,
     cx: &mut ViewContext<Workspace>,
 ) {
+    let workspace_handle = cx.view().downgrade();
     Vim::update(cx, |vim, cx| {
         let pane = workspace.active_pane().clone();
         let count = vim.take_count(cx).unwrap_or(1);
+        vim.update_active_editor(cx, |_, editor, cx| editor.set_search_scope(None, cx));
+        // a keyword with characters its language's `iskeyword` adds, which the search
+        // bar doesn't know about
+        let keyword = if vim.state().mode.is_visual() {
+            None
+        } else {
+            vim.update_active_editor(cx, |_, editor, cx| keyword_at_cursor(editor, cx))
+                .flatten()
+        };
 
         pane.update(cx, |pane, cx| {
             if let Some(search_bar) = pane.toolbar().read(cx).item_of_type::<BufferSearchBar>() {
@@ -158,13 +428,25 @@ pub fn move_to_internal(
                     if !search_bar.show(cx) {
                         return None;
                     }
-                    let Some(query) = search_bar.query_suggestion(cx) else {
-                        return None;
+                    let (query, extra) = match keyword.clone() {
+                        Some(keyword) => keyword,
+                        None => (search_bar.query_suggestion(cx)?, Vec::new()),
                     };
-                    let mut query = regex::escape(&query);
+                    let mut escaped = regex::escape(&query);
                     if whole_word {
-                        query = format!(r"\b{}\b", query);
+                        // `\b` doesn't match next to the characters `iskeyword` adds
+                        let boundary = |c: Option<char>| match c {
+                            Some(c) if extra.contains(&c) => "",
+                            _ => r"\b",
+                        };
+                        escaped = format!(
+                            "{}{}{}",
+                            boundary(query.chars().next()),
+                            escaped,
+                            boundary(query.chars().last())
+                        );
                     }
+                    let query = escaped;
                     search_bar.activate_search_mode(SearchMode::Regex, cx);
                     Some(search_bar.search(&query, Some(options), cx))
                 });
@@ -176,6 +458,7 @@ pub fn move_to_internal(
                         search_bar.update(&mut cx, |search_bar, cx| {
                             search_bar.select_match(direction, count, cx)
                         })?;
+                        workspace_handle.update(&mut cx, highlight_search)?;
                         anyhow::Ok(())
                     })
                     .detach_and_log_err(cx);
@@ -193,6 +476,7 @@ pub fn move_to_internal(
 
 fn find_command(workspace: &mut Workspace, action: &FindCommand, cx: &mut ViewContext<Workspace>) {
     let pane = workspace.active_pane().clone();
+    let workspace_handle = cx.view().downgrade();
     pane.update(cx, |pane, cx| {
         if let Some(search_bar) = pane.toolbar().read(cx).item_of_type::<BufferSearchBar>() {
             let search = search_bar.update(cx, |search_bar, cx| {
@@ -203,6 +487,11 @@ fn find_command(workspace: &mut Workspace, action: &FindCommand, cx: &mut ViewCo
                 if query == "" {
                     query = search_bar.query(cx);
                 };
+                Vim::update(cx, |vim, cx| {
+                    vim.workspace_state.record_search(query.clone());
+                    vim.update_active_editor(cx, |_, editor, cx| editor.set_search_scope(None, cx));
+                });
+                let query = scope_query(&query, cx);
 
                 search_bar.activate_search_mode(SearchMode::Regex, cx);
                 Some(search_bar.search(&query, Some(SearchOptions::CASE_SENSITIVE), cx))
@@ -219,6 +508,7 @@ fn find_command(workspace: &mut Workspace, action: &FindCommand, cx: &mut ViewCo
                 search_bar.update(&mut cx, |search_bar, cx| {
                     search_bar.select_match(direction, 1, cx)
                 })?;
+                workspace_handle.update(&mut cx, highlight_search)?;
                 anyhow::Ok(())
             })
             .detach_and_log_err(cx);
@@ -231,66 +521,410 @@ fn replace_command(
     action: &ReplaceCommand,
     cx: &mut ViewContext<Workspace>,
 ) {
-    let replacement = parse_replace_all(&action.query);
+    let mut replacement = parse_replace_all(&action.query);
     let pane = workspace.active_pane().clone();
-    pane.update(cx, |pane, cx| {
-        let Some(search_bar) = pane.toolbar().read(cx).item_of_type::<BufferSearchBar>() else {
+    let Some(search_bar) = pane
+        .read(cx)
+        .toolbar()
+        .read(cx)
+        .item_of_type::<BufferSearchBar>()
+    else {
+        return;
+    };
+    if replacement.search == "" {
+        replacement.search = search_bar.read(cx).query(cx);
+    }
+    if replacement.search == "" {
+        log::error!("E35: No previous regular expression");
+        return;
+    }
+    Vim::update(cx, |vim, _| {
+        replacement.replacement = expand_previous_replacement(
+            &replacement.replacement,
+            vim.workspace_state
+                .last_replacement
+                .as_deref()
+                .unwrap_or_default(),
+        );
+        vim.workspace_state.last_replacement = Some(replacement.replacement.clone());
+    });
+
+    if replacement.should_replace_all {
+        Vim::update(cx, |vim, cx| {
+            if let Err(err) = substitute_all(vim, &replacement, cx) {
+                log::error!("{err}");
+            }
+        });
+        return;
+    }
+
+    // with the c or n flags, we let the user step through the matches in the search bar.
+    search_bar.update(cx, |search_bar, cx| {
+        if !search_bar.show(cx) {
             return;
+        }
+
+        let mut options = SearchOptions::default();
+        if replacement.is_case_sensitive {
+            options.set(SearchOptions::CASE_SENSITIVE, true)
+        }
+
+        search_bar.set_replacement(Some(&zed_replacement(&replacement.replacement)), cx);
+        search_bar.activate_search_mode(SearchMode::Regex, cx);
+        let query = scope_query(&replacement.search, cx);
+        let _ = search_bar.search(&query, Some(options), cx);
+    });
+}
+
+fn substitute_all(
+    vim: &mut Vim,
+    replacement: &Replacement,
+    cx: &mut WindowContext,
+) -> anyhow::Result<()> {
+    let regex = RegexBuilder::new(&replacement.search.replace(VISUAL_SCOPE, ""))
+        .case_insensitive(!replacement.is_case_sensitive)
+        .multi_line(true)
+        .build()?;
+    let substitution = Substitution::parse(&replacement.replacement)?;
+
+    // matches are only replaced inside the last visual selection with \%V, and
+    // otherwise inside the scope of the last search, while it's highlighted
+    let scope = if replacement.search.contains(VISUAL_SCOPE) {
+        Some(visual_scope(vim, cx))
+    } else {
+        vim.update_active_editor(cx, |_, editor, _| {
+            editor.search_scope().map(|scope| scope.to_vec())
+        })
+        .flatten()
+    };
+
+    // the columns of each line that can be changed
+    let mode = vim.state().mode;
+    let Some((snapshot, lines, scope)) = vim.update_active_editor(cx, |_, editor, cx| {
+        let snapshot = editor.buffer().read(cx).snapshot(cx);
+        let scope = scope.map(|scope| {
+            scope
+                .iter()
+                .map(|range| range.start.to_point(&snapshot)..range.end.to_point(&snapshot))
+                .collect::<Vec<_>>()
+        });
+        let lines = if replacement.whole_buffer {
+            // the lines of very large buffers are only gone through in the background,
+            // a chunk at a time
+            if snapshot.len() >= LARGE_BUFFER_LEN {
+                None
+            } else {
+                Some(
+                    (0..=snapshot.max_point().row)
+                        .map(|row| (row, 0..snapshot.line_len(row)))
+                        .collect::<Vec<_>>(),
+                )
+            }
+        } else if mode == Mode::VisualBlock {
+            // like vim's \%V, only matches inside the block are replaced
+            Some(
+                editor
+                    .selections
+                    .all::<Point>(cx)
+                    .into_iter()
+                    .map(|selection| {
+                        (
+                            selection.start.row,
+                            selection.start.column..selection.end.column,
+                        )
+                    })
+                    .collect(),
+            )
+        } else {
+            // with multiple cursors (or selections), the lines of each of them, with
+            // those that several share substituted in once
+            let mut lines = Vec::new();
+            for selection in editor.selections.all::<Point>(cx) {
+                let mut end_row = selection.end.row;
+                if end_row > selection.start.row && selection.end.column == 0 {
+                    end_row -= 1;
+                }
+                let start_row = match lines.last() {
+                    Some((row, _)) => selection.start.row.max(row + 1),
+                    None => selection.start.row,
+                };
+                lines.extend((start_row..=end_row).map(|row| (row, 0..snapshot.line_len(row))));
+            }
+            Some(lines)
         };
-        let search = search_bar.update(cx, |search_bar, cx| {
-            if !search_bar.show(cx) {
-                return None;
+        (snapshot, lines, scope)
+    }) else {
+        return Ok(());
+    };
+    if mode.is_visual() {
+        vim.switch_mode(Mode::Normal, false, cx);
+    }
+
+    let Some(lines) = lines else {
+        substitute_in_background(vim, snapshot, regex, substitution, scope, cx);
+        return Ok(());
+    };
+    let edits = find_substitutions(&snapshot, lines, &regex, &substitution, scope.as_deref())?;
+    vim.update_active_editor(cx, |_, editor, cx| {
+        apply_substitutions(editor, &snapshot, edits, cx)
+    });
+    Ok(())
+}
+
+/// Substitutes in a very large buffer (like `:%s` on a log file of hundreds of MB)
+/// without blocking the editor: the matches are found in the background a chunk
+/// of lines at a time, and then replaced a batch at a time, with the progress shown
+/// in the status bar. The batches are grouped into one undo step as they're made.
+/// `ctrl-c` or `escape` abandons it, and so does an edit made in the meantime,
+/// leaving the replacements already made (which `u` undoes).
+fn substitute_in_background(
+    vim: &mut Vim,
+    snapshot: MultiBufferSnapshot,
+    regex: Regex,
+    substitution: Substitution,
+    scope: Option<Vec<Range<Point>>>,
+    cx: &mut WindowContext,
+) {
+    if vim.refuse_pending_operation() {
+        return;
+    }
+    let multiline = is_multiline(&regex);
+    let search = Arc::new((regex, substitution, scope));
+    let max_row = snapshot.max_point().row;
+    vim.show_progress("Substituting: 0%");
+    let task = vim.update_active_editor(cx, |_, _, cx| {
+        cx.spawn(|editor, mut cx| async move {
+            let mut edits: Vec<(Range<Point>, String)> = Vec::new();
+            for chunk_start in (0..=max_row).step_by(SUBSTITUTE_CHUNK_ROWS as usize) {
+                let chunk_end = max_row.min(chunk_start + SUBSTITUTE_CHUNK_ROWS - 1);
+                // a match across lines that starts in the chunk can end in the next one
+                let lines_end = if multiline {
+                    max_row.min(chunk_end + MULTILINE_MATCH_ROWS)
+                } else {
+                    chunk_end
+                };
+                let chunk_edits = cx
+                    .background_executor()
+                    .spawn({
+                        let snapshot = snapshot.clone();
+                        let search = search.clone();
+                        async move {
+                            let (regex, substitution, scope) = &*search;
+                            let lines = (chunk_start..=lines_end)
+                                .map(|row| (row, 0..snapshot.line_len(row)));
+                            find_substitutions(
+                                &snapshot,
+                                lines,
+                                regex,
+                                substitution,
+                                scope.as_deref(),
+                            )
+                        }
+                    })
+                    .await;
+                let result = cx.update(|cx| {
+                    Vim::update(cx, |vim, _| match chunk_edits {
+                        Ok(chunk_edits) => {
+                            // the matches starting in the chunk, after the last one of
+                            // the previous chunk (which can run into this one)
+                            let last_end = edits.last().map(|(range, _)| range.end);
+                            edits.extend(chunk_edits.into_iter().filter(|(range, _)| {
+                                range.start.row <= chunk_end
+                                    && last_end.map_or(true, |end| range.start >= end)
+                            }));
+                            let percent = (chunk_end as u64 + 1) * 50 / (max_row as u64 + 1);
+                            vim.show_progress(format!("Substituting: {percent}%"));
+                            Ok(())
+                        }
+                        Err(err) => {
+                            vim.finish_pending_operation();
+                            vim.show_message(err.to_string());
+                            Err(())
+                        }
+                    })
+                });
+                if !matches!(result, Ok(Ok(()))) {
+                    return;
+                }
             }
 
-            let mut options = SearchOptions::default();
-            if replacement.is_case_sensitive {
-                options.set(SearchOptions::CASE_SENSITIVE, true)
+            // the batches are made from the end of the buffer back, so that the
+            // replacements still to make aren't moved by those made
+            let Some((last_range, _)) = edits.last() else {
+                cx.update(|cx| Vim::update(cx, |vim, _| vim.finish_pending_operation()))
+                    .ok();
+                return;
+            };
+            let last_row = last_range.start.row;
+            let total = edits.len();
+            let mut edit_count = snapshot.edit_count();
+            let Ok(undo_group) = editor.update(&mut cx, |editor, cx| UndoGroup::new(editor, cx))
+            else {
+                return;
+            };
+            while !edits.is_empty() {
+                let batch = edits.split_off(edits.len().saturating_sub(SUBSTITUTE_BATCH_LEN));
+                let made = editor.update(&mut cx, |editor, cx| {
+                    let current = editor.buffer().read(cx).snapshot(cx);
+                    if current.edit_count() != edit_count {
+                        Vim::update(cx, |vim, _| {
+                            vim.finish_pending_operation();
+                            vim.show_message("Substitute abandoned: the buffer changed");
+                        });
+                        return false;
+                    }
+                    editor.transact(cx, |editor, cx| editor.edit(batch, cx));
+                    edit_count = editor.buffer().read(cx).snapshot(cx).edit_count();
+                    let percent = 50 + (total - edits.len()) * 50 / total;
+                    Vim::update(cx, |vim, _| {
+                        vim.show_progress(format!("Substituting: {percent}%"))
+                    });
+                    true
+                });
+                if !matches!(made, Ok(true)) {
+                    return;
+                }
+                // each batch joins the undo step as it's made, in case the rest
+                // are abandoned
+                if cx.update(|cx| end_undo_group(&undo_group, cx)).is_err() {
+                    return;
+                }
+                // lets the editor take input between batches
+                cx.background_executor().timer(Duration::ZERO).await;
             }
-            let search = if replacement.search == "" {
-                search_bar.query(cx)
-            } else {
-                replacement.search
+
+            editor
+                .update(&mut cx, |editor, cx| {
+                    editor.change_selections(Some(Autoscroll::fit()), cx, |s| {
+                        s.select_ranges([Point::new(last_row, 0)..Point::new(last_row, 0)])
+                    });
+                    Vim::update(cx, |vim, _| vim.finish_pending_operation());
+                })
+                .ok();
+        })
+    });
+    vim.pending_operation = task;
+}
+
+/// Whether the pattern has a `\n` in it, so its matches can span lines.
+fn is_multiline(regex: &Regex) -> bool {
+    regex.as_str().contains("\\n")
+}
+
+/// The replacements for the matches in each line (and the columns of it that can
+/// be changed), that are inside the scope, if there is one. A pattern with `\n` in
+/// it is matched against each run of consecutive lines at once (and the line break
+/// after it, as in vim), rather than line by line.
+fn find_substitutions(
+    snapshot: &MultiBufferSnapshot,
+    lines: impl IntoIterator<Item = (u32, Range<u32>)>,
+    regex: &Regex,
+    substitution: &Substitution,
+    scope: Option<&[Range<Point>]>,
+) -> anyhow::Result<Vec<(Range<Point>, String)>> {
+    let lines = lines.into_iter().collect::<Vec<_>>();
+    let multiline = is_multiline(regex);
+    let mut runs = Vec::new();
+    let mut run_start = 0;
+    for ix in 1..=lines.len() {
+        if ix == lines.len() || !multiline || lines[ix].0 != lines[ix - 1].0 + 1 {
+            runs.push(&lines[run_start..ix]);
+            run_start = ix;
+        }
+    }
+
+    let mut edits = Vec::new();
+    for run in runs {
+        let first_row = run[0].0;
+        let last_row = run[run.len() - 1].0;
+        let run_start = Point::new(first_row, 0);
+        let run_end = if multiline && last_row < snapshot.max_point().row {
+            Point::new(last_row + 1, 0)
+        } else {
+            Point::new(last_row, snapshot.line_len(last_row))
+        };
+        let offset = snapshot.point_to_offset(run_start);
+        let text = snapshot
+            .text_for_range(run_start..run_end)
+            .collect::<String>();
+        // the columns of the row that can be changed, all of them for the line after
+        // the run
+        let columns = |row: u32| run.get((row - first_row) as usize).map(|(_, c)| c.clone());
+        for captures in regex.captures_iter(&text) {
+            let Some(range) = captures.get(0).map(|m| m.range()) else {
+                continue;
             };
+            let start = snapshot.offset_to_point(offset + range.start);
+            let end = snapshot.offset_to_point(offset + range.end);
+            if columns(start.row).is_some_and(|columns| start.column < columns.start)
+                || columns(end.row).is_some_and(|columns| end.column > columns.end)
+            {
+                continue;
+            }
+            if scope.is_some_and(|scope| {
+                !scope
+                    .iter()
+                    .any(|scope| scope.start <= start && end <= scope.end)
+            }) {
+                continue;
+            }
+            edits.push((start..end, substitution.expand(&captures, start.row + 1)?));
+        }
+    }
+    Ok(edits)
+}
 
-            search_bar.set_replacement(Some(&replacement.replacement), cx);
-            search_bar.activate_search_mode(SearchMode::Regex, cx);
-            Some(search_bar.search(&search, Some(options), cx))
+/// Makes the replacements in one transaction, and puts the cursor at the start of
+/// the last line changed.
+fn apply_substitutions(
+    editor: &mut Editor,
+    snapshot: &MultiBufferSnapshot,
+    edits: Vec<(Range<Point>, String)>,
+    cx: &mut ViewContext<Editor>,
+) {
+    let Some((last_range, _)) = edits.last() else {
+        return;
+    };
+    let last_substitution = snapshot.anchor_before(last_range.start);
+    editor.transact(cx, |editor, cx| {
+        editor.edit(edits, cx);
+        let snapshot = editor.buffer().read(cx).snapshot(cx);
+        let row = last_substitution.to_point(&snapshot).row;
+        editor.change_selections(Some(Autoscroll::fit()), cx, |s| {
+            s.select_ranges([Point::new(row, 0)..Point::new(row, 0)])
         });
-        let Some(search) = search else { return };
-        let search_bar = search_bar.downgrade();
-        cx.spawn(|_, mut cx| async move {
-            search.await?;
-            search_bar.update(&mut cx, |search_bar, cx| {
-                if replacement.should_replace_all {
-                    search_bar.select_last_match(cx);
-                    search_bar.replace_all(&Default::default(), cx);
-                    Vim::update(cx, |vim, cx| {
-                        move_cursor(
-                            vim,
-                            Motion::StartOfLine {
-                                display_lines: false,
-                            },
-                            None,
-                            cx,
-                        )
-                    })
-                }
-            })?;
-            anyhow::Ok(())
+    });
+}
+
+/// Whether the command is `:s`, rather than another command starting with s (like
+/// `:sp` or `:sort`): the pattern's delimiter can't be a letter or digit.
+pub(crate) fn is_substitute(query: &str) -> bool {
+    let query = query.strip_prefix("'<,'>").unwrap_or(query);
+    query
+        .strip_prefix('s')
+        .and_then(|rest| rest.chars().next())
+        .is_some_and(|c| {
+            !c.is_alphanumeric() && !c.is_whitespace() && !matches!(c, '\\' | '"' | '|')
         })
-        .detach_and_log_err(cx);
-    })
 }
 
 // convert a vim query into something more usable by zed.
 // we don't attempt to fully convert between the two regex syntaxes,
-// but we do flip \( and \) to ( and ) (and vice-versa) in the pattern,
-// and convert \0..\9 to $0..$9 in the replacement so that common idioms work.
+// but we do flip \( and \) to ( and ) (and vice-versa) in the pattern.
+// The replacement is left as vim wrote it (see Substitution).
 fn parse_replace_all(query: &str) -> Replacement {
-    let mut chars = query.chars();
-    if Some('%') != chars.next() || Some('s') != chars.next() {
+    let (whole_buffer, rest) = if let Some(rest) = query.strip_prefix("%s") {
+        (true, rest)
+    } else if let Some(rest) = query
+        .strip_prefix("'<,'>")
+        .unwrap_or(query)
+        .strip_prefix('s')
+    {
+        (false, rest)
+    } else {
         return Replacement::default();
-    }
+    };
+    let mut chars = rest.chars();
 
     let Some(delimiter) = chars.next() else {
         return Replacement::default();
@@ -311,10 +945,8 @@ fn parse_replace_all(query: &str) -> Replacement {
     for c in chars {
         if escaped {
             escaped = false;
-            if phase == 1 && c.is_digit(10) {
-                buffer.push('$')
-            // unescape escaped parens
-            } else if phase == 0 && c == '(' || c == ')' {
+            if phase == 0 && (c == '(' || c == ')') {
+                // unescape escaped parens
             } else if c != delimiter {
                 buffer.push('\\')
             }
@@ -333,18 +965,22 @@ fn parse_replace_all(query: &str) -> Replacement {
             }
         } else {
             // escape unescaped parens
-            if phase == 0 && c == '(' || c == ')' {
+            if phase == 0 && (c == '(' || c == ')') {
                 buffer.push('\\')
             }
             buffer.push(c)
         }
     }
+    if escaped {
+        buffer.push('\\')
+    }
 
     let mut replacement = Replacement {
         search,
         replacement,
         should_replace_all: true,
         is_case_sensitive: true,
+        whole_buffer,
     };
 
     for c in flags.chars() {
@@ -359,14 +995,259 @@ fn parse_replace_all(query: &str) -> Replacement {
     replacement
 }
 
+// an unescaped ~ in the replacement is replaced by the previous replacement.
+fn expand_previous_replacement(replacement: &str, previous: &str) -> String {
+    if replacement.starts_with("\\=") {
+        return replacement.to_string();
+    }
+    let mut result = String::new();
+    let mut chars = replacement.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => {
+                result.push(c);
+                result.extend(chars.next());
+            }
+            '~' => result.push_str(previous),
+            _ => result.push(c),
+        }
+    }
+    result
+}
+
+// convert a vim replacement into zed's syntax (\0..\9 become $0..$9).
+fn zed_replacement(replacement: &str) -> String {
+    let mut result = String::new();
+    let mut chars = replacement.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some(c) if c.is_ascii_digit() => {
+                    result.push('$');
+                    result.push(c);
+                }
+                Some(c) => {
+                    result.push('\\');
+                    result.push(c);
+                }
+                None => result.push('\\'),
+            },
+            _ => result.push(c),
+        }
+    }
+    result
+}
+
+#[derive(Debug, PartialEq)]
+enum Substitution {
+    Text(Vec<ReplacementPart>),
+    // \= followed by an expression
+    Expression(Vec<ExpressionTerm>),
+}
+
+#[derive(Debug, PartialEq)]
+enum ReplacementPart {
+    Literal(String),
+    Group(usize),
+    // \u and \l
+    UpperNext,
+    LowerNext,
+    // \U and \L, until \E or \e
+    Upper,
+    Lower,
+    EndCase,
+}
+
+// we support a very small subset of vim's expressions: string and number
+// literals, line('.') and submatch(n), concatenated with `.`.
+#[derive(Debug, PartialEq)]
+enum ExpressionTerm {
+    String(String),
+    Line,
+    Submatch(usize),
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Case {
+    Upper,
+    Lower,
+}
+
+impl Substitution {
+    fn parse(replacement: &str) -> anyhow::Result<Self> {
+        if let Some(expression) = replacement.strip_prefix("\\=") {
+            return Ok(Self::Expression(parse_expression(expression)?));
+        }
+
+        let mut parts = Vec::new();
+        let mut literal = String::new();
+        let mut chars = replacement.chars();
+        while let Some(c) = chars.next() {
+            let part = match c {
+                '&' => ReplacementPart::Group(0),
+                '\\' => match chars.next() {
+                    Some(c) if c.is_ascii_digit() => {
+                        ReplacementPart::Group(c.to_digit(10).unwrap() as usize)
+                    }
+                    Some('u') => ReplacementPart::UpperNext,
+                    Some('l') => ReplacementPart::LowerNext,
+                    Some('U') => ReplacementPart::Upper,
+                    Some('L') => ReplacementPart::Lower,
+                    Some('E') | Some('e') => ReplacementPart::EndCase,
+                    Some('n') | Some('r') => {
+                        literal.push('\n');
+                        continue;
+                    }
+                    Some('t') => {
+                        literal.push('\t');
+                        continue;
+                    }
+                    Some(c) => {
+                        literal.push(c);
+                        continue;
+                    }
+                    None => {
+                        literal.push('\\');
+                        continue;
+                    }
+                },
+                c => {
+                    literal.push(c);
+                    continue;
+                }
+            };
+            if !literal.is_empty() {
+                parts.push(ReplacementPart::Literal(std::mem::take(&mut literal)));
+            }
+            parts.push(part);
+        }
+        if !literal.is_empty() {
+            parts.push(ReplacementPart::Literal(literal));
+        }
+
+        Ok(Self::Text(parts))
+    }
+
+    fn expand(&self, captures: &Captures, line_number: u32) -> anyhow::Result<String> {
+        let group = |ix: usize| captures.get(ix).map_or("", |m| m.as_str());
+        let mut result = String::new();
+        match self {
+            Self::Expression(terms) => {
+                for term in terms {
+                    match term {
+                        ExpressionTerm::String(text) => result.push_str(text),
+                        ExpressionTerm::Line => result.push_str(&line_number.to_string()),
+                        ExpressionTerm::Submatch(ix) => result.push_str(group(*ix)),
+                    }
+                }
+            }
+            Self::Text(parts) => {
+                let mut next_case = None;
+                let mut case = None;
+                for part in parts {
+                    let text = match part {
+                        ReplacementPart::Literal(text) => text.as_str(),
+                        ReplacementPart::Group(ix) => group(*ix),
+                        ReplacementPart::UpperNext => {
+                            next_case = Some(Case::Upper);
+                            continue;
+                        }
+                        ReplacementPart::LowerNext => {
+                            next_case = Some(Case::Lower);
+                            continue;
+                        }
+                        ReplacementPart::Upper => {
+                            case = Some(Case::Upper);
+                            continue;
+                        }
+                        ReplacementPart::Lower => {
+                            case = Some(Case::Lower);
+                            continue;
+                        }
+                        ReplacementPart::EndCase => {
+                            case = None;
+                            continue;
+                        }
+                    };
+                    for c in text.chars() {
+                        match next_case.take().or(case) {
+                            Some(Case::Upper) => result.extend(c.to_uppercase()),
+                            Some(Case::Lower) => result.extend(c.to_lowercase()),
+                            None => result.push(c),
+                        }
+                    }
+                }
+            }
+        }
+        Ok(result)
+    }
+}
+
+fn parse_expression(expression: &str) -> anyhow::Result<Vec<ExpressionTerm>> {
+    let invalid = || anyhow!("E15: Invalid expression: \"{}\"", expression);
+
+    let mut terms = Vec::new();
+    let mut rest = expression.trim_start();
+    loop {
+        let (term, remaining) = if let Some(remaining) = rest.strip_prefix('\'') {
+            let end = remaining.find('\'').ok_or_else(invalid)?;
+            (
+                ExpressionTerm::String(remaining[..end].to_string()),
+                &remaining[end + 1..],
+            )
+        } else if let Some(remaining) = rest.strip_prefix('"') {
+            let end = remaining.find('"').ok_or_else(invalid)?;
+            (
+                ExpressionTerm::String(remaining[..end].replace("\\n", "\n").replace("\\t", "\t")),
+                &remaining[end + 1..],
+            )
+        } else if rest.starts_with(|c: char| c.is_ascii_digit()) {
+            let end = rest
+                .find(|c: char| !c.is_ascii_digit())
+                .unwrap_or(rest.len());
+            (
+                ExpressionTerm::String(rest[..end].to_string()),
+                &rest[end..],
+            )
+        } else if let Some(remaining) = rest
+            .strip_prefix("line('.')")
+            .or_else(|| rest.strip_prefix("line(\".\")"))
+        {
+            (ExpressionTerm::Line, remaining)
+        } else if let Some(remaining) = rest.strip_prefix("submatch(") {
+            let end = remaining.find(')').ok_or_else(invalid)?;
+            let ix = remaining[..end].trim().parse().map_err(|_| invalid())?;
+            (ExpressionTerm::Submatch(ix), &remaining[end + 1..])
+        } else {
+            return Err(invalid());
+        };
+        terms.push(term);
+
+        rest = remaining.trim_start();
+        if rest.is_empty() {
+            return Ok(terms);
+        }
+        rest = rest
+            .strip_prefix("..")
+            .or_else(|| rest.strip_prefix('.'))
+            .ok_or_else(invalid)?
+            .trim_start();
+    }
+}
+
 #[cfg(test)]
 mod test {
     use editor::DisplayPoint;
+    use gpui::Action;
     use search::BufferSearchBar;
+    use settings::SettingsStore;
 
+    use super::{ReplaceCommand, SUBSTITUTE_CHUNK_ROWS};
     use crate::{
+        insert::Interrupt,
         state::Mode,
         test::{NeovimBackedTestContext, VimTestContext},
+        VimSettings,
     };
 
     #[gpui::test]
@@ -485,6 +1366,188 @@ mod test {
         cx.assert_state("one two ˇone", Mode::Normal);
     }
 
+    #[gpui::test]
+    async fn test_search_in_scope(cx: &mut gpui::TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+
+        // \%V only matches in the last visual selection
+        cx.set_state("foo\nˇfoo\nfoo\nfoo", Mode::Normal);
+        cx.simulate_keystrokes(["shift-v", "j", "escape", "g", "g", "/"]);
+        cx.simulate_input(r"\%Vfoo");
+        cx.simulate_keystrokes(["enter"]);
+        cx.run_until_parked();
+        cx.assert_state("foo\nˇfoo\nfoo\nfoo", Mode::Normal);
+        cx.simulate_keystrokes(["n"]);
+        cx.assert_state("foo\nfoo\nˇfoo\nfoo", Mode::Normal);
+        cx.simulate_keystrokes(["n"]);
+        cx.assert_state("foo\nˇfoo\nfoo\nfoo", Mode::Normal);
+
+        // and so does :s, while the scope is highlighted
+        cx.simulate_keystrokes([":"]);
+        cx.simulate_input("%s/foo/bar/");
+        cx.simulate_keystrokes(["enter"]);
+        cx.run_until_parked();
+        cx.assert_state("foo\nbar\nˇbar\nfoo", Mode::Normal);
+
+        // until the next search
+        cx.simulate_keystrokes(["/", "f", "o", "o", "enter"]);
+        cx.simulate_keystrokes([":"]);
+        cx.simulate_input("%s/foo/baz/");
+        cx.simulate_keystrokes(["enter"]);
+        cx.run_until_parked();
+        cx.assert_state("baz\nbar\nbar\nˇbaz", Mode::Normal);
+
+        // with search_in_selection, / in visual mode searches the selection
+        cx.update_global(|store: &mut SettingsStore, cx| {
+            store.update_user_settings::<VimSettings>(cx, |settings| {
+                settings.search_in_selection = Some(true)
+            });
+        });
+        cx.set_state("x\nˇone a\ntwo a\nthree a", Mode::Normal);
+        cx.simulate_keystrokes(["shift-v", "j", "/", "a", "enter"]);
+        cx.assert_state("x\none a\ntwo ˇa\nthree a", Mode::Normal);
+        cx.simulate_keystrokes(["n"]);
+        cx.assert_state("x\none ˇa\ntwo a\nthree a", Mode::Normal);
+    }
+
+    #[gpui::test]
+    async fn test_substitute_large_buffer(cx: &mut gpui::TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+        let text = "the quick brown fox jumps over the lazy dog, again and again\n".repeat(20_000);
+
+        // the matches are found in the background, and replaced once they all have been
+        cx.set_state(&format!("ˇ{text}needle"), Mode::Normal);
+        cx.simulate_keystrokes([":"]);
+        cx.simulate_input("%s/needle/pin/");
+        cx.simulate_keystrokes(["enter"]);
+        cx.run_until_parked();
+        cx.assert_state(&format!("{text}ˇpin"), Mode::Normal);
+        assert_eq!(cx.message(), None);
+
+        // ctrl-c abandons it
+        cx.update(|cx| {
+            cx.dispatch_action(
+                ReplaceCommand {
+                    query: "%s/pin/needle/".to_string(),
+                }
+                .boxed_clone(),
+            );
+            cx.dispatch_action(Interrupt.boxed_clone());
+        });
+        cx.run_until_parked();
+        cx.assert_state(&format!("{text}ˇpin"), Mode::Normal);
+        assert_eq!(cx.message(), Some("Interrupted".to_string()));
+
+        // another can't be started while one is running
+        cx.update(|cx| {
+            cx.dispatch_action(
+                ReplaceCommand {
+                    query: "%s/pin/needle/".to_string(),
+                }
+                .boxed_clone(),
+            );
+            cx.dispatch_action(
+                ReplaceCommand {
+                    query: "%s/pin/thread/".to_string(),
+                }
+                .boxed_clone(),
+            );
+        });
+        assert_eq!(
+            cx.message(),
+            Some("Another operation is still running (ctrl-c interrupts it)".to_string())
+        );
+        cx.run_until_parked();
+        cx.assert_state(&format!("{text}ˇneedle"), Mode::Normal);
+
+        // the replacements are made in batches, which are undone at once
+        cx.simulate_keystrokes([":"]);
+        cx.simulate_input("%s/again/more/");
+        cx.simulate_keystrokes(["enter"]);
+        cx.run_until_parked();
+        assert_eq!(
+            cx.buffer_text(),
+            format!("{}needle", text.replace("again", "more"))
+        );
+        cx.simulate_keystrokes(["u"]);
+        assert_eq!(cx.buffer_text(), format!("{text}needle"));
+
+        // a match across lines can straddle two chunks
+        let line = "the quick brown fox jumps over the lazy dog, again and again\n";
+        let before = line.repeat(SUBSTITUTE_CHUNK_ROWS as usize - 1);
+        let after = line.repeat(10_000);
+        cx.set_state(&format!("ˇ{before}needle\nhaystack\n{after}"), Mode::Normal);
+        cx.simulate_keystrokes([":"]);
+        cx.simulate_input(r"%s/needle\nhaystack/pin/");
+        cx.simulate_keystrokes(["enter"]);
+        cx.run_until_parked();
+        cx.assert_state(&format!("{before}ˇpin\n{after}"), Mode::Normal);
+    }
+
+    #[gpui::test]
+    async fn test_substitute_multiline(cx: &mut gpui::TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+
+        // a pattern with \n in it matches across the lines of the range
+        cx.set_state("ˇa\nb\na\nb\nx", Mode::Normal);
+        cx.simulate_keystrokes([":"]);
+        cx.simulate_input(r"%s/a\nb/c/");
+        cx.simulate_keystrokes(["enter"]);
+        cx.run_until_parked();
+        cx.assert_state("c\nˇc\nx", Mode::Normal);
+
+        // including the line break after the last one
+        cx.set_state("ˇone\ntwo\nthree", Mode::Normal);
+        cx.simulate_keystrokes([":"]);
+        cx.simulate_input(r"s/e\n/e /");
+        cx.simulate_keystrokes(["enter"]);
+        cx.run_until_parked();
+        cx.assert_state("ˇone two\nthree", Mode::Normal);
+    }
+
+    #[gpui::test]
+    async fn test_operator_search(cx: &mut gpui::TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+
+        // the search motion is exclusive, and can cross lines
+        cx.set_state("one tˇwo\nthree four\nfive", Mode::Normal);
+        cx.simulate_keystrokes(["d", "/", "f", "o"]);
+        cx.run_until_parked();
+        cx.simulate_keystrokes(["enter"]);
+        cx.assert_state("one tˇfour\nfive", Mode::Normal);
+
+        // ending in the first column makes it linewise
+        cx.set_state("ˇone\ntwo\nthree", Mode::Normal);
+        cx.simulate_keystrokes(["d", "/", "t", "h"]);
+        cx.run_until_parked();
+        cx.simulate_keystrokes(["enter"]);
+        cx.assert_state("ˇthree", Mode::Normal);
+
+        cx.set_state("ˇone two", Mode::Normal);
+        cx.simulate_keystrokes(["c", "/", "t", "w"]);
+        cx.run_until_parked();
+        cx.simulate_keystrokes(["enter"]);
+        cx.assert_state("ˇtwo", Mode::Insert);
+        cx.simulate_keystrokes(["escape"]);
+
+        // escaping from the search leaves the buffer alone, and drops the operator
+        cx.set_state("ˇone\ntwo", Mode::Normal);
+        cx.simulate_keystrokes(["d", "/", "t", "w"]);
+        cx.run_until_parked();
+        cx.simulate_keystrokes(["escape"]);
+        cx.run_until_parked();
+        cx.assert_state("ˇone\ntwo", Mode::Normal);
+        cx.simulate_keystrokes(["x"]);
+        cx.assert_state("ˇne\ntwo", Mode::Normal);
+
+        cx.set_state("ˇone\ntwo", Mode::Normal);
+        cx.simulate_keystrokes(["d", "/", "z", "z"]);
+        cx.run_until_parked();
+        cx.simulate_keystrokes(["enter"]);
+        cx.assert_state("ˇone\ntwo", Mode::Normal);
+        assert_eq!(cx.message().as_deref(), Some("E486: Pattern not found"));
+    }
+
     #[gpui::test]
     async fn test_non_vim_search(cx: &mut gpui::TestAppContext) {
         let mut cx = VimTestContext::new(cx, false).await;
@@ -0,0 +1,159 @@
+use collections::HashMap;
+use editor::{scroll::Autoscroll, Editor, MultiBufferSnapshot};
+use gpui::{AppContext, ViewContext};
+use language::Point;
+use settings::Settings;
+
+use crate::{utils::screen_width, VimSettings};
+
+/// The columns `>` and `<` shift lines by in the language at the point: the
+/// `shiftwidth` setting, or the language's in the `languages` setting. `None` when
+/// that's 0, for the editor's indent, which shifts to the next multiple of the
+/// `tab_size`.
+pub(crate) fn shiftwidth(
+    buffer: &MultiBufferSnapshot,
+    point: Point,
+    cx: &AppContext,
+) -> Option<u32> {
+    let settings = VimSettings::get_global(cx);
+    let width = settings
+        .language_options(buffer.language_at(point))
+        .and_then(|options| options.shiftwidth)
+        .unwrap_or(settings.shiftwidth);
+    (width > 0).then_some(width)
+}
+
+/// Shifts the lines of each selection right (or left) by the `shiftwidth`, like vim's
+/// `>` and `<`, or `ctrl-t` and `ctrl-d` in insert mode. Each line is shifted by the
+/// `shiftwidth` (and indented with the tab settings) of the language it's in, or by
+/// its `tab_size` if that language has none. Empty lines are left alone, unless
+/// `skip_empty` is false. Returns false without changing anything when none of the
+/// lines has a `shiftwidth`, for the editor's indent to be used instead.
+pub(crate) fn shift_lines(
+    editor: &mut Editor,
+    right: bool,
+    skip_empty: bool,
+    cx: &mut ViewContext<Editor>,
+) -> bool {
+    let buffer = editor.buffer().read(cx).snapshot(cx);
+    let mut selections = editor.selections.all::<Point>(cx);
+
+    // the length of each shifted line's indent, before and after
+    let mut indents = HashMap::default();
+    let mut edits = Vec::new();
+    let mut has_shiftwidth = false;
+    for selection in &selections {
+        let mut end_row = selection.end.row;
+        if selection.end.column == 0 && end_row > selection.start.row {
+            end_row -= 1;
+        }
+        for row in selection.start.row..=end_row {
+            if indents.contains_key(&row) || (skip_empty && buffer.line_len(row) == 0) {
+                continue;
+            }
+            let old_len = buffer.indent_size_for_line(row).len;
+            let point = Point::new(row, old_len);
+            let settings = buffer.settings_at(point, cx);
+            let tab_size = settings.tab_size.get();
+            let hard_tabs = settings.hard_tabs;
+            let width = match shiftwidth(&buffer, point, cx) {
+                Some(width) => {
+                    has_shiftwidth = true;
+                    width
+                }
+                None => tab_size,
+            };
+            let old_indent = buffer
+                .text_for_range(Point::new(row, 0)..Point::new(row, old_len))
+                .collect::<String>();
+            let columns = screen_width(&old_indent, tab_size as usize) as u32;
+            let columns = if right {
+                columns + width
+            } else {
+                columns.saturating_sub(width)
+            };
+            let new_indent = if hard_tabs {
+                "\t".repeat((columns / tab_size) as usize)
+                    + &" ".repeat((columns % tab_size) as usize)
+            } else {
+                " ".repeat(columns as usize)
+            };
+            indents.insert(row, (old_len, new_indent.len() as u32));
+            edits.push((Point::new(row, 0)..Point::new(row, old_len), new_indent));
+        }
+    }
+
+    if !has_shiftwidth {
+        return false;
+    }
+
+    // the selections keep their place in the text of the lines
+    let shift = |point: &mut Point| {
+        if let Some((old_len, new_len)) = indents.get(&point.row) {
+            point.column = if point.column >= *old_len {
+                point.column - old_len + new_len
+            } else {
+                point.column.min(*new_len)
+            };
+        }
+    };
+    for selection in &mut selections {
+        shift(&mut selection.start);
+        shift(&mut selection.end);
+    }
+    editor.transact(cx, |editor, cx| {
+        editor.edit(edits, cx);
+        editor.change_selections(Some(Autoscroll::fit()), cx, |s| s.select(selections));
+    });
+    true
+}
+
+#[cfg(test)]
+mod test {
+    use settings::SettingsStore;
+
+    use crate::{state::Mode, test::VimTestContext, LanguageOptions, VimSettings};
+
+    #[gpui::test]
+    async fn test_shiftwidth(cx: &mut gpui::TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+        cx.update_global(|store: &mut SettingsStore, cx| {
+            store.update_user_settings::<VimSettings>(cx, |s| s.shiftwidth = Some(2));
+        });
+
+        cx.set_state("aa\nbˇb", Mode::Normal);
+        cx.simulate_keystrokes([">", ">"]);
+        cx.assert_state("aa\n  bˇb", Mode::Normal);
+        cx.simulate_keystrokes([">", ">"]);
+        cx.assert_state("aa\n    bˇb", Mode::Normal);
+        cx.simulate_keystrokes(["<", "<"]);
+        cx.assert_state("aa\n  bˇb", Mode::Normal);
+
+        // empty lines are left alone, except by ctrl-t in insert mode
+        cx.set_state("ˇaa\n\ncc", Mode::Normal);
+        cx.simulate_keystrokes(["shift-v", "j", "j", ">"]);
+        cx.assert_state("  aa\n\n  ˇcc", Mode::Normal);
+        cx.set_state("ˇ", Mode::Insert);
+        cx.simulate_keystrokes(["ctrl-t"]);
+        cx.assert_state("  ˇ", Mode::Insert);
+
+        // a language's shiftwidth replaces the global one
+        cx.update_global(|store: &mut SettingsStore, cx| {
+            store.update_user_settings::<VimSettings>(cx, |s| {
+                s.languages = Some(
+                    [(
+                        "Rust".to_string(),
+                        LanguageOptions {
+                            shiftwidth: Some(3),
+                            ..Default::default()
+                        },
+                    )]
+                    .into(),
+                );
+            });
+        });
+        cx.set_state("ˇaa", Mode::Normal);
+        cx.simulate_keystrokes([">", ">"]);
+        cx.assert_state("   ˇaa", Mode::Normal);
+    }
+}
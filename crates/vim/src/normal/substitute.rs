@@ -3,7 +3,11 @@ use gpui::{actions, ViewContext, WindowContext};
 use language::Point;
 use workspace::Workspace;
 
-use crate::{motion::Motion, utils::copy_selections_content, Mode, Vim};
+use crate::{
+    motion::Motion,
+    utils::{copy_selections_content, KeywordChars},
+    Mode, Vim,
+};
 
 actions!(vim, [Substitute, SubstituteLine]);
 
@@ -42,6 +46,7 @@ pub fn substitute(vim: &mut Vim, count: Option<usize>, line_mode: bool, cx: &mut
                             count,
                             true,
                             &text_layout_details,
+                            &KeywordChars::default(),
                         );
                     }
                     if line_mode {
@@ -50,12 +55,16 @@ pub fn substitute(vim: &mut Vim, count: Option<usize>, line_mode: bool, cx: &mut
                         if !selection.is_empty() && selection.end.column() == 0 {
                             selection.end = movement::left(map, selection.end);
                         }
-                        Motion::CurrentLine.expand_selection(
+                        Motion::CurrentLine {
+                            display_lines: false,
+                        }
+                        .expand_selection(
                             map,
                             selection,
                             None,
                             false,
                             &text_layout_details,
+                            &KeywordChars::default(),
                         );
                         if let Some((point, _)) = (Motion::FirstNonWhitespace {
                             display_lines: false,
@@ -66,6 +75,7 @@ pub fn substitute(vim: &mut Vim, count: Option<usize>, line_mode: bool, cx: &mut
                             selection.goal,
                             None,
                             &text_layout_details,
+                            &KeywordChars::default(),
                         ) {
                             selection.start = point;
                         }
@@ -1,8 +1,12 @@
 use std::ops::Range;
 
 use crate::{
-    motion::right, normal::normal_object, state::Mode, utils::coerce_punctuation,
-    visual::visual_object, Vim,
+    motion::{right, touches_fold},
+    normal::normal_object,
+    state::Mode,
+    utils::{char_kind, coerce_punctuation, KeywordChars},
+    visual::visual_object,
+    Vim,
 };
 use editor::{
     display_map::{DisplaySnapshot, ToDisplayPoint},
@@ -10,13 +14,15 @@ use editor::{
     Bias, DisplayPoint,
 };
 use gpui::{actions, impl_actions, ViewContext, WindowContext};
-use language::{char_kind, BufferSnapshot, CharKind, Point, Selection};
+use language::{BufferSnapshot, CharKind, Point, Selection};
 use serde::Deserialize;
 use workspace::Workspace;
 
 #[derive(Copy, Clone, Debug, PartialEq)]
 pub enum Object {
-    Word { ignore_punctuation: bool },
+    Word {
+        ignore_punctuation: bool,
+    },
     Sentence,
     Paragraph,
     Quotes,
@@ -27,6 +33,11 @@ pub enum Object {
     SquareBrackets,
     CurlyBrackets,
     AngleBrackets,
+    /// A pair the `matchpairs` setting adds for the language, like `i«`.
+    MatchPair {
+        open: char,
+        close: char,
+    },
     Argument,
     Tag,
 }
@@ -94,11 +105,11 @@ pub fn register(workspace: &mut Workspace, _: &mut ViewContext<Workspace>) {
         .register_action(|_: &mut Workspace, _: &Argument, cx: _| object(Object::Argument, cx));
 }
 
-fn object(object: Object, cx: &mut WindowContext) {
+pub(crate) fn object(object: Object, cx: &mut WindowContext) {
     match Vim::read(cx).state().mode {
         Mode::Normal => normal_object(object, cx),
         Mode::Visual | Mode::VisualLine | Mode::VisualBlock => visual_object(object, cx),
-        Mode::Insert => {
+        Mode::Insert | Mode::Select => {
             // Shouldn't execute a text object in insert mode. Ignoring
         }
     }
@@ -119,6 +130,7 @@ impl Object {
             | Object::AngleBrackets
             | Object::CurlyBrackets
             | Object::SquareBrackets
+            | Object::MatchPair { .. }
             | Object::Argument => true,
         }
     }
@@ -134,7 +146,8 @@ impl Object {
             | Object::SquareBrackets
             | Object::Tag
             | Object::CurlyBrackets
-            | Object::AngleBrackets => true,
+            | Object::AngleBrackets
+            | Object::MatchPair { .. } => true,
         }
     }
 
@@ -155,6 +168,7 @@ impl Object {
             | Object::SquareBrackets
             | Object::CurlyBrackets
             | Object::AngleBrackets
+            | Object::MatchPair { .. }
             | Object::VerticalBars
             | Object::Tag
             | Object::Argument => Mode::Visual,
@@ -167,13 +181,14 @@ impl Object {
         map: &DisplaySnapshot,
         relative_to: DisplayPoint,
         around: bool,
+        keyword_chars: &KeywordChars,
     ) -> Option<Range<DisplayPoint>> {
         match self {
             Object::Word { ignore_punctuation } => {
                 if around {
-                    around_word(map, relative_to, ignore_punctuation)
+                    around_word(map, relative_to, ignore_punctuation, keyword_chars)
                 } else {
-                    in_word(map, relative_to, ignore_punctuation)
+                    in_word(map, relative_to, ignore_punctuation, keyword_chars)
                 }
             }
             Object::Sentence => sentence(map, relative_to, around),
@@ -203,6 +218,9 @@ impl Object {
             Object::AngleBrackets => {
                 surrounding_markers(map, relative_to, around, self.is_multiline(), '<', '>')
             }
+            Object::MatchPair { open, close } => {
+                surrounding_markers(map, relative_to, around, self.is_multiline(), open, close)
+            }
             Object::Argument => argument(map, relative_to, around),
         }
     }
@@ -212,10 +230,16 @@ impl Object {
         map: &DisplaySnapshot,
         selection: &mut Selection<DisplayPoint>,
         around: bool,
+        keyword_chars: &KeywordChars,
     ) -> bool {
-        if let Some(range) = self.range(map, selection.head(), around) {
+        if let Some(range) = self.range(map, selection.head(), around, keyword_chars) {
             selection.start = range.start;
             selection.end = range.end;
+            // like vim, a closed fold is included as a whole, with its lines
+            if touches_fold(map, selection) {
+                selection.start = map.prev_line_boundary(selection.start.to_point(map)).1;
+                selection.end = map.next_line_boundary(selection.end.to_point(map)).1;
+            }
             true
         } else {
             false
@@ -231,6 +255,7 @@ fn in_word(
     map: &DisplaySnapshot,
     relative_to: DisplayPoint,
     ignore_punctuation: bool,
+    keyword_chars: &KeywordChars,
 ) -> Option<Range<DisplayPoint>> {
     // Use motion::right so that we consider the character under the cursor when looking for the start
     let scope = map
@@ -241,14 +266,14 @@ fn in_word(
         right(map, relative_to, 1),
         movement::FindRange::SingleLine,
         |left, right| {
-            coerce_punctuation(char_kind(&scope, left), ignore_punctuation)
-                != coerce_punctuation(char_kind(&scope, right), ignore_punctuation)
+            coerce_punctuation(char_kind(&scope, left, keyword_chars), ignore_punctuation)
+                != coerce_punctuation(char_kind(&scope, right, keyword_chars), ignore_punctuation)
         },
     );
 
     let end = movement::find_boundary(map, relative_to, FindRange::SingleLine, |left, right| {
-        coerce_punctuation(char_kind(&scope, left), ignore_punctuation)
-            != coerce_punctuation(char_kind(&scope, right), ignore_punctuation)
+        coerce_punctuation(char_kind(&scope, left, keyword_chars), ignore_punctuation)
+            != coerce_punctuation(char_kind(&scope, right, keyword_chars), ignore_punctuation)
     });
 
     Some(start..end)
@@ -337,6 +362,7 @@ fn around_word(
     map: &DisplaySnapshot,
     relative_to: DisplayPoint,
     ignore_punctuation: bool,
+    keyword_chars: &KeywordChars,
 ) -> Option<Range<DisplayPoint>> {
     let scope = map
         .buffer_snapshot
@@ -344,13 +370,13 @@ fn around_word(
     let in_word = map
         .chars_at(relative_to)
         .next()
-        .map(|(c, _)| char_kind(&scope, c) != CharKind::Whitespace)
+        .map(|(c, _)| language::char_kind(&scope, c) != CharKind::Whitespace)
         .unwrap_or(false);
 
     if in_word {
-        around_containing_word(map, relative_to, ignore_punctuation)
+        around_containing_word(map, relative_to, ignore_punctuation, keyword_chars)
     } else {
-        around_next_word(map, relative_to, ignore_punctuation)
+        around_next_word(map, relative_to, ignore_punctuation, keyword_chars)
     }
 }
 
@@ -358,8 +384,9 @@ fn around_containing_word(
     map: &DisplaySnapshot,
     relative_to: DisplayPoint,
     ignore_punctuation: bool,
+    keyword_chars: &KeywordChars,
 ) -> Option<Range<DisplayPoint>> {
-    in_word(map, relative_to, ignore_punctuation)
+    in_word(map, relative_to, ignore_punctuation, keyword_chars)
         .map(|range| expand_to_include_whitespace(map, range, true))
 }
 
@@ -367,6 +394,7 @@ fn around_next_word(
     map: &DisplaySnapshot,
     relative_to: DisplayPoint,
     ignore_punctuation: bool,
+    keyword_chars: &KeywordChars,
 ) -> Option<Range<DisplayPoint>> {
     let scope = map
         .buffer_snapshot
@@ -377,15 +405,17 @@ fn around_next_word(
         right(map, relative_to, 1),
         FindRange::SingleLine,
         |left, right| {
-            coerce_punctuation(char_kind(&scope, left), ignore_punctuation)
-                != coerce_punctuation(char_kind(&scope, right), ignore_punctuation)
+            coerce_punctuation(char_kind(&scope, left, keyword_chars), ignore_punctuation)
+                != coerce_punctuation(char_kind(&scope, right, keyword_chars), ignore_punctuation)
         },
     );
 
     let mut word_found = false;
     let end = movement::find_boundary(map, relative_to, FindRange::MultiLine, |left, right| {
-        let left_kind = coerce_punctuation(char_kind(&scope, left), ignore_punctuation);
-        let right_kind = coerce_punctuation(char_kind(&scope, right), ignore_punctuation);
+        let left_kind =
+            coerce_punctuation(char_kind(&scope, left, keyword_chars), ignore_punctuation);
+        let right_kind =
+            coerce_punctuation(char_kind(&scope, right, keyword_chars), ignore_punctuation);
 
         let found = (word_found && left_kind != right_kind) || right == '\n' && left == '\n';
 
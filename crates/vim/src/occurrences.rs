@@ -0,0 +1,258 @@
+use std::sync::Arc;
+
+use editor::{scroll::Autoscroll, Editor};
+use fuzzy::{StringMatch, StringMatchCandidate};
+use gpui::{
+    impl_actions, AppContext, DismissEvent, EventEmitter, FocusHandle, FocusableView,
+    ParentElement, Render, Styled, Task, View, ViewContext, VisualContext, WeakView,
+};
+use language::Point;
+use picker::{Picker, PickerDelegate};
+use serde_derive::Deserialize;
+use ui::{prelude::*, HighlightedLabel, ListItem, ListItemSpacing};
+use workspace::{ModalView, Workspace};
+
+use crate::{
+    completion::{keyword_chars, keywords},
+    list_picker::{self, ModalDelegate, StringListDelegate},
+    Vim,
+};
+
+/// `[I` lists every line that contains the identifier under the cursor, and `]I`
+/// the ones below the cursor, like vim's `:ilist`. Picking a line jumps to it.
+#[derive(Clone, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+struct ListOccurrences {
+    #[serde(default)]
+    after_cursor: bool,
+}
+
+impl_actions!(vim, [ListOccurrences]);
+
+pub(crate) fn register(workspace: &mut Workspace, _: &mut ViewContext<Workspace>) {
+    workspace.register_action(|workspace: &mut Workspace, action: &ListOccurrences, cx| {
+        let Some(editor) = workspace.active_item_as::<Editor>(cx) else {
+            return;
+        };
+        Vim::update(cx, |vim, cx| vim.clear_operator(cx));
+        let occurrences = match occurrences(&editor, action.after_cursor, cx) {
+            Ok(occurrences) => occurrences,
+            Err(message) => {
+                Vim::update(cx, |vim, _| vim.show_message(message));
+                return;
+            }
+        };
+        workspace.toggle_modal(cx, |cx| OccurrenceList::new(editor, occurrences, cx));
+    });
+}
+
+struct Occurrence {
+    position: Point,
+    text: String,
+}
+
+/// The lines that contain the keyword under (or after) the cursor, as a whole word.
+fn occurrences(
+    editor: &View<Editor>,
+    after_cursor: bool,
+    cx: &AppContext,
+) -> Result<Vec<Occurrence>, &'static str> {
+    let editor = editor.read(cx);
+    let snapshot = editor.buffer().read(cx).snapshot(cx);
+    let cursor = editor.selections.newest::<Point>(cx).head();
+    let line = |row| {
+        snapshot
+            .text_for_range(Point::new(row, 0)..Point::new(row, snapshot.line_len(row)))
+            .collect::<String>()
+    };
+
+    let cursor_line = line(cursor.row);
+    let extra = keyword_chars(snapshot.language_at(cursor), cx);
+    let Some(identifier) = keywords(&cursor_line, &extra)
+        .into_iter()
+        .find(|(start, word)| start + word.len() > cursor.column as usize)
+        .map(|(_, word)| word.to_string())
+    else {
+        return Err("E349: No identifier under cursor");
+    };
+
+    let first_row = if after_cursor { cursor.row + 1 } else { 0 };
+    let occurrences = (first_row..=snapshot.max_point().row)
+        .filter_map(|row| {
+            let text = line(row);
+            let (column, _) = keywords(&text, &extra)
+                .into_iter()
+                .find(|(_, word)| *word == identifier)?;
+            Some(Occurrence {
+                position: Point::new(row, column as u32),
+                text: text.trim().to_string(),
+            })
+        })
+        .collect::<Vec<_>>();
+    if occurrences.is_empty() {
+        return Err("E389: Couldn't find pattern");
+    }
+    Ok(occurrences)
+}
+
+pub struct OccurrenceList {
+    picker: View<Picker<OccurrenceListDelegate>>,
+}
+
+impl OccurrenceList {
+    fn new(editor: View<Editor>, occurrences: Vec<Occurrence>, cx: &mut ViewContext<Self>) -> Self {
+        let candidates = occurrences
+            .iter()
+            .enumerate()
+            .map(|(id, occurrence)| StringMatchCandidate::new(id, occurrence.text.clone()))
+            .collect();
+        let delegate = OccurrenceListDelegate {
+            occurrence_list: cx.view().downgrade(),
+            editor,
+            occurrences,
+            candidates,
+            matches: Vec::new(),
+            selected_index: 0,
+        };
+        let picker = cx.new_view(|cx| Picker::uniform_list(delegate, cx));
+        Self { picker }
+    }
+}
+
+impl Render for OccurrenceList {
+    fn render(&mut self, _cx: &mut ViewContext<Self>) -> impl IntoElement {
+        v_flex().w(rems(34.)).child(self.picker.clone())
+    }
+}
+
+impl FocusableView for OccurrenceList {
+    fn focus_handle(&self, cx: &AppContext) -> FocusHandle {
+        self.picker.focus_handle(cx)
+    }
+}
+
+impl EventEmitter<DismissEvent> for OccurrenceList {}
+impl ModalView for OccurrenceList {}
+
+pub struct OccurrenceListDelegate {
+    occurrence_list: WeakView<OccurrenceList>,
+    editor: View<Editor>,
+    occurrences: Vec<Occurrence>,
+    candidates: Vec<StringMatchCandidate>,
+    matches: Vec<StringMatch>,
+    selected_index: usize,
+}
+
+impl ModalDelegate for OccurrenceListDelegate {
+    type Modal = OccurrenceList;
+
+    fn modal(&self) -> &WeakView<OccurrenceList> {
+        &self.occurrence_list
+    }
+}
+
+impl StringListDelegate for OccurrenceListDelegate {
+    fn candidates(&self) -> &[StringMatchCandidate] {
+        &self.candidates
+    }
+
+    fn set_matches(&mut self, matches: Vec<StringMatch>) {
+        self.matches = matches;
+    }
+}
+
+impl PickerDelegate for OccurrenceListDelegate {
+    type ListItem = ListItem;
+
+    fn placeholder_text(&self, _cx: &mut WindowContext) -> Arc<str> {
+        "Filter occurrences...".into()
+    }
+
+    fn match_count(&self) -> usize {
+        self.matches.len()
+    }
+
+    fn selected_index(&self) -> usize {
+        self.selected_index
+    }
+
+    fn set_selected_index(&mut self, ix: usize, _: &mut ViewContext<Picker<Self>>) {
+        self.selected_index = ix;
+    }
+
+    fn update_matches(&mut self, query: String, cx: &mut ViewContext<Picker<Self>>) -> Task<()> {
+        list_picker::update_matches(self, query, cx)
+    }
+
+    fn confirm(&mut self, _: bool, cx: &mut ViewContext<Picker<Self>>) {
+        if let Some(mat) = self.matches.get(self.selected_index) {
+            let position = self.occurrences[mat.candidate_id].position;
+            self.editor.update(cx, |editor, cx| {
+                editor.change_selections(Some(Autoscroll::center()), cx, |s| {
+                    s.select_ranges([position..position])
+                });
+                editor.focus(cx);
+            });
+        }
+        self.dismissed(cx);
+    }
+
+    fn dismissed(&mut self, cx: &mut ViewContext<Picker<Self>>) {
+        list_picker::dismiss(self, cx)
+    }
+
+    fn render_match(
+        &self,
+        ix: usize,
+        selected: bool,
+        _: &mut ViewContext<Picker<Self>>,
+    ) -> Option<Self::ListItem> {
+        let mat = &self.matches[ix];
+        let occurrence = &self.occurrences[mat.candidate_id];
+        let line_number = format!("{}: ", occurrence.position.row + 1);
+        let positions = mat
+            .positions
+            .iter()
+            .map(|position| position + line_number.len())
+            .collect();
+        Some(
+            ListItem::new(ix)
+                .inset(true)
+                .spacing(ListItemSpacing::Sparse)
+                .selected(selected)
+                .child(HighlightedLabel::new(
+                    line_number + &occurrence.text,
+                    positions,
+                )),
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::OccurrenceList;
+    use crate::{state::Mode, test::VimTestContext};
+
+    #[gpui::test]
+    async fn test_list_occurrences(cx: &mut gpui::TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+
+        cx.set_state(
+            "fn ˇfoo() {}\nlet x = foo();\nbar\nfoo + foofoo",
+            Mode::Normal,
+        );
+        cx.simulate_keystrokes(["[", "shift-i"]);
+        assert!(
+            cx.workspace(|workspace, cx| workspace.active_modal::<OccurrenceList>(cx).is_some())
+        );
+        cx.run_until_parked();
+        cx.simulate_keystrokes(["down", "down", "enter"]);
+        cx.assert_state(
+            "fn foo() {}\nlet x = foo();\nbar\nˇfoo + foofoo",
+            Mode::Normal,
+        );
+
+        cx.simulate_keystrokes(["]", "shift-i"]);
+        assert_eq!(cx.message().as_deref(), Some("E389: Couldn't find pattern"));
+    }
+}
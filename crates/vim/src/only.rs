@@ -0,0 +1,147 @@
+use collections::HashSet;
+use editor::Editor;
+use gpui::{impl_actions, AppContext, EntityId, ViewContext};
+use serde_derive::Deserialize;
+use workspace::{item::ItemHandle, pane::CloseAllItems, SaveIntent, Workspace};
+
+use crate::Vim;
+
+/// `ctrl-w o` (or `:only`) closes the other panes, leaving the tabs of the active one
+/// alone, as vim's `:only` leaves the buffer list. A pane with unsaved changes that
+/// no tab of the active pane shows is kept, with E445. `:only!` closes it too, moving
+/// its unsaved tabs into the active pane so that nothing is lost.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct OnlyPane {
+    #[serde(default)]
+    pub force: bool,
+}
+
+impl_actions!(vim, [OnlyPane]);
+
+pub(crate) fn register(workspace: &mut Workspace, _: &mut ViewContext<Workspace>) {
+    workspace.register_action(only_pane);
+}
+
+fn only_pane(workspace: &mut Workspace, action: &OnlyPane, cx: &mut ViewContext<Workspace>) {
+    Vim::update(cx, |vim, cx| vim.clear_operator(cx));
+    let active_pane = workspace.active_pane().clone();
+    let kept = active_pane
+        .read(cx)
+        .items()
+        .map(|item| buffer_id(item.as_ref(), cx))
+        .collect::<HashSet<_>>();
+
+    let mut refused = false;
+    for pane in workspace.panes().to_vec() {
+        if pane == active_pane {
+            continue;
+        }
+        let unsaved = pane
+            .read(cx)
+            .items()
+            .filter(|item| item.is_dirty(cx) && !kept.contains(&buffer_id(item.as_ref(), cx)))
+            .map(|item| item.boxed_clone())
+            .collect::<Vec<_>>();
+        if !unsaved.is_empty() {
+            if !action.force {
+                refused = true;
+                continue;
+            }
+            let active_item = active_pane.read(cx).active_item();
+            for item in unsaved {
+                pane.update(cx, |pane, cx| {
+                    if let Some(ix) = pane.index_for_item(item.as_ref()) {
+                        pane.remove_item(ix, false, cx);
+                    }
+                });
+                active_pane.update(cx, |active_pane, cx| {
+                    active_pane.add_item(item, false, false, None, cx)
+                });
+            }
+            // the moved tabs are kept in the background, like vim's hidden buffers
+            if let Some(active_item) = active_item {
+                active_pane.update(cx, |active_pane, cx| {
+                    if let Some(ix) = active_pane.index_for_item(active_item.as_ref()) {
+                        active_pane.activate_item(ix, true, true, cx);
+                    }
+                });
+            }
+        }
+        // the changes left in the pane are shown by the active one, so nothing's lost
+        let close = pane.update(cx, |pane, cx| {
+            pane.close_all_items(
+                &CloseAllItems {
+                    save_intent: Some(SaveIntent::Skip),
+                },
+                cx,
+            )
+        });
+        if let Some(close) = close {
+            close.detach_and_log_err(cx);
+        }
+    }
+
+    if refused {
+        Vim::update(cx, |vim, _| {
+            vim.show_message("E445: Other window contains changes")
+        });
+    }
+}
+
+/// What identifies the text a tab shows: the buffer of an editor, or else the tab
+/// itself.
+pub(crate) fn buffer_id(item: &dyn ItemHandle, cx: &AppContext) -> EntityId {
+    item.act_as::<Editor>(cx)
+        .and_then(|editor| editor.read(cx).buffer().read(cx).as_singleton())
+        .map_or_else(|| item.item_id(), |buffer| buffer.entity_id())
+}
+
+#[cfg(test)]
+mod test {
+    use editor::Editor;
+
+    use crate::{state::Mode, test::VimTestContext};
+
+    fn pane_and_tab_counts(cx: &mut VimTestContext) -> (usize, usize) {
+        cx.workspace(|workspace, cx| {
+            (
+                workspace.panes().len(),
+                workspace.active_pane().read(cx).items_len(),
+            )
+        })
+    }
+
+    #[gpui::test]
+    async fn test_only_pane(cx: &mut gpui::TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+
+        // a split showing the same buffer can go, even with changes
+        cx.set_state("ˇchanged", Mode::Normal);
+        cx.simulate_keystrokes(["ctrl-w", "v", "ctrl-w", "o"]);
+        cx.run_until_parked();
+        assert_eq!(pane_and_tab_counts(&mut cx), (1, 1));
+
+        // but a pane with a buffer shown nowhere else is kept
+        cx.simulate_keystrokes(["ctrl-w", "n", "i"]);
+        cx.simulate_input("notes");
+        cx.simulate_keystrokes(["escape", "ctrl-w", "p", "ctrl-w", "o"]);
+        cx.run_until_parked();
+        assert_eq!(pane_and_tab_counts(&mut cx), (2, 1));
+        assert_eq!(
+            cx.message().as_deref(),
+            Some("E445: Other window contains changes")
+        );
+
+        // unless forced, which keeps the buffer in the active pane
+        cx.simulate_keystrokes([":"]);
+        cx.simulate_input("only!");
+        cx.simulate_keystrokes(["enter"]);
+        cx.run_until_parked();
+        assert_eq!(pane_and_tab_counts(&mut cx), (1, 2));
+        cx.workspace(|workspace, cx| {
+            let editor = workspace.active_item_as::<Editor>(cx).unwrap();
+            assert_eq!(editor.read(cx).text(cx), "changed");
+        });
+    }
+}
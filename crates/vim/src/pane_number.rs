@@ -0,0 +1,114 @@
+use gpui::{actions, ViewContext};
+use workspace::Workspace;
+
+use crate::Vim;
+
+actions!(vim, [NextPane]);
+
+pub(crate) fn register(workspace: &mut Workspace, cx: &mut ViewContext<Workspace>) {
+    workspace.register_action(next_pane);
+    cx.observe_pending_input(pending_input_changed).detach();
+}
+
+/// `ctrl-w w` goes to the next pane, and with a count, like `3 ctrl-w w`, to the pane
+/// of that number, counting from the top left as the numbers shown while `ctrl-w` is
+/// pending do. A count past the last pane goes to the last one.
+fn next_pane(workspace: &mut Workspace, _: &NextPane, cx: &mut ViewContext<Workspace>) {
+    let count = Vim::update(cx, |vim, cx| {
+        let count = vim.take_count(cx);
+        vim.clear_operator(cx);
+        count
+    });
+    let Some(count) = count else {
+        workspace.activate_next_pane(cx);
+        return;
+    };
+    let panes = workspace.center_panes();
+    if let Some(pane) = panes.get(count.min(panes.len()).max(1) - 1) {
+        cx.focus_view(pane);
+    }
+}
+
+/// Numbers the panes while `ctrl-w` waits for the rest of a window command, and
+/// takes the numbers away once it's complete, or abandoned (as it is after a while
+/// without the next key).
+fn pending_input_changed(workspace: &mut Workspace, cx: &mut ViewContext<Workspace>) {
+    let vim = Vim::read(cx);
+    let show = vim.enabled
+        && cx
+            .pending_input_keystrokes()
+            .and_then(|keystrokes| keystrokes.first())
+            .is_some_and(|keystroke| keystroke.key == "w" && keystroke.modifiers.control);
+    if show == vim.workspace_state.pane_numbers_shown {
+        return;
+    }
+    Vim::update(cx, |vim, _| vim.workspace_state.pane_numbers_shown = show);
+    let panes = workspace.center_panes();
+    // a single pane needs no number
+    let show = show && panes.len() > 1;
+    for (ix, pane) in panes.iter().enumerate() {
+        pane.update(cx, |pane, cx| {
+            pane.set_number_badge(show.then_some(ix + 1), cx)
+        });
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use crate::test::VimTestContext;
+
+    fn active_pane_index(cx: &mut VimTestContext) -> usize {
+        cx.workspace(|workspace, _| {
+            let panes = workspace.center_panes();
+            panes
+                .iter()
+                .position(|pane| pane == workspace.active_pane())
+                .unwrap()
+        })
+    }
+
+    #[gpui::test]
+    async fn test_next_pane_with_count(cx: &mut gpui::TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+
+        cx.simulate_keystrokes(["ctrl-w", "v", "ctrl-w", "v"]);
+        cx.run_until_parked();
+        cx.simulate_keystrokes(["1", "ctrl-w", "w"]);
+        assert_eq!(active_pane_index(&mut cx), 0);
+        cx.simulate_keystrokes(["ctrl-w", "w"]);
+        assert_eq!(active_pane_index(&mut cx), 1);
+        cx.simulate_keystrokes(["9", "ctrl-w", "ctrl-w"]);
+        assert_eq!(active_pane_index(&mut cx), 2);
+    }
+
+    #[gpui::test]
+    async fn test_pane_numbers(cx: &mut gpui::TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+        let numbers = |cx: &mut VimTestContext| {
+            cx.workspace(|workspace, cx| {
+                workspace
+                    .center_panes()
+                    .iter()
+                    .map(|pane| pane.read(cx).number_badge())
+                    .collect::<Vec<_>>()
+            })
+        };
+
+        cx.simulate_keystrokes(["ctrl-w", "v"]);
+        cx.run_until_parked();
+        assert_eq!(numbers(&mut cx), [None, None]);
+        cx.simulate_keystrokes(["ctrl-w"]);
+        assert_eq!(numbers(&mut cx), [Some(1), Some(2)]);
+        cx.simulate_keystrokes(["w"]);
+        assert_eq!(numbers(&mut cx), [None, None]);
+
+        // they're taken away when ctrl-w is dropped for waiting too long
+        cx.simulate_keystrokes(["ctrl-w"]);
+        assert_eq!(numbers(&mut cx), [Some(1), Some(2)]);
+        cx.executor().advance_clock(Duration::from_secs(2));
+        cx.run_until_parked();
+        assert_eq!(numbers(&mut cx), [None, None]);
+    }
+}
@@ -0,0 +1,143 @@
+use editor::{scroll::Autoscroll, Editor};
+use gpui::{actions, ViewContext};
+use language::ToOffset;
+use workspace::{pane::CloseAllItems, SaveIntent, SplitDirection, Workspace};
+
+use crate::Vim;
+
+actions!(vim, [PreviewDefinition, ClosePreview]);
+
+pub(crate) fn register(workspace: &mut Workspace, _: &mut ViewContext<Workspace>) {
+    workspace.register_action(preview_definition);
+    workspace.register_action(|workspace: &mut Workspace, _: &ClosePreview, cx| {
+        close_preview(workspace, cx)
+    });
+}
+
+/// `ctrl-w }` shows the definition of the symbol under the cursor in the preview
+/// pane, like vim's preview window: a split above, opened the first time and reused
+/// after that. The cursor stays where it is, so neither its place nor the jump list
+/// changes.
+fn preview_definition(
+    workspace: &mut Workspace,
+    _: &PreviewDefinition,
+    cx: &mut ViewContext<Workspace>,
+) {
+    let Some(editor) = workspace.active_item_as::<Editor>(cx) else {
+        return;
+    };
+    Vim::update(cx, |vim, cx| vim.clear_operator(cx));
+    let Some((buffer, position)) = editor.update(cx, |editor, cx| {
+        let head = editor.selections.newest::<usize>(cx).head();
+        editor.buffer().read(cx).text_anchor_for_position(head, cx)
+    }) else {
+        return;
+    };
+    let definitions = workspace
+        .project()
+        .update(cx, |project, cx| project.definition(&buffer, position, cx));
+    cx.spawn(|workspace, mut cx| async move {
+        let definitions = definitions.await?;
+        workspace.update(&mut cx, |workspace, cx| {
+            let Some(target) = definitions.into_iter().next().map(|link| link.target) else {
+                Vim::update(cx, |vim, _| vim.show_message("E426: Tag not found"));
+                return;
+            };
+            let existing = Vim::read(cx).workspace_state.preview_pane.clone();
+            let pane = existing
+                .and_then(|pane| pane.upgrade())
+                .filter(|pane| workspace.panes().contains(pane))
+                .unwrap_or_else(|| {
+                    workspace.split_pane(workspace.active_pane().clone(), SplitDirection::Up, cx)
+                });
+            Vim::update(cx, |vim, _| {
+                vim.workspace_state.preview_pane = Some(pane.downgrade())
+            });
+
+            let offset = target.range.start.to_offset(target.buffer.read(cx));
+            let preview = workspace.open_project_item::<Editor>(pane, target.buffer, cx);
+            preview.update(cx, |preview, cx| {
+                preview.change_selections(Some(Autoscroll::center()), cx, |s| {
+                    s.select_ranges([offset..offset])
+                });
+            });
+            cx.focus_view(&editor);
+        })
+    })
+    .detach_and_log_err(cx);
+}
+
+/// `ctrl-w z` (or `:pclose`) closes the preview pane.
+fn close_preview(workspace: &mut Workspace, cx: &mut ViewContext<Workspace>) {
+    let Some(pane) = Vim::update(cx, |vim, _| vim.workspace_state.preview_pane.take())
+        .and_then(|pane| pane.upgrade())
+        .filter(|pane| workspace.panes().contains(pane))
+    else {
+        return;
+    };
+    let close = pane.update(cx, |pane, cx| {
+        pane.close_all_items(
+            &CloseAllItems {
+                save_intent: Some(SaveIntent::Close),
+            },
+            cx,
+        )
+    });
+    if let Some(close) = close {
+        close.detach_and_log_err(cx);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use editor::Editor;
+
+    use crate::{state::Mode, test::VimTestContext};
+
+    #[gpui::test]
+    async fn test_preview_definition(cx: &mut gpui::TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+
+        cx.set_state("fn foo() {}\nfn bar() { ˇfoo(); }", Mode::Normal);
+        cx.lsp
+            .handle_request::<lsp::request::GotoDefinition, _, _>(|_, _| async move {
+                Ok(Some(lsp::GotoDefinitionResponse::Scalar(lsp::Location {
+                    uri: lsp::Url::from_file_path("/root/dir/file.rs").unwrap(),
+                    range: lsp::Range::new(lsp::Position::new(0, 3), lsp::Position::new(0, 6)),
+                })))
+            });
+
+        cx.simulate_keystrokes(["ctrl-w", "}"]);
+        cx.run_until_parked();
+        // the cursor stays put, and the definition is shown in a split above
+        cx.assert_state("fn foo() {}\nfn bar() { ˇfoo(); }", Mode::Normal);
+        let preview = cx.workspace(|workspace, cx| {
+            assert_eq!(workspace.panes().len(), 2);
+            let preview_pane = workspace
+                .panes()
+                .iter()
+                .find(|pane| *pane != workspace.active_pane())
+                .unwrap();
+            preview_pane
+                .read(cx)
+                .active_item()
+                .and_then(|item| item.act_as::<Editor>(cx))
+                .unwrap()
+        });
+        let cursor = cx.update(|cx| {
+            preview.update(cx, |preview, cx| {
+                preview.selections.newest::<usize>(cx).head()
+            })
+        });
+        assert_eq!(cursor, 3);
+
+        // it's reused by the next preview, and closed with ctrl-w z
+        cx.simulate_keystrokes(["ctrl-w", "}"]);
+        cx.run_until_parked();
+        cx.workspace(|workspace, _| assert_eq!(workspace.panes().len(), 2));
+        cx.simulate_keystrokes(["ctrl-w", "z"]);
+        cx.run_until_parked();
+        cx.workspace(|workspace, _| assert_eq!(workspace.panes().len(), 1));
+        cx.assert_state("fn foo() {}\nfn bar() { ˇfoo(); }", Mode::Normal);
+    }
+}
@@ -0,0 +1,660 @@
+use std::{
+    cell::RefCell,
+    path::{Path, PathBuf},
+    rc::Rc,
+};
+
+use editor::{scroll::Autoscroll, Anchor, Editor, ToPoint};
+use gpui::{actions, impl_actions, Action, ViewContext, WeakView, WindowContext};
+use language::Point;
+use regex::Regex;
+use serde_derive::Deserialize;
+use settings::Settings;
+use workspace::Workspace;
+
+use crate::{
+    command::{command_steps, finish_normal},
+    navigate::step_in_list,
+    normal::repeat::{replay, ReplayStep},
+    state::Mode,
+    utils::{end_undo_group, run_with_input, worktree_root, UndoGroup},
+    Vim, VimSettings,
+};
+
+/// `:mak[e] {args}` runs the `makeprg` setting (with the args after it) in the root
+/// of the project, and fills the quickfix list with the errors the `errorformat`
+/// setting finds in its output, then jumps to the first.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub(crate) struct Make {
+    args: String,
+}
+
+/// `:cdo {cmd}` runs an ex command (or `:normal` keys) at each entry of the quickfix
+/// list, and `:cfdo {cmd}` runs it once in each file the list has entries in, at the
+/// first of them. A file's entries are marked when it's opened, so that lines the
+/// command adds or deletes don't throw off where it runs next. The changes to each
+/// file are undone in one step, and the status bar shows how far along it is.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub(crate) struct QuickfixDo {
+    command: String,
+    per_file: bool,
+}
+
+impl_actions!(vim, [Make, QuickfixDo]);
+
+actions!(vim, [NextQuickfix, PreviousQuickfix]);
+
+/// The errors found by the last `:make`, the places the last `:Rename` changed, or the
+/// symbols the last `:wsymbol` found.
+/// Until there is one, `:cnext` and `:cprevious` go through the project's
+/// diagnostics instead.
+#[derive(Clone, Debug, PartialEq)]
+pub struct QuickfixList {
+    pub entries: Vec<QuickfixEntry>,
+    /// The entry last jumped to
+    pub current: usize,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct QuickfixEntry {
+    pub path: PathBuf,
+    /// 1-based, as compilers print them
+    pub line: u32,
+    pub column: u32,
+    pub text: String,
+}
+
+pub(crate) fn register(workspace: &mut Workspace, _: &mut ViewContext<Workspace>) {
+    workspace.register_action(make);
+    workspace.register_action(quickfix_do);
+    workspace.register_action(|workspace: &mut Workspace, _: &NextQuickfix, cx| {
+        let count = Vim::update(cx, |vim, cx| vim.take_count(cx)).unwrap_or(1);
+        move_in_list(workspace, count as isize, cx)
+    });
+    workspace.register_action(|workspace: &mut Workspace, _: &PreviousQuickfix, cx| {
+        let count = Vim::update(cx, |vim, cx| vim.take_count(cx)).unwrap_or(1);
+        move_in_list(workspace, -(count as isize), cx)
+    });
+}
+
+fn make(workspace: &mut Workspace, action: &Make, cx: &mut ViewContext<Workspace>) {
+    let settings = VimSettings::get_global(cx);
+    let command = format!("{} {}", settings.makeprg, action.args)
+        .trim()
+        .to_string();
+    let formats = settings.errorformat.clone();
+
+    let project = workspace.project().clone();
+    let root = workspace
+        .active_item_as::<Editor>(cx)
+        .and_then(|editor| editor.read(cx).buffer().read(cx).as_singleton())
+        .and_then(|buffer| worktree_root(&buffer, &project, cx))
+        .or_else(|| {
+            let worktree = project.read(cx).visible_worktrees(cx).next()?;
+            let root = worktree.read(cx).abs_path().to_path_buf();
+            Some(root)
+        });
+    let Some(root) = root else {
+        Vim::update(cx, |vim, _| {
+            vim.show_message("E344: Can't find a directory to run make in")
+        });
+        return;
+    };
+
+    if Vim::update(cx, |vim, _| vim.refuse_pending_operation()) {
+        return;
+    }
+    // interrupting it (`ctrl-c`) kills the command
+    let task = cx.spawn(|workspace, mut cx| async move {
+        let output = run_with_input(&command, "", Some(&root)).await;
+        workspace
+            .update(&mut cx, |workspace, cx| {
+                Vim::update(cx, |vim, _| vim.finish_pending_operation());
+                let output = match output {
+                    Ok(output) => output,
+                    Err(error) => {
+                        return Vim::update(cx, |vim, _| vim.show_message(error.to_string()));
+                    }
+                };
+                let text = String::from_utf8_lossy(&output.stdout).to_string()
+                    + &String::from_utf8_lossy(&output.stderr);
+                let entries = parse_errors(&text, &formats, &root);
+                Vim::update(cx, |vim, _| vim.workspace_state.last_output = Some(text));
+                if entries.is_empty() {
+                    Vim::update(cx, |vim, _| {
+                        vim.workspace_state.quickfix = None;
+                        vim.show_message(format!("\"{command}\" found no errors"))
+                    });
+                    return;
+                }
+                let list = QuickfixList {
+                    entries,
+                    current: 0,
+                };
+                jump_to(workspace, &list, cx);
+                Vim::update(cx, |vim, _| vim.workspace_state.quickfix = Some(list));
+            })
+            .ok();
+    });
+    Vim::update(cx, |vim, _| vim.pending_operation = Some(task));
+}
+
+fn quickfix_do(workspace: &mut Workspace, action: &QuickfixDo, cx: &mut ViewContext<Workspace>) {
+    let Some(list) = Vim::read(cx).workspace_state.quickfix.clone() else {
+        Vim::update(cx, |vim, _| vim.show_message("E42: No Errors"));
+        return;
+    };
+    let Some(command) = command_steps(&action.command, cx) else {
+        Vim::update(cx, |vim, _| {
+            vim.show_message(format!("E492: Not an editor command: {}", action.command))
+        });
+        return;
+    };
+
+    // the entries of each file, with the files in the order the list has them
+    let mut files: Vec<(PathBuf, Vec<usize>)> = Vec::new();
+    for (ix, entry) in list.entries.iter().enumerate() {
+        match files.iter_mut().find(|(path, _)| *path == entry.path) {
+            Some((_, entries)) => entries.push(ix),
+            None => files.push((entry.path.clone(), vec![ix])),
+        }
+    }
+    if action.per_file {
+        for (_, entries) in &mut files {
+            entries.truncate(1);
+        }
+    }
+
+    let file_count = files.len();
+    let entry_count = files
+        .iter()
+        .map(|(_, entries)| entries.len())
+        .sum::<usize>();
+    let undo_groups = Rc::new(RefCell::new(Vec::new()));
+    let mut steps = Vec::new();
+    for (file_ix, (path, entries)) in files.into_iter().enumerate() {
+        let marks = Rc::new(RefCell::new(None));
+        let points = entries
+            .iter()
+            .map(|ix| {
+                let entry = &list.entries[*ix];
+                Point::new(entry.line.saturating_sub(1), entry.column.saturating_sub(1))
+            })
+            .collect();
+        let progress = format!("{}: file {} of {file_count}", action.name(), file_ix + 1);
+        let workspace = cx.view().downgrade();
+        steps.push(ReplayStep::Run(Box::new({
+            let marks = marks.clone();
+            let undo_groups = undo_groups.clone();
+            move |cx| open_and_mark(workspace, path, points, marks, undo_groups, progress, cx)
+        })));
+        for (mark_ix, entry_ix) in entries.into_iter().enumerate() {
+            let marks = marks.clone();
+            let command = command.clone();
+            steps.push(ReplayStep::Run(Box::new(move |cx| {
+                let Some((editor, anchors)) = marks.borrow().clone() else {
+                    return;
+                };
+                editor
+                    .update(cx, |editor, cx| {
+                        let snapshot = editor.buffer().read(cx).snapshot(cx);
+                        let point = anchors[mark_ix].to_point(&snapshot);
+                        editor.change_selections(Some(Autoscroll::center()), cx, |s| {
+                            s.select_ranges([point..point])
+                        });
+                    })
+                    .ok();
+                Vim::update(cx, |vim, _| {
+                    if let Some(list) = &mut vim.workspace_state.quickfix {
+                        list.current = entry_ix;
+                    }
+                });
+                replay(command(), cx);
+            })));
+        }
+    }
+
+    // each file's changes are undone together, however the replay ends
+    let name = action.name();
+    replay(steps, cx).on_finish(move |cx| {
+        for undo_group in undo_groups.borrow().iter() {
+            end_undo_group(undo_group, cx);
+        }
+        finish_normal(cx);
+        Vim::update(cx, |vim, _| {
+            vim.show_message(format!(
+                "{name}: ran at {entry_count} entries in {file_count} files"
+            ))
+        });
+    });
+}
+
+/// Opens a file for `:cdo`, and marks the places its entries are, holding the
+/// replay back until it's open. The file's changes are grouped into one undo step
+/// from here.
+fn open_and_mark(
+    workspace: WeakView<Workspace>,
+    path: PathBuf,
+    points: Vec<Point>,
+    marks: Rc<RefCell<Option<(WeakView<Editor>, Vec<Anchor>)>>>,
+    undo_groups: Rc<RefCell<Vec<UndoGroup>>>,
+    progress: String,
+    cx: &mut WindowContext,
+) {
+    let Some(replayer) = Vim::read(cx).workspace_state.replayer.clone() else {
+        return;
+    };
+    let Ok(open) = workspace.update(cx, |workspace, cx| workspace.open_abs_path(path, true, cx))
+    else {
+        return;
+    };
+    Vim::update(cx, |vim, _| vim.show_progress(progress));
+    replayer.pause();
+    cx.spawn(|mut cx| async move {
+        let item = open.await;
+        cx.update(|cx| {
+            match item.map(|item| item.downcast::<Editor>()) {
+                Ok(Some(editor)) => {
+                    let (anchors, undo_group) = editor.update(cx, |editor, cx| {
+                        let snapshot = editor.buffer().read(cx).snapshot(cx);
+                        let anchors = points
+                            .into_iter()
+                            .map(|point| {
+                                let point = snapshot.clip_point(point, language::Bias::Left);
+                                snapshot.anchor_before(point)
+                            })
+                            .collect();
+                        (anchors, UndoGroup::new(editor, cx))
+                    });
+                    *marks.borrow_mut() = Some((editor.downgrade(), anchors));
+                    undo_groups.borrow_mut().push(undo_group);
+                    Vim::update(cx, |vim, cx| vim.switch_mode(Mode::Normal, false, cx));
+                }
+                Ok(None) => {}
+                // the file's entries are skipped
+                Err(error) => Vim::update(cx, |vim, _| {
+                    vim.show_message(format!("E37: Can't open the file: {error}"))
+                }),
+            }
+            replayer.resume(cx);
+        })
+        .ok();
+    })
+    .detach();
+}
+
+impl QuickfixDo {
+    pub(crate) fn name(&self) -> &'static str {
+        if self.per_file {
+            "cfdo"
+        } else {
+            "cdo"
+        }
+    }
+}
+
+/// Finds the errors in a command's output: the lines that match one of the formats,
+/// with file names relative to the root.
+fn parse_errors(output: &str, formats: &[String], root: &Path) -> Vec<QuickfixEntry> {
+    let regexes = formats
+        .iter()
+        .filter_map(|format| errorformat_regex(format))
+        .collect::<Vec<_>>();
+    output
+        .lines()
+        .filter_map(|line| {
+            let captures = regexes.iter().find_map(|regex| regex.captures(line))?;
+            let number = |name| {
+                captures
+                    .name(name)
+                    .and_then(|number| number.as_str().parse::<u32>().ok())
+                    .unwrap_or(1)
+            };
+            Some(QuickfixEntry {
+                path: root.join(captures.name("file")?.as_str()),
+                line: number("line"),
+                column: number("column"),
+                text: captures
+                    .name("message")
+                    .map_or("", |message| message.as_str())
+                    .trim()
+                    .to_string(),
+            })
+        })
+        .collect()
+}
+
+/// Turns an `errorformat` pattern into a regex for a whole line: `%f` matches the file
+/// name, `%l` the line, `%c` the column, `%m` the message and `%%` a percent sign.
+/// Anything else is matched as it is.
+fn errorformat_regex(format: &str) -> Option<Regex> {
+    let mut pattern = "^".to_string();
+    let mut chars = format.chars();
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            pattern.push_str(&regex::escape(c.encode_utf8(&mut [0; 4])));
+            continue;
+        }
+        pattern.push_str(match chars.next()? {
+            'f' => r"(?P<file>.+?)",
+            'l' => r"(?P<line>\d+)",
+            'c' => r"(?P<column>\d+)",
+            'm' => r"(?P<message>.*)",
+            '%' => "%",
+            _ => return None,
+        });
+    }
+    pattern.push('$');
+    Regex::new(&pattern).ok()
+}
+
+/// `:cnext` and `:cprevious` (and `]q` and `[q`, which take a count) go through the
+/// errors of the last `:make`, or the diagnostics when there's been none.
+fn move_in_list(workspace: &mut Workspace, delta: isize, cx: &mut ViewContext<Workspace>) {
+    let Some(mut list) = Vim::read(cx).workspace_state.quickfix.clone() else {
+        let action = if delta > 0 {
+            editor::actions::GoToDiagnostic.boxed_clone()
+        } else {
+            editor::actions::GoToPrevDiagnostic.boxed_clone()
+        };
+        for _ in 0..delta.unsigned_abs() {
+            cx.dispatch_action(action.boxed_clone());
+        }
+        return;
+    };
+    let wrap = VimSettings::get_global(cx).bracket_wrap.lists;
+    let Some(target) = step_in_list(list.current as isize, delta, list.entries.len(), wrap) else {
+        Vim::update(cx, |vim, _| vim.show_message("E553: No more items"));
+        return;
+    };
+    list.current = target;
+    jump_to(workspace, &list, cx);
+    Vim::update(cx, |vim, _| vim.workspace_state.quickfix = Some(list));
+}
+
+/// Opens the file of the list's current entry, and moves the cursor to the error.
+pub(crate) fn jump_to(
+    workspace: &mut Workspace,
+    list: &QuickfixList,
+    cx: &mut ViewContext<Workspace>,
+) {
+    let entry = list.entries[list.current].clone();
+    let message = format!(
+        "({} of {}): {}",
+        list.current + 1,
+        list.entries.len(),
+        entry.text
+    );
+    let open = workspace.open_abs_path(entry.path.clone(), true, cx);
+    cx.spawn(|_, mut cx| async move {
+        let item = open.await?;
+        cx.update(|cx| {
+            if let Some(editor) = item.downcast::<Editor>() {
+                editor.update(cx, |editor, cx| {
+                    let snapshot = editor.buffer().read(cx).snapshot(cx);
+                    let point = snapshot.clip_point(
+                        Point::new(entry.line.saturating_sub(1), entry.column.saturating_sub(1)),
+                        language::Bias::Left,
+                    );
+                    editor.change_selections(Some(Autoscroll::center()), cx, |s| {
+                        s.select_ranges([point..point])
+                    });
+                });
+            }
+            Vim::update(cx, |vim, cx| {
+                vim.switch_mode(Mode::Normal, false, cx);
+                vim.show_message(message)
+            });
+        })
+    })
+    .detach_and_log_err(cx);
+}
+
+/// `:cdo {cmd}` or `:cfd[o] {cmd}`.
+pub(crate) fn parse_quickfix_do(query: &str) -> Option<QuickfixDo> {
+    let (name, command) = query.split_once(' ')?;
+    let per_file = match name {
+        "cdo" => false,
+        "cfd" | "cfdo" => true,
+        _ => return None,
+    };
+    let command = command.trim();
+    if command.is_empty() {
+        return None;
+    }
+    Some(QuickfixDo {
+        command: command.to_string(),
+        per_file,
+    })
+}
+
+pub(crate) fn parse_make(query: &str) -> Option<Make> {
+    let name_len = query
+        .find(|c: char| !c.is_ascii_alphabetic())
+        .unwrap_or(query.len());
+    let (name, args) = query.split_at(name_len);
+    if !matches!(name, "mak" | "make") || !(args.is_empty() || args.starts_with(' ')) {
+        return None;
+    }
+    Some(Make {
+        args: args.trim().to_string(),
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::{Path, PathBuf};
+
+    use editor::Editor;
+    use settings::SettingsStore;
+
+    use super::{parse_errors, QuickfixEntry, QuickfixList};
+    use crate::{state::Mode, test::VimTestContext, Vim, VimSettings};
+
+    fn editor_texts(cx: &mut VimTestContext) -> Vec<String> {
+        cx.workspace(|workspace, cx| {
+            let mut texts = workspace
+                .items_of_type::<Editor>(cx)
+                .map(|editor| editor.read(cx).text(cx))
+                .collect::<Vec<_>>();
+            texts.sort();
+            texts
+        })
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let output = "\
+            compiling\n\
+            src/main.c:3:5: error: expected ';'\n\
+            /abs/lib.c:10: warning: unused\n\
+            done";
+        let formats = ["%f:%l:%c: %m".to_string(), "%f:%l: %m".to_string()];
+        assert_eq!(
+            parse_errors(output, &formats, Path::new("/root")),
+            vec![
+                QuickfixEntry {
+                    path: PathBuf::from("/root/src/main.c"),
+                    line: 3,
+                    column: 5,
+                    text: "error: expected ';'".to_string(),
+                },
+                QuickfixEntry {
+                    path: PathBuf::from("/abs/lib.c"),
+                    line: 10,
+                    column: 1,
+                    text: "warning: unused".to_string(),
+                },
+            ]
+        );
+    }
+
+    #[gpui::test]
+    async fn test_quickfix_navigation(cx: &mut gpui::TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+        let fs = cx.workspace(|workspace, cx| workspace.project().read(cx).fs().clone());
+        fs.as_fake()
+            .insert_file("/root/dir/lib.rs", "one\ntwo\nthree".into())
+            .await;
+
+        cx.set_state("ˇfn main() {}", Mode::Normal);
+        let entry = |path: &str, line, column| QuickfixEntry {
+            path: PathBuf::from(path),
+            line,
+            column,
+            text: format!("error {line}"),
+        };
+        cx.update_global(|vim: &mut Vim, _| {
+            vim.workspace_state.quickfix = Some(QuickfixList {
+                entries: vec![
+                    entry("/root/dir/file.rs", 1, 4),
+                    entry("/root/dir/lib.rs", 3, 2),
+                ],
+                current: 0,
+            })
+        });
+
+        cx.simulate_keystrokes([":"]);
+        cx.simulate_input("cn");
+        cx.simulate_keystrokes(["enter"]);
+        cx.run_until_parked();
+        cx.workspace(|workspace, cx| {
+            let editor = workspace.active_item_as::<Editor>(cx).unwrap();
+            let editor = editor.read(cx);
+            assert_eq!(editor.text(cx), "one\ntwo\nthree");
+            assert_eq!(
+                editor.selections.newest::<language::Point>(cx).head(),
+                language::Point::new(2, 1)
+            );
+        });
+        assert_eq!(cx.message().as_deref(), Some("(2 of 2): error 3"));
+
+        cx.simulate_keystrokes([":"]);
+        cx.simulate_input("cn");
+        cx.simulate_keystrokes(["enter"]);
+        assert_eq!(cx.message().as_deref(), Some("E553: No more items"));
+    }
+
+    #[gpui::test]
+    async fn test_quickfix_do(cx: &mut gpui::TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+        let fs = cx.workspace(|workspace, cx| workspace.project().read(cx).fs().clone());
+        fs.as_fake()
+            .insert_file("/root/dir/lib.rs", "foo\nboo".into())
+            .await;
+
+        cx.set_state("ˇone\ntwo", Mode::Normal);
+        let entry = |path: &str, line| QuickfixEntry {
+            path: PathBuf::from(path),
+            line,
+            column: 1,
+            text: String::new(),
+        };
+        cx.update_global(|vim: &mut Vim, _| {
+            vim.workspace_state.quickfix = Some(QuickfixList {
+                entries: vec![
+                    entry("/root/dir/file.rs", 1),
+                    entry("/root/dir/file.rs", 2),
+                    entry("/root/dir/lib.rs", 1),
+                ],
+                current: 0,
+            })
+        });
+
+        // the entries are marked, so a deleted line doesn't move the ones after it
+        cx.simulate_keystrokes([":"]);
+        cx.simulate_input("cdo normal Ox");
+        cx.simulate_keystrokes(["enter"]);
+        cx.run_until_parked();
+        assert_eq!(editor_texts(&mut cx), ["x\nfoo\nboo", "x\none\nx\ntwo"]);
+        assert_eq!(
+            cx.message().as_deref(),
+            Some("cdo: ran at 3 entries in 2 files")
+        );
+
+        // each file's changes are undone together
+        cx.simulate_keystrokes(["u"]);
+        assert_eq!(editor_texts(&mut cx), ["foo\nboo", "x\none\nx\ntwo"]);
+
+        // :cfdo runs once in each file
+        cx.simulate_keystrokes([":"]);
+        cx.simulate_input("cfdo %s/o/0/g");
+        cx.simulate_keystrokes(["enter"]);
+        cx.run_until_parked();
+        assert_eq!(editor_texts(&mut cx), ["f00\nb00", "x\n0ne\nx\ntw0"]);
+    }
+
+    #[gpui::test]
+    async fn test_make(cx: &mut gpui::TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+        cx.update_global(|store: &mut SettingsStore, cx| {
+            store.update_user_settings::<VimSettings>(cx, |s| {
+                s.makeprg = Some("printf 'file.rs:1:4: error: one\\n'".to_string())
+            });
+        });
+
+        cx.set_state("ˇfn main() {}", Mode::Normal);
+        cx.simulate_keystrokes([":"]);
+        cx.simulate_input("make");
+        cx.simulate_keystrokes(["enter"]);
+        cx.run_until_operation_finishes();
+        let quickfix = cx.update_global(|vim: &mut Vim, _| vim.workspace_state.quickfix.clone());
+        assert_eq!(
+            quickfix.map(|list| list.entries),
+            Some(vec![QuickfixEntry {
+                path: PathBuf::from("/root/dir/file.rs"),
+                line: 1,
+                column: 4,
+                text: "error: one".to_string(),
+            }])
+        );
+
+        cx.simulate_keystrokes([":"]);
+        cx.simulate_input("make > /dev/null");
+        cx.simulate_keystrokes(["enter"]);
+        cx.run_until_operation_finishes();
+        assert!(cx.update_global(|vim: &mut Vim, _| vim.workspace_state.quickfix.is_none()));
+        assert_eq!(
+            cx.message().as_deref(),
+            Some("\"printf 'file.rs:1:4: error: one\\n' > /dev/null\" found no errors")
+        );
+    }
+
+    #[gpui::test]
+    async fn test_quickfix_do_aborted(cx: &mut gpui::TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+
+        cx.set_state("ˇone\ntwo", Mode::Normal);
+        let entry = |line| QuickfixEntry {
+            path: PathBuf::from("/root/dir/file.rs"),
+            line,
+            column: 1,
+            text: String::new(),
+        };
+        cx.update_global(|vim: &mut Vim, _| {
+            vim.workspace_state.quickfix = Some(QuickfixList {
+                entries: vec![entry(2), entry(1), entry(2)],
+                current: 0,
+            })
+        });
+
+        // the k on the first line fails, which stops the replay, and the changes it
+        // made are still undone together
+        cx.simulate_keystrokes([":"]);
+        cx.simulate_input("cdo normal xk");
+        cx.simulate_keystrokes(["enter"]);
+        cx.run_until_parked();
+        cx.assert_state("ˇne\nwo", Mode::Normal);
+        cx.simulate_keystrokes(["u"]);
+        cx.assert_state("ˇone\ntwo", Mode::Normal);
+    }
+
+    #[test]
+    fn test_parse_quickfix_do() {
+        let cdo = super::parse_quickfix_do("cdo s/a/b/").unwrap();
+        assert_eq!(cdo.command, "s/a/b/");
+        assert!(!cdo.per_file);
+        assert!(super::parse_quickfix_do("cfd norm x").unwrap().per_file);
+        assert!(super::parse_quickfix_do("cdo").is_none());
+    }
+}
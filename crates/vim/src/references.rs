@@ -0,0 +1,310 @@
+use std::{ops::Range, sync::Arc};
+
+use editor::{scroll::Autoscroll, Editor};
+use fuzzy::{StringMatch, StringMatchCandidate};
+use gpui::{
+    actions, AppContext, DismissEvent, EventEmitter, FocusHandle, FocusableView, Model,
+    ParentElement, Render, Styled, Task, View, ViewContext, VisualContext, WeakView,
+};
+use language::{Anchor, Buffer, Point, ToOffset, ToPoint};
+use picker::{Picker, PickerDelegate};
+use ui::{prelude::*, HighlightedLabel, ListItem, ListItemSpacing};
+use workspace::{ModalView, SplitDirection, Workspace};
+
+use crate::{
+    list_picker::{self, ModalDelegate, StringListDelegate},
+    Vim,
+};
+
+actions!(
+    vim,
+    [
+        ListReferences,
+        OpenReferenceInSplit,
+        OpenReferenceInVerticalSplit
+    ]
+);
+
+/// `gr` lists the references to the symbol under the cursor, as the language server
+/// finds them. In the list, `enter` opens a reference in the current pane, `ctrl-x`
+/// in a split below and `ctrl-v` in a split to the right, like vim's file pickers.
+pub(crate) fn register(workspace: &mut Workspace, _: &mut ViewContext<Workspace>) {
+    workspace.register_action(|workspace: &mut Workspace, _: &ListReferences, cx| {
+        let Some(editor) = workspace.active_item_as::<Editor>(cx) else {
+            return;
+        };
+        Vim::update(cx, |vim, cx| vim.clear_operator(cx));
+        let Some((buffer, position)) = editor.update(cx, |editor, cx| {
+            let head = editor.selections.newest::<usize>(cx).head();
+            editor.buffer().read(cx).text_anchor_for_position(head, cx)
+        }) else {
+            return;
+        };
+        let references = workspace
+            .project()
+            .update(cx, |project, cx| project.references(&buffer, position, cx));
+        cx.spawn(|workspace, mut cx| async move {
+            let locations = references.await?;
+            workspace.update(&mut cx, |workspace, cx| {
+                if locations.is_empty() {
+                    Vim::update(cx, |vim, _| vim.show_message("No references found"));
+                    return;
+                }
+                let references = locations
+                    .into_iter()
+                    .map(|location| Reference::new(location.buffer, location.range, cx))
+                    .collect();
+                let workspace_handle = cx.view().downgrade();
+                workspace.toggle_modal(cx, |cx| {
+                    ReferenceList::new(workspace_handle, references, cx)
+                });
+            })
+        })
+        .detach_and_log_err(cx);
+    });
+}
+
+struct Reference {
+    buffer: Model<Buffer>,
+    position: Anchor,
+    /// The path, line number and text of the line, as the list shows them
+    label: String,
+}
+
+impl Reference {
+    fn new(buffer: Model<Buffer>, range: Range<Anchor>, cx: &AppContext) -> Self {
+        let snapshot = buffer.read(cx);
+        let point = range.start.to_point(snapshot);
+        let line = snapshot
+            .text_for_range(
+                Point::new(point.row, 0)..Point::new(point.row, snapshot.line_len(point.row)),
+            )
+            .collect::<String>();
+        let path = snapshot
+            .file()
+            .map(|file| file.path().to_string_lossy().to_string())
+            .unwrap_or_default();
+        Self {
+            position: range.start,
+            label: format!("{path}:{}: {}", point.row + 1, line.trim()),
+            buffer,
+        }
+    }
+}
+
+pub struct ReferenceList {
+    picker: View<Picker<ReferenceListDelegate>>,
+}
+
+impl ReferenceList {
+    fn new(
+        workspace: WeakView<Workspace>,
+        references: Vec<Reference>,
+        cx: &mut ViewContext<Self>,
+    ) -> Self {
+        let candidates = references
+            .iter()
+            .enumerate()
+            .map(|(id, reference)| StringMatchCandidate::new(id, reference.label.clone()))
+            .collect();
+        let delegate = ReferenceListDelegate {
+            reference_list: cx.view().downgrade(),
+            workspace,
+            references,
+            candidates,
+            matches: Vec::new(),
+            selected_index: 0,
+        };
+        let picker = cx.new_view(|cx| Picker::uniform_list(delegate, cx));
+        Self { picker }
+    }
+
+    fn open(&mut self, split: Option<SplitDirection>, cx: &mut ViewContext<Self>) {
+        self.picker
+            .update(cx, |picker, cx| picker.delegate.open(split, cx))
+    }
+}
+
+impl Render for ReferenceList {
+    fn render(&mut self, cx: &mut ViewContext<Self>) -> impl IntoElement {
+        v_flex()
+            .key_context("ReferenceList")
+            .w(rems(34.))
+            .on_action(cx.listener(|this, _: &OpenReferenceInSplit, cx| {
+                this.open(Some(SplitDirection::Down), cx)
+            }))
+            .on_action(cx.listener(|this, _: &OpenReferenceInVerticalSplit, cx| {
+                this.open(Some(SplitDirection::Right), cx)
+            }))
+            .child(self.picker.clone())
+    }
+}
+
+impl FocusableView for ReferenceList {
+    fn focus_handle(&self, cx: &AppContext) -> FocusHandle {
+        self.picker.focus_handle(cx)
+    }
+}
+
+impl EventEmitter<DismissEvent> for ReferenceList {}
+impl ModalView for ReferenceList {}
+
+pub struct ReferenceListDelegate {
+    reference_list: WeakView<ReferenceList>,
+    workspace: WeakView<Workspace>,
+    references: Vec<Reference>,
+    candidates: Vec<StringMatchCandidate>,
+    matches: Vec<StringMatch>,
+    selected_index: usize,
+}
+
+impl ReferenceListDelegate {
+    /// Opens the selected reference in the active pane, or in a new split of it.
+    fn open(&mut self, split: Option<SplitDirection>, cx: &mut ViewContext<Picker<Self>>) {
+        if let Some(mat) = self.matches.get(self.selected_index) {
+            let reference = &self.references[mat.candidate_id];
+            let buffer = reference.buffer.clone();
+            let offset = reference.position.to_offset(buffer.read(cx));
+            self.workspace
+                .update(cx, |workspace, cx| {
+                    let pane = match split {
+                        Some(direction) => {
+                            workspace.split_pane(workspace.active_pane().clone(), direction, cx)
+                        }
+                        None => workspace.active_pane().clone(),
+                    };
+                    let editor = workspace.open_project_item::<Editor>(pane, buffer, cx);
+                    editor.update(cx, |editor, cx| {
+                        editor.change_selections(Some(Autoscroll::center()), cx, |s| {
+                            s.select_ranges([offset..offset])
+                        });
+                        editor.focus(cx);
+                    });
+                })
+                .ok();
+        }
+        self.dismissed(cx);
+    }
+}
+
+impl ModalDelegate for ReferenceListDelegate {
+    type Modal = ReferenceList;
+
+    fn modal(&self) -> &WeakView<ReferenceList> {
+        &self.reference_list
+    }
+}
+
+impl StringListDelegate for ReferenceListDelegate {
+    fn candidates(&self) -> &[StringMatchCandidate] {
+        &self.candidates
+    }
+
+    fn set_matches(&mut self, matches: Vec<StringMatch>) {
+        self.matches = matches;
+    }
+}
+
+impl PickerDelegate for ReferenceListDelegate {
+    type ListItem = ListItem;
+
+    fn placeholder_text(&self, _cx: &mut WindowContext) -> Arc<str> {
+        "Filter references...".into()
+    }
+
+    fn match_count(&self) -> usize {
+        self.matches.len()
+    }
+
+    fn selected_index(&self) -> usize {
+        self.selected_index
+    }
+
+    fn set_selected_index(&mut self, ix: usize, _: &mut ViewContext<Picker<Self>>) {
+        self.selected_index = ix;
+    }
+
+    fn update_matches(&mut self, query: String, cx: &mut ViewContext<Picker<Self>>) -> Task<()> {
+        list_picker::update_matches(self, query, cx)
+    }
+
+    fn confirm(&mut self, _: bool, cx: &mut ViewContext<Picker<Self>>) {
+        self.open(None, cx);
+    }
+
+    fn dismissed(&mut self, cx: &mut ViewContext<Picker<Self>>) {
+        list_picker::dismiss(self, cx)
+    }
+
+    fn render_match(
+        &self,
+        ix: usize,
+        selected: bool,
+        _: &mut ViewContext<Picker<Self>>,
+    ) -> Option<Self::ListItem> {
+        let mat = &self.matches[ix];
+        let reference = &self.references[mat.candidate_id];
+        Some(
+            ListItem::new(ix)
+                .inset(true)
+                .spacing(ListItemSpacing::Sparse)
+                .selected(selected)
+                .child(HighlightedLabel::new(
+                    reference.label.clone(),
+                    mat.positions.clone(),
+                )),
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use editor::Editor;
+    use language::Point;
+
+    use super::ReferenceList;
+    use crate::{state::Mode, test::VimTestContext};
+
+    #[gpui::test]
+    async fn test_list_references(cx: &mut gpui::TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+
+        cx.set_state(
+            "fn ˇfoo() {}\nfn bar() { foo(); }\nfn baz() { foo(); }",
+            Mode::Normal,
+        );
+        cx.lsp
+            .handle_request::<lsp::request::References, _, _>(|_, _| async move {
+                let location = |row| lsp::Location {
+                    uri: lsp::Url::from_file_path("/root/dir/file.rs").unwrap(),
+                    range: lsp::Range::new(
+                        lsp::Position::new(row, 11),
+                        lsp::Position::new(row, 14),
+                    ),
+                };
+                Ok(Some(vec![location(1), location(2)]))
+            });
+
+        cx.simulate_keystrokes(["g", "r"]);
+        cx.run_until_parked();
+        assert!(cx.workspace(|workspace, cx| workspace.active_modal::<ReferenceList>(cx).is_some()));
+        cx.simulate_keystrokes(["enter"]);
+        cx.assert_state(
+            "fn foo() {}\nfn bar() { ˇfoo(); }\nfn baz() { foo(); }",
+            Mode::Normal,
+        );
+
+        // ctrl-v opens the reference in a split to the right
+        cx.simulate_keystrokes(["g", "r"]);
+        cx.run_until_parked();
+        cx.simulate_keystrokes(["ctrl-j", "ctrl-v"]);
+        cx.run_until_parked();
+        cx.workspace(|workspace, cx| {
+            assert_eq!(workspace.panes().len(), 2);
+            assert!(workspace.active_modal::<ReferenceList>(cx).is_none());
+            let editor = workspace.active_item_as::<Editor>(cx).unwrap();
+            let cursor = editor.read(cx).selections.newest::<Point>(cx).head();
+            assert_eq!(cursor, Point::new(2, 11));
+        });
+    }
+}
@@ -0,0 +1,212 @@
+use command_palette_hooks::ToggleWithQuery;
+use editor::Editor;
+use gpui::{actions, impl_actions, ViewContext};
+use language::{Point, ToPoint};
+use serde_derive::Deserialize;
+use workspace::Workspace;
+
+use crate::{
+    command_line::word_under_cursor,
+    quickfix::{QuickfixEntry, QuickfixList},
+    Vim,
+};
+
+/// `:Rename {name}` renames the symbol under the cursor everywhere in the project, as
+/// the language server finds it. Each file's changes are undone in one step, and the
+/// places that changed fill the quickfix list, so `:cnext` goes through them.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub(crate) struct Rename {
+    new_name: String,
+}
+
+impl_actions!(vim, [Rename]);
+
+actions!(vim, [RenameSymbol]);
+
+pub(crate) fn register(workspace: &mut Workspace, _: &mut ViewContext<Workspace>) {
+    // opens the `:` prompt with `Rename` and the word under the cursor, to be edited
+    // into the new name.
+    workspace.register_action(|_: &mut Workspace, _: &RenameSymbol, cx| {
+        let word = Vim::update(cx, |vim, cx| {
+            vim.clear_operator(cx);
+            vim.update_active_editor(cx, |_, editor, cx| word_under_cursor(editor, false, cx))
+        })
+        .flatten()
+        .unwrap_or_default();
+        cx.dispatch_action(Box::new(ToggleWithQuery {
+            query: format!("Rename {word}"),
+        }));
+    });
+    workspace.register_action(rename);
+}
+
+fn rename(workspace: &mut Workspace, action: &Rename, cx: &mut ViewContext<Workspace>) {
+    Vim::update(cx, |vim, cx| vim.clear_operator(cx));
+    let Some(editor) = workspace.active_item_as::<Editor>(cx) else {
+        return;
+    };
+    let Some((buffer, position)) = editor.update(cx, |editor, cx| {
+        let head = editor.selections.newest::<usize>(cx).head();
+        editor.buffer().read(cx).text_anchor_for_position(head, cx)
+    }) else {
+        return;
+    };
+    let cursor_path = buffer
+        .read(cx)
+        .file()
+        .and_then(|file| file.as_local())
+        .map(|file| file.abs_path(cx));
+    let cursor_row = position.to_point(buffer.read(cx)).row;
+
+    let rename = workspace.project().update(cx, |project, cx| {
+        project.perform_rename(buffer, position, action.new_name.clone(), true, cx)
+    });
+    cx.spawn(|workspace, mut cx| async move {
+        let transaction = rename.await?;
+        workspace.update(&mut cx, |_, cx| {
+            let files = transaction.0.len();
+            let mut entries = Vec::new();
+            for (buffer, transaction) in transaction.0 {
+                let buffer = buffer.read(cx);
+                let Some(path) = buffer
+                    .file()
+                    .and_then(|file| file.as_local())
+                    .map(|file| file.abs_path(cx))
+                else {
+                    continue;
+                };
+                for range in buffer.edited_ranges_for_transaction::<Point>(&transaction) {
+                    let line = buffer
+                        .text_for_range(
+                            Point::new(range.start.row, 0)
+                                ..Point::new(range.start.row, buffer.line_len(range.start.row)),
+                        )
+                        .collect::<String>();
+                    entries.push(QuickfixEntry {
+                        path: path.clone(),
+                        line: range.start.row + 1,
+                        column: range.start.column + 1,
+                        text: line.trim().to_string(),
+                    });
+                }
+            }
+            if entries.is_empty() {
+                Vim::update(cx, |vim, _| vim.show_message("Nothing to rename"));
+                return;
+            }
+            entries.sort_by(|a, b| (&a.path, a.line, a.column).cmp(&(&b.path, b.line, b.column)));
+
+            Vim::update(cx, |vim, _| {
+                vim.show_message(rename_message(entries.len(), files));
+                // the cursor is on one of the places already, so `:cnext` starts from it.
+                let current = entries
+                    .iter()
+                    .position(|entry| {
+                        Some(&entry.path) == cursor_path.as_ref() && entry.line == cursor_row + 1
+                    })
+                    .unwrap_or(0);
+                vim.workspace_state.quickfix = Some(QuickfixList { entries, current });
+            });
+        })
+    })
+    .detach_and_log_err(cx);
+}
+
+fn rename_message(changes: usize, files: usize) -> String {
+    let plural = |count: usize| if count == 1 { "" } else { "s" };
+    format!(
+        "Renamed {changes} occurrence{} in {files} file{}",
+        plural(changes),
+        plural(files)
+    )
+}
+
+/// The name after `:Rename`.
+pub(crate) fn parse_rename(query: &str) -> Option<Rename> {
+    let name_len = query
+        .find(|c: char| !c.is_ascii_alphabetic())
+        .unwrap_or(query.len());
+    let (name, args) = query.split_at(name_len);
+    if !matches!(name, "Ren" | "Rena" | "Renam" | "Rename") || !args.starts_with(' ') {
+        return None;
+    }
+    let new_name = args.trim();
+    if new_name.is_empty() {
+        return None;
+    }
+    Some(Rename {
+        new_name: new_name.to_string(),
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::PathBuf;
+
+    use futures::StreamExt;
+
+    use crate::{state::Mode, test::VimTestContext, Vim};
+
+    #[gpui::test]
+    async fn test_rename_command(cx: &mut gpui::TestAppContext) {
+        let mut cx = VimTestContext::new_typescript(cx).await;
+
+        cx.set_state("const beˇfore = 2;\nconsole.log(before)", Mode::Normal);
+        let def_range = cx.lsp_range("const «beforeˇ» = 2;\nconsole.log(before)");
+        let tgt_range = cx.lsp_range("const before = 2;\nconsole.log(«beforeˇ»)");
+        let mut rename_request =
+            cx.handle_request::<lsp::request::Rename, _, _>(move |url, params, _| async move {
+                Ok(Some(lsp::WorkspaceEdit {
+                    changes: Some(
+                        [(
+                            url.clone(),
+                            vec![
+                                lsp::TextEdit::new(def_range, params.new_name.clone()),
+                                lsp::TextEdit::new(tgt_range, params.new_name),
+                            ],
+                        )]
+                        .into(),
+                    ),
+                    ..Default::default()
+                }))
+            });
+
+        cx.simulate_keystrokes([":"]);
+        cx.simulate_input("Rename after");
+        cx.simulate_keystrokes(["enter"]);
+        rename_request.next().await.unwrap();
+        cx.run_until_parked();
+        assert_eq!(cx.buffer_text(), "const after = 2;\nconsole.log(after)");
+
+        let list = cx.update(|cx| Vim::read(cx).workspace_state.quickfix.clone().unwrap());
+        assert_eq!(
+            list.entries
+                .iter()
+                .map(|entry| (entry.line, entry.column))
+                .collect::<Vec<_>>(),
+            [(1, 7), (2, 13)]
+        );
+        assert_eq!(list.current, 0);
+        assert!(list.entries[0].path.ends_with(PathBuf::from("file.ts")));
+
+        cx.simulate_keystrokes([":"]);
+        cx.simulate_input("cnext");
+        cx.simulate_keystrokes(["enter"]);
+        cx.run_until_parked();
+        cx.assert_state("const after = 2;\nconsole.log(ˇafter)", Mode::Normal);
+
+        // the whole rename is undone at once
+        cx.simulate_keystrokes(["u"]);
+        assert_eq!(cx.buffer_text(), "const before = 2;\nconsole.log(before)");
+    }
+
+    #[test]
+    fn test_parse_rename() {
+        assert_eq!(
+            super::parse_rename("Rename  after ").map(|rename| rename.new_name),
+            Some("after".to_string())
+        );
+        assert!(super::parse_rename("Rename").is_none());
+        assert!(super::parse_rename("Renamer x").is_none());
+    }
+}
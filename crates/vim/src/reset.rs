@@ -0,0 +1,151 @@
+use gpui::{actions, ViewContext, WindowContext};
+use workspace::Workspace;
+
+use crate::{
+    state::{Mode, Operator},
+    Vim,
+};
+
+actions!(vim, [ResetState]);
+
+/// `vim::ResetState` (which has no key by default) is a way out when vim's state has
+/// got stuck: the pending operator, count and register are dropped, as are a macro
+/// being recorded (which isn't stored), the change being recorded for `.` and the rest
+/// of a replay. What was dropped is logged.
+pub(crate) fn register(workspace: &mut Workspace, _: &mut ViewContext<Workspace>) {
+    workspace.register_action(|_: &mut Workspace, _: &ResetState, cx| {
+        Vim::update(cx, |vim, cx| {
+            let discarded = reset_state(vim, cx);
+            if discarded.is_empty() {
+                vim.show_message("Nothing to reset");
+            } else {
+                log::info!("vim state reset, discarding {}", discarded.join(", "));
+                vim.show_message(format!("Reset, discarding {}", discarded.join(", ")));
+            }
+        })
+    });
+}
+
+/// Checks the state once a keystroke has been handled, and resets it when it
+/// contradicts itself, so that a bug that wedges it costs the command being typed
+/// rather than the rest of the session.
+pub(crate) fn check_state(cx: &mut WindowContext) {
+    Vim::update(cx, |vim, cx| {
+        // a replay passes through states that a keystroke doesn't leave behind
+        if vim.workspace_state.replayer.is_some() {
+            return;
+        }
+        let problems = inconsistencies(vim);
+        if problems.is_empty() {
+            return;
+        }
+        let discarded = reset_state(vim, cx);
+        log::error!(
+            "vim state was inconsistent ({}), so it was reset, discarding {}",
+            problems.join("; "),
+            discarded.join(", ")
+        );
+    })
+}
+
+/// What's wrong with the state, if anything.
+fn inconsistencies(vim: &Vim) -> Vec<&'static str> {
+    let state = vim.state();
+    let workspace_state = &vim.workspace_state;
+    let mut problems = Vec::new();
+    if !state.operator_stack.is_empty() && matches!(state.mode, Mode::Insert | Mode::Select) {
+        problems.push("an operator is pending outside normal and visual mode");
+    }
+    if state.operator_stack.contains(&Operator::Custom) && state.custom_operator.is_none() {
+        problems.push("g@ is pending without an operator to run");
+    }
+    if workspace_state.replaying {
+        problems.push("`.` is replaying with no replay running");
+    }
+    if workspace_state.stop_recording_after_next_action && !workspace_state.recording {
+        problems.push("recording for `.` is to stop without having started");
+    }
+    if workspace_state.recording_prompt && workspace_state.recording_register.is_none() {
+        problems.push("a prompt is recorded into no macro");
+    }
+    problems
+}
+
+/// Puts the state back to where it is in normal mode with nothing pending, and
+/// returns what it dropped, for the log.
+fn reset_state(vim: &mut Vim, cx: &mut WindowContext) -> Vec<String> {
+    let mut discarded = Vec::new();
+    let command = vim.pending_command();
+    if !command.is_empty() {
+        discarded.push(format!("the pending command {command}"));
+    }
+    vim.update_state(|state| {
+        state.clear_pending();
+        state.custom_operator = None;
+    });
+    vim.workspace_state.selected_register = None;
+
+    let workspace_state = &mut vim.workspace_state;
+    if let Some(register) = workspace_state.recording_register.take() {
+        discarded.push(format!("the macro being recorded into register {register}"));
+    }
+    workspace_state.recorded_macro.clear();
+    workspace_state.recorded_keys.clear();
+    workspace_state.recording_prompt = false;
+    workspace_state.ignore_current_insertion = false;
+    if workspace_state.recording {
+        discarded.push("the change being recorded for `.`".to_string());
+        workspace_state.recorded_actions.clear();
+    }
+    workspace_state.recording = false;
+    workspace_state.stop_recording_after_next_action = false;
+    workspace_state.replaying = false;
+    if vim.is_replaying() {
+        discarded.push("the rest of the replay".to_string());
+        vim.abort_replay();
+    }
+
+    vim.sync_vim_settings(cx);
+    discarded
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{state::Mode, test::VimTestContext, Vim};
+
+    #[gpui::test]
+    async fn test_reset_state(cx: &mut gpui::TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+
+        // the action drops what's pending
+        cx.set_state("ˇone two", Mode::Normal);
+        cx.simulate_keystrokes(["q", "a", "\"", "b", "2", "d"]);
+        cx.dispatch_action(super::ResetState);
+        cx.update(|cx| {
+            let vim = Vim::read(cx);
+            assert!(vim.state().operator_stack.is_empty());
+            assert_eq!(vim.state().pre_count, None);
+            assert_eq!(vim.workspace_state.selected_register, None);
+            assert_eq!(vim.workspace_state.recording_register, None);
+        });
+        assert_eq!(
+            cx.message().as_deref(),
+            Some(concat!(
+                "Reset, discarding the pending command \"b2d, ",
+                "the macro being recorded into register a, ",
+                "the change being recorded for `.`"
+            ))
+        );
+        cx.simulate_keystrokes(["w"]);
+        cx.assert_state("one ˇtwo", Mode::Normal);
+
+        // and a state that contradicts itself is reset after the next keystroke, like
+        // a `.` that was left replaying
+        cx.update_global(|vim: &mut Vim, _| vim.workspace_state.replaying = true);
+        cx.simulate_keystrokes(["b"]);
+        cx.update(|cx| assert!(!Vim::read(cx).workspace_state.replaying));
+        cx.assert_state("ˇone two", Mode::Normal);
+        cx.simulate_keystrokes(["d", "w", "."]);
+        cx.assert_state("ˇ", Mode::Normal);
+    }
+}
@@ -0,0 +1,102 @@
+use editor::Editor;
+use gpui::{div, Element, Render, Subscription, View, ViewContext};
+use language::Point;
+use settings::{Settings, SettingsStore};
+use workspace::{item::ItemHandle, ui::prelude::*, StatusItemView};
+
+use crate::{utils::screen_width, Vim, VimSettings};
+
+/// The Ruler shows the line and column of the cursor in the status bar, and how far
+/// through the file it is, like vim's `ruler` option. It is off by default.
+pub struct Ruler {
+    pub(crate) text: Option<String>,
+    editor: Option<View<Editor>>,
+    _observe_active_editor: Option<Subscription>,
+    _subscriptions: [Subscription; 2],
+}
+
+impl Ruler {
+    /// Construct a new ruler in this window.
+    pub fn new(cx: &mut ViewContext<Self>) -> Self {
+        let _subscriptions = [
+            cx.observe_global::<Vim>(|this, cx| this.update_text(cx)),
+            cx.observe_global::<SettingsStore>(|this, cx| this.update_text(cx)),
+        ];
+        Self {
+            text: None,
+            editor: None,
+            _observe_active_editor: None,
+            _subscriptions,
+        }
+    }
+
+    fn update_text(&mut self, cx: &mut ViewContext<Self>) {
+        // Vim doesn't exist in some tests
+        let enabled = cx.try_global::<Vim>().is_some_and(|vim| vim.enabled);
+        let text = match &self.editor {
+            Some(editor) if enabled && VimSettings::get_global(cx).ruler => {
+                Some(ruler_text(editor, cx))
+            }
+            _ => None,
+        };
+        if text != self.text {
+            self.text = text;
+            cx.notify();
+        }
+    }
+}
+
+/// Formats the cursor position the way vim does: `line,column`, then the screen
+/// column after a dash when tabs, multi-byte or wide characters make it differ, and
+/// the percentage of the way through the file.
+fn ruler_text(editor: &View<Editor>, cx: &ViewContext<Ruler>) -> String {
+    let editor = editor.read(cx);
+    let snapshot = editor.buffer().read(cx).snapshot(cx);
+    let cursor = editor.selections.newest::<Point>(cx).head();
+    let tab_size = snapshot.settings_at(cursor, cx).tab_size.get();
+
+    let line_start = Point::new(cursor.row, 0);
+    let screen_column = screen_width(
+        &snapshot
+            .text_for_range(line_start..cursor)
+            .collect::<String>(),
+        tab_size as usize,
+    );
+
+    let column = if snapshot.line_len(cursor.row) == 0 {
+        "0-1".to_string()
+    } else if screen_column != cursor.column as usize {
+        format!("{}-{}", cursor.column + 1, screen_column + 1)
+    } else {
+        format!("{}", cursor.column + 1)
+    };
+    let lines = snapshot.max_point().row + 1;
+    let percentage = (cursor.row + 1) * 100 / lines;
+    format!("{},{}  {}%", cursor.row + 1, column, percentage)
+}
+
+impl Render for Ruler {
+    fn render(&mut self, _: &mut ViewContext<Self>) -> impl IntoElement {
+        let Some(text) = self.text.as_ref() else {
+            return div().into_any();
+        };
+        Label::new(text.clone())
+            .size(LabelSize::Small)
+            .into_any_element()
+    }
+}
+
+impl StatusItemView for Ruler {
+    fn set_active_pane_item(
+        &mut self,
+        active_pane_item: Option<&dyn ItemHandle>,
+        cx: &mut ViewContext<Self>,
+    ) {
+        self.editor = active_pane_item.and_then(|item| item.act_as::<Editor>(cx));
+        self._observe_active_editor = self
+            .editor
+            .as_ref()
+            .map(|editor| cx.observe(editor, |this, _, cx| this.update_text(cx)));
+        self.update_text(cx);
+    }
+}
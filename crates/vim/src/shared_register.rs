@@ -0,0 +1,107 @@
+use editor::Editor;
+use gpui::{AppContext, ViewContext};
+use rpc::proto::update_shared_register::Kind;
+use settings::Settings;
+use workspace::Workspace;
+
+use crate::{
+    normal::registers::set_register,
+    state::{RegisterKind, WorkspaceState},
+    VimSettings,
+};
+
+/// With the `shared_register` setting, text yanked or deleted into that register is
+/// sent to the collaborators in the project, whose own register of that name gets it
+/// (if they've chosen the same one), so that it can be put on their machine.
+pub(crate) fn register(workspace: &mut Workspace, cx: &mut ViewContext<Workspace>) {
+    cx.subscribe(workspace.project(), |_, _, event, cx| {
+        if let project::Event::SharedRegisterUpdated { name, text, kind } = event {
+            let Some(register) = VimSettings::get_global(cx).shared_register else {
+                return;
+            };
+            if name.chars().eq([register]) {
+                set_register(register, text.clone(), register_kind(*kind), cx);
+            }
+        }
+    })
+    .detach();
+}
+
+/// Sends the shared register to the collaborators, if it's the one that was written.
+pub(crate) fn share_register(
+    state: &WorkspaceState,
+    written: Option<char>,
+    editor: &Editor,
+    cx: &AppContext,
+) {
+    let Some(register) = VimSettings::get_global(cx).shared_register else {
+        return;
+    };
+    // `"A` appends to `"a`, which is sent as a whole
+    if written.map(|written| written.to_ascii_lowercase()) != Some(register) {
+        return;
+    }
+    let (Some(text), Some(project)) = (state.read_register(register), editor.project()) else {
+        return;
+    };
+    let kind = proto_kind(state.register_kind(register));
+    project
+        .read(cx)
+        .share_register(register.to_string(), text.clone(), kind)
+        .ok();
+}
+
+fn proto_kind(kind: RegisterKind) -> Kind {
+    match kind {
+        RegisterKind::Charwise => Kind::Charwise,
+        RegisterKind::Linewise => Kind::Linewise,
+        RegisterKind::Blockwise => Kind::Blockwise,
+    }
+}
+
+fn register_kind(kind: Kind) -> RegisterKind {
+    match kind {
+        Kind::Charwise => RegisterKind::Charwise,
+        Kind::Linewise => RegisterKind::Linewise,
+        Kind::Blockwise => RegisterKind::Blockwise,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use rpc::proto::update_shared_register::Kind;
+    use settings::SettingsStore;
+
+    use crate::{state::Mode, test::VimTestContext, VimSettings};
+
+    #[gpui::test]
+    async fn test_shared_register(cx: &mut gpui::TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+        let project = cx.workspace(|workspace, _| workspace.project().clone());
+        let share = |cx: &mut VimTestContext, name: &str| {
+            cx.update(|cx| {
+                project.update(cx, |_, cx| {
+                    cx.emit(project::Event::SharedRegisterUpdated {
+                        name: name.to_string(),
+                        text: "shared\n".to_string(),
+                        kind: Kind::Linewise,
+                    })
+                })
+            })
+        };
+
+        // it's opt-in
+        cx.set_state("ˇone", Mode::Normal);
+        share(&mut cx, "s");
+        cx.simulate_keystrokes(["\"", "s", "p"]);
+        cx.assert_state("ˇone", Mode::Normal);
+
+        cx.update_global(|store: &mut SettingsStore, cx| {
+            store.update_user_settings::<VimSettings>(cx, |s| s.shared_register = Some('s'));
+        });
+        share(&mut cx, "t");
+        share(&mut cx, "s");
+        cx.simulate_keystrokes(["\"", "s", "p"]);
+        cx.assert_state("one\nˇshared", Mode::Normal);
+    }
+}
@@ -0,0 +1,190 @@
+use editor::{Editor, GutterSign};
+use gpui::{AppContext, ViewContext};
+use language::{Bias, Point};
+use settings::Settings;
+
+use crate::{Vim, VimSettings};
+
+/// The names of marks, beside the lines they're on.
+struct MarkSigns;
+
+/// `@q` (or whichever register) on the cursor's line while a macro is recorded.
+struct RecordingSign;
+
+/// `>` beside the lines the quickfix list has an entry for.
+struct QuickfixSigns;
+
+/// Updates the signs vim shows in the gutter of an editor.
+pub(crate) fn sync_signs(vim: &Vim, editor: &mut Editor, cx: &mut ViewContext<Editor>) {
+    let mark_signs = if VimSettings::get_global(cx).show_marks_in_gutter {
+        vim.state()
+            .marks
+            .iter()
+            .map(|(name, anchor)| GutterSign {
+                anchor: *anchor,
+                text: name.to_string().into(),
+                color: |colors| colors.text_accent,
+                priority: 1,
+            })
+            .collect()
+    } else {
+        Vec::new()
+    };
+    editor.set_gutter_signs::<MarkSigns>(mark_signs, cx);
+
+    let recording_sign = vim
+        .workspace_state
+        .recording_register
+        .map(|register| GutterSign {
+            anchor: editor.selections.newest_anchor().head(),
+            text: format!("@{register}").into(),
+            color: |colors| colors.terminal_ansi_red,
+            priority: 2,
+        });
+    editor.set_gutter_signs::<RecordingSign>(recording_sign.into_iter().collect(), cx);
+
+    let quickfix_signs = quickfix_signs(vim, editor, cx);
+    editor.set_gutter_signs::<QuickfixSigns>(quickfix_signs, cx);
+}
+
+fn quickfix_signs(vim: &Vim, editor: &Editor, cx: &AppContext) -> Vec<GutterSign> {
+    let Some(list) = &vim.workspace_state.quickfix else {
+        return Vec::new();
+    };
+    let Some(path) = editor.buffer().read(cx).as_singleton().and_then(|buffer| {
+        buffer
+            .read(cx)
+            .file()?
+            .as_local()
+            .map(|file| file.abs_path(cx))
+    }) else {
+        return Vec::new();
+    };
+    let snapshot = editor.buffer().read(cx).snapshot(cx);
+    list.entries
+        .iter()
+        .filter(|entry| entry.path == path)
+        .map(|entry| {
+            let point =
+                snapshot.clip_point(Point::new(entry.line.saturating_sub(1), 0), Bias::Left);
+            GutterSign {
+                anchor: snapshot.anchor_before(point),
+                text: ">".into(),
+                color: |colors| colors.terminal_ansi_yellow,
+                priority: 0,
+            }
+        })
+        .collect()
+}
+
+pub(crate) fn clear_recording_sign(editor: &mut Editor, cx: &mut ViewContext<Editor>) {
+    editor.clear_gutter_signs::<RecordingSign>(cx);
+}
+
+pub(crate) fn clear_signs(editor: &mut Editor, cx: &mut ViewContext<Editor>) {
+    editor.clear_gutter_signs::<MarkSigns>(cx);
+    editor.clear_gutter_signs::<RecordingSign>(cx);
+    editor.clear_gutter_signs::<QuickfixSigns>(cx);
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::PathBuf;
+
+    use settings::SettingsStore;
+    use ui::ActiveTheme;
+
+    use crate::{
+        quickfix::{QuickfixEntry, QuickfixList},
+        state::Mode,
+        test::VimTestContext,
+        Vim, VimSettings,
+    };
+
+    fn show_marks(cx: &mut VimTestContext) {
+        cx.update_global(|store: &mut SettingsStore, cx| {
+            store.update_user_settings::<VimSettings>(cx, |s| s.show_marks_in_gutter = Some(true));
+        });
+    }
+
+    fn signs(cx: &mut VimTestContext) -> Vec<(u32, String)> {
+        cx.update_editor(|editor, cx| {
+            let snapshot = editor.snapshot(cx);
+            editor
+                .gutter_signs_in_range(0..u32::MAX, &snapshot.display_snapshot, cx.theme().colors())
+                .into_iter()
+                .map(|(row, (text, _))| (row, text))
+                .collect()
+        })
+    }
+
+    #[gpui::test]
+    async fn test_recording_sign(cx: &mut gpui::TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+        show_marks(&mut cx);
+
+        cx.set_state("ˇone\ntwo\nthree", Mode::Normal);
+        cx.simulate_keystrokes(["m", "a", "m", "b", "j", "m", "c"]);
+        assert_eq!(
+            signs(&mut cx),
+            vec![(0, "ab".to_string()), (1, "c".to_string())]
+        );
+
+        // the recording sign follows the cursor, and hides the marks on its line
+        cx.simulate_keystrokes(["q", "q"]);
+        assert_eq!(
+            signs(&mut cx),
+            vec![(0, "ab".to_string()), (1, "@q".to_string())]
+        );
+        cx.simulate_keystrokes(["j"]);
+        assert_eq!(
+            signs(&mut cx),
+            vec![
+                (0, "ab".to_string()),
+                (1, "c".to_string()),
+                (2, "@q".to_string())
+            ]
+        );
+        cx.simulate_keystrokes(["q"]);
+        assert_eq!(
+            signs(&mut cx),
+            vec![(0, "ab".to_string()), (1, "c".to_string())]
+        );
+    }
+
+    #[gpui::test]
+    async fn test_quickfix_signs(cx: &mut gpui::TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+        show_marks(&mut cx);
+
+        cx.set_state("ˇone\ntwo\nthree", Mode::Normal);
+        let entry = |path: &str, line| QuickfixEntry {
+            path: PathBuf::from(path),
+            line,
+            column: 1,
+            text: format!("error {line}"),
+        };
+        cx.update_global(|vim: &mut Vim, _| {
+            vim.workspace_state.quickfix = Some(QuickfixList {
+                entries: vec![
+                    entry("/root/dir/file.rs", 2),
+                    entry("/root/dir/lib.rs", 1),
+                    entry("/root/dir/file.rs", 9),
+                ],
+                current: 0,
+            })
+        });
+
+        // only the entries in the editor's file are shown, and marks hide them
+        cx.simulate_keystrokes(["i", "escape"]);
+        assert_eq!(
+            signs(&mut cx),
+            vec![(1, ">".to_string()), (2, ">".to_string())]
+        );
+        cx.simulate_keystrokes(["j", "m", "a"]);
+        assert_eq!(
+            signs(&mut cx),
+            vec![(1, "a".to_string()), (2, ">".to_string())]
+        );
+    }
+}
@@ -0,0 +1,420 @@
+use std::{ops::Range, sync::Arc};
+
+use editor::{scroll::Autoscroll, Editor};
+use fuzzy::{StringMatch, StringMatchCandidate};
+use gpui::{
+    actions, AppContext, DismissEvent, EventEmitter, FocusHandle, FocusableView, Global,
+    ParentElement, Render, Styled, Task, View, ViewContext, VisualContext, WeakView,
+};
+use language::Point;
+use picker::{Picker, PickerDelegate};
+use settings::Settings;
+use ui::{prelude::*, HighlightedLabel, ListItem, ListItemSpacing};
+use workspace::{ModalView, Workspace};
+
+use crate::{
+    completion::{keyword_chars, keywords},
+    list_picker::{self, ModalDelegate, StringListDelegate},
+    navigate::{hit_end_message, nth_item},
+    Vim, VimSettings,
+};
+
+actions!(
+    vim,
+    [
+        NextMisspelling,
+        PreviousMisspelling,
+        SpellSuggestions,
+        AddWordToDictionary
+    ]
+);
+
+/// What vim's spelling keys ask about words: `]s` and `[s` go to the next or previous
+/// misspelled word, `z=` lists the suggestions for the word under the cursor and `zg`
+/// adds it to the dictionary. Until a spell checker is set with [`set_spell_checker`],
+/// they show vim's "spell checking is not enabled" error.
+pub trait SpellChecker {
+    /// The byte ranges of the misspelled words in the text.
+    fn misspellings(&self, text: &str) -> Vec<Range<usize>>;
+    /// The corrections for a misspelled word, best first.
+    fn suggestions(&self, word: &str) -> Vec<String>;
+    /// Adds the word to the user's dictionary, so it's no longer a misspelling.
+    fn add_word(&self, word: &str);
+}
+
+struct GlobalSpellChecker(Arc<dyn SpellChecker>);
+
+impl Global for GlobalSpellChecker {}
+
+/// Sets the spell checker the spelling keys use.
+pub fn set_spell_checker(checker: Arc<dyn SpellChecker>, cx: &mut AppContext) {
+    cx.set_global(GlobalSpellChecker(checker));
+}
+
+fn spell_checker(cx: &mut WindowContext) -> Option<Arc<dyn SpellChecker>> {
+    let checker = cx
+        .try_global::<GlobalSpellChecker>()
+        .map(|checker| checker.0.clone());
+    if checker.is_none() {
+        Vim::update(cx, |vim, _| {
+            vim.show_message("E756: Spell checking is not enabled")
+        });
+    }
+    checker
+}
+
+pub(crate) fn register(workspace: &mut Workspace, _: &mut ViewContext<Workspace>) {
+    workspace.register_action(|workspace: &mut Workspace, _: &NextMisspelling, cx| {
+        move_to_misspelling(workspace, true, cx)
+    });
+    workspace.register_action(|workspace: &mut Workspace, _: &PreviousMisspelling, cx| {
+        move_to_misspelling(workspace, false, cx)
+    });
+    workspace.register_action(spell_suggestions);
+    workspace.register_action(|workspace: &mut Workspace, _: &AddWordToDictionary, cx| {
+        Vim::update(cx, |vim, cx| vim.clear_operator(cx));
+        let Some(editor) = workspace.active_item_as::<Editor>(cx) else {
+            return;
+        };
+        let Some(checker) = spell_checker(cx) else {
+            return;
+        };
+        let Some((_, word)) = word_under_cursor(&editor, cx) else {
+            Vim::update(cx, |vim, _| {
+                vim.show_message("E349: No identifier under cursor")
+            });
+            return;
+        };
+        checker.add_word(&word);
+        Vim::update(cx, |vim, _| {
+            vim.show_message(format!("Word '{word}' added to the dictionary"))
+        });
+    });
+}
+
+/// `]s` and `[s` move to the start of the next or previous misspelled word (the
+/// count-th one), wrapping around the end of the buffer unless `bracket_wrap` says
+/// not to.
+fn move_to_misspelling(workspace: &mut Workspace, forwards: bool, cx: &mut ViewContext<Workspace>) {
+    let count = Vim::update(cx, |vim, cx| {
+        let count = vim.take_count(cx).unwrap_or(1);
+        vim.clear_operator(cx);
+        count
+    });
+    let Some(editor) = workspace.active_item_as::<Editor>(cx) else {
+        return;
+    };
+    let Some(checker) = spell_checker(cx) else {
+        return;
+    };
+    let wrap = VimSettings::get_global(cx).bracket_wrap.in_file;
+    let moved = editor.update(cx, |editor, cx| {
+        let snapshot = editor.buffer().read(cx).snapshot(cx);
+        let starts = checker
+            .misspellings(&snapshot.text())
+            .into_iter()
+            .map(|range| range.start)
+            .collect::<Vec<_>>();
+        if starts.is_empty() {
+            return Err("No misspelled words");
+        }
+        let cursor = editor.selections.newest::<usize>(cx).head();
+        let Some(target) = nth_item(&starts, cursor, forwards, count, wrap) else {
+            return Err(hit_end_message(forwards));
+        };
+        editor.change_selections(Some(Autoscroll::fit()), cx, |s| {
+            s.select_ranges([target..target])
+        });
+        Ok(())
+    });
+    if let Err(message) = moved {
+        Vim::update(cx, |vim, _| vim.show_message(message));
+    }
+}
+
+/// The keyword under (or after) the cursor, and its range in the buffer.
+fn word_under_cursor(editor: &View<Editor>, cx: &AppContext) -> Option<(Range<Point>, String)> {
+    let editor = editor.read(cx);
+    let snapshot = editor.buffer().read(cx).snapshot(cx);
+    let cursor = editor.selections.newest::<Point>(cx).head();
+    let line = snapshot
+        .text_for_range(
+            Point::new(cursor.row, 0)..Point::new(cursor.row, snapshot.line_len(cursor.row)),
+        )
+        .collect::<String>();
+    let extra = keyword_chars(snapshot.language_at(cursor), cx);
+    let (start, word) = keywords(&line, &extra)
+        .into_iter()
+        .find(|(start, word)| start + word.len() > cursor.column as usize)?;
+    let range =
+        Point::new(cursor.row, start as u32)..Point::new(cursor.row, (start + word.len()) as u32);
+    Some((range, word.to_string()))
+}
+
+/// `z=` lists the suggestions for the word under the cursor, and replaces the word with
+/// the one that's picked. With a count, the count-th suggestion is used straight away.
+fn spell_suggestions(
+    workspace: &mut Workspace,
+    _: &SpellSuggestions,
+    cx: &mut ViewContext<Workspace>,
+) {
+    let count = Vim::update(cx, |vim, cx| {
+        let count = vim.take_count(cx);
+        vim.clear_operator(cx);
+        count
+    });
+    let Some(editor) = workspace.active_item_as::<Editor>(cx) else {
+        return;
+    };
+    let Some(checker) = spell_checker(cx) else {
+        return;
+    };
+    let Some((range, word)) = word_under_cursor(&editor, cx) else {
+        Vim::update(cx, |vim, _| {
+            vim.show_message("E349: No identifier under cursor")
+        });
+        return;
+    };
+    let suggestions = checker.suggestions(&word);
+    if suggestions.is_empty() {
+        Vim::update(cx, |vim, _| {
+            vim.show_message(format!("Sorry, no suggestions for \"{word}\""))
+        });
+        return;
+    }
+    if let Some(count) = count {
+        if let Some(suggestion) = suggestions.get(count - 1) {
+            replace_word(&editor, range, suggestion, cx);
+        }
+        return;
+    }
+    workspace.toggle_modal(cx, |cx| {
+        SpellSuggestionList::new(editor, range, suggestions, cx)
+    });
+}
+
+fn replace_word(editor: &View<Editor>, range: Range<Point>, text: &str, cx: &mut WindowContext) {
+    editor.update(cx, |editor, cx| {
+        editor.transact(cx, |editor, cx| {
+            editor.edit([(range.clone(), text)], cx);
+            editor.change_selections(Some(Autoscroll::fit()), cx, |s| {
+                s.select_ranges([range.start..range.start])
+            });
+        });
+    });
+}
+
+pub struct SpellSuggestionList {
+    picker: View<Picker<SpellSuggestionListDelegate>>,
+}
+
+impl SpellSuggestionList {
+    fn new(
+        editor: View<Editor>,
+        range: Range<Point>,
+        suggestions: Vec<String>,
+        cx: &mut ViewContext<Self>,
+    ) -> Self {
+        let candidates = suggestions
+            .iter()
+            .enumerate()
+            .map(|(id, suggestion)| StringMatchCandidate::new(id, suggestion.clone()))
+            .collect();
+        let delegate = SpellSuggestionListDelegate {
+            suggestion_list: cx.view().downgrade(),
+            editor,
+            range,
+            suggestions,
+            candidates,
+            matches: Vec::new(),
+            selected_index: 0,
+        };
+        let picker = cx.new_view(|cx| Picker::uniform_list(delegate, cx));
+        Self { picker }
+    }
+}
+
+impl Render for SpellSuggestionList {
+    fn render(&mut self, _cx: &mut ViewContext<Self>) -> impl IntoElement {
+        v_flex().w(rems(24.)).child(self.picker.clone())
+    }
+}
+
+impl FocusableView for SpellSuggestionList {
+    fn focus_handle(&self, cx: &AppContext) -> FocusHandle {
+        self.picker.focus_handle(cx)
+    }
+}
+
+impl EventEmitter<DismissEvent> for SpellSuggestionList {}
+impl ModalView for SpellSuggestionList {}
+
+pub struct SpellSuggestionListDelegate {
+    suggestion_list: WeakView<SpellSuggestionList>,
+    editor: View<Editor>,
+    /// The misspelled word
+    range: Range<Point>,
+    suggestions: Vec<String>,
+    candidates: Vec<StringMatchCandidate>,
+    matches: Vec<StringMatch>,
+    selected_index: usize,
+}
+
+impl ModalDelegate for SpellSuggestionListDelegate {
+    type Modal = SpellSuggestionList;
+
+    fn modal(&self) -> &WeakView<SpellSuggestionList> {
+        &self.suggestion_list
+    }
+}
+
+impl StringListDelegate for SpellSuggestionListDelegate {
+    fn candidates(&self) -> &[StringMatchCandidate] {
+        &self.candidates
+    }
+
+    fn set_matches(&mut self, matches: Vec<StringMatch>) {
+        self.matches = matches;
+    }
+}
+
+impl PickerDelegate for SpellSuggestionListDelegate {
+    type ListItem = ListItem;
+
+    fn placeholder_text(&self, _cx: &mut WindowContext) -> Arc<str> {
+        "Change to...".into()
+    }
+
+    fn match_count(&self) -> usize {
+        self.matches.len()
+    }
+
+    fn selected_index(&self) -> usize {
+        self.selected_index
+    }
+
+    fn set_selected_index(&mut self, ix: usize, _: &mut ViewContext<Picker<Self>>) {
+        self.selected_index = ix;
+    }
+
+    fn update_matches(&mut self, query: String, cx: &mut ViewContext<Picker<Self>>) -> Task<()> {
+        list_picker::update_matches(self, query, cx)
+    }
+
+    fn confirm(&mut self, _: bool, cx: &mut ViewContext<Picker<Self>>) {
+        if let Some(mat) = self.matches.get(self.selected_index) {
+            let suggestion = self.suggestions[mat.candidate_id].clone();
+            replace_word(&self.editor, self.range.clone(), &suggestion, cx);
+            self.editor.update(cx, |editor, cx| editor.focus(cx));
+        }
+        self.dismissed(cx);
+    }
+
+    fn dismissed(&mut self, cx: &mut ViewContext<Picker<Self>>) {
+        list_picker::dismiss(self, cx)
+    }
+
+    fn render_match(
+        &self,
+        ix: usize,
+        selected: bool,
+        _: &mut ViewContext<Picker<Self>>,
+    ) -> Option<Self::ListItem> {
+        let mat = &self.matches[ix];
+        Some(
+            ListItem::new(ix)
+                .inset(true)
+                .spacing(ListItemSpacing::Sparse)
+                .selected(selected)
+                .child(HighlightedLabel::new(
+                    mat.string.clone(),
+                    mat.positions.clone(),
+                )),
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{cell::RefCell, ops::Range, sync::Arc};
+
+    use super::{set_spell_checker, SpellChecker, SpellSuggestionList};
+    use crate::{state::Mode, test::VimTestContext};
+
+    /// Knows the words it's given, and suggests the known ones of the same length.
+    struct FakeSpellChecker {
+        words: RefCell<Vec<String>>,
+    }
+
+    impl SpellChecker for FakeSpellChecker {
+        fn misspellings(&self, text: &str) -> Vec<Range<usize>> {
+            crate::completion::keywords(text, &[])
+                .into_iter()
+                .filter(|(_, word)| !self.words.borrow().iter().any(|known| known == word))
+                .map(|(start, word)| start..start + word.len())
+                .collect()
+        }
+
+        fn suggestions(&self, word: &str) -> Vec<String> {
+            self.words
+                .borrow()
+                .iter()
+                .filter(|known| known.len() == word.len())
+                .cloned()
+                .collect()
+        }
+
+        fn add_word(&self, word: &str) {
+            self.words.borrow_mut().push(word.to_string());
+        }
+    }
+
+    #[gpui::test]
+    async fn test_spelling(cx: &mut gpui::TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+
+        cx.set_state("ˇthe cat sta on teh mat", Mode::Normal);
+        cx.simulate_keystrokes(["]", "s"]);
+        assert_eq!(
+            cx.message().as_deref(),
+            Some("E756: Spell checking is not enabled")
+        );
+
+        cx.update(|cx| {
+            let words = ["the", "cat", "sat", "on", "mat", "mop"];
+            set_spell_checker(
+                Arc::new(FakeSpellChecker {
+                    words: RefCell::new(words.iter().map(|word| word.to_string()).collect()),
+                }),
+                cx,
+            )
+        });
+        cx.simulate_keystrokes(["]", "s"]);
+        cx.assert_state("the cat ˇsta on teh mat", Mode::Normal);
+        cx.simulate_keystrokes(["]", "s"]);
+        cx.assert_state("the cat sta on ˇteh mat", Mode::Normal);
+        // searches wrap around the end of the buffer
+        cx.simulate_keystrokes(["]", "s"]);
+        cx.assert_state("the cat ˇsta on teh mat", Mode::Normal);
+        cx.simulate_keystrokes(["[", "s"]);
+        cx.assert_state("the cat sta on ˇteh mat", Mode::Normal);
+
+        cx.simulate_keystrokes(["z", "g"]);
+        assert_eq!(
+            cx.message().as_deref(),
+            Some("Word 'teh' added to the dictionary")
+        );
+        cx.simulate_keystrokes(["2", "]", "s"]);
+        cx.assert_state("the cat ˇsta on teh mat", Mode::Normal);
+
+        cx.simulate_keystrokes(["z", "="]);
+        assert!(cx.workspace(|workspace, cx| workspace
+            .active_modal::<SpellSuggestionList>(cx)
+            .is_some()));
+        cx.simulate_keystrokes(["enter"]);
+        cx.assert_state("the cat ˇthe on teh mat", Mode::Normal);
+
+        cx.simulate_keystrokes(["u", "2", "z", "="]);
+        cx.assert_state("the cat ˇcat on teh mat", Mode::Normal);
+    }
+}
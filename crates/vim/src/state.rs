@@ -1,12 +1,17 @@
 use std::{fmt::Display, ops::Range, sync::Arc};
 
-use collections::HashMap;
-use gpui::{Action, KeyContext};
-use language::CursorShape;
+use collections::{BTreeMap, HashMap, HashSet};
+use editor::Anchor;
+use gpui::{Action, EntityId, KeyContext, Keystroke, WeakView};
+use language::{CursorShape, TransactionId};
+use project::search::SearchQuery;
 use serde::{Deserialize, Serialize};
-use workspace::searchable::Direction;
+use workspace::{searchable::Direction, Pane};
 
-use crate::motion::Motion;
+use crate::{
+    location_list::LocationList, motion::Motion, normal::repeat::Replayer, quickfix::QuickfixList,
+    tags::TagEntry,
+};
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
 pub enum Mode {
@@ -15,6 +20,9 @@ pub enum Mode {
     Visual,
     VisualLine,
     VisualBlock,
+    /// Like vim's Select mode: entered when a snippet selects a placeholder, so that
+    /// typing replaces it.
+    Select,
 }
 
 impl Display for Mode {
@@ -25,6 +33,7 @@ impl Display for Mode {
             Mode::Visual => write!(f, "VISUAL"),
             Mode::VisualLine => write!(f, "VISUAL LINE"),
             Mode::VisualBlock => write!(f, "VISUAL BLOCK"),
+            Mode::Select => write!(f, "SELECT"),
         }
     }
 }
@@ -32,7 +41,7 @@ impl Display for Mode {
 impl Mode {
     pub fn is_visual(&self) -> bool {
         match self {
-            Mode::Normal | Mode::Insert => false,
+            Mode::Normal | Mode::Insert | Mode::Select => false,
             Mode::Visual | Mode::VisualLine | Mode::VisualBlock => true,
         }
     }
@@ -50,9 +59,40 @@ pub enum Operator {
     Delete,
     Yank,
     Replace,
-    Object { around: bool },
-    FindForward { before: bool },
-    FindBackward { after: bool },
+    Object {
+        around: bool,
+    },
+    FindForward {
+        before: bool,
+    },
+    FindBackward {
+        after: bool,
+    },
+    /// A two-character search, like the vim-sneak plugin's `s` (and `S` for
+    /// `SneakBackward`), waiting for its first character and then its second
+    Sneak {
+        first_char: Option<char>,
+    },
+    SneakBackward {
+        first_char: Option<char>,
+    },
+    RecordRegister,
+    ReplayRegister,
+    Register,
+    Fold,
+    Rewrap,
+    Filter,
+    AutoIndent,
+    Uppercase,
+    Lowercase,
+    OppositeCase,
+    Mark,
+    Jump {
+        line: bool,
+    },
+    /// One of the operators the `operators` setting defines, whose name is the
+    /// editor's `custom_operator`
+    Custom,
 }
 
 #[derive(Default, Clone)]
@@ -64,8 +104,35 @@ pub struct EditorState {
     pub pre_count: Option<usize>,
     /// post_count is the number after an operator is specified (2 in 3d2d)
     pub post_count: Option<usize>,
+    /// register_count is the number before a register is selected (2 in 2"a3dd),
+    /// which multiplies the others
+    pub register_count: Option<usize>,
 
     pub operator_stack: Vec<Operator>,
+    /// The name of the custom operator, while `Operator::Custom` is pending
+    pub custom_operator: Option<String>,
+
+    /// Set when this editor is a command-line window (opened with `q:` or `q/`)
+    pub command_line_window: Option<CommandLineKind>,
+
+    /// The fold level set by `zm`, `zr` (or the `fold_level` setting)
+    pub fold_level: Option<u32>,
+
+    /// The marks set with `m{a-z}`, which are local to each editor
+    pub marks: BTreeMap<char, Anchor>,
+
+    /// What the last visual selection covered, which `\%V` searches in
+    pub last_visual_selection: Vec<Range<Anchor>>,
+
+    /// The last change made before insert mode was entered, which the changes made
+    /// in insert mode aren't grouped with
+    pub insert_undo_base: Option<TransactionId>,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum CommandLineKind {
+    Command,
+    Search { backwards: bool },
 }
 
 #[derive(Default, Clone, Debug)]
@@ -91,21 +158,107 @@ pub enum RecordedSelection {
 #[derive(Default, Clone)]
 pub struct WorkspaceState {
     pub search: SearchState,
-    pub last_find: Option<Motion>,
+    /// The last motion of each kind that `;` and `,` repeat, the latest first
+    pub repeatable_motions: Vec<Motion>,
+    pub last_replacement: Option<String>,
 
     pub recording: bool,
     pub stop_recording_after_next_action: bool,
     pub replaying: bool,
     pub recorded_count: Option<usize>,
+    /// The register the change was made with (as in `"adw`), which `.` uses again
+    pub recorded_register: Option<char>,
     pub recorded_actions: Vec<ReplayableAction>,
     pub recorded_selection: RecordedSelection,
 
+    /// The register a macro is being recorded into (`q{register}`)
+    pub recording_register: Option<char>,
+    /// Set when recording starts, so the register name isn't recorded
+    pub ignore_current_insertion: bool,
+    /// The macro being recorded, which is only stored in the register once
+    /// recording stops
+    pub recorded_macro: Vec<ReplayableAction>,
+    pub recordings: HashMap<char, Vec<ReplayableAction>>,
+    /// The keys typed into the macro being recorded, in vim's notation (like
+    /// `dw<Esc>`), which become the register's text when recording stops
+    pub recorded_keys: String,
+    /// The register last replayed with `@`, which `@@` replays again. This is
+    /// `:` after `@:`, which repeats the last command line.
+    pub last_replayed_register: Option<char>,
+    /// Set while a macro is being recorded and the command palette (opened with `:`)
+    /// or the search bar (opened with `/`) is open. The keys typed there aren't
+    /// recorded, the command or search they end up running is.
+    pub recording_prompt: bool,
+    /// Set while a macro, `.` or `:g` is being replayed
+    pub replayer: Option<Replayer>,
+
     pub registers: HashMap<String, String>,
+    /// How the text in each register was yanked or deleted
+    pub register_kinds: HashMap<String, RegisterKind>,
+    /// For registers yanked or deleted into with multiple cursors, the piece of
+    /// their text that came from each cursor (the pieces are joined by newlines)
+    pub register_pieces: HashMap<String, Vec<RegisterPiece>>,
+    /// The register chosen with `"{register}` for the next yank, delete or put
+    pub selected_register: Option<char>,
+
+    pub command_history: Vec<String>,
+    pub search_history: Vec<String>,
+    /// The search highlighted in the editor shown in every pane, until
+    /// `:nohlsearch`
+    pub hlsearch: Option<Arc<SearchQuery>>,
+    /// For each editor `hlsearch` was highlighted in, the query and the edit count of
+    /// its buffer then, so that it isn't searched again until one of them changes
+    pub hlsearch_synced: HashMap<EntityId, (Arc<SearchQuery>, usize)>,
+
+    /// The message shown in the status bar, like vim's message area
+    pub message: Option<String>,
+    /// Set once a keystroke has been handled after the message was shown,
+    /// so that the next one clears it.
+    pub message_seen: bool,
+    /// The messages shown so far, the last one last, which `:messages` lists
+    pub messages: Vec<String>,
+    /// The output of the last command that had some, which `g<` shows again
+    pub last_output: Option<String>,
+
+    /// Each pane's location list, keyed by the pane
+    pub location_lists: HashMap<EntityId, LocationList>,
+    /// The errors found by the last `:make`
+    pub quickfix: Option<QuickfixList>,
+    /// Where each jump to a tag (with `ctrl-]`) was made from, the last one last
+    pub tag_stack: Vec<TagEntry>,
+    /// The pane `ctrl-w }` shows definitions in, until `ctrl-w z` closes it
+    pub preview_pane: Option<WeakView<Pane>>,
+    /// Whether the panes show their numbers, while `ctrl-w` is pending
+    pub pane_numbers_shown: bool,
+    /// The buffers made read-only with `:set nomodifiable` or `:view`, which every
+    /// editor showing them is
+    pub read_only_buffers: HashSet<EntityId>,
+}
+
+/// The text yanked or deleted at one of several cursors, so that it can be put at
+/// the matching cursor.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RegisterPiece {
+    pub len: usize,
+    pub first_line_indent: u32,
+}
+
+/// Whether a register holds characters, whole lines, or a block from visual block
+/// mode, which decides how it's put.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RegisterKind {
+    #[default]
+    Charwise,
+    Linewise,
+    Blockwise,
 }
 
 #[derive(Debug)]
 pub enum ReplayableAction {
     Action(Box<dyn Action>),
+    /// A key from a register set from text rather than recorded, like `dw<Esc>`
+    Keystroke(Keystroke),
     Insertion {
         text: Arc<str>,
         utf16_range_to_replace: Option<Range<isize>>,
@@ -116,6 +269,7 @@ impl Clone for ReplayableAction {
     fn clone(&self) -> Self {
         match self {
             Self::Action(action) => Self::Action(action.boxed_clone()),
+            Self::Keystroke(keystroke) => Self::Keystroke(keystroke.clone()),
             Self::Insertion {
                 text,
                 utf16_range_to_replace,
@@ -132,6 +286,13 @@ pub struct SearchState {
     pub direction: Direction,
     pub count: usize,
     pub initial_query: String,
+    /// The operator that was pending when the search began (as in `d/foo`), which is
+    /// applied up to the match once the search is submitted.
+    pub prior_operator: Option<Operator>,
+    pub prior_register: Option<char>,
+    /// The selections from before the search, which the search bar moves as matches
+    /// are found.
+    pub prior_selections: Vec<Range<Anchor>>,
 }
 
 impl Default for SearchState {
@@ -140,11 +301,230 @@ impl Default for SearchState {
             direction: Direction::Next,
             count: 1,
             initial_query: "".to_string(),
+            prior_operator: None,
+            prior_register: None,
+            prior_selections: Vec::new(),
+        }
+    }
+}
+
+/// The number of entries kept in the command and search histories.
+const HISTORY_LIMIT: usize = 50;
+
+/// The number of messages `:messages` keeps, like vim's.
+const MESSAGE_HISTORY_LIMIT: usize = 200;
+
+fn push_history(history: &mut Vec<String>, entry: String) {
+    if entry.is_empty() {
+        return;
+    }
+    history.retain(|existing| existing != &entry);
+    history.push(entry);
+    if history.len() > HISTORY_LIMIT {
+        history.remove(0);
+    }
+}
+
+impl WorkspaceState {
+    pub fn record_command(&mut self, command: String) {
+        push_history(&mut self.command_history, command)
+    }
+
+    pub fn record_search(&mut self, query: String) {
+        push_history(&mut self.search_history, query)
+    }
+
+    pub fn record_message(&mut self, message: String) {
+        self.messages.push(message);
+        if self.messages.len() > MESSAGE_HISTORY_LIMIT {
+            self.messages.remove(0);
+        }
+    }
+
+    /// Stores yanked or deleted text like vim: yanks go in `"0`, deletes of a line
+    /// or more shift `"1` down into `"2`–`"9`, and smaller deletes go in `"-`.
+    /// Whichever register is written, `""` holds the text too, or the whole of the
+    /// register that was appended to.
+    pub fn write_register(
+        &mut self,
+        register: Option<char>,
+        text: String,
+        kind: RegisterKind,
+        is_yank: bool,
+        pieces: Vec<RegisterPiece>,
+    ) {
+        let big_delete = !is_yank && (kind == RegisterKind::Linewise || text.contains('\n'));
+        let mut written = vec!["\"".to_string()];
+        let mut appended = None;
+        match register {
+            Some('_') => return,
+            Some(register) if register.is_ascii_uppercase() => {
+                let name = register.to_ascii_lowercase().to_string();
+                self.append_register(name.clone(), &text, kind);
+                appended = self
+                    .registers
+                    .get(&name)
+                    .cloned()
+                    .zip(self.register_kinds.get(&name).copied());
+            }
+            Some(register) if register.is_ascii_alphanumeric() || register == '-' => {
+                self.store_register(register.to_string(), text.clone(), kind);
+                written.push(register.to_string());
+            }
+            _ if is_yank => {
+                self.store_register("0".to_string(), text.clone(), kind);
+                written.push("0".to_string());
+            }
+            _ if !big_delete => {
+                self.store_register("-".to_string(), text.clone(), kind);
+                written.push("-".to_string());
+            }
+            _ => {}
+        }
+        // even when another register was given, big deletes are kept in "1
+        if big_delete && !register.is_some_and(|register| register.is_ascii_digit()) {
+            for register in (1..9).rev() {
+                if let Some(text) = self.registers.remove(&register.to_string()) {
+                    let kind = self
+                        .register_kinds
+                        .remove(&register.to_string())
+                        .unwrap_or_default();
+                    let pieces = self.register_pieces.remove(&register.to_string());
+                    self.store_register((register + 1).to_string(), text, kind);
+                    if let Some(pieces) = pieces {
+                        self.register_pieces
+                            .insert((register + 1).to_string(), pieces);
+                    }
+                }
+            }
+            self.store_register("1".to_string(), text.clone(), kind);
+            written.push("1".to_string());
         }
+        let (text, kind) = appended.unwrap_or((text, kind));
+        self.store_register("\"".to_string(), text, kind);
+        if pieces.len() > 1 {
+            for name in written {
+                self.register_pieces.insert(name, pieces.clone());
+            }
+        }
+    }
+
+    /// Sets a register to text of the given kind, like vim's `setreg()`: the text
+    /// of a linewise register always ends in a newline, and an uppercase name
+    /// appends to the register.
+    pub fn set_register(&mut self, register: char, mut text: String, kind: RegisterKind) {
+        match kind {
+            RegisterKind::Linewise if !text.ends_with('\n') => text.push('\n'),
+            RegisterKind::Blockwise if text.ends_with('\n') => {
+                text.pop();
+            }
+            _ => {}
+        }
+        if register == '_' {
+            return;
+        } else if register.is_ascii_uppercase() {
+            self.append_register(register.to_ascii_lowercase().to_string(), &text, kind);
+        } else {
+            self.store_register(register.to_string(), text, kind);
+        }
+    }
+
+    /// Stores a recorded macro in the register, with the keys it was recorded from
+    /// as the register's text.
+    pub fn store_macro(&mut self, register: char, actions: Vec<ReplayableAction>, keys: String) {
+        self.store_register(register.to_string(), keys, RegisterKind::Charwise);
+        self.recordings.insert(register, actions);
+    }
+
+    fn store_register(&mut self, name: String, text: String, kind: RegisterKind) {
+        self.forget_recording(&name);
+        self.register_pieces.remove(&name);
+        self.registers.insert(name.clone(), text);
+        self.register_kinds.insert(name, kind);
+    }
+
+    /// Appends to a register, as `"A` does. If either part is linewise, so is the
+    /// result, with the parts on separate lines.
+    fn append_register(&mut self, name: String, text: &str, kind: RegisterKind) {
+        self.forget_recording(&name);
+        self.register_pieces.remove(&name);
+        let existing_kind = self.register_kinds.get(&name).copied().unwrap_or(kind);
+        let Some(existing) = self.registers.get_mut(&name) else {
+            return self.store_register(name, text.to_string(), kind);
+        };
+        let kind = match (existing_kind, kind) {
+            (RegisterKind::Linewise, _) | (_, RegisterKind::Linewise) => {
+                if !existing.is_empty() && !existing.ends_with('\n') {
+                    existing.push('\n');
+                }
+                RegisterKind::Linewise
+            }
+            (RegisterKind::Blockwise, RegisterKind::Blockwise) => {
+                existing.push('\n');
+                RegisterKind::Blockwise
+            }
+            _ => existing_kind,
+        };
+        existing.push_str(text);
+        if kind == RegisterKind::Linewise && !existing.ends_with('\n') {
+            existing.push('\n');
+        }
+        self.register_kinds.insert(name, kind);
+    }
+
+    /// Text written to a register replaces the macro recorded in it, so that
+    /// replaying the register types the text instead.
+    fn forget_recording(&mut self, name: &str) {
+        if let Some(register) = name.chars().next() {
+            self.recordings.remove(&register);
+        }
+    }
+
+    pub fn read_register(&self, register: char) -> Option<&String> {
+        self.registers
+            .get(&register.to_ascii_lowercase().to_string())
+    }
+
+    /// Whether the system clipboard holds text copied outside of vim since vim last
+    /// saw it, so that `p` puts it rather than the unnamed register. Vim looks at the
+    /// clipboard after each yank or delete (`note_system_clipboard`), so the text
+    /// the clipboard holds at put time is newer when it's changed since.
+    pub fn system_clipboard_is_newer(&self, text: &str) -> bool {
+        self.registers
+            .get(".system.")
+            .map_or(true, |last_state| last_state != text)
+    }
+
+    /// Records what the system clipboard holds, as it was last seen by vim.
+    pub fn note_system_clipboard(&mut self, text: &str) {
+        self.registers
+            .insert(".system.".to_string(), text.to_string());
+    }
+
+    /// The pieces of the register's text that came from each of several cursors,
+    /// if it was yanked or deleted into with more than one.
+    pub fn register_pieces(&self, register: char) -> Option<&Vec<RegisterPiece>> {
+        self.register_pieces
+            .get(&register.to_ascii_lowercase().to_string())
+    }
+
+    pub fn register_kind(&self, register: char) -> RegisterKind {
+        self.register_kinds
+            .get(&register.to_ascii_lowercase().to_string())
+            .copied()
+            .unwrap_or_default()
     }
 }
 
 impl EditorState {
+    /// Forgets the counts and operators typed so far.
+    pub fn clear_pending(&mut self) {
+        self.pre_count = None;
+        self.post_count = None;
+        self.register_count = None;
+        self.operator_stack.clear();
+    }
+
     pub fn cursor_shape(&self) -> CursorShape {
         match self.mode {
             Mode::Normal => {
@@ -154,16 +534,23 @@ impl EditorState {
                     CursorShape::Underscore
                 }
             }
-            Mode::Visual | Mode::VisualLine | Mode::VisualBlock => CursorShape::Block,
+            Mode::Visual | Mode::VisualLine | Mode::VisualBlock | Mode::Select => {
+                CursorShape::Block
+            }
             Mode::Insert => CursorShape::Bar,
         }
     }
 
     pub fn vim_controlled(&self) -> bool {
-        !matches!(self.mode, Mode::Insert)
+        !matches!(self.mode, Mode::Insert | Mode::Select)
             || matches!(
                 self.operator_stack.last(),
-                Some(Operator::FindForward { .. }) | Some(Operator::FindBackward { .. })
+                Some(
+                    Operator::FindForward { .. }
+                        | Operator::FindBackward { .. }
+                        | Operator::Sneak { .. }
+                        | Operator::SneakBackward { .. }
+                )
             )
     }
 
@@ -173,7 +560,9 @@ impl EditorState {
 
     pub fn clip_at_line_ends(&self) -> bool {
         match self.mode {
-            Mode::Insert | Mode::Visual | Mode::VisualLine | Mode::VisualBlock => false,
+            Mode::Insert | Mode::Visual | Mode::VisualLine | Mode::VisualBlock | Mode::Select => {
+                false
+            }
             Mode::Normal => true,
         }
     }
@@ -190,6 +579,7 @@ impl EditorState {
                 Mode::Normal => "normal",
                 Mode::Visual | Mode::VisualLine | Mode::VisualBlock => "visual",
                 Mode::Insert => "insert",
+                Mode::Select => "select",
             },
         );
 
@@ -197,6 +587,10 @@ impl EditorState {
             context.add("VimControl");
         }
 
+        if self.command_line_window.is_some() {
+            context.add("VimCommandLineWindow");
+        }
+
         if self.active_operator().is_none() && self.pre_count.is_some()
             || self.active_operator().is_some() && self.post_count.is_some()
         {
@@ -233,15 +627,66 @@ impl Operator {
             Operator::FindForward { before: true } => "t",
             Operator::FindBackward { after: false } => "F",
             Operator::FindBackward { after: true } => "T",
+            Operator::Sneak { .. } => "s",
+            Operator::SneakBackward { .. } => "S",
+            Operator::RecordRegister => "q",
+            Operator::ReplayRegister => "@",
+            Operator::Register => "\"",
+            Operator::Fold => "zf",
+            Operator::Rewrap => "gq",
+            Operator::Filter => "filter",
+            Operator::AutoIndent => "equal",
+            Operator::Uppercase => "gU",
+            Operator::Lowercase => "gu",
+            Operator::OppositeCase => "gtilde",
+            Operator::Mark => "m",
+            Operator::Jump { line: true } => "'",
+            Operator::Jump { line: false } => "`",
+            Operator::Custom => "custom",
+        }
+    }
+
+    /// The keys that started the operator, as shown by showcmd.
+    pub fn keys(&self) -> &'static str {
+        match self {
+            Operator::OppositeCase => "g~",
+            Operator::AutoIndent => "=",
+            Operator::Filter => "!",
+            Operator::Custom => "g@",
+            operator => operator.id(),
         }
     }
 
+    /// Whether the operator changes the text it's applied to, so that it can't be used
+    /// in a read-only editor. Custom operators run commands that may not.
+    pub fn changes_text(&self) -> bool {
+        matches!(
+            self,
+            Operator::Change
+                | Operator::Delete
+                | Operator::Replace
+                | Operator::Rewrap
+                | Operator::Filter
+                | Operator::AutoIndent
+                | Operator::Uppercase
+                | Operator::Lowercase
+                | Operator::OppositeCase
+        )
+    }
+
     pub fn context_flags(&self) -> &'static [&'static str] {
         match self {
             Operator::Object { .. } => &["VimObject"],
-            Operator::FindForward { .. } | Operator::FindBackward { .. } | Operator::Replace => {
-                &["VimWaiting"]
-            }
+            Operator::FindForward { .. }
+            | Operator::FindBackward { .. }
+            | Operator::Sneak { .. }
+            | Operator::SneakBackward { .. }
+            | Operator::Replace
+            | Operator::RecordRegister
+            | Operator::ReplayRegister
+            | Operator::Register
+            | Operator::Mark
+            | Operator::Jump { .. } => &["VimWaiting"],
             _ => &[],
         }
     }
@@ -0,0 +1,322 @@
+use std::sync::Arc;
+
+use editor::{scroll::Autoscroll, Anchor, Editor, ToOffset};
+use fuzzy::StringMatch;
+use gpui::{
+    actions, AppContext, DismissEvent, EventEmitter, FocusHandle, FocusableView, Model,
+    ParentElement, Render, Styled, Task, View, ViewContext, VisualContext, WeakView,
+};
+use language::{Buffer, CharKind, Outline};
+use picker::{Picker, PickerDelegate};
+use ui::{prelude::*, HighlightedLabel, ListItem, ListItemSpacing};
+use workspace::{ModalView, SplitDirection, Workspace};
+
+use crate::{
+    list_picker::{self, ModalDelegate},
+    Vim,
+};
+
+actions!(
+    vim,
+    [GoToSymbol, OpenSymbolInSplit, OpenSymbolInVerticalSplit]
+);
+
+/// `gs` lists the symbols of the buffer, filtered by the word under the cursor. In
+/// the list, `enter` jumps to a symbol, `ctrl-x` opens it in a split below and
+/// `ctrl-v` in a split to the right. Where the jump was made from is pushed onto the
+/// jump list, so `ctrl-o` comes back to it however close the symbol is.
+pub(crate) fn register(workspace: &mut Workspace, _: &mut ViewContext<Workspace>) {
+    workspace.register_action(|workspace: &mut Workspace, _: &GoToSymbol, cx| {
+        Vim::update(cx, |vim, cx| vim.clear_operator(cx));
+        let Some(editor) = workspace.active_item_as::<Editor>(cx) else {
+            return;
+        };
+        let (outline, query) = {
+            let editor = editor.read(cx);
+            let snapshot = editor.buffer().read(cx).snapshot(cx);
+            let head = editor.selections.newest::<usize>(cx).head();
+            let query = match snapshot.surrounding_word(head) {
+                (word, Some(CharKind::Word)) => snapshot.text_for_range(word).collect(),
+                _ => String::new(),
+            };
+            (snapshot.outline(None), query)
+        };
+        let Some(outline) = outline.filter(|outline| !outline.items.is_empty()) else {
+            Vim::update(cx, |vim, _| vim.show_message("No symbols in the buffer"));
+            return;
+        };
+        let workspace_handle = cx.view().downgrade();
+        workspace.toggle_modal(cx, |cx| {
+            SymbolList::new(workspace_handle, editor, outline, query, cx)
+        });
+    });
+}
+
+pub struct SymbolList {
+    picker: View<Picker<SymbolListDelegate>>,
+}
+
+impl SymbolList {
+    fn new(
+        workspace: WeakView<Workspace>,
+        editor: View<Editor>,
+        outline: Outline<Anchor>,
+        query: String,
+        cx: &mut ViewContext<Self>,
+    ) -> Self {
+        let buffer = editor.read(cx).buffer().read(cx).as_singleton();
+        let delegate = SymbolListDelegate {
+            symbol_list: cx.view().downgrade(),
+            workspace,
+            editor,
+            buffer,
+            outline: Arc::new(outline),
+            matches: Vec::new(),
+            selected_index: 0,
+        };
+        let picker = cx.new_view(|cx| {
+            let picker = Picker::uniform_list(delegate, cx);
+            picker.set_query(query, cx);
+            picker
+        });
+        Self { picker }
+    }
+
+    fn open(&mut self, split: Option<SplitDirection>, cx: &mut ViewContext<Self>) {
+        self.picker
+            .update(cx, |picker, cx| picker.delegate.open(split, cx))
+    }
+}
+
+impl Render for SymbolList {
+    fn render(&mut self, cx: &mut ViewContext<Self>) -> impl IntoElement {
+        v_flex()
+            .key_context("SymbolList")
+            .w(rems(34.))
+            .on_action(cx.listener(|this, _: &OpenSymbolInSplit, cx| {
+                this.open(Some(SplitDirection::Down), cx)
+            }))
+            .on_action(cx.listener(|this, _: &OpenSymbolInVerticalSplit, cx| {
+                this.open(Some(SplitDirection::Right), cx)
+            }))
+            .child(self.picker.clone())
+    }
+}
+
+impl FocusableView for SymbolList {
+    fn focus_handle(&self, cx: &AppContext) -> FocusHandle {
+        self.picker.focus_handle(cx)
+    }
+}
+
+impl EventEmitter<DismissEvent> for SymbolList {}
+impl ModalView for SymbolList {}
+
+pub struct SymbolListDelegate {
+    symbol_list: WeakView<SymbolList>,
+    workspace: WeakView<Workspace>,
+    editor: View<Editor>,
+    /// The buffer the editor shows, which a split opens. A multibuffer's symbols
+    /// can only be jumped to in the editor itself.
+    buffer: Option<Model<Buffer>>,
+    outline: Arc<Outline<Anchor>>,
+    matches: Vec<StringMatch>,
+    selected_index: usize,
+}
+
+impl SymbolListDelegate {
+    /// Jumps to the selected symbol in the editor, or opens it in a new split of the
+    /// active pane.
+    fn open(&mut self, split: Option<SplitDirection>, cx: &mut ViewContext<Picker<Self>>) {
+        if let Some(mat) = self.matches.get(self.selected_index) {
+            let position = self.outline.items[mat.candidate_id].range.start;
+            let offset = self.editor.update(cx, |editor, cx| {
+                let snapshot = editor.buffer().read(cx).snapshot(cx);
+                let cursor = editor.selections.newest_anchor().head();
+                editor.push_to_nav_history(cursor, None, cx);
+                position.to_offset(&snapshot)
+            });
+            let editor = match (split, self.buffer.clone()) {
+                (Some(direction), Some(buffer)) => self
+                    .workspace
+                    .update(cx, |workspace, cx| {
+                        let pane =
+                            workspace.split_pane(workspace.active_pane().clone(), direction, cx);
+                        workspace.open_project_item::<Editor>(pane, buffer, cx)
+                    })
+                    .ok(),
+                _ => Some(self.editor.clone()),
+            };
+            if let Some(editor) = editor {
+                editor.update(cx, |editor, cx| {
+                    editor.change_selections(Some(Autoscroll::center()), cx, |s| {
+                        s.select_ranges([offset..offset])
+                    });
+                    editor.focus(cx);
+                });
+            }
+        }
+        self.dismissed(cx);
+    }
+}
+
+impl ModalDelegate for SymbolListDelegate {
+    type Modal = SymbolList;
+
+    fn modal(&self) -> &WeakView<SymbolList> {
+        &self.symbol_list
+    }
+}
+
+impl PickerDelegate for SymbolListDelegate {
+    type ListItem = ListItem;
+
+    fn placeholder_text(&self, _cx: &mut WindowContext) -> Arc<str> {
+        "Go to symbol...".into()
+    }
+
+    fn match_count(&self) -> usize {
+        self.matches.len()
+    }
+
+    fn selected_index(&self) -> usize {
+        self.selected_index
+    }
+
+    fn set_selected_index(&mut self, ix: usize, _: &mut ViewContext<Picker<Self>>) {
+        self.selected_index = ix;
+    }
+
+    fn update_matches(&mut self, query: String, cx: &mut ViewContext<Picker<Self>>) -> Task<()> {
+        let background = cx.background_executor().clone();
+        let outline = self.outline.clone();
+        cx.spawn(|this, mut cx| async move {
+            let matches = if query.is_empty() {
+                (0..outline.items.len())
+                    .map(|candidate_id| StringMatch {
+                        candidate_id,
+                        string: Default::default(),
+                        positions: Vec::new(),
+                        score: 0.0,
+                    })
+                    .collect()
+            } else {
+                outline.search(&query, background).await
+            };
+            this.update(&mut cx, |this, cx| {
+                let delegate = &mut this.delegate;
+                // the symbols that contain a match are listed above it, so the best
+                // match is selected rather than the first
+                delegate.selected_index = matches
+                    .iter()
+                    .enumerate()
+                    .min_by(|(_, a), (_, b)| b.score.total_cmp(&a.score))
+                    .map_or(0, |(ix, _)| ix);
+                delegate.matches = matches;
+                cx.notify();
+            })
+            .ok();
+        })
+    }
+
+    fn confirm(&mut self, _: bool, cx: &mut ViewContext<Picker<Self>>) {
+        self.open(None, cx);
+    }
+
+    fn dismissed(&mut self, cx: &mut ViewContext<Picker<Self>>) {
+        list_picker::dismiss(self, cx)
+    }
+
+    fn render_match(
+        &self,
+        ix: usize,
+        selected: bool,
+        _: &mut ViewContext<Picker<Self>>,
+    ) -> Option<Self::ListItem> {
+        let mat = &self.matches[ix];
+        let item = &self.outline.items[mat.candidate_id];
+        Some(
+            ListItem::new(ix)
+                .inset(true)
+                .spacing(ListItemSpacing::Sparse)
+                .selected(selected)
+                .child(
+                    div()
+                        .pl(rems(item.depth as f32))
+                        .child(HighlightedLabel::new(
+                            item.text.clone(),
+                            mat.positions.clone(),
+                        )),
+                ),
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use editor::Editor;
+    use indoc::indoc;
+    use language::Point;
+
+    use super::SymbolList;
+    use crate::{state::Mode, test::VimTestContext};
+
+    #[gpui::test]
+    async fn test_go_to_symbol(cx: &mut gpui::TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+
+        cx.set_state(
+            indoc! {"
+                fn one() {}
+                fn two() {}
+                fn main() {
+                    ˇtwo();
+                }"},
+            Mode::Normal,
+        );
+        cx.simulate_keystrokes(["g", "s"]);
+        cx.run_until_parked();
+        let query = cx.workspace(|workspace, cx| {
+            let list = workspace.active_modal::<SymbolList>(cx).unwrap();
+            let picker = list.read(cx).picker.clone();
+            picker.read(cx).query(cx)
+        });
+        assert_eq!(query, "two");
+        cx.simulate_keystrokes(["enter"]);
+        cx.assert_state(
+            indoc! {"
+                fn one() {}
+                ˇfn two() {}
+                fn main() {
+                    two();
+                }"},
+            Mode::Normal,
+        );
+
+        // the jump is on the jump list, although it's a short one
+        cx.simulate_keystrokes(["ctrl-o"]);
+        cx.run_until_parked();
+        cx.assert_state(
+            indoc! {"
+                fn one() {}
+                fn two() {}
+                fn main() {
+                    ˇtwo();
+                }"},
+            Mode::Normal,
+        );
+
+        // ctrl-v opens the symbol in a split to the right
+        cx.simulate_keystrokes(["g", "g", "w", "g", "s"]);
+        cx.run_until_parked();
+        cx.simulate_keystrokes(["ctrl-v"]);
+        cx.run_until_parked();
+        cx.workspace(|workspace, cx| {
+            assert_eq!(workspace.panes().len(), 2);
+            assert!(workspace.active_modal::<SymbolList>(cx).is_none());
+            let editor = workspace.active_item_as::<Editor>(cx).unwrap();
+            let cursor = editor.read(cx).selections.newest::<Point>(cx).head();
+            assert_eq!(cursor, Point::new(0, 0));
+        });
+    }
+}
@@ -0,0 +1,877 @@
+use std::sync::Arc;
+
+use editor::{scroll::Autoscroll, Bias, Editor};
+use fuzzy::{StringMatch, StringMatchCandidate};
+use gpui::{
+    actions, impl_actions, AppContext, DismissEvent, EventEmitter, FocusHandle, FocusableView,
+    Model, ParentElement, Render, Styled, Task, View, ViewContext, VisualContext, WeakModel,
+    WeakView, WindowContext,
+};
+use language::{Anchor, Buffer, Location, Point, ToOffset, ToPoint};
+use picker::{Picker, PickerDelegate};
+use project::{Item as _, ProjectPath, Symbol};
+use serde_derive::Deserialize;
+use ui::{prelude::*, HighlightedLabel, ListItem, ListItemSpacing};
+use workspace::{ModalView, Workspace};
+
+use crate::{
+    list_picker::{self, ModalDelegate, StringListDelegate},
+    quickfix::{self, QuickfixEntry, QuickfixList},
+    Vim,
+};
+
+actions!(vim, [GoToTag, SelectTag, PopTag]);
+
+/// `:ta[g] {name}` (or `:sym[bol] {name}`) jumps to the symbol of the project called
+/// `name`, as the language servers find it, pushing the tag stack like `ctrl-]`.
+/// `:ts[elect] [name]` lists the symbols called `name` to pick from even when there's
+/// only one, and without a name, those of the last tag jumped to.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub(crate) struct Tag {
+    name: String,
+    #[serde(default)]
+    select: bool,
+}
+
+/// `:ws[ymbol] {query}` fills the quickfix list with the symbols of the project that
+/// match the query, as the language servers find them, and jumps to the first, so
+/// that `:cnext` (or `:cdo`, from a macro) goes through the rest. `:ws! {query}` lists
+/// them to pick from instead, like an ambiguous `:tag`.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct WorkspaceSymbols {
+    query: String,
+    #[serde(default)]
+    pick: bool,
+}
+
+impl_actions!(vim, [Tag, WorkspaceSymbols]);
+
+/// Like vim's, the tag stack only keeps the last 20 jumps.
+const TAG_STACK_SIZE: usize = 20;
+
+/// Where a jump to a tag (with `ctrl-]`) was made from, which `ctrl-t` goes back to.
+/// The tag stack doesn't keep the buffer open: once it's closed, `ctrl-t` opens the
+/// file again at the same point.
+#[derive(Clone)]
+pub struct TagEntry {
+    /// The tag that was jumped to
+    pub name: String,
+    pub buffer: WeakModel<Buffer>,
+    pub path: Option<ProjectPath>,
+    pub position: Anchor,
+    pub point: Point,
+}
+
+pub(crate) fn register(workspace: &mut Workspace, _: &mut ViewContext<Workspace>) {
+    workspace.register_action(go_to_tag);
+    workspace.register_action(select_tag);
+    workspace.register_action(pop_tag);
+    workspace.register_action(tag);
+    workspace.register_action(workspace_symbols);
+}
+
+/// `ctrl-]` jumps to the definition of the symbol under the cursor, as the language
+/// server finds it, and pushes where it was onto the tag stack. Unlike the jump list
+/// (`ctrl-o`), the tag stack only holds these jumps, so that `ctrl-t` goes back along
+/// them, last first, however far the cursor has moved since.
+fn go_to_tag(workspace: &mut Workspace, _: &GoToTag, cx: &mut ViewContext<Workspace>) {
+    find_definitions(workspace, false, cx)
+}
+
+/// `g]` lists the definitions of the symbol under the cursor to pick one from, where
+/// `ctrl-]` jumps to the first.
+fn select_tag(workspace: &mut Workspace, _: &SelectTag, cx: &mut ViewContext<Workspace>) {
+    find_definitions(workspace, true, cx)
+}
+
+fn find_definitions(workspace: &mut Workspace, select: bool, cx: &mut ViewContext<Workspace>) {
+    Vim::update(cx, |vim, cx| vim.clear_operator(cx));
+    let Some(editor) = workspace.active_item_as::<Editor>(cx) else {
+        return;
+    };
+    let Some(from) = tag_origin(&editor, cx) else {
+        return;
+    };
+    let Some(buffer) = from.buffer.upgrade() else {
+        return;
+    };
+    let definitions = workspace.project().update(cx, |project, cx| {
+        project.definition(&buffer, from.position, cx)
+    });
+    cx.spawn(|workspace, mut cx| async move {
+        let definitions = definitions.await?;
+        workspace.update(&mut cx, |workspace, cx| {
+            if definitions.is_empty() {
+                Vim::update(cx, |vim, _| {
+                    vim.show_message(format!("E426: Tag not found: {}", from.name))
+                });
+                return;
+            }
+            let count = definitions.len();
+            let mut targets = definitions
+                .into_iter()
+                .map(|definition| TagTarget::Definition(definition.target));
+            if select {
+                let targets = targets.collect();
+                let workspace_handle = cx.view().downgrade();
+                workspace.toggle_modal(cx, |cx| TagList::new(workspace_handle, from, targets, cx));
+                return;
+            }
+            let Some(TagTarget::Definition(definition)) = targets.next() else {
+                return;
+            };
+            push_tag(from, cx);
+            jump_to_position(workspace, definition.buffer, definition.range.start, cx);
+            if count > 1 {
+                Vim::update(cx, |vim, _| vim.show_message(format!("tag 1 of {count}")));
+            }
+        })
+    })
+    .detach_and_log_err(cx);
+}
+
+/// `ctrl-t` (and `:pop`) go back to where the last jump to a tag was made from, or
+/// with a count, to where the count-th last one was, taking them off the tag stack.
+fn pop_tag(workspace: &mut Workspace, _: &PopTag, cx: &mut ViewContext<Workspace>) {
+    let entry = Vim::update(cx, |vim, cx| {
+        let count = vim.take_count(cx).unwrap_or(1);
+        vim.clear_operator(cx);
+        let stack = &mut vim.workspace_state.tag_stack;
+        if stack.is_empty() {
+            vim.show_message("E73: Tag stack empty");
+            return None;
+        }
+        let popped = stack.split_off(stack.len().saturating_sub(count));
+        popped.into_iter().next()
+    });
+    if let Some(entry) = entry {
+        jump_back(workspace, entry, cx);
+    }
+}
+
+/// Goes back to where a jump to a tag was made from, opening its file again if its
+/// buffer has been closed since.
+fn jump_back(workspace: &mut Workspace, entry: TagEntry, cx: &mut ViewContext<Workspace>) {
+    if let Some(buffer) = entry.buffer.upgrade() {
+        jump_to_position(workspace, buffer, entry.position, cx);
+        return;
+    }
+    let Some(path) = entry.path else {
+        return;
+    };
+    let open = workspace.open_path(path, None, true, cx);
+    cx.spawn(|_, mut cx| async move {
+        let item = open.await?;
+        cx.update(|cx| {
+            if let Some(editor) = item.downcast::<Editor>() {
+                editor.update(cx, |editor, cx| {
+                    let snapshot = editor.buffer().read(cx).snapshot(cx);
+                    let point = snapshot.clip_point(entry.point, Bias::Left);
+                    editor.change_selections(Some(Autoscroll::center()), cx, |s| {
+                        s.select_ranges([point..point])
+                    });
+                });
+            }
+        })
+    })
+    .detach_and_log_err(cx);
+}
+
+/// Looks the name up in the symbols of the project. The symbol called just that is
+/// jumped to, and when there's more than one (or only symbols the name is a part
+/// of, or it's `:tselect`), they're listed to pick from.
+fn tag(workspace: &mut Workspace, action: &Tag, cx: &mut ViewContext<Workspace>) {
+    let name = Vim::update(cx, |vim, cx| {
+        vim.clear_operator(cx);
+        if !action.name.is_empty() {
+            return Some(action.name.clone());
+        }
+        let last = vim
+            .workspace_state
+            .tag_stack
+            .last()
+            .map(|entry| entry.name.clone());
+        if last.is_none() {
+            vim.show_message("E73: Tag stack empty");
+        }
+        last
+    });
+    let Some(name) = name else {
+        return;
+    };
+    let Some(editor) = workspace.active_item_as::<Editor>(cx) else {
+        return;
+    };
+    let Some(mut from) = tag_origin(&editor, cx) else {
+        return;
+    };
+    from.name = name;
+    let select = action.select;
+    let symbols = workspace
+        .project()
+        .update(cx, |project, cx| project.symbols(&from.name, cx));
+    cx.spawn(|workspace, mut cx| async move {
+        let mut symbols = symbols.await?;
+        workspace.update(&mut cx, |workspace, cx| {
+            if symbols.is_empty() {
+                Vim::update(cx, |vim, _| {
+                    vim.show_message(format!("E426: Tag not found: {}", from.name))
+                });
+                return;
+            }
+            if symbols.iter().any(|symbol| symbol.name == from.name) {
+                symbols.retain(|symbol| symbol.name == from.name);
+            }
+            if symbols.len() == 1 && !select {
+                jump_to_symbol(workspace, from, symbols.remove(0), cx);
+                return;
+            }
+            let targets = symbols.into_iter().map(TagTarget::Symbol).collect();
+            let workspace_handle = cx.view().downgrade();
+            workspace.toggle_modal(cx, |cx| TagList::new(workspace_handle, from, targets, cx));
+        })
+    })
+    .detach_and_log_err(cx);
+}
+
+fn workspace_symbols(
+    workspace: &mut Workspace,
+    action: &WorkspaceSymbols,
+    cx: &mut ViewContext<Workspace>,
+) {
+    Vim::update(cx, |vim, cx| vim.clear_operator(cx));
+    let from = workspace
+        .active_item_as::<Editor>(cx)
+        .and_then(|editor| tag_origin(&editor, cx));
+    let query = action.query.clone();
+    let pick = action.pick;
+    let symbols = workspace
+        .project()
+        .update(cx, |project, cx| project.symbols(&query, cx));
+    cx.spawn(|workspace, mut cx| async move {
+        let symbols = symbols.await?;
+        workspace.update(&mut cx, |workspace, cx| {
+            let not_found = format!("E480: No match: {query}");
+            if pick {
+                if symbols.is_empty() {
+                    Vim::update(cx, |vim, _| vim.show_message(not_found));
+                    return;
+                }
+                // the picker pushes the tag stack, so it needs somewhere to come back to
+                let Some(mut from) = from else {
+                    return;
+                };
+                from.name = query;
+                let targets = symbols.into_iter().map(TagTarget::Symbol).collect();
+                let workspace_handle = cx.view().downgrade();
+                workspace.toggle_modal(cx, |cx| TagList::new(workspace_handle, from, targets, cx));
+                return;
+            }
+
+            let project = workspace.project().read(cx);
+            let mut entries = symbols
+                .iter()
+                .filter_map(|symbol| {
+                    Some(QuickfixEntry {
+                        path: project.absolute_path(&symbol.path, cx)?,
+                        line: symbol.range.start.0.row + 1,
+                        column: symbol.range.start.0.column + 1,
+                        text: symbol.label.text.clone(),
+                    })
+                })
+                .collect::<Vec<_>>();
+            entries.sort_by(|a, b| (&a.path, a.line, a.column).cmp(&(&b.path, b.line, b.column)));
+            if entries.is_empty() {
+                Vim::update(cx, |vim, _| vim.show_message(not_found));
+                return;
+            }
+            let list = QuickfixList {
+                entries,
+                current: 0,
+            };
+            quickfix::jump_to(workspace, &list, cx);
+            Vim::update(cx, |vim, _| vim.workspace_state.quickfix = Some(list));
+        })
+    })
+    .detach_and_log_err(cx);
+}
+
+/// Opens the file of a symbol and jumps to it, pushing where the jump was made from
+/// onto the tag stack.
+fn jump_to_symbol(
+    workspace: &mut Workspace,
+    from: TagEntry,
+    symbol: Symbol,
+    cx: &mut ViewContext<Workspace>,
+) {
+    let buffer = workspace.project().update(cx, |project, cx| {
+        project.open_buffer_for_symbol(&symbol, cx)
+    });
+    cx.spawn(|workspace, mut cx| async move {
+        let buffer = buffer.await?;
+        workspace.update(&mut cx, |workspace, cx| {
+            let position = {
+                let snapshot = buffer.read(cx);
+                snapshot.anchor_before(snapshot.clip_point_utf16(symbol.range.start, Bias::Left))
+            };
+            push_tag(from, cx);
+            jump_to_position(workspace, buffer, position, cx);
+        })
+    })
+    .detach_and_log_err(cx);
+}
+
+/// Where the cursor is, and the word under it, for the tag stack.
+pub(crate) fn tag_origin(editor: &View<Editor>, cx: &WindowContext) -> Option<TagEntry> {
+    let editor = editor.read(cx);
+    let multibuffer = editor.buffer().read(cx);
+    let snapshot = multibuffer.snapshot(cx);
+    let head = editor.selections.newest::<usize>(cx).head();
+    let (word, _) = snapshot.surrounding_word(head);
+    let name = snapshot.text_for_range(word).collect::<String>();
+    let (buffer, position) = multibuffer.text_anchor_for_position(head, cx)?;
+    let buffer_snapshot = buffer.read(cx);
+    Some(TagEntry {
+        name,
+        path: buffer_snapshot.project_path(cx),
+        point: position.to_point(buffer_snapshot),
+        buffer: buffer.downgrade(),
+        position,
+    })
+}
+
+/// Pushes where a jump to a tag was made from onto the tag stack.
+pub(crate) fn push_tag(entry: TagEntry, cx: &mut WindowContext) {
+    Vim::update(cx, |vim, _| {
+        let stack = &mut vim.workspace_state.tag_stack;
+        stack.push(entry);
+        if stack.len() > TAG_STACK_SIZE {
+            stack.remove(0);
+        }
+    });
+}
+
+/// Opens the buffer in the active pane (or goes to its tab there), with the cursor
+/// at the position.
+pub(crate) fn jump_to_position(
+    workspace: &mut Workspace,
+    buffer: Model<Buffer>,
+    position: Anchor,
+    cx: &mut ViewContext<Workspace>,
+) {
+    let offset = position.to_offset(buffer.read(cx));
+    let pane = workspace.active_pane().clone();
+    let editor = workspace.open_project_item::<Editor>(pane, buffer, cx);
+    editor.update(cx, |editor, cx| {
+        editor.change_selections(Some(Autoscroll::center()), cx, |s| {
+            s.select_ranges([offset..offset])
+        });
+        editor.focus(cx);
+    });
+}
+
+pub(crate) fn parse_tag(query: &str) -> Option<Tag> {
+    let (name, tag) = query.split_once(' ')?;
+    if !matches!(name, "ta" | "tag" | "sym" | "symb" | "symbo" | "symbol") || tag.trim().is_empty()
+    {
+        return None;
+    }
+    Some(Tag {
+        name: tag.trim().to_string(),
+        select: false,
+    })
+}
+
+/// `:ts[elect] [name]`
+pub(crate) fn parse_tag_select(query: &str) -> Option<Tag> {
+    let (name, tag) = query.split_once(' ').unwrap_or((query, ""));
+    if !matches!(name, "ts" | "tse" | "tsel" | "tsele" | "tselec" | "tselect") {
+        return None;
+    }
+    Some(Tag {
+        name: tag.trim().to_string(),
+        select: true,
+    })
+}
+
+/// `:ws[ymbol][!] {query}`
+pub(crate) fn parse_workspace_symbols(query: &str) -> Option<WorkspaceSymbols> {
+    let name_len = query
+        .find(|c: char| !c.is_ascii_alphabetic())
+        .unwrap_or(query.len());
+    let (name, args) = query.split_at(name_len);
+    if !matches!(name, "ws" | "wsy" | "wsym" | "wsymb" | "wsymbo" | "wsymbol") {
+        return None;
+    }
+    let (pick, args) = match args.strip_prefix('!') {
+        Some(args) => (true, args),
+        None => (false, args),
+    };
+    if !args.starts_with(' ') || args.trim().is_empty() {
+        return None;
+    }
+    Some(WorkspaceSymbols {
+        query: args.trim().to_string(),
+        pick,
+    })
+}
+
+/// Where a tag list goes: a symbol of the project, or a definition of the symbol
+/// under the cursor.
+#[derive(Clone)]
+enum TagTarget {
+    Symbol(Symbol),
+    Definition(Location),
+}
+
+/// The symbols `:tag` found when the name is ambiguous (or that `:tselect` or `:ws!`
+/// found), or the definitions `g]` found. Picking one jumps to it.
+pub struct TagList {
+    picker: View<Picker<TagListDelegate>>,
+}
+
+impl TagList {
+    fn new(
+        workspace: WeakView<Workspace>,
+        from: TagEntry,
+        targets: Vec<TagTarget>,
+        cx: &mut ViewContext<Self>,
+    ) -> Self {
+        let candidates = targets
+            .iter()
+            .enumerate()
+            .map(|(id, target)| {
+                let label = match target {
+                    TagTarget::Symbol(symbol) => format!(
+                        "{}  {}:{}",
+                        symbol.label.text,
+                        symbol.path.path.to_string_lossy(),
+                        symbol.range.start.0.row + 1
+                    ),
+                    TagTarget::Definition(location) => {
+                        let buffer = location.buffer.read(cx);
+                        let row = location.range.start.to_point(buffer).row;
+                        let line = buffer
+                            .text_for_range(
+                                Point::new(row, 0)..Point::new(row, buffer.line_len(row)),
+                            )
+                            .collect::<String>();
+                        let path = buffer
+                            .file()
+                            .map(|file| file.path().to_string_lossy().into_owned())
+                            .unwrap_or_default();
+                        format!("{}  {}:{}", line.trim(), path, row + 1)
+                    }
+                };
+                StringMatchCandidate::new(id, label)
+            })
+            .collect();
+        let delegate = TagListDelegate {
+            tag_list: cx.view().downgrade(),
+            workspace,
+            from,
+            targets,
+            candidates,
+            matches: Vec::new(),
+            selected_index: 0,
+        };
+        let picker = cx.new_view(|cx| Picker::uniform_list(delegate, cx));
+        Self { picker }
+    }
+}
+
+impl Render for TagList {
+    fn render(&mut self, _: &mut ViewContext<Self>) -> impl IntoElement {
+        v_flex()
+            .key_context("TagList")
+            .w(rems(34.))
+            .child(self.picker.clone())
+    }
+}
+
+impl FocusableView for TagList {
+    fn focus_handle(&self, cx: &AppContext) -> FocusHandle {
+        self.picker.focus_handle(cx)
+    }
+}
+
+impl EventEmitter<DismissEvent> for TagList {}
+impl ModalView for TagList {}
+
+pub struct TagListDelegate {
+    tag_list: WeakView<TagList>,
+    workspace: WeakView<Workspace>,
+    from: TagEntry,
+    targets: Vec<TagTarget>,
+    candidates: Vec<StringMatchCandidate>,
+    matches: Vec<StringMatch>,
+    selected_index: usize,
+}
+
+impl ModalDelegate for TagListDelegate {
+    type Modal = TagList;
+
+    fn modal(&self) -> &WeakView<TagList> {
+        &self.tag_list
+    }
+}
+
+impl StringListDelegate for TagListDelegate {
+    fn candidates(&self) -> &[StringMatchCandidate] {
+        &self.candidates
+    }
+
+    fn set_matches(&mut self, matches: Vec<StringMatch>) {
+        self.matches = matches;
+    }
+}
+
+impl PickerDelegate for TagListDelegate {
+    type ListItem = ListItem;
+
+    fn placeholder_text(&self, _cx: &mut WindowContext) -> Arc<str> {
+        format!("Pick a definition of {}...", self.from.name).into()
+    }
+
+    fn match_count(&self) -> usize {
+        self.matches.len()
+    }
+
+    fn selected_index(&self) -> usize {
+        self.selected_index
+    }
+
+    fn set_selected_index(&mut self, ix: usize, _: &mut ViewContext<Picker<Self>>) {
+        self.selected_index = ix;
+    }
+
+    fn update_matches(&mut self, query: String, cx: &mut ViewContext<Picker<Self>>) -> Task<()> {
+        list_picker::update_matches(self, query, cx)
+    }
+
+    fn confirm(&mut self, _: bool, cx: &mut ViewContext<Picker<Self>>) {
+        if let Some(mat) = self.matches.get(self.selected_index) {
+            let target = self.targets[mat.candidate_id].clone();
+            let from = self.from.clone();
+            self.workspace
+                .update(cx, |workspace, cx| match target {
+                    TagTarget::Symbol(symbol) => jump_to_symbol(workspace, from, symbol, cx),
+                    TagTarget::Definition(location) => {
+                        push_tag(from, cx);
+                        jump_to_position(workspace, location.buffer, location.range.start, cx);
+                    }
+                })
+                .ok();
+        }
+        self.dismissed(cx);
+    }
+
+    fn dismissed(&mut self, cx: &mut ViewContext<Picker<Self>>) {
+        list_picker::dismiss(self, cx)
+    }
+
+    fn render_match(
+        &self,
+        ix: usize,
+        selected: bool,
+        _: &mut ViewContext<Picker<Self>>,
+    ) -> Option<Self::ListItem> {
+        let mat = &self.matches[ix];
+        Some(
+            ListItem::new(ix)
+                .inset(true)
+                .spacing(ListItemSpacing::Sparse)
+                .selected(selected)
+                .child(HighlightedLabel::new(
+                    mat.string.clone(),
+                    mat.positions.clone(),
+                )),
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::PathBuf;
+
+    use super::{parse_tag, parse_tag_select, parse_workspace_symbols, TagList};
+    use crate::{state::Mode, test::VimTestContext, Vim};
+
+    #[test]
+    fn test_parse_tag() {
+        assert_eq!(parse_tag("tag foo").unwrap().name, "foo");
+        assert_eq!(parse_tag("sym  Foo::bar ").unwrap().name, "Foo::bar");
+        assert!(parse_tag("tag").is_none());
+        assert!(parse_tag("tags foo").is_none());
+        let select = parse_tag_select("ts foo").unwrap();
+        assert_eq!((select.name.as_str(), select.select), ("foo", true));
+        assert_eq!(parse_tag_select("tselect").unwrap().name, "");
+        assert!(parse_tag_select("tsfoo").is_none());
+    }
+
+    #[test]
+    fn test_parse_workspace_symbols() {
+        let symbols = parse_workspace_symbols("ws foo").unwrap();
+        assert_eq!((symbols.query.as_str(), symbols.pick), ("foo", false));
+        assert!(parse_workspace_symbols("wsymbol! foo").unwrap().pick);
+        assert!(parse_workspace_symbols("ws").is_none());
+        assert!(parse_workspace_symbols("wsfoo").is_none());
+    }
+
+    #[gpui::test]
+    async fn test_tag_stack(cx: &mut gpui::TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+
+        cx.set_state(
+            "fn foo() {}\nfn bar() { foo(); }\nfn baz() { ˇbar(); }",
+            Mode::Normal,
+        );
+        cx.lsp
+            .handle_request::<lsp::request::GotoDefinition, _, _>(|params, _| async move {
+                // each function is defined on the line above where it's called
+                let row = params.text_document_position_params.position.line - 1;
+                Ok(Some(lsp::GotoDefinitionResponse::Scalar(lsp::Location {
+                    uri: lsp::Url::from_file_path("/root/dir/file.rs").unwrap(),
+                    range: lsp::Range::new(lsp::Position::new(row, 3), lsp::Position::new(row, 6)),
+                })))
+            });
+
+        cx.simulate_keystrokes(["ctrl-]"]);
+        cx.run_until_parked();
+        cx.assert_state(
+            "fn foo() {}\nfn ˇbar() { foo(); }\nfn baz() { bar(); }",
+            Mode::Normal,
+        );
+        cx.simulate_keystrokes(["w", "w", "w", "ctrl-]"]);
+        cx.run_until_parked();
+        cx.assert_state(
+            "fn ˇfoo() {}\nfn bar() { foo(); }\nfn baz() { bar(); }",
+            Mode::Normal,
+        );
+
+        // ctrl-t goes back along the jumps, wherever the cursor has moved since
+        cx.simulate_keystrokes(["j", "ctrl-t"]);
+        cx.assert_state(
+            "fn foo() {}\nfn bar() { ˇfoo(); }\nfn baz() { bar(); }",
+            Mode::Normal,
+        );
+        cx.simulate_keystrokes(["ctrl-t"]);
+        cx.assert_state(
+            "fn foo() {}\nfn bar() { foo(); }\nfn baz() { ˇbar(); }",
+            Mode::Normal,
+        );
+        cx.simulate_keystrokes(["ctrl-t"]);
+        assert_eq!(cx.message().as_deref(), Some("E73: Tag stack empty"));
+
+        // with a count, it goes back that many
+        cx.simulate_keystrokes(["ctrl-]"]);
+        cx.run_until_parked();
+        cx.simulate_keystrokes(["w", "w", "w", "ctrl-]"]);
+        cx.run_until_parked();
+        cx.simulate_keystrokes(["2", "ctrl-t"]);
+        cx.assert_state(
+            "fn foo() {}\nfn bar() { foo(); }\nfn baz() { ˇbar(); }",
+            Mode::Normal,
+        );
+    }
+
+    #[gpui::test]
+    async fn test_select_tag(cx: &mut gpui::TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+
+        cx.set_state(
+            "fn foo() {}
+fn foo() {}
+fn baz() { ˇfoo(); }",
+            Mode::Normal,
+        );
+        cx.lsp
+            .handle_request::<lsp::request::GotoDefinition, _, _>(|_, _| async move {
+                let location = |row| lsp::Location {
+                    uri: lsp::Url::from_file_path("/root/dir/file.rs").unwrap(),
+                    range: lsp::Range::new(lsp::Position::new(row, 3), lsp::Position::new(row, 6)),
+                };
+                Ok(Some(lsp::GotoDefinitionResponse::Array(vec![
+                    location(0),
+                    location(1),
+                ])))
+            });
+
+        // ctrl-] goes to the first definition
+        cx.simulate_keystrokes(["ctrl-]"]);
+        cx.run_until_parked();
+        cx.assert_state(
+            "fn ˇfoo() {}
+fn foo() {}
+fn baz() { foo(); }",
+            Mode::Normal,
+        );
+        assert_eq!(cx.message().as_deref(), Some("tag 1 of 2"));
+        cx.simulate_keystrokes(["ctrl-t"]);
+
+        // while g] lists them to pick one, pushing the tag stack the same
+        cx.simulate_keystrokes(["g", "]"]);
+        cx.run_until_parked();
+        assert!(cx.workspace(|workspace, cx| workspace.active_modal::<TagList>(cx).is_some()));
+        cx.simulate_keystrokes(["ctrl-j", "enter"]);
+        cx.run_until_parked();
+        cx.assert_state(
+            "fn foo() {}
+fn ˇfoo() {}
+fn baz() { foo(); }",
+            Mode::Normal,
+        );
+        cx.simulate_keystrokes(["ctrl-t"]);
+        cx.assert_state(
+            "fn foo() {}
+fn foo() {}
+fn baz() { ˇfoo(); }",
+            Mode::Normal,
+        );
+    }
+
+    #[gpui::test]
+    async fn test_tag_command(cx: &mut gpui::TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+
+        cx.set_state(
+            "fn foo() {}\nfn foo_bar() {}\nfn baz() { ˇfoo(); }",
+            Mode::Normal,
+        );
+        cx.lsp
+            .handle_request::<lsp::request::WorkspaceSymbolRequest, _, _>(|params, _| async move {
+                #[allow(deprecated)]
+                let symbol = |name: &str, row| lsp::SymbolInformation {
+                    name: name.to_string(),
+                    kind: lsp::SymbolKind::FUNCTION,
+                    tags: None,
+                    deprecated: None,
+                    container_name: None,
+                    location: lsp::Location::new(
+                        lsp::Url::from_file_path("/root/dir/file.rs").unwrap(),
+                        lsp::Range::new(lsp::Position::new(row, 3), lsp::Position::new(row, 6)),
+                    ),
+                };
+                let symbols = [symbol("foo", 0), symbol("foo_bar", 1)]
+                    .into_iter()
+                    .filter(|symbol| symbol.name.contains(&params.query))
+                    .collect();
+                Ok(Some(lsp::WorkspaceSymbolResponse::Flat(symbols)))
+            });
+        let run = |cx: &mut VimTestContext, command: &str| {
+            cx.simulate_keystrokes([":"]);
+            cx.simulate_input(command);
+            cx.simulate_keystrokes(["enter"]);
+            cx.run_until_parked();
+        };
+
+        // the symbol called just that is jumped to, pushing the tag stack
+        run(&mut cx, "tag foo");
+        cx.assert_state(
+            "fn ˇfoo() {}\nfn foo_bar() {}\nfn baz() { foo(); }",
+            Mode::Normal,
+        );
+        cx.simulate_keystrokes(["ctrl-t"]);
+        cx.assert_state(
+            "fn foo() {}\nfn foo_bar() {}\nfn baz() { ˇfoo(); }",
+            Mode::Normal,
+        );
+
+        // and others are picked from a list
+        run(&mut cx, "symbol fo");
+        assert!(cx.workspace(|workspace, cx| workspace.active_modal::<TagList>(cx).is_some()));
+        cx.simulate_keystrokes(["ctrl-j", "enter"]);
+        cx.run_until_parked();
+        cx.assert_state(
+            "fn foo() {}\nfn ˇfoo_bar() {}\nfn baz() { foo(); }",
+            Mode::Normal,
+        );
+
+        run(&mut cx, "tag nothing");
+        assert_eq!(
+            cx.message().as_deref(),
+            Some("E426: Tag not found: nothing")
+        );
+
+        // :tselect lists a single match too, and without a name, the last tag's
+        run(&mut cx, "tselect foo");
+        assert!(cx.workspace(|workspace, cx| workspace.active_modal::<TagList>(cx).is_some()));
+        cx.simulate_keystrokes(["enter"]);
+        cx.run_until_parked();
+        cx.assert_state(
+            "fn ˇfoo() {}\nfn foo_bar() {}\nfn baz() { foo(); }",
+            Mode::Normal,
+        );
+        run(&mut cx, "tselect");
+        assert!(cx.workspace(|workspace, cx| workspace.active_modal::<TagList>(cx).is_some()));
+        cx.simulate_keystrokes(["escape"]);
+        cx.simulate_keystrokes(["ctrl-t", "ctrl-t"]);
+        run(&mut cx, "tselect");
+        assert_eq!(cx.message().as_deref(), Some("E73: Tag stack empty"));
+    }
+
+    #[gpui::test]
+    async fn test_workspace_symbols(cx: &mut gpui::TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+
+        cx.set_state(
+            "fn foo() {}\nfn foo_bar() {}\nfn baz() { ˇfoo(); }",
+            Mode::Normal,
+        );
+        cx.lsp
+            .handle_request::<lsp::request::WorkspaceSymbolRequest, _, _>(|params, _| async move {
+                #[allow(deprecated)]
+                let symbol = |name: &str, row| lsp::SymbolInformation {
+                    name: name.to_string(),
+                    kind: lsp::SymbolKind::FUNCTION,
+                    tags: None,
+                    deprecated: None,
+                    container_name: None,
+                    location: lsp::Location::new(
+                        lsp::Url::from_file_path("/root/dir/file.rs").unwrap(),
+                        lsp::Range::new(lsp::Position::new(row, 3), lsp::Position::new(row, 6)),
+                    ),
+                };
+                // listed out of order, as servers may
+                let symbols = [symbol("foo_bar", 1), symbol("foo", 0)]
+                    .into_iter()
+                    .filter(|symbol| symbol.name.contains(&params.query))
+                    .collect();
+                Ok(Some(lsp::WorkspaceSymbolResponse::Flat(symbols)))
+            });
+        let run = |cx: &mut VimTestContext, command: &str| {
+            cx.simulate_keystrokes([":"]);
+            cx.simulate_input(command);
+            cx.simulate_keystrokes(["enter"]);
+            cx.run_until_parked();
+        };
+
+        // the matches fill the quickfix list in order, and the first is jumped to
+        run(&mut cx, "ws foo");
+        cx.assert_state(
+            "fn ˇfoo() {}\nfn foo_bar() {}\nfn baz() { foo(); }",
+            Mode::Normal,
+        );
+        let list = cx.update(|cx| Vim::read(cx).workspace_state.quickfix.clone().unwrap());
+        let places = list
+            .entries
+            .iter()
+            .map(|entry| (entry.path.clone(), entry.line, entry.column))
+            .collect::<Vec<_>>();
+        let path = PathBuf::from("/root/dir/file.rs");
+        assert_eq!(places, [(path.clone(), 1, 4), (path, 2, 4)]);
+        run(&mut cx, "cnext");
+        cx.assert_state(
+            "fn foo() {}\nfn ˇfoo_bar() {}\nfn baz() { foo(); }",
+            Mode::Normal,
+        );
+
+        run(&mut cx, "ws nothing");
+        assert_eq!(cx.message().as_deref(), Some("E480: No match: nothing"));
+
+        // with a bang, they're picked from a list
+        run(&mut cx, "ws! foo");
+        assert!(cx.workspace(|workspace, cx| workspace.active_modal::<TagList>(cx).is_some()));
+    }
+}
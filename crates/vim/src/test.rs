@@ -14,9 +14,18 @@ pub use neovim_backed_test_context::*;
 pub use vim_test_context::*;
 
 use indoc::indoc;
+use language::language_settings::{AllLanguageSettings, SoftWrap};
 use search::BufferSearchBar;
 
-use crate::{insert::NormalBefore, motion, state::Mode, ModeIndicator};
+use settings::SettingsStore;
+use snippet::Snippet;
+
+use crate::{
+    insert::NormalBefore,
+    motion,
+    state::{Mode, Operator},
+    DisplayLines, ModeIndicator, Ruler, VimSettings,
+};
 
 #[gpui::test]
 async fn test_initially_disabled(cx: &mut gpui::TestAppContext) {
@@ -248,6 +257,35 @@ async fn test_selection_on_search(cx: &mut gpui::TestAppContext) {
     cx.assert_state(indoc! {"aa\nbb\nˇcc\ncc\ncc\n"}, Mode::Normal);
 }
 
+#[gpui::test]
+async fn test_search_bar_navigation(cx: &mut gpui::TestAppContext) {
+    let mut cx = VimTestContext::new(cx, true).await;
+
+    cx.set_state(indoc! {"aa\nbˇb\ncc\ncc\ncc\n"}, Mode::Normal);
+    cx.simulate_keystrokes(["/", "c", "c"]);
+    cx.simulate_keystrokes(["ctrl-n"]);
+    cx.assert_editor_state(indoc! {"aa\nbb\ncc\nˇcc\ncc\n"});
+    cx.simulate_keystrokes(["ctrl-j"]);
+    cx.assert_editor_state(indoc! {"aa\nbb\ncc\ncc\nˇcc\n"});
+    cx.simulate_keystrokes(["ctrl-k"]);
+    cx.assert_editor_state(indoc! {"aa\nbb\ncc\nˇcc\ncc\n"});
+    cx.simulate_keystrokes(["ctrl-p"]);
+    cx.assert_editor_state(indoc! {"aa\nbb\nˇcc\ncc\ncc\n"});
+
+    let search_bar = cx.workspace(|workspace, cx| {
+        workspace
+            .active_pane()
+            .read(cx)
+            .toolbar()
+            .read(cx)
+            .item_of_type::<BufferSearchBar>()
+            .expect("Buffer search bar should be deployed")
+    });
+    cx.simulate_keystrokes(["escape"]);
+    cx.run_until_parked();
+    cx.update_view(search_bar, |bar, _| assert!(bar.is_dismissed()));
+}
+
 #[gpui::test]
 async fn test_status_indicator(cx: &mut gpui::TestAppContext) {
     let mut cx = VimTestContext::new(cx, true).await;
@@ -296,6 +334,157 @@ async fn test_status_indicator(cx: &mut gpui::TestAppContext) {
     });
 }
 
+#[gpui::test]
+async fn test_count_and_register_order(cx: &mut gpui::TestAppContext) {
+    let mut cx = VimTestContext::new(cx, true).await;
+
+    // counts can come before or after the register, and after the operator, and
+    // they're multiplied together
+    for (keys, deleted_lines) in [
+        ("\"a3dd", 3),
+        ("3\"add", 3),
+        ("2\"a3dd", 6),
+        ("\"a2d2d", 4),
+        ("2\"ad3d", 6),
+        ("2\"a2d2d", 8),
+        ("12\"add", 12),
+    ] {
+        let lines = (1..=12).map(|n| n.to_string()).collect::<Vec<_>>();
+        cx.set_state(&format!("ˇ{}", lines.join("\n")), Mode::Normal);
+        for key in keys.chars() {
+            cx.simulate_keystroke(&key.to_string());
+        }
+        let deleted = lines[..deleted_lines].join("\n") + "\n";
+        assert_eq!(cx.read_register('a'), Some(deleted), "keys: {keys}");
+        assert_eq!(
+            cx.buffer_text(),
+            lines[deleted_lines..].join("\n"),
+            "keys: {keys}"
+        );
+    }
+
+    // but a register can't come after the operator
+    cx.set_state("ˇone", Mode::Normal);
+    cx.simulate_keystrokes(["d", "\"", "b"]);
+    cx.assert_state("ˇone", Mode::Normal);
+    assert_eq!(cx.active_operator(), None);
+}
+
+#[gpui::test]
+async fn test_escape_clears_pending_state(cx: &mut gpui::TestAppContext) {
+    let mut cx = VimTestContext::new(cx, true).await;
+
+    // after escape, the count, register and operator are all forgotten
+    for keys in ["3", "\"a", "2\"a", "\"a3d", "d2", "\"a2d\""] {
+        cx.set_state("ˇone two three", Mode::Normal);
+        for key in keys.chars() {
+            cx.simulate_keystroke(&key.to_string());
+        }
+        cx.simulate_keystrokes(["escape", "y", "w", "x"]);
+        cx.assert_state("ˇne two three", Mode::Normal);
+        assert_eq!(cx.read_register('a'), None, "keys: {keys}");
+        assert_eq!(cx.read_register('"'), Some("o".to_string()), "keys: {keys}");
+    }
+
+    // as they are when focus moves to another pane, even once that pane's editor
+    // has taken over
+    cx.set_state("ˇone two three", Mode::Normal);
+    cx.simulate_keystrokes(["ctrl-w", "v"]);
+    cx.simulate_keystrokes(["2", "\"", "a", "d"]);
+    assert_eq!(cx.active_operator(), Some(Operator::Delete));
+    cx.workspace(|workspace, cx| workspace.activate_next_pane(cx));
+    cx.run_until_parked();
+    cx.workspace(|workspace, cx| workspace.activate_next_pane(cx));
+    cx.run_until_parked();
+    assert_eq!(cx.active_operator(), None);
+    cx.simulate_keystrokes(["w", "x"]);
+    assert_eq!(cx.buffer_text(), "one wo three");
+    assert_eq!(cx.read_register('a'), None);
+}
+
+#[gpui::test]
+async fn test_showcmd(cx: &mut gpui::TestAppContext) {
+    let mut cx = VimTestContext::new(cx, true).await;
+
+    let mode_indicator = cx.workspace(|workspace, cx| {
+        let status_bar = workspace.status_bar().read(cx);
+        status_bar.item_of_type::<ModeIndicator>().unwrap()
+    });
+    cx.set_state("ˇone\ntwo\nthree\nfour\nfive\nsix\nseven", Mode::Normal);
+
+    for (key, pending) in [
+        ("2", "2"),
+        ("\"", "2\""),
+        ("a", "2\"a"),
+        ("3", "2\"a3"),
+        ("d", "2\"a3d"),
+        ("1", "2\"a3d1"),
+        ("d", ""),
+    ] {
+        cx.simulate_keystroke(key);
+        assert_eq!(
+            cx.workspace(|_, cx| mode_indicator.read(cx).pending.clone()),
+            pending
+        );
+    }
+    cx.assert_state("ˇseven", Mode::Normal);
+
+    // while a register is selected, the start of what it holds is shown
+    let preview = |cx: &mut VimTestContext| {
+        cx.workspace(|_, cx| mode_indicator.read(cx).register_preview.clone())
+    };
+    cx.set_state(&format!("ˇone\n{}", "x".repeat(40)), Mode::Normal);
+    cx.simulate_keystrokes(["\"", "a", "y", "y", "j", "\"", "b", "y", "y"]);
+    cx.simulate_keystrokes(["\"", "a"]);
+    assert_eq!(preview(&mut cx), Some("one^J".to_string()));
+    cx.simulate_keystrokes(["p"]);
+    assert_eq!(preview(&mut cx), None);
+    cx.simulate_keystrokes(["\"", "b"]);
+    assert_eq!(preview(&mut cx), Some(format!("{}…", "x".repeat(30))));
+    cx.simulate_keystrokes(["\"", "c"]);
+    assert_eq!(preview(&mut cx), None);
+}
+
+#[gpui::test]
+async fn test_ruler(cx: &mut gpui::TestAppContext) {
+    let mut cx = VimTestContext::new(cx, true).await;
+
+    let ruler = cx.workspace(|workspace, cx| {
+        let status_bar = workspace.status_bar().read(cx);
+        status_bar.item_of_type::<Ruler>().unwrap()
+    });
+
+    // off by default
+    cx.set_state("ˇone\n\ttwo\n\nfour", Mode::Normal);
+    assert_eq!(cx.workspace(|_, cx| ruler.read(cx).text.clone()), None);
+
+    cx.update_global(|store: &mut SettingsStore, cx| {
+        store.update_user_settings::<VimSettings>(cx, |s| s.ruler = Some(true));
+    });
+    cx.run_until_parked();
+    assert_eq!(
+        cx.workspace(|_, cx| ruler.read(cx).text.clone()).as_deref(),
+        Some("1,1  25%")
+    );
+
+    // tabs are counted once in the column, and by their width on screen
+    cx.simulate_keystrokes(["j", "l"]);
+    assert_eq!(
+        cx.workspace(|_, cx| ruler.read(cx).text.clone()).as_deref(),
+        Some("2,2-5  50%")
+    );
+
+    cx.simulate_keystrokes(["j"]);
+    assert_eq!(
+        cx.workspace(|_, cx| ruler.read(cx).text.clone()).as_deref(),
+        Some("3,0-1  75%")
+    );
+
+    cx.disable_vim();
+    cx.run_until_parked();
+    assert_eq!(cx.workspace(|_, cx| ruler.read(cx).text.clone()), None);
+}
+
 #[gpui::test]
 async fn test_word_characters(cx: &mut gpui::TestAppContext) {
     let mut cx = VimTestContext::new_typescript(cx).await;
@@ -563,8 +752,7 @@ async fn test_folds(cx: &mut gpui::TestAppContext) {
     //  [FOLDED]
     // }
 
-    // TODO: this should not be needed but currently zf does not
-    // return to normal mode.
+    // zf returns to normal mode, so this is a no-op
     cx.simulate_shared_keystrokes(["escape"]).await;
 
     // skip over fold downward
@@ -749,6 +937,48 @@ async fn test_wrapped_motions(cx: &mut gpui::TestAppContext) {
     .await;
 }
 
+#[gpui::test]
+async fn test_display_lines_setting(cx: &mut gpui::TestAppContext) {
+    let mut cx = VimTestContext::new(cx, true).await;
+
+    cx.update_global(|store: &mut SettingsStore, cx| {
+        store.update_user_settings::<AllLanguageSettings>(cx, |settings| {
+            settings.defaults.soft_wrap = Some(SoftWrap::PreferredLineLength);
+            settings.defaults.preferred_line_length = Some(12);
+        });
+        store.update_user_settings::<VimSettings>(cx, |s| {
+            s.display_lines = Some(DisplayLines {
+                motions: true,
+                operators: false,
+            })
+        });
+    });
+
+    // j moves within the wrapped line, but dj still deletes buffer lines
+    cx.set_state("tˇwelve char twelve char\ntwelve char\nend", Mode::Normal);
+    cx.simulate_keystrokes(["j"]);
+    cx.assert_state("twelve char tˇwelve char\ntwelve char\nend", Mode::Normal);
+    cx.simulate_keystrokes(["d", "j"]);
+    cx.assert_state("ˇend", Mode::Normal);
+
+    cx.update_global(|store: &mut SettingsStore, cx| {
+        store.update_user_settings::<VimSettings>(cx, |s| {
+            s.display_lines = Some(DisplayLines {
+                motions: false,
+                operators: true,
+            })
+        });
+    });
+
+    // dd deletes the part of a wrapped line that's on the cursor's display line,
+    // and the whole of a line that isn't wrapped
+    cx.set_state("twelve char tˇwelve char\ntwelve char", Mode::Normal);
+    cx.simulate_keystrokes(["d", "d"]);
+    cx.assert_state("twelve charˇ \ntwelve char", Mode::Normal);
+    cx.simulate_keystrokes(["j", "d", "d"]);
+    cx.assert_state("ˇtwelve char ", Mode::Normal);
+}
+
 #[gpui::test]
 async fn test_paragraphs_dont_wrap(cx: &mut gpui::TestAppContext) {
     let mut cx = NeovimBackedTestContext::new(cx).await;
@@ -981,3 +1211,46 @@ async fn test_remap(cx: &mut gpui::TestAppContext) {
     cx.simulate_keystrokes(["g", "t"]);
     cx.assert_state("12ˇ 34", Mode::Normal);
 }
+
+#[gpui::test]
+async fn test_select_mode_in_snippets(cx: &mut gpui::TestAppContext) {
+    let mut cx = VimTestContext::new(cx, true).await;
+
+    cx.set_state("ˇ", Mode::Insert);
+    cx.update_editor(|editor, cx| {
+        let snippet = Snippet::parse("foo(${1:bar}, ${2:baz})").unwrap();
+        editor.insert_snippet(&[0..0], snippet, cx).unwrap();
+    });
+    cx.assert_state("foo(«barˇ», baz)", Mode::Select);
+
+    cx.simulate_keystrokes(["x"]);
+    cx.assert_state("foo(xˇ, baz)", Mode::Insert);
+
+    cx.simulate_keystrokes(["tab"]);
+    cx.assert_state("foo(x, «bazˇ»)", Mode::Select);
+
+    cx.simulate_keystrokes(["escape"]);
+    cx.assert_state("foo(x, baˇz)", Mode::Normal);
+}
+
+#[gpui::test]
+async fn test_snippet_tabstops_in_normal_mode(cx: &mut gpui::TestAppContext) {
+    let mut cx = VimTestContext::new(cx, true).await;
+
+    cx.set_state("ˇ", Mode::Insert);
+    cx.update_editor(|editor, cx| {
+        let snippet = Snippet::parse("foo(${1:bar}, ${2:baz})").unwrap();
+        editor.insert_snippet(&[0..0], snippet, cx).unwrap();
+    });
+    cx.simulate_keystrokes(["x", "escape"]);
+    cx.assert_state("foo(xˇ, baz)", Mode::Normal);
+
+    cx.simulate_keystrokes(["tab"]);
+    cx.assert_state("foo(x, «bazˇ»)", Mode::Select);
+    cx.simulate_keystrokes(["escape", "shift-tab"]);
+    cx.assert_state("foo(«xˇ», baz)", Mode::Select);
+
+    cx.set_state("ˇ", Mode::Normal);
+    cx.simulate_keystrokes(["tab"]);
+    cx.assert_state("ˇ", Mode::Normal);
+}
@@ -1,4 +1,8 @@
-use std::ops::{Deref, DerefMut};
+use std::{
+    ops::{Deref, DerefMut},
+    thread,
+    time::Duration,
+};
 
 use editor::test::editor_lsp_test_context::EditorLspTestContext;
 use gpui::{Context, View, VisualContext};
@@ -79,6 +83,8 @@ impl VimTestContext {
             workspace.status_bar().update(cx, |status_bar, cx| {
                 let vim_mode_indicator = cx.new_view(ModeIndicator::new);
                 status_bar.add_right_item(vim_mode_indicator, cx);
+                let vim_ruler = cx.new_view(Ruler::new);
+                status_bar.add_right_item(vim_ruler, cx);
             });
         });
 
@@ -127,6 +133,36 @@ impl VimTestContext {
             .read(|cx| cx.global::<Vim>().state().operator_stack.last().copied())
     }
 
+    pub fn message(&mut self) -> Option<String> {
+        self.cx
+            .read(|cx| cx.global::<Vim>().workspace_state.message.clone())
+    }
+
+    /// Waits for the operation running in the background to be over, for one (like a
+    /// shell command) that waits on something outside of the test's executor.
+    pub fn run_until_operation_finishes(&mut self) {
+        self.executor().allow_parking();
+        loop {
+            self.run_until_parked();
+            if self
+                .cx
+                .read(|cx| cx.global::<Vim>().pending_operation.is_none())
+            {
+                break;
+            }
+            thread::sleep(Duration::from_millis(1));
+        }
+    }
+
+    pub fn read_register(&mut self, register: char) -> Option<String> {
+        self.cx.read(|cx| {
+            cx.global::<Vim>()
+                .workspace_state
+                .read_register(register)
+                .cloned()
+        })
+    }
+
     pub fn set_state(&mut self, text: &str, mode: Mode) {
         let window = self.window;
         self.cx.set_state(text);
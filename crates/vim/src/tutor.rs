@@ -0,0 +1,183 @@
+use collections::HashSet;
+use editor::{Editor, EditorEvent};
+use gpui::{actions, View, ViewContext, VisualContext, WindowContext};
+use language::Point;
+use workspace::Workspace;
+
+use crate::Vim;
+
+actions!(vim, [OpenTutor]);
+
+/// The tutor, which is opened as a new buffer for each `:Tutor`.
+const TUTOR: &str = include_str!("tutor.txt");
+
+/// The exercise lines of the tutor start with this.
+const EXERCISE_MARKER: &str = "--->";
+
+/// What the exercise lines of each lesson that has them read once it's done.
+const CHECKPOINTS: &[(&str, &[&str])] = &[
+    ("1.2", &["The cow jumped over the moon."]),
+    ("1.3", &["There is some text missing from this line."]),
+    ("1.4", &["There is some text missing from this line."]),
+    (
+        "2.1",
+        &["There are some words that don't belong in this sentence."],
+    ),
+    ("2.2", &["Somebody typed the end of this line twice."]),
+    ("2.3", &["Roses are red,", "Violets are blue,"]),
+    ("3.1", &["Roses are red,", "Violets are blue,"]),
+    (
+        "3.2",
+        &["When this line was typed in, someone pressed some wrong keys!"],
+    ),
+    (
+        "3.3",
+        &["This line has a few words that need changing using the change operator."],
+    ),
+    (
+        "4.2",
+        &["the best time to see the flowers is in the spring."],
+    ),
+];
+
+enum TutorCheckpoints {}
+
+pub(crate) fn register(workspace: &mut Workspace, _: &mut ViewContext<Workspace>) {
+    workspace.register_action(open_tutor);
+}
+
+/// `:Tutor` opens a copy of the tutor, like vimtutor, whose lessons have the user
+/// edit it. Each time it's edited, the lessons are checked, and the exercises of
+/// those that are done are highlighted.
+fn open_tutor(workspace: &mut Workspace, _: &OpenTutor, cx: &mut ViewContext<Workspace>) {
+    let project = workspace.project().clone();
+    let Ok(buffer) = project.update(cx, |project, cx| project.create_buffer(TUTOR, None, cx))
+    else {
+        return;
+    };
+    let editor = cx.new_view(|cx| Editor::for_buffer(buffer, Some(project), cx));
+    let mut done = HashSet::default();
+    cx.subscribe(&editor, move |_, editor, event: &EditorEvent, cx| {
+        if matches!(event, EditorEvent::BufferEdited) {
+            check_lessons(&editor, &mut done, cx);
+        }
+    })
+    .detach();
+    workspace.add_item_to_active_pane(Box::new(editor), cx);
+}
+
+fn check_lessons(editor: &View<Editor>, done: &mut HashSet<&'static str>, cx: &mut WindowContext) {
+    let text = editor.read(cx).text(cx);
+    let mut finished = None;
+    editor.update(cx, |editor, cx| {
+        let snapshot = editor.buffer().read(cx).snapshot(cx);
+        let mut ranges = Vec::new();
+        for (lesson, is_done, rows) in lesson_status(&text) {
+            if !is_done {
+                done.remove(lesson);
+                continue;
+            }
+            if done.insert(lesson) {
+                finished = Some(lesson);
+            }
+            ranges.extend(rows.into_iter().map(|row| {
+                snapshot.anchor_before(Point::new(row, 0))
+                    ..snapshot.anchor_after(Point::new(row, snapshot.line_len(row)))
+            }));
+        }
+        editor.highlight_background::<TutorCheckpoints>(
+            ranges,
+            |theme| theme.editor_document_highlight_write_background,
+            cx,
+        );
+    });
+    if let Some(lesson) = finished {
+        let message = if done.len() == CHECKPOINTS.len() {
+            format!("Lesson {lesson} done: that's every lesson!")
+        } else {
+            format!("Lesson {lesson} done")
+        };
+        Vim::update(cx, |vim, _| vim.show_message(message));
+    }
+}
+
+/// Each lesson with a checkpoint, whether its exercise is done, and the rows of
+/// its exercise lines: those marked `--->` between its heading and the next.
+fn lesson_status(text: &str) -> Vec<(&'static str, bool, Vec<u32>)> {
+    let lines = text.lines().collect::<Vec<_>>();
+    CHECKPOINTS
+        .iter()
+        .filter_map(|(lesson, expected)| {
+            let heading = format!("Lesson {lesson}:");
+            let start = lines.iter().position(|line| line.starts_with(&heading))?;
+            let end = lines[start + 1..]
+                .iter()
+                .position(|line| line.starts_with("Lesson "))
+                .map_or(lines.len(), |ix| start + 1 + ix);
+            let rows = (start..end)
+                .filter(|row| lines[*row].starts_with(EXERCISE_MARKER))
+                .map(|row| row as u32)
+                .collect::<Vec<_>>();
+            let is_done = rows
+                .iter()
+                .map(|row| lines[*row as usize][EXERCISE_MARKER.len()..].trim())
+                .eq(expected.iter().copied());
+            Some((*lesson, is_done, rows))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use editor::Editor;
+
+    use super::{lesson_status, CHECKPOINTS, TUTOR};
+    use crate::{state::Mode, test::VimTestContext};
+
+    #[test]
+    fn test_lesson_status() {
+        // every checkpoint has its lesson, which isn't done to start with
+        let status = lesson_status(TUTOR);
+        assert_eq!(status.len(), CHECKPOINTS.len());
+        assert!(status
+            .iter()
+            .all(|(_, is_done, rows)| !is_done && !rows.is_empty()));
+
+        let text = TUTOR.replace(
+            "---> The ccow jumpedd ovverr thhe moon.",
+            "---> The cow jumped over the moon.",
+        );
+        let text = text.replace("---> Mud is fun,\n", "");
+        let done = lesson_status(&text)
+            .into_iter()
+            .filter(|(_, is_done, _)| *is_done)
+            .map(|(lesson, _, _)| lesson)
+            .collect::<Vec<_>>();
+        assert_eq!(done, ["1.2", "2.3"]);
+    }
+
+    #[gpui::test]
+    async fn test_tutor(cx: &mut gpui::TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+
+        cx.set_state("ˇone", Mode::Normal);
+        cx.simulate_keystrokes([":"]);
+        cx.simulate_input("Tutor");
+        cx.simulate_keystrokes(["enter"]);
+        cx.run_until_parked();
+        let editor = cx.workspace(|workspace, cx| workspace.active_item_as::<Editor>(cx).unwrap());
+        let row = cx.update(|cx| {
+            let text = editor.read(cx).text(cx);
+            text.lines()
+                .position(|line| line == "---> Mud is fun,")
+                .unwrap()
+        });
+
+        // deleting the line that doesn't belong finishes lesson 2.3
+        cx.simulate_keystrokes([":"]);
+        cx.simulate_input(&(row + 1).to_string());
+        cx.simulate_keystrokes(["enter", "d", "d"]);
+        cx.run_until_parked();
+        assert_eq!(cx.message().as_deref(), Some("Lesson 2.3 done"));
+    }
+}
@@ -0,0 +1,204 @@
+use std::time::{Duration, Instant};
+
+use collections::HashSet;
+use editor::scroll::Autoscroll;
+use gpui::{actions, ViewContext, WindowContext};
+use language::{Point, UndoTreeNode};
+use workspace::Workspace;
+
+use crate::{messages::show_output, Vim};
+
+actions!(vim, [UndoOlder, UndoNewer, ShowUndoList]);
+
+pub(crate) fn register(workspace: &mut Workspace, _: &mut ViewContext<Workspace>) {
+    workspace.register_action(|_: &mut Workspace, _: &UndoOlder, cx| {
+        Vim::update(cx, |vim, cx| {
+            let count = vim.take_count(cx).unwrap_or(1);
+            step_through_history(vim, -(count as isize), cx)
+        })
+    });
+    workspace.register_action(|_: &mut Workspace, _: &UndoNewer, cx| {
+        Vim::update(cx, |vim, cx| {
+            let count = vim.take_count(cx).unwrap_or(1);
+            step_through_history(vim, count as isize, cx)
+        })
+    });
+    workspace.register_action(show_undo_list);
+}
+
+/// `g-` and `g+` go back and forward through the states the buffer has been in, in
+/// the order they were made. Unlike `u` and `ctrl-r`, they reach the changes that
+/// were undone before an edit was made, which stay in the undo tree as a branch.
+/// The cursor goes to the first line that changed.
+fn step_through_history(vim: &mut Vim, steps: isize, cx: &mut WindowContext) {
+    vim.clear_operator(cx);
+    let message = vim.update_active_editor(cx, |_, editor, cx| {
+        if editor.read_only(cx) {
+            return None;
+        }
+        let buffer = editor.buffer().read(cx).as_singleton()?;
+        let (tree, current) = {
+            let buffer = buffer.read(cx);
+            let current = buffer.peek_undo_stack().map(|entry| entry.transaction_id());
+            (buffer.undo_tree(), current)
+        };
+        // the states are the text before any change, and after each of them
+        let position = current
+            .and_then(|id| tree.iter().position(|node| node.transaction_id == id))
+            .map_or(0, |ix| ix + 1);
+        let target = (position as isize + steps).clamp(0, tree.len() as isize) as usize;
+        if target == position {
+            return Some(if steps < 0 {
+                "Already at oldest change"
+            } else {
+                "Already at newest change"
+            });
+        }
+
+        let old_version = buffer.read(cx).version();
+        let target = target.checked_sub(1).map(|ix| tree[ix].transaction_id);
+        buffer.update(cx, |buffer, cx| buffer.restore_undo_state(target, cx));
+        let first_change = buffer
+            .read(cx)
+            .edits_since::<Point>(&old_version)
+            .map(|edit| edit.new.start)
+            .min();
+        if let Some(point) = first_change {
+            editor.change_selections(Some(Autoscroll::fit()), cx, |s| {
+                s.select_ranges([point..point])
+            });
+        }
+        None
+    });
+    if let Some(message) = message.flatten() {
+        vim.show_message(message);
+    }
+}
+
+/// `:undol[ist]` lists the ends of the branches of the undo tree: the number of the
+/// change (counting in the order they were made, as `g-` and `g+` go through them),
+/// how many changes lead to it, and when it was made.
+fn show_undo_list(workspace: &mut Workspace, _: &ShowUndoList, cx: &mut ViewContext<Workspace>) {
+    Vim::update(cx, |vim, cx| vim.clear_operator(cx));
+    let Some(tree) = Vim::update(cx, |vim, cx| {
+        vim.update_active_editor(cx, |_, editor, cx| {
+            let buffer = editor.buffer().read(cx).as_singleton()?;
+            let tree = buffer.read(cx).undo_tree();
+            Some(tree)
+        })
+    })
+    .flatten() else {
+        return;
+    };
+    if tree.is_empty() {
+        Vim::update(cx, |vim, _| vim.show_message("Nothing to undo"));
+        return;
+    }
+    show_output(workspace, &undo_list_text(&tree, Instant::now()), cx);
+}
+
+fn undo_list_text(tree: &[UndoTreeNode], now: Instant) -> String {
+    let parents = tree
+        .iter()
+        .filter_map(|node| node.parent)
+        .collect::<HashSet<_>>();
+    let mut text = "number changes  when".to_string();
+    for (ix, node) in tree.iter().enumerate() {
+        if parents.contains(&node.transaction_id) {
+            continue;
+        }
+        let mut changes = 1;
+        let mut parent = node.parent;
+        while let Some(id) = parent {
+            changes += 1;
+            parent = tree
+                .iter()
+                .find(|node| node.transaction_id == id)
+                .and_then(|node| node.parent);
+        }
+        let when = elapsed_text(now.saturating_duration_since(node.last_edit_at));
+        text.push_str(&format!("\n{:>6} {:>7}  {when}", ix + 1, changes));
+    }
+    text
+}
+
+fn elapsed_text(elapsed: Duration) -> String {
+    let seconds = elapsed.as_secs();
+    if seconds < 100 {
+        format!("{seconds} seconds ago")
+    } else if seconds < 100 * 60 {
+        format!("{} minutes ago", seconds / 60)
+    } else {
+        format!("{} hours ago", seconds / 3600)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{state::Mode, test::VimTestContext};
+
+    #[gpui::test]
+    async fn test_undo_branches(cx: &mut gpui::TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+
+        cx.set_state("ˇone", Mode::Normal);
+        cx.simulate_keystrokes(["x", "x", "u"]);
+        cx.assert_state("ˇne", Mode::Normal);
+        // this leaves "e" on a branch that u and ctrl-r can't get back to
+        cx.simulate_keystrokes(["shift-a", "!", "escape"]);
+        cx.assert_state("neˇ!", Mode::Normal);
+        cx.simulate_keystrokes(["ctrl-r"]);
+        cx.assert_state("neˇ!", Mode::Normal);
+
+        // but g- goes back through it, in the order the changes were made
+        cx.simulate_keystrokes(["g", "-"]);
+        cx.assert_state("ˇe", Mode::Normal);
+        cx.simulate_keystrokes(["g", "-"]);
+        cx.assert_state("ˇne", Mode::Normal);
+        cx.simulate_keystrokes(["g", "-"]);
+        cx.assert_state("ˇone", Mode::Normal);
+        cx.simulate_keystrokes(["g", "-"]);
+        assert_eq!(cx.message().as_deref(), Some("Already at oldest change"));
+        cx.simulate_keystrokes(["3", "g", "+"]);
+        cx.assert_state("ˇne!", Mode::Normal);
+        cx.simulate_keystrokes(["g", "-"]);
+        cx.assert_state("ˇe", Mode::Normal);
+        cx.simulate_keystrokes(["u", "ctrl-r"]);
+        cx.assert_state("ˇe", Mode::Normal);
+
+        // they can be replayed in a macro
+        cx.set_state("ˇabcd", Mode::Normal);
+        cx.simulate_keystrokes(["q", "a", "x", "g", "-", "g", "+", "q"]);
+        cx.assert_state("ˇbcd", Mode::Normal);
+        cx.simulate_keystrokes(["@", "a"]);
+        cx.assert_state("ˇcd", Mode::Normal);
+        cx.simulate_keystrokes(["u"]);
+        cx.assert_state("ˇbcd", Mode::Normal);
+    }
+
+    #[gpui::test]
+    async fn test_undo_list(cx: &mut gpui::TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+
+        cx.set_state("ˇone", Mode::Normal);
+        cx.simulate_keystrokes(["x", "x", "u", "shift-a", "!", "escape"]);
+        cx.simulate_keystrokes([":"]);
+        cx.simulate_input("undolist");
+        cx.simulate_keystrokes(["enter"]);
+        let output = cx.workspace(|_, cx| {
+            crate::Vim::read(cx)
+                .workspace_state
+                .last_output
+                .clone()
+                .unwrap()
+        });
+        let lines = output
+            .lines()
+            .map(|line| line.rsplit_once("  ").unwrap().0.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            lines,
+            ["number changes", "     2       2", "     3       2"]
+        );
+    }
+}
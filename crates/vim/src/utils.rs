@@ -1,11 +1,28 @@
-use std::time::Duration;
+use std::{
+    ops::Range,
+    path::{Path, PathBuf},
+    process::{Output, Stdio},
+    sync::Arc,
+    time::Duration,
+};
 
-use editor::{ClipboardSelection, Editor};
-use gpui::{ClipboardItem, ViewContext};
-use language::{CharKind, Point};
+use anyhow::Context as _;
+use collections::HashMap;
+use futures::AsyncWriteExt as _;
+
+use editor::{Anchor, ClipboardSelection, Editor, MultiBufferSnapshot};
+use gpui::{AppContext, ClipboardItem, Model, ViewContext, WeakView, WindowContext};
+use language::{Buffer, CharKind, LanguageScope, Point, Selection, TransactionId};
+use project::Project;
 use settings::Settings;
+use unicode_width::UnicodeWidthChar;
+use workspace::Workspace;
 
-use crate::{state::Mode, UseSystemClipboard, Vim, VimSettings};
+use crate::{
+    shared_register::share_register,
+    state::{Mode, RegisterKind, RegisterPiece},
+    UseSystemClipboard, Vim, VimSettings,
+};
 
 pub struct HighlightOnYank;
 
@@ -36,68 +53,81 @@ fn copy_selections_content_internal(
 ) {
     let selections = editor.selections.all_adjusted(cx);
     let buffer = editor.buffer().read(cx).snapshot(cx);
-    let mut text = String::new();
+    let mut ranges = Vec::with_capacity(selections.len());
     let mut clipboard_selections = Vec::with_capacity(selections.len());
     let mut ranges_to_highlight = Vec::new();
-    {
-        let mut is_first = true;
-        for selection in selections.iter() {
-            let mut start = selection.start;
-            let end = selection.end;
-            if is_first {
-                is_first = false;
-            } else {
-                text.push_str("\n");
-            }
-            let initial_len = text.len();
-
-            // if the file does not end with \n, and our line-mode selection ends on
-            // that line, we will have expanded the start of the selection to ensure it
-            // contains a newline (so that delete works as expected). We undo that change
-            // here.
-            let is_last_line = linewise
-                && end.row == buffer.max_buffer_row()
-                && buffer.max_point().column > 0
-                && start.row < buffer.max_buffer_row()
-                && start == Point::new(start.row, buffer.line_len(start.row));
-
-            if is_last_line {
-                start = Point::new(start.row + 1, 0);
-            }
+    let mut len = 0;
+    for selection in selections.iter() {
+        let mut start = selection.start;
+        let end = selection.end;
 
-            let start_anchor = buffer.anchor_after(start);
-            let end_anchor = buffer.anchor_before(end);
-            ranges_to_highlight.push(start_anchor..end_anchor);
+        // if the file does not end with \n, and our line-mode selection ends on
+        // that line, we will have expanded the start of the selection to ensure it
+        // contains a newline (so that delete works as expected). We undo that change
+        // here.
+        let is_last_line = linewise
+            && end.row == buffer.max_buffer_row()
+            && buffer.max_point().column > 0
+            && start.row < buffer.max_buffer_row()
+            && start == Point::new(start.row, buffer.line_len(start.row));
 
-            for chunk in buffer.text_for_range(start..end) {
-                text.push_str(chunk);
-            }
-            if is_last_line {
-                text.push_str("\n");
-            }
-            clipboard_selections.push(ClipboardSelection {
-                len: text.len() - initial_len,
-                is_entire_line: linewise,
-                first_line_indent: buffer.indent_size_for_line(start.row).len,
-            });
+        if is_last_line {
+            start = Point::new(start.row + 1, 0);
         }
+
+        let start_anchor = buffer.anchor_after(start);
+        let end_anchor = buffer.anchor_before(end);
+        ranges_to_highlight.push(start_anchor..end_anchor);
+
+        let selection_len =
+            buffer.point_to_offset(end) - buffer.point_to_offset(start) + usize::from(is_last_line);
+        len += selection_len;
+        ranges.push((start..end, is_last_line));
+        clipboard_selections.push(ClipboardSelection {
+            len: selection_len,
+            is_entire_line: linewise,
+            first_line_indent: buffer.indent_size_for_line(start.row).len,
+        });
     }
 
-    let setting = VimSettings::get_global(cx).use_system_clipboard;
-    if setting == UseSystemClipboard::Always || setting == UseSystemClipboard::OnYank && is_yank {
-        cx.write_to_clipboard(ClipboardItem::new(text.clone()).with_metadata(clipboard_selections));
-        vim.workspace_state
-            .registers
-            .insert(".system.".to_string(), text.clone());
+    let register = vim.workspace_state.selected_register.take();
+    let kind = if linewise {
+        RegisterKind::Linewise
+    } else if vim.state().mode == Mode::VisualBlock {
+        RegisterKind::Blockwise
     } else {
-        vim.workspace_state.registers.insert(
-            ".system.".to_string(),
-            cx.read_from_clipboard()
-                .map(|item| item.text().clone())
-                .unwrap_or_default(),
-        );
+        RegisterKind::Charwise
+    };
+    let copied = CopiedText {
+        clipboard_selections,
+        register,
+        kind,
+        is_yank,
+    };
+    // the text of a very large copy (like `ggdG` in a log file of hundreds of MB)
+    // is put together in the background, and written to the register once it is
+    if len >= LARGE_COPY_LEN && vim.pending_operation.is_none() {
+        vim.show_progress(if is_yank { "Yanking" } else { "Deleting" });
+        let task = cx.spawn(|editor, mut cx| async move {
+            let text = cx
+                .background_executor()
+                .spawn(async move { selections_text(&buffer, &ranges) })
+                .await;
+            editor
+                .update(&mut cx, |editor, cx| {
+                    Vim::update(cx, |vim, cx| {
+                        vim.finish_pending_operation();
+                        copied.write(vim, editor, text, cx);
+                    })
+                })
+                .ok();
+        });
+        vim.pending_operation = Some(task);
+    } else {
+        let text = selections_text(&buffer, &ranges);
+        copied.write(vim, editor, text, cx);
     }
-    vim.workspace_state.registers.insert("\"".to_string(), text);
+
     if !is_yank || vim.state().mode == Mode::Visual {
         return;
     }
@@ -119,6 +149,247 @@ fn copy_selections_content_internal(
     .detach();
 }
 
+/// From this many bytes, the text of a yank or delete is put together in the
+/// background.
+const LARGE_COPY_LEN: usize = 1 << 20;
+
+/// The text of the selections, one after another on lines of their own.
+fn selections_text(buffer: &MultiBufferSnapshot, ranges: &[(Range<Point>, bool)]) -> String {
+    let mut text = String::new();
+    for (ix, (range, is_last_line)) in ranges.iter().enumerate() {
+        if ix > 0 {
+            text.push('\n');
+        }
+        for chunk in buffer.text_for_range(range.clone()) {
+            text.push_str(chunk);
+        }
+        if *is_last_line {
+            text.push('\n');
+        }
+    }
+    text
+}
+
+/// Where the text of a yank or delete goes, and how it's put.
+struct CopiedText {
+    clipboard_selections: Vec<ClipboardSelection>,
+    register: Option<char>,
+    kind: RegisterKind,
+    is_yank: bool,
+}
+
+impl CopiedText {
+    fn write(self, vim: &mut Vim, editor: &Editor, text: String, cx: &mut WindowContext) {
+        let pieces = register_pieces(&self.clipboard_selections);
+        let setting = VimSettings::get_global(cx).use_system_clipboard;
+        if setting == UseSystemClipboard::Always
+            || setting == UseSystemClipboard::OnYank && self.is_yank
+        {
+            cx.write_to_clipboard(
+                ClipboardItem::new(text.clone()).with_metadata(self.clipboard_selections),
+            );
+            vim.workspace_state.note_system_clipboard(&text);
+        } else if setting != UseSystemClipboard::Never {
+            // read once the command is done (rather than while it's typed), so that
+            // what's copied to the clipboard after it is put rather than the register
+            let clipboard = cx
+                .read_from_clipboard()
+                .map(|item| item.text().clone())
+                .unwrap_or_default();
+            vim.workspace_state.note_system_clipboard(&clipboard);
+        }
+        // a block is put as a whole, rather than a line of it at each cursor
+        let pieces = if self.kind == RegisterKind::Blockwise {
+            Vec::new()
+        } else {
+            pieces
+        };
+        vim.workspace_state
+            .write_register(self.register, text, self.kind, self.is_yank, pieces);
+        share_register(&vim.workspace_state, self.register, editor, cx);
+    }
+}
+
+/// After Zed's own copy or cut, puts the copied text in the registers as a yank or
+/// delete would, so `p` pastes it whatever the `use_system_clipboard` setting.
+pub fn register_native_copy(is_yank: bool, cx: &mut WindowContext) {
+    let Some(item) = cx.read_from_clipboard() else {
+        return;
+    };
+    // with no selection, zed copies whole lines
+    let linewise = item
+        .metadata::<Vec<ClipboardSelection>>()
+        .is_some_and(|selections| {
+            !selections.is_empty() && selections.iter().all(|selection| selection.is_entire_line)
+        });
+    let kind = if linewise {
+        RegisterKind::Linewise
+    } else {
+        RegisterKind::Charwise
+    };
+    Vim::update(cx, |vim, _| {
+        if !vim.enabled {
+            return;
+        }
+        let text = item.text().clone();
+        vim.workspace_state.note_system_clipboard(&text);
+        let register = vim.workspace_state.selected_register.take();
+        let pieces = item
+            .metadata::<Vec<ClipboardSelection>>()
+            .map(|selections| register_pieces(&selections))
+            .unwrap_or_default();
+        vim.workspace_state
+            .write_register(register, text, kind, is_yank, pieces);
+    })
+}
+
+fn register_pieces(selections: &[ClipboardSelection]) -> Vec<RegisterPiece> {
+    selections
+        .iter()
+        .map(|selection| RegisterPiece {
+            len: selection.len,
+            first_line_indent: selection.first_line_indent,
+        })
+        .collect()
+}
+
+/// The place in an editor's undo history that a command's edits start from, for
+/// commands whose edits are made by several actions (like an ex command on a range,
+/// `:g` or a macro). No transaction is kept open while they run, as the actions
+/// may undo (and `u` can't undo inside a transaction): `end_undo_group` merges the
+/// edits made since into one undo step afterwards, so that undoing it puts the
+/// cursor back where it was before the command. Groups can be nested, and the
+/// outermost one makes the undo step. In a multibuffer (like project search's), the
+/// step undoes the edits in each of its buffers.
+pub(crate) struct UndoGroup {
+    editor: WeakView<Editor>,
+    base: Option<TransactionId>,
+    selections: Arc<[Selection<Anchor>]>,
+}
+
+impl UndoGroup {
+    pub(crate) fn new(editor: &mut Editor, cx: &mut ViewContext<Editor>) -> Self {
+        editor.finalize_last_transaction(cx);
+        Self {
+            editor: cx.view().downgrade(),
+            base: undo_base(editor, cx),
+            selections: editor.selections.disjoint_anchors(),
+        }
+    }
+}
+
+/// The transaction on top of the editor's undo stack, which edits made after it can
+/// be merged from (see `merge_transactions_since`).
+pub(crate) fn undo_base(editor: &Editor, cx: &AppContext) -> Option<TransactionId> {
+    let multibuffer = editor.buffer().read(cx);
+    match multibuffer.as_singleton() {
+        Some(buffer) => buffer
+            .read(cx)
+            .peek_undo_stack()
+            .map(|entry| entry.transaction_id()),
+        None => multibuffer.undo_stack_transaction_ids().last().copied(),
+    }
+}
+
+/// The transactions made on top of the base (as `undo_base` gave it), oldest first,
+/// or `None` when the base itself was undone.
+pub(crate) fn transactions_since(
+    editor: &Editor,
+    base: Option<TransactionId>,
+    cx: &AppContext,
+) -> Option<Vec<TransactionId>> {
+    let multibuffer = editor.buffer().read(cx);
+    match multibuffer.as_singleton() {
+        Some(buffer) => {
+            let buffer = buffer.read(cx);
+            let parents = buffer
+                .undo_tree()
+                .into_iter()
+                .map(|node| (node.transaction_id, node.parent))
+                .collect::<HashMap<_, _>>();
+            let mut made = Vec::new();
+            let mut next = buffer.peek_undo_stack().map(|entry| entry.transaction_id());
+            while next != base {
+                let id = next?;
+                made.push(id);
+                next = parents.get(&id).copied().flatten();
+            }
+            made.reverse();
+            Some(made)
+        }
+        // a multibuffer's history has no branches
+        None => {
+            let mut made = multibuffer.undo_stack_transaction_ids();
+            if let Some(base) = base {
+                let start = made.iter().position(|id| *id == base)? + 1;
+                made.drain(..start);
+            }
+            Some(made)
+        }
+    }
+}
+
+/// Merges the transactions made on top of the base (as `undo_base` gave it) into the
+/// first of them, and returns it. Nothing is merged when the base itself was undone.
+pub(crate) fn merge_transactions_since(
+    editor: &mut Editor,
+    base: Option<TransactionId>,
+    cx: &mut ViewContext<Editor>,
+) -> Option<TransactionId> {
+    let made = transactions_since(editor, base, cx)?;
+    let (first, rest) = made.split_first()?;
+    match editor.buffer().read(cx).as_singleton() {
+        Some(buffer) => buffer.update(cx, |buffer, _| {
+            for id in rest {
+                buffer.merge_transactions(*id, *first);
+            }
+        }),
+        None => editor.buffer().update(cx, |multibuffer, cx| {
+            for id in rest {
+                multibuffer.merge_transactions(*id, *first, cx);
+            }
+        }),
+    }
+    Some(*first)
+}
+
+/// Starts grouping the edits made to the active editor into one undo step, until
+/// `end_undo_group` is called with the group it returns.
+pub(crate) fn start_undo_group(vim: &mut Vim, cx: &mut WindowContext) -> Option<UndoGroup> {
+    vim.update_active_editor(cx, |_, editor, cx| UndoGroup::new(editor, cx))
+}
+
+/// Merges the transactions made on top of the group's base into the first of them,
+/// which undoing then puts back the selections the group started with. Nothing is
+/// merged when the base itself was undone.
+pub(crate) fn end_undo_group(group: &UndoGroup, cx: &mut WindowContext) {
+    group
+        .editor
+        .update(cx, |editor, cx| {
+            if let Some(first) = merge_transactions_since(editor, group.base, cx) {
+                editor.set_transaction_selections(first, group.selections.clone());
+            }
+            editor.finalize_last_transaction(cx);
+        })
+        .ok();
+}
+
+/// How many screen columns the character takes at the column, like in vim: a tab
+/// reaches the next tab stop, wide characters (like those of East Asian scripts and
+/// emoji) take two and combining ones none.
+pub fn char_width(c: char, column: usize, tab_size: usize) -> usize {
+    match c {
+        '\t' => tab_size - column % tab_size,
+        c => c.width().unwrap_or(1),
+    }
+}
+
+/// How many screen columns the text takes, starting from the start of a line.
+pub fn screen_width(text: &str, tab_size: usize) -> usize {
+    text.chars()
+        .fold(0, |column, c| column + char_width(c, column, tab_size))
+}
+
 pub fn coerce_punctuation(kind: CharKind, treat_punctuation_as_word: bool) -> CharKind {
     if treat_punctuation_as_word && kind == CharKind::Punctuation {
         CharKind::Word
@@ -126,3 +397,146 @@ pub fn coerce_punctuation(kind: CharKind, treat_punctuation_as_word: bool) -> Ch
         kind
     }
 }
+
+/// The characters the `iskeyword` of each language's entry in the `languages`
+/// setting adds to its words, by language name. It's read from the settings where
+/// a motion or object starts, and passed down to `char_kind`.
+#[derive(Default)]
+pub struct KeywordChars(HashMap<String, Vec<char>>);
+
+impl KeywordChars {
+    pub fn new(cx: &AppContext) -> Self {
+        let chars = VimSettings::get_global(cx)
+            .languages
+            .iter()
+            .filter_map(|(name, options)| {
+                let chars = options.iskeyword.as_ref()?.chars().collect();
+                Some((name.clone(), chars))
+            })
+            .collect();
+        Self(chars)
+    }
+}
+
+/// `language::char_kind`, with the characters the language's `iskeyword` adds taken
+/// as word characters, so that `w`, `b`, `e`, `iw` and insert mode's `ctrl-w` treat
+/// them as part of a word.
+pub fn char_kind(scope: &Option<LanguageScope>, c: char, keyword_chars: &KeywordChars) -> CharKind {
+    let kind = language::char_kind(scope, c);
+    let Some(scope) = scope.as_ref().filter(|_| kind == CharKind::Punctuation) else {
+        return kind;
+    };
+    let is_keyword = keyword_chars
+        .0
+        .get(scope.language_name().as_ref())
+        .is_some_and(|chars| chars.contains(&c));
+    if is_keyword {
+        CharKind::Word
+    } else {
+        kind
+    }
+}
+
+/// The root of the worktree the buffer's file is in. Vim mode resolves relative
+/// paths from here, where vim would use the current directory.
+pub fn worktree_root(
+    buffer: &Model<Buffer>,
+    project: &Model<Project>,
+    cx: &AppContext,
+) -> Option<PathBuf> {
+    if let Some(root) = file_root(buffer.read(cx), cx) {
+        return Some(root);
+    }
+    let worktree = project.read(cx).visible_worktrees(cx).next()?;
+    let root = worktree.read(cx).abs_path().to_path_buf();
+    Some(root)
+}
+
+/// The absolute path of a file named in a command. A relative one is resolved from
+/// the worktree root of the active buffer, or of the project when there isn't one.
+pub fn command_abs_path(workspace: &Workspace, path: &str, cx: &AppContext) -> Option<PathBuf> {
+    let path = Path::new(path);
+    if path.is_absolute() {
+        return Some(path.to_path_buf());
+    }
+    let project = workspace.project();
+    let root = workspace
+        .active_item_as::<Editor>(cx)
+        .and_then(|editor| editor.read(cx).buffer().read(cx).as_singleton())
+        .and_then(|buffer| worktree_root(&buffer, project, cx))
+        .or_else(|| {
+            let worktree = project.read(cx).visible_worktrees(cx).next()?;
+            let root = worktree.read(cx).abs_path().to_path_buf();
+            Some(root)
+        })?;
+    Some(root.join(path))
+}
+
+/// The root of the worktree the buffer's file is in, if it has a file on disk.
+pub fn file_root(buffer: &Buffer, cx: &AppContext) -> Option<PathBuf> {
+    let file = buffer.file()?.as_local()?;
+    let mut root = file.abs_path(cx);
+    for _ in file.path().components() {
+        root.pop();
+    }
+    Some(root)
+}
+
+/// How long a shell command can run before it's killed.
+const COMMAND_TIMEOUT: Duration = Duration::from_secs(5 * 60);
+
+/// Runs a shell command with the text as its input, and returns its output. A command
+/// that exits with an error fails with the first line it wrote to stderr.
+pub async fn run_filter(command: &str, input: &str, root: Option<&Path>) -> anyhow::Result<String> {
+    let output = run_with_input(command, input, root).await?;
+    if !output.status.success() {
+        let error = String::from_utf8_lossy(&output.stderr);
+        anyhow::bail!(error
+            .lines()
+            .next()
+            .map(str::to_string)
+            .unwrap_or_else(|| format!("{command:?} failed ({})", output.status)));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+/// Runs a shell command in the root (when it's a directory) with the text as its
+/// input, and returns how it exited along with what it wrote. The command is killed
+/// when the future is dropped (as when the operation waiting on it is interrupted),
+/// or when it runs for longer than [`COMMAND_TIMEOUT`].
+pub async fn run_with_input(
+    command: &str,
+    input: &str,
+    root: Option<&Path>,
+) -> anyhow::Result<Output> {
+    let mut process = smol::process::Command::new("sh");
+    process
+        .arg("-c")
+        .arg(command)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true);
+    if let Some(root) = root.filter(|root| root.is_dir()) {
+        process.current_dir(root);
+    }
+    let mut child = process
+        .spawn()
+        .with_context(|| format!("failed to run {command:?}"))?;
+    // written while the output is read, so that a command that writes its output
+    // before it has read all of its input doesn't wait on a full pipe. One that exits
+    // without reading it all closes the pipe, which isn't an error.
+    let mut stdin = child.stdin.take().context("no stdin")?;
+    let write = async move {
+        stdin.write_all(input.as_bytes()).await.ok();
+    };
+    let run = async {
+        let ((), output) = futures::join!(write, child.output());
+        anyhow::Ok(output?)
+    };
+    let timeout = async {
+        smol::Timer::after(COMMAND_TIMEOUT).await;
+        Err(anyhow::anyhow!("{command:?} timed out"))
+    };
+    smol::future::or(run, timeout).await
+}
@@ -3,40 +3,96 @@
 #[cfg(test)]
 mod test;
 
+mod autocmd;
+mod buffers;
 mod command;
+mod command_line;
+mod command_line_window;
+mod completion;
+mod cquit;
 mod editor_events;
+mod file_name;
+mod filter;
+mod fixes;
+mod formatoptions;
+mod help;
+mod hlsearch;
+mod ime;
 mod insert;
+mod key_notation;
+mod list_picker;
+mod location_list;
+mod messages;
 mod mode_indicator;
+mod modifiable;
 mod motion;
+mod navigate;
 mod normal;
 mod object;
+mod occurrences;
+mod only;
+mod pane_number;
+mod preview;
+mod quickfix;
+mod references;
+mod rename;
+mod reset;
+mod ruler;
+mod shared_register;
+mod signs;
+mod spell;
 mod state;
+mod symbols;
+mod tags;
+mod tutor;
+mod undo_tree;
 mod utils;
 mod visual;
+mod write;
 
 use anyhow::Result;
+use autocmd::run_autocmds;
 use collections::HashMap;
-use command_palette_hooks::{CommandPaletteFilter, CommandPaletteInterceptor};
+use command_palette_hooks::{
+    CommandPaletteActionObserver, CommandPaletteFilter, CommandPaletteInterceptor,
+};
 use editor::{
     movement::{self, FindRange},
     Editor, EditorEvent, EditorMode,
 };
 use gpui::{
     actions, impl_actions, Action, AppContext, EntityId, Global, KeystrokeEvent, Subscription,
-    View, ViewContext, WeakView, WindowContext,
+    Task, View, ViewContext, WeakView, WindowContext,
 };
-use language::{CursorShape, Point, Selection, SelectionGoal};
+use key_notation::{key_notation, keystrokes_for_action};
+use language::{CursorShape, Language, Point, Selection, SelectionGoal};
 pub use mode_indicator::ModeIndicator;
-use motion::Motion;
-use normal::normal_replace;
+use modifiable::check_modifiable;
+use motion::{match_pairs, Motion};
+pub use normal::registers::set_register;
+use normal::{
+    fold::open_folds_at_cursors,
+    mark::{create_mark, jump_to_mark},
+    normal_replace,
+    repeat::{
+        is_completion_menu_action, record_macro_action, record_palette_action, record_register,
+        replay_register,
+    },
+    search::search_dismissed,
+};
+use object::Object;
+pub use ruler::Ruler;
 use schemars::JsonSchema;
 use serde::Deserialize;
 use serde_derive::Serialize;
 use settings::{update_settings_file, Settings, SettingsStore};
+pub use spell::{set_spell_checker, SpellChecker};
+pub use state::RegisterKind;
 use state::{EditorState, Mode, Operator, RecordedSelection, WorkspaceState};
-use std::{ops::Range, sync::Arc};
-use visual::{visual_block_motion, visual_replace};
-use workspace::{self, Workspace};
+use std::{ops::Range, rc::Rc, sync::Arc};
+use utils::register_native_copy;
+use visual::{visual_block_motion, visual_replace, visual_selection_ranges, RecordedChange};
+use workspace::{self, PaneSizing, Workspace};
 
 use crate::state::ReplayableAction;
 
@@ -78,6 +134,7 @@ pub fn init(cx: &mut AppContext) {
 
     cx.observe_keystrokes(observe_keystrokes).detach();
     editor_events::init(cx);
+    command_line::init(cx);
 
     cx.observe_new_views(|workspace: &mut Workspace, cx| register(workspace, cx))
         .detach();
@@ -101,11 +158,22 @@ pub fn init(cx: &mut AppContext) {
 
 fn register(workspace: &mut Workspace, cx: &mut ViewContext<Workspace>) {
     workspace.register_action(|_: &mut Workspace, &SwitchMode(mode): &SwitchMode, cx| {
-        Vim::update(cx, |vim, cx| vim.switch_mode(mode, false, cx))
+        Vim::update(cx, |vim, cx| {
+            // escape abandons the register along with any count and operator
+            vim.clear_operator(cx);
+            vim.switch_mode(mode, false, cx)
+        })
     });
     workspace.register_action(
         |_: &mut Workspace, &PushOperator(operator): &PushOperator, cx| {
-            Vim::update(cx, |vim, cx| vim.push_operator(operator, cx))
+            Vim::update(cx, |vim, cx| {
+                // like vim, a register can only be given before an operator, so d"a is abandoned
+                if operator == Operator::Register && vim.active_operator().is_some() {
+                    vim.clear_operator(cx)
+                } else {
+                    vim.push_operator(operator, cx)
+                }
+            })
         },
     );
     workspace.register_action(|_: &mut Workspace, n: &Number, cx: _| {
@@ -128,34 +196,106 @@ fn register(workspace: &mut Workspace, cx: &mut ViewContext<Workspace>) {
         })
     });
 
+    sync_pane_sizing(workspace, cx);
+    cx.observe_global::<SettingsStore>(sync_pane_sizing)
+        .detach();
+
     normal::register(workspace, cx);
     insert::register(workspace, cx);
     motion::register(workspace, cx);
+    buffers::register(workspace, cx);
     command::register(workspace, cx);
+    command_line_window::register(workspace, cx);
+    completion::register(workspace, cx);
+    cquit::register(workspace, cx);
+    file_name::register(workspace, cx);
+    filter::register(workspace, cx);
+    fixes::register(workspace, cx);
+    help::register(workspace, cx);
+    hlsearch::register(workspace, cx);
     object::register(workspace, cx);
+    location_list::register(workspace, cx);
+    messages::register(workspace, cx);
+    modifiable::register(workspace, cx);
+    navigate::register(workspace, cx);
+    occurrences::register(workspace, cx);
+    only::register(workspace, cx);
+    pane_number::register(workspace, cx);
+    preview::register(workspace, cx);
+    quickfix::register(workspace, cx);
+    references::register(workspace, cx);
+    rename::register(workspace, cx);
+    reset::register(workspace, cx);
+    shared_register::register(workspace, cx);
+    spell::register(workspace, cx);
+    symbols::register(workspace, cx);
+    tags::register(workspace, cx);
+    tutor::register(workspace, cx);
+    undo_tree::register(workspace, cx);
     visual::register(workspace, cx);
+    write::register(workspace, cx);
+}
+
+/// Like vim's `equalalways`, splitting or closing a pane resizes all of them to be
+/// equal, unless it's turned off, in which case only the pane being split or closed
+/// gives up or takes its space.
+fn sync_pane_sizing(workspace: &mut Workspace, cx: &mut ViewContext<Workspace>) {
+    let sizing = if !VimModeSetting::get_global(cx).0 {
+        PaneSizing::default()
+    } else if VimSettings::get_global(cx).equalalways {
+        PaneSizing::EqualizeAll
+    } else {
+        PaneSizing::Preserve
+    };
+    workspace.set_pane_sizing(sizing);
 }
 
 /// Called whenever an keystroke is typed so vim can observe all actions
 /// and keystrokes accordingly.
 fn observe_keystrokes(keystroke_event: &KeystrokeEvent, cx: &mut WindowContext) {
+    reset::check_state(cx);
+    Vim::update(cx, |vim, cx| {
+        if vim.workspace_state.message_seen {
+            vim.workspace_state.message = None;
+        }
+        vim.workspace_state.message_seen = vim.workspace_state.message.is_some();
+
+        if vim.workspace_state.recording_register.is_some() && !vim.is_replaying() {
+            let keystrokes = match &keystroke_event.action {
+                Some(action) => keystrokes_for_action(&**action, &keystroke_event.keystroke, cx),
+                None => vec![keystroke_event.keystroke.clone()],
+            };
+            vim.workspace_state
+                .recorded_keys
+                .push_str(&key_notation(&keystrokes));
+        }
+    });
+
     if let Some(action) = keystroke_event
         .action
         .as_ref()
         .map(|action| action.boxed_clone())
     {
-        Vim::update(cx, |vim, _| {
-            if vim.workspace_state.recording {
-                vim.workspace_state
-                    .recorded_actions
-                    .push(ReplayableAction::Action(action.boxed_clone()));
-
-                if vim.workspace_state.stop_recording_after_next_action {
-                    vim.workspace_state.recording = false;
-                    vim.workspace_state.stop_recording_after_next_action = false;
-                }
+        Vim::update(cx, |vim, cx| {
+            if vim.workspace_state.recording_register.is_some() && !vim.is_replaying() {
+                record_macro_action(vim, &*action, cx);
+            }
+            if !is_completion_menu_action(&*action, vim.state().mode) {
+                vim.observe_action(action.boxed_clone());
             }
         });
+        if action.as_any().is::<search::buffer_search::Dismiss>() {
+            search_dismissed(cx);
+            hlsearch::search_bar_dismissed(cx);
+        } else if action.as_any().is::<search::SelectNextMatch>()
+            || action.as_any().is::<search::SelectPrevMatch>()
+        {
+            hlsearch::search_repeated(cx);
+        } else if action.as_any().is::<editor::actions::Copy>() {
+            register_native_copy(true, cx);
+        } else if action.as_any().is::<editor::actions::Cut>() {
+            register_native_copy(false, cx);
+        }
 
         // Keystroke is handled by the vim system, so continue forward
         if action.name().starts_with("vim::") {
@@ -166,11 +306,31 @@ fn observe_keystrokes(keystroke_event: &KeystrokeEvent, cx: &mut WindowContext)
     }
 
     Vim::update(cx, |vim, cx| match vim.active_operator() {
-        Some(Operator::FindForward { .. } | Operator::FindBackward { .. } | Operator::Replace) => {}
+        Some(
+            Operator::FindForward { .. }
+            | Operator::FindBackward { .. }
+            | Operator::Sneak { .. }
+            | Operator::SneakBackward { .. }
+            | Operator::Replace
+            | Operator::RecordRegister
+            | Operator::ReplayRegister
+            | Operator::Register
+            | Operator::Mark
+            | Operator::Jump { .. },
+        ) => {}
         Some(_) => {
             vim.clear_operator(cx);
         }
-        _ => {}
+        // a count or register on its own is abandoned by escape (or by any other
+        // key that isn't a vim command), except in insert mode where `3i` keeps
+        // its count until the insertion ends
+        None if vim.state().mode != Mode::Insert
+            && (vim.state().pre_count.is_some()
+                || vim.workspace_state.selected_register.is_some()) =>
+        {
+            vim.clear_operator(cx);
+        }
+        None => {}
     });
 }
 
@@ -179,10 +339,18 @@ fn observe_keystrokes(keystroke_event: &KeystrokeEvent, cx: &mut WindowContext)
 struct Vim {
     active_editor: Option<WeakView<Editor>>,
     editor_subscription: Option<Subscription>,
+    /// Watches the language of the active editor's buffer
+    buffer_subscription: Option<Subscription>,
     enabled: bool,
     editor_states: HashMap<EntityId, EditorState>,
     workspace_state: WorkspaceState,
     default_state: EditorState,
+    /// An operation running in the background (like `:%s` on a very large
+    /// buffer), which is abandoned when this is dropped
+    pending_operation: Option<Task<()>>,
+    /// The change a `VisualAction` recorded for `.`, set aside until the action's
+    /// edits land, and dropped when another change is recorded first
+    pending_visual_change: Option<(RecordedChange, Subscription)>,
 }
 
 impl Global for Vim {}
@@ -204,14 +372,26 @@ impl Vim {
             return;
         }
 
+        // the recording sign is only shown in the active editor
+        if let Some(previous) = self
+            .active_editor
+            .take()
+            .and_then(|editor| editor.upgrade())
+        {
+            if previous != editor {
+                previous.update(cx, |editor, cx| signs::clear_recording_sign(editor, cx));
+            }
+        }
         self.active_editor = Some(editor.clone().downgrade());
+        modifiable::sync_read_only(self, &editor, cx);
         self.editor_subscription = Some(cx.subscribe(&editor, |editor, event, cx| match event {
             EditorEvent::SelectionsChanged { local: true } => {
                 let editor = editor.read(cx);
                 if editor.leader_peer_id().is_none() {
                     let newest = editor.selections.newest::<usize>(cx);
                     let is_multicursor = editor.selections.count() > 1;
-                    local_selections_changed(newest, is_multicursor, cx);
+                    let in_snippet = editor.has_active_snippet();
+                    local_selections_changed(newest, is_multicursor, in_snippet, cx);
                 }
             }
             EditorEvent::InputIgnored { text } => {
@@ -221,13 +401,27 @@ impl Vim {
             EditorEvent::InputHandled {
                 text,
                 utf16_range_to_replace: range_to_replace,
-            } => Vim::record_insertion(text, range_to_replace.clone(), cx),
+            } => {
+                Vim::record_insertion(text, range_to_replace.clone(), cx);
+                formatoptions::wrap_on_input(text, cx);
+            }
             _ => {}
         }));
+        // a buffer's language can change after it's opened (when it's saved with
+        // another extension, say), and the options of the `languages` setting with it
+        let buffer = editor.read(cx).buffer().read(cx).as_singleton();
+        self.buffer_subscription = buffer.map(|buffer| {
+            cx.subscribe(&buffer, |_, event, cx| {
+                if let language::Event::LanguageChanged = event {
+                    Vim::update(cx, |vim, cx| vim.sync_vim_settings(cx));
+                }
+            })
+        });
 
         let editor = editor.read(cx);
         let editor_mode = editor.mode();
         let newest_selection_empty = editor.selections.newest::<usize>(cx).is_empty();
+        let workspace = editor.workspace();
 
         if editor_mode == EditorMode::Full
                 && !newest_selection_empty
@@ -238,6 +432,14 @@ impl Vim {
             self.switch_mode(Mode::Visual, true, cx);
         }
 
+        // the editor may be in a pane that was just opened, or now show another buffer
+        if let Some(workspace) = workspace.filter(|_| self.workspace_state.hlsearch.is_some()) {
+            cx.defer(move |cx| {
+                let panes = workspace.read(cx).panes().to_vec();
+                hlsearch::sync_highlights(&panes, cx);
+            });
+        }
+
         self.sync_vim_settings(cx);
     }
 
@@ -247,6 +449,17 @@ impl Vim {
         cx: &mut WindowContext,
     ) {
         Vim::update(cx, |vim, _| {
+            if vim.workspace_state.ignore_current_insertion {
+                vim.workspace_state.ignore_current_insertion = false;
+            } else if vim.workspace_state.recording_register.is_some() && !vim.is_replaying() {
+                vim.workspace_state
+                    .recorded_macro
+                    .push(ReplayableAction::Insertion {
+                        text: text.clone(),
+                        utf16_range_to_replace: range_to_replace.clone(),
+                    });
+            }
+
             if vim.workspace_state.recording {
                 vim.workspace_state
                     .recorded_actions
@@ -262,6 +475,94 @@ impl Vim {
         });
     }
 
+    /// Records an action for `.`, if the current command is being recorded.
+    fn observe_action(&mut self, action: Box<dyn Action>) {
+        if self.workspace_state.recording {
+            self.workspace_state
+                .recorded_actions
+                .push(ReplayableAction::Action(action));
+
+            if self.workspace_state.stop_recording_after_next_action {
+                self.workspace_state.recording = false;
+                self.workspace_state.stop_recording_after_next_action = false;
+            }
+        }
+    }
+
+    /// Whether a macro, `.` or `:g` is being replayed. Replayed actions
+    /// are not recorded into macros.
+    fn is_replaying(&self) -> bool {
+        self.workspace_state
+            .replayer
+            .as_ref()
+            .is_some_and(|replayer| replayer.is_running())
+    }
+
+    /// Shows a message in the status bar, until the next keystroke.
+    fn show_message(&mut self, message: impl Into<String>) {
+        let message = message.into();
+        self.workspace_state.record_message(message.clone());
+        self.workspace_state.message = Some(message);
+        self.workspace_state.message_seen = false;
+    }
+
+    /// Shows how far along an operation is, without keeping it in the message
+    /// history.
+    fn show_progress(&mut self, message: impl Into<String>) {
+        self.workspace_state.message = Some(message.into());
+        self.workspace_state.message_seen = false;
+    }
+
+    /// Called by an operation running in the background once it's over.
+    fn finish_pending_operation(&mut self) {
+        if let Some(task) = self.pending_operation.take() {
+            task.detach();
+        }
+        self.workspace_state.message = None;
+    }
+
+    /// Whether an operation is already running in the background, in which case
+    /// another can't be started until it's over or interrupted.
+    fn refuse_pending_operation(&mut self) -> bool {
+        if self.pending_operation.is_none() {
+            return false;
+        }
+        self.show_message("Another operation is still running (ctrl-c interrupts it)");
+        true
+    }
+
+    /// Abandons the operation running in the background, if there is one, for
+    /// `ctrl-c` and `escape`.
+    fn cancel_pending_operation(&mut self) -> bool {
+        if self.pending_operation.take().is_none() {
+            return false;
+        }
+        self.show_message("Interrupted");
+        true
+    }
+
+    /// Stops the macro (or `:g` command) being replayed when `ctrl-c` is pressed
+    /// while it's waiting between steps.
+    fn interrupt_replay(&mut self) -> bool {
+        let interrupted = self
+            .workspace_state
+            .replayer
+            .as_ref()
+            .is_some_and(|replayer| replayer.interrupt());
+        if interrupted {
+            self.show_message("Interrupted");
+        }
+        interrupted
+    }
+
+    /// Stops the running macro (or `:g` command). Called when a command
+    /// fails, for example when `j` is used on the last line.
+    fn abort_replay(&mut self) {
+        if let Some(replayer) = self.workspace_state.replayer.as_ref() {
+            replayer.abort()
+        }
+    }
+
     fn update_active_editor<S>(
         &mut self,
         cx: &mut WindowContext,
@@ -274,10 +575,14 @@ impl Vim {
     /// When doing an action that modifies the buffer, we start recording so that `.`
     /// will replay the action.
     pub fn start_recording(&mut self, cx: &mut WindowContext) {
+        // the edits are ignored by a read-only editor, which this explains
+        check_modifiable(self, cx);
         if !self.workspace_state.replaying {
+            self.pending_visual_change = None;
             self.workspace_state.recording = true;
             self.workspace_state.recorded_actions = Default::default();
             self.workspace_state.recorded_count = None;
+            self.workspace_state.recorded_register = self.workspace_state.selected_register;
 
             let selections = self
                 .active_editor
@@ -347,6 +652,12 @@ impl Vim {
     }
 
     fn switch_mode(&mut self, mode: Mode, leave_selections: bool, cx: &mut WindowContext) {
+        // a read-only editor can't be typed in
+        let mode = if mode == Mode::Insert && !check_modifiable(self, cx) {
+            Mode::Normal
+        } else {
+            mode
+        };
         let state = self.state();
         let last_mode = state.mode;
         let prior_mode = state.last_mode;
@@ -355,9 +666,30 @@ impl Vim {
             state.mode = mode;
             state.operator_stack.clear();
         });
+        if mode != last_mode {
+            if mode == Mode::Insert {
+                let undo_base = self
+                    .update_active_editor(cx, |_, editor, cx| utils::undo_base(editor, cx))
+                    .flatten();
+                self.update_state(|state| state.insert_undo_base = undo_base);
+                open_folds_at_cursors(self, FoldOpen::Insert, cx);
+                run_autocmds(self, AutocmdEvent::InsertEnter, cx);
+            } else if last_mode == Mode::Insert {
+                run_autocmds(self, AutocmdEvent::InsertLeave, cx);
+            }
+            if mode == Mode::Normal {
+                run_autocmds(self, AutocmdEvent::NormalEnter, cx);
+            }
+        }
         if mode != Mode::Insert {
             self.take_count(cx);
         }
+        if last_mode.is_visual() && !mode.is_visual() {
+            let selection = self
+                .update_active_editor(cx, |_, editor, cx| visual_selection_ranges(editor, cx))
+                .unwrap_or_default();
+            self.update_state(|state| state.last_visual_selection = selection);
+        }
 
         // Sync editor settings like clip mode
         self.sync_vim_settings(cx);
@@ -392,7 +724,7 @@ impl Vim {
                 }
 
                 s.move_with(|map, selection| {
-                    if last_mode.is_visual() && !mode.is_visual() {
+                    if (last_mode.is_visual() || last_mode == Mode::Select) && !mode.is_visual() {
                         let mut point = selection.head();
                         if !selection.reversed && !selection.is_empty() {
                             point = movement::left(map, selection.head());
@@ -427,11 +759,16 @@ impl Vim {
             return self.workspace_state.recorded_count;
         }
 
-        let count = if self.state().post_count == None && self.state().pre_count == None {
+        let count = if self.state().post_count == None
+            && self.state().pre_count == None
+            && self.state().register_count == None
+        {
             return None;
         } else {
             Some(self.update_state(|state| {
-                state.post_count.take().unwrap_or(1) * state.pre_count.take().unwrap_or(1)
+                state.post_count.take().unwrap_or(1)
+                    * state.pre_count.take().unwrap_or(1)
+                    * state.register_count.take().unwrap_or(1)
             }))
         };
         if self.workspace_state.recording {
@@ -442,9 +779,21 @@ impl Vim {
     }
 
     fn push_operator(&mut self, operator: Operator, cx: &mut WindowContext) {
+        if operator.changes_text() && !check_modifiable(self, cx) {
+            self.clear_operator(cx);
+            return;
+        }
         if matches!(
             operator,
-            Operator::Change | Operator::Delete | Operator::Replace
+            Operator::Change
+                | Operator::Delete
+                | Operator::Replace
+                | Operator::Rewrap
+                | Operator::AutoIndent
+                | Operator::Uppercase
+                | Operator::Lowercase
+                | Operator::OppositeCase
+                | Operator::Custom
         ) {
             self.start_recording(cx)
         };
@@ -464,6 +813,7 @@ impl Vim {
     }
     fn clear_operator(&mut self, cx: &mut WindowContext) {
         self.take_count(cx);
+        self.workspace_state.selected_register.take();
         self.update_state(|state| state.operator_stack.clear());
         self.sync_vim_settings(cx);
     }
@@ -472,6 +822,29 @@ impl Vim {
         self.state().operator_stack.last().copied()
     }
 
+    /// The command typed so far, like `2"a3d`, which vim shows when `showcmd` is on.
+    fn pending_command(&self) -> String {
+        let state = self.state();
+        let mut command = String::new();
+        if let Some(count) = state.register_count {
+            command.push_str(&count.to_string());
+        }
+        if let Some(register) = self.workspace_state.selected_register {
+            command.push('"');
+            command.push(register);
+        }
+        if let Some(count) = state.pre_count {
+            command.push_str(&count.to_string());
+        }
+        for operator in &state.operator_stack {
+            command.push_str(operator.keys());
+        }
+        if let Some(count) = state.post_count {
+            command.push_str(&count.to_string());
+        }
+        command
+    }
+
     fn active_editor_input_ignored(text: Arc<str>, cx: &mut WindowContext) {
         if text.is_empty() {
             return;
@@ -489,9 +862,6 @@ impl Vim {
                     },
                     smartcase: VimSettings::get_global(cx).use_smartcase_find,
                 };
-                Vim::update(cx, |vim, _| {
-                    vim.workspace_state.last_find = Some(find.clone())
-                });
                 motion::motion(find, cx)
             }
             Some(Operator::FindBackward { after }) => {
@@ -505,11 +875,74 @@ impl Vim {
                     },
                     smartcase: VimSettings::get_global(cx).use_smartcase_find,
                 };
-                Vim::update(cx, |vim, _| {
-                    vim.workspace_state.last_find = Some(find.clone())
-                });
                 motion::motion(find, cx)
             }
+            Some(Operator::Sneak { first_char: None }) => Vim::update(cx, |vim, cx| {
+                vim.pop_operator(cx);
+                vim.push_operator(
+                    Operator::Sneak {
+                        first_char: text.chars().next(),
+                    },
+                    cx,
+                )
+            }),
+            Some(Operator::SneakBackward { first_char: None }) => Vim::update(cx, |vim, cx| {
+                vim.pop_operator(cx);
+                vim.push_operator(
+                    Operator::SneakBackward {
+                        first_char: text.chars().next(),
+                    },
+                    cx,
+                )
+            }),
+            Some(Operator::Sneak {
+                first_char: Some(first_char),
+            }) => {
+                let sneak = Motion::Sneak {
+                    first_char,
+                    second_char: text.chars().next().unwrap(),
+                    smartcase: VimSettings::get_global(cx).use_smartcase_find,
+                };
+                motion::motion(sneak, cx)
+            }
+            Some(Operator::SneakBackward {
+                first_char: Some(first_char),
+            }) => {
+                let sneak = Motion::SneakBackward {
+                    first_char,
+                    second_char: text.chars().next().unwrap(),
+                    smartcase: VimSettings::get_global(cx).use_smartcase_find,
+                };
+                motion::motion(sneak, cx)
+            }
+            Some(Operator::RecordRegister) => record_register(text.chars().next().unwrap(), cx),
+            Some(Operator::ReplayRegister) => replay_register(text.chars().next().unwrap(), cx),
+            Some(Operator::Register) => Vim::update(cx, |vim, cx| {
+                vim.pop_operator(cx);
+                let register = text.chars().next().unwrap();
+                if register.is_ascii_alphanumeric() || matches!(register, '"' | '-' | '_') {
+                    vim.workspace_state.selected_register = Some(register);
+                    // a count after the register starts again, and is multiplied by this one
+                    vim.update_state(|state| {
+                        if let Some(count) = state.pre_count.take() {
+                            state.register_count = Some(state.register_count.unwrap_or(1) * count);
+                        }
+                    });
+                } else {
+                    vim.clear_operator(cx);
+                }
+            }),
+            Some(Operator::Mark) => Vim::update(cx, |vim, cx| create_mark(vim, text, cx)),
+            Some(Operator::Jump { line }) => jump_to_mark(text, line, cx),
+            Some(Operator::Object { .. }) => {
+                let c = text.chars().next().unwrap();
+                if let Some(&(open, close)) = match_pairs(cx)
+                    .iter()
+                    .find(|(open, close)| c == *open || c == *close)
+                {
+                    object::object(Object::MatchPair { open, close }, cx)
+                }
+            }
             Some(Operator::Replace) => match Vim::read(cx).state().mode {
                 Mode::Normal => normal_replace(text, cx),
                 Mode::Visual | Mode::VisualLine | Mode::VisualBlock => visual_replace(text, cx),
@@ -525,6 +958,7 @@ impl Vim {
         }
         if !enabled {
             let _ = cx.remove_global::<CommandPaletteInterceptor>();
+            let _ = cx.remove_global::<CommandPaletteActionObserver>();
             cx.update_global::<CommandPaletteFilter, _>(|filter, _| {
                 filter.hidden_namespaces.insert("vim");
             });
@@ -539,6 +973,7 @@ impl Vim {
         cx.set_global::<CommandPaletteInterceptor>(CommandPaletteInterceptor(Box::new(
             command::command_interceptor,
         )));
+        cx.set_global(CommandPaletteActionObserver(Rc::new(record_palette_action)));
 
         if let Some(active_window) = cx
             .active_window()
@@ -586,8 +1021,14 @@ impl Vim {
             editor.set_clip_at_line_ends(state.clip_at_line_ends(), cx);
             editor.set_collapse_matches(true);
             editor.set_input_enabled(!state.vim_controlled());
+            editor.set_ime_composition_enabled(ime::ime_composition_enabled(state.mode, cx), cx);
             editor.set_autoindent(state.should_autoindent());
             editor.selections.line_mode = matches!(state.mode, Mode::VisualLine);
+            formatoptions::sync_continue_on_enter(editor, cx);
+            editor.set_discard_untitled_changes(
+                VimSettings::get_global(cx).scratch_buffers == ScratchBuffers::Discard,
+            );
+            signs::sync_signs(vim, editor, cx);
             if editor.is_focused(cx) {
                 editor.set_keymap_context_layer::<Self>(state.keymap_context_layer(), cx);
             } else {
@@ -602,9 +1043,13 @@ impl Vim {
             editor.set_clip_at_line_ends(false, cx);
             editor.set_collapse_matches(false);
             editor.set_input_enabled(true);
+            editor.set_ime_composition_enabled(true, cx);
             editor.set_autoindent(true);
             editor.selections.line_mode = false;
+            editor.set_extend_comment_on_newline(None);
+            editor.set_discard_untitled_changes(false);
         }
+        signs::clear_signs(editor, cx);
         editor.remove_keymap_context_layer::<Self>(cx)
     }
 }
@@ -637,6 +1082,222 @@ pub enum UseSystemClipboard {
     OnYank,
 }
 
+/// The language rules for changing case.
+#[derive(Copy, Clone, Debug, Default, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum CaseLocale {
+    /// Unicode's default case mapping.
+    #[default]
+    Default,
+    /// Turkish and Azerbaijani, where the uppercase of i is İ, and of ı is I.
+    Turkish,
+}
+
+/// What `ctrl-z` and `:stop` do, since the editor can't be suspended like terminal vim.
+#[derive(Copy, Clone, Debug, Default, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SuspendBehavior {
+    /// Minimize the window.
+    #[default]
+    Minimize,
+    /// Hide the application.
+    Hide,
+}
+
+/// Whether closing a buffer that has no file yet (like one opened with `:new`) asks
+/// to save it, like vim's `bufhidden` and `buftype` for scratch buffers.
+#[derive(Copy, Clone, Debug, Default, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ScratchBuffers {
+    /// Ask to save it when it has changes, like any other buffer.
+    #[default]
+    Prompt,
+    /// Discard its changes without asking, and never show it as modified.
+    Discard,
+}
+
+/// The events the `autocmds` setting runs actions on, like vim's autocommand events.
+#[derive(Copy, Clone, Debug, Serialize, Deserialize, PartialEq, Eq, Hash, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum AutocmdEvent {
+    /// Entering insert mode, like vim's `InsertEnter`.
+    InsertEnter,
+    /// Leaving insert mode, like vim's `InsertLeave`.
+    InsertLeave,
+    /// Switching to normal mode from any other mode.
+    NormalEnter,
+    /// Writing the buffer with `:w` (or `:update` or `:wall`), before it's saved,
+    /// like vim's `BufWritePre`.
+    BufWrite,
+}
+
+/// What tab does in normal mode, outside of a snippet.
+#[derive(Copy, Clone, Debug, Default, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum NormalModeTab {
+    /// Go forward through the jump list, like vim, where tab is the same key as
+    /// `ctrl-i` (as it is in a terminal).
+    #[default]
+    JumpForward,
+    /// Indent the line like `>>`, with shift-tab outdenting it like `<<`.
+    Indent,
+}
+
+/// Which motion `;` and `,` repeat when both a find (`f`, `t`, `F` or `T`) and a
+/// sneak have been made.
+#[derive(Copy, Clone, Debug, Default, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum RepeatMotion {
+    /// Whichever was made last.
+    #[default]
+    Last,
+    /// The last find, and a sneak only if there hasn't been a find.
+    Find,
+    /// The last sneak, and a find only if there hasn't been a sneak.
+    Sneak,
+}
+
+/// What `ctrl-c` does outside of the terminal.
+#[derive(Copy, Clone, Debug, Default, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum CtrlCBehavior {
+    /// Interrupt, like vim: leave insert, visual or select mode, and abandon any
+    /// pending count or operator.
+    #[default]
+    Escape,
+    /// Copy the selection, like in other editors.
+    Copy,
+}
+
+/// How registers yanked or deleted into with multiple cursors are put.
+#[derive(Copy, Clone, Debug, Default, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum MulticursorRegisters {
+    /// Each cursor puts the text yanked at the matching cursor, like Zed's paste.
+    #[default]
+    PerCursor,
+    /// Each cursor puts all of the text, joined by newlines.
+    Shared,
+}
+
+/// The kinds of commands that open a closed fold the cursor ends up in, like vim's
+/// `foldopen` option. (Searches always open the fold their match is in.)
+#[derive(Copy, Clone, Debug, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum FoldOpen {
+    /// Every motion, and entering insert mode.
+    All,
+    /// Paragraph and excerpt motions, like `{` and `]]`.
+    Block,
+    /// Horizontal motions, like `l`, `w` and `fx`.
+    Hor,
+    /// Entering insert mode.
+    Insert,
+    /// Far jumps, like `G` and `gg`.
+    Jump,
+    /// Jumping to a mark.
+    Mark,
+    /// `%`
+    Percent,
+}
+
+/// What `escape` in normal mode clears, one at a time.
+#[derive(Copy, Clone, Debug, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum EscapeLayer {
+    /// Hovers, completions, code actions and the other popups over the editor.
+    Popups,
+    /// The highlights of the last search.
+    Highlights,
+    /// The cursors besides the first (or the selection, made with the mouse).
+    Cursors,
+}
+
+/// When soft wrap is on, whether `j`, `k`, `0`, `^` and `$` act on display lines
+/// (like `gj`, `gk`, `g0`, `g^` and `g$`) rather than buffer lines.
+#[derive(Copy, Clone, Debug, Default, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
+#[serde(default)]
+pub struct DisplayLines {
+    /// When moving the cursor, or the selection in visual mode.
+    pub motions: bool,
+    /// After an operator, like `d$`. This also makes `dd`, `yy` and `cc` act on the
+    /// display line, unless the line isn't wrapped.
+    pub operators: bool,
+}
+
+/// Options that a language (by its name) sets in place of the global ones.
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
+#[serde(default)]
+pub struct LanguageOptions {
+    /// Replaces `formatoptions`.
+    pub formatoptions: Option<String>,
+    /// Replaces `textwidth`.
+    pub textwidth: Option<u32>,
+    /// Added to `toggles`, ahead of them.
+    pub toggles: Option<Vec<Vec<String>>>,
+    /// Replaces `shiftwidth`.
+    pub shiftwidth: Option<u32>,
+    /// Characters that are part of keywords in the language, besides letters,
+    /// digits and `_`, like vim's `iskeyword` (as in `"-"` for CSS).
+    pub iskeyword: Option<String>,
+}
+
+/// In which modes an input method (as for Chinese or Japanese) can compose text.
+/// Where it can't, the text it's composing isn't shown, so the keys run commands,
+/// and only the text it commits is typed.
+#[derive(Copy, Clone, Debug, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
+#[serde(default)]
+pub struct ImeSettings {
+    /// In normal mode.
+    pub normal: bool,
+    /// In visual mode.
+    pub visual: bool,
+    /// In insert mode (and in select mode, where typing replaces the selection).
+    pub insert: bool,
+}
+
+impl Default for ImeSettings {
+    fn default() -> Self {
+        Self {
+            normal: false,
+            visual: false,
+            insert: true,
+        }
+    }
+}
+
+/// Whether the bracket commands wrap around the end, or stop there.
+#[derive(Copy, Clone, Debug, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
+#[serde(default)]
+pub struct BracketWrap {
+    /// For the things `]d`, `]c`, `]m` and `]s` go to in the file.
+    pub in_file: bool,
+    /// For the entries of the quickfix and location lists (`]q` and `]l`).
+    pub lists: bool,
+}
+
+impl Default for BracketWrap {
+    fn default() -> Self {
+        Self {
+            in_file: true,
+            lists: false,
+        }
+    }
+}
+
+/// The kinds of number `ctrl-a` and `ctrl-x` recognize, besides decimal ones (like
+/// vim's `nrformats`).
+#[derive(Copy, Clone, Debug, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum NumberFormat {
+    /// Numbers that start with `0b`.
+    Bin,
+    /// Numbers that start with `0x`.
+    Hex,
+    /// Numbers that start with a `0`, which are otherwise decimal with leading zeros.
+    Octal,
+}
+
 #[derive(Deserialize)]
 struct VimSettings {
     // all vim uses vim clipboard
@@ -645,6 +1306,40 @@ struct VimSettings {
     pub use_system_clipboard: UseSystemClipboard,
     pub use_multiline_find: bool,
     pub use_smartcase_find: bool,
+    pub fold_level: Option<u32>,
+    pub shared_register: Option<char>,
+    pub case_locale: CaseLocale,
+    pub ruler: bool,
+    pub suspend: SuspendBehavior,
+    pub scratch_buffers: ScratchBuffers,
+    pub show_marks_in_gutter: bool,
+    pub equalalways: bool,
+    pub display_lines: DisplayLines,
+    pub nrformats: Vec<NumberFormat>,
+    pub toggles: Vec<Vec<String>>,
+    pub makeprg: String,
+    pub errorformat: Vec<String>,
+    pub keywordprg: HashMap<String, String>,
+    pub formatprg: HashMap<String, String>,
+    pub formatoptions: Option<String>,
+    pub textwidth: u32,
+    pub shiftwidth: u32,
+    pub languages: HashMap<String, LanguageOptions>,
+    pub joinspaces: bool,
+    pub ctrl_c: CtrlCBehavior,
+    pub autocmds: HashMap<AutocmdEvent, Vec<String>>,
+    pub ime: ImeSettings,
+    pub matchpairs: HashMap<String, Vec<String>>,
+    pub search_in_selection: bool,
+    pub bracket_wrap: BracketWrap,
+    pub operators: HashMap<String, String>,
+    pub escape: Vec<EscapeLayer>,
+    pub multicursor_registers: MulticursorRegisters,
+    pub foldopen: Vec<FoldOpen>,
+    pub whichwrap: String,
+    pub hidden: bool,
+    pub normal_mode_tab: NormalModeTab,
+    pub repeat_motion: RepeatMotion,
 }
 
 #[derive(Clone, Default, Serialize, Deserialize, JsonSchema)]
@@ -652,6 +1347,50 @@ struct VimSettingsContent {
     pub use_system_clipboard: Option<UseSystemClipboard>,
     pub use_multiline_find: Option<bool>,
     pub use_smartcase_find: Option<bool>,
+    pub fold_level: Option<u32>,
+    pub shared_register: Option<char>,
+    pub case_locale: Option<CaseLocale>,
+    pub ruler: Option<bool>,
+    pub suspend: Option<SuspendBehavior>,
+    pub scratch_buffers: Option<ScratchBuffers>,
+    pub show_marks_in_gutter: Option<bool>,
+    pub equalalways: Option<bool>,
+    pub display_lines: Option<DisplayLines>,
+    pub nrformats: Option<Vec<NumberFormat>>,
+    pub toggles: Option<Vec<Vec<String>>>,
+    pub makeprg: Option<String>,
+    pub errorformat: Option<Vec<String>>,
+    pub keywordprg: Option<HashMap<String, String>>,
+    pub formatprg: Option<HashMap<String, String>>,
+    pub formatoptions: Option<String>,
+    pub textwidth: Option<u32>,
+    pub shiftwidth: Option<u32>,
+    pub languages: Option<HashMap<String, LanguageOptions>>,
+    pub joinspaces: Option<bool>,
+    pub ctrl_c: Option<CtrlCBehavior>,
+    pub autocmds: Option<HashMap<AutocmdEvent, Vec<String>>>,
+    pub ime: Option<ImeSettings>,
+    pub matchpairs: Option<HashMap<String, Vec<String>>>,
+    pub search_in_selection: Option<bool>,
+    pub bracket_wrap: Option<BracketWrap>,
+    pub operators: Option<HashMap<String, String>>,
+    pub escape: Option<Vec<EscapeLayer>>,
+    pub multicursor_registers: Option<MulticursorRegisters>,
+    pub foldopen: Option<Vec<FoldOpen>>,
+    pub whichwrap: Option<String>,
+    pub hidden: Option<bool>,
+    pub normal_mode_tab: Option<NormalModeTab>,
+    pub repeat_motion: Option<RepeatMotion>,
+}
+
+impl VimSettings {
+    /// The language's entry in the `languages` setting, if it has one.
+    pub(crate) fn language_options(
+        &self,
+        language: Option<&Arc<Language>>,
+    ) -> Option<&LanguageOptions> {
+        self.languages.get(language?.name().as_ref())
+    }
 }
 
 impl Settings for VimSettings {
@@ -671,10 +1410,17 @@ impl Settings for VimSettings {
 fn local_selections_changed(
     newest: Selection<usize>,
     is_multicursor: bool,
+    in_snippet: bool,
     cx: &mut WindowContext,
 ) {
     Vim::update(cx, |vim, cx| {
-        if vim.state().mode == Mode::Normal && !newest.is_empty() {
+        // a snippet's placeholder is selected so that typing replaces it, and
+        // once it has been (or a tab stop without one is reached) it's insert mode again.
+        if vim.state().mode == Mode::Insert && !newest.is_empty() && in_snippet {
+            vim.switch_mode(Mode::Select, true, cx)
+        } else if vim.state().mode == Mode::Select && newest.is_empty() {
+            vim.switch_mode(Mode::Insert, true, cx)
+        } else if vim.state().mode == Mode::Normal && !newest.is_empty() {
             if matches!(newest.goal, SelectionGoal::HorizontalRange { .. }) {
                 vim.switch_mode(Mode::VisualBlock, false, cx);
             } else {
@@ -686,5 +1432,17 @@ fn local_selections_changed(
         {
             vim.switch_mode(Mode::Normal, true, cx)
         }
+
+        // enter continues comments by the options of the language at the cursor
+        if vim.state().mode == Mode::Insert {
+            vim.update_active_editor(cx, |_, editor, cx| {
+                formatoptions::sync_continue_on_enter(editor, cx)
+            });
+        }
+
+        // the recording sign follows the cursor
+        if vim.workspace_state.recording_register.is_some() {
+            vim.update_active_editor(cx, |vim, editor, cx| signs::sync_signs(vim, editor, cx));
+        }
     })
 }
@@ -1,22 +1,26 @@
 use anyhow::Result;
-use std::sync::Arc;
+use std::{ops::Range, sync::Arc};
 
 use collections::HashMap;
 use editor::{
     display_map::{DisplaySnapshot, ToDisplayPoint},
     movement,
     scroll::Autoscroll,
-    Bias, DisplayPoint, Editor,
+    Anchor, Bias, DisplayPoint, Editor, EditorEvent,
 };
-use gpui::{actions, ViewContext, WindowContext};
-use language::{Point, Selection, SelectionGoal};
+use gpui::{actions, impl_actions, px, Subscription, View, ViewContext, WindowContext};
+use language::{Point, Selection, SelectionGoal, TransactionId};
+use serde_derive::Deserialize;
 use workspace::Workspace;
 
 use crate::{
     motion::{start_of_line, Motion},
     object::Object,
-    state::{Mode, Operator},
-    utils::{copy_selections_content, yank_selections_content},
+    state::{Mode, Operator, RecordedSelection, ReplayableAction, WorkspaceState},
+    utils::{
+        copy_selections_content, transactions_since, undo_base, yank_selections_content,
+        KeywordChars,
+    },
     Vim,
 };
 
@@ -31,9 +35,51 @@ actions!(
         OtherEnd,
         SelectNext,
         SelectPrevious,
+        AddCursorAtNext,
     ]
 );
 
+/// Runs any Zed action on the visual selection (whole lines in visual line mode),
+/// and then goes back to normal mode, as in
+/// `"space r": ["vim::VisualAction", { "action": "editor::Rename" }]`. When the
+/// action edits the buffer (even once it's done, as a format does), `.` repeats it
+/// on a selection of the same size.
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+pub struct VisualAction {
+    pub action: String,
+    /// The action's arguments, for actions that take them
+    #[serde(default)]
+    pub args: Option<serde_json::Value>,
+}
+
+impl_actions!(vim, [VisualAction]);
+
+/// The change `.` repeats, as it was last recorded.
+pub(crate) struct RecordedChange {
+    actions: Vec<ReplayableAction>,
+    count: Option<usize>,
+    register: Option<char>,
+    selection: RecordedSelection,
+}
+
+impl RecordedChange {
+    fn save(state: &WorkspaceState) -> Self {
+        Self {
+            actions: state.recorded_actions.clone(),
+            count: state.recorded_count,
+            register: state.recorded_register,
+            selection: state.recorded_selection.clone(),
+        }
+    }
+
+    fn restore(self, state: &mut WorkspaceState) {
+        state.recorded_actions = self.actions;
+        state.recorded_count = self.count;
+        state.recorded_register = self.register;
+        state.recorded_selection = self.selection;
+    }
+}
+
 pub fn register(workspace: &mut Workspace, _: &mut ViewContext<Workspace>) {
     workspace.register_action(|_, _: &ToggleVisual, cx: &mut ViewContext<Workspace>| {
         toggle_mode(Mode::Visual, cx)
@@ -49,6 +95,7 @@ pub fn register(workspace: &mut Workspace, _: &mut ViewContext<Workspace>) {
     workspace.register_action(other_end);
     workspace.register_action(delete);
     workspace.register_action(yank);
+    workspace.register_action(visual_action);
 
     workspace.register_action(|workspace, action, cx| {
         select_next(workspace, action, cx).ok();
@@ -56,23 +103,38 @@ pub fn register(workspace: &mut Workspace, _: &mut ViewContext<Workspace>) {
     workspace.register_action(|workspace, action, cx| {
         select_previous(workspace, action, cx).ok();
     });
+    workspace.register_action(|workspace, action, cx| {
+        add_cursor_at_next(workspace, action, cx).ok();
+    });
 }
 
 pub fn visual_motion(motion: Motion, times: Option<usize>, cx: &mut WindowContext) {
     Vim::update(cx, |vim, cx| {
         vim.update_active_editor(cx, |vim, editor, cx| {
             let text_layout_details = editor.text_layout_details(cx);
-            if vim.state().mode == Mode::VisualBlock
-                && !matches!(
+            let keyword_chars = KeywordChars::new(cx);
+            if vim.state().mode == Mode::VisualBlock {
+                let is_up_or_down = matches!(motion, Motion::Up { .. } | Motion::Down { .. });
+                let to_line_end = matches!(
                     motion,
                     Motion::EndOfLine {
                         display_lines: false
                     }
-                )
-            {
-                let is_up_or_down = matches!(motion, Motion::Up { .. } | Motion::Down { .. });
+                );
                 visual_block_motion(is_up_or_down, editor, cx, |map, point, goal| {
-                    motion.move_point(map, point, goal, times, &text_layout_details)
+                    let (point, goal) = motion.move_point(
+                        map,
+                        point,
+                        goal,
+                        times,
+                        &text_layout_details,
+                        &keyword_chars,
+                    )?;
+                    if to_line_end {
+                        Some((point, LINE_END_GOAL))
+                    } else {
+                        Some((point, goal))
+                    }
                 })
             } else {
                 editor.change_selections(Some(Autoscroll::fit()), cx, |s| {
@@ -101,6 +163,7 @@ pub fn visual_motion(motion: Motion, times: Option<usize>, cx: &mut WindowContex
                             selection.goal,
                             times,
                             &text_layout_details,
+                            &keyword_chars,
                         ) else {
                             return;
                         };
@@ -135,6 +198,13 @@ pub fn visual_motion(motion: Motion, times: Option<usize>, cx: &mut WindowContex
     });
 }
 
+/// The goal of a block extended with `$`, which reaches the end of every line (until
+/// a motion other than `j` or `k` moves it).
+const LINE_END_GOAL: SelectionGoal = SelectionGoal::HorizontalRange {
+    start: 0.,
+    end: f32::INFINITY,
+};
+
 pub fn visual_block_motion(
     preserve_goal: bool,
     editor: &mut Editor,
@@ -166,13 +236,17 @@ pub fn visual_block_motion(
             head = movement::saturating_left(map, head);
         }
 
-        let Some((new_head, _)) = move_selection(&map, head, goal) else {
+        let Some((new_head, new_goal)) = move_selection(&map, head, goal) else {
             return;
         };
+        let to_line_end = matches!(
+            new_goal,
+            SelectionGoal::HorizontalRange { end, .. } if end == f32::INFINITY
+        );
         head = new_head;
         head_x = map.x_for_display_point(head, &text_layout_details);
 
-        let is_reversed = tail_x > head_x;
+        let is_reversed = !to_line_end && tail_x > head_x;
         if was_reversed && !is_reversed {
             tail = movement::saturating_left(map, tail);
             tail_x = map.x_for_display_point(tail, &text_layout_details);
@@ -185,13 +259,15 @@ pub fn visual_block_motion(
             head_x = map.x_for_display_point(head, &text_layout_details);
         }
 
-        let positions = if is_reversed {
+        let positions = if to_line_end {
+            tail_x.min(head_x)..px(f32::INFINITY)
+        } else if is_reversed {
             head_x..tail_x
         } else {
             tail_x..head_x
         };
 
-        if !preserve_goal {
+        if !preserve_goal || to_line_end {
             goal = SelectionGoal::HorizontalRange {
                 start: positions.start.0,
                 end: positions.end.0,
@@ -253,6 +329,7 @@ pub fn visual_object(object: Object, cx: &mut WindowContext) {
             }
 
             vim.update_active_editor(cx, |_, editor, cx| {
+                let keyword_chars = KeywordChars::new(cx);
                 editor.change_selections(Some(Autoscroll::fit()), cx, |s| {
                     s.move_with(|map, selection| {
                         let mut head = selection.head();
@@ -264,7 +341,7 @@ pub fn visual_object(object: Object, cx: &mut WindowContext) {
                             head = movement::left(map, head);
                         }
 
-                        if let Some(range) = object.range(map, head, around) {
+                        if let Some(range) = object.range(map, head, around, &keyword_chars) {
                             if !range.is_empty() {
                                 let expand_both_ways = object.always_expands_both_ways()
                                     || selection.is_empty()
@@ -427,9 +504,7 @@ pub(crate) fn visual_replace(text: Arc<str>, cx: &mut WindowContext) {
                     }
                 }
 
-                editor.buffer().update(cx, |buffer, cx| {
-                    buffer.edit(edits, None, cx);
-                });
+                editor.edit(edits, cx);
                 editor.change_selections(None, cx, |s| s.select_ranges(stable_anchors));
             });
         });
@@ -481,16 +556,233 @@ pub fn select_previous(
     .unwrap_or(Ok(()))
 }
 
+/// `g b` adds a cursor at the start of the next copy of the word under the cursor
+/// (count times), staying in normal mode, so that the commands that follow apply
+/// at each copy.
+pub fn add_cursor_at_next(
+    _: &mut Workspace,
+    _: &AddCursorAtNext,
+    cx: &mut ViewContext<Workspace>,
+) -> Result<()> {
+    Vim::update(cx, |vim, cx| {
+        let count = vim.take_count(cx).unwrap_or(1);
+        let result = vim.update_active_editor(cx, |_, editor, cx| {
+            // the first time, the word under the cursor is selected
+            if editor.selections.newest_anchor().is_empty() {
+                editor.select_next(&Default::default(), cx)?;
+            }
+            for _ in 0..count {
+                editor.select_next(&Default::default(), cx)?;
+            }
+            editor.change_selections(Some(Autoscroll::fit()), cx, |s| {
+                s.move_with(|_, selection| {
+                    selection.collapse_to(selection.start, SelectionGoal::None)
+                })
+            });
+            Ok(())
+        });
+        vim.switch_mode(Mode::Normal, true, cx);
+        result.unwrap_or(Ok(()))
+    })
+}
+
+fn visual_action(_: &mut Workspace, action: &VisualAction, cx: &mut ViewContext<Workspace>) {
+    Vim::update(cx, |vim, cx| {
+        let Ok(zed_action) = cx.build_action(&action.action, action.args.clone()) else {
+            vim.show_message(format!("E492: Not an editor command: {}", action.action));
+            return;
+        };
+        if !vim.state().mode.is_visual() {
+            cx.dispatch_action(zed_action);
+            return;
+        }
+
+        // the action is recorded for `.`, unless it turns out not to edit the buffer,
+        // in which case the change that was recorded before is kept
+        let previous_change =
+            (!vim.workspace_state.replaying).then(|| RecordedChange::save(&vim.workspace_state));
+        vim.record_current_action(cx);
+        let Some((editor, edit_count, base)) = vim.update_active_editor(cx, |_, editor, cx| {
+            let ranges = visual_selection_ranges(editor, cx);
+            editor.change_selections(None, cx, |s| s.select_anchor_ranges(ranges));
+            let edit_count = editor.buffer().read(cx).snapshot(cx).edit_count();
+            (cx.view().downgrade(), edit_count, undo_base(editor, cx))
+        }) else {
+            return;
+        };
+
+        // the action is dispatched once this one is over, as a key binding would be
+        cx.dispatch_action(zed_action);
+        cx.defer(move |cx| {
+            let editor = editor.upgrade();
+            let edited = editor.as_ref().is_some_and(|editor| {
+                editor.read(cx).buffer().read(cx).snapshot(cx).edit_count() != edit_count
+            });
+            Vim::update(cx, |vim, cx| {
+                if let Some(previous_change) = previous_change.filter(|_| !edited) {
+                    let change = RecordedChange::save(&vim.workspace_state);
+                    previous_change.restore(&mut vim.workspace_state);
+                    vim.pending_visual_change =
+                        editor.map(|editor| (change, record_once_edited(&editor, base, cx)));
+                }
+                if vim.state().mode.is_visual() {
+                    vim.switch_mode(Mode::Normal, false, cx);
+                }
+            });
+        });
+    });
+}
+
+/// Records the change set aside in `pending_visual_change` once a transaction lands on
+/// top of `base`, for actions that edit later, like a format waiting on a language
+/// server.
+fn record_once_edited(
+    editor: &View<Editor>,
+    base: Option<TransactionId>,
+    cx: &mut WindowContext,
+) -> Subscription {
+    cx.subscribe(editor, move |editor, event: &EditorEvent, cx| {
+        if !matches!(event, EditorEvent::BufferEdited) {
+            return;
+        }
+        let landed =
+            transactions_since(editor.read(cx), base, cx).is_some_and(|made| !made.is_empty());
+        if landed {
+            Vim::update(cx, |vim, _| {
+                if let Some((change, _)) = vim.pending_visual_change.take() {
+                    change.restore(&mut vim.workspace_state);
+                }
+            });
+        }
+    })
+}
+
+/// The text a visual selection covers, which is whole lines in visual line mode,
+/// and a range for each line of a block in visual block mode.
+pub(crate) fn visual_selection_ranges(editor: &Editor, cx: &WindowContext) -> Vec<Range<Anchor>> {
+    let snapshot = editor.buffer().read(cx).snapshot(cx);
+    let line_mode = editor.selections.line_mode;
+    editor
+        .selections
+        .all::<Point>(cx)
+        .into_iter()
+        .map(|selection| {
+            let (mut start, mut end) = (selection.start, selection.end);
+            if line_mode {
+                let mut end_row = end.row;
+                if end_row > start.row && end.column == 0 {
+                    end_row -= 1;
+                }
+                start = Point::new(start.row, 0);
+                end = Point::new(end_row, snapshot.line_len(end_row));
+            }
+            snapshot.anchor_before(start)..snapshot.anchor_after(end)
+        })
+        .collect()
+}
+
 #[cfg(test)]
 mod test {
+    use editor::test::editor_lsp_test_context::EditorLspTestContext;
+    use gpui::KeyBinding;
     use indoc::indoc;
     use workspace::item::Item;
 
+    use super::VisualAction;
     use crate::{
         state::Mode,
         test::{NeovimBackedTestContext, VimTestContext},
     };
 
+    #[gpui::test]
+    async fn test_visual_action(cx: &mut gpui::TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+        let visual_action = |action: &str| VisualAction {
+            action: action.to_string(),
+            args: None,
+        };
+        cx.update(|cx| {
+            cx.bind_keys([
+                KeyBinding::new(
+                    "g x",
+                    visual_action("editor::ConvertToUpperCase"),
+                    Some("Editor && VimControl && !VimWaiting && !menu"),
+                ),
+                KeyBinding::new(
+                    "g y",
+                    visual_action("editor::Copy"),
+                    Some("Editor && VimControl && !VimWaiting && !menu"),
+                ),
+            ])
+        });
+
+        // the action runs on the selection, and it's normal mode after
+        cx.set_state("ˇone two\nthree", Mode::Normal);
+        cx.simulate_keystrokes(["v", "e", "g", "x"]);
+        cx.run_until_parked();
+        cx.assert_state("ONˇE two\nthree", Mode::Normal);
+
+        // an action that edits is repeated by .
+        cx.simulate_keystrokes(["w", "."]);
+        cx.run_until_parked();
+        cx.assert_state("ONE TWˇO\nthree", Mode::Normal);
+
+        // on whole lines in visual line mode
+        cx.simulate_keystrokes(["j", "V", "g", "x"]);
+        cx.run_until_parked();
+        cx.assert_state("ONE TWO\nTHREˇE", Mode::Normal);
+
+        // and one that doesn't edit leaves the last change to be repeated
+        cx.set_state("ˇone two", Mode::Normal);
+        cx.simulate_keystrokes(["x", "v", "e", "g", "y"]);
+        cx.run_until_parked();
+        cx.assert_state("nˇe two", Mode::Normal);
+        cx.simulate_keystrokes(["."]);
+        cx.assert_state("nˇ two", Mode::Normal);
+    }
+
+    #[gpui::test]
+    async fn test_visual_action_edits_later(cx: &mut gpui::TestAppContext) {
+        VimTestContext::init(cx);
+        let lsp = EditorLspTestContext::new_rust(
+            lsp::ServerCapabilities {
+                document_formatting_provider: Some(lsp::OneOf::Left(true)),
+                ..Default::default()
+            },
+            cx,
+        )
+        .await;
+        let mut cx = VimTestContext::new_with_lsp(lsp, true);
+        // each format puts a / at the start of the file
+        cx.lsp
+            .handle_request::<lsp::request::Formatting, _, _>(|_, _| async move {
+                Ok(Some(vec![lsp::TextEdit::new(
+                    lsp::Range::new(lsp::Position::new(0, 0), lsp::Position::new(0, 0)),
+                    "/".to_string(),
+                )]))
+            });
+        cx.update(|cx| {
+            cx.bind_keys([KeyBinding::new(
+                "g x",
+                VisualAction {
+                    action: "editor::Format".to_string(),
+                    args: None,
+                },
+                Some("Editor && VimControl && !VimWaiting && !menu"),
+            )])
+        });
+
+        // the format edits once the language server answers, and it's what . repeats
+        // rather than the change before it
+        cx.set_state("ˇab", Mode::Normal);
+        cx.simulate_keystrokes(["x", "v", "g", "x"]);
+        cx.run_until_parked();
+        assert_eq!(cx.update_editor(|editor, cx| editor.text(cx)), "/b");
+        cx.simulate_keystrokes(["."]);
+        cx.run_until_parked();
+        assert_eq!(cx.update_editor(|editor, cx| editor.text(cx)), "//b");
+    }
+
     #[gpui::test]
     async fn test_enter_visual_mode(cx: &mut gpui::TestAppContext) {
         let mut cx = NeovimBackedTestContext::new(cx).await;
@@ -1047,4 +1339,59 @@ mod test {
         cx.simulate_keystrokes(["cmd-shift-p", "escape"]);
         assert_eq!(cx.mode(), Mode::VisualBlock);
     }
+
+    #[gpui::test]
+    async fn test_visual_block_append_at_line_ends(cx: &mut gpui::TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+
+        cx.set_state(
+            indoc! {"
+                ˇlet a = 1
+                let bb = 22
+                let c = 3"},
+            Mode::Normal,
+        );
+        // after $, the block reaches the end of every line it's moved onto
+        cx.simulate_keystrokes(["ctrl-v", "$", "j", "j"]);
+        cx.assert_state(
+            indoc! {"
+                «let a = 1ˇ»
+                «let bb = 22ˇ»
+                «let c = 3ˇ»"},
+            Mode::VisualBlock,
+        );
+        cx.simulate_keystrokes(["shift-a", ";", "escape"]);
+        cx.assert_state(
+            indoc! {"
+                let a = 1ˇ;
+                let bb = 22;
+                let c = 3;"},
+            Mode::Normal,
+        );
+
+        // moving sideways gives the block a fixed width again
+        cx.simulate_keystrokes(["ctrl-v", "$", "j", "h"]);
+        cx.assert_state(
+            indoc! {"
+                let a = 1«;ˇ»
+                let bb = «22ˇ»;
+                let c = 3;"},
+            Mode::VisualBlock,
+        );
+    }
+
+    #[gpui::test]
+    async fn test_add_cursor_at_next(cx: &mut gpui::TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+
+        cx.set_state("let ˇfoo = 1;\nlet foo = foo + 1;", Mode::Normal);
+        cx.simulate_keystrokes(["g", "b"]);
+        cx.assert_state("let ˇfoo = 1;\nlet ˇfoo = foo + 1;", Mode::Normal);
+
+        // the commands that follow apply at each cursor
+        cx.simulate_keystrokes(["c", "i", "w", "b", "a", "r", "escape"]);
+        cx.assert_state("let baˇr = 1;\nlet baˇr = foo + 1;", Mode::Normal);
+        cx.simulate_keystrokes(["shift-a", ";", "escape"]);
+        cx.assert_state("let bar = 1;ˇ;\nlet bar = foo + 1;ˇ;", Mode::Normal);
+    }
 }
@@ -0,0 +1,354 @@
+use std::path::Path;
+
+use editor::Editor;
+use gpui::{impl_actions, ViewContext};
+use language::Point;
+use serde_derive::Deserialize;
+use workspace::Workspace;
+
+use crate::{
+    messages::show_output,
+    normal::rewrap::line_rows,
+    state::Mode,
+    utils::{run_with_input, worktree_root},
+    Vim,
+};
+
+/// `:w {file}` saves a buffer that has no file yet (like one opened with `:new` or
+/// `ctrl-w n`) as the file, which is relative to the project's root. A buffer that
+/// has a file is written to the other one as a copy, and keeps its own. Like vim,
+/// an existing file is only overwritten with `:w! {file}`.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub(crate) struct WriteAs {
+    path: String,
+    overwrite: bool,
+}
+
+/// `:w !{cmd}` writes the buffer to a shell command's input, without touching the
+/// file, as in `:w !sudo tee %`. With a range (like `:'<,'>w !pbcopy`) only those
+/// lines are written. A `%` in the command is the path of the buffer's file.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub(crate) struct WriteCommand {
+    command: String,
+}
+
+impl_actions!(vim, [WriteAs, WriteCommand]);
+
+pub(crate) fn register(workspace: &mut Workspace, _: &mut ViewContext<Workspace>) {
+    workspace.register_action(write_as);
+    workspace.register_action(write_command);
+}
+
+fn write_as(workspace: &mut Workspace, action: &WriteAs, cx: &mut ViewContext<Workspace>) {
+    let Some(item) = workspace.active_item(cx) else {
+        return;
+    };
+    let Some(buffer) = item
+        .act_as::<Editor>(cx)
+        .and_then(|editor| editor.read(cx).buffer().read(cx).as_singleton())
+    else {
+        return;
+    };
+    let project = workspace.project().clone();
+    let path = Path::new(&action.path);
+    let abs_path = if path.is_absolute() {
+        path.to_path_buf()
+    } else if let Some(root) = worktree_root(&buffer, &project, cx) {
+        root.join(path)
+    } else {
+        Vim::update(cx, |vim, _| {
+            vim.show_message("E32: No project to write the file in")
+        });
+        return;
+    };
+
+    let fs = workspace.app_state().fs.clone();
+    let buffer = buffer.read(cx);
+    // a buffer with a file has its text written as a copy
+    let copy = buffer
+        .file()
+        .is_some()
+        .then(|| (buffer.as_rope().clone(), buffer.line_ending()));
+    let overwrite = action.overwrite;
+    cx.spawn(|workspace, mut cx| async move {
+        if !overwrite && fs.is_file(&abs_path).await {
+            return workspace.update(&mut cx, |_, cx| {
+                Vim::update(cx, |vim, _| {
+                    vim.show_message("E13: File exists (add ! to override)")
+                })
+            });
+        }
+        let written = match copy {
+            Some((text, line_ending)) => fs.save(&abs_path, &text, line_ending).await,
+            None => {
+                workspace
+                    .update(&mut cx, |_, cx| item.save_as(project, abs_path.clone(), cx))?
+                    .await
+            }
+        };
+        workspace.update(&mut cx, |_, cx| {
+            Vim::update(cx, |vim, _| match written {
+                Ok(()) => vim.show_message(format!("\"{}\" written", display_path(&abs_path))),
+                Err(error) => vim.show_message(format!("E212: Can't write the file: {error}")),
+            })
+        })
+    })
+    .detach_and_log_err(cx);
+}
+
+/// Runs the command in the background with the lines as its input, each ending with a
+/// newline. Anything it writes is shown like the output of `:!{cmd}`, and when it
+/// fails, so is its exit status.
+fn write_command(
+    workspace: &mut Workspace,
+    action: &WriteCommand,
+    cx: &mut ViewContext<Workspace>,
+) {
+    let Some(editor) = workspace.active_item_as::<Editor>(cx) else {
+        return;
+    };
+    if Vim::update(cx, |vim, _| vim.refuse_pending_operation()) {
+        return;
+    }
+    let buffer = editor.read(cx).buffer().read(cx).as_singleton();
+    let root = buffer
+        .as_ref()
+        .and_then(|buffer| worktree_root(buffer, workspace.project(), cx));
+    let path = buffer.as_ref().and_then(|buffer| {
+        let file = buffer.read(cx).file()?;
+        Some(file.path().to_string_lossy().to_string())
+    });
+    let Some(command) = expand_file_name(&action.command, path.as_deref()) else {
+        Vim::update(cx, |vim, cx| {
+            vim.switch_mode(Mode::Normal, false, cx);
+            vim.show_message("E499: Empty file name for '%'")
+        });
+        return;
+    };
+
+    // a range is run as a linewise selection
+    let ranged = Vim::read(cx).state().mode.is_visual();
+    let input = editor.update(cx, |editor, cx| {
+        let snapshot = editor.buffer().read(cx).snapshot(cx);
+        let rows = if ranged {
+            editor
+                .selections
+                .all::<Point>(cx)
+                .into_iter()
+                .map(|selection| line_rows(selection.start..selection.end))
+                .collect()
+        } else {
+            vec![0..snapshot.max_point().row + 1]
+        };
+        rows.into_iter()
+            .flatten()
+            .map(|row| {
+                let line = Point::new(row, 0)..Point::new(row, snapshot.line_len(row));
+                snapshot.text_for_range(line).collect::<String>() + "\n"
+            })
+            .collect::<String>()
+    });
+    if ranged {
+        Vim::update(cx, |vim, cx| vim.switch_mode(Mode::Normal, true, cx));
+    }
+
+    let task = cx.spawn(|workspace, mut cx| async move {
+        let output = run_with_input(&command, &input, root.as_deref()).await;
+        workspace
+            .update(&mut cx, |workspace, cx| {
+                Vim::update(cx, |vim, _| vim.finish_pending_operation());
+                let output = match output {
+                    Ok(output) => output,
+                    Err(error) => {
+                        return Vim::update(cx, |vim, _| vim.show_message(error.to_string()));
+                    }
+                };
+                let text = String::from_utf8_lossy(&output.stdout).to_string()
+                    + &String::from_utf8_lossy(&output.stderr);
+                if !text.is_empty() {
+                    show_output(workspace, &text, cx);
+                }
+                let message = match output.status.code() {
+                    Some(0) => format!("\"!{command}\" written"),
+                    Some(code) => format!("shell returned {code}"),
+                    None => "shell was killed".to_string(),
+                };
+                Vim::update(cx, |vim, _| vim.show_message(message));
+            })
+            .ok();
+    });
+    Vim::update(cx, |vim, _| vim.pending_operation = Some(task));
+}
+
+/// Replaces each `%` in the command with the path of the file (but not `\%`, which is
+/// a plain `%`), or returns `None` when there's a `%` and no file.
+fn expand_file_name(command: &str, path: Option<&str>) -> Option<String> {
+    let mut expanded = String::new();
+    let mut chars = command.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' if chars.peek() == Some(&'%') => expanded.push(chars.next()?),
+            '%' => expanded.push_str(path?),
+            c => expanded.push(c),
+        }
+    }
+    Some(expanded)
+}
+
+fn display_path(path: &Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_else(|| path.to_string_lossy().to_string())
+}
+
+/// `:w {file}` or `:w! {file}`, with the file after a space.
+pub(crate) fn parse_write_as(query: &str) -> Option<WriteAs> {
+    let name_len = query
+        .find(|c: char| !c.is_ascii_alphabetic())
+        .unwrap_or(query.len());
+    let (name, args) = query.split_at(name_len);
+    if !matches!(name, "w" | "wr" | "wri" | "writ" | "write") {
+        return None;
+    }
+    let (overwrite, args) = match args.strip_prefix('!') {
+        Some(args) => (true, args),
+        None => (false, args),
+    };
+    if !args.starts_with(' ') {
+        return None;
+    }
+    let path = args.trim();
+    if path.is_empty() {
+        return None;
+    }
+    Some(WriteAs {
+        path: path.to_string(),
+        overwrite,
+    })
+}
+
+/// `:w !{cmd}`, with a space before the `!` (`:w!` is a forced write).
+pub(crate) fn parse_write_command(query: &str) -> Option<WriteCommand> {
+    let name_len = query
+        .find(|c: char| !c.is_ascii_alphabetic())
+        .unwrap_or(query.len());
+    let (name, args) = query.split_at(name_len);
+    if !matches!(name, "w" | "wr" | "wri" | "writ" | "write") || !args.starts_with(' ') {
+        return None;
+    }
+    let command = args.trim_start().strip_prefix('!')?.trim();
+    if command.is_empty() {
+        return None;
+    }
+    Some(WriteCommand {
+        command: command.to_string(),
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::Path;
+
+    use crate::{state::Mode, test::VimTestContext};
+
+    #[gpui::test]
+    async fn test_write_as(cx: &mut gpui::TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+        let fs = cx.workspace(|workspace, cx| workspace.project().read(cx).fs().clone());
+
+        // a new buffer is saved as the file, and then writes to it
+        cx.simulate_keystrokes(["ctrl-w", "n", "i"]);
+        cx.simulate_input("notes");
+        cx.simulate_keystrokes(["escape", ":"]);
+        cx.simulate_input("w notes.txt");
+        cx.simulate_keystrokes(["enter"]);
+        cx.run_until_parked();
+        let path = Path::new("/root/dir/notes.txt");
+        assert_eq!(fs.load(path).await.unwrap(), "notes");
+        cx.workspace(|workspace, cx| {
+            let title = workspace.active_item(cx).unwrap().tab_description(0, cx);
+            assert_eq!(title.as_deref(), Some("notes.txt"));
+        });
+        cx.simulate_keystrokes(["shift-a", "!", "escape", ":", "w", "enter"]);
+        cx.run_until_parked();
+        assert_eq!(fs.load(path).await.unwrap(), "notes!");
+
+        // a buffer with a file writes a copy, and only over a file with !
+        cx.simulate_keystrokes(["ctrl-w", "p"]);
+        cx.set_state("ˇcopy", Mode::Normal);
+        cx.simulate_keystrokes([":"]);
+        cx.simulate_input("w notes.txt");
+        cx.simulate_keystrokes(["enter"]);
+        cx.run_until_parked();
+        assert_eq!(fs.load(path).await.unwrap(), "notes!");
+        cx.simulate_keystrokes([":"]);
+        cx.simulate_input("w! notes.txt");
+        cx.simulate_keystrokes(["enter"]);
+        cx.run_until_parked();
+        assert_eq!(fs.load(path).await.unwrap(), "copy");
+        let file_path = cx.update_editor(|editor, cx| {
+            editor
+                .buffer()
+                .read(cx)
+                .as_singleton()
+                .and_then(|buffer| buffer.read(cx).file().map(|file| file.path().clone()))
+        });
+        assert_eq!(file_path.as_deref(), Some(Path::new("file.rs")));
+    }
+
+    #[gpui::test]
+    async fn test_write_command(cx: &mut gpui::TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+        let last_output = |cx: &mut VimTestContext| {
+            cx.workspace(|_, cx| crate::Vim::read(cx).workspace_state.last_output.clone())
+        };
+
+        cx.set_state("ˇone\ntwo\nthree", Mode::Normal);
+        cx.simulate_keystrokes([":"]);
+        cx.simulate_input("w !tr a-z A-Z");
+        cx.simulate_keystrokes(["enter"]);
+        cx.run_until_operation_finishes();
+        assert_eq!(last_output(&mut cx).as_deref(), Some("ONE\nTWO\nTHREE\n"));
+        assert_eq!(cx.message().as_deref(), Some("\"!tr a-z A-Z\" written"));
+
+        // a range writes only its lines, and % is the file's path
+        cx.simulate_keystrokes(["ctrl-w", "p", "j", "shift-v", "j", ":"]);
+        cx.simulate_input("w !cat; echo %");
+        cx.simulate_keystrokes(["enter"]);
+        cx.run_until_operation_finishes();
+        assert_eq!(
+            last_output(&mut cx).as_deref(),
+            Some("two\nthree\nfile.rs\n")
+        );
+
+        // the buffer is left as it is, and a failure shows the exit status
+        cx.simulate_keystrokes(["ctrl-w", "p"]);
+        assert_eq!(cx.editor(|editor, cx| editor.text(cx)), "one\ntwo\nthree");
+        cx.simulate_keystrokes([":"]);
+        cx.simulate_input("w !exit 3");
+        cx.simulate_keystrokes(["enter"]);
+        cx.run_until_operation_finishes();
+        assert_eq!(cx.message().as_deref(), Some("shell returned 3"));
+    }
+
+    #[test]
+    fn test_parse_write_as() {
+        let write_as = super::parse_write_as("w! a b.txt").unwrap();
+        assert_eq!(write_as.path, "a b.txt");
+        assert!(write_as.overwrite);
+        assert!(!super::parse_write_as("write x").unwrap().overwrite);
+        assert!(super::parse_write_as("w").is_none());
+        assert!(super::parse_write_as("wq x").is_none());
+        assert_eq!(
+            super::parse_write_command("w !sudo tee %").unwrap().command,
+            "sudo tee %"
+        );
+        assert!(super::parse_write_command("w! tee").is_none());
+        assert!(super::parse_write_command("w !").is_none());
+        assert_eq!(
+            super::expand_file_name("tee % \\%", Some("a.rs")).as_deref(),
+            Some("tee a.rs %")
+        );
+        assert_eq!(super::expand_file_name("tee %", None), None);
+    }
+}
@@ -839,6 +839,7 @@ pub mod test {
         pub is_dirty: bool,
         pub is_singleton: bool,
         pub has_conflict: bool,
+        pub save_error: Option<&'static str>,
         pub project_items: Vec<Model<TestProjectItem>>,
         pub nav_history: Option<ItemNavHistory>,
         pub tab_descriptions: Option<Vec<&'static str>>,
@@ -891,6 +892,7 @@ pub mod test {
                 reload_count: 0,
                 is_dirty: false,
                 has_conflict: false,
+                save_error: None,
                 project_items: Vec::new(),
                 is_singleton: true,
                 nav_history: None,
@@ -927,6 +929,11 @@ pub mod test {
             self
         }
 
+        pub fn with_save_error(mut self, error: &'static str) -> Self {
+            self.save_error = Some(error);
+            self
+        }
+
         pub fn with_project_items(mut self, items: &[Model<TestProjectItem>]) -> Self {
             self.project_items.clear();
             self.project_items.extend(items.iter().cloned());
@@ -1036,6 +1043,7 @@ pub mod test {
                 is_dirty: self.is_dirty,
                 is_singleton: self.is_singleton,
                 has_conflict: self.has_conflict,
+                save_error: self.save_error,
                 project_items: self.project_items.clone(),
                 nav_history: None,
                 tab_descriptions: None,
@@ -1067,6 +1075,9 @@ pub mod test {
             _: Model<Project>,
             _: &mut ViewContext<Self>,
         ) -> Task<anyhow::Result<()>> {
+            if let Some(error) = self.save_error {
+                return Task::ready(Err(anyhow::anyhow!(error)));
+            }
             self.save_count += 1;
             self.is_dirty = false;
             Task::ready(Ok(()))
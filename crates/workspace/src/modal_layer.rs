@@ -90,7 +90,7 @@ impl ModalLayer {
         cx.notify();
     }
 
-    fn hide_modal(&mut self, cx: &mut ViewContext<Self>) -> bool {
+    pub fn hide_modal(&mut self, cx: &mut ViewContext<Self>) -> bool {
         let Some(active_modal) = self.active_modal.as_mut() else {
             self.dismiss_on_focus_lost = false;
             return false;
@@ -183,6 +183,7 @@ pub struct Pane {
     _subscriptions: Vec<Subscription>,
     tab_bar_scroll_handle: ScrollHandle,
     display_nav_history_buttons: bool,
+    number_badge: Option<usize>,
 }
 
 pub struct ItemNavHistory {
@@ -345,6 +346,7 @@ impl Pane {
                     .into_any_element()
             }),
             display_nav_history_buttons: true,
+            number_badge: None,
             _subscriptions: subscriptions,
         }
     }
@@ -664,6 +666,14 @@ impl Pane {
         })
     }
 
+    /// The index of the item that was active before the active one, if it's open.
+    pub fn alternate_item_index(&self) -> Option<usize> {
+        let alternate_id = *self.activation_history.iter().rev().nth(1)?;
+        self.items
+            .iter()
+            .position(|item| item.item_id() == alternate_id)
+    }
+
     pub fn index_for_item(&self, item: &dyn ItemHandle) -> Option<usize> {
         self.items
             .iter()
@@ -1756,6 +1766,18 @@ impl Pane {
     pub fn display_nav_history_buttons(&mut self, display: bool) {
         self.display_nav_history_buttons = display;
     }
+
+    pub fn number_badge(&self) -> Option<usize> {
+        self.number_badge
+    }
+
+    /// Shows a number in the corner of the pane, so that a pane can be picked by it.
+    pub fn set_number_badge(&mut self, number: Option<usize>, cx: &mut ViewContext<Self>) {
+        if self.number_badge != number {
+            self.number_badge = number;
+            cx.notify();
+        }
+    }
 }
 
 impl FocusableView for Pane {
@@ -1883,6 +1905,19 @@ impl Render for Pane {
                             }
                         }
                     })
+                    .when_some(self.number_badge, |div, number| {
+                        div.child(
+                            h_flex()
+                                .z_index(2)
+                                .absolute()
+                                .top_1()
+                                .right_1()
+                                .px_1p5()
+                                .rounded_md()
+                                .bg(cx.theme().colors().element_selected)
+                                .child(Label::new(number.to_string())),
+                        )
+                    })
                     .child(
                         // drag target
                         div()
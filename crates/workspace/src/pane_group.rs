@@ -16,6 +16,19 @@ pub const HANDLE_HITBOX_SIZE: f32 = 4.0;
 const HORIZONTAL_MIN_SIZE: f32 = 80.;
 const VERTICAL_MIN_SIZE: f32 = 100.;
 
+/// What happens to the sizes of the other panes when a pane is split or closed.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum PaneSizing {
+    /// The panes alongside it (in the same row or column) are made the same size.
+    #[default]
+    EqualizeSiblings,
+    /// Every pane is made the same size, like vim's `equalalways`.
+    EqualizeAll,
+    /// The other panes keep their sizes: a split pane is halved, and a closed
+    /// pane's space goes to its neighbor.
+    Preserve,
+}
+
 #[derive(Clone)]
 pub struct PaneGroup {
     pub(crate) root: Member,
@@ -37,17 +50,33 @@ impl PaneGroup {
         old_pane: &View<Pane>,
         new_pane: &View<Pane>,
         direction: SplitDirection,
+        sizing: PaneSizing,
     ) -> Result<()> {
         match &mut self.root {
             Member::Pane(pane) => {
                 if pane == old_pane {
                     self.root = Member::new_axis(old_pane.clone(), new_pane.clone(), direction);
-                    Ok(())
                 } else {
-                    Err(anyhow!("Pane not found"))
+                    return Err(anyhow!("Pane not found"));
                 }
             }
-            Member::Axis(axis) => axis.split(old_pane, new_pane, direction),
+            Member::Axis(axis) => axis.split(
+                old_pane,
+                new_pane,
+                direction,
+                sizing == PaneSizing::Preserve,
+            )?,
+        }
+        if sizing == PaneSizing::EqualizeAll {
+            self.reset_pane_sizes();
+        }
+        Ok(())
+    }
+
+    /// Makes every pane the same size.
+    pub fn reset_pane_sizes(&mut self) {
+        if let Member::Axis(axis) = &mut self.root {
+            axis.reset_pane_sizes();
         }
     }
 
@@ -69,13 +98,16 @@ impl PaneGroup {
     /// - Ok(true) if it found and removed a pane
     /// - Ok(false) if it found but did not remove the pane
     /// - Err(_) if it did not find the pane
-    pub fn remove(&mut self, pane: &View<Pane>) -> Result<bool> {
+    pub fn remove(&mut self, pane: &View<Pane>, sizing: PaneSizing) -> Result<bool> {
         match &mut self.root {
             Member::Pane(_) => Ok(false),
             Member::Axis(axis) => {
-                if let Some(last_pane) = axis.remove(pane)? {
+                if let Some(last_pane) = axis.remove(pane, sizing == PaneSizing::Preserve)? {
                     self.root = last_pane;
                 }
+                if sizing == PaneSizing::EqualizeAll {
+                    self.reset_pane_sizes();
+                }
                 Ok(true)
             }
         }
@@ -88,6 +120,56 @@ impl PaneGroup {
         };
     }
 
+    /// Exchanges the pane with the next one in its row or column (or the previous
+    /// one, if it's the last), returning the one it was exchanged with. Nothing is
+    /// exchanged if that's split itself.
+    pub fn exchange_with_next(&mut self, pane: &View<Pane>) -> Option<View<Pane>> {
+        let Member::Axis(root) = &mut self.root else {
+            return None;
+        };
+        let axis = root.axis_containing(pane)?;
+        let ix = axis
+            .members
+            .iter()
+            .position(|member| matches!(member, Member::Pane(found) if found == pane))?;
+        let other_ix = if ix + 1 < axis.members.len() {
+            ix + 1
+        } else {
+            ix.checked_sub(1)?
+        };
+        let Member::Pane(other) = &axis.members[other_ix] else {
+            return None;
+        };
+        let other = other.clone();
+        axis.members.swap(ix, other_ix);
+        Some(other)
+    }
+
+    /// Moves each pane in the pane's row or column to the next place in it (or the
+    /// previous one), the last going first. Returns false if any of them is split,
+    /// as they can't be rotated then.
+    pub fn rotate(&mut self, pane: &View<Pane>, forward: bool) -> bool {
+        let Member::Axis(root) = &mut self.root else {
+            return true;
+        };
+        let Some(axis) = root.axis_containing(pane) else {
+            return false;
+        };
+        if axis
+            .members
+            .iter()
+            .any(|member| matches!(member, Member::Axis(_)))
+        {
+            return false;
+        }
+        if forward {
+            axis.members.rotate_right(1);
+        } else {
+            axis.members.rotate_left(1);
+        }
+        true
+    }
+
     #[allow(clippy::too_many_arguments)]
     pub(crate) fn render(
         &self,
@@ -349,23 +431,36 @@ impl PaneAxis {
         old_pane: &View<Pane>,
         new_pane: &View<Pane>,
         direction: SplitDirection,
+        preserve_sizes: bool,
     ) -> Result<()> {
         for (mut idx, member) in self.members.iter_mut().enumerate() {
             match member {
                 Member::Axis(axis) => {
-                    if axis.split(old_pane, new_pane, direction).is_ok() {
+                    if axis
+                        .split(old_pane, new_pane, direction, preserve_sizes)
+                        .is_ok()
+                    {
                         return Ok(());
                     }
                 }
                 Member::Pane(pane) => {
                     if pane == old_pane {
                         if direction.axis() == self.axis {
+                            let old_idx = idx;
                             if direction.increasing() {
                                 idx += 1;
                             }
 
                             self.members.insert(idx, Member::Pane(new_pane.clone()));
-                            *self.flexes.lock() = vec![1.; self.members.len()];
+                            let mut flexes = self.flexes.lock();
+                            if preserve_sizes {
+                                let half = flexes[old_idx] / 2.;
+                                flexes[old_idx] = half;
+                                flexes.insert(idx, half);
+                                normalize_flexes(&mut flexes);
+                            } else {
+                                *flexes = vec![1.; self.members.len()];
+                            }
                         } else {
                             *member =
                                 Member::new_axis(old_pane.clone(), new_pane.clone(), direction);
@@ -378,13 +473,17 @@ impl PaneAxis {
         Err(anyhow!("Pane not found"))
     }
 
-    fn remove(&mut self, pane_to_remove: &View<Pane>) -> Result<Option<Member>> {
+    fn remove(
+        &mut self,
+        pane_to_remove: &View<Pane>,
+        preserve_sizes: bool,
+    ) -> Result<Option<Member>> {
         let mut found_pane = false;
         let mut remove_member = None;
         for (idx, member) in self.members.iter_mut().enumerate() {
             match member {
                 Member::Axis(axis) => {
-                    if let Ok(last_pane) = axis.remove(pane_to_remove) {
+                    if let Ok(last_pane) = axis.remove(pane_to_remove, preserve_sizes) {
                         if let Some(last_pane) = last_pane {
                             *member = last_pane;
                         }
@@ -405,7 +504,14 @@ impl PaneAxis {
         if found_pane {
             if let Some(idx) = remove_member {
                 self.members.remove(idx);
-                *self.flexes.lock() = vec![1.; self.members.len()];
+                let mut flexes = self.flexes.lock();
+                if preserve_sizes && !self.members.is_empty() {
+                    let flex = flexes.remove(idx);
+                    flexes[idx.saturating_sub(1)] += flex;
+                    normalize_flexes(&mut flexes);
+                } else {
+                    *flexes = vec![1.; self.members.len()];
+                }
             }
 
             if self.members.len() == 1 {
@@ -420,6 +526,30 @@ impl PaneAxis {
         }
     }
 
+    fn reset_pane_sizes(&mut self) {
+        *self.flexes.lock() = vec![1.; self.members.len()];
+        for member in self.members.iter_mut() {
+            if let Member::Axis(axis) = member {
+                axis.reset_pane_sizes();
+            }
+        }
+    }
+
+    /// The row or column the pane is directly in.
+    fn axis_containing(&mut self, pane: &View<Pane>) -> Option<&mut PaneAxis> {
+        if self
+            .members
+            .iter()
+            .any(|member| matches!(member, Member::Pane(found) if found == pane))
+        {
+            return Some(self);
+        }
+        self.members.iter_mut().find_map(|member| match member {
+            Member::Axis(axis) => axis.axis_containing(pane),
+            Member::Pane(_) => None,
+        })
+    }
+
     fn swap(&mut self, from: &View<Pane>, to: &View<Pane>) {
         for member in self.members.iter_mut() {
             match member {
@@ -517,6 +647,15 @@ impl PaneAxis {
     }
 }
 
+/// Scales the flexes so that they add up to the number of members again,
+/// keeping their proportions.
+fn normalize_flexes(flexes: &mut [f32]) {
+    let scale = flexes.len() as f32 / flexes.iter().sum::<f32>();
+    for flex in flexes {
+        *flex *= scale;
+    }
+}
+
 #[derive(Clone, Copy, Debug, Deserialize, PartialEq)]
 pub enum SplitDirection {
     Up,
@@ -122,6 +122,10 @@ actions!(
         ToggleRightDock,
         ToggleBottomDock,
         CloseAllDocks,
+        ResetPaneSizes,
+        ExchangePaneWithNext,
+        RotatePanesForward,
+        RotatePanesBackward,
     ]
 );
 
@@ -370,6 +374,47 @@ struct GlobalAppState(Weak<AppState>);
 
 impl Global for GlobalAppState {}
 
+/// The items a `zed --wait` is waiting for, with the status it exits with if they were
+/// closed to abandon editing them (as vim's `:cq` does), so that the tool that ran it
+/// (like `git commit`) cancels.
+#[derive(Default)]
+struct AwaitedItems(HashMap<EntityId, Option<i32>>);
+
+impl Global for AwaitedItems {}
+
+/// Records that the CLI is waiting for the items to be closed.
+pub fn await_items(item_ids: impl IntoIterator<Item = EntityId>, cx: &mut AppContext) {
+    let awaited = &mut cx.default_global::<AwaitedItems>().0;
+    awaited.extend(item_ids.into_iter().map(|item_id| (item_id, None)));
+}
+
+/// Records that the items are being closed to abandon editing them, and the status
+/// the CLI should exit with if it's waiting for them. Items it isn't waiting for are
+/// left alone. Returns whether it's waiting for any of them.
+pub fn abandon_items(
+    item_ids: impl IntoIterator<Item = EntityId>,
+    status: i32,
+    cx: &mut AppContext,
+) -> bool {
+    let awaited = &mut cx.default_global::<AwaitedItems>().0;
+    let mut any_awaited = false;
+    for item_id in item_ids {
+        if let Some(abandoned_status) = awaited.get_mut(&item_id) {
+            *abandoned_status = Some(status);
+            any_awaited = true;
+        }
+    }
+    any_awaited
+}
+
+/// Stops waiting for the item, and returns the status it was abandoned with, if it was.
+pub fn take_abandoned_status(item_id: EntityId, cx: &mut AppContext) -> Option<i32> {
+    cx.default_global::<AwaitedItems>()
+        .0
+        .remove(&item_id)
+        .flatten()
+}
+
 pub struct WorkspaceStore {
     workspaces: HashSet<WindowHandle<Workspace>>,
     client: Arc<Client>,
@@ -494,6 +539,7 @@ pub struct Workspace {
     zoomed: Option<AnyWeakView>,
     zoomed_position: Option<DockPosition>,
     center: PaneGroup,
+    pane_sizing: PaneSizing,
     left_dock: View<Dock>,
     bottom_dock: View<Dock>,
     right_dock: View<Dock>,
@@ -743,6 +789,7 @@ impl Workspace {
             zoomed: None,
             zoomed_position: None,
             center: PaneGroup::new(center_pane.clone()),
+            pane_sizing: PaneSizing::default(),
             panes: vec![center_pane.clone()],
             panes_by_item: Default::default(),
             active_pane: center_pane.clone(),
@@ -1651,6 +1698,25 @@ impl Workspace {
         save_intent: SaveIntent,
         cx: &mut ViewContext<Self>,
     ) -> Option<Task<Result<()>>> {
+        // Closing everything while saving (like vim's `:wqa`) saves every dirty item
+        // before anything is closed, so a failed save doesn't leave half the panes gone.
+        if !retain_active_pane && matches!(save_intent, SaveIntent::SaveAll | SaveIntent::Overwrite)
+        {
+            let save_all = self.save_all_before_close(save_intent, cx);
+            return Some(cx.spawn(|workspace, mut cx| async move {
+                if !save_all.await? {
+                    return Ok(());
+                }
+                let close_all = workspace.update(&mut cx, |workspace, cx| {
+                    workspace.close_all_internal(false, SaveIntent::Skip, cx)
+                })?;
+                if let Some(close_all) = close_all {
+                    close_all.await?;
+                }
+                Ok(())
+            }));
+        }
+
         let current_pane = self.active_pane();
 
         let mut tasks = Vec::new();
@@ -1692,6 +1758,80 @@ impl Workspace {
         }
     }
 
+    /// Saves (and formats) every dirty item, carrying on past the ones that fail, which
+    /// are then listed in a single prompt. Resolves to whether closing should go ahead.
+    fn save_all_before_close(
+        &mut self,
+        save_intent: SaveIntent,
+        cx: &mut ViewContext<Self>,
+    ) -> Task<Result<bool>> {
+        if self.project.read(cx).is_disconnected() {
+            return Task::ready(Ok(true));
+        }
+        let dirty_items = self
+            .panes
+            .iter()
+            .flat_map(|pane| {
+                pane.read(cx).items().filter_map(|item| {
+                    if item.is_dirty(cx) {
+                        Some((pane.downgrade(), item.boxed_clone()))
+                    } else {
+                        None
+                    }
+                })
+            })
+            .collect::<Vec<_>>();
+
+        let project = self.project.clone();
+        cx.spawn(|workspace, mut cx| async move {
+            let mut failures = Vec::new();
+            for (pane, item) in dirty_items {
+                let (singleton, project_entry_ids) =
+                    cx.update(|cx| (item.is_singleton(cx), item.project_entry_ids(cx)))?;
+                if !singleton && project_entry_ids.is_empty() {
+                    continue;
+                }
+                let Some(ix) =
+                    pane.update(&mut cx, |pane, _| pane.index_for_item(item.as_ref()))?
+                else {
+                    continue;
+                };
+                match Pane::save_item(project.clone(), &pane, ix, &*item, save_intent, &mut cx)
+                    .await
+                {
+                    Ok(true) => {}
+                    Ok(false) => return Ok(false),
+                    Err(error) => {
+                        let name = cx.update(|cx| {
+                            item.project_path(cx)
+                                .and_then(|path| path.path.to_str().map(ToOwned::to_owned))
+                                .unwrap_or_else(|| "untitled".to_string())
+                        })?;
+                        failures.push(format!("{name}: {error}"));
+                    }
+                }
+            }
+
+            if failures.is_empty() {
+                return Ok(true);
+            }
+            let answer = workspace.update(&mut cx, |_, cx| {
+                let prompt = if failures.len() == 1 {
+                    "1 file could not be saved".to_string()
+                } else {
+                    format!("{} files could not be saved", failures.len())
+                };
+                cx.prompt(
+                    PromptLevel::Critical,
+                    &prompt,
+                    Some(&failures.join("\n")),
+                    &["Cancel", "Close Anyway"],
+                )
+            })?;
+            Ok(answer.await.log_err() == Some(1))
+        })
+    }
+
     pub fn toggle_dock(&mut self, dock_side: DockPosition, cx: &mut ViewContext<Self>) {
         let dock = match dock_side {
             DockPosition::Left => &self.left_dock,
@@ -2111,6 +2251,16 @@ impl Workspace {
         }
     }
 
+    /// Sets what happens to the sizes of the other panes when a pane is split or closed.
+    pub fn set_pane_sizing(&mut self, sizing: PaneSizing) {
+        self.pane_sizing = sizing;
+    }
+
+    pub fn reset_pane_sizes(&mut self, cx: &mut ViewContext<Self>) {
+        self.center.reset_pane_sizes();
+        cx.notify();
+    }
+
     pub fn activate_next_pane(&mut self, cx: &mut WindowContext) {
         let panes = self.center.panes();
         if let Some(ix) = panes.iter().position(|pane| **pane == self.active_pane) {
@@ -2269,6 +2419,24 @@ impl Workspace {
         }
     }
 
+    /// Exchanges the active pane with the next one in its row or column (or the
+    /// previous one, for the last), and focuses that one, which is now where the
+    /// active pane was.
+    pub fn exchange_pane_with_next(&mut self, cx: &mut ViewContext<Self>) {
+        if let Some(other) = self.center.exchange_with_next(&self.active_pane) {
+            cx.focus_view(&other);
+            cx.notify();
+        }
+    }
+
+    /// Moves each pane in the active pane's row or column to the next place in it
+    /// (or to the previous one, backwards), the last one going first.
+    pub fn rotate_panes(&mut self, forward: bool, cx: &mut ViewContext<Self>) {
+        if self.center.rotate(&self.active_pane, forward) {
+            cx.notify();
+        }
+    }
+
     fn handle_pane_focused(&mut self, pane: View<Pane>, cx: &mut ViewContext<Self>) {
         if self.active_pane != pane {
             self.active_pane = pane.clone();
@@ -2359,7 +2527,7 @@ impl Workspace {
     ) -> View<Pane> {
         let new_pane = self.add_pane(cx);
         self.center
-            .split(&pane_to_split, &new_pane, split_direction)
+            .split(&pane_to_split, &new_pane, split_direction, self.pane_sizing)
             .unwrap();
         cx.notify();
         new_pane
@@ -2375,7 +2543,9 @@ impl Workspace {
         let maybe_pane_handle = if let Some(clone) = item.clone_on_split(self.database_id(), cx) {
             let new_pane = self.add_pane(cx);
             new_pane.update(cx, |pane, cx| pane.add_item(clone, true, true, None, cx));
-            self.center.split(&pane, &new_pane, direction).unwrap();
+            self.center
+                .split(&pane, &new_pane, direction, self.pane_sizing)
+                .unwrap();
             Some(new_pane)
         } else {
             None
@@ -2402,7 +2572,7 @@ impl Workspace {
         let new_pane = self.add_pane(cx);
         self.move_item(from.clone(), new_pane.clone(), item_id_to_move, 0, cx);
         self.center
-            .split(&pane_to_split, &new_pane, split_direction)
+            .split(&pane_to_split, &new_pane, split_direction, self.pane_sizing)
             .unwrap();
         cx.notify();
     }
@@ -2417,7 +2587,7 @@ impl Workspace {
         let pane_to_split = pane_to_split.upgrade()?;
         let new_pane = self.add_pane(cx);
         self.center
-            .split(&pane_to_split, &new_pane, split_direction)
+            .split(&pane_to_split, &new_pane, split_direction, self.pane_sizing)
             .unwrap();
 
         let path = self.project.read(cx).path_for_entry(project_entry, cx)?;
@@ -2463,7 +2633,7 @@ impl Workspace {
     }
 
     fn remove_pane(&mut self, pane: View<Pane>, cx: &mut ViewContext<Self>) {
-        if self.center.remove(&pane).unwrap() {
+        if self.center.remove(&pane, self.pane_sizing).unwrap() {
             self.force_remove_pane(&pane, cx);
             self.unfollow(&pane, cx);
             self.last_leaders_by_pane.remove(&pane.downgrade());
@@ -2481,6 +2651,12 @@ impl Workspace {
         &self.panes
     }
 
+    /// The panes of the center, from the top left to the bottom right, in the order
+    /// `ActivateNextPane` goes through them.
+    pub fn center_panes(&self) -> Vec<View<Pane>> {
+        self.center.panes().into_iter().cloned().collect()
+    }
+
     pub fn active_pane(&self) -> &View<Pane> {
         &self.active_pane
     }
@@ -3557,6 +3733,20 @@ impl Workspace {
             .on_action(cx.listener(|workspace, action: &SwapPaneInDirection, cx| {
                 workspace.swap_pane_in_direction(action.0, cx)
             }))
+            .on_action(cx.listener(|workspace, _: &ExchangePaneWithNext, cx| {
+                workspace.exchange_pane_with_next(cx)
+            }))
+            .on_action(
+                cx.listener(|workspace, _: &RotatePanesForward, cx| {
+                    workspace.rotate_panes(true, cx)
+                }),
+            )
+            .on_action(cx.listener(|workspace, _: &RotatePanesBackward, cx| {
+                workspace.rotate_panes(false, cx)
+            }))
+            .on_action(
+                cx.listener(|workspace, _: &ResetPaneSizes, cx| workspace.reset_pane_sizes(cx)),
+            )
             .on_action(cx.listener(|this, _: &ToggleLeftDock, cx| {
                 this.toggle_dock(DockPosition::Left, cx);
             }))
@@ -3651,6 +3841,11 @@ impl Workspace {
         self.modal_layer
             .update(cx, |modal_layer, cx| modal_layer.toggle_modal(cx, build))
     }
+
+    pub fn hide_modal(&mut self, cx: &mut WindowContext) -> bool {
+        self.modal_layer
+            .update(cx, |modal_layer, cx| modal_layer.hide_modal(cx))
+    }
 }
 
 fn window_bounds_env_override(cx: &AsyncAppContext) -> Option<WindowBounds> {
@@ -5019,6 +5214,75 @@ mod tests {
         });
     }
 
+    #[gpui::test]
+    async fn test_close_all_items_and_panes_saves_first(cx: &mut TestAppContext) {
+        init_test(cx);
+
+        let fs = FakeFs::new(cx.executor());
+        let project = Project::test(fs, None, cx).await;
+        let (workspace, cx) = cx.add_window_view(|cx| Workspace::test_new(project, cx));
+
+        let item1 = cx.new_view(|cx| {
+            TestItem::new(cx)
+                .with_dirty(true)
+                .with_project_items(&[TestProjectItem::new(1, "1.txt", cx)])
+        });
+        let item2 = cx.new_view(|cx| {
+            TestItem::new(cx)
+                .with_dirty(true)
+                .with_save_error("permission denied")
+                .with_project_items(&[TestProjectItem::new(2, "2.txt", cx)])
+        });
+        let item3 = cx.new_view(|cx| {
+            TestItem::new(cx)
+                .with_dirty(true)
+                .with_project_items(&[TestProjectItem::new(3, "3.txt", cx)])
+        });
+        workspace.update(cx, |workspace, cx| {
+            workspace.add_item_to_active_pane(Box::new(item1.clone()), cx);
+            workspace.add_item_to_active_pane(Box::new(item2.clone()), cx);
+            let right_pane =
+                workspace.split_pane(workspace.active_pane().clone(), SplitDirection::Right, cx);
+            workspace.add_item(right_pane, Box::new(item3.clone()), cx);
+        });
+        let close_all = CloseAllItemsAndPanes {
+            save_intent: Some(SaveIntent::SaveAll),
+        };
+
+        // Every item is saved that can be, and the failure is reported before
+        // anything is closed.
+        workspace.update(cx, |workspace, cx| {
+            workspace.close_all_items_and_panes(&close_all, cx)
+        });
+        cx.executor().run_until_parked();
+        assert!(cx.has_pending_prompt());
+        workspace.update(cx, |workspace, cx| {
+            assert_eq!(item1.read(cx).save_count, 1);
+            assert_eq!(item2.read(cx).save_count, 0);
+            assert_eq!(item3.read(cx).save_count, 1);
+            assert_eq!(workspace.items(cx).count(), 3);
+        });
+
+        // Cancel closing.
+        cx.simulate_prompt_answer(0);
+        cx.executor().run_until_parked();
+        workspace.update(cx, |workspace, cx| {
+            assert_eq!(workspace.items(cx).count(), 3);
+        });
+
+        // Close anyway.
+        workspace.update(cx, |workspace, cx| {
+            workspace.close_all_items_and_panes(&close_all, cx)
+        });
+        cx.executor().run_until_parked();
+        cx.simulate_prompt_answer(1);
+        cx.executor().run_until_parked();
+        assert!(!cx.has_pending_prompt());
+        workspace.update(cx, |workspace, cx| {
+            assert_eq!(workspace.items(cx).count(), 0);
+        });
+    }
+
     #[gpui::test]
     async fn test_prompting_to_save_only_on_last_item_for_entry(cx: &mut TestAppContext) {
         init_test(cx);
@@ -5300,6 +5564,104 @@ mod tests {
         });
     }
 
+    #[gpui::test]
+    async fn test_pane_sizing(cx: &mut gpui::TestAppContext) {
+        init_test(cx);
+
+        let fs = FakeFs::new(cx.executor());
+        let project = Project::test(fs, [], cx).await;
+        let (workspace, cx) = cx.add_window_view(|cx| Workspace::test_new(project, cx));
+
+        fn flexes(workspace: &Workspace) -> Vec<f32> {
+            match &workspace.center.root {
+                Member::Axis(axis) => axis
+                    .flexes
+                    .lock()
+                    .iter()
+                    .map(|flex| (flex * 100.).round() / 100.)
+                    .collect(),
+                Member::Pane(_) => vec![1.],
+            }
+        }
+
+        workspace.update(cx, |workspace, cx| {
+            let first_pane = workspace.active_pane().clone();
+            let second_pane = workspace.split_pane(first_pane.clone(), SplitDirection::Right, cx);
+            assert_eq!(flexes(workspace), vec![1., 1.]);
+
+            // when sizes are preserved, only the pane being split gives up space
+            workspace.set_pane_sizing(PaneSizing::Preserve);
+            let third_pane = workspace.split_pane(second_pane, SplitDirection::Right, cx);
+            assert_eq!(flexes(workspace), vec![1.5, 0.75, 0.75]);
+
+            // and closing a pane gives its space to its neighbor
+            workspace.remove_pane(first_pane, cx);
+            assert_eq!(flexes(workspace), vec![1.5, 0.5]);
+
+            workspace.set_pane_sizing(PaneSizing::EqualizeAll);
+            workspace.split_pane(third_pane, SplitDirection::Right, cx);
+            assert_eq!(flexes(workspace), vec![1., 1., 1.]);
+
+            workspace.set_pane_sizing(PaneSizing::Preserve);
+            if let Member::Axis(axis) = &workspace.center.root {
+                *axis.flexes.lock() = vec![2., 0.5, 0.5];
+            }
+            workspace.reset_pane_sizes(cx);
+            assert_eq!(flexes(workspace), vec![1., 1., 1.]);
+        });
+    }
+
+    #[gpui::test]
+    async fn test_exchange_and_rotate_panes(cx: &mut gpui::TestAppContext) {
+        init_test(cx);
+
+        let fs = FakeFs::new(cx.executor());
+        let project = Project::test(fs, [], cx).await;
+        let (workspace, cx) = cx.add_window_view(|cx| Workspace::test_new(project, cx));
+
+        let (a, b, c) = workspace.update(cx, |workspace, cx| {
+            let a = workspace.active_pane().clone();
+            let b = workspace.split_pane(a.clone(), SplitDirection::Right, cx);
+            let c = workspace.split_pane(b.clone(), SplitDirection::Right, cx);
+            (a, b, c)
+        });
+        let panes = |cx: &mut VisualTestContext| {
+            workspace.update(cx, |workspace, _| {
+                workspace
+                    .center
+                    .panes()
+                    .into_iter()
+                    .cloned()
+                    .collect::<Vec<_>>()
+            })
+        };
+
+        // the active pane is exchanged with the next one, which is focused in its place
+        cx.focus_view(&a);
+        workspace.update(cx, |workspace, cx| workspace.exchange_pane_with_next(cx));
+        assert_eq!(panes(cx), [b.clone(), a.clone(), c.clone()]);
+        workspace.update(cx, |workspace, _| assert_eq!(workspace.active_pane(), &b));
+        // or with the previous one, from the last
+        cx.focus_view(&c);
+        workspace.update(cx, |workspace, cx| workspace.exchange_pane_with_next(cx));
+        assert_eq!(panes(cx), [b.clone(), c.clone(), a.clone()]);
+
+        // rotating keeps the active pane active
+        workspace.update(cx, |workspace, cx| workspace.rotate_panes(true, cx));
+        assert_eq!(panes(cx), [a.clone(), b.clone(), c.clone()]);
+        workspace.update(cx, |workspace, cx| workspace.rotate_panes(false, cx));
+        assert_eq!(panes(cx), [b.clone(), c.clone(), a.clone()]);
+        workspace.update(cx, |workspace, _| assert_eq!(workspace.active_pane(), &a));
+
+        // but panes can't be rotated when one of them is split
+        let d = workspace.update(cx, |workspace, cx| {
+            workspace.split_pane(a.clone(), SplitDirection::Down, cx)
+        });
+        cx.focus_view(&b);
+        workspace.update(cx, |workspace, cx| workspace.rotate_panes(true, cx));
+        assert_eq!(panes(cx), [b, c, a, d]);
+    }
+
     #[gpui::test]
     async fn test_toggle_docks_and_panels(cx: &mut gpui::TestAppContext) {
         init_test(cx);
@@ -249,6 +249,7 @@ pub async fn handle_cli_connection(
                 };
 
                 let mut errored = false;
+                let mut item_ids = Vec::new();
 
                 match open_paths_with_positions(&paths, app_state, &mut cx).await {
                     Ok((workspace, items)) => {
@@ -257,6 +258,7 @@ pub async fn handle_cli_connection(
                         for (item, path) in items.into_iter().zip(&paths) {
                             match item {
                                 Some(Ok(item)) => {
+                                    item_ids.push(item.item_id());
                                     cx.update(|cx| {
                                         let released = oneshot::channel();
                                         item.on_release(
@@ -283,11 +285,14 @@ pub async fn handle_cli_connection(
                         }
 
                         if wait {
+                            cx.update(|cx| workspace::await_items(item_ids.iter().copied(), cx))
+                                .log_err();
                             let background = cx.background_executor().clone();
+                            let mut wait_cx = cx.clone();
                             let wait = async move {
                                 if paths.is_empty() {
                                     let (done_tx, done_rx) = oneshot::channel();
-                                    let _subscription = workspace.update(&mut cx, |_, cx| {
+                                    let _subscription = workspace.update(&mut wait_cx, |_, cx| {
                                         cx.on_release(move |_, _, _| {
                                             let _ = done_tx.send(());
                                         })
@@ -314,6 +319,24 @@ pub async fn handle_cli_connection(
                                     }
                                 }
                             }
+
+                            // an item closed with an error (like vim's `:cq`) makes the
+                            // CLI exit with its status, so the tool that ran it cancels
+                            let abandoned_status = cx
+                                .update(|cx| {
+                                    item_ids
+                                        .iter()
+                                        .filter_map(|item_id| {
+                                            workspace::take_abandoned_status(*item_id, cx)
+                                        })
+                                        .max()
+                                })
+                                .ok()
+                                .flatten();
+                            if let Some(status) = abandoned_status {
+                                responses.send(CliResponse::Exit { status }).log_err();
+                                return;
+                            }
                         }
                     }
                     Err(error) => {